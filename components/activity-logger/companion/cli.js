@@ -449,6 +449,63 @@ program
     }
   });
 
+// ============================================
+// BACKUP COMMANDS
+// ============================================
+
+const DEFAULT_STORE = path.join(__dirname, 'src/data/companion.db');
+
+program
+  .command('backup <dest>')
+  .description('Back up the local store to a directory (safe while the service is running)')
+  .option('--db <path>', 'Store to back up', DEFAULT_STORE)
+  .option('--full', 'Start a new chain instead of extending the latest backup')
+  .action(async (dest, options) => {
+    try {
+      const { backupStore } = require('./src/database/backup.js');
+      info(`Backing up ${options.db}...`);
+      const result = await backupStore(options.db, dest, { incremental: !options.full });
+
+      success(`Backup ${result.id} written to ${dest}`);
+      info(
+        `${result.incremental ? `Incremental on ${result.parent}` : 'Full backup'}: ` +
+          `${result.chunksWritten}/${result.chunks} chunks written, ` +
+          `${(result.bytesWritten / 1024 / 1024).toFixed(2)} MB`
+      );
+    } catch (err) {
+      error(`Backup failed: ${err.message}`);
+      process.exit(1);
+    }
+  });
+
+program
+  .command('restore <src>')
+  .description('Restore a backup directory (latest backup) or manifest file to a store')
+  .option('--db <path>', 'Store to restore into', DEFAULT_STORE)
+  .option('--force', 'Replace an existing store (stop the service first)')
+  .option('--verify-only', 'Check the backup without restoring it')
+  .action(async (src, options) => {
+    try {
+      const { restoreStore, verifyBackup } = require('./src/database/backup.js');
+      if (options.verifyOnly) {
+        const result = verifyBackup(src);
+        if (!result.ok) {
+          result.problems.forEach((problem) => warn(problem));
+          throw new Error(`Backup ${result.id} is damaged`);
+        }
+        success(`Backup ${result.id} verified`);
+        return;
+      }
+
+      const result = await restoreStore(src, options.db, { overwrite: options.force });
+      success(`Restored backup ${result.id} to ${options.db}`);
+      info(`Chain of ${result.chain} backup(s), ${(result.size / 1024 / 1024).toFixed(2)} MB`);
+    } catch (err) {
+      error(`Restore failed: ${err.message}`);
+      process.exit(1);
+    }
+  });
+
 // ============================================
 // PRIVACY RUNGS COMMANDS
 // ============================================
@@ -664,6 +721,14 @@ program
         title: 'List privacy levels',
         cmd: 'cursor-telemetry rungs list'
       },
+      {
+        title: 'Back up the local store (incremental)',
+        cmd: 'cursor-telemetry backup ~/telemetry-backups'
+      },
+      {
+        title: 'Restore the latest backup',
+        cmd: 'cursor-telemetry restore ~/telemetry-backups --force'
+      },
       {
         title: 'Open dashboard',
         cmd: 'cursor-telemetry open'
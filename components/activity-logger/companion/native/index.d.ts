@@ -3,17 +3,3990 @@
 
 /* auto-generated by NAPI-RS */
 
+/** One step of an agent run */
+export interface AgentStep {
+  /** "plan", "tool_call", "edit", or "test" */
+  kind: string
+  eventId?: string
+  timestamp: number
+  /** Time since the trace started */
+  offsetMs: number
+  /** Time since the previous step */
+  latencyMs: number
+  filePath?: string
+  status?: string
+}
+/** A single agent run from plan to its last step */
+export interface AgentTrace {
+  /** run_id when events carry one, otherwise `trace-<start>` */
+  id: string
+  start: number
+  end: number
+  durationMs: number
+  steps: Array<AgentStep>
+  toolCalls: number
+  edits: number
+  tests: number
+  files: Array<string>
+  timeToFirstEditMs?: number
+  /** Outcome of the last test step; None when the run never ran tests */
+  success?: boolean
+}
+/**
+ * Link agent steps into traces
+ *
+ * Events tagged with the same run_id always form one trace. Untagged steps
+ * are chained heuristically: a plan starts a new trace, and a trace ends
+ * once no step follows within `max_gap_ms`. Events that aren't agent steps
+ * (human edits, unrelated commands) are skipped.
+ *
+ * @param events - Activity events in any order
+ * @param max_gap_ms - Idle time that ends an untagged trace (default: 5 minutes)
+ * @returns Traces ordered by start time
+*/
+export declare function linkAgentSteps(events: Array<ActivityEvent>, maxGapMs?: number | undefined | null): Array<AgentTrace>
+/** Metrics accumulated for one group of events */
+export interface AggregateMetrics {
+  key: string
+  eventCount: number
+  linesAdded: number
+  linesRemoved: number
+  churn: number
+  aiEvents: number
+  /** Fraction of events flagged ai_generated */
+  aiRatio: number
+  firstSeen: number
+  lastSeen: number
+  /** Time attributed to this group: gaps between consecutive events, ignoring idle gaps */
+  activeMs: number
+  /** Events carrying line counts, which the per-edit averages are taken over */
+  editCount: number
+  /** Per-edit lines added + removed, summarized with the selected estimator */
+  averageChurn: number
+  averageLinesAdded: number
+  averageLinesRemoved: number
+  /** Median absolute deviation of per-edit churn */
+  churnMad: number
+}
+/** Options for aggregate_events */
+export interface AggregateOptions {
+  /** Per-edit average: "mean" (default), "trimmed_mean", or "median" */
+  estimator?: string
+  /** Fraction trimmed from each end by "trimmed_mean" (default: 0.1) */
+  trim?: number
+}
+/**
+ * Aggregate events into per-group metrics
+ *
+ * Per-edit averages use the arithmetic mean unless a robust estimator is
+ * selected: one huge generated-file event dominates a mean but barely
+ * moves a trimmed mean or median.
+ *
+ * @param events - Activity events in any order
+ * @param group_by - "branch", "file", "session", or "type"; events missing the key group under "unknown"
+ * @param options - Estimator for per-edit averages ("mean", "trimmed_mean", "median") and trim fraction
+ * @returns AggregateMetrics per group, ordered by first activity
+*/
+export declare function aggregateEvents(events: Array<ActivityEvent>, groupBy: string, options?: AggregateOptions | undefined | null): Array<AggregateMetrics>
+/** Options for summarize_branches */
+export interface BranchReportOptions {
+  /** Trunk branch excluded from divergence checks (default: "main" or "master") */
+  defaultBranch?: string
+  /** Lifetime after which a branch counts as long-lived (default: 14 days) */
+  longLivedDays?: number
+  /** Churn a long-lived branch must accumulate to count as divergent (default: 500 lines) */
+  divergenceChurn?: number
+}
+/** Lifetime summary for one branch */
+export interface BranchSummary {
+  branch: string
+  metrics: AggregateMetrics
+  /** Time from first to last event seen on the branch */
+  lifetimeMs: number
+  isLongLived: boolean
+  isDivergent: boolean
+}
+/** Per-branch attribution report */
+export interface BranchReport {
+  branches: Array<BranchSummary>
+  /** Long-lived branches that accumulated enough churn to have likely drifted from trunk */
+  longLivedDivergent: Array<string>
+}
+/**
+ * Attribute activity to git branches
+ *
+ * Summarizes time on branch, churn, and AI ratio per branch, and flags
+ * long-lived branches carrying a lot of unmerged-looking churn.
+*/
+export declare function summarizeBranches(events: Array<ActivityEvent>, options?: BranchReportOptions | undefined | null): BranchReport
+/** Options for an AnnIndex */
+export interface AnnIndexOptions {
+  /** "cosine" (default) or "euclidean" */
+  metric?: string
+  /** Links per node and layer; layer 0 keeps twice as many (default: 16) */
+  m?: number
+  /** Candidates considered while linking a new node (default: 200) */
+  efConstruction?: number
+  /** Candidates considered per search; raised to k when smaller (default: 50) */
+  efSearch?: number
+}
+/** A stored vector near the query */
+export interface AnnMatch {
+  id: string
+  /** 1 - cosine similarity for the cosine metric, Euclidean distance otherwise */
+  distance: number
+}
+/** HNSW index of fixed-length vectors keyed by id */
+export class AnnIndex {
+  /** Create an empty index for vectors of length `dimensions` */
+  constructor(dimensions: number, options?: AnnIndexOptions | undefined | null)
+  /**
+   * Store `vector` under `id`
+   *
+   * Re-adding an id replaces its vector. The graph node of the old vector
+   * stays as a routing point, hidden from results, until the index is
+   * rebuilt.
+  */
+  add(id: string, vector: Float32Array | number[]): void
+  /** Hide a vector from results; returns whether it was stored */
+  remove(id: string): boolean
+  /**
+   * The `k` stored vectors nearest `vector`
+   *
+   * Approximate: a larger `ef` (default: the index's ef_search) finds the
+   * true neighbors more often at the cost of speed.
+   *
+   * @returns Matches, nearest first
+  */
+  search(vector: Float32Array | number[], k?: number | undefined | null, ef?: number | undefined | null): Array<AnnMatch>
+  /** Whether a vector is stored under `id` */
+  has(id: string): boolean
+  /** Number of stored vectors */
+  get size(): number
+  /** Length of each vector */
+  get dimensions(): number
+  /**
+   * Write the index to `path`
+   *
+   * The graph is saved along with the vectors, so loading needs no
+   * rebuild. Hidden nodes are kept. The file is written to a temporary
+   * file first and renamed into place.
+  */
+  save(path: string): void
+  /** Load an index written by save */
+  static load(path: string): AnnIndex
+}
+/** One TODO-style comment */
+export interface Annotation {
+  /** "TODO", "FIXME", "HACK", or "XXX" */
+  tag: string
+  /** Comment text after the tag, author, and colon */
+  text: string
+  /** 1-based line of the tag */
+  line: number
+  /** Author from `TODO(alice)`, `TODO[alice]`, or `TODO @alice` */
+  author?: string
+  /** First issue reference: "#123", "ABC-123", "gh-12", or an issue/pull URL */
+  issue?: string
+}
+/**
+ * Extract TODO, FIXME, HACK, and XXX comments
+ *
+ * Only comments are searched (string literals and identifiers such as
+ * `todoList` are ignored), and tags must be uppercase. Recognizes authors
+ * written as `TODO(alice):`, `TODO[alice]`, or `TODO @alice`, and issue
+ * references (`#123`, `ABC-123`, `gh-12`, or an issues/pull URL) anywhere in
+ * the comment, including `TODO(#123)`.
+ *
+ * @param content - Source code
+ * @param language - Language name as returned by detect_language
+ * @returns Annotations in source order
+*/
+export declare function extractAnnotations(content: string, language: string): Array<Annotation>
+/** Options for attribute_authorship */
+export interface AttributionOptions {
+  /** How long after an AI response its code counts as recent (default: 600000) */
+  windowMs?: number
+  /** Tokens per shingle when comparing code (default: 3) */
+  shingleSize?: number
+  /** Share of the score from matching AI responses, the rest from burst size and rate, 0-1 (default: 0.6) */
+  contentWeight?: number
+  /** Lines inserted at once that count as a full burst (default: 5) */
+  burstLines?: number
+  /** Insertion rate that counts as fully machine-like; people type well under it (default: 40) */
+  fastCharsPerSecond?: number
+  /** Longest time since the file's previous edit from which a rate is measured (default: 30000) */
+  maxGapMs?: number
+}
+/** Who most likely wrote one inserted line */
+export interface LineAttribution {
+  diffId?: string
+  filePath?: string
+  /** 1-based line in the new content, when the edit carried it */
+  line?: number
+  /** "ai" or "human" */
+  author: string
+  /** Confidence in `author`, 0.5-1 */
+  confidence: number
+  /** How closely the line matches the best recent AI response, 0-1 */
+  contentMatch: number
+  /** Non-blank lines the edit inserted */
+  burstLines: number
+  /** Characters inserted per second, when the edit's duration is known */
+  charsPerSecond?: number
+  /** Prompt behind the matching response, for lines attributed to AI */
+  promptId?: string
+}
+/** Lines written by AI and by hand in one file */
+export interface FileAttribution {
+  filePath: string
+  aiLines: number
+  humanLines: number
+  /** 0-1 */
+  aiFraction: number
+}
+/** Result of attribute_authorship */
+export interface AttributionReport {
+  aiLines: number
+  humanLines: number
+  /** Share of inserted lines written by AI, 0-1 */
+  aiFraction: number
+  /** By file path */
+  files: Array<FileAttribution>
+  /** Every non-blank inserted line, in time order */
+  lines: Array<LineAttribution>
+}
+/**
+ * Classify inserted lines as AI-applied or hand-typed
+ *
+ * Each line of each edit (`code_change` or `file_change`) gets a score
+ * from three signals. Content: how closely the line matches code in an AI
+ * response (as grouped by link_prompts_to_diffs) from the last
+ * `window_ms`, exact after collapsing whitespace or by shingle
+ * containment. Burst: how many lines the edit inserted at once. Rate:
+ * characters per second, over `duration_ms` when the edit records it or
+ * since the file's previous edit when that was at most `max_gap_ms` ago.
+ * Burst and rate average into a behaviour score, which `content_weight`
+ * blends with the content score; lines scoring 0.5 or more are AI.
+ *
+ * Without a matching response the content score is 0, so a large paste
+ * of code no response contained counts as written by hand. Blank lines
+ * are not counted.
+ *
+ * @param events - Chat, suggestion, and edit events, in any order
+ * @param options - Window and signal thresholds
+ * @returns Per-line authorship and the share of lines written by AI, overall and per file
+*/
+export declare function attributeAuthorship(events: Array<Record<string, any>>, options?: AttributionOptions | undefined | null): AttributionReport
+/** One observation of a metric */
+export interface SeriesPoint {
+  /** Milliseconds since the Unix epoch */
+  timestamp: number
+  value: number
+}
+/** Options for compute_baseline */
+export interface BaselineOptions {
+  /** Local offset from UTC used to place points in slots (default: 0) */
+  utcOffsetMinutes?: number
+  /** Count periods with no points between the first and last point as 0 (default: true) */
+  fillMissing?: boolean
+}
+/** Expected value for one seasonal slot */
+export interface BaselineSlot {
+  /** Slot index: hour of week (0 = Monday 00:00), hour of day, or day of week (0 = Monday) */
+  slot: number
+  /** e.g. "Mon 09:00", "09:00", or "Mon" */
+  label: string
+  /** Median of the slot's historical values */
+  expected: number
+  mean: number
+  stdDev: number
+  /** Median absolute deviation, for robust anomaly thresholds */
+  mad: number
+  p10: number
+  p90: number
+  /** Historical periods observed in this slot */
+  samples: number
+}
+/** Result of compute_baseline */
+export interface Baseline {
+  seasonality: string
+  /** One entry per slot in slot order; slots with no history have zero samples */
+  slots: Array<BaselineSlot>
+}
+/**
+ * Compute a seasonal baseline from a historical series
+ *
+ * Points are summed per hour (per day for "day_of_week"), then the totals
+ * for each slot are summarized across weeks. The median is the expected
+ * value; MAD and percentiles give the anomaly detector and "typical day"
+ * chart overlays a band to compare against.
+ *
+ * @param series - Historical observations, e.g. one point per event with value 1
+ * @param seasonality - "hour_of_week", "hour_of_day", or "day_of_week"
+ * @returns Expected value and spread per slot
+*/
+export declare function computeBaseline(series: Array<SeriesPoint>, seasonality: string, options?: BaselineOptions | undefined | null): Baseline
+/** Limits on the text a call processes */
+export interface InputBudget {
+  /** Most lines processed per input */
+  maxLines?: number
+  /** Most UTF-8 bytes processed per input */
+  maxBytes?: number
+  /** "truncate" keeps the start (default), "sample" keeps evenly spaced lines, "error" rejects the call */
+  onExceed?: string
+}
+/** How an input over budget was cut down */
+export interface Truncation {
+  /** Which input was cut: "content", "before", or "after" */
+  input: string
+  /** "truncate" or "sample" */
+  mode: string
+  originalLines: number
+  originalBytes: number
+  keptLines: number
+  keptBytes: number
+}
+/** Calls from one function to another */
+export interface CallEdge {
+  /** Calling function, or "<module>" for top-level code */
+  caller: string
+  callee: string
+  /** Number of call sites */
+  calls: number
+  /** 1-based line of the first call site */
+  line: number
+  /** The callee is defined in this file */
+  internal: boolean
+}
+/** Result of extract_call_graph */
+export interface CallGraph {
+  /** Functions and methods defined in the file, in source order */
+  functions: Array<string>
+  /** Edges in order of their first call site */
+  edges: Array<CallEdge>
+}
+/**
+ * Extract the call graph of a file
+ *
+ * Returns caller → callee edges by symbol name, so the functions an AI edit
+ * rippled through can be followed from the edited function. With the
+ * `tree-sitter` feature, calls in javascript, typescript, python, rust, and
+ * go are found with syntax queries (plain calls, method calls, and
+ * constructors); other languages use a heuristic scan for `name(`. Callees
+ * are matched by name only, so same-named methods of different types share
+ * a node.
+ *
+ * @param content - Source code
+ * @param language - Language name as returned by detect_language
+ * @returns Defined functions and caller → callee edges
+*/
+export declare function extractCallGraph(content: string, language: string): CallGraph
+/** One logical change spanning one or more file edits */
+export interface ChangeSet {
+  /** Id of the first edit in the set, or `changeset-<start>` when it has none */
+  id: string
+  start: number
+  end: number
+  eventIds: Array<string>
+  files: Array<string>
+  editCount: number
+  linesAdded: number
+  linesRemoved: number
+  /** True when every edit in the set was AI-applied */
+  aiGenerated: boolean
+  isMultiFile: boolean
+}
+/**
+ * Cluster near-simultaneous file edits into change-sets
+ *
+ * Consecutive edits join the same set while each follows the previous one
+ * within `window_ms`, stays in the same session, and has the same AI/human
+ * origin. Every edit event belongs to exactly one change-set; non-edit
+ * events (no file path) are ignored.
+ *
+ * @param events - Activity events in any order
+ * @param window_ms - Maximum gap between edits of one operation (default: 1500)
+ * @returns Change-sets in chronological order
+*/
+export declare function groupAtomicEdits(events: Array<ActivityEvent>, windowMs?: number | undefined | null): Array<ChangeSet>
+/**
+ * Guess the language of a snippet from its content alone
+ *
+ * Scores the text against weighted keyword and token features for 28
+ * languages, including shell, SQL, Dockerfile, Makefile, YAML, JSON,
+ * HTML, CSS, and Markdown, which keyword checks tend to miss. The
+ * confidence is a probability that shrinks for short inputs; below a
+ * minimum amount of evidence the result is "unknown" with confidence 0.
+ *
+ * @param content - Text to classify
+ * @returns Most likely language, its confidence, and up to three alternates
+*/
+export declare function classifyLanguage(content: string): LanguageGuess
+/** A file to scan for clones */
+export interface CloneSource {
+  path: string
+  content: string
+  /** Language name as returned by detect_language; inferred from the path when omitted */
+  language?: string
+}
+/** Options for detect_clones */
+export interface CloneOptions {
+  /** Smallest clone reported, in tokens (default: 50) */
+  minTokens?: number
+  /** Lowest similarity reported, 0-1 (default: 0.8) */
+  minSimilarity?: number
+  /** Treat identifiers and literals as equal, so renamed copies match (default: false) */
+  normalizeIdentifiers?: boolean
+  /** Also report blocks duplicated within one file (default: false) */
+  sameFile?: boolean
+}
+/** One side of a clone */
+export interface CloneLocation {
+  path: string
+  /** 1-based, inclusive */
+  startLine: number
+  endLine: number
+}
+/** A block duplicated between two locations */
+export interface ClonePair {
+  a: CloneLocation
+  b: CloneLocation
+  /** Tokens in the longer side */
+  tokens: number
+  /** Fraction of the block's tokens inside windows shared by both sides, 0-1 */
+  similarity: number
+}
+/** Result of detect_clones */
+export interface CloneReport {
+  /** Largest clones first */
+  clones: Array<ClonePair>
+  filesScanned: number
+  tokensScanned: number
+}
+/**
+ * Find blocks of code duplicated across a set of files
+ *
+ * Files are tokenized with comments and string contents removed, and
+ * every window of 12 tokens is hashed. Windows shared between files are
+ * chained into blocks, bridging short edits, so a copy with a few changed
+ * tokens is still one clone with similarity below 1. With
+ * `normalizeIdentifiers`, copies that only rename variables or change
+ * literals match too. Windows shared by more than 32 places (license
+ * headers, import lists) are ignored.
+ *
+ * @param files - Paths and contents to compare
+ * @param options - Minimum size, minimum similarity, renaming tolerance, same-file clones
+ * @returns Clone pairs with both locations, size, and similarity, largest first
+*/
+export declare function detectClones(files: Array<CloneSource>, options?: CloneOptions | undefined | null): CloneReport
+/** Options for cluster_vectors */
+export interface ClusterOptions {
+  /** "kmeans" (default) or "hdbscan" */
+  method?: string
+  /** "cosine" (default) compares directions, "euclidean" raw positions */
+  metric?: string
+  /** Clusters to find with k-means (default: 8, capped at the number of vectors) */
+  k?: number
+  /** k-means rounds before giving up on convergence (default: 100) */
+  maxIterations?: number
+  /** Seed for k-means++ initialization (default: 42) */
+  seed?: number
+  /** Smallest HDBSCAN cluster; smaller groups are noise (default: 5) */
+  minClusterSize?: number
+  /** Neighbors defining a dense point for HDBSCAN (default: min_cluster_size) */
+  minSamples?: number
+}
+/** One cluster found by cluster_vectors */
+export interface Cluster {
+  label: number
+  size: number
+  /** Mean of the members */
+  centroid: Array<number>
+  /** Index of the member nearest the centroid, a representative session */
+  exemplar: number
+}
+/** Result of cluster_vectors */
+export interface Clustering {
+  /** Cluster per input vector; -1 marks HDBSCAN noise */
+  labels: Array<number>
+  /** Ordered by label */
+  clusters: Array<Cluster>
+  /** k-means rounds run; 0 for HDBSCAN */
+  iterations: number
+  /** k-means sum of squared distances to the assigned centroid; 0 for HDBSCAN */
+  inertia: number
+}
+/**
+ * Group vectors into clusters
+ *
+ * k-means finds exactly `k` clusters from k-means++ seeds and assigns every
+ * vector. HDBSCAN needs no cluster count: it finds dense groups of at least
+ * `min_cluster_size` vectors and labels outliers -1, at O(n²) time, so it
+ * suits up to a few thousand sessions. With the cosine metric, vectors are
+ * normalized to unit length first. Labels are numbered in order of each
+ * cluster's first member, so reruns on the same input agree.
+ *
+ * @param vectors - One vector per session, e.g. from EmbeddingModel.embed; all the same length
+ * @param options - Method, metric, and method parameters
+ * @returns Labels per vector, and each cluster's size, centroid, and exemplar
+*/
+export declare function clusterVectors(vectors: Array<Float32Array | number[]>, options?: ClusterOptions | undefined | null): Clustering
+/** Options for EventStore.export_arrow */
+export interface ArrowOptions {
+  /** "stream" (default) or "file", the IPC stream or file (Feather v2) format */
+  format?: string
+}
+/** Options for EventStore.export_parquet */
+export interface ParquetOptions {
+  /** "zstd" (default), "snappy", or "none" */
+  compression?: string
+  /** Rows per row group; larger groups compress better, smaller ones read selectively (default: 131072) */
+  rowGroupSize?: number
+}
+/** Result of EventStore.export_parquet */
+export interface ParquetExport {
+  path: string
+  rows: number
+  rowGroups: number
+  /** Size of the written file */
+  bytes: number
+}
+/** One metric measured on both sessions */
+export interface MetricDelta {
+  metric: string
+  a?: number
+  b?: number
+  /** b − a, when both sessions have the metric */
+  difference?: number
+  /** difference / a, when a is non-zero */
+  relativeDifference?: number
+}
+/** A file touched in both sessions */
+export interface SharedFile {
+  filePath: string
+  eventsA: number
+  eventsB: number
+}
+/** Which files the sessions have in common */
+export interface FileOverlap {
+  /** Shared files, most combined events first */
+  shared: Array<SharedFile>
+  onlyA: Array<string>
+  onlyB: Array<string>
+  /** |shared| / |all files touched|, 0 when neither session touched a file */
+  jaccard: number
+}
+/** How much of each session one behavior accounts for */
+export interface PatternDifference {
+  /** Event type (e.g. "prompt") or transition (e.g. "prompt → code_change") */
+  pattern: string
+  /** Share of the session's events (or transitions) matching the pattern */
+  shareA: number
+  shareB: number
+  /** share_b − share_a */
+  difference: number
+}
+/** Result of compare_sessions */
+export interface SessionComparison {
+  eventsA: number
+  eventsB: number
+  /**
+   * events, churn, ai_ratio, acceptance_rate, errors, active_minutes,
+   * files_touched, and file_switches_per_hour
+  */
+  metrics: Array<MetricDelta>
+  files: FileOverlap
+  /** Event type mix, largest absolute difference first */
+  eventTypes: Array<PatternDifference>
+  /** Consecutive event type pairs with the largest absolute share differences */
+  transitions: Array<PatternDifference>
+}
+/**
+ * Compare two sessions (or days) of activity
+ *
+ * Reports the same per-unit metrics as analyze_experiment plus files
+ * touched and file switching rate for each side, which files both sessions
+ * worked on, and how the mix of event types and type-to-type transitions
+ * differs, e.g. contrasting "a good day" with "a bad day".
+ *
+ * @param session_a - Events of the baseline session
+ * @param session_b - Events of the session compared against it
+ * @returns Metric deltas (b − a), file overlap, and behavior differences
+*/
+export declare function compareSessions(sessionA: Array<ActivityEvent>, sessionB: Array<ActivityEvent>): SessionComparison
+/** Cyclomatic complexity of one function */
+export interface FunctionComplexity {
+  name: string
+  startLine: number
+  endLine: number
+  /** 1 + decision points in the body, excluding nested functions */
+  cyclomatic: number
+  /** Sonar-style cognitive complexity: structures weighted by nesting depth */
+  cognitive: number
+}
+/** Complexity of a whole file */
+export interface ComplexityReport {
+  language: string
+  functions: Array<FunctionComplexity>
+  /** Sum over functions plus decision points in top-level code */
+  totalCyclomatic: number
+  maxCyclomatic: number
+  averageCyclomatic: number
+  /** Sum of per-function cognitive complexity */
+  totalCognitive: number
+  maxCognitive: number
+}
+/**
+ * Calculate cyclomatic complexity per function and per file
+ *
+ * Counts decision points (if/elif, loops, case arms, catch/except, boolean
+ * operators, ternaries) in comment- and string-stripped code. Functions are
+ * located heuristically, see `find_functions` for supported languages.
+ *
+ * @param content - Source code
+ * @param language - Language name as returned by detect_language
+ * @returns ComplexityReport with per-function and per-file values
+*/
+export declare function calculateComplexity(content: string, language: string): ComplexityReport
+/** How one function's complexity changed between two versions of a file */
+export interface FunctionComplexityChange {
+  name: string
+  /** "added", "removed", or "modified" */
+  change: string
+  /** Line range in the new version, or the old one for removed functions */
+  startLine: number
+  endLine: number
+  beforeCyclomatic?: number
+  afterCyclomatic?: number
+  /** After minus before, treating a missing side as 0 */
+  cyclomaticDelta: number
+  beforeCognitive?: number
+  afterCognitive?: number
+  cognitiveDelta: number
+  /** Lines of the function inserted or deleted by the edit */
+  linesChanged: number
+}
+/** Result of compare_complexity */
+export interface ComplexityDelta {
+  language: string
+  /** Changed functions, largest cyclomatic change first */
+  functions: Array<FunctionComplexityChange>
+  beforeTotalCyclomatic: number
+  afterTotalCyclomatic: number
+  cyclomaticDelta: number
+  beforeTotalCognitive: number
+  afterTotalCognitive: number
+  cognitiveDelta: number
+  /** One sentence per function whose cyclomatic complexity changed, e.g. "complexity of parse_config rose from 8 to 19" */
+  summary: Array<string>
+}
+/**
+ * Compare per-function complexity between two versions of a file
+ *
+ * Both versions are measured like calculate_complexity and functions are
+ * paired by name (in order, for repeated names). A function is "modified"
+ * when its complexity changed or the line diff touched its body; untouched
+ * functions are left out. A renamed function shows as removed plus added.
+ *
+ * @param before - Source before the edit
+ * @param after - Source after the edit
+ * @param language - Language name as returned by detect_language
+ * @returns Changed functions, file totals on both sides, and one summary sentence per complexity change
+*/
+export declare function compareComplexity(before: string, after: string, language: string): ComplexityDelta
+/** Options for compress and ZstdCompressStream */
+export interface CompressOptions {
+  /** 1 (fastest) to 22 (smallest); negative levels trade ratio for speed (default: 3) */
+  level?: number
+  /** Dictionary from train_dictionary; the same one is needed to decompress */
+  dictionary?: Buffer
+}
+/** Options for decompress and ZstdDecompressStream */
+export interface DecompressOptions {
+  /** Dictionary the data was compressed with */
+  dictionary?: Buffer
+  /** Refuse to inflate past this many bytes (default: 256 MiB) */
+  maxOutputBytes?: number
+}
+/**
+ * Compress `data` into a single zstd frame
+ *
+ * With a dictionary, small payloads such as single events compress far
+ * better, since the repeated field names and values live in the
+ * dictionary instead of each frame.
+ *
+ * @param data - Bytes to compress
+ * @param options - Level and dictionary
+ * @returns The zstd frame
+*/
+export declare function compress(data: Buffer, options?: CompressOptions | undefined | null): Buffer
+/**
+ * Decompress zstd data, which may be several concatenated frames
+ *
+ * Throws if the data is corrupt, was compressed with a different
+ * dictionary, or would inflate past max_output_bytes.
+ *
+ * @param data - Output of compress or a compression stream
+ * @param options - Dictionary and output limit
+ * @returns The original bytes
+*/
+export declare function decompress(data: Buffer, options?: DecompressOptions | undefined | null): Buffer
+/**
+ * Train a compression dictionary on sample payloads
+ *
+ * Samples should look like what will be compressed, e.g. a few thousand
+ * serialized events. zstd needs a reasonable number of samples and
+ * throws when there are too few to learn from.
+ *
+ * @param samples - Representative payloads
+ * @param maxBytes - Largest dictionary to produce (default: 112640)
+ * @returns The dictionary, to pass to compress and decompress
+*/
+export declare function trainDictionary(samples: Array<Buffer>, maxBytes?: number | undefined | null): Buffer
+/** Incremental zstd compression, one frame across many writes */
+export class ZstdCompressStream {
+  constructor(options?: CompressOptions | undefined | null)
+  /** Feed `chunk`; returns whatever compressed output is ready, possibly nothing */
+  write(chunk: Buffer): Buffer
+  /** Emit everything written so far, so the reader can decode it without waiting for finish */
+  flush(): Buffer
+  /** End the frame; returns the remaining output. Later writes throw */
+  finish(): Buffer
+}
+/** Incremental zstd decompression of data arriving in chunks */
+export class ZstdDecompressStream {
+  constructor(options?: DecompressOptions | undefined | null)
+  /** Feed `chunk`; returns whatever decompressed output is ready, possibly nothing */
+  write(chunk: Buffer): Buffer
+}
+/** Options for EventStore.export_csv */
+export interface CsvOptions {
+  /** Columns in order (default: id, timestamp, type, session_id, workspace_path, file_path, lines_added, lines_removed); "event" is the whole event as JSON, and any other name is read from the event or its details */
+  columns?: Array<string>
+  /** Field separator, a single character (default: ",") */
+  delimiter?: string
+  /** Write a header row of column names (default: true) */
+  header?: boolean
+  /** "iso" (default) for ISO-8601 UTC, or "epoch" for epoch milliseconds */
+  timestampFormat?: string
+  /** Start the file with a UTF-8 byte order mark, so Excel reads non-ASCII text correctly (default: false) */
+  bom?: boolean
+}
+/** Result of EventStore.export_csv */
+export interface CsvExport {
+  path: string
+  /** Events written, not counting the header */
+  rows: number
+  /** Size of the written file */
+  bytes: number
+}
+/** Options for a Deduper */
+export interface DeduperOptions {
+  /** Distinct strings the filter is sized for; past it, false positives rise (default: 1,000,000) */
+  capacity?: number
+  /** Chance that a new string is reported as seen, at capacity (default: 0.001) */
+  falsePositiveRate?: number
+  /** File to load the filter from, if it exists, and to save it to */
+  path?: string
+}
+/** Bloom filter for dropping duplicate strings as they stream past */
+export class Deduper {
+  /**
+   * Create a filter, or load the one saved at `path`
+   *
+   * A loaded filter keeps the size it was saved with; `capacity` and
+   * `false_positive_rate` only size a new one.
+  */
+  constructor(options?: DeduperOptions | undefined | null)
+  /** Whether `value` was added before; false positives are possible, false negatives are not */
+  seen(value: string): boolean
+  /** Record `value`; returns true if it is new, false if it was (probably) seen before */
+  add(value: string): boolean
+  /** Record each value and return the new ones in order, dropping repeats within the batch too */
+  filter(values: Array<string>): Array<string>
+  /** Distinct values added, not counting those taken for false positives */
+  get count(): number
+  /** Chance, at the current fill, that a new value is reported as seen */
+  get falsePositiveRate(): number
+  /**
+   * Write the filter to `path`, or to the path it was created with
+   *
+   * The file is written to a temporary file first and renamed into place.
+  */
+  save(path?: string | undefined | null): void
+  /** Forget every value */
+  clear(): void
+}
+/** Fewest single-character insertions, deletions, and substitutions turning `a` into `b` */
+export declare function levenshtein(a: string, b: string): number
+/** Levenshtein distance that also counts swapping two adjacent characters as one edit */
+export declare function damerauLevenshtein(a: string, b: string): number
+/**
+ * Jaro-Winkler similarity
+ *
+ * Counts characters matching within a window and their transpositions,
+ * then boosts strings sharing a prefix of up to four characters, which
+ * suits identifiers and filenames that differ in a suffix.
+ *
+ * @returns 0 (nothing in common) to 1 (identical)
+*/
+export declare function jaroWinkler(a: string, b: string): number
+/**
+ * One metric over many string pairs, in parallel
+ *
+ * Strings are compared by Unicode character, so pairs of short strings
+ * (identifiers, filenames, prompts) are the intended workload.
+ *
+ * @param pairs - [a, b] pairs
+ * @param metric - "levenshtein" (default), "damerau", or "jaro-winkler"
+ * @returns Edit counts for the distances, 0-1 similarities for jaro-winkler, in pair order
+*/
+export declare function batchEditDistance(pairs: Array<[string, string]>, metric?: string | undefined | null): Array<number>
+/** Options for EmbeddingModel.load */
+export interface EmbeddingModelOptions {
+  /** Tokens embedded per text; the rest are ignored (default: 512) */
+  maxTokens?: number
+}
+/** A static embedding model loaded from disk */
+export class EmbeddingModel {
+  /**
+   * Load a model2vec model directory
+   *
+   * Reads `tokenizer.json` (a WordPiece tokenizer), `model.safetensors`
+   * (an F32, F16, or BF16 `embeddings` table), and, if present,
+   * `config.json` for its `normalize` flag. Nothing is downloaded.
+  */
+  static load(path: string, options?: EmbeddingModelOptions | undefined | null): EmbeddingModel
+  /** Embed each text, in parallel; a text without known tokens gets the zero vector */
+  embed(texts: Array<string>): Array<Float32Array>
+  /** Cosine similarity of two texts' embeddings, 0 when either is the zero vector */
+  similarity(a: string, b: string): number
+  /** Length of each embedding */
+  get dimensions(): number
+  /** Tokens in the vocabulary */
+  get vocabularySize(): number
+}
+/** Cosine similarity of two stored embeddings of the same length */
+export declare function cosineSimilarity(a: Float32Array, b: Float32Array): number
+/** Result of detect_encoding */
+export interface EncodingDetection {
+  /** "utf-8", "utf-16le", "utf-16be", "utf-32le", "utf-32be", "windows-1252", or "iso-8859-1" */
+  encoding: string
+  /** 0-1; 1 for a BOM or valid non-ASCII UTF-8 */
+  confidence: number
+  /** The input starts with a byte order mark */
+  bom: boolean
+}
+/** Result of decode_to_utf8 */
+export interface DecodedText {
+  text: string
+  encoding: string
+  bom: boolean
+  /** Some bytes were invalid in the detected encoding and became U+FFFD */
+  lossy: boolean
+}
+/**
+ * Detect the text encoding of raw bytes
+ *
+ * A byte order mark decides outright (UTF-8, UTF-16LE/BE, UTF-32LE/BE).
+ * Without one, UTF-16 is recognized by the zero byte in every other
+ * position that mostly-ASCII text has, then the bytes are checked as
+ * UTF-8. Anything else is treated as a single-byte Western encoding:
+ * Windows-1252 when bytes 0x80-0x9F appear, ISO-8859-1 otherwise.
+ * Multi-byte legacy encodings (Shift_JIS, GBK, EUC-KR) are not detected.
+ *
+ * @param bytes - Raw file content
+ * @returns Encoding name, confidence, and whether a BOM is present
+*/
+export declare function detectEncoding(bytes: Buffer): EncodingDetection
+/**
+ * Decode raw bytes to a UTF-8 string
+ *
+ * Uses the encoding detect_encoding picks and strips any BOM. Invalid
+ * sequences become U+FFFD and set `lossy`.
+ *
+ * @param bytes - Raw file content
+ * @returns Decoded text, the encoding used, and BOM and lossy flags
+*/
+export declare function decodeToUtf8(bytes: Buffer): DecodedText
+/** Cache counters for a DiffEngine */
+export interface DiffCacheStats {
+  hits: number
+  misses: number
+  size: number
+  capacity: number
+}
+/** Diff calculator that remembers recent results */
+export class DiffEngine {
+  /**
+   * Create an engine caching up to `capacity` results (default: 256)
+  */
+  constructor(capacity?: number | undefined | null)
+  /**
+   * Same as calculate_diff, returning a cached result when this exact
+   * content and option combination was diffed recently
+  */
+  calculateDiff(text1: string, text2: string, threshold?: number | undefined | null, includeUnified?: boolean | undefined | null, normalizeEol?: boolean | undefined | null, budget?: InputBudget | undefined | null): DiffResult
+  /** Hit/miss counters and current occupancy */
+  cacheStats(): DiffCacheStats
+  /** Drop all cached results and reset counters */
+  clearCache(): void
+}
+/** A single activity event as recorded by the logger */
+export interface ActivityEvent {
+  id?: string
+  sessionId?: string
+  /** Milliseconds since the Unix epoch (JS `Date.now()`) */
+  timestamp: number
+  /** Event kind, e.g. "code_change", "prompt", "terminal_command", "error" */
+  type: string
+  filePath?: string
+  linesAdded?: number
+  linesRemoved?: number
+  aiGenerated?: boolean
+  /** Free-form description (error message, prompt text, command line) */
+  details?: string
+  /** For error events: whether the error was later resolved */
+  resolved?: boolean
+  /** Git branch checked out when the event was captured */
+  branch?: string
+  /** Agent run that produced the event, when the capture layer knows it */
+  runId?: string
+  /** Outcome of a tool call or test run: "success" or "failure" */
+  status?: string
+  /** User verdict on an AI edit: "good", "bad", or "reverted" */
+  label?: string
+  /** Model that produced the event, e.g. "claude-sonnet" or "gpt-4o" */
+  model?: string
+  /** How the prompt was phrased or issued, e.g. "chat", "inline", "agent" */
+  promptStyle?: string
+  /** For AI suggestions: whether the user accepted it */
+  accepted?: boolean
+  /** Time from request to model response */
+  latencyMs?: number
+  /** Billed cost of the model call in US dollars */
+  costUsd?: number
+  /** Of lines_added, how many were still present at the last survival check */
+  linesSurviving?: number
+}
+/** Experiment definition shared by assign_bucket and analyze_experiment */
+export interface ExperimentConfig {
+  name: string
+  /** Variant names; the first is the control (default: ["control", "treatment"]) */
+  variants?: Array<string>
+  /** Extra hash input so the same experiment name can be re-run with fresh assignments */
+  salt?: string
+  /** What gets bucketed: "session" (default), "run", or "branch" */
+  unit?: string
+}
+/** Per-variant sample size */
+export interface VariantSummary {
+  variant: string
+  units: number
+  events: number
+}
+/** One metric of one variant compared against the control */
+export interface MetricComparison {
+  metric: string
+  variant: string
+  controlMean: number
+  variantMean: number
+  difference: number
+  /** difference / control_mean, when the control mean is non-zero */
+  relativeDifference?: number
+  /** Welch's t statistic (variant − control) */
+  tStatistic: number
+  degreesOfFreedom: number
+  /** Two-sided p-value */
+  pValue: number
+  /** Cohen's d */
+  effectSize: number
+  /** p_value < 0.05 */
+  significant: boolean
+}
+/** Result of analyze_experiment */
+export interface ExperimentAnalysis {
+  experiment: string
+  control: string
+  variants: Array<VariantSummary>
+  /** Metrics where both groups have at least two units, in variant then metric order */
+  comparisons: Array<MetricComparison>
+}
+/**
+ * Assign a unit to an experiment variant
+ *
+ * Hashes salt, experiment, and unit id, so the same unit always lands in
+ * the same variant and different experiments are independent.
+ *
+ * @param unit_id - Session, run, or user id being bucketed
+ * @param experiment - Experiment name
+ * @param salt - Optional salt to reshuffle assignments
+ * @param variants - Variant names (default: ["control", "treatment"])
+ * @returns The assigned variant name
+*/
+export declare function assignBucket(unitId: string, experiment: string, salt?: string | undefined | null, variants?: Array<string> | undefined | null): string
+/**
+ * Analyze an A/B experiment
+ *
+ * Buckets each unit with assign_bucket's hashing, computes per-unit metrics
+ * (events, churn, AI ratio, acceptance rate, errors, active minutes), and
+ * compares every variant against the control with Welch's t-test and
+ * Cohen's d. Events without a unit id are ignored.
+*/
+export declare function analyzeExperiment(events: Array<ActivityEvent>, experiment: ExperimentConfig): ExperimentAnalysis
+/** A piece of session text to index: an event summary, a prompt, a diff hunk */
+export interface SearchDocument {
+  /** Unique per document; indexing the same id again replaces it */
+  id: string
+  sessionId: string
+  text: string
+  /** Milliseconds since the epoch */
+  timestamp?: number
+  /** Event type or source, e.g. "code_change", "prompt" */
+  kind?: string
+}
+/** Options for SearchIndex.search */
+export interface SearchOptions {
+  /** Hits returned (default: 10) */
+  limit?: number
+  /** Only search documents of this session */
+  sessionId?: string
+  /** Return only the best hit per session (default: false) */
+  groupBySession?: boolean
+}
+/** Character range to highlight within a snippet */
+export interface HighlightRange {
+  start: number
+  end: number
+}
+/** A ranked document */
+export interface SearchHit {
+  id: string
+  sessionId: string
+  /** BM25 score; higher is better */
+  score: number
+  timestamp?: number
+  kind?: string
+  /** Excerpt around the densest cluster of matched terms */
+  snippet: string
+  /** Matched terms within the snippet, in characters */
+  highlights: Array<HighlightRange>
+}
+/** Size counters for a SearchIndex */
+export interface SearchIndexStats {
+  documents: number
+  sessions: number
+  /** Distinct indexed terms */
+  terms: number
+}
+/** Full-text index over session documents, ranked with BM25 */
+export class SearchIndex {
+  /** Create an empty index */
+  constructor()
+  /**
+   * Load an index written by save
+   *
+   * A torn last line, left by a crash during save, is skipped; the next
+   * save then rewrites the file.
+   *
+   * @param path - File written by save
+  */
+  static load(path: string): SearchIndex
+  /**
+   * Write the indexed documents to `path`
+   *
+   * The file is a journal of added and removed documents, not postings,
+   * which load rebuilds. Saving again to the same path appends only what
+   * changed since the last save; once the journal holds more than twice
+   * as many entries as there are documents, it is rewritten through a
+   * temporary file and renamed into place.
+  */
+  save(path: string): void
+  /** Index documents, replacing any already indexed under the same id */
+  addDocuments(documents: Array<SearchDocument>): void
+  /** Drop a document; returns whether it was indexed */
+  remove(id: string): boolean
+  /** Drop every document of a session; returns how many were removed */
+  removeSession(sessionId: string): number
+  /**
+   * Ranked full-text search
+   *
+   * Words are matched case-insensitively against whole words and the
+   * words inside identifiers, so `retry logic` finds `retryLogic`. Any
+   * word may match; `+word` must match, `-word` must not, `"a b"` matches
+   * the words in sequence, and `retr*` matches words starting with
+   * `retr`. Hits are ranked by BM25 and come with a highlighted snippet.
+   *
+   * @param query - Query text
+   * @param options - Result limit, session filter, one hit per session
+   * @returns Hits, best first
+  */
+  search(query: string, options?: SearchOptions | undefined | null): Array<SearchHit>
+  /** Document, session, and term counts */
+  stats(): SearchIndexStats
+}
+/** Options for fuzzy_match */
+export interface FuzzyOptions {
+  /** Matches returned, best first (default: all) */
+  limit?: number
+  /** Match case exactly; by default a query with an uppercase letter is case-sensitive and one without is not */
+  caseSensitive?: boolean
+}
+/** A candidate that matched the query */
+export interface FuzzyMatch {
+  candidate: string
+  /** Position in the candidates array */
+  index: number
+  score: number
+  /** Character indices of the matched query characters, ascending */
+  positions: Array<number>
+}
+/**
+ * Rank candidates against a fuzzy query, fzf style
+ *
+ * Every query character must appear in order. Matches score higher at
+ * word boundaries (after `/`, `_`, `-`, a space, or a lower-to-upper
+ * camelCase step) and in consecutive runs, and lose points for gaps;
+ * the best-scoring alignment is reported. Whitespace separates terms that
+ * must all match. Ties go to the shorter candidate, then input order.
+ * Candidates are scored in parallel.
+ *
+ * @param query - Search text; empty matches everything with score 0
+ * @param candidates - Paths, session titles, or other strings
+ * @param options - Result limit and case sensitivity (smart case by default)
+ * @returns Matches, best first, with matched character positions
+*/
+export declare function fuzzyMatch(query: string, candidates: Array<string>, options?: FuzzyOptions | undefined | null): Array<FuzzyMatch>
+/** Result of detect_generated */
+export interface GeneratedDetection {
+  /** Produced by a tool rather than written by hand; true for every minified file */
+  isGenerated: boolean
+  isMinified: boolean
+  /** Signals that fired: "marker", "filename", "sourcemap", "long-lines", "low-whitespace" */
+  reasons: Array<string>
+  /** Header text or file pattern behind the "marker" or "filename" reason */
+  evidence?: string
+  /** Longest line, in bytes */
+  longestLine: number
+  /** Mean length of non-blank lines, in bytes */
+  averageLineLength: number
+  /** Whitespace bytes over all bytes, 0-1 */
+  whitespaceRatio: number
+}
+/**
+ * Detect generated and minified files
+ *
+ * A file is generated when its first 30 lines carry a generator marker
+ * (`@generated`, `DO NOT EDIT`, `Code generated by`, `auto-generated`,
+ * `<auto-generated>`), its name matches a codegen or build-output pattern
+ * (`*.min.js`, `*.pb.go`, `*_pb2.py`, `*.g.dart`, lockfiles, ...), or it
+ * references a source map. It is minified when it is at least 256 bytes and
+ * either its non-blank lines average over 110 bytes with little whitespace,
+ * or one line runs past 500 bytes with almost none. Minified files also
+ * count as generated.
+ *
+ * @param content - File content
+ * @param filename - File path, for name patterns
+ * @returns Both flags, the signals behind them, and the line measurements used
+*/
+export declare function detectGenerated(content: string, filename?: string | undefined | null): GeneratedDetection
+/** Whether `content` was produced by a tool; see detect_generated */
+export declare function isGenerated(content: string, filename?: string | undefined | null): boolean
+/** Whether `content` is minified; see detect_generated */
+export declare function isMinified(content: string, filename?: string | undefined | null): boolean
+/** Options for grep_directory */
+export interface GrepOptions {
+  /** ripgrep `-g` globs: plain globs restrict the search to matching files, `!glob` excludes */
+  globs?: Array<string>
+  /** Search hidden files and directories (default: false) */
+  hidden?: boolean
+  /** Ignore .gitignore, .ignore, and git exclude files (default: false) */
+  noIgnore?: boolean
+  /** Skip files larger than this many bytes */
+  maxFileSize?: number
+  /** Stop after this many matches in total */
+  maxMatches?: number
+}
+/** One match in a file */
+export interface GrepMatch {
+  path: string
+  pattern: string
+  /** 1-based */
+  line: number
+  /** 1-based, in characters */
+  column: number
+  /** Byte offsets into the decoded file content */
+  start: number
+  end: number
+  text: string
+  /** The whole line the match starts on, without its terminator */
+  lineText: string
+}
+/** Result of grep_directory */
+export interface GrepResult {
+  /** Ordered by path, then position */
+  matches: Array<GrepMatch>
+  filesSearched: number
+  filesMatched: number
+  /** Binary and unreadable files; files over max_file_size are left out without counting */
+  skippedFiles: number
+  /** max_matches was reached and the search stopped early */
+  truncated: boolean
+}
+/**
+ * Search every file under a directory for regex patterns
+ *
+ * Walks the tree in parallel like ripgrep: `.gitignore`, `.ignore`, and
+ * hidden files are skipped unless the options say otherwise, and files
+ * are searched on the walker's threads as they are found. Files are
+ * decoded like decode_to_utf8; binary files (a NUL in the first 8 KiB)
+ * are skipped.
+ *
+ * @param root - Directory to search
+ * @param patterns - Regex patterns; an invalid pattern fails the call
+ * @param options - Globs, hidden files, ignore files, size and match limits
+ * @returns Matches with locations and line text, plus file counts
+*/
+export declare function grepDirectory(root: string, patterns: Array<string>, options?: GrepOptions | undefined | null): GrepResult
+/** Options for search_file */
+export interface FileSearchOptions {
+  /** Bytes read at a time (default: 1 MiB); lines longer than this are buffered whole */
+  chunkSize?: number
+  /** Matches returned at most; counts keep covering the whole file (default: unlimited) */
+  maxMatches?: number
+}
+/** One match in a streamed file */
+export interface FileSearchMatch {
+  pattern: string
+  /** 1-based */
+  line: number
+  /** 1-based, in characters */
+  column: number
+  /** Byte offset of the match in the file */
+  offset: number
+  text: string
+  /** The whole line the match starts on, without its terminator */
+  lineText: string
+}
+/** Result of search_file */
+export interface FileSearchResult {
+  path: string
+  /** Ordered by position */
+  matches: Array<FileSearchMatch>
+  /** Matches per pattern over the whole file, including any past max_matches */
+  counts: Record<string, number>
+  bytesRead: number
+  lines: number
+  /** max_matches cut matches off */
+  truncated: boolean
+}
+/**
+ * Search a file for regex patterns without loading it whole
+ *
+ * The file is read in chunks cut at line boundaries, so memory stays at
+ * about one chunk plus the longest line however large the file is.
+ * Matches therefore never span lines. Content is read as UTF-8 with
+ * invalid bytes replaced.
+ *
+ * @param path - File to search
+ * @param patterns - Regex patterns; an invalid pattern fails the call
+ * @param options - Chunk size and match cap
+ * @returns Matches with line, column, and file offset, plus per-pattern counts
+*/
+export declare function searchFile(path: string, patterns: Array<string>, options?: FileSearchOptions | undefined | null): FileSearchResult
+/**
+ * search_file off the main thread
+ * Same result, returned as a Promise
+ *
+ * Aborting the optional signal stops reading at the next chunk and
+ * rejects with an AbortError.
+*/
+export declare function searchFileAsync(path: string, patterns: Array<string>, options?: FileSearchOptions | undefined | null, signal?: AbortSignal | undefined | null): Promise<FileSearchResult>
+/** Options for connect_grpc */
+export interface GrpcOptions {
+  /** Request metadata, e.g. { authorization: "Bearer …" } */
+  headers?: Record<string, string>
+  /** Batches sent but not yet acknowledged before send waits (default: 64) */
+  maxInFlight?: number
+  /** (default: 10000) */
+  connectTimeoutMs?: number
+}
+/** Counters for a GrpcStream */
+export interface GrpcStreamStats {
+  /** Batches sent */
+  sent: number
+  /** Batches the collector acknowledged */
+  acknowledged: number
+  /** Batches sent and not yet acknowledged */
+  inFlight: number
+  /** Events the collector reported accepting */
+  accepted: number
+  /** Events the collector reported rejecting */
+  rejected: number
+  /** The collector's last non-empty error message */
+  lastError?: string
+}
+/**
+ * Open a stream of event batches to a gRPC collector
+ *
+ * Calls Collector.StreamEvents from proto/telemetry.proto, a
+ * bidirectional stream: batches go out as they are sent, and the
+ * collector answers each with an Ack carrying its sequence number and
+ * accepted and rejected counts. "https://" endpoints use TLS with the
+ * system's root certificates.
+ *
+ * @param endpoint - Collector address, e.g. "http://collector.internal:50051"
+ * @param options - Metadata, in-flight limit, and connect timeout
+ * @returns The open stream
+*/
+export declare function connectGrpc(endpoint: string, options?: GrpcOptions | undefined | null): Promise<GrpcStream>
+/** A bidirectional stream of event batches to a collector */
+export class GrpcStream {
+  /**
+   * Send a batch of events; resolves with its sequence number
+   *
+   * Waits while max_in_flight batches are unacknowledged, so a slow
+   * collector slows the sender instead of letting batches pile up in
+   * memory. Rejects once the stream has failed or been closed.
+  */
+  send(events: Array<Record<string, any>>): Promise<number>
+  /** Resolve once every batch sent so far is acknowledged; rejects if the stream fails first */
+  flush(): Promise<void>
+  /**
+   * End the stream and wait for the collector to finish it
+   *
+   * Batches already sent are still acknowledged; rejects if the
+   * collector ends the call with some unacknowledged.
+  */
+  close(): Promise<void>
+  stats(): GrpcStreamStats
+  /** The collector address connected to */
+  get endpoint(): string
+}
+/** Halstead counts, derived measures, and maintainability index for one file */
+export interface HalsteadMetrics {
+  language: string
+  /** Distinct operators (n1) */
+  distinctOperators: number
+  /** Distinct operands (n2) */
+  distinctOperands: number
+  /** Operator occurrences (N1) */
+  totalOperators: number
+  /** Operand occurrences (N2) */
+  totalOperands: number
+  /** n1 + n2 */
+  vocabulary: number
+  /** N1 + N2 */
+  length: number
+  /** N × log2(n) */
+  volume: number
+  /** (n1 / 2) × (N2 / n2) */
+  difficulty: number
+  /** Difficulty × volume */
+  effort: number
+  /** Effort / 18, Halstead's estimate of seconds to write the code */
+  timeSeconds: number
+  /** Volume / 3000, Halstead's delivered-bugs estimate */
+  estimatedBugs: number
+  /** Non-blank, non-comment lines */
+  codeLines: number
+  /** File-level cyclomatic complexity (1 + decision points) */
+  cyclomatic: number
+  /** 171 − 5.2 ln(V) − 0.23 G − 16.2 ln(LOC), rescaled to 0–100 */
+  maintainabilityIndex: number
+}
+/**
+ * Calculate Halstead metrics and maintainability index
+ *
+ * Tokenizes comment-stripped code into operators (punctuation, keywords,
+ * opening brackets) and operands (identifiers, numbers, string literals),
+ * then combines volume, cyclomatic complexity, and code lines into the
+ * rescaled maintainability index used by Visual Studio (100 = trivial).
+ *
+ * @param content - Source code to measure
+ * @param language - Language name as returned by detect_language
+ * @returns Halstead counts and derived measures
+*/
+export declare function calculateHalstead(content: string, language: string): HalsteadMetrics
+/** One identifier and how it is used */
+export interface IdentifierStat {
+  name: string
+  /** All occurrences */
+  count: number
+  /** Occurrences that introduce the name: `let x`, `def f`, `class C`, Python/Ruby `x = ...`, Go `x := ...` */
+  declarations: number
+  /** count - declarations */
+  usages: number
+  /** 1-based line of the first occurrence */
+  firstLine: number
+  /** "camelCase", "PascalCase", "snake_case", "SCREAMING_CASE", "kebab-case", or "lowercase" */
+  style: string
+}
+/** Result of extract_identifiers */
+export interface IdentifierReport {
+  /** Most frequent first, then by name */
+  identifiers: Array<IdentifierStat>
+  total: number
+  distinct: number
+  /** Names declared in this text */
+  declared: number
+  /** Share of distinct names per naming style, 0-1 */
+  styles: Record<string, number>
+}
+/** Vocabulary change between two versions, from compare_identifiers */
+export interface IdentifierDelta {
+  /** Names in after but not before, sorted */
+  introduced: Array<string>
+  /** Names in before but not after, sorted */
+  removed: Array<string>
+  /** Names in both */
+  kept: number
+  /** (introduced + removed) / names in either, 0-1 */
+  churn: number
+}
+/**
+ * Extract identifiers with their counts
+ *
+ * Identifiers come from the tokenizer (tokenize_code), so names inside
+ * comments and strings are ignored. An occurrence counts as a declaration
+ * when it follows a declaring keyword (`let`, `const`, `fn`, `def`,
+ * `class`, `struct`, `type`, ...), is assigned with Go's `:=`, or, in
+ * Python, Ruby, Lua, R, and shell, is assigned at the start of a line.
+ *
+ * @param content - Source code
+ * @param language - Language name as returned by detect_language
+ * @returns Identifiers with counts, declaration/usage split, first line, and naming style
+*/
+export declare function extractIdentifiers(content: string, language: string): IdentifierReport
+/**
+ * Compare the vocabulary of two versions of a file
+ *
+ * @param before - Earlier content
+ * @param after - Later content
+ * @param language - Language name as returned by detect_language
+ * @returns Names introduced and removed, names kept, and the churn ratio
+*/
+export declare function compareIdentifiers(before: string, after: string, language: string): IdentifierDelta
+/** One import statement (or one module of a multi-module statement) */
+export interface Import {
+  /** Module path as written, e.g. "./util", "os.path", "std::collections", "net/http" */
+  module: string
+  /** Top-level package or library, e.g. "@scope/pkg", "os", "serde"; None for relative imports */
+  package?: string
+  /** Names imported from the module when listed ("*" for wildcards); empty for whole-module imports */
+  names: Array<string>
+  /** Imports of the project's own files (./x, from . import, crate::, #include "x") */
+  relative: boolean
+  /** 1-based line of the statement */
+  line: number
+}
+/**
+ * Extract imported modules and packages from a file
+ *
+ * Recognizes import statements in javascript/typescript (import, export
+ * from, require, dynamic import), python, rust (use, extern crate), go,
+ * java, csharp (using), c/cpp (#include), and ruby (require). Statements
+ * inside comments and strings are ignored. Each import reports the module
+ * as written, its top-level package for grouping by library, the names
+ * imported, and whether it refers to the project's own files.
+ *
+ * @param content - Source code
+ * @param language - Language name as returned by detect_language
+ * @returns Imports in source order
+*/
+export declare function extractImports(content: string, language: string): Array<Import>
+/** Dominant indentation style of a file */
+export interface IndentationStyle {
+  /** "spaces", "tabs", or "none" when no line is indented */
+  style: string
+  /** Spaces per indent level (0 for tabs or none) */
+  width: number
+  indentedLines: number
+  spaceLines: number
+  tabLines: number
+  /** Lines whose leading whitespace contains both tabs and spaces */
+  mixedLines: number
+  /** Lines indented against the dominant style (minority character or mixed) */
+  inconsistentLines: number
+  /** Space-indented lines whose depth is not a multiple of the width */
+  misalignedLines: number
+}
+/** Indentation changes between two versions of a file */
+export interface IndentationDrift {
+  before: IndentationStyle
+  after: IndentationStyle
+  /** Dominant style flipped between tabs and spaces */
+  styleChanged: boolean
+  /** Both versions use spaces but with different widths */
+  widthChanged: boolean
+  /** Increase in inconsistent lines (0 if the new version is no worse) */
+  newInconsistentLines: number
+  /** Whether the new version drifted from the established style */
+  drifted: boolean
+}
+/**
+ * Detect a file's indentation style
+ *
+ * The width is the most common increase in leading spaces between
+ * consecutive indented lines. Blank lines and block comment continuation
+ * lines (` * ...`) are ignored.
+ *
+ * @param content - File content
+ * @returns Dominant style, width, and inconsistency counts
+*/
+export declare function detectIndentation(content: string): IndentationStyle
+/**
+ * Compare indentation before and after an edit
+ *
+ * Used to flag AI apply operations that introduce a different indentation
+ * style or width, or new inconsistently indented lines, into a file.
+ *
+ * @param before - Content before the edit
+ * @param after - Content after the edit
+ * @returns Both styles and what changed
+*/
+export declare function detectIndentationDrift(before: string, after: string): IndentationDrift
+/** Options for ingest_batch */
+export interface IngestOptions {
+  /**
+   * "atomic" (default): any invalid event rejects the whole batch;
+   * "partial": valid events are accepted and invalid ones reported
+  */
+  mode?: string
+  /** Replace secrets (keys, tokens, passwords) in event text (default: true) */
+  redact?: boolean
+  /**
+   * Merge consecutive edits to the same file in the same session that are
+   * at most this far apart (default: 0, no coalescing)
+  */
+  coalesceWindowMs?: number
+}
+/** An event that failed validation */
+export interface IngestRejection {
+  /** Position in the input batch */
+  index: number
+  id?: string
+  /** "InvalidEvent", "MissingType", "InvalidTimestamp", "InvalidId", "InvalidCount", or "InvalidLabel" */
+  code: string
+  message: string
+}
+/** Result of ingest_batch */
+export interface IngestBatch {
+  /** True when every event was accepted */
+  ok: boolean
+  mode: string
+  received: number
+  /** Events to persist, in input order; empty when an atomic batch had rejections */
+  accepted: Array<Record<string, any>>
+  rejected: Array<IngestRejection>
+  /** Events folded into a later edit of the same file */
+  coalesced: number
+  /** Events dropped because a later event in the batch has the same id */
+  duplicates: number
+  /** Secrets replaced across all accepted events */
+  redactions: number
+}
+/**
+ * Prepare a batch of raw events for persistence
+ *
+ * Validates each event (object with a type, a timestamp in epoch ms or
+ * ISO-8601, non-negative line counts, and a known outcome label), replaces
+ * secrets in event text, drops earlier copies of repeated ids, and, given a
+ * coalescing window, merges consecutive edits of the same file. Fields may be
+ * snake_case or camelCase, and line counts and file paths may sit inside
+ * `details`.
+ *
+ * In "atomic" mode a single invalid event rejects the batch so the caller
+ * persists nothing; "partial" mode returns the valid events alongside the
+ * rejections.
+ *
+ * @param events - Raw events as captured
+ * @param options - Mode, redaction, and coalescing window
+ * @returns Events ready to write in one transaction, plus any rejections
+*/
+export declare function ingestBatch(events: Array<Record<string, any>>, options?: IngestOptions | undefined | null): IngestBatch
+/** Options for a JsonlLog */
+export interface JsonlLogOptions {
+  /** Segment file name prefix, `<prefix>-<sequence>-<created ms>.jsonl` (default: "events") */
+  prefix?: string
+  /** Start a new segment once the active one reaches this size (default: 64 MiB) */
+  maxSegmentBytes?: number
+  /** Start a new segment once the active one is this old; 0 disables (default: 24 hours) */
+  maxSegmentAgeMs?: number
+  /** Fsync after this many unsynced events (default: 100) */
+  fsyncEvery?: number
+  /** Fsync when the last one was at least this long ago (default: 1000) */
+  fsyncIntervalMs?: number
+}
+/** One segment file */
+export interface JsonlSegment {
+  path: string
+  sequence: number
+  /** When the segment was started, in epoch milliseconds */
+  createdMs: number
+  bytes: number
+  /** The segment currently appended to */
+  active: boolean
+}
+/** Result of JsonlLog.append */
+export interface JsonlAppend {
+  /** Events written */
+  written: number
+  /** Whether this append ended with an fsync */
+  synced: boolean
+  /** Segment the events went to */
+  segment: string
+}
+/** Options for JsonlLog.compact */
+export interface CompactOptions {
+  /** Only compact segments started at least this long ago (default: 0, every sealed segment) */
+  olderThanMs?: number
+  /** Drop events with a timestamp earlier than this, in epoch milliseconds */
+  dropBefore?: number
+  /** Keep only the last copy of each event id (default: true) */
+  dedupe?: boolean
+}
+/** Result of JsonlLog.compact */
+export interface CompactResult {
+  /** Sealed segments merged; 0 when there was nothing to compact */
+  segmentsMerged: number
+  /** The merged segment, or none when every event was dropped */
+  path?: string
+  linesKept: number
+  /** Older copies of an id that a later line replaced */
+  duplicatesDropped: number
+  /** Events before drop_before */
+  expiredDropped: number
+  /** Torn or unparsable lines */
+  corruptDropped: number
+  bytesBefore: number
+  bytesAfter: number
+}
+/** Events read back from the log */
+export interface JsonlRead {
+  events: Array<Record<string, any>>
+  /** Lines skipped because they weren't valid JSON */
+  corruptLines: number
+}
+/** Append-only JSONL log split into rotating segments */
+export class JsonlLog {
+  /**
+   * Open the log in `dir`, creating the directory if needed
+   *
+   * Appends resume in the newest existing segment. If the process died
+   * mid-write, the partial last line is cut off so the segment stays
+   * valid JSONL.
+  */
+  constructor(dir: string, options?: JsonlLogOptions | undefined | null)
+  /**
+   * Append `events`, one JSON line each, in a single write
+   *
+   * The write reaches the OS before this returns, so it survives the
+   * process crashing. It is fsynced, surviving power loss, once
+   * fsync_every events or fsync_interval_ms have gone by since the last
+   * fsync. A batch is never split across segments.
+  */
+  append(events: Array<Record<string, any>>): JsonlAppend
+  /** Fsync everything appended so far */
+  flush(): void
+  /** Seal the active segment and start a new one; returns the new segment's path */
+  rotate(): string
+  /** Segment files, oldest first */
+  segments(): Array<JsonlSegment>
+  /** Every event in the log, oldest segment first; unparsable lines are skipped and counted */
+  readAll(): JsonlRead
+  /**
+   * Merge sealed segments into one, dropping what is no longer needed
+   *
+   * The active segment is never touched. Corrupt lines are always
+   * dropped, expired events when `drop_before` is set, and earlier copies
+   * of an id unless `dedupe` is false. The merged segment is written to a
+   * temporary file, fsynced, and renamed over the oldest input before the
+   * other inputs are deleted, so a crash leaves at worst duplicates that
+   * the next compaction removes.
+  */
+  compact(options?: CompactOptions | undefined | null): CompactResult
+  /**
+   * Apply a retention policy to the sealed segments
+   *
+   * Segments are visited oldest first, until batch_size events have been
+   * examined. In each, events within aggregate_ms are replaced by
+   * per-bucket rollup records (`type: "aggregate"`, timestamped at the
+   * bucket start), older events are dropped, and so are rollups whose
+   * newest event is past aggregate_ms. A changed segment is rewritten in
+   * place, or deleted once empty; one with nothing expired is left as is.
+   * Call repeatedly until `done`; the next call after that starts a new
+   * pass. Rollups of the same bucket may be split across segments, so
+   * sum them when reading.
+  */
+  applyRetention(policy: RetentionPolicy): RetentionProgress
+  /** Directory the segments live in */
+  get dir(): string
+}
+/** A piece of session text: a diff hunk, a prompt, a commit message */
+export interface KeywordDocument {
+  sessionId: string
+  text: string
+}
+/** Options for extract_keywords */
+export interface KeywordOptions {
+  /** Terms returned per session (default: 10) */
+  topN?: number
+  /** "tfidf" (default) scores single terms against the other sessions; "rake" scores multi-word phrases within each session */
+  method?: string
+  /** Shortest term kept, in characters (default: 3) */
+  minLength?: number
+  /** Terms in the label (default: 3) */
+  labelTerms?: number
+}
+/** A ranked term */
+export interface Keyword {
+  term: string
+  score: number
+  /** Occurrences in the session */
+  count: number
+}
+/** Keywords for one session */
+export interface SessionKeywords {
+  sessionId: string
+  /** Highest score first */
+  keywords: Array<Keyword>
+  /** Top terms joined with ", ", for a session title */
+  label: string
+}
+/**
+ * Extract the top keywords of each session
+ *
+ * Documents are grouped by session. Diff headers and `+`/`-` markers are
+ * dropped, identifiers are split into words (`parseConfig` -> parse,
+ * config), and stopwords and language keywords are ignored. TF-IDF ranks a
+ * session's terms by how much more often they appear there than in the
+ * other sessions, so pass several sessions together for distinctive
+ * labels. RAKE ranks multi-word phrases within each session on its own.
+ *
+ * @param documents - Session texts; a session may have many
+ * @param options - Terms per session, method, minimum term length, label size
+ * @returns Keywords and a label per session, in order of first appearance
+*/
+export declare function extractKeywords(documents: Array<KeywordDocument>, options?: KeywordOptions | undefined | null): Array<SessionKeywords>
+/** A less likely language for the same input */
+export interface LanguageAlternate {
+  language: string
+  confidence: number
+}
+/** Result of detect_language */
+export interface LanguageGuess {
+  /** Language name, or "unknown" */
+  language: string
+  /** 0-1; 0 when nothing matched */
+  confidence: number
+  /** What decided it: "modeline", "filename", "shebang", "extension", "content", or "none" */
+  source: string
+  /** Other plausible languages, most likely first */
+  alternates: Array<LanguageAlternate>
+}
+/**
+ * Detect the language of a file
+ *
+ * Checks, in order: a Vim (`vim: ft=python`) or Emacs (`-*- mode: ruby -*-`)
+ * modeline, well-known file names (Makefile, Dockerfile, CMakeLists.txt,
+ * Gemfile, BUILD, ...), a `#!` interpreter line, the extension (over 60
+ * languages), and finally the content itself (see classify_language). Extensions shared between
+ * languages (`.h`, `.m`, `.pl`, `.v`) are settled by content hints and
+ * report the other candidates as alternates.
+ *
+ * @param content - File content
+ * @param filename - File path, when known
+ * @returns Language name ("unknown" when nothing matched), confidence, deciding signal, and alternates
+*/
+export declare function detectLanguage(content: string, filename?: string | undefined | null): LanguageGuess
+/** A lexical token */
+export interface CodeToken {
+  /** "keyword", "identifier", "number", "string", "comment", "operator", "punctuation", "whitespace", or "other" */
+  kind: string
+  text: string
+  /** UTF-8 byte offsets, end exclusive */
+  start: number
+  end: number
+  /** 1-based line of the first character */
+  line: number
+  /** 1-based column of the first character, in characters */
+  column: number
+}
+/** Options for tokenize_code */
+export interface TokenizeOptions {
+  /** Include comment tokens (default: true) */
+  comments?: boolean
+  /** Include whitespace tokens (default: false) */
+  whitespace?: boolean
+}
+/**
+ * Split source code into lexical tokens
+ *
+ * Recognizes the language's comment styles, string literals (including
+ * Python triple quotes, template literals, and multi-line strings where
+ * the language allows them), numbers (`0x1F`, `1_000`, `3.14e-2`), keywords,
+ * identifiers, and multi-character operators. Unknown languages use the
+ * common `//`, `#`, and `/* */` comment styles.
+ *
+ * @param content - Source code
+ * @param language - Language name as returned by detect_language
+ * @param options - Whether to include comment and whitespace tokens
+ * @returns Tokens in source order with their kind, text, and position
+*/
+export declare function tokenizeCode(content: string, language: string, options?: TokenizeOptions | undefined | null): Array<CodeToken>
+/** Line counts for one language across a directory */
+export interface LanguageBreakdown {
+  language: string
+  files: number
+  lines: number
+  codeLines: number
+  commentLines: number
+  blankLines: number
+  bytes: number
+}
+/** Result of analyze_directory */
+export interface DirectoryAnalysis {
+  root: string
+  files: number
+  lines: number
+  codeLines: number
+  commentLines: number
+  blankLines: number
+  bytes: number
+  /** Files skipped as unrecognized, binary, or unreadable */
+  skippedFiles: number
+  /** Per-language totals, most code lines first */
+  languages: Array<LanguageBreakdown>
+}
+/**
+ * Count code, comment, and blank lines per language under a directory
+ *
+ * The tree is walked in parallel. `.gitignore`, `.ignore`, and hidden files
+ * are skipped, so build output and dependencies stay out of the totals.
+ * Languages are recognized by file extension; other files, binary files,
+ * and non-UTF-8 files count as skipped.
+ *
+ * @param path - Root directory
+ * @param ignore_globs - Extra gitignore-style globs to exclude, e.g. `["dist/", "*.min.js"]`
+ * @returns Totals and per-language breakdown
+*/
+export declare function analyzeDirectory(path: string, ignoreGlobs?: Array<string> | undefined | null): DirectoryAnalysis
+/** One declared dependency */
+export interface Dependency {
+  name: string
+  /** Requirement as declared, e.g. "^1.2.0", ">=2.31", "v0.4.1"; "path:..", "git:..", or "workspace" for non-registry sources */
+  version: string
+  /** "normal", "dev", "build", "peer", "optional", or "indirect" */
+  kind: string
+}
+/** Dependencies declared by one manifest */
+export interface Manifest {
+  /** "package.json", "Cargo.toml", "pyproject.toml", or "go.mod" */
+  format: string
+  /** Package or module name, when declared */
+  name?: string
+  version?: string
+  /** In declaration order */
+  dependencies: Array<Dependency>
+}
+/** One dependency that differs between two versions of a manifest */
+export interface DependencyChange {
+  name: string
+  kind: string
+  /** "added", "removed", "upgraded", "downgraded", or "changed" (same version numbers, different requirement or source) */
+  change: string
+  before?: string
+  after?: string
+}
+/** Result of diff_manifests */
+export interface ManifestDiff {
+  format: string
+  /** Sorted by kind, then name */
+  changes: Array<DependencyChange>
+  added: number
+  removed: number
+  upgraded: number
+  downgraded: number
+  changed: number
+}
+/**
+ * Parse the dependencies declared by a manifest
+ *
+ * The format comes from the file name: package.json (dependencies, dev,
+ * peer, and optional), Cargo.toml (normal, dev, and build dependencies,
+ * including target-specific tables), pyproject.toml (PEP 621 project
+ * dependencies and extras, dependency groups, build requirements, and Poetry
+ * tables), or go.mod (require lines, with `// indirect` ones marked). Empty
+ * content parses as a manifest with no dependencies.
+ *
+ * @param content - Manifest text
+ * @param filename - Manifest path; only the file name is used
+ * @returns Package name and version, and dependencies in declaration order
+*/
+export declare function parseManifest(content: string, filename: string): Manifest
+/**
+ * Compare two versions of a manifest
+ *
+ * Dependencies are matched by name and kind. A dependency whose leading
+ * version numbers went up is "upgraded", down is "downgraded", and one
+ * whose requirement text changed otherwise (a new range operator, a path or
+ * git source) is "changed". Moving a package from dev to normal shows as a
+ * removal and an addition.
+ *
+ * @param before - Manifest text before the change; empty for a new file
+ * @param after - Manifest text after the change; empty for a deleted file
+ * @param filename - Manifest path; only the file name is used
+ * @returns Changed dependencies and counts per kind of change
+*/
+export declare function diffManifests(before: string, after: string, filename: string): ManifestDiff
+/** Options for a MinHashIndex */
+export interface MinHashIndexOptions {
+  /** Signature length; more is more accurate and slower (default: 128) */
+  numHashes?: number
+  /** LSH bands the signature is split into; must divide num_hashes (default: 32) */
+  bands?: number
+  /** Consecutive tokens per shingle (default: 3) */
+  shingleSize?: number
+}
+/** An indexed document similar to the query */
+export interface MinHashMatch {
+  id: string
+  /** Estimated Jaccard similarity of the token shingle sets, 0-1 */
+  similarity: number
+}
+/** Index of MinHash signatures bucketed by LSH band */
+export class MinHashIndex {
+  /** Create an empty index; throws if `bands` doesn't divide `num_hashes` */
+  constructor(options?: MinHashIndexOptions | undefined | null)
+  /**
+   * Index `text` under `id`, replacing any document already stored there
+   *
+   * Text without tokens is stored but never matches.
+  */
+  insert(id: string, text: string): void
+  /** Drop a document; returns whether it was indexed */
+  remove(id: string): boolean
+  /**
+   * Indexed documents whose estimated similarity to `text` is at least `threshold`
+   *
+   * Only documents sharing a whole band with the query are compared, so
+   * lookups stay fast however many documents are indexed. With the
+   * default 32 bands of 4 rows, documents above about 0.6 similarity are
+   * found almost always and those near 0.4 about half the time; use more
+   * bands for lower thresholds.
+   *
+   * @param text - Text to look up
+   * @param threshold - Lowest similarity returned, 0-1 (default: 0.5)
+   * @param limit - Most matches to return
+   * @returns Matches, most similar first, ties by id
+  */
+  query(text: string, threshold?: number | undefined | null, limit?: number | undefined | null): Array<MinHashMatch>
+  /** Whether a document is indexed under `id` */
+  has(id: string): boolean
+  /** Number of indexed documents */
+  get size(): number
+  /** Drop every document */
+  clear(): void
+}
+/** Half-open time window `[start, end)` in epoch milliseconds; open ends are unbounded */
+export interface TimeRange {
+  start?: number
+  end?: number
+}
+/** Options for compare_models */
+export interface ModelComparisonOptions {
+  /** Bootstrap resamples per metric (default: 1000) */
+  iterations?: number
+  /** Confidence level of the intervals (default: 0.95) */
+  confidence?: number
+  /** Seed for the resampling, so reports are reproducible (default: fixed) */
+  seed?: number
+}
+/** A metric value with a bootstrap confidence interval */
+export interface Estimate {
+  value: number
+  lower: number
+  upper: number
+  /** Events contributing to the estimate */
+  samples: number
+}
+/** Comparison row for one model */
+export interface ModelScorecard {
+  model: string
+  events: number
+  /** Suggestions with a known accept/reject outcome */
+  suggestions: number
+  accepted: number
+  /** accepted / suggestions */
+  acceptance?: Estimate
+  /** Surviving AI-added lines / AI-added lines, over events with a survival check */
+  survival?: Estimate
+  good: number
+  bad: number
+  reverted: number
+  /** Weighted share of positive outcomes: labels count double, accept/reject once */
+  feedback?: Estimate
+  /** Mean response latency */
+  latencyMs?: Estimate
+  /** Mean cost per call */
+  costUsd?: Estimate
+  totalCostUsd: number
+}
+/** Result of compare_models */
+export interface ModelComparison {
+  start?: number
+  end?: number
+  confidence: number
+  /** Scorecards ordered by feedback score, best first; models without feedback last */
+  models: Array<ModelScorecard>
+  bestModel?: string
+  /** Whether the best model's feedback interval lies entirely above the runner-up's */
+  bestIsSignificant: boolean
+}
+/**
+ * Compare models across acceptance, survival, feedback, latency, and cost
+ *
+ * Considers events with a `model` inside `range`. Every rate and mean comes
+ * with a percentile bootstrap confidence interval so small samples are not
+ * over-read in the model scorecard.
+*/
+export declare function compareModels(events: Array<ActivityEvent>, range?: TimeRange | undefined | null, options?: ModelComparisonOptions | undefined | null): ModelComparison
+/**
+ * Encode events as a MessagePack batch
+ *
+ * The batch is one version byte followed by a MessagePack array of the
+ * events, with their field names kept, so it decodes without a schema.
+ * Typically several times smaller than the same events as JSON.
+ *
+ * @param events - Events as plain objects
+ * @returns The encoded batch
+*/
+export declare function encodeEvents(events: Array<Record<string, any>>): Buffer
+/**
+ * Decode a batch written by encode_events
+ *
+ * Throws on a batch from a newer format version rather than guessing at
+ * its layout, and on truncated or malformed input.
+ *
+ * @param bytes - An encoded batch
+ * @returns The events, in the order they were encoded
+*/
+export declare function decodeEvents(bytes: Buffer): Array<Record<string, any>>
+/** Resource description shared by the conversions and export_otlp */
+export interface OtlpResourceOptions {
+  /** `service.name` resource attribute (default: "cursor-telemetry") */
+  serviceName?: string
+  /** More resource attributes, e.g. { "host.name": "laptop", "team": "web" } */
+  resourceAttributes?: Record<string, string>
+}
+/** Options for export_otlp */
+export interface OtlpExportOptions {
+  /** Collector base URL for OTLP/HTTP, e.g. "http://localhost:4318"; /v1/logs and /v1/metrics are appended */
+  endpoint: string
+  /** Extra request headers, e.g. an API key */
+  headers?: Record<string, string>
+  serviceName?: string
+  resourceAttributes?: Record<string, string>
+  /** Send events as log records (default: true) */
+  logs?: boolean
+  /** Send per-type counts and line totals as metrics (default: true) */
+  metrics?: boolean
+  /** Per-request timeout (default: 10000) */
+  timeoutMs?: number
+}
+/** Outcome of export_otlp */
+export interface OtlpExportResult {
+  /** Log records sent */
+  logRecords: number
+  /** Metric data points sent */
+  dataPoints: number
+  /** Log records the collector reported rejecting */
+  rejectedLogRecords: number
+  /** Data points the collector reported rejecting */
+  rejectedDataPoints: number
+  /** Collector's explanation of a partial rejection */
+  errorMessage?: string
+}
+/**
+ * Events as an OTLP ExportLogsServiceRequest in the OTLP/HTTP JSON encoding
+ *
+ * Each event becomes one log record: its timestamp, the whole event as
+ * the body, and event.type, event.id, session.id, workspace.path, and
+ * code.filepath attributes when present.
+ *
+ * @param events - Events with at least a type and timestamp
+ * @param options - service.name and other resource attributes
+ * @returns The request body, ready to POST to a collector's /v1/logs
+*/
+export declare function toOtlpLogs(events: Array<Record<string, any>>, options?: OtlpResourceOptions | undefined | null): Record<string, any>
+/**
+ * Event counts and line totals as an OTLP ExportMetricsServiceRequest
+ *
+ * Three monotonic delta sums, with a data point per event type and
+ * workspace: cursor.events, and cursor.lines_added and
+ * cursor.lines_removed for groups with line counts. Every point spans
+ * the batch's earliest to latest timestamp.
+ *
+ * @param events - Events with at least a type and timestamp
+ * @param options - service.name and other resource attributes
+ * @returns The request body, ready to POST to a collector's /v1/metrics
+*/
+export declare function toOtlpMetrics(events: Array<Record<string, any>>, options?: OtlpResourceOptions | undefined | null): Record<string, any>
+/**
+ * Send events to an OpenTelemetry collector over OTLP/HTTP
+ *
+ * Posts to_otlp_logs to `<endpoint>/v1/logs` and to_otlp_metrics to
+ * `<endpoint>/v1/metrics` as JSON, off the main thread. Rejects when the
+ * collector can't be reached or answers with an error status; records
+ * the collector accepted only in part are counted in the result instead.
+ * gRPC collectors need their OTLP/HTTP receiver (port 4318 by default)
+ * enabled.
+ *
+ * @param events - Events to send
+ * @param options - Collector endpoint, headers, and what to send
+ * @returns Counts sent and rejected
+*/
+export declare function exportOtlp(events: Array<Record<string, any>>, options: OtlpExportOptions): Promise<OtlpExportResult>
+/** Outcome counts for one group of labeled events */
+export interface OutcomeQuality {
+  key: string
+  /** Events carrying a recognised label */
+  labeled: number
+  good: number
+  bad: number
+  reverted: number
+  /** good / labeled */
+  acceptanceRate: number
+  /** reverted / labeled */
+  revertRate: number
+}
+/**
+ * Compute acceptance quality of labeled AI edits
+ *
+ * Only events labeled "good", "bad", or "reverted" are counted; groups are
+ * ordered by number of labeled events, most first.
+ *
+ * @param events - Activity events with `label` set by the outcome labeling API
+ * @param group_by - "model", "language" (from the file extension), or "prompt_style"
+ * @returns Outcome counts and rates per group
+*/
+export declare function acceptanceQuality(events: Array<ActivityEvent>, groupBy: string): Array<OutcomeQuality>
+/** One labeled value of a metric */
+export interface PrometheusSample {
+  labels?: Record<string, string>
+  /** The value, for counters and gauges */
+  value?: number
+  /** Raw observations, for histograms; bucketed when rendered */
+  observations?: Array<number>
+}
+/** A metric family: a name, its type, and its samples */
+export interface PrometheusMetric {
+  name: string
+  help?: string
+  /** "counter", "gauge", or "histogram" */
+  type: string
+  samples: Array<PrometheusSample>
+  /** Histogram bucket upper bounds, ascending; +Inf is always added */
+  buckets?: Array<number>
+}
+/**
+ * Render metric families in the Prometheus text exposition format (0.0.4)
+ *
+ * Counters and gauges print one line per sample. Histograms bucket each
+ * sample's observations into cumulative `_bucket{le="..."}` lines plus
+ * `_sum` and `_count`. Label values are escaped and label keys sorted, so
+ * the output is stable for a given input.
+ *
+ * @param metrics - Metric families, e.g. from activity_metrics
+ * @returns The exposition text, ending in a newline
+*/
+export declare function renderPrometheus(metrics: Array<PrometheusMetric>): string
+/**
+ * Standard activity metrics for a set of events
+ *
+ * cursor_events_total and cursor_lines_{added,removed}_total counters by
+ * type and workspace, a cursor_sessions gauge of distinct sessions per
+ * workspace, and a cursor_edit_size_lines histogram of lines touched per
+ * edit. Pass the result to render_prometheus or a PrometheusServer.
+ *
+ * @param events - Events with a type, and optionally workspace, session, and line counts
+ * @returns Metric families
+*/
+export declare function activityMetrics(events: Array<Record<string, any>>): Array<PrometheusMetric>
+/** A minimal HTTP server answering Prometheus scrapes at /metrics */
+export class PrometheusServer {
+  /**
+   * Listen on `host:port` (default host 127.0.0.1) and serve /metrics
+   *
+   * Port 0 picks a free port; read it back from `port`. Requests are
+   * answered one at a time on a background thread with whatever was last
+   * passed to update, so scrapes never wait on the event loop.
+  */
+  static start(port: number, host?: string | undefined | null): PrometheusServer
+  /** Replace what scrapes return with these metrics */
+  update(metrics: Array<PrometheusMetric>): void
+  /** The port being listened on */
+  get port(): number
+  /** Stop listening; waits for a request in progress to finish */
+  stop(): void
+}
+/** A run of lines of one kind */
+export interface TextSegment {
+  /** "prose" or "code" */
+  kind: string
+  /** 1-based, inclusive */
+  startLine: number
+  endLine: number
+}
+/** Result of classify_prose */
+export interface ProseAnalysis {
+  /** Language whose rules were applied, or "text" */
+  language: string
+  /** Prose characters over non-blank characters, 0-1 */
+  proseFraction: number
+  proseLines: number
+  codeLines: number
+  blankLines: number
+  proseChars: number
+  codeChars: number
+  /** Consecutive lines of the same kind, blank lines absorbed into the run around them */
+  segments: Array<TextSegment>
+}
+/**
+ * Estimate how much of a text is prose and how much is code
+ *
+ * Markdown and other document formats are prose except fenced and
+ * indented code blocks. In source code, comment lines that read as
+ * sentences are prose and everything else, commented-out code included,
+ * is code. Text with no known language (chat messages, snippets) is
+ * judged line by line from word shapes and code punctuation, with fenced
+ * blocks always code.
+ *
+ * @param content - Text to classify
+ * @param language - Language name as returned by detect_language; omit for chat and other free text
+ * @returns Prose fraction by characters, line counts, and prose/code segments
+*/
+export declare function classifyProse(content: string, language?: string | undefined | null): ProseAnalysis
+/** Options for link_prompts_to_diffs */
+export interface ProvenanceOptions {
+  /** How long after an interaction's last response a change can still be linked to it (default: 600000) */
+  windowMs?: number
+  /** Tokens per shingle when comparing code (default: 3) */
+  shingleSize?: number
+  /** Share of confidence from content, the rest from timing, 0-1 (default: 0.8) */
+  contentWeight?: number
+  /** Lowest confidence reported as a link (default: 0.3) */
+  minConfidence?: number
+}
+/** Where one file change most likely came from */
+export interface DiffProvenance {
+  diffId?: string
+  timestamp: number
+  filePath?: string
+  /** The prompt, or the first response when no prompt was captured; None when unlinked */
+  promptId?: string
+  /** Response events of the interaction, in time order */
+  responseIds: Array<string>
+  /** 0-1 */
+  confidence: number
+  /** Share of the inserted code found in the interaction's responses, 0-1 */
+  contentMatch: number
+  /** Time from the interaction's last response before the change */
+  gapMs?: number
+  /** "content", "timing" (no code to compare), or "none" (unlinked) */
+  method: string
+  /** Interactions within the window that were considered */
+  candidates: number
+}
+/**
+ * Link each file change to the prompt most likely to have caused it
+ *
+ * Prompts and the responses after them (`response` events, `chat` events
+ * by role, and `ai_suggestion` events) form interactions. Each edit
+ * (`code_change` or `file_change`) is compared with the interactions that
+ * started before it and whose last response is at most `window_ms`
+ * earlier, in the same workspace when both record one. The lines it
+ * inserted, from `added_text` or by diffing `before_content` and
+ * `after_content`, are matched against the code in the responses (fenced
+ * blocks, or whole texts without fences) by shingle containment; the match
+ * is weighted with how soon the change followed. When there is no code to
+ * compare, timing alone links the change, with confidence at most 0.5.
+ *
+ * @param events - Chat, suggestion, and edit events, in any order
+ * @param options - Window, weights, and the confidence needed for a link
+ * @returns One entry per edit, in time order; unlinked edits have no prompt_id
+*/
+export declare function linkPromptsToDiffs(events: Array<Record<string, any>>, options?: ProvenanceOptions | undefined | null): Array<DiffProvenance>
+/** Options for a DiskQueue */
+export interface DiskQueueOptions {
+  /** Start a new segment file once the current one reaches this size (default: 16 MiB) */
+  maxSegmentBytes?: number
+  /** Fsync every enqueue before returning (default: true) */
+  fsync?: boolean
+  /** Hand an unacknowledged record out again after this long; 0 waits for nack or a restart (default: 0) */
+  visibilityTimeoutMs?: number
+}
+/** A record handed out by dequeue */
+export interface QueueItem {
+  /** Pass to ack once the record is safely uploaded */
+  id: number
+  data: Buffer
+  /** Times this record has been handed out since the queue was opened, this one included */
+  attempts: number
+}
+/** Counts for a DiskQueue */
+export interface QueueStats {
+  /** Records waiting to be dequeued */
+  pending: number
+  /** Records dequeued but not yet acknowledged */
+  inFlight: number
+  segments: number
+  /** Size of all segment files */
+  bytes: number
+  /** Bytes of torn or corrupt records cut off when the queue was opened */
+  recoveredBytes: number
+}
+/** Crash-safe FIFO of byte records with acknowledgement */
+export class DiskQueue {
+  /**
+   * Open the queue in `dir`, creating it if needed
+   *
+   * Records that were enqueued but never acknowledged come back as
+   * pending, including ones dequeued before a crash. A record torn by a
+   * crash mid-write fails its checksum and is cut off, along with
+   * anything after it in that segment.
+  */
+  constructor(dir: string, options?: DiskQueueOptions | undefined | null)
+  /**
+   * Append records; returns their ids
+   *
+   * The records are written in one write and, unless fsync is off,
+   * flushed to disk before this returns.
+  */
+  enqueue(records: Array<Buffer>): Array<number>
+  /**
+   * Hand out up to `max` records, oldest first (default: 1)
+   *
+   * Each stays on disk until acknowledged. Until then it is in flight:
+   * not handed out again unless nacked, its visibility timeout passes,
+   * or the queue is reopened.
+  */
+  dequeue(max?: number | undefined | null): Array<QueueItem>
+  /**
+   * Confirm records are handled; returns how many were in flight
+   *
+   * The index only advances past the oldest unacknowledged record, so
+   * records acknowledged out of order can be handed out again after a
+   * crash. Delivery is at least once: make uploads idempotent by id.
+  */
+  ack(ids: Array<number>): number
+  /** Return in-flight records to the front of the queue; returns how many were in flight */
+  nack(ids: Array<number>): number
+  /** Records not yet acknowledged, pending or in flight */
+  get length(): number
+  /** Pending and in-flight counts and disk usage */
+  stats(): QueueStats
+}
+/** Line churn split into real additions/removals and refactoring */
+export interface ChurnStats {
+  /** Raw inserted lines from the line diff */
+  linesAdded: number
+  /** Raw deleted lines from the line diff */
+  linesRemoved: number
+  movedLines: number
+  renamedLines: number
+  /** moved_lines + renamed_lines */
+  refactorLines: number
+  /** Inserted lines that are neither moves nor renames */
+  netLinesAdded: number
+  /** Deleted lines that are neither moves nor renames */
+  netLinesRemoved: number
+  /** True when every changed line is a move or rename */
+  isPureRefactor: boolean
+}
+/**
+ * Calculate churn with moved and renamed lines reported separately
+ *
+ * @param text1 - Original text
+ * @param text2 - Modified text
+ * @returns ChurnStats where net_lines_* exclude refactoring
+*/
+export declare function calculateChurn(text1: string, text2: string): ChurnStats
+/** Pacing limits for replay_at_speed */
+export interface ReplayOptions {
+  /** Upper bound on delivered events per second of wall time (default: 500) */
+  maxEventsPerSecond?: number
+  /**
+   * Longest wall-clock pause between consecutive events after speedup, so idle
+   * stretches don't stall the replay (default: 2000)
+  */
+  maxGapMs?: number
+  /** Events due within the same frame are delivered in one callback (default: 50) */
+  frameMs?: number
+  /** Batches queued on the JS thread before the replay waits for the renderer (default: 2) */
+  maxInFlight?: number
+}
+/** Outcome of a completed replay */
+export interface ReplaySummary {
+  events: number
+  /** Number of callback invocations */
+  batches: number
+  /** Time between the first and last event, in event time */
+  spanMs: number
+  /** Wall time the replay took */
+  durationMs: number
+  /** Wall time spent waiting for the JS thread to drain queued batches */
+  stalledMs: number
+  /** Events delivered after their scheduled time because of the rate limit */
+  rateLimitedEvents: number
+}
+/**
+ * Replay historical events at an accelerated but bounded rate
+ *
+ * Events are sorted by timestamp and delivered to `callback` in batches,
+ * one batch per frame, with gaps shrunk by `speedup` and capped at
+ * `max_gap_ms`. At most `max_events_per_second` are delivered; events over
+ * the limit slip to later frames. When the renderer falls behind and
+ * `max_in_flight` batches are already queued on the JS thread, the replay
+ * clock pauses until it catches up, so a slow frame never turns into a burst.
+ *
+ * Pacing runs on its own thread. Aborting the signal stops the replay and
+ * rejects the Promise with an AbortError.
+ *
+ * @param events - Events to replay
+ * @param speedup - Event-time milliseconds per wall-clock millisecond, e.g. 60 for a minute per second
+ * @param callback - Called with each batch of events
+ * @returns Promise resolving to a summary once every event is delivered
+*/
+export declare function replayAtSpeed(events: Array<ActivityEvent>, speedup: number, callback: (events: Array<ActivityEvent>) => void, options?: ReplayOptions | undefined | null, signal?: AbortSignal | undefined | null): Promise<ReplaySummary>
+/** How long events are kept, and in what detail */
+export interface RetentionPolicy {
+  /** Keep every event this long, e.g. 30 days */
+  fullDetailMs: number
+  /** Keep rollups of older events until they are this old, e.g. 1 year; unset deletes them outright */
+  aggregateMs?: number
+  /** Width of a rollup bucket (default: 1 hour) */
+  bucketMs?: number
+  /** Most events handled per call (default: 10000) */
+  batchSize?: number
+  /** The time the cutoffs are measured back from, in epoch milliseconds (default: now) */
+  now?: number
+}
+/** What one retention call did, and whether more is left */
+export interface RetentionProgress {
+  /** Nothing past the full-detail cutoff is left to process */
+  done: boolean
+  /** Events examined in this call */
+  processed: number
+  /** Events folded into rollups */
+  aggregated: number
+  /** Events deleted without a rollup, being past aggregate_ms */
+  deleted: number
+  /** Rollups written or added to */
+  buckets: number
+  /** Rollups deleted for being past aggregate_ms */
+  aggregatesDeleted: number
+  /** Work left: expired events in the store, segments not yet examined in the log */
+  remaining: number
+}
+/** Events of one type, workspace, and file within one bucket */
+export interface EventAggregate {
+  /** Start of the bucket, in epoch milliseconds */
+  bucketStart: number
+  type: string
+  workspacePath?: string
+  filePath?: string
+  count: number
+  linesAdded: number
+  linesRemoved: number
+  firstSeen: number
+  lastSeen: number
+}
+/** Usage of a ring, read from its shared header */
+export interface RingStats {
+  /** Bytes available for records */
+  capacity: number
+  /** Bytes currently holding unread records, including padding */
+  used: number
+  /** Records the producer couldn't fit and discarded */
+  dropped: number
+}
+/** A memory-mapped ring file, shared by one producer and one consumer process */
+export class SharedRing {
+  /**
+   * Create (or replace) a ring file at `path` with room for `capacity` bytes of records
+   *
+   * Each record takes its length plus 4 bytes, rounded up to a multiple
+   * of 8. Put the file on a RAM-backed filesystem (/dev/shm on Linux)
+   * to keep it off disk.
+   *
+   * The new ring is built in a temporary file and renamed over `path`,
+   * so a process still attached to a ring it replaces keeps a valid
+   * mapping of the old file; it has to open the new one to see it.
+  */
+  static create(path: string, capacity: number): SharedRing
+  /** Attach to a ring file made by create, typically from the other process */
+  static open(path: string): SharedRing
+  /** The writing end; use it from exactly one process at a time */
+  producer(): RingProducer
+  /** The reading end; use it from exactly one process at a time */
+  consumer(): RingConsumer
+  /** Capacity, bytes in use, and records dropped so far */
+  stats(): RingStats
+  /** The ring file */
+  get path(): string
+}
+/** Writing end of a SharedRing */
+export class RingProducer {
+  /** Append a record; returns false, counting it as dropped, when the ring is full. Throws if the ring file is corrupt */
+  push(data: Buffer): boolean
+  /** Append records in order until one doesn't fit; returns how many were written. Throws if the ring file is corrupt */
+  pushBatch(records: Array<Buffer>): number
+  /** Capacity, bytes in use, and records dropped so far */
+  stats(): RingStats
+}
+/** Reading end of a SharedRing */
+export class RingConsumer {
+  /** The oldest unread record, or null when the ring is empty; never blocks. Throws if the ring file is corrupt */
+  pop(): Buffer | null
+  /** Up to `max` unread records, oldest first (default: all). Throws if the ring file is corrupt */
+  drain(max?: number | undefined | null): Array<Buffer>
+  /** Capacity, bytes in use, and records dropped so far */
+  stats(): RingStats
+}
+/** Where and how to upload; unset fields fall back to the usual environment variables */
+export interface S3Options {
+  /** Service URL, e.g. "http://localhost:9000" for MinIO (default: $AWS_ENDPOINT_URL_S3, $AWS_ENDPOINT_URL, or $S3_ENDPOINT, else AWS in `region`) */
+  endpoint?: string
+  /** (default: $S3_BUCKET) */
+  bucket?: string
+  /** (default: $AWS_REGION, $AWS_DEFAULT_REGION, or "us-east-1") */
+  region?: string
+  /** (default: $AWS_ACCESS_KEY_ID) */
+  accessKeyId?: string
+  /** (default: $AWS_SECRET_ACCESS_KEY) */
+  secretAccessKey?: string
+  /** Token for temporary credentials (default: $AWS_SESSION_TOKEN) */
+  sessionToken?: string
+  /** Address the bucket in the path, endpoint/bucket/key, rather than as a subdomain (default: true with a custom endpoint, false for AWS) */
+  pathStyle?: boolean
+  /** Bytes per part of a multipart upload, at least 5 MiB; files up to this size go in one request (default: 8 MiB) */
+  partSize?: number
+  /** Content-Type stored with the object (default: "application/octet-stream") */
+  contentType?: string
+  /** Tries per request, the first included; network errors, 429, and 5xx are retried (default: 3) */
+  maxAttempts?: number
+  /** Per-request timeout (default: 60000) */
+  timeoutMs?: number
+}
+/** Result of upload_to_s3 */
+export interface S3UploadResult {
+  bucket: string
+  key: string
+  /** URL of the object */
+  url: string
+  /** The object's ETag, without quotes */
+  etag: string
+  bytes: number
+  /** Parts uploaded; 1 for a single request */
+  parts: number
+}
+/**
+ * Upload a file to S3-compatible object storage
+ *
+ * Requests are signed with AWS Signature Version 4, so the same call
+ * works against AWS S3, MinIO, Cloudflare R2, and other services speaking
+ * the S3 API. Files larger than part_size go up as a multipart upload,
+ * read a part at a time, and an upload that fails partway is aborted so
+ * its parts don't linger. Runs off the main thread.
+ *
+ * @param path - File to upload, e.g. a session bundle or a Parquet export
+ * @param key - Object key, e.g. "sessions/2024-05-01/abc.parquet"
+ * @param options - Endpoint, bucket, and credentials, each defaulting to the environment
+ * @returns The object's bucket, key, URL, ETag, size, and part count
+*/
+export declare function uploadToS3(path: string, key: string, options?: S3Options | undefined | null): Promise<S3UploadResult>
+/** How one event type is thinned out */
+export interface SamplingRule {
+  /** Event type the rule applies to, or "*" for every type without its own rule */
+  type: string
+  /** Fraction of events to keep, 0 to 1 (default: 1) */
+  rate?: number
+  /** Keep one event in this many; shorthand for rate = 1 / keep_one_in */
+  keepOneIn?: number
+  /** Most events kept per second, per value of `per` */
+  maxPerSecond?: number
+  /** Events allowed at once before max_per_second applies (default: max_per_second, at least 1) */
+  burst?: number
+  /** Field the rate limit is counted by, e.g. "file_path"; unset counts the whole type together */
+  per?: string
+  /** Fields whose values make two events duplicates, e.g. ["file_path", "diff"] */
+  dedupeFields?: Array<string>
+  /** How long after an event its duplicates are dropped; each repeat extends it (default: 10000) */
+  dedupeWindowMs?: number
+}
+/** Options for a Sampler */
+export interface SamplerOptions {
+  /** Changes which events a rate keeps; the same seed keeps the same events (default: "") */
+  seed?: string
+}
+/** Result of Sampler.filter */
+export interface SampleResult {
+  /** Events that passed, in input order */
+  kept: Array<Record<string, any>>
+  /** Events dropped by a rule's rate */
+  sampledOut: number
+  /** Events dropped by a rule's max_per_second */
+  rateLimited: number
+  /** Events dropped as repeats within a rule's dedupe window */
+  duplicates: number
+}
+/** Running counts for one event type */
+export interface SamplerTypeStats {
+  type: string
+  seen: number
+  kept: number
+  sampledOut: number
+  rateLimited: number
+  duplicates: number
+}
+/** Per-type sampling and rate limiting */
+export class Sampler {
+  /**
+   * Build a sampler from per-type rules
+   *
+   * Types without a rule, and without a "*" rule, are always kept.
+   * Each rule drops duplicates first, then samples, then rate limits.
+   * Sampling hashes the seed, type, and event id, so a given event is
+   * kept or dropped the same way on every run. Rate limits use token
+   * buckets driven by event timestamps, so replaying a session limits it
+   * the same way too.
+  */
+  constructor(rules: Array<SamplingRule>, options?: SamplerOptions | undefined | null)
+  /** Whether to keep one event; counts toward rate limits and stats */
+  keep(event: Record<string, any>): boolean
+  /** The events to keep from a batch, in order */
+  filter(events: Array<Record<string, any>>): SampleResult
+  /** Counts per event type since the sampler was built or reset, by type */
+  stats(): Array<SamplerTypeStats>
+  /** Clear rate-limit buckets, remembered duplicates, and counts */
+  reset(): void
+}
+/** Fields every event carries */
+export interface Event {
+  id: string
+  sessionId?: string
+  /** Milliseconds since the Unix epoch (JS `Date.now()`) */
+  timestamp: number
+  /** Event kind, e.g. "code_change", "prompt", "terminal_command" */
+  type: string
+  workspacePath?: string
+  /** Free-form payload, as an object or its JSON string */
+  details?: Record<string, any> | string
+}
+/** A "code_change" or "file_change" event */
+export interface EditEvent {
+  id: string
+  sessionId?: string
+  timestamp: number
+  /** "code_change" or "file_change" */
+  type: string
+  workspacePath?: string
+  filePath: string
+  linesAdded?: number
+  linesRemoved?: number
+  beforeContent?: string
+  afterContent?: string
+  aiGenerated?: boolean
+  /** User verdict on an AI edit: "good", "bad", or "reverted" */
+  label?: string
+  details?: Record<string, any> | string
+}
+/** A "prompt", "response", or "chat" event */
+export interface ChatEvent {
+  id: string
+  sessionId?: string
+  timestamp: number
+  /** "prompt", "response", or "chat" */
+  type: string
+  workspacePath?: string
+  text: string
+  /** "user", "assistant", or "system" */
+  role?: string
+  model?: string
+  conversationId?: string
+  /** Time from request to model response */
+  latencyMs?: number
+  details?: Record<string, any> | string
+}
+/** Options for validate_event */
+export interface SchemaOptions {
+  /** Largest serialized event, in UTF-8 bytes (default: 1 MiB) */
+  maxEventBytes?: number
+  /** Largest single string value anywhere in the event, in UTF-8 bytes (default: 256 KiB) */
+  maxFieldBytes?: number
+  /** How far past the current time a timestamp may be (default: 300000, five minutes) */
+  maxClockSkewMs?: number
+}
+/** One way an event breaks the schema */
+export interface SchemaError {
+  /** snake_case field name, or a dotted path for nested values; empty for the whole event */
+  field: string
+  /**
+   * "InvalidEvent", "EventTooLarge", "MissingField", "InvalidField", "InvalidTimestamp",
+   * "TimestampOutOfRange", "InvalidCount", "InvalidLabel", "InvalidRole", or "FieldTooLarge"
+  */
+  code: string
+  message: string
+}
+/** Result of validate_event */
+export interface EventValidation {
+  valid: boolean
+  /** Schema the event was checked against: "edit", "chat", or "event" */
+  kind: string
+  /** Serialized size in UTF-8 bytes */
+  bytes: number
+  /** Epoch milliseconds, when the timestamp parsed */
+  timestamp?: number
+  /** Every problem found, in field order */
+  errors: Array<SchemaError>
+}
+/**
+ * Check a raw event against the Event, EditEvent, or ChatEvent schema
+ *
+ * The schema is picked from `type`: "code_change" and "file_change" are
+ * edits, "prompt", "response", and "chat" are chat events, and anything
+ * else is checked as a plain Event. Fields may be snake_case or camelCase,
+ * and edit and chat fields may sit inside `details`, as ingest_batch
+ * accepts them.
+ *
+ * Every event needs an `id`, a `type`, and a `timestamp` (epoch ms or
+ * ISO-8601) no earlier than 2000 and no later than now plus the allowed
+ * clock skew; a timestamp in seconds is caught by the first bound. Edits
+ * need a `file_path` and chat events a `text`. All problems are reported,
+ * not just the first.
+ *
+ * @param event - The record as the JS side would write it
+ * @param options - Size limits and clock skew
+ * @returns Whether it is valid, which schema applied, and each error
+*/
+export declare function validateEvent(event: any, options?: SchemaOptions | undefined | null): EventValidation
+/** A single regex match, from find_patterns and PatternSet.find */
+export interface PatternMatch {
+  pattern: string
+  /** Byte offsets into the UTF-8 content */
+  start: number
+  end: number
+  /** 1-based line of the match start */
+  line: number
+  /** 1-based column of the match start, in characters */
+  column: number
+  text: string
+}
+/** Result of find_patterns */
+export interface PatternMatches {
+  /** Ordered by position */
+  matches: Array<PatternMatch>
+  /** More matches exist past the cap */
+  truncated: boolean
+}
+/** Result of redact_patterns */
+export interface RedactedText {
+  text: string
+  /** Matches replaced per pattern */
+  counts: Record<string, number>
+  /** Total replacements */
+  redactions: number
+}
+/** Options for search_literals */
+export interface LiteralSearchOptions {
+  /** Match ASCII letters regardless of case (default: false) */
+  caseInsensitive?: boolean
+  /** Only count matches not touching a letter, digit, or underscore on either side (default: false) */
+  wholeWord?: boolean
+}
+/**
+ * Count many plain-text keywords in one pass
+ *
+ * Builds an Aho-Corasick automaton from all literals, so the content is
+ * scanned once however many there are, where search_patterns runs one
+ * regex per pattern. Each literal is counted independently: overlapping
+ * keywords (`api`, `api_key`) both count, and repeats of one literal don't
+ * overlap, the same counts search_patterns gives for escaped patterns.
+ *
+ * @param content - Text to search
+ * @param literals - Keywords matched verbatim; empty strings are left out
+ * @param options - ASCII case-insensitivity and whole-word matching
+ * @returns Matches per literal
+*/
+export declare function searchLiterals(content: string, literals: Array<string>, options?: LiteralSearchOptions | undefined | null): Record<string, number>
+/** Regexes compiled once and reused across searches */
+export class PatternSet {
+  /**
+   * Compile `patterns` (regex syntax) for repeated searches
+   *
+   * Fails on the first invalid pattern, naming it, as search_patterns
+   * does. Repeated patterns are kept once.
+  */
+  constructor(patterns: Array<string>)
+  /** Compiled patterns, in the order given */
+  get patterns(): Array<string>
+  /** Whether any pattern matches */
+  isMatch(content: string): boolean
+  /** Patterns that match at least once, in the order given */
+  match(content: string): Array<string>
+  /**
+   * Matches per pattern, the same counts search_patterns returns
+   *
+   * One set scan finds which patterns occur; only those are run again
+   * to count, so the common no-match case costs a single pass.
+  */
+  count(content: string): Record<string, number>
+  /**
+   * Every match of every pattern, ordered by position, with line and column
+   *
+   * @param content - Text to search
+   * @param limit - Stop after this many matches in total
+  */
+  find(content: string, limit?: number | undefined | null): Array<PatternMatch>
+}
+/**
+ * Find pattern matches with their locations
+ *
+ * The positional counterpart of search_patterns, for linking findings to
+ * a file location. Invalid patterns are left out; use PatternSet to have
+ * them reported.
+ *
+ * @param content - Text to search
+ * @param patterns - Regex patterns
+ * @param max_matches - Cap on matches returned in total (default: unlimited)
+ * @returns Matches ordered by position, and whether the cap cut any off
+*/
+export declare function findPatterns(content: string, patterns: Array<string>, maxMatches?: number | undefined | null): PatternMatches
+/**
+ * Replace pattern matches, e.g. to scrub secrets before content is stored
+ *
+ * Every pattern is matched against the original content and the text is
+ * rebuilt in one pass, so one pattern's replacement is never rescanned by
+ * another. Where matches overlap, the one starting first wins, ties going
+ * to the earlier pattern. Empty matches are left alone.
+ *
+ * @param content - Text to redact
+ * @param patterns - Regex patterns; an invalid pattern fails the call
+ * @param replacement - One replacement for all patterns, or one per pattern;
+ *   `$1` and `${name}` expand to capture groups, `$$` is a literal `$`.
+ *   Omitted, each match is masked with one `*` per character, keeping line breaks
+ * @returns The redacted text and replacement counts
+*/
+export declare function redactPatterns(content: string, patterns: Array<string>, replacement?: string | Array<string> | undefined | null): RedactedText
+/** Options for IngestServer.start */
+export interface IngestServerOptions {
+  /** Shared secret clients send as `Authorization: Bearer <token>` */
+  token: string
+  /** Address to bind (default: "127.0.0.1") */
+  host?: string
+  /** Largest /ingest body accepted (default: 8 MiB) */
+  maxBodyBytes?: number
+  /** Most events one /query returns, whatever its limit (default: 10000) */
+  maxQueryRows?: number
+  /** Connections served at once; more are answered 503 (default: 32) */
+  maxConnections?: number
+  /** Time a client has to send its whole request before it is disconnected (default: 10000) */
+  requestTimeoutMs?: number
+}
+/** Counters for an IngestServer */
+export interface IngestServerStats {
+  /** Requests answered, including refused ones */
+  requests: number
+  /** Events written by /ingest */
+  ingested: number
+  /** Requests refused for a missing or wrong token */
+  unauthorized: number
+  /** Requests answered with another 4xx or 5xx status */
+  failed: number
+}
+/** An HTTP server storing events from several editor instances in one store */
+export class IngestServer {
+  /**
+   * Open the event store at `store_path` and serve it on `host:port`
+   *
+   * `POST /ingest` takes a JSON array of events (or `{ events: [...] }`)
+   * and writes it with EventStore.insert_batch, answering
+   * `{ inserted, replaced }`. `GET /query` takes EventQuery fields as
+   * query parameters and answers the matching events. Every request needs
+   * `Authorization: Bearer <token>`. Each connection is read on its own
+   * thread, up to `max_connections`, and must send its whole request
+   * within `request_timeout_ms`; the threads share one connection to the
+   * store, so writes from all clients are serialized.
+  */
+  static start(storePath: string, port: number, options: IngestServerOptions): IngestServer
+  /** Request and event counts since start */
+  stats(): IngestServerStats
+  /** The port being listened on */
+  get port(): number
+  /** Stop listening and close the store; waits for requests in progress to finish */
+  stop(): void
+}
+/** A contiguous stretch of activity with no idle gap longer than the threshold */
+export interface SessionSegment {
+  start: number
+  end: number
+  durationMs: number
+  eventCount: number
+  fileCount: number
+}
+/** Per-file activity totals */
+export interface FileActivity {
+  filePath: string
+  edits: number
+  linesAdded: number
+  linesRemoved: number
+}
+/** Structured standup summary plus a ready-to-paste text rendering */
+export interface StandupSummary {
+  date: string
+  sessions: Array<SessionSegment>
+  activeMinutes: number
+  filesTouched: number
+  topFiles: Array<FileActivity>
+  resolvedErrors: Array<string>
+  text: string
+}
+/**
+ * Split events into sessions wherever the gap between consecutive events
+ * exceeds the idle threshold
+ *
+ * @param events - Activity events in any order
+ * @param idle_gap_minutes - Gap that ends a session (default: 30)
+ * @returns Sessions in chronological order
+*/
+export declare function segmentSessions(events: Array<ActivityEvent>, idleGapMinutes?: number | undefined | null): Array<SessionSegment>
+/**
+ * Generate a short "yesterday I…" standup summary for one day
+ *
+ * Combines session segmentation, the most-edited files, and errors that
+ * were resolved during the day into text that can be pasted into chat tools.
+ *
+ * @param events - Activity events (only those on `date` are used)
+ * @param date - Day to summarize as YYYY-MM-DD
+ * @param utc_offset_minutes - Local offset from UTC, e.g. `-new Date().getTimezoneOffset()` (default: 0)
+ * @returns StandupSummary with structured fields and rendered text
+*/
+export declare function generateStandup(events: Array<ActivityEvent>, date: string, utcOffsetMinutes?: number | undefined | null): StandupSummary
+/** Number of functions whose length falls in one range of lines */
+export interface LengthBucket {
+  /** e.g. "1-10" or "101+" */
+  label: string
+  min: number
+  /** Inclusive upper bound; absent for the open-ended last bucket */
+  max?: number
+  count: number
+}
+/** Nesting and function length statistics for one file */
+export interface CodeShapeStats {
+  language: string
+  /** Deepest block nesting of any code line (0 = top level) */
+  maxNestingDepth: number
+  /** Mean block nesting over code lines */
+  averageNestingDepth: number
+  functionCount: number
+  /** Function lengths in lines; all zero when no functions were found */
+  minFunctionLength: number
+  maxFunctionLength: number
+  meanFunctionLength: number
+  medianFunctionLength: number
+  p90FunctionLength: number
+  /** Function counts for 1-10, 11-25, 26-50, 51-100, and 101+ lines */
+  functionLengthHistogram: Array<LengthBucket>
+}
+/**
+ * Calculate nesting depth and function length statistics
+ *
+ * Nesting is measured at the start of each code line: open `{}` blocks for
+ * brace languages, indentation levels otherwise. Functions are located the
+ * same way as calculate_complexity (nested functions are counted too).
+ *
+ * @param content - Source code to measure
+ * @param language - Language name (detected from content if not provided)
+ * @returns Nesting and function length statistics
+*/
+export declare function calculateCodeShape(content: string, language?: string | undefined | null): CodeShapeStats
+/** Options for simhash */
+export interface SimHashOptions {
+  /** Consecutive tokens hashed together as one feature (default: 3) */
+  shingleSize?: number
+  /** Fold letters to lowercase before hashing (default: false) */
+  ignoreCase?: boolean
+}
+/** A candidate within range of the fingerprint, from find_near_duplicates */
+export interface NearDuplicate {
+  /** Position in the candidates array */
+  index: number
+  fingerprint: string
+  /** Differing bits, 0-64 */
+  distance: number
+}
+/**
+ * SimHash fingerprint of a text
+ *
+ * Each run of `shingle_size` tokens (words and punctuation, whitespace
+ * ignored) is hashed, and every bit of the fingerprint takes the majority
+ * vote of those hashes. Texts that share most of their shingles, such as
+ * two snapshots differing only in a timestamp comment, get fingerprints a
+ * few bits apart; unrelated texts differ in about half the bits. The hash
+ * is stable across platforms and releases, so fingerprints can be stored.
+ *
+ * @param content - Text to fingerprint
+ * @param options - Shingle size and case folding
+ * @returns 16 lowercase hex digits; all zeros for content without tokens
+*/
+export declare function simhash(content: string, options?: SimHashOptions | undefined | null): string
+/** Number of bits that differ between two simhash fingerprints */
+export declare function hammingDistance(a: string, b: string): number
+/**
+ * Stored fingerprints within `max_distance` bits of a new one
+ *
+ * A linear scan, but only an XOR and a popcount per candidate, so checking
+ * a snapshot against thousands of stored fingerprints before saving it is
+ * cheap. About 3 bits suits whole-file snapshots; short texts need more.
+ *
+ * @param fingerprint - Fingerprint from simhash
+ * @param candidates - Fingerprints to compare against
+ * @param max_distance - Most differing bits to report (default: 3)
+ * @returns Matches, closest first, ties in input order
+*/
+export declare function findNearDuplicates(fingerprint: string, candidates: Array<string>, maxDistance?: number | undefined | null): Array<NearDuplicate>
+/** Two texts at least as similar as the threshold */
+export interface SimilarPair {
+  /** Indices into the texts array, a < b */
+  a: number
+  b: number
+  similarity: number
+}
+/** Result of similarity_matrix */
+export interface SimilarityMatrix {
+  size: number
+  /** Row per text, symmetric with 1 on the diagonal; left out when a threshold is given */
+  matrix?: Array<Array<number>>
+  /** Pairs meeting the threshold, most similar first; empty without a threshold */
+  pairs: Array<SimilarPair>
+}
+/** Options for containment and find_sources */
+export interface ContainmentOptions {
+  /** Consecutive tokens per shingle; smaller catches shorter pastes but more coincidences (default: 3) */
+  shingleSize?: number
+  /** find_sources: lowest containment reported (default: 0.5) */
+  minContainment?: number
+  /** find_sources: most sources returned (default: all) */
+  limit?: number
+}
+/** A candidate source holding much of the pasted text */
+export interface ContainmentMatch {
+  /** Position in the candidates array */
+  index: number
+  /** Fraction of the text's shingles found in the candidate, 0-1 */
+  containment: number
+}
+/**
+ * Similarity of every pair of texts, computed in parallel
+ *
+ * Each pair is compared once. "ratio" is calculate_similarity's character
+ * diff ratio, exact but slow on long texts; "jaccard" compares sets of
+ * token 3-shingles and suits whole files and sessions; the edit metrics
+ * are for short strings, normalized to 1 - edits / longer length.
+ *
+ * @param texts - Texts to compare
+ * @param metric - "ratio" (default), "jaccard", "levenshtein", "damerau", or "jaro-winkler"
+ * @param threshold - Report only pairs at least this similar, as a sparse list
+ * @returns The full matrix, or the pairs meeting the threshold
+*/
+export declare function similarityMatrix(texts: Array<string>, metric?: string | undefined | null, threshold?: number | undefined | null): SimilarityMatrix
+/**
+ * Fraction of `a`'s token shingles that also appear in `b`
+ *
+ * One-sided, unlike Jaccard: a block pasted into a much larger file
+ * scores near 1 against that file, where a symmetric score is pulled
+ * toward 0 by everything else in it. Whitespace and formatting don't
+ * change tokens, so reindented pastes still match.
+ *
+ * @param a - The smaller text, e.g. a pasted block
+ * @param b - The text it may have come from
+ * @param options - Shingle size
+ * @returns 0-1; 0 when `a` has no tokens
+*/
+export declare function containment(a: string, b: string, options?: ContainmentOptions | undefined | null): number
+/**
+ * Candidates that contain most of `text`, for copy-paste provenance
+ *
+ * Scores every candidate (earlier files, chat responses, clipboard
+ * captures) by containment of `text` in parallel.
+ *
+ * @param text - The pasted block
+ * @param candidates - Possible sources
+ * @param options - Shingle size, containment floor, and result limit
+ * @returns Matches, highest containment first, ties in input order
+*/
+export declare function findSources(text: string, candidates: Array<string>, options?: ContainmentOptions | undefined | null): Array<ContainmentMatch>
+/** Options for bootstrap_ci */
+export interface BootstrapOptions {
+  /** "mean" (default) or "median" */
+  statistic?: string
+  /** Resamples (default: 1000) */
+  iterations?: number
+  /** Confidence level (default: 0.95) */
+  confidence?: number
+  /** Resampling seed, so intervals are reproducible (default: fixed) */
+  seed?: number
+}
+/** A statistic with its bootstrap confidence interval */
+export interface ConfidenceInterval {
+  statistic: string
+  estimate: number
+  lower: number
+  upper: number
+  confidence: number
+  samples: number
+}
+/** Result of t_test */
+export interface TTestResult {
+  meanA: number
+  meanB: number
+  /** mean(b) − mean(a) */
+  meanDifference: number
+  tStatistic: number
+  degreesOfFreedom: number
+  /** Two-sided p-value */
+  pValue: number
+}
+/** Result of mann_whitney */
+export interface MannWhitneyResult {
+  /** U of b over a: pairs where b > a, ties counting half */
+  uStatistic: number
+  /** Normal approximation with tie and continuity corrections */
+  zScore: number
+  /** Two-sided p-value */
+  pValue: number
+  /** 2U / (n_a n_b) − 1, from −1 (a always larger) to 1 (b always larger) */
+  rankBiserial: number
+}
+/** Result of effect_size */
+export interface EffectSizeResult {
+  /** Standardized mean difference of b over a (pooled standard deviation) */
+  cohensD: number
+  /** Cohen's d with the small-sample bias correction */
+  hedgesG: number
+  /** P(b > a) − P(b < a) */
+  cliffsDelta: number
+  /** Cohen's conventional label for |d|: "negligible", "small", "medium", or "large" */
+  magnitude: string
+}
+/**
+ * Bootstrap confidence interval for the mean or median of a metric
+ *
+ * @param values - Metric samples
+ * @param options - Statistic, resamples, confidence level, and seed
+ * @returns Point estimate with percentile interval
+*/
+export declare function bootstrapCi(values: Array<number>, options?: BootstrapOptions | undefined | null): ConfidenceInterval
+/**
+ * Welch's unequal-variance t-test of mean(b) − mean(a)
+ *
+ * @param a - Baseline samples (at least two)
+ * @param b - Comparison samples (at least two)
+ * @returns t statistic, Welch–Satterthwaite degrees of freedom, and two-sided p-value
+*/
+export declare function tTest(a: Array<number>, b: Array<number>): TTestResult
+/**
+ * Mann–Whitney U test (Wilcoxon rank-sum) of b against a
+ *
+ * Distribution-free alternative to t_test for skewed metrics like latency
+ * or churn. Uses the normal approximation, so p-values are approximate for
+ * very small samples.
+ *
+ * @param a - Baseline samples
+ * @param b - Comparison samples
+ * @returns U statistic, z score, two-sided p-value, and rank-biserial correlation
+*/
+export declare function mannWhitney(a: Array<number>, b: Array<number>): MannWhitneyResult
+/**
+ * Effect sizes of b relative to a
+ *
+ * @param a - Baseline samples (at least two)
+ * @param b - Comparison samples (at least two)
+ * @returns Cohen's d, Hedges' g, Cliff's delta, and a magnitude label
+*/
+export declare function effectSize(a: Array<number>, b: Array<number>): EffectSizeResult
+/** Options for an EventStore */
+export interface EventStoreOptions {
+  /** Open an existing store without write access (default: false) */
+  readOnly?: boolean
+  /** How long a write waits for another connection's lock (default: 5000) */
+  busyTimeoutMs?: number
+}
+/** Filter for query, count, and delete_where; unset fields match everything */
+export interface EventQuery {
+  /** Earliest timestamp, inclusive, in epoch milliseconds */
+  start?: number
+  /** Latest timestamp, exclusive, in epoch milliseconds */
+  end?: number
+  sessionId?: string
+  workspacePath?: string
+  filePath?: string
+  type?: string
+  /** Most events returned by query (default: all) */
+  limit?: number
+  offset?: number
+  /** Newest first instead of oldest first (default: false) */
+  descending?: boolean
+}
+/** Result of insert_batch */
+export interface StoreWrite {
+  /** Events whose id was new */
+  inserted: number
+  /** Events that overwrote a stored event with the same id */
+  replaced: number
+}
+/** An index on the events table */
+export interface StoreIndex {
+  name: string
+  columns: Array<string>
+}
+/** SQLite-backed store of events keyed by id */
+export class EventStore {
+  /**
+   * Open the store at `path`, creating it if needed
+   *
+   * A new store gets the events table and indexes on timestamp,
+   * (session_id, timestamp), and (file_path, timestamp). The database is
+   * put in WAL mode so readers don't block the writer. ":memory:" opens a
+   * private in-memory store.
+  */
+  constructor(path: string, options?: EventStoreOptions | undefined | null)
+  /**
+   * Write `events` in a single transaction
+   *
+   * Each event needs an `id` (string or number), a `type`, and a
+   * `timestamp` (epoch ms or ISO-8601); `session_id`, `workspace_path`,
+   * and `file_path` are indexed when present, at the top level or inside
+   * `details`, in snake_case or camelCase. The whole event is stored as
+   * JSON. An event whose id is already stored replaces it. If any event
+   * is invalid nothing is written.
+  */
+  insertBatch(events: Array<Record<string, any>>): StoreWrite
+  /**
+   * Stored events matching `filter`, oldest first
+   *
+   * Time bounds are half-open, `[start, end)`. Session and file filters
+   * use their (column, timestamp) indexes, so a range within one session
+   * or file reads only the matching rows.
+  */
+  query(filter?: EventQuery | undefined | null): Array<Record<string, any>>
+  /** Number of stored events matching `filter`; `limit` and `offset` are ignored */
+  count(filter?: EventQuery | undefined | null): number
+  /** The stored event with this id */
+  get(id: string): Record<string, any> | null
+  /** Delete events by id; returns how many were stored */
+  delete(ids: Array<string>): number
+  /** Delete every event matching `filter`; `limit` and `offset` are ignored */
+  deleteWhere(filter: EventQuery): number
+  /**
+   * Add an index on `columns`, in order; returns its name
+   *
+   * Columns are any of session_id, workspace_path, file_path, type, and
+   * timestamp. Creating an index that exists is a no-op.
+  */
+  createIndex(columns: Array<string>): string
+  /** Drop an index by name; returns whether it existed */
+  dropIndex(name: string): boolean
+  /** Indexes on the events table, by name; the primary key isn't listed */
+  indexes(): Array<StoreIndex>
+  /**
+   * Apply a retention policy to the oldest events past its full-detail cutoff
+   *
+   * Up to batch_size events are handled per call, in one transaction:
+   * those within aggregate_ms are folded into per-bucket rollups in the
+   * event_aggregates table, older ones are deleted, and so are rollups
+   * whose newest event is past aggregate_ms. Call repeatedly, e.g. when
+   * idle, until `done`; `remaining` counts the expired events left.
+  */
+  applyRetention(policy: RetentionPolicy): RetentionProgress
+  /**
+   * Rollups written by apply_retention, oldest bucket first
+   *
+   * `start` and `end` bound the bucket start; `type`, `workspace_path`,
+   * `file_path`, `limit`, and `offset` apply as in query, and
+   * `session_id` is ignored since rollups span sessions.
+  */
+  aggregates(filter?: EventQuery | undefined | null): Array<EventAggregate>
+  /**
+   * Write the events matching `filter` to a Parquet file at `path`
+   *
+   * Columns are id, timestamp (UTC milliseconds), type, session_id,
+   * workspace_path, file_path, lines_added, lines_removed, and the whole
+   * event as JSON in `event`. Rows are read from the store in batches, so
+   * memory stays flat however many match. The file is written next to
+   * `path` and renamed into place once complete.
+  */
+  exportParquet(path: string, filter?: EventQuery | undefined | null, options?: ParquetOptions | undefined | null): ParquetExport
+  /**
+   * The events matching `filter` as an Arrow IPC buffer
+   *
+   * Same columns as export_parquet. Arrow JS reads the buffer with
+   * tableFromIPC and pyarrow with ipc.open_stream (or ipc.open_file for
+   * the "file" format), without converting each event to an object.
+  */
+  exportArrow(filter?: EventQuery | undefined | null, options?: ArrowOptions | undefined | null): Buffer
+  /**
+   * Write the events matching `filter` to a CSV file at `path`
+   *
+   * Rows are read from the store and written a chunk at a time, so memory
+   * stays flat however many match. Fields are quoted per RFC 4180 and rows
+   * end in CRLF. The file is written next to `path` and renamed into place
+   * once complete.
+  */
+  exportCsv(path: string, filter?: EventQuery | undefined | null, options?: CsvOptions | undefined | null): CsvExport
+  /** Refresh the query planner's statistics, after large inserts or deletes */
+  optimize(): void
+  /** Number of stored events */
+  get size(): number
+  /** Where the store was opened */
+  get path(): string
+  /** Close the database; later calls throw */
+  close(): void
+}
+/** A definition located in source */
+export interface Symbol {
+  name: string
+  /** "function", "method", "class", "struct", "enum", "interface", "trait", or "type" */
+  kind: string
+  /** Declaration up to the body, whitespace collapsed */
+  signature: string
+  /** 1-based, including decorators and export keywords */
+  startLine: number
+  endLine: number
+  /** Doc comment above the definition, or the Python docstring, without markers */
+  docstring?: string
+  /** Enclosing class, impl, trait, or function */
+  parent?: string
+}
+/**
+ * Extract function and method definitions as structured symbols
+ *
+ * With the `tree-sitter` feature (default), javascript, typescript, python,
+ * rust, and go are parsed properly: arrow functions assigned to variables
+ * and class fields, decorated Python defs, and methods inside impl and
+ * trait blocks are all found, and methods report their class or type as
+ * `parent`. Other languages, and builds without the feature, fall back to
+ * the heuristic function finder used by calculate_complexity.
+ *
+ * @param content - Source code
+ * @param language - Language name as returned by detect_language
+ * @returns Symbols ordered by start line
+*/
+export declare function extractSymbols(content: string, language: string): Array<symbol>
+/**
+ * Extract type definitions as structured symbols
+ *
+ * Returns classes, structs, enums, interfaces, traits, and type aliases with
+ * their line ranges, signatures, doc comments, and enclosing definition,
+ * for outline views. Uses the same backends as extract_symbols; the
+ * heuristic fallback also covers java, c, cpp, and csharp.
+ *
+ * @param content - Source code
+ * @param language - Language name as returned by detect_language
+ * @returns Type symbols ordered by start line
+*/
+export declare function extractTypes(content: string, language: string): Array<symbol>
+/** One test or test group */
+export interface TestCase {
+  /** Test description or function name */
+  name: string
+  /** "test" for a single test, "suite" for a describe block, test class, or test module */
+  kind: string
+  /** 1-based line of the definition */
+  line: number
+  /** Marked skipped: .skip/.todo, xit, or a skip, ignore, or disabled decorator or attribute */
+  skipped: boolean
+}
+/** Result of detect_tests */
+export interface TestDetection {
+  /** The file is a test file by its name or location */
+  isTestFile: boolean
+  /** Naming convention or directory that marked it, e.g. "*.test.*", "tests/" */
+  reason?: string
+  /** "jest", "vitest", "mocha", "node:test", "pytest", "unittest", "rust", "go", "junit", "xunit", "nunit", "mstest", "rspec", or "minitest" */
+  framework?: string
+  /** Tests and suites in source order */
+  tests: Array<TestCase>
+}
+/**
+ * Detect test files and the tests they define
+ *
+ * Flags test files by naming convention and location (`*.test.js`,
+ * `test_*.py`, `*_test.go`, `FooTest.java`, `__tests__/`, `tests/`, ...)
+ * and lists their tests: jest/vitest/mocha `describe`/`it`/`test` blocks,
+ * pytest and unittest `test*` functions and `Test*` classes, Rust `#[test]`
+ * functions and `#[cfg(test)]` modules, Go `TestXxx` functions and `t.Run`
+ * subtests, JUnit/xUnit annotated methods, and RSpec examples. Tests are
+ * listed for any file, so Rust's inline test modules count even though the
+ * file itself is not a test file.
+ *
+ * @param content - Source code
+ * @param language - Language name as returned by detect_language
+ * @param filename - File path, used for naming conventions
+ * @returns Whether the file is a test file, its framework, and its tests
+*/
+export declare function detectTests(content: string, language: string, filename: string): TestDetection
+/** Options for a TfidfModel */
+export interface TfidfOptions {
+  /** Weight terms by 1 + ln(count) rather than the raw count (default: true) */
+  sublinearTf?: boolean
+  /** Drop terms in fewer documents than this (default: 1) */
+  minDf?: number
+  /** Drop terms in more than this fraction of documents (default: 1.0, keep all) */
+  maxDf?: number
+  /** Keep only the most frequent terms across the corpus (default: all) */
+  maxFeatures?: number
+}
+/** One weighted term of a vector */
+export interface TfidfTerm {
+  term: string
+  weight: number
+}
+/** A fitted document close to the query */
+export interface TfidfNeighbor {
+  /** Position in the documents the model was fitted on */
+  index: number
+  /** Cosine similarity, 0-1 */
+  similarity: number
+}
+/** TF-IDF model fitted on a corpus, with the corpus vectors kept for neighbor queries */
+export class TfidfModel {
+  /**
+   * Fit term weights on `documents`
+   *
+   * Text is split into words as in extract_keywords: identifiers are
+   * broken at camelCase and snake_case boundaries, lowercased, and
+   * stopwords and language keywords are dropped. IDF is smoothed,
+   * ln((1 + n) / (1 + df)) + 1, so terms in every document still count a
+   * little.
+  */
+  constructor(documents: Array<string>, options?: TfidfOptions | undefined | null)
+  /** Weighted terms of `text`, heaviest first; words outside the vocabulary are left out */
+  vectorize(text: string): Array<TfidfTerm>
+  /** Cosine similarity of two texts' vectors, 0 when either has no known terms */
+  similarity(a: string, b: string): number
+  /**
+   * Fitted documents most similar to `text`
+   *
+   * @param text - Query text
+   * @param limit - Most neighbors to return (default: 10)
+   * @param min_similarity - Lowest cosine similarity returned (default: any above 0)
+   * @returns Neighbors, most similar first, ties by index
+  */
+  nearest(text: string, limit?: number | undefined | null, minSimilarity?: number | undefined | null): Array<TfidfNeighbor>
+  /** Number of documents the model was fitted on */
+  get documents(): number
+  /** Number of terms kept */
+  get vocabularySize(): number
+}
+/** Result of load_token_encoding */
+export interface TokenEncodingInfo {
+  name: string
+  /** Number of tokens in the rank file */
+  vocabularySize: number
+}
+/** Result of count_tokens */
+export interface TokenCount {
+  tokens: number
+  /** Encoding counted with, or asked for when the count is an estimate */
+  encoding: string
+  /** False when the encoding isn't loaded and the heuristic was used */
+  exact: boolean
+}
+/**
+ * Load a tiktoken encoding from its rank file
+ *
+ * The file is the `.tiktoken` format tiktoken itself downloads (for example
+ * `cl100k_base.tiktoken`): one base64 token and its rank per line. Loading
+ * replaces any earlier encoding of the same name. Encodings can also be
+ * picked up lazily from `TIKTOKEN_ENCODINGS_DIR/<name>.tiktoken`.
+ *
+ * @param name - "o200k_base", "cl100k_base", "p50k_base", or "r50k_base"
+ * @param path - Path to the rank file
+ * @returns The encoding name and vocabulary size
+*/
+export declare function loadTokenEncoding(name: string, path: string): TokenEncodingInfo
+/**
+ * Count tokens in text
+ *
+ * Exact when the encoding is loaded (see loadTokenEncoding); otherwise the
+ * estimateTokens heuristic, flagged with `exact: false`.
+ *
+ * @param text - Text to count
+ * @param encoding - Encoding name (default "cl100k_base")
+ * @returns Token count, the encoding, and whether the count is exact
+*/
+export declare function countTokens(text: string, encoding?: string | undefined | null): TokenCount
+/**
+ * Encode text into token ids
+ *
+ * Matches tiktoken's `encode_ordinary`: special tokens such as
+ * `<|endoftext|>` are encoded as plain text.
+ *
+ * @param text - Text to encode
+ * @param encoding - Loaded encoding name (default "cl100k_base")
+ * @returns Token ids
+*/
+export declare function encodeTokens(text: string, encoding?: string | undefined | null): Array<number>
+/**
+ * Decode token ids back into text
+ *
+ * @param tokens - Token ids
+ * @param encoding - Loaded encoding name (default "cl100k_base")
+ * @returns Decoded text; incomplete UTF-8 sequences become U+FFFD
+*/
+export declare function decodeTokens(tokens: Array<number>, encoding?: string | undefined | null): string
+/** One chat message */
+export interface ChatMessage {
+  /** "system", "user", "assistant", "tool", ... */
+  role: string
+  content: string
+  /** Optional participant name, which costs tokens of its own */
+  name?: string
+}
+/** Price of a model, in dollars per million tokens */
+export interface ModelPrice {
+  /** Model name or prefix; the longest matching prefix wins */
+  model: string
+  promptPerMillion: number
+  completionPerMillion: number
+}
+/** Options for count_chat_tokens */
+export interface ChatTokenOptions {
+  /** Model name, for per-message overhead, encoding, and price (default "gpt-4o") */
+  model?: string
+  /** Encoding override; defaults to the model's */
+  encoding?: string
+  /** Prices checked before the built-in table */
+  prices?: Array<ModelPrice>
+  /** Roles counted as completion tokens (default ["assistant"]) */
+  completionRoles?: Array<string>
+}
+/** Tokens of one message, overhead included */
+export interface MessageTokens {
+  role: string
+  tokens: number
+  /** True when counted toward completion tokens */
+  completion: boolean
+}
+/** Result of count_chat_tokens */
+export interface ChatTokenUsage {
+  model: string
+  encoding: string
+  /** False when the encoding isn't loaded and the heuristic was used */
+  exact: boolean
+  /** Non-completion messages, their overhead, and the reply priming tokens */
+  promptTokens: number
+  completionTokens: number
+  totalTokens: number
+  messages: Array<MessageTokens>
+  /** Dollars; absent when the model has no price */
+  promptCost?: number
+  completionCost?: number
+  totalCost?: number
+  /** Price entry that matched */
+  priceModel?: string
+}
+/**
+ * Count the tokens of a chat transcript and estimate its cost
+ *
+ * Each message costs its content and name tokens plus the model's
+ * per-message framing (3 tokens, 4 for gpt-3.5-turbo-0301), and every
+ * request adds 3 tokens priming the reply, following OpenAI's chat format.
+ * Assistant messages count as completion tokens, everything else as
+ * prompt. Counts are exact when the model's encoding is loaded (see
+ * loadTokenEncoding) and heuristic otherwise. Models outside OpenAI's are
+ * counted with cl100k_base, so their counts are approximate.
+ *
+ * @param messages - Messages in order
+ * @param options - Model, encoding override, price table, completion roles
+ * @returns Prompt, completion, and total tokens, per-message counts, and costs in dollars
+*/
+export declare function countChatTokens(messages: Array<ChatMessage>, options?: ChatTokenOptions | undefined | null): ChatTokenUsage
+/** Result of batch_estimate_tokens */
+export interface BatchTokenCounts {
+  /** Tokens per text, in input order */
+  counts: Array<number>
+  total: number
+  encoding: string
+  /** False when the encoding isn't loaded and the heuristic was used */
+  exact: boolean
+}
+/**
+ * Count tokens in many texts in parallel
+ *
+ * Resolves the encoding once and counts the texts across threads, for
+ * context-size reports over a whole workspace.
+ *
+ * @param texts - Texts to count
+ * @param model - Model whose encoding to use (default "gpt-4o"), or an encoding name such as "cl100k_base"
+ * @returns Per-text counts in input order, their total, and whether they are exact
+*/
+export declare function batchEstimateTokens(texts: Array<string>, model?: string | undefined | null): BatchTokenCounts
+/** Options for a TrigramIndex */
+export interface TrigramIndexOptions {
+  /** Distinguish ASCII case (default: false) */
+  caseSensitive?: boolean
+}
+/** Size counters for a TrigramIndex */
+export interface TrigramIndexStats {
+  documents: number
+  /** Distinct trigrams across all documents */
+  trigrams: number
+  /** Document entries across all posting lists */
+  postings: number
+}
+/** Inverted index from byte trigrams to documents, updated one document at a time */
+export class TrigramIndex {
+  /** Create an empty index, case-insensitive unless `case_sensitive` is set */
+  constructor(options?: TrigramIndexOptions | undefined | null)
+  /** Index `text` under `id`, replacing any document already stored there */
+  addDocument(id: string, text: string): void
+  /** Drop a document; returns whether it was indexed */
+  remove(id: string): boolean
+  /**
+   * Ids of documents that may contain `text` as a substring
+   *
+   * A document is a candidate when it has every trigram of the query, so
+   * all true matches are returned and a few false positives may be;
+   * confirm with an exact search. Queries under three bytes have no
+   * trigrams and return every document.
+   *
+   * @param text - Literal substring to look for
+   * @param limit - Most candidates to return
+   * @returns Candidate ids
+  */
+  query(text: string, limit?: number | undefined | null): Array<string>
+  /** Whether a document is indexed under `id` */
+  has(id: string): boolean
+  /** Number of indexed documents */
+  get size(): number
+  /** Document, trigram, and posting counts */
+  stats(): TrigramIndexStats
+  /** Drop every document */
+  clear(): void
+}
+/** Options for upload_events and upload_queued */
+export interface UploadOptions {
+  /** URL batches are POSTed to */
+  endpoint: string
+  /** Extra request headers, e.g. an authorization token */
+  headers?: Record<string, string>
+  /** "json" (default), "ndjson", or "msgpack" (as encode_events); sets Content-Type, and how upload_events encodes a batch */
+  format?: string
+  /** "gzip" (default), "zstd", or "none"; sets Content-Encoding */
+  compression?: string
+  /** Tries per batch, the first included (default: 5) */
+  maxAttempts?: number
+  /** Upper bound on the first retry's delay; doubles each retry, and the delay is drawn uniformly below it (default: 500) */
+  initialBackoffMs?: number
+  /** Longest delay between tries, Retry-After included (default: 30000) */
+  maxBackoffMs?: number
+  /** Per-request timeout (default: 10000) */
+  timeoutMs?: number
+  /** Treat a success without a JSON object body as a failed try, e.g. a captive portal's HTML page (default: false) */
+  expectJson?: boolean
+}
+/** Outcome of upload_events */
+export interface UploadResult {
+  /** Status of the accepting response */
+  status: number
+  /** Tries made, the first included */
+  attempts: number
+  /** Batch size before compression */
+  rawBytes: number
+  /** Request body size as sent */
+  sentBytes: number
+  /** The response body, when it is JSON */
+  response?: any
+}
+/** Outcome of upload_queued; pass the ids on to the queue */
+export interface QueuedUploadResult {
+  /** Records the endpoint accepted, for DiskQueue.ack */
+  delivered: Array<number>
+  /** Records the endpoint refused outright (a 4xx other than 408 or 429); retrying won't help, so ack or set them aside */
+  rejected: Array<number>
+  /** Records not delivered for now, for DiskQueue.nack */
+  failed: Array<number>
+  /** Requests made across all records */
+  attempts: number
+  /** Why the first rejected or failed record wasn't delivered */
+  error?: string
+}
+/**
+ * Post a batch of events to an ingest endpoint, retrying transient failures
+ *
+ * The batch is encoded and compressed per `options` and sent from a thread
+ * of its own, so retries never tie up the libuv pool. Network errors,
+ * timeouts, 408, 429, and 5xx responses are retried up to max_attempts
+ * with exponential backoff and full jitter, honoring Retry-After as
+ * seconds or an HTTP date. Rejects once attempts run out, or at once on
+ * another 4xx, so the caller can keep the batch (e.g. in a DiskQueue).
+ *
+ * @param events - Events to send
+ * @param options - Endpoint, encoding, compression, and retry policy
+ * @returns The accepting response's status and body, and attempts made
+*/
+export declare function uploadEvents(events: Array<Record<string, any>>, options: UploadOptions): Promise<UploadResult>
+/**
+ * Upload records dequeued from a DiskQueue, one request per record
+ *
+ * Each record is sent as it was enqueued, compressed per `options`, so
+ * enqueue batches already encoded in the chosen format. Records go in
+ * order with the same retry policy as upload_events. Once one runs out of
+ * attempts the rest are left for later rather than tried against an
+ * endpoint that is likely unreachable. Hand the ids in the result to
+ * ack and nack.
+ *
+ * @param items - Records from DiskQueue.dequeue
+ * @param options - Endpoint, format, compression, and retry policy
+ * @returns Ids delivered, rejected, and still to send
+*/
+export declare function uploadQueued(items: Array<QueueItem>, options: UploadOptions): Promise<QueuedUploadResult>
+/** Point-in-time `git status` sample captured by the watcher */
+export interface GitStatusSnapshot {
+  timestamp: number
+  branch?: string
+  head?: string
+  stashCount?: number
+  /** Paths reported as modified/untracked at this moment */
+  dirtyFiles?: Array<string>
+}
+/** Detected VCS operations and the file events they account for */
+export interface VcsDetection {
+  /** Timeline events of type "branch_switch", "stash", or "stash_pop" */
+  events: Array<ActivityEvent>
+  /** Ids of file events caused by those operations rather than by editing */
+  explainedEventIds: Array<string>
+}
+/**
+ * Detect branch switches and stashes from git status deltas and watcher events
+ *
+ * Compares consecutive status snapshots (branch, stash count, dirty files),
+ * and falls back to branch changes between consecutive events when no
+ * snapshot covers them. File events landing inside an operation's window
+ * are returned as explained so callers can exclude them from edit metrics.
+ *
+ * @param events - Watcher/activity events (with `branch` when known)
+ * @param snapshots - Git status samples in any order
+ * @param window_ms - Grace period after an operation for its file events (default: 2000)
+*/
+export declare function detectVcsEvents(events: Array<ActivityEvent>, snapshots: Array<GitStatusSnapshot>, windowMs?: number | undefined | null): VcsDetection
+/** Options for read_cursor_chats */
+export interface CursorChatOptions {
+  /** Skip transcripts whose last activity is before this, in epoch milliseconds */
+  since?: number
+}
+/** Options for read_cursor_ai_events */
+export interface CursorAiEventOptions {
+  /** Skip events before this, in epoch milliseconds */
+  since?: number
+  /** Keep only events for files under this directory */
+  workspacePath?: string
+}
+/** One turn of a Cursor chat */
+export interface CursorChatMessage {
+  /** Bubble or generation id, when Cursor stored one */
+  id?: string
+  /** "user" or "assistant" */
+  role: string
+  text: string
+  /** Epoch milliseconds, when Cursor stored one */
+  timestamp?: number
+  model?: string
+}
+/** A conversation read from state.vscdb */
+export interface CursorChatTranscript {
+  /** Composer id, chat tab id, or "aiService" */
+  id: string
+  /** "composer" (cursorDiskKV composerData), "chat" (the chat panel), or "aiService" (prompt history) */
+  source: string
+  title?: string
+  createdAt?: number
+  /** Last activity, from the record or its newest message */
+  updatedAt?: number
+  /** Model configured for the conversation, else the last one that answered */
+  model?: string
+  messages: Array<CursorChatMessage>
+}
+/**
+ * Read the AI chat transcripts stored in a Cursor state.vscdb
+ *
+ * Collects composer conversations from cursorDiskKV (in the global
+ * storage database, following each bubble's own key in newer versions),
+ * chat panel tabs from a workspace database's ItemTable, and the
+ * aiService prompt history. The database is opened read-only off the main
+ * thread, so Cursor can keep writing to it. Messages without text, such
+ * as tool-call bubbles, are skipped, as are transcripts left empty.
+ *
+ * @param path - A state.vscdb, e.g. under User/globalStorage or User/workspaceStorage/<id>
+ * @param options - Only transcripts active since a time
+ * @returns Transcripts, least recently active first
+*/
+export declare function readCursorChats(path: string, options?: CursorChatOptions | undefined | null): Promise<Array<CursorChatTranscript>>
+/**
+ * Read the AI suggestions and applied edits recorded in a Cursor state.vscdb
+ *
+ * Normalizes Cursor's own records into events for insert_batch or
+ * ingest_batch, so the interactions behind a change are captured and not
+ * just the diff it left. Each code block a composer proposed becomes an
+ * `ai_suggestion` event, `accepted` true or false once the user kept or
+ * rejected it and unset while pending; `details.status` keeps Cursor's
+ * status. Each inline diff Cursor applied becomes a `code_change` event
+ * with its line counts. Records without a time are skipped. Ids are
+ * derived from Cursor's, so re-reading the same database replaces events
+ * rather than duplicating them.
+ *
+ * @param path - A state.vscdb, usually User/globalStorage/state.vscdb
+ * @param options - Only events since a time, or under a workspace
+ * @returns Events, oldest first
+*/
+export declare function readCursorAiEvents(path: string, options?: CursorAiEventOptions | undefined | null): Promise<Array<Record<string, any>>>
+/** Options for CursorDbWatcher.start */
+export interface CursorWatchOptions {
+  /** Time between polls (default: 1000) */
+  intervalMs?: number
+  /** Tables to watch, of ItemTable and cursorDiskKV (default: both) */
+  tables?: Array<string>
+  /** Report only keys starting with one of these, e.g. "composerData:" (default: every key) */
+  keyPrefixes?: Array<string>
+  /** Last rowid already seen per table, from a previous watcher's cursor(); tables not listed start at their current end */
+  cursor?: Record<string, number>
+  /** Most rows read per table per poll; the rest wait for the next (default: 1000) */
+  maxBatch?: number
+}
+/** A row written to a watched table */
+export interface CursorDbChange {
+  table: string
+  key: string
+  rowid: number
+  /** The value parsed as JSON, else as text, else null */
+  value: any
+}
+/** Counters for a CursorDbWatcher */
+export interface CursorWatchStats {
+  /** Polls that found the database changed and read it */
+  reads: number
+  /** Polls skipped because nothing was committed since the last read */
+  skipped: number
+  /** Rows passed to the callback */
+  changes: number
+  /** Polls that failed, e.g. while the file was being replaced */
+  errors: number
+  lastError?: string
+}
+/** Watches a state.vscdb and reports rows Cursor adds, without re-reading whole tables */
+export class CursorDbWatcher {
+  /**
+   * Poll the state.vscdb at `path` and call `callback` with rows added since the last poll
+   *
+   * Each table is read past the highest rowid seen so far, so a poll
+   * costs the new rows rather than the table. VS Code's storage writes a
+   * changed key as a replacement row, so updates to existing keys arrive
+   * too, with a new rowid. A poll is skipped outright when SQLite reports
+   * no commit since the last read. Reads happen on a background thread
+   * over a read-only connection; the callback gets one batch per poll
+   * that found matching rows.
+  */
+  static start(path: string, callback: (changes: Array<CursorDbChange>) => void, options?: CursorWatchOptions | undefined | null): CursorDbWatcher
+  /** Last rowid read per table; pass it as options.cursor to resume after a restart */
+  cursor(): Record<string, number>
+  /** Poll and row counts since start */
+  stats(): CursorWatchStats
+  /** Stop polling; waits for a poll in progress to finish */
+  stop(): void
+}
+/** Options for WebSocketServer.start */
+export interface WebSocketServerOptions {
+  /** Connections beyond this, handshakes included, are refused with a 503 (default: 64) */
+  maxClients?: number
+  /** Messages waiting per client before new ones are dropped for it (default: 256) */
+  maxQueue?: number
+  /**
+   * Time a client has for its whole handshake before the connection is
+   * dropped (default: 5000)
+   */
+  handshakeTimeoutMs?: number
+  /**
+   * Time a client has to take each message; one that doesn't has stopped
+   * reading and is disconnected (default: 5000)
+   */
+  writeTimeoutMs?: number
+}
+/** Counters for a WebSocketServer */
+export interface WebSocketServerStats {
+  /** Clients connected now */
+  clients: number
+  /** Messages queued to a client, counted once per client */
+  sent: number
+  /** Messages skipped for a client whose queue was full */
+  dropped: number
+}
+/** Broadcasts JSON messages to WebSocket clients, for live dashboards */
+export class WebSocketServer {
+  /**
+   * Listen on `host:port` (default host 127.0.0.1) and accept WebSocket clients
+   *
+   * Port 0 picks a free port; read it back from `port`. Clients connect
+   * to `ws://host:port/`, optionally with
+   * `?topics=diffs,sessions` to receive only messages broadcast under
+   * those topics. Each client has its own thread and queue, so one that
+   * reads slowly loses messages instead of delaying the others.
+  */
+  static start(port: number, host?: string | undefined | null, options?: WebSocketServerOptions | undefined | null): WebSocketServer
+  /**
+   * Queue a message, as JSON text, for every client that wants it
+   *
+   * Without a topic every client gets it; with one, clients that asked
+   * for topics get it only if it is among them. Never blocks: a client
+   * whose queue is full misses this message. Returns the clients it was
+   * queued for.
+  */
+  broadcast(message: any, topic?: string | undefined | null): number
+  /** Connected clients and message counts */
+  stats(): WebSocketServerStats
+  /** The port being listened on */
+  get port(): number
+  /** Close every client and stop listening */
+  stop(): void
+}
 /** Diff result structure */
 export interface DiffResult {
+  /** Difference in length, in Unicode code points */
   diffSize: number
   isSignificant: boolean
   summary: string
   linesAdded: number
   linesRemoved: number
+  /** Changed lines that are moves or renames (counted once per line pair) */
+  refactorLines: number
+  /** Net code points added / deleted */
   charsAdded: number
   charsDeleted: number
+  /** Net user-perceived characters (grapheme clusters) added / deleted */
+  graphemesAdded: number
+  graphemesDeleted: number
+  /** Net UTF-8 bytes added / deleted */
+  bytesAdded: number
+  bytesDeleted: number
   afterContent: string
   unifiedDiff?: string
+  /** An input was cut down to fit the call's budget */
+  truncated: boolean
+  truncations: Array<Truncation>
 }
 /** Line change information */
 export interface LineChange {
@@ -21,13 +3994,46 @@ export interface LineChange {
   changeType: string
   content: string
 }
+/** Error details for a single failed item in a batch call */
+export interface BatchItemError {
+  code: string
+  message: string
+}
+/** Per-pair outcome of a batch diff; exactly one of result/error is set */
+export interface DiffBatchItem {
+  ok: boolean
+  result?: DiffResult
+  error?: BatchItemError
+}
 /** File statistics */
 export interface FileStats {
   lines: number
+  /** Unicode code points */
   chars: number
+  /** User-perceived characters (extended grapheme clusters), e.g. one per emoji */
+  graphemes: number
+  /** UTF-8 encoded size */
+  bytes: number
   words: number
   blankLines: number
   commentLines: number
+  /** Non-blank lines of natural language: document text outside code blocks, and comments that read as sentences */
+  proseLines: number
+  /** Language whose comment syntax was applied */
+  language: string
+  /** Encoding the content was decoded from; "utf-8" for string input */
+  encoding: string
+  /** Line terminators by kind */
+  lfLines: number
+  crlfLines: number
+  crLines: number
+  /** Most common terminator: "lf", "crlf", "cr", or "none" for a single unterminated line */
+  lineEnding: string
+  /** More than one kind of line terminator is present */
+  mixedLineEndings: boolean
+  /** The content was cut down to fit the call's budget; counts cover the kept part */
+  truncated: boolean
+  truncations: Array<Truncation>
 }
 /**
  * Calculate diff between two text strings
@@ -35,13 +4041,25 @@ export interface FileStats {
  * This is 5-10x faster than the JavaScript 'diff' library
  * Uses the 'similar' crate which implements Myers' diff algorithm in Rust
  *
- * @param text1 - Original text
- * @param text2 - Modified text
+ * @param text1 - Original text, or raw bytes decoded like decode_to_utf8
+ * @param text2 - Modified text, or raw bytes decoded like decode_to_utf8
  * @param threshold - Minimum change size to be considered significant
  * @param include_unified - Whether to include unified diff format
+ * @param normalize_eol - Treat CRLF and CR line endings as LF, so an editor
+ *   rewriting line endings doesn't show up as every line changed
+ * @param budget - Line and byte limits applied to each side before diffing
  * @returns DiffResult with detailed change information
 */
-export declare function calculateDiff(text1: string, text2: string, threshold?: number | undefined | null, includeUnified?: boolean | undefined | null): DiffResult
+export declare function calculateDiff(text1: string | Buffer, text2: string | Buffer, threshold?: number | undefined | null, includeUnified?: boolean | undefined | null, normalizeEol?: boolean | undefined | null, budget?: InputBudget | undefined | null): DiffResult
+/**
+ * Calculate a diff off the main thread
+ * Same result as calculate_diff, returned as a Promise
+ *
+ * Aborting the optional signal (e.g. when the file changes again) skips
+ * work that has not started yet and rejects with an AbortError.
+ * normalize_eol comes last so existing (signal, budget) callers keep working.
+*/
+export declare function calculateDiffAsync(text1: string | Buffer, text2: string | Buffer, threshold?: number | undefined | null, includeUnified?: boolean | undefined | null, signal?: AbortSignal | undefined | null, budget?: InputBudget | undefined | null, normalizeEol?: boolean | undefined | null): Promise<DiffResult>
 /**
  * Get detailed line-by-line changes
  * Useful for showing exact changes in the UI
@@ -50,33 +4068,111 @@ export declare function getLineChanges(text1: string, text2: string): Array<Line
 /**
  * Calculate file statistics
  * Fast analysis of code files
+ *
+ * Comment lines use the comment syntax of `language` (as returned by
+ * detect_language); when omitted the language is detected from content.
+ * Block comment bodies and Python docstrings count as comment lines;
+ * prose lines are document text and comments that read as sentences.
+ * Raw bytes are decoded like decode_to_utf8 and the encoding is reported.
+ * With a budget, content over its limits is cut down first and the
+ * result is flagged as truncated.
 */
-export declare function calculateFileStats(content: string): FileStats
+export declare function calculateFileStats(content: string | Buffer, language?: string | undefined | null, budget?: InputBudget | undefined | null): FileStats
+/**
+ * Calculate file statistics off the main thread
+ * Same result as calculate_file_stats, returned as a Promise
+*/
+export declare function calculateFileStatsAsync(content: string | Buffer, language?: string | undefined | null, signal?: AbortSignal | undefined | null, budget?: InputBudget | undefined | null): Promise<FileStats>
 /**
  * Batch diff calculation for multiple files
  * Uses parallel processing with Rayon for maximum performance
  *
- * This can process hundreds of files simultaneously
+ * This can process hundreds of files simultaneously.
+ * Each pair gets its own DiffBatchItem, so a failing pair is reported
+ * in place instead of failing the whole batch.
+*/
+export declare function batchCalculateDiffs(pairs: Array<[string, string]>, threshold?: number | undefined | null, budget?: InputBudget | undefined | null): Array<DiffBatchItem>
+/**
+ * Batch diff calculation that runs off the main thread
+ * Same results as batch_calculate_diffs, returned as a Promise
+ *
+ * The optional callback is invoked with (completed, total) as each pair
+ * finishes, so long sessions can show progress and estimate time remaining.
+ * The synchronous variant blocks the event loop, so it cannot report progress.
+ *
+ * Aborting the optional signal stops scheduling the remaining pairs and
+ * rejects the Promise with an AbortError.
+*/
+export declare function batchCalculateDiffsAsync(pairs: Array<[string, string]>, threshold?: number | undefined | null, onProgress?: ((completed: number, total: number) => void) | undefined | null, signal?: AbortSignal | undefined | null, budget?: InputBudget | undefined | null): Promise<Array<DiffBatchItem>>
+/** Per-path outcome of a batch file stats call; exactly one of result/error is set */
+export interface FileStatsBatchItem {
+  path: string
+  ok: boolean
+  result?: FileStats
+  error?: BatchItemError
+}
+/**
+ * Read and analyze many files in parallel
+ *
+ * Each file is read from disk and measured like calculate_file_stats on
+ * the Rayon pool, so a workspace scan doesn't pay for a JS read plus a
+ * native call per file. The language comes from the file extension, then
+ * from content. Files are decoded like decode_to_utf8, so UTF-16 and
+ * Latin-1 files are measured as text; unreadable and binary files are
+ * reported in place.
+*/
+export declare function batchFileStats(paths: Array<string>, budget?: InputBudget | undefined | null): Array<FileStatsBatchItem>
+/**
+ * Batch file stats that run off the main thread
+ * Same results as batch_file_stats, returned as a Promise
+ *
+ * The optional callback is invoked with (completed, total) as each file
+ * finishes. Aborting the optional signal stops reading the remaining files
+ * and rejects the Promise with an AbortError.
 */
-export declare function batchCalculateDiffs(pairs: Array<[string, string]>, threshold?: number | undefined | null): Array<DiffResult>
+export declare function batchFileStatsAsync(paths: Array<string>, onProgress?: ((completed: number, total: number) => void) | undefined | null, signal?: AbortSignal | undefined | null, budget?: InputBudget | undefined | null): Promise<Array<FileStatsBatchItem>>
+/** Options for search_patterns */
+export interface SearchPatternOptions {
+  /** Match regardless of case, Unicode-aware (default: false) */
+  caseInsensitive?: boolean
+  /** Only count matches that start and end at word boundaries (default: false) */
+  wholeWord?: boolean
+  /** Treat every pattern as plain text rather than a regex (default: false) */
+  literal?: boolean
+  /** Let `^` and `$` match at line breaks, not just the ends of the content (default: false) */
+  multiline?: boolean
+}
+/** Result of search_patterns_with_budget */
+export interface PatternCounts {
+  /** Matches per pattern */
+  counts: Record<string, number>
+  /** The content was cut down to fit the budget; counts cover the kept part */
+  truncated: boolean
+  truncations: Array<Truncation>
+}
 /**
  * Fast text search with multiple patterns
  * Uses parallel regex matching for speed
+ *
+ * Patterns without regex metacharacters are counted together in a single
+ * Aho-Corasick pass (see search_literals). An invalid pattern fails the
+ * call, naming the pattern, so a count of 0 always means no matches.
 */
-export declare function searchPatterns(content: string, patterns: Array<string>): Record<string, number>
+export declare function searchPatterns(content: string, patterns: Array<string>, options?: SearchPatternOptions | undefined | null): Record<string, number>
 /**
- * Detect language from file content
- * Fast heuristic-based language detection
+ * search_patterns over at most a budget of the content
+ * Counts come back with the truncation flag, which a bare map can't carry
+ * Options come after the budget so existing callers keep working
 */
-export declare function detectLanguage(content: string, filename?: string | undefined | null): string
+export declare function searchPatternsWithBudget(content: string, patterns: Array<string>, budget?: InputBudget | undefined | null, options?: SearchPatternOptions | undefined | null): PatternCounts
 /**
  * Calculate similarity between two texts
  * Returns a ratio between 0.0 (completely different) and 1.0 (identical)
 */
 export declare function calculateSimilarity(text1: string, text2: string): number
 /**
- * Extract function signatures from code
- * Fast parsing for common languages
+ * Extract function names from code
+ * Names of the function and method symbols found by extract_symbols
 */
 export declare function extractFunctions(content: string, language: string): Array<string>
 /**
@@ -86,6 +4182,7 @@ export declare function extractFunctions(content: string, language: string): Arr
 export declare function deduplicateStrings(strings: Array<string>): Array<string>
 /**
  * Calculate token count estimate
- * Fast approximation without calling external APIs
+ * Exact when the encoding (default cl100k_base) is loaded, see tokens::count_tokens;
+ * otherwise a fast approximation without calling external APIs
 */
-export declare function estimateTokens(text: string): number
+export declare function estimateTokens(text: string, encoding?: string | undefined | null): number
@@ -310,14 +310,134 @@ if (!nativeBinding) {
   throw new Error(`Failed to load native binding`)
 }
 
-const { calculateDiff, getLineChanges, calculateFileStats, batchCalculateDiffs, searchPatterns, detectLanguage, calculateSimilarity, extractFunctions, deduplicateStrings, estimateTokens } = nativeBinding
+const { linkAgentSteps, aggregateEvents, summarizeBranches, AnnIndex, extractAnnotations, attributeAuthorship, computeBaseline, extractCallGraph, groupAtomicEdits, classifyLanguage, detectClones, clusterVectors, compareSessions, calculateComplexity, compareComplexity, compress, decompress, trainDictionary, ZstdCompressStream, ZstdDecompressStream, Deduper, levenshtein, damerauLevenshtein, jaroWinkler, batchEditDistance, EmbeddingModel, cosineSimilarity, detectEncoding, decodeToUtf8, DiffEngine, assignBucket, analyzeExperiment, SearchIndex, fuzzyMatch, detectGenerated, isGenerated, isMinified, grepDirectory, searchFile, searchFileAsync, connectGrpc, GrpcStream, calculateHalstead, extractIdentifiers, compareIdentifiers, extractImports, detectIndentation, detectIndentationDrift, ingestBatch, JsonlLog, extractKeywords, detectLanguage, tokenizeCode, analyzeDirectory, parseManifest, diffManifests, MinHashIndex, compareModels, encodeEvents, decodeEvents, toOtlpLogs, toOtlpMetrics, exportOtlp, acceptanceQuality, renderPrometheus, activityMetrics, PrometheusServer, classifyProse, linkPromptsToDiffs, DiskQueue, calculateChurn, replayAtSpeed, SharedRing, RingProducer, RingConsumer, uploadToS3, Sampler, validateEvent, searchLiterals, PatternSet, findPatterns, redactPatterns, IngestServer, segmentSessions, generateStandup, calculateCodeShape, simhash, hammingDistance, findNearDuplicates, similarityMatrix, containment, findSources, bootstrapCi, tTest, mannWhitney, effectSize, EventStore, extractSymbols, extractTypes, detectTests, TfidfModel, loadTokenEncoding, countTokens, encodeTokens, decodeTokens, countChatTokens, batchEstimateTokens, TrigramIndex, uploadEvents, uploadQueued, detectVcsEvents, readCursorChats, readCursorAiEvents, CursorDbWatcher, WebSocketServer, calculateDiff, calculateDiffAsync, getLineChanges, calculateFileStats, calculateFileStatsAsync, batchCalculateDiffs, batchCalculateDiffsAsync, batchFileStats, batchFileStatsAsync, searchPatterns, searchPatternsWithBudget, calculateSimilarity, extractFunctions, deduplicateStrings, estimateTokens } = nativeBinding
 
+module.exports.linkAgentSteps = linkAgentSteps
+module.exports.aggregateEvents = aggregateEvents
+module.exports.summarizeBranches = summarizeBranches
+module.exports.AnnIndex = AnnIndex
+module.exports.extractAnnotations = extractAnnotations
+module.exports.attributeAuthorship = attributeAuthorship
+module.exports.computeBaseline = computeBaseline
+module.exports.extractCallGraph = extractCallGraph
+module.exports.groupAtomicEdits = groupAtomicEdits
+module.exports.classifyLanguage = classifyLanguage
+module.exports.detectClones = detectClones
+module.exports.clusterVectors = clusterVectors
+module.exports.compareSessions = compareSessions
+module.exports.calculateComplexity = calculateComplexity
+module.exports.compareComplexity = compareComplexity
+module.exports.compress = compress
+module.exports.decompress = decompress
+module.exports.trainDictionary = trainDictionary
+module.exports.ZstdCompressStream = ZstdCompressStream
+module.exports.ZstdDecompressStream = ZstdDecompressStream
+module.exports.Deduper = Deduper
+module.exports.levenshtein = levenshtein
+module.exports.damerauLevenshtein = damerauLevenshtein
+module.exports.jaroWinkler = jaroWinkler
+module.exports.batchEditDistance = batchEditDistance
+module.exports.EmbeddingModel = EmbeddingModel
+module.exports.cosineSimilarity = cosineSimilarity
+module.exports.detectEncoding = detectEncoding
+module.exports.decodeToUtf8 = decodeToUtf8
+module.exports.DiffEngine = DiffEngine
+module.exports.assignBucket = assignBucket
+module.exports.analyzeExperiment = analyzeExperiment
+module.exports.SearchIndex = SearchIndex
+module.exports.fuzzyMatch = fuzzyMatch
+module.exports.detectGenerated = detectGenerated
+module.exports.isGenerated = isGenerated
+module.exports.isMinified = isMinified
+module.exports.grepDirectory = grepDirectory
+module.exports.searchFile = searchFile
+module.exports.searchFileAsync = searchFileAsync
+module.exports.connectGrpc = connectGrpc
+module.exports.GrpcStream = GrpcStream
+module.exports.calculateHalstead = calculateHalstead
+module.exports.extractIdentifiers = extractIdentifiers
+module.exports.compareIdentifiers = compareIdentifiers
+module.exports.extractImports = extractImports
+module.exports.detectIndentation = detectIndentation
+module.exports.detectIndentationDrift = detectIndentationDrift
+module.exports.ingestBatch = ingestBatch
+module.exports.JsonlLog = JsonlLog
+module.exports.extractKeywords = extractKeywords
+module.exports.detectLanguage = detectLanguage
+module.exports.tokenizeCode = tokenizeCode
+module.exports.analyzeDirectory = analyzeDirectory
+module.exports.parseManifest = parseManifest
+module.exports.diffManifests = diffManifests
+module.exports.MinHashIndex = MinHashIndex
+module.exports.compareModels = compareModels
+module.exports.encodeEvents = encodeEvents
+module.exports.decodeEvents = decodeEvents
+module.exports.toOtlpLogs = toOtlpLogs
+module.exports.toOtlpMetrics = toOtlpMetrics
+module.exports.exportOtlp = exportOtlp
+module.exports.acceptanceQuality = acceptanceQuality
+module.exports.renderPrometheus = renderPrometheus
+module.exports.activityMetrics = activityMetrics
+module.exports.PrometheusServer = PrometheusServer
+module.exports.classifyProse = classifyProse
+module.exports.linkPromptsToDiffs = linkPromptsToDiffs
+module.exports.DiskQueue = DiskQueue
+module.exports.calculateChurn = calculateChurn
+module.exports.replayAtSpeed = replayAtSpeed
+module.exports.SharedRing = SharedRing
+module.exports.RingProducer = RingProducer
+module.exports.RingConsumer = RingConsumer
+module.exports.uploadToS3 = uploadToS3
+module.exports.Sampler = Sampler
+module.exports.validateEvent = validateEvent
+module.exports.searchLiterals = searchLiterals
+module.exports.PatternSet = PatternSet
+module.exports.findPatterns = findPatterns
+module.exports.redactPatterns = redactPatterns
+module.exports.IngestServer = IngestServer
+module.exports.segmentSessions = segmentSessions
+module.exports.generateStandup = generateStandup
+module.exports.calculateCodeShape = calculateCodeShape
+module.exports.simhash = simhash
+module.exports.hammingDistance = hammingDistance
+module.exports.findNearDuplicates = findNearDuplicates
+module.exports.similarityMatrix = similarityMatrix
+module.exports.containment = containment
+module.exports.findSources = findSources
+module.exports.bootstrapCi = bootstrapCi
+module.exports.tTest = tTest
+module.exports.mannWhitney = mannWhitney
+module.exports.effectSize = effectSize
+module.exports.EventStore = EventStore
+module.exports.extractSymbols = extractSymbols
+module.exports.extractTypes = extractTypes
+module.exports.detectTests = detectTests
+module.exports.TfidfModel = TfidfModel
+module.exports.loadTokenEncoding = loadTokenEncoding
+module.exports.countTokens = countTokens
+module.exports.encodeTokens = encodeTokens
+module.exports.decodeTokens = decodeTokens
+module.exports.countChatTokens = countChatTokens
+module.exports.batchEstimateTokens = batchEstimateTokens
+module.exports.TrigramIndex = TrigramIndex
+module.exports.uploadEvents = uploadEvents
+module.exports.uploadQueued = uploadQueued
+module.exports.detectVcsEvents = detectVcsEvents
+module.exports.readCursorChats = readCursorChats
+module.exports.readCursorAiEvents = readCursorAiEvents
+module.exports.CursorDbWatcher = CursorDbWatcher
+module.exports.WebSocketServer = WebSocketServer
 module.exports.calculateDiff = calculateDiff
+module.exports.calculateDiffAsync = calculateDiffAsync
 module.exports.getLineChanges = getLineChanges
 module.exports.calculateFileStats = calculateFileStats
+module.exports.calculateFileStatsAsync = calculateFileStatsAsync
 module.exports.batchCalculateDiffs = batchCalculateDiffs
+module.exports.batchCalculateDiffsAsync = batchCalculateDiffsAsync
+module.exports.batchFileStats = batchFileStats
+module.exports.batchFileStatsAsync = batchFileStatsAsync
 module.exports.searchPatterns = searchPatterns
-module.exports.detectLanguage = detectLanguage
+module.exports.searchPatternsWithBudget = searchPatternsWithBudget
 module.exports.calculateSimilarity = calculateSimilarity
 module.exports.extractFunctions = extractFunctions
 module.exports.deduplicateStrings = deduplicateStrings
@@ -0,0 +1,213 @@
+/*!
+ * Agent run tracing
+ *
+ * Chains an agent's plan → tool call → edit → test steps into a single
+ * trace with timing, for per-run success and latency analytics.
+ */
+
+use crate::events::{sorted_by_time, ActivityEvent};
+use ahash::AHashMap;
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+
+const DEFAULT_MAX_GAP_MS: i64 = 5 * 60_000;
+const TEST_COMMANDS: &[&str] = &[
+    "npm test",
+    "npm run test",
+    "yarn test",
+    "pnpm test",
+    "jest",
+    "vitest",
+    "mocha",
+    "pytest",
+    "python -m unittest",
+    "cargo test",
+    "go test",
+    "mvn test",
+    "gradle test",
+    "rspec",
+];
+
+/// One step of an agent run
+#[napi(object)]
+pub struct AgentStep {
+    /// "plan", "tool_call", "edit", or "test"
+    pub kind: String,
+    pub event_id: Option<String>,
+    pub timestamp: i64,
+    /// Time since the trace started
+    pub offset_ms: i64,
+    /// Time since the previous step
+    pub latency_ms: i64,
+    pub file_path: Option<String>,
+    pub status: Option<String>,
+}
+
+/// A single agent run from plan to its last step
+#[napi(object)]
+pub struct AgentTrace {
+    /// run_id when events carry one, otherwise `trace-<start>`
+    pub id: String,
+    pub start: i64,
+    pub end: i64,
+    pub duration_ms: i64,
+    pub steps: Vec<AgentStep>,
+    pub tool_calls: u32,
+    pub edits: u32,
+    pub tests: u32,
+    pub files: Vec<String>,
+    pub time_to_first_edit_ms: Option<i64>,
+    /// Outcome of the last test step; None when the run never ran tests
+    pub success: Option<bool>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum StepKind {
+    Plan,
+    ToolCall,
+    Edit,
+    Test,
+}
+
+impl StepKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            StepKind::Plan => "plan",
+            StepKind::ToolCall => "tool_call",
+            StepKind::Edit => "edit",
+            StepKind::Test => "test",
+        }
+    }
+
+    /// Classify an event as an agent step, or None when it isn't part of agent runs
+    fn of(event: &ActivityEvent) -> Option<StepKind> {
+        match event.event_type.as_str() {
+            "agent_plan" | "plan" => return Some(StepKind::Plan),
+            "tool_call" | "tool_use" => return Some(StepKind::ToolCall),
+            "test_run" | "test" => return Some(StepKind::Test),
+            "terminal_command" => {
+                let command = event.details.as_deref().unwrap_or("");
+                if TEST_COMMANDS.iter().any(|t| command.contains(t)) {
+                    return Some(StepKind::Test);
+                }
+            }
+            _ => {}
+        }
+        // Edits belong to a run only when AI-applied or explicitly tagged with one
+        if event.file_path.is_some() && (event.ai_generated == Some(true) || event.run_id.is_some())
+        {
+            return Some(StepKind::Edit);
+        }
+        None
+    }
+}
+
+/**
+ * Link agent steps into traces
+ *
+ * Events tagged with the same run_id always form one trace. Untagged steps
+ * are chained heuristically: a plan starts a new trace, and a trace ends
+ * once no step follows within `max_gap_ms`. Events that aren't agent steps
+ * (human edits, unrelated commands) are skipped.
+ *
+ * @param events - Activity events in any order
+ * @param max_gap_ms - Idle time that ends an untagged trace (default: 5 minutes)
+ * @returns Traces ordered by start time
+ */
+#[napi]
+pub fn link_agent_steps(
+    events: Vec<ActivityEvent>,
+    max_gap_ms: Option<i64>,
+) -> Result<Vec<AgentTrace>> {
+    let max_gap_ms = max_gap_ms.unwrap_or(DEFAULT_MAX_GAP_MS);
+    let mut tagged: AHashMap<&str, Vec<(StepKind, &ActivityEvent)>> = AHashMap::new();
+    let mut untagged: Vec<Vec<(StepKind, &ActivityEvent)>> = Vec::new();
+
+    for event in sorted_by_time(&events) {
+        let Some(kind) = StepKind::of(event) else {
+            continue;
+        };
+
+        if let Some(run_id) = event.run_id.as_deref() {
+            tagged.entry(run_id).or_default().push((kind, event));
+            continue;
+        }
+
+        let starts_new = match untagged.last().and_then(|trace| trace.last()) {
+            None => true,
+            Some((_, last)) => {
+                kind == StepKind::Plan || event.timestamp - last.timestamp > max_gap_ms
+            }
+        };
+        if starts_new {
+            untagged.push(Vec::new());
+        }
+        if let Some(trace) = untagged.last_mut() {
+            trace.push((kind, event));
+        }
+    }
+
+    let mut traces: Vec<AgentTrace> = tagged
+        .into_iter()
+        .map(|(run_id, steps)| build_trace(Some(run_id), &steps))
+        .chain(untagged.iter().map(|steps| build_trace(None, steps)))
+        .collect();
+    traces.sort_by(|a, b| a.start.cmp(&b.start).then_with(|| a.id.cmp(&b.id)));
+
+    Ok(traces)
+}
+
+fn build_trace(run_id: Option<&str>, steps: &[(StepKind, &ActivityEvent)]) -> AgentTrace {
+    let start = steps[0].1.timestamp;
+    let end = steps[steps.len() - 1].1.timestamp;
+
+    let mut previous = start;
+    let mut files: Vec<String> = Vec::new();
+    let mut trace_steps = Vec::with_capacity(steps.len());
+    for (kind, event) in steps {
+        if *kind == StepKind::Edit {
+            if let Some(path) = &event.file_path {
+                if !files.contains(path) {
+                    files.push(path.clone());
+                }
+            }
+        }
+        trace_steps.push(AgentStep {
+            kind: kind.as_str().to_string(),
+            event_id: event.id.clone(),
+            timestamp: event.timestamp,
+            offset_ms: event.timestamp - start,
+            latency_ms: event.timestamp - previous,
+            file_path: event.file_path.clone(),
+            status: event.status.clone(),
+        });
+        previous = event.timestamp;
+    }
+
+    let count = |wanted: StepKind| steps.iter().filter(|(kind, _)| *kind == wanted).count() as u32;
+    let success = steps
+        .iter()
+        .rev()
+        .find(|(kind, _)| *kind == StepKind::Test)
+        .and_then(|(_, event)| event.status.as_deref())
+        .map(|status| status == "success");
+
+    AgentTrace {
+        id: run_id
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("trace-{}", start)),
+        start,
+        end,
+        duration_ms: end - start,
+        tool_calls: count(StepKind::ToolCall),
+        edits: count(StepKind::Edit),
+        tests: count(StepKind::Test),
+        time_to_first_edit_ms: steps
+            .iter()
+            .find(|(kind, _)| *kind == StepKind::Edit)
+            .map(|(_, event)| event.timestamp - start),
+        success,
+        files,
+        steps: trace_steps,
+    }
+}
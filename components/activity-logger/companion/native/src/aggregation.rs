@@ -0,0 +1,314 @@
+/*!
+ * Aggregation engine
+ * Rolls activity events up into metrics keyed by a chosen dimension
+ */
+
+use crate::events::{
+    sorted_by_time, ActivityEvent, DEFAULT_IDLE_GAP_MINUTES, MS_PER_DAY, MS_PER_MINUTE,
+};
+use crate::stats::{median_absolute_deviation, Estimator};
+use ahash::AHashMap;
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+
+const UNKNOWN_KEY: &str = "unknown";
+const DEFAULT_LONG_LIVED_DAYS: u32 = 14;
+const DEFAULT_DIVERGENCE_CHURN: i64 = 500;
+
+/// Metrics accumulated for one group of events
+#[napi(object)]
+#[derive(Clone)]
+pub struct AggregateMetrics {
+    pub key: String,
+    pub event_count: u32,
+    pub lines_added: i64,
+    pub lines_removed: i64,
+    pub churn: i64,
+    pub ai_events: u32,
+    /// Fraction of events flagged ai_generated
+    pub ai_ratio: f64,
+    pub first_seen: i64,
+    pub last_seen: i64,
+    /// Time attributed to this group: gaps between consecutive events, ignoring idle gaps
+    pub active_ms: i64,
+    /// Events carrying line counts, which the per-edit averages are taken over
+    pub edit_count: u32,
+    /// Per-edit lines added + removed, summarized with the selected estimator
+    pub average_churn: f64,
+    pub average_lines_added: f64,
+    pub average_lines_removed: f64,
+    /// Median absolute deviation of per-edit churn
+    pub churn_mad: f64,
+}
+
+/// Options for aggregate_events
+#[napi(object)]
+pub struct AggregateOptions {
+    /// Per-edit average: "mean" (default), "trimmed_mean", or "median"
+    pub estimator: Option<String>,
+    /// Fraction trimmed from each end by "trimmed_mean" (default: 0.1)
+    pub trim: Option<f64>,
+}
+
+/// Per-edit line counts collected for one group
+#[derive(Default)]
+struct EditSamples {
+    added: Vec<f64>,
+    removed: Vec<f64>,
+    churn: Vec<f64>,
+}
+
+impl EditSamples {
+    fn add(&mut self, event: &ActivityEvent) {
+        if event.lines_added.is_none() && event.lines_removed.is_none() {
+            return;
+        }
+        let added = event.lines_added.unwrap_or(0) as f64;
+        let removed = event.lines_removed.unwrap_or(0) as f64;
+        self.added.push(added);
+        self.removed.push(removed);
+        self.churn.push(added + removed);
+    }
+}
+
+impl AggregateMetrics {
+    fn new(key: &str, timestamp: i64) -> Self {
+        AggregateMetrics {
+            key: key.to_string(),
+            event_count: 0,
+            lines_added: 0,
+            lines_removed: 0,
+            churn: 0,
+            ai_events: 0,
+            ai_ratio: 0.0,
+            first_seen: timestamp,
+            last_seen: timestamp,
+            active_ms: 0,
+            edit_count: 0,
+            average_churn: 0.0,
+            average_lines_added: 0.0,
+            average_lines_removed: 0.0,
+            churn_mad: 0.0,
+        }
+    }
+
+    fn add(&mut self, event: &ActivityEvent) {
+        let added = event.lines_added.unwrap_or(0) as i64;
+        let removed = event.lines_removed.unwrap_or(0) as i64;
+        self.event_count += 1;
+        self.lines_added += added;
+        self.lines_removed += removed;
+        self.churn += added + removed;
+        if event.ai_generated == Some(true) {
+            self.ai_events += 1;
+        }
+        self.first_seen = self.first_seen.min(event.timestamp);
+        self.last_seen = self.last_seen.max(event.timestamp);
+    }
+
+    fn finish(&mut self, mut edits: EditSamples, estimator: Estimator) {
+        self.ai_ratio = if self.event_count > 0 {
+            self.ai_events as f64 / self.event_count as f64
+        } else {
+            0.0
+        };
+        self.edit_count = edits.churn.len() as u32;
+        self.churn_mad = median_absolute_deviation(&edits.churn);
+        self.average_churn = estimator.estimate(&mut edits.churn);
+        self.average_lines_added = estimator.estimate(&mut edits.added);
+        self.average_lines_removed = estimator.estimate(&mut edits.removed);
+    }
+}
+
+/// Dimension that aggregate_events groups by
+#[derive(Clone, Copy)]
+enum GroupBy {
+    Branch,
+    File,
+    Session,
+    Type,
+}
+
+impl GroupBy {
+    fn parse(value: &str) -> Result<Self> {
+        match value {
+            "branch" => Ok(GroupBy::Branch),
+            "file" => Ok(GroupBy::File),
+            "session" => Ok(GroupBy::Session),
+            "type" => Ok(GroupBy::Type),
+            other => Err(Error::new(
+                Status::InvalidArg,
+                format!(
+                    "Unknown group_by '{}', expected branch, file, session, or type",
+                    other
+                ),
+            )),
+        }
+    }
+
+    fn key<'a>(&self, event: &'a ActivityEvent) -> &'a str {
+        let key = match self {
+            GroupBy::Branch => event.branch.as_deref(),
+            GroupBy::File => event.file_path.as_deref(),
+            GroupBy::Session => event.session_id.as_deref(),
+            GroupBy::Type => Some(event.event_type.as_str()),
+        };
+        key.unwrap_or(UNKNOWN_KEY)
+    }
+}
+
+/// Group events and accumulate metrics, ordered by first activity
+fn aggregate(
+    events: &[ActivityEvent],
+    group_by: GroupBy,
+    estimator: Estimator,
+) -> Vec<AggregateMetrics> {
+    let sorted = sorted_by_time(events);
+    let idle_gap_ms = DEFAULT_IDLE_GAP_MINUTES as i64 * MS_PER_MINUTE;
+    let mut groups: AHashMap<&str, (AggregateMetrics, EditSamples)> = AHashMap::new();
+
+    for (i, event) in sorted.iter().enumerate() {
+        let key = group_by.key(event);
+        let (metrics, edits) = groups.entry(key).or_insert_with(|| {
+            (
+                AggregateMetrics::new(key, event.timestamp),
+                EditSamples::default(),
+            )
+        });
+        metrics.add(event);
+        edits.add(event);
+
+        // Time until the next event belongs to this event's group, unless the user went idle
+        if let Some(next) = sorted.get(i + 1) {
+            let gap = next.timestamp - event.timestamp;
+            if gap <= idle_gap_ms {
+                metrics.active_ms += gap;
+            }
+        }
+    }
+
+    let mut results: Vec<AggregateMetrics> = groups
+        .into_values()
+        .map(|(mut metrics, edits)| {
+            metrics.finish(edits, estimator);
+            metrics
+        })
+        .collect();
+    results.sort_by(|a, b| {
+        a.first_seen
+            .cmp(&b.first_seen)
+            .then_with(|| a.key.cmp(&b.key))
+    });
+    results
+}
+
+/**
+ * Aggregate events into per-group metrics
+ *
+ * Per-edit averages use the arithmetic mean unless a robust estimator is
+ * selected: one huge generated-file event dominates a mean but barely
+ * moves a trimmed mean or median.
+ *
+ * @param events - Activity events in any order
+ * @param group_by - "branch", "file", "session", or "type"; events missing the key group under "unknown"
+ * @param options - Estimator for per-edit averages ("mean", "trimmed_mean", "median") and trim fraction
+ * @returns AggregateMetrics per group, ordered by first activity
+ */
+#[napi]
+pub fn aggregate_events(
+    events: Vec<ActivityEvent>,
+    group_by: String,
+    options: Option<AggregateOptions>,
+) -> Result<Vec<AggregateMetrics>> {
+    let group_by = GroupBy::parse(&group_by)?;
+    let estimator = match options {
+        Some(AggregateOptions {
+            estimator: Some(name),
+            trim,
+        }) => Estimator::parse(&name, trim)?,
+        _ => Estimator::Mean,
+    };
+    Ok(aggregate(&events, group_by, estimator))
+}
+
+/// Options for summarize_branches
+#[napi(object)]
+pub struct BranchReportOptions {
+    /// Trunk branch excluded from divergence checks (default: "main" or "master")
+    pub default_branch: Option<String>,
+    /// Lifetime after which a branch counts as long-lived (default: 14 days)
+    pub long_lived_days: Option<u32>,
+    /// Churn a long-lived branch must accumulate to count as divergent (default: 500 lines)
+    pub divergence_churn: Option<i64>,
+}
+
+/// Lifetime summary for one branch
+#[napi(object)]
+pub struct BranchSummary {
+    pub branch: String,
+    pub metrics: AggregateMetrics,
+    /// Time from first to last event seen on the branch
+    pub lifetime_ms: i64,
+    pub is_long_lived: bool,
+    pub is_divergent: bool,
+}
+
+/// Per-branch attribution report
+#[napi(object)]
+pub struct BranchReport {
+    pub branches: Vec<BranchSummary>,
+    /// Long-lived branches that accumulated enough churn to have likely drifted from trunk
+    pub long_lived_divergent: Vec<String>,
+}
+
+/**
+ * Attribute activity to git branches
+ *
+ * Summarizes time on branch, churn, and AI ratio per branch, and flags
+ * long-lived branches carrying a lot of unmerged-looking churn.
+ */
+#[napi]
+pub fn summarize_branches(
+    events: Vec<ActivityEvent>,
+    options: Option<BranchReportOptions>,
+) -> Result<BranchReport> {
+    let options = options.unwrap_or(BranchReportOptions {
+        default_branch: None,
+        long_lived_days: None,
+        divergence_churn: None,
+    });
+    let long_lived_ms =
+        options.long_lived_days.unwrap_or(DEFAULT_LONG_LIVED_DAYS) as i64 * MS_PER_DAY;
+    let divergence_churn = options.divergence_churn.unwrap_or(DEFAULT_DIVERGENCE_CHURN);
+    let is_trunk = |branch: &str| match &options.default_branch {
+        Some(default_branch) => branch == default_branch,
+        None => branch == "main" || branch == "master",
+    };
+
+    let branches: Vec<BranchSummary> = aggregate(&events, GroupBy::Branch, Estimator::Mean)
+        .into_iter()
+        .map(|metrics| {
+            let lifetime_ms = metrics.last_seen - metrics.first_seen;
+            let tracked = metrics.key != UNKNOWN_KEY && !is_trunk(&metrics.key);
+            let is_long_lived = tracked && lifetime_ms >= long_lived_ms;
+            BranchSummary {
+                branch: metrics.key.clone(),
+                is_divergent: is_long_lived && metrics.churn >= divergence_churn,
+                is_long_lived,
+                lifetime_ms,
+                metrics,
+            }
+        })
+        .collect();
+
+    let long_lived_divergent = branches
+        .iter()
+        .filter(|b| b.is_divergent)
+        .map(|b| b.branch.clone())
+        .collect();
+
+    Ok(BranchReport {
+        branches,
+        long_lived_divergent,
+    })
+}
@@ -0,0 +1,509 @@
+/*!
+ * Approximate nearest neighbors
+ * An HNSW graph over embedding vectors, saved to and loaded from disk
+ */
+
+use crate::minhash::splitmix64;
+use ahash::{AHashMap, AHashSet};
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+
+/// Leading bytes of a saved index, bumped when the layout changes
+const MAGIC: &[u8; 8] = b"CTHNSW01";
+
+/// Options for an AnnIndex
+#[napi(object)]
+pub struct AnnIndexOptions {
+    /// "cosine" (default) or "euclidean"
+    pub metric: Option<String>,
+    /// Links per node and layer; layer 0 keeps twice as many (default: 16)
+    pub m: Option<u32>,
+    /// Candidates considered while linking a new node (default: 200)
+    pub ef_construction: Option<u32>,
+    /// Candidates considered per search; raised to k when smaller (default: 50)
+    pub ef_search: Option<u32>,
+}
+
+/// A stored vector near the query
+#[napi(object)]
+pub struct AnnMatch {
+    pub id: String,
+    /// 1 - cosine similarity for the cosine metric, Euclidean distance otherwise
+    pub distance: f64,
+}
+
+/// Distance and node, ordered by distance
+#[derive(Clone, Copy, PartialEq)]
+struct Scored(f32, u32);
+
+impl Eq for Scored {}
+
+impl Ord for Scored {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0).then(self.1.cmp(&other.1))
+    }
+}
+
+impl PartialOrd for Scored {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+struct Node {
+    id: String,
+    deleted: bool,
+    /// Neighbor lists, layer 0 first
+    links: Vec<Vec<u32>>,
+}
+
+/// HNSW index of fixed-length vectors keyed by id
+#[napi]
+pub struct AnnIndex {
+    dimensions: usize,
+    cosine: bool,
+    m: usize,
+    ef_construction: usize,
+    ef_search: usize,
+    /// Row-major, normalized to unit length for the cosine metric
+    vectors: Vec<f32>,
+    nodes: Vec<Node>,
+    /// Live node per id
+    slots: AHashMap<String, u32>,
+    entry: Option<u32>,
+    rng: u64,
+}
+
+fn vector_of(vector: Either<Float32Array, Vec<f64>>) -> Vec<f32> {
+    match vector {
+        Either::A(typed) => typed.to_vec(),
+        Either::B(values) => values.into_iter().map(|v| v as f32).collect(),
+    }
+}
+
+impl AnnIndex {
+    fn row(&self, node: u32) -> &[f32] {
+        let start = node as usize * self.dimensions;
+        &self.vectors[start..start + self.dimensions]
+    }
+
+    fn distance(&self, query: &[f32], node: u32) -> f32 {
+        let row = self.row(node);
+        if self.cosine {
+            1.0 - query.iter().zip(row).map(|(a, b)| a * b).sum::<f32>()
+        } else {
+            query
+                .iter()
+                .zip(row)
+                .map(|(a, b)| (a - b) * (a - b))
+                .sum::<f32>()
+                .sqrt()
+        }
+    }
+
+    /// Check the length and, for cosine, normalize
+    fn prepare(&self, vector: Either<Float32Array, Vec<f64>>) -> Result<Vec<f32>> {
+        let mut vector = vector_of(vector);
+        if vector.len() != self.dimensions {
+            return Err(Error::new(
+                Status::InvalidArg,
+                format!(
+                    "Vector has {} dimensions, expected {}",
+                    vector.len(),
+                    self.dimensions
+                ),
+            ));
+        }
+        if self.cosine {
+            let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+            if norm > 0.0 {
+                vector.iter_mut().for_each(|v| *v /= norm);
+            }
+        }
+        Ok(vector)
+    }
+
+    fn max_links(&self, layer: usize) -> usize {
+        if layer == 0 {
+            self.m * 2
+        } else {
+            self.m
+        }
+    }
+
+    /// Best `ef` nodes on `layer` reachable from `entry`, nearest first
+    fn search_layer(&self, query: &[f32], entry: u32, ef: usize, layer: usize) -> Vec<Scored> {
+        let first = Scored(self.distance(query, entry), entry);
+        let mut visited = AHashSet::from([entry]);
+        let mut candidates = BinaryHeap::from([Reverse(first)]);
+        let mut best = BinaryHeap::from([first]);
+        while let Some(Reverse(current)) = candidates.pop() {
+            if best.len() >= ef && best.peek().is_some_and(|worst| current.0 > worst.0) {
+                break;
+            }
+            for &neighbor in &self.nodes[current.1 as usize].links[layer] {
+                if !visited.insert(neighbor) {
+                    continue;
+                }
+                let scored = Scored(self.distance(query, neighbor), neighbor);
+                if best.len() < ef || best.peek().is_some_and(|worst| scored.0 < worst.0) {
+                    candidates.push(Reverse(scored));
+                    best.push(scored);
+                    if best.len() > ef {
+                        best.pop();
+                    }
+                }
+            }
+        }
+        best.into_sorted_vec()
+    }
+
+    /// Greedy walk down to `layer`, one best node per layer above it
+    fn descend(&self, query: &[f32], mut entry: u32, from: usize, to: usize) -> u32 {
+        for layer in (to + 1..=from).rev() {
+            entry = self.search_layer(query, entry, 1, layer)[0].1;
+        }
+        entry
+    }
+
+    /// The HNSW neighbor heuristic: prefer candidates not already covered by a closer pick
+    fn select(&self, candidates: &[Scored], keep: usize) -> Vec<u32> {
+        let mut chosen: Vec<Scored> = Vec::with_capacity(keep);
+        let mut pruned = Vec::new();
+        for candidate in candidates {
+            if chosen.len() >= keep {
+                break;
+            }
+            let row = self.row(candidate.1).to_vec();
+            if chosen
+                .iter()
+                .all(|picked| self.distance(&row, picked.1) > candidate.0)
+            {
+                chosen.push(*candidate);
+            } else {
+                pruned.push(*candidate);
+            }
+        }
+        // Fill up with the pruned ones so sparse regions stay connected
+        chosen.extend(pruned.into_iter().take(keep.saturating_sub(chosen.len())));
+        chosen.into_iter().map(|s| s.1).collect()
+    }
+
+    fn insert(&mut self, id: String, vector: Vec<f32>) {
+        let uniform = (splitmix64(&mut self.rng) >> 11) as f64 / (1u64 << 53) as f64;
+        let level = (-(1.0 - uniform).ln() / (self.m as f64).ln()).floor() as usize;
+        let node = self.nodes.len() as u32;
+        self.vectors.extend_from_slice(&vector);
+        self.nodes.push(Node {
+            id: id.clone(),
+            deleted: false,
+            links: vec![Vec::new(); level + 1],
+        });
+        self.slots.insert(id, node);
+
+        let Some(entry) = self.entry else {
+            self.entry = Some(node);
+            return;
+        };
+        let top = self.nodes[entry as usize].links.len() - 1;
+        let mut current = self.descend(&vector, entry, top, level);
+        for layer in (0..=level.min(top)).rev() {
+            let candidates = self.search_layer(&vector, current, self.ef_construction, layer);
+            current = candidates[0].1;
+            let neighbors = self.select(&candidates, self.m);
+            self.nodes[node as usize].links[layer] = neighbors.clone();
+            for neighbor in neighbors {
+                self.nodes[neighbor as usize].links[layer].push(node);
+                if self.nodes[neighbor as usize].links[layer].len() > self.max_links(layer) {
+                    let row = self.row(neighbor).to_vec();
+                    let mut scored: Vec<Scored> = self.nodes[neighbor as usize].links[layer]
+                        .iter()
+                        .map(|&n| Scored(self.distance(&row, n), n))
+                        .collect();
+                    scored.sort();
+                    self.nodes[neighbor as usize].links[layer] =
+                        self.select(&scored, self.max_links(layer));
+                }
+            }
+        }
+        if level > top {
+            self.entry = Some(node);
+        }
+    }
+}
+
+fn corrupt(path: &str) -> Error {
+    Error::new(Status::InvalidArg, format!("Invalid ANN index {}", path))
+}
+
+/// Little-endian reader over a saved index
+struct Reader<'a> {
+    bytes: &'a [u8],
+    path: &'a str,
+}
+
+impl Reader<'_> {
+    fn take(&mut self, count: usize) -> Result<&[u8]> {
+        if self.bytes.len() < count {
+            return Err(corrupt(self.path));
+        }
+        let (head, rest) = self.bytes.split_at(count);
+        self.bytes = rest;
+        Ok(head)
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+}
+
+#[napi]
+impl AnnIndex {
+    /// Create an empty index for vectors of length `dimensions`
+    #[napi(constructor)]
+    pub fn new(dimensions: u32, options: Option<AnnIndexOptions>) -> Result<Self> {
+        let cosine = match options
+            .as_ref()
+            .and_then(|o| o.metric.as_deref())
+            .unwrap_or("cosine")
+        {
+            "cosine" => true,
+            "euclidean" => false,
+            other => {
+                return Err(Error::new(
+                    Status::InvalidArg,
+                    format!("Unknown metric '{}' (expected cosine or euclidean)", other),
+                ))
+            }
+        };
+        if dimensions == 0 {
+            return Err(Error::new(
+                Status::InvalidArg,
+                "dimensions must be positive",
+            ));
+        }
+        Ok(AnnIndex {
+            dimensions: dimensions as usize,
+            cosine,
+            m: options.as_ref().and_then(|o| o.m).unwrap_or(16).max(2) as usize,
+            ef_construction: options
+                .as_ref()
+                .and_then(|o| o.ef_construction)
+                .unwrap_or(200)
+                .max(1) as usize,
+            ef_search: options.and_then(|o| o.ef_search).unwrap_or(50).max(1) as usize,
+            vectors: Vec::new(),
+            nodes: Vec::new(),
+            slots: AHashMap::new(),
+            entry: None,
+            rng: 0x686e_7377_0000_0001,
+        })
+    }
+
+    /**
+     * Store `vector` under `id`
+     *
+     * Re-adding an id replaces its vector. The graph node of the old vector
+     * stays as a routing point, hidden from results, until the index is
+     * rebuilt.
+     */
+    #[napi(ts_args_type = "id: string, vector: Float32Array | number[]")]
+    pub fn add(&mut self, id: String, vector: Either<Float32Array, Vec<f64>>) -> Result<()> {
+        let vector = self.prepare(vector)?;
+        self.remove(id.clone());
+        self.insert(id, vector);
+        Ok(())
+    }
+
+    /// Hide a vector from results; returns whether it was stored
+    #[napi]
+    pub fn remove(&mut self, id: String) -> bool {
+        let Some(node) = self.slots.remove(&id) else {
+            return false;
+        };
+        self.nodes[node as usize].deleted = true;
+        true
+    }
+
+    /**
+     * The `k` stored vectors nearest `vector`
+     *
+     * Approximate: a larger `ef` (default: the index's ef_search) finds the
+     * true neighbors more often at the cost of speed.
+     *
+     * @returns Matches, nearest first
+     */
+    #[napi(
+        ts_args_type = "vector: Float32Array | number[], k?: number | undefined | null, ef?: number | undefined | null"
+    )]
+    pub fn search(
+        &self,
+        vector: Either<Float32Array, Vec<f64>>,
+        k: Option<u32>,
+        ef: Option<u32>,
+    ) -> Result<Vec<AnnMatch>> {
+        let query = self.prepare(vector)?;
+        let k = k.unwrap_or(10) as usize;
+        let Some(entry) = self.entry else {
+            return Ok(Vec::new());
+        };
+        // Hidden nodes take room in the beam, so widen it by how many there are, within reason
+        let hidden = self.nodes.len() - self.slots.len();
+        let ef = ef
+            .map_or(self.ef_search, |ef| ef as usize)
+            .max(k)
+            .saturating_add(hidden.min(k * 4));
+        let top = self.nodes[entry as usize].links.len() - 1;
+        let entry = self.descend(&query, entry, top, 0);
+        Ok(self
+            .search_layer(&query, entry, ef, 0)
+            .into_iter()
+            .filter(|scored| !self.nodes[scored.1 as usize].deleted)
+            .take(k)
+            .map(|scored| AnnMatch {
+                id: self.nodes[scored.1 as usize].id.clone(),
+                distance: scored.0.max(0.0) as f64,
+            })
+            .collect())
+    }
+
+    /// Whether a vector is stored under `id`
+    #[napi]
+    pub fn has(&self, id: String) -> bool {
+        self.slots.contains_key(&id)
+    }
+
+    /// Number of stored vectors
+    #[napi(getter)]
+    pub fn size(&self) -> u32 {
+        self.slots.len() as u32
+    }
+
+    /// Length of each vector
+    #[napi(getter)]
+    pub fn dimensions(&self) -> u32 {
+        self.dimensions as u32
+    }
+
+    /**
+     * Write the index to `path`
+     *
+     * The graph is saved along with the vectors, so loading needs no
+     * rebuild. Hidden nodes are kept. The file is written to a temporary
+     * file first and renamed into place.
+     */
+    #[napi]
+    pub fn save(&self, path: String) -> Result<()> {
+        let mut bytes = Vec::with_capacity(self.vectors.len() * 4 + self.nodes.len() * 64);
+        bytes.extend_from_slice(MAGIC);
+        for value in [
+            self.dimensions as u32,
+            self.cosine as u32,
+            self.m as u32,
+            self.ef_construction as u32,
+            self.ef_search as u32,
+            self.entry.unwrap_or(u32::MAX),
+            self.nodes.len() as u32,
+        ] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        bytes.extend_from_slice(&self.rng.to_le_bytes());
+        for value in &self.vectors {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        for node in &self.nodes {
+            bytes.extend_from_slice(&(node.id.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(node.id.as_bytes());
+            bytes.push(node.deleted as u8);
+            bytes.extend_from_slice(&(node.links.len() as u32).to_le_bytes());
+            for links in &node.links {
+                bytes.extend_from_slice(&(links.len() as u32).to_le_bytes());
+                for link in links {
+                    bytes.extend_from_slice(&link.to_le_bytes());
+                }
+            }
+        }
+        let temporary = format!("{}.tmp", path);
+        std::fs::write(&temporary, bytes)
+            .and_then(|_| std::fs::rename(&temporary, &path))
+            .map_err(|e| {
+                Error::new(
+                    Status::GenericFailure,
+                    format!("Failed to write {}: {}", path, e),
+                )
+            })
+    }
+
+    /// Load an index written by save
+    #[napi(factory)]
+    pub fn load(path: String) -> Result<Self> {
+        let bytes = std::fs::read(&path).map_err(|e| {
+            Error::new(
+                Status::GenericFailure,
+                format!("Failed to read {}: {}", path, e),
+            )
+        })?;
+        let mut reader = Reader {
+            bytes: &bytes,
+            path: &path,
+        };
+        if reader.take(8)? != MAGIC {
+            return Err(corrupt(&path));
+        }
+        let dimensions = reader.u32()? as usize;
+        let cosine = reader.u32()? == 1;
+        let m = reader.u32()? as usize;
+        let ef_construction = reader.u32()? as usize;
+        let ef_search = reader.u32()? as usize;
+        let entry = Some(reader.u32()?).filter(|entry| *entry != u32::MAX);
+        let count = reader.u32()? as usize;
+        let rng = u64::from_le_bytes(reader.take(8)?.try_into().unwrap());
+        let vectors: Vec<f32> = reader
+            .take(count * dimensions * 4)?
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes(b.try_into().unwrap()))
+            .collect();
+        let mut nodes = Vec::with_capacity(count);
+        let mut slots = AHashMap::new();
+        for index in 0..count {
+            let length = reader.u32()? as usize;
+            let id =
+                String::from_utf8(reader.take(length)?.to_vec()).map_err(|_| corrupt(&path))?;
+            let deleted = reader.take(1)?[0] == 1;
+            let layers = reader.u32()? as usize;
+            let mut links = Vec::with_capacity(layers);
+            for _ in 0..layers {
+                let size = reader.u32()? as usize;
+                let layer: Vec<u32> = (0..size).map(|_| reader.u32()).collect::<Result<_>>()?;
+                if layer.iter().any(|link| *link as usize >= count) {
+                    return Err(corrupt(&path));
+                }
+                links.push(layer);
+            }
+            if links.is_empty() {
+                return Err(corrupt(&path));
+            }
+            if !deleted {
+                slots.insert(id.clone(), index as u32);
+            }
+            nodes.push(Node { id, deleted, links });
+        }
+        if dimensions == 0 || entry.is_some_and(|entry| entry as usize >= count) {
+            return Err(corrupt(&path));
+        }
+        Ok(AnnIndex {
+            dimensions,
+            cosine,
+            m,
+            ef_construction,
+            ef_search,
+            vectors,
+            nodes,
+            slots,
+            entry,
+            rng,
+        })
+    }
+}
@@ -0,0 +1,113 @@
+/*!
+ * Code annotation extraction
+ * TODO/FIXME/HACK/XXX comments, for tracking whether sessions add or pay down debt
+ */
+
+use crate::comments::comment_ranges;
+use crate::functions::{line_of, line_starts};
+use napi_derive::napi;
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// One TODO-style comment
+#[napi(object)]
+pub struct Annotation {
+    /// "TODO", "FIXME", "HACK", or "XXX"
+    pub tag: String,
+    /// Comment text after the tag, author, and colon
+    pub text: String,
+    /// 1-based line of the tag
+    pub line: u32,
+    /// Author from `TODO(alice)`, `TODO[alice]`, or `TODO @alice`
+    pub author: Option<String>,
+    /// First issue reference: "#123", "ABC-123", "gh-12", or an issue/pull URL
+    pub issue: Option<String>,
+}
+
+static TAG: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"\b(TODO|FIXME|HACK|XXX)\b(?:\s*[(\[]([^)\]\n]*)[)\]])?(?:\s*:?\s*@([\w.-]+))?\s*[:\-]?[ \t]*([^\n]*)",
+    )
+    .unwrap()
+});
+
+static ISSUE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"https?://\S+/(?:issues|pull|merge_requests)/\d+|\b[A-Z][A-Z0-9]+-\d+\b|\bgh-\d+\b|(?:^|[\s(\[])(#\d+)\b",
+    )
+    .unwrap()
+});
+
+/// Uppercase-dash-number tokens that are standards and encodings, not ticket keys
+const NOT_ISSUE_KEYS: &[&str] = &[
+    "UTF", "ISO", "RFC", "SHA", "CVE", "ES", "IE", "HTTP", "TLS", "PEP",
+];
+
+/// First issue reference in `text`
+fn issue_reference(text: &str) -> Option<String> {
+    ISSUE.captures_iter(text).find_map(|caps| {
+        let found = caps.get(1).or_else(|| caps.get(0))?.as_str().trim();
+        let key = found.split('-').next().unwrap_or(found);
+        if NOT_ISSUE_KEYS.contains(&key) {
+            return None;
+        }
+        Some(found.to_string())
+    })
+}
+
+/// Comment text without a trailing block-comment close or doc-comment markers
+fn clean_text(text: &str) -> String {
+    text.trim()
+        .trim_end_matches("*/")
+        .trim_end_matches("-->")
+        .trim_end_matches("#}")
+        .trim()
+        .to_string()
+}
+
+/**
+ * Extract TODO, FIXME, HACK, and XXX comments
+ *
+ * Only comments are searched (string literals and identifiers such as
+ * `todoList` are ignored), and tags must be uppercase. Recognizes authors
+ * written as `TODO(alice):`, `TODO[alice]`, or `TODO @alice`, and issue
+ * references (`#123`, `ABC-123`, `gh-12`, or an issues/pull URL) anywhere in
+ * the comment, including `TODO(#123)`.
+ *
+ * @param content - Source code
+ * @param language - Language name as returned by detect_language
+ * @returns Annotations in source order
+ */
+#[napi]
+pub fn extract_annotations(content: String, language: String) -> Vec<Annotation> {
+    let starts = line_starts(&content);
+    let mut annotations = Vec::new();
+    for range in comment_ranges(&content, &language) {
+        let comment = &content[range.clone()];
+        for caps in TAG.captures_iter(comment) {
+            let (Some(tag), Some(text)) = (caps.get(1), caps.get(4)) else {
+                continue;
+            };
+            let text = clean_text(text.as_str());
+            let bracketed = caps.get(2).map(|m| m.as_str().trim());
+            // `TODO(#123)` and `TODO(ABC-123)` name an issue, not a person
+            let bracket_issue = bracketed.and_then(issue_reference);
+            let author = caps.get(3).map(|m| m.as_str().to_string()).or_else(|| {
+                match (bracketed, &bracket_issue) {
+                    (Some(name), None) if !name.is_empty() => {
+                        Some(name.trim_start_matches('@').to_string())
+                    }
+                    _ => None,
+                }
+            });
+            annotations.push(Annotation {
+                tag: tag.as_str().to_string(),
+                line: line_of(&starts, range.start + tag.start()),
+                author,
+                issue: bracket_issue.or_else(|| issue_reference(&text)),
+                text,
+            });
+        }
+    }
+    annotations
+}
@@ -0,0 +1,281 @@
+/*!
+ * Authorship attribution
+ * Classifies inserted lines as AI-applied or hand-typed from how they
+ * arrived (burst size, insertion rate) and whether they match a recent AI
+ * response
+ */
+
+use crate::provenance::{
+    gap_before, inserted_lines, interactions, is_edit, sorted_fields, Interaction,
+};
+use crate::simhash::tokens;
+use crate::similarity::{contained, shingles};
+use ahash::{AHashMap, AHashSet};
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use serde_json::Value;
+
+const DEFAULT_WINDOW_MS: f64 = 10.0 * 60_000.0;
+const DEFAULT_SHINGLE_SIZE: u32 = 3;
+const DEFAULT_CONTENT_WEIGHT: f64 = 0.6;
+const DEFAULT_BURST_LINES: u32 = 5;
+const DEFAULT_FAST_CHARS_PER_SECOND: f64 = 40.0;
+const DEFAULT_MAX_GAP_MS: f64 = 30_000.0;
+/// Lines and edits with fewer tokens ("}", "else {") match any code, so
+/// lines take their edit's match and edits carry no content evidence
+const MIN_TOKENS: usize = 3;
+
+/// Options for attribute_authorship
+#[napi(object)]
+#[derive(Default)]
+pub struct AttributionOptions {
+    /// How long after an AI response its code counts as recent (default: 600000)
+    pub window_ms: Option<f64>,
+    /// Tokens per shingle when comparing code (default: 3)
+    pub shingle_size: Option<u32>,
+    /// Share of the score from matching AI responses, the rest from burst size and rate, 0-1 (default: 0.6)
+    pub content_weight: Option<f64>,
+    /// Lines inserted at once that count as a full burst (default: 5)
+    pub burst_lines: Option<u32>,
+    /// Insertion rate that counts as fully machine-like; people type well under it (default: 40)
+    pub fast_chars_per_second: Option<f64>,
+    /// Longest time since the file's previous edit from which a rate is measured (default: 30000)
+    pub max_gap_ms: Option<f64>,
+}
+
+/// Who most likely wrote one inserted line
+#[napi(object)]
+pub struct LineAttribution {
+    pub diff_id: Option<String>,
+    pub file_path: Option<String>,
+    /// 1-based line in the new content, when the edit carried it
+    pub line: Option<u32>,
+    /// "ai" or "human"
+    pub author: String,
+    /// Confidence in `author`, 0.5-1
+    pub confidence: f64,
+    /// How closely the line matches the best recent AI response, 0-1
+    pub content_match: f64,
+    /// Non-blank lines the edit inserted
+    pub burst_lines: u32,
+    /// Characters inserted per second, when the edit's duration is known
+    pub chars_per_second: Option<f64>,
+    /// Prompt behind the matching response, for lines attributed to AI
+    pub prompt_id: Option<String>,
+}
+
+/// Lines written by AI and by hand in one file
+#[napi(object)]
+pub struct FileAttribution {
+    pub file_path: String,
+    pub ai_lines: u32,
+    pub human_lines: u32,
+    /// 0-1
+    pub ai_fraction: f64,
+}
+
+/// Result of attribute_authorship
+#[napi(object)]
+pub struct AttributionReport {
+    pub ai_lines: u32,
+    pub human_lines: u32,
+    /// Share of inserted lines written by AI, 0-1
+    pub ai_fraction: f64,
+    /// By file path
+    pub files: Vec<FileAttribution>,
+    /// Every non-blank inserted line, in time order
+    pub lines: Vec<LineAttribution>,
+}
+
+/// An interaction's code in the two forms lines are matched against
+struct Evidence {
+    shingles: AHashSet<u64>,
+    lines: AHashSet<String>,
+}
+
+impl Evidence {
+    fn of(interaction: &Interaction, size: usize) -> Self {
+        Evidence {
+            shingles: shingles(&interaction.code, size),
+            lines: interaction.code.lines().map(normalized).collect(),
+        }
+    }
+}
+
+fn normalized(line: &str) -> String {
+    line.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn fraction(ai: u32, human: u32) -> f64 {
+    if ai + human == 0 {
+        0.0
+    } else {
+        ai as f64 / (ai + human) as f64
+    }
+}
+
+/**
+ * Classify inserted lines as AI-applied or hand-typed
+ *
+ * Each line of each edit (`code_change` or `file_change`) gets a score
+ * from three signals. Content: how closely the line matches code in an AI
+ * response (as grouped by link_prompts_to_diffs) from the last
+ * `window_ms`, exact after collapsing whitespace or by shingle
+ * containment. Burst: how many lines the edit inserted at once. Rate:
+ * characters per second, over `duration_ms` when the edit records it or
+ * since the file's previous edit when that was at most `max_gap_ms` ago.
+ * Burst and rate average into a behaviour score, which `content_weight`
+ * blends with the content score; lines scoring 0.5 or more are AI.
+ *
+ * Without a matching response the content score is 0, so a large paste
+ * of code no response contained counts as written by hand. Blank lines
+ * are not counted.
+ *
+ * @param events - Chat, suggestion, and edit events, in any order
+ * @param options - Window and signal thresholds
+ * @returns Per-line authorship and the share of lines written by AI, overall and per file
+ */
+#[napi(
+    ts_args_type = "events: Array<Record<string, any>>, options?: AttributionOptions | undefined | null"
+)]
+pub fn attribute_authorship(
+    events: Vec<Value>,
+    options: Option<AttributionOptions>,
+) -> Result<AttributionReport> {
+    let options = options.unwrap_or_default();
+    let window_ms = options.window_ms.unwrap_or(DEFAULT_WINDOW_MS);
+    let content_weight = options.content_weight.unwrap_or(DEFAULT_CONTENT_WEIGHT);
+    let fast_rate = options
+        .fast_chars_per_second
+        .unwrap_or(DEFAULT_FAST_CHARS_PER_SECOND);
+    if window_ms.is_nan()
+        || window_ms <= 0.0
+        || fast_rate.is_nan()
+        || fast_rate <= 0.0
+        || !(0.0..=1.0).contains(&content_weight)
+    {
+        return Err(Error::new(
+            Status::InvalidArg,
+            "window_ms and fast_chars_per_second must be positive and content_weight between 0 and 1"
+                .to_string(),
+        ));
+    }
+    let size = options.shingle_size.unwrap_or(DEFAULT_SHINGLE_SIZE) as usize;
+    let burst_lines = options.burst_lines.unwrap_or(DEFAULT_BURST_LINES).max(1);
+    let max_gap_ms = options.max_gap_ms.unwrap_or(DEFAULT_MAX_GAP_MS);
+
+    let events = sorted_fields(&events);
+    let interactions = interactions(&events);
+    let evidence: Vec<Evidence> = interactions.iter().map(|i| Evidence::of(i, size)).collect();
+
+    let mut previous_edit: AHashMap<String, f64> = AHashMap::new();
+    let mut lines = Vec::new();
+    for edit in events.iter().filter(|event| is_edit(event)) {
+        let file = edit.file();
+        let previous = file
+            .as_ref()
+            .and_then(|file| previous_edit.insert(file.clone(), edit.timestamp));
+        let inserted: Vec<(Option<u32>, String)> = inserted_lines(edit)
+            .into_iter()
+            .filter(|(_, text)| !text.trim().is_empty())
+            .collect();
+        if inserted.is_empty() {
+            continue;
+        }
+
+        let chars: usize = inserted.iter().map(|(_, t)| t.trim().chars().count()).sum();
+        let elapsed = edit
+            .text(&["duration_ms", "durationMs"])
+            .and_then(|ms| ms.parse::<f64>().ok())
+            .or_else(|| previous.map(|at| edit.timestamp - at))
+            .filter(|ms| *ms <= max_gap_ms);
+        let chars_per_second = elapsed.map(|ms| chars as f64 * 1000.0 / ms.max(1.0));
+        let burst = (inserted.len() as f64 / burst_lines as f64).min(1.0);
+        let behaviour = match chars_per_second {
+            Some(rate) => (burst + (rate / fast_rate).min(1.0)) / 2.0,
+            None => burst,
+        };
+
+        // The recent response that best matches the edit as a whole
+        let text = inserted
+            .iter()
+            .map(|(_, t)| t.as_str())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let workspace = edit.workspace();
+        let best = if tokens(&text).len() < MIN_TOKENS {
+            None
+        } else {
+            let inserted = shingles(&text, size);
+            interactions
+                .iter()
+                .enumerate()
+                .filter(|(_, i)| gap_before(i, edit, workspace.as_deref(), window_ms).is_some())
+                .map(|(index, _)| (contained(&inserted, &evidence[index].shingles), index))
+                .filter(|(matched, _)| *matched > 0.0)
+                // Later interactions win ties
+                .max_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)))
+        };
+
+        for (line, text) in &inserted {
+            let content_match = match best {
+                None => 0.0,
+                Some((edit_match, _)) if tokens(text).len() < MIN_TOKENS => edit_match,
+                Some((_, index)) if evidence[index].lines.contains(&normalized(text)) => 1.0,
+                Some((_, index)) => contained(&shingles(text, size), &evidence[index].shingles),
+            };
+            let score = content_weight * content_match + (1.0 - content_weight) * behaviour;
+            let ai = score >= 0.5;
+            lines.push(LineAttribution {
+                diff_id: edit.id(),
+                file_path: file.clone(),
+                line: *line,
+                author: if ai { "ai" } else { "human" }.to_string(),
+                confidence: if ai { score } else { 1.0 - score },
+                content_match,
+                burst_lines: inserted.len() as u32,
+                chars_per_second,
+                prompt_id: best
+                    .filter(|_| ai)
+                    .and_then(|(_, index)| interactions[index].prompt_id.clone()),
+            });
+        }
+    }
+
+    let mut by_file: AHashMap<&str, (u32, u32)> = AHashMap::new();
+    let (mut ai_lines, mut human_lines) = (0, 0);
+    for line in &lines {
+        let ai = line.author == "ai";
+        if ai {
+            ai_lines += 1;
+        } else {
+            human_lines += 1;
+        }
+        if let Some(file) = &line.file_path {
+            let counts = by_file.entry(file.as_str()).or_default();
+            if ai {
+                counts.0 += 1;
+            } else {
+                counts.1 += 1;
+            }
+        }
+    }
+    let mut files: Vec<FileAttribution> = by_file
+        .into_iter()
+        .map(|(file, (ai, human))| FileAttribution {
+            file_path: file.to_string(),
+            ai_lines: ai,
+            human_lines: human,
+            ai_fraction: fraction(ai, human),
+        })
+        .collect();
+    files.sort_by(|a, b| a.file_path.cmp(&b.file_path));
+
+    Ok(AttributionReport {
+        ai_lines,
+        human_lines,
+        ai_fraction: fraction(ai_lines, human_lines),
+        files,
+        lines,
+    })
+}
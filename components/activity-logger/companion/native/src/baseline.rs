@@ -0,0 +1,191 @@
+/*!
+ * Seasonal baselines
+ * Expected values per hour-of-week (or hour-of-day, day-of-week) from historical series
+ */
+
+use crate::events::{MS_PER_DAY, MS_PER_HOUR, MS_PER_MINUTE};
+use crate::stats::{mean, median_absolute_deviation, quantile, variance};
+use ahash::AHashMap;
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+
+const DAY_NAMES: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+/// 1970-01-01 was a Thursday; shifts epoch days so Monday is day 0
+const EPOCH_WEEKDAY_OFFSET: i64 = 3;
+
+/// One observation of a metric
+#[napi(object)]
+pub struct SeriesPoint {
+    /// Milliseconds since the Unix epoch
+    pub timestamp: i64,
+    pub value: f64,
+}
+
+/// Options for compute_baseline
+#[napi(object)]
+pub struct BaselineOptions {
+    /// Local offset from UTC used to place points in slots (default: 0)
+    pub utc_offset_minutes: Option<i32>,
+    /// Count periods with no points between the first and last point as 0 (default: true)
+    pub fill_missing: Option<bool>,
+}
+
+/// Expected value for one seasonal slot
+#[napi(object)]
+pub struct BaselineSlot {
+    /// Slot index: hour of week (0 = Monday 00:00), hour of day, or day of week (0 = Monday)
+    pub slot: u32,
+    /// e.g. "Mon 09:00", "09:00", or "Mon"
+    pub label: String,
+    /// Median of the slot's historical values
+    pub expected: f64,
+    pub mean: f64,
+    pub std_dev: f64,
+    /// Median absolute deviation, for robust anomaly thresholds
+    pub mad: f64,
+    pub p10: f64,
+    pub p90: f64,
+    /// Historical periods observed in this slot
+    pub samples: u32,
+}
+
+/// Result of compute_baseline
+#[napi(object)]
+pub struct Baseline {
+    pub seasonality: String,
+    /// One entry per slot in slot order; slots with no history have zero samples
+    pub slots: Vec<BaselineSlot>,
+}
+
+#[derive(Clone, Copy)]
+enum Seasonality {
+    HourOfWeek,
+    HourOfDay,
+    DayOfWeek,
+}
+
+impl Seasonality {
+    fn parse(value: &str) -> Result<Self> {
+        match value {
+            "hour_of_week" => Ok(Seasonality::HourOfWeek),
+            "hour_of_day" => Ok(Seasonality::HourOfDay),
+            "day_of_week" => Ok(Seasonality::DayOfWeek),
+            other => Err(Error::new(
+                Status::InvalidArg,
+                format!(
+                    "Unknown seasonality '{}', expected hour_of_week, hour_of_day, or day_of_week",
+                    other
+                ),
+            )),
+        }
+    }
+
+    /// Length of the period whose totals are compared across seasons
+    fn period_ms(&self) -> i64 {
+        match self {
+            Seasonality::DayOfWeek => MS_PER_DAY,
+            _ => MS_PER_HOUR,
+        }
+    }
+
+    fn slot_count(&self) -> u32 {
+        match self {
+            Seasonality::HourOfWeek => 168,
+            Seasonality::HourOfDay => 24,
+            Seasonality::DayOfWeek => 7,
+        }
+    }
+
+    /// Slot of the period with the given index (periods counted from the local epoch)
+    fn slot(&self, period: i64) -> u32 {
+        let slot = match self {
+            Seasonality::HourOfWeek => (period + EPOCH_WEEKDAY_OFFSET * 24).rem_euclid(168),
+            Seasonality::HourOfDay => period.rem_euclid(24),
+            Seasonality::DayOfWeek => (period + EPOCH_WEEKDAY_OFFSET).rem_euclid(7),
+        };
+        slot as u32
+    }
+
+    fn label(&self, slot: u32) -> String {
+        match self {
+            Seasonality::HourOfWeek => {
+                format!("{} {:02}:00", DAY_NAMES[(slot / 24) as usize], slot % 24)
+            }
+            Seasonality::HourOfDay => format!("{:02}:00", slot),
+            Seasonality::DayOfWeek => DAY_NAMES[slot as usize].to_string(),
+        }
+    }
+}
+
+/**
+ * Compute a seasonal baseline from a historical series
+ *
+ * Points are summed per hour (per day for "day_of_week"), then the totals
+ * for each slot are summarized across weeks. The median is the expected
+ * value; MAD and percentiles give the anomaly detector and "typical day"
+ * chart overlays a band to compare against.
+ *
+ * @param series - Historical observations, e.g. one point per event with value 1
+ * @param seasonality - "hour_of_week", "hour_of_day", or "day_of_week"
+ * @returns Expected value and spread per slot
+ */
+#[napi]
+pub fn compute_baseline(
+    series: Vec<SeriesPoint>,
+    seasonality: String,
+    options: Option<BaselineOptions>,
+) -> Result<Baseline> {
+    let season = Seasonality::parse(&seasonality)?;
+    let offset_ms = options
+        .as_ref()
+        .and_then(|o| o.utc_offset_minutes)
+        .unwrap_or(0) as i64
+        * MS_PER_MINUTE;
+    let fill_missing = options
+        .as_ref()
+        .and_then(|o| o.fill_missing)
+        .unwrap_or(true);
+    let period_ms = season.period_ms();
+
+    let mut totals: AHashMap<i64, f64> = AHashMap::new();
+    for point in series.iter().filter(|p| p.value.is_finite()) {
+        let period = (point.timestamp + offset_ms).div_euclid(period_ms);
+        *totals.entry(period).or_insert(0.0) += point.value;
+    }
+    if fill_missing {
+        if let (Some(first), Some(last)) =
+            (totals.keys().min().copied(), totals.keys().max().copied())
+        {
+            for period in first..=last {
+                totals.entry(period).or_insert(0.0);
+            }
+        }
+    }
+
+    let mut by_slot: Vec<Vec<f64>> = vec![Vec::new(); season.slot_count() as usize];
+    for (period, total) in totals {
+        by_slot[season.slot(period) as usize].push(total);
+    }
+
+    let slots = by_slot
+        .into_iter()
+        .enumerate()
+        .map(|(slot, mut values)| {
+            values.sort_by(|a, b| a.total_cmp(b));
+            let slot = slot as u32;
+            BaselineSlot {
+                slot,
+                label: season.label(slot),
+                expected: quantile(&values, 0.5),
+                mean: mean(&values),
+                std_dev: variance(&values).sqrt(),
+                mad: median_absolute_deviation(&values),
+                p10: quantile(&values, 0.1),
+                p90: quantile(&values, 0.9),
+                samples: values.len() as u32,
+            }
+        })
+        .collect();
+
+    Ok(Baseline { seasonality, slots })
+}
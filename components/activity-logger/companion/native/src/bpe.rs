@@ -0,0 +1,208 @@
+/*!
+ * Byte-pair encoding compatible with OpenAI's tiktoken
+ *
+ * Loads a `.tiktoken` rank file (one `base64-token rank` pair per line, the
+ * format tiktoken downloads and caches) and encodes text exactly as
+ * tiktoken's `encode_ordinary` does: split with the encoding's pre-tokenizer
+ * pattern, then merge each piece's bytes by lowest rank.
+ */
+
+use ahash::AHashMap;
+use regex::Regex;
+
+/// Encodings whose pre-tokenizer patterns are known
+pub(crate) const ENCODINGS: &[&str] = &["o200k_base", "cl100k_base", "p50k_base", "r50k_base"];
+
+/**
+ * Pre-tokenizer pattern for `encoding`
+ *
+ * tiktoken's patterns end in `\s+(?!\S)|\s+`, which needs lookahead; here
+ * both become `\s+` and the lookahead is applied by hand in `split`.
+ */
+fn pattern(encoding: &str) -> Option<&'static str> {
+    let pattern = match encoding {
+        "o200k_base" => {
+            r"[^\r\n\p{L}\p{N}]?[\p{Lu}\p{Lt}\p{Lm}\p{Lo}\p{M}]*[\p{Ll}\p{Lm}\p{Lo}\p{M}]+(?i:'s|'t|'re|'ve|'m|'ll|'d)?|[^\r\n\p{L}\p{N}]?[\p{Lu}\p{Lt}\p{Lm}\p{Lo}\p{M}]+[\p{Ll}\p{Lm}\p{Lo}\p{M}]*(?i:'s|'t|'re|'ve|'m|'ll|'d)?|\p{N}{1,3}| ?[^\s\p{L}\p{N}]+[\r\n/]*|\s*[\r\n]+|\s+"
+        }
+        "cl100k_base" => {
+            r"(?i:'s|'t|'re|'ve|'m|'ll|'d)|[^\r\n\p{L}\p{N}]?\p{L}+|\p{N}{1,3}| ?[^\s\p{L}\p{N}]+[\r\n]*|\s*[\r\n]+|\s+"
+        }
+        "p50k_base" | "r50k_base" => {
+            r"'s|'t|'re|'ve|'m|'ll|'d| ?\p{L}+| ?\p{N}+| ?[^\s\p{L}\p{N}]+|\s+"
+        }
+        _ => return None,
+    };
+    Some(pattern)
+}
+
+/// A loaded encoding
+pub(crate) struct Encoding {
+    pub(crate) name: String,
+    splitter: Regex,
+    ranks: AHashMap<Vec<u8>, u32>,
+    tokens: AHashMap<u32, Vec<u8>>,
+}
+
+impl Encoding {
+    /// Parse a `.tiktoken` rank file for `name`
+    pub(crate) fn parse(name: &str, ranks_file: &str) -> Result<Encoding, String> {
+        let pattern = pattern(name).ok_or_else(|| {
+            format!(
+                "Unknown encoding '{}' (expected {})",
+                name,
+                ENCODINGS.join(", ")
+            )
+        })?;
+        let mut ranks = AHashMap::new();
+        let mut tokens = AHashMap::new();
+        for (number, line) in ranks_file.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let invalid = || format!("Invalid rank file line {}: '{}'", number + 1, line);
+            let (token, rank) = line.trim().split_once(' ').ok_or_else(invalid)?;
+            let token = base64_decode(token).ok_or_else(invalid)?;
+            let rank: u32 = rank.parse().map_err(|_| invalid())?;
+            tokens.insert(rank, token.clone());
+            ranks.insert(token, rank);
+        }
+        if ranks.is_empty() {
+            return Err("Rank file has no tokens".to_string());
+        }
+        Ok(Encoding {
+            name: name.to_string(),
+            splitter: Regex::new(pattern).unwrap(),
+            ranks,
+            tokens,
+        })
+    }
+
+    pub(crate) fn vocabulary_size(&self) -> usize {
+        self.ranks.len()
+    }
+
+    /// Token ids of `text`; special tokens such as `<|endoftext|>` are encoded as ordinary text
+    pub(crate) fn encode(&self, text: &str) -> Result<Vec<u32>, String> {
+        let mut out = Vec::new();
+        for piece in self.split(text) {
+            let bytes = piece.as_bytes();
+            match self.ranks.get(bytes) {
+                Some(&rank) => out.push(rank),
+                None => self.merge(bytes, &mut out)?,
+            }
+        }
+        Ok(out)
+    }
+
+    /// Number of tokens in `text`
+    pub(crate) fn count(&self, text: &str) -> Result<usize, String> {
+        self.encode(text).map(|tokens| tokens.len())
+    }
+
+    /// Text of `tokens`; byte sequences that aren't valid UTF-8 on their own are replaced with U+FFFD
+    pub(crate) fn decode(&self, tokens: &[u32]) -> Result<String, String> {
+        let mut bytes = Vec::new();
+        for token in tokens {
+            let piece = self
+                .tokens
+                .get(token)
+                .ok_or_else(|| format!("Token {} is not in {}", token, self.name))?;
+            bytes.extend_from_slice(piece);
+        }
+        Ok(String::from_utf8_lossy(&bytes).into_owned())
+    }
+
+    /// Pieces of `text` per the pre-tokenizer, with `\s+(?!\S)` applied
+    fn split<'a>(&self, text: &'a str) -> Vec<&'a str> {
+        let mut pieces = Vec::new();
+        let mut start = 0;
+        while let Some(found) = self.splitter.find_at(text, start) {
+            let mut end = found.end();
+            let piece = found.as_str();
+            // A whitespace run before a non-space char leaves its last char to that next piece
+            let followed_by_text = text[end..].starts_with(|c: char| !c.is_whitespace());
+            if followed_by_text
+                && piece.chars().all(char::is_whitespace)
+                && !piece.ends_with(['\r', '\n'])
+                && piece.chars().count() > 1
+            {
+                end -= piece.chars().next_back().unwrap().len_utf8();
+            }
+            pieces.push(&text[found.start()..end]);
+            start = end;
+        }
+        pieces
+    }
+
+    /// Merge the bytes of one piece by rank, as tiktoken's byte_pair_merge does
+    fn merge(&self, piece: &[u8], out: &mut Vec<u32>) -> Result<(), String> {
+        // parts[i] = (start offset, rank of the pair starting there)
+        let rank_of = |parts: &[(usize, u32)], i: usize| -> u32 {
+            if i + 3 < parts.len() {
+                self.ranks
+                    .get(&piece[parts[i].0..parts[i + 3].0])
+                    .copied()
+                    .unwrap_or(u32::MAX)
+            } else {
+                u32::MAX
+            }
+        };
+        let mut parts: Vec<(usize, u32)> = (0..=piece.len()).map(|i| (i, u32::MAX)).collect();
+        for i in 0..parts.len().saturating_sub(2) {
+            parts[i].1 = self
+                .ranks
+                .get(&piece[parts[i].0..parts[i + 2].0])
+                .copied()
+                .unwrap_or(u32::MAX);
+        }
+        while let Some((i, _)) = parts[..parts.len() - 1]
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, rank))| *rank != u32::MAX)
+            .min_by_key(|(_, (_, rank))| *rank)
+        {
+            parts[i].1 = rank_of(&parts, i);
+            if i > 0 {
+                parts[i - 1].1 = rank_of(&parts, i - 1);
+            }
+            parts.remove(i + 1);
+        }
+        for window in parts.windows(2) {
+            let bytes = &piece[window[0].0..window[1].0];
+            let rank = self.ranks.get(bytes).ok_or_else(|| {
+                format!(
+                    "Rank file for {} has no token for byte sequence {:?}",
+                    self.name, bytes
+                )
+            })?;
+            out.push(*rank);
+        }
+        Ok(())
+    }
+}
+
+/// Standard base64 with optional padding
+fn base64_decode(text: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(text.len() * 3 / 4);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for byte in text.bytes() {
+        let value = match byte {
+            b'A'..=b'Z' => byte - b'A',
+            b'a'..=b'z' => byte - b'a' + 26,
+            b'0'..=b'9' => byte - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            b'=' => break,
+            _ => return None,
+        };
+        buffer = (buffer << 6) | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    Some(out)
+}
@@ -0,0 +1,182 @@
+/*!
+ * Input budgets
+ * Per-call caps on how much text a diff, stats, or search call will process
+ */
+
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use std::borrow::Cow;
+
+/// Limits on the text a call processes
+#[napi(object)]
+#[derive(Clone)]
+pub struct InputBudget {
+    /// Most lines processed per input
+    pub max_lines: Option<u32>,
+    /// Most UTF-8 bytes processed per input
+    pub max_bytes: Option<u32>,
+    /// "truncate" keeps the start (default), "sample" keeps evenly spaced lines, "error" rejects the call
+    pub on_exceed: Option<String>,
+}
+
+/// How an input over budget was cut down
+#[napi(object)]
+#[derive(Clone)]
+pub struct Truncation {
+    /// Which input was cut: "content", "before", or "after"
+    pub input: String,
+    /// "truncate" or "sample"
+    pub mode: String,
+    pub original_lines: i64,
+    pub original_bytes: i64,
+    pub kept_lines: i64,
+    pub kept_bytes: i64,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum OnExceed {
+    Truncate,
+    Sample,
+    Error,
+}
+
+/// A validated InputBudget
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct Budget {
+    max_lines: Option<usize>,
+    max_bytes: Option<usize>,
+    on_exceed: OnExceed,
+}
+
+impl Budget {
+    pub(crate) fn parse(budget: Option<&InputBudget>) -> Result<Option<Budget>> {
+        let Some(budget) = budget else {
+            return Ok(None);
+        };
+        let on_exceed = match budget.on_exceed.as_deref().unwrap_or("truncate") {
+            "truncate" => OnExceed::Truncate,
+            "sample" => OnExceed::Sample,
+            "error" => OnExceed::Error,
+            other => {
+                return Err(Error::new(
+                    Status::InvalidArg,
+                    format!(
+                        "Unknown onExceed '{}' (expected truncate, sample, or error)",
+                        other
+                    ),
+                ))
+            }
+        };
+        Ok(Some(Budget {
+            max_lines: budget.max_lines.map(|n| n as usize),
+            max_bytes: budget.max_bytes.map(|n| n as usize),
+            on_exceed,
+        }))
+    }
+
+    /**
+     * Fit `text` within the budget
+     *
+     * Returns the text unchanged (borrowed) when it fits. Otherwise cuts at line
+     * boundaries, falling back to a character boundary when not even one line
+     * fits the byte limit, and reports what was kept as `input`.
+     */
+    pub(crate) fn apply<'a>(
+        &self,
+        text: &'a str,
+        input: &str,
+    ) -> Result<(Cow<'a, str>, Option<Truncation>)> {
+        let lines: Vec<&str> = text.split_inclusive('\n').collect();
+        let max_lines = self.max_lines.unwrap_or(usize::MAX);
+        let max_bytes = self.max_bytes.unwrap_or(usize::MAX);
+        if lines.len() <= max_lines && text.len() <= max_bytes {
+            return Ok((Cow::Borrowed(text), None));
+        }
+
+        let kept = match self.on_exceed {
+            OnExceed::Error => {
+                let over = if lines.len() > max_lines {
+                    format!("{} lines, over the limit of {}", lines.len(), max_lines)
+                } else {
+                    format!("{} bytes, over the limit of {}", text.len(), max_bytes)
+                };
+                return Err(Error::new(
+                    Status::InvalidArg,
+                    format!("Input {} has {}", input, over),
+                ));
+            }
+            OnExceed::Truncate => fit(lines.iter().copied(), max_lines, max_bytes),
+            OnExceed::Sample => {
+                // Aim for as many lines as the byte limit allows at the average line length
+                let average = (text.len() / lines.len().max(1)).max(1);
+                let target = max_lines.min(max_bytes / average).max(1);
+                let step = lines.len() as f64 / target as f64;
+                let sampled = (0..target).map(|i| lines[(i as f64 * step) as usize]);
+                fit(sampled, max_lines, max_bytes)
+            }
+        };
+
+        let kept_text = if kept.is_empty() {
+            // A single line longer than the byte limit, e.g. minified code
+            let mut end = max_bytes.min(text.len());
+            while !text.is_char_boundary(end) {
+                end -= 1;
+            }
+            text[..end].to_string()
+        } else {
+            kept.concat()
+        };
+        let truncation = Truncation {
+            input: input.to_string(),
+            mode: match self.on_exceed {
+                OnExceed::Sample => "sample",
+                _ => "truncate",
+            }
+            .to_string(),
+            original_lines: lines.len() as i64,
+            original_bytes: text.len() as i64,
+            kept_lines: kept_text.split_inclusive('\n').count() as i64,
+            kept_bytes: kept_text.len() as i64,
+        };
+        Ok((Cow::Owned(kept_text), Some(truncation)))
+    }
+}
+
+/**
+ * Fit an owned input within `budget`, recording any cut in `truncations`
+ * Returns `text` itself, without copying, when there is no budget or it fits
+ */
+pub(crate) fn limit(
+    budget: Option<&Budget>,
+    text: String,
+    input: &str,
+    truncations: &mut Vec<Truncation>,
+) -> Result<String> {
+    let Some(budget) = budget else {
+        return Ok(text);
+    };
+    let (kept, truncation) = budget.apply(&text, input)?;
+    match truncation.map(|truncation| (kept.into_owned(), truncation)) {
+        Some((kept, truncation)) => {
+            truncations.push(truncation);
+            Ok(kept)
+        }
+        None => Ok(text),
+    }
+}
+
+/// Leading lines of `lines` within both limits
+fn fit<'a>(
+    lines: impl Iterator<Item = &'a str>,
+    max_lines: usize,
+    max_bytes: usize,
+) -> Vec<&'a str> {
+    let mut bytes = 0;
+    lines
+        .take(max_lines)
+        .take_while(|line| {
+            bytes += line.len();
+            bytes <= max_bytes
+        })
+        .collect()
+}
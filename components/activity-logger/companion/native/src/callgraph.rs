@@ -0,0 +1,133 @@
+/*!
+ * Call graph extraction
+ * Caller → callee edges by symbol name within one file
+ */
+
+use crate::comments::strip_comments_and_strings;
+use crate::functions::{find_functions, line_of, line_starts, NOT_FUNCTION_NAMES};
+use crate::symbols::function_symbols;
+use ahash::{AHashMap, AHashSet};
+use napi_derive::napi;
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// Caller name for calls made outside any function
+pub const TOP_LEVEL_CALLER: &str = "<module>";
+
+/// A call found in source: (enclosing function, called name, 1-based line)
+pub(crate) type CallSite = (Option<String>, String, u32);
+
+/// Calls from one function to another
+#[napi(object)]
+pub struct CallEdge {
+    /// Calling function, or "<module>" for top-level code
+    pub caller: String,
+    pub callee: String,
+    /// Number of call sites
+    pub calls: u32,
+    /// 1-based line of the first call site
+    pub line: u32,
+    /// The callee is defined in this file
+    pub internal: bool,
+}
+
+/// Result of extract_call_graph
+#[napi(object)]
+pub struct CallGraph {
+    /// Functions and methods defined in the file, in source order
+    pub functions: Vec<String>,
+    /// Edges in order of their first call site
+    pub edges: Vec<CallEdge>,
+}
+
+/// Call sites from the heuristic function finder, for builds and languages without a grammar
+fn regex_call_sites(content: &str, language: &str) -> Vec<CallSite> {
+    static CALL: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\b([A-Za-z_]\w*)\s*\(").unwrap());
+    let code = strip_comments_and_strings(content, language);
+    let spans = find_functions(&code, language);
+    let starts = line_starts(&code);
+
+    CALL.captures_iter(&code)
+        .filter_map(|caps| {
+            let name = caps.get(1)?;
+            let callee = name.as_str();
+            let line = line_of(&starts, name.start());
+            // The definition's own name on its first line is not a call
+            let defines = spans.iter().any(|span| {
+                span.start_line == line && span.name == callee && name.start() < span.end
+            });
+            let before = code[..name.start()].trim_end();
+            if NOT_FUNCTION_NAMES.contains(&callee)
+                || defines
+                || ["fn", "def", "func", "function", "class", "new"]
+                    .iter()
+                    .any(|keyword| before.ends_with(keyword))
+            {
+                return None;
+            }
+            let caller = spans
+                .iter()
+                .filter(|span| span.start <= name.start() && name.start() < span.end)
+                .max_by_key(|span| span.start)
+                .map(|span| span.name.clone());
+            Some((caller, callee.to_string(), line))
+        })
+        .collect()
+}
+
+/**
+ * Extract the call graph of a file
+ *
+ * Returns caller → callee edges by symbol name, so the functions an AI edit
+ * rippled through can be followed from the edited function. With the
+ * `tree-sitter` feature, calls in javascript, typescript, python, rust, and
+ * go are found with syntax queries (plain calls, method calls, and
+ * constructors); other languages use a heuristic scan for `name(`. Callees
+ * are matched by name only, so same-named methods of different types share
+ * a node.
+ *
+ * @param content - Source code
+ * @param language - Language name as returned by detect_language
+ * @returns Defined functions and caller → callee edges
+ */
+#[napi]
+pub fn extract_call_graph(content: String, language: String) -> CallGraph {
+    let mut functions: Vec<String> = Vec::new();
+    let mut defined = AHashSet::new();
+    for symbol in function_symbols(&content, &language) {
+        if defined.insert(symbol.name.clone()) {
+            functions.push(symbol.name);
+        }
+    }
+
+    #[cfg(feature = "tree-sitter")]
+    let sites = crate::syntax::call_sites(&content, &language)
+        .unwrap_or_else(|| regex_call_sites(&content, &language));
+    #[cfg(not(feature = "tree-sitter"))]
+    let sites = regex_call_sites(&content, &language);
+
+    let mut edges: Vec<CallEdge> = Vec::new();
+    let mut index: AHashMap<(String, String), usize> = AHashMap::new();
+    for (caller, callee, line) in sites {
+        let caller = caller.unwrap_or_else(|| TOP_LEVEL_CALLER.to_string());
+        match index.get(&(caller.clone(), callee.clone())) {
+            Some(&i) => {
+                edges[i].calls += 1;
+                edges[i].line = edges[i].line.min(line);
+            }
+            None => {
+                index.insert((caller.clone(), callee.clone()), edges.len());
+                edges.push(CallEdge {
+                    internal: defined.contains(&callee),
+                    caller,
+                    callee,
+                    calls: 1,
+                    line,
+                });
+            }
+        }
+    }
+    edges.sort_by_key(|edge| edge.line);
+
+    CallGraph { functions, edges }
+}
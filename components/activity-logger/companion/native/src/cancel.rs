@@ -0,0 +1,73 @@
+/*!
+ * Cooperative cancellation for async and batch work
+ *
+ * An AbortSignal passed from JS flips a shared flag that worker threads
+ * check between units of work, so aborted diffs stop consuming CPU.
+ */
+
+use napi::bindgen_prelude::*;
+use napi::{JsFunction, JsObject};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Cancellation flag shared between the JS thread and worker threads
+#[derive(Clone, Default)]
+pub struct CancelFlag(Arc<AtomicBool>);
+
+impl CancelFlag {
+    /**
+     * Build a flag that is raised when the given AbortSignal fires
+     *
+     * A signal that is already aborted yields a raised flag. The same signal
+     * may be shared by any number of calls.
+     */
+    pub fn from_signal(env: &Env, signal: Option<JsObject>) -> Result<Self> {
+        let flag = CancelFlag::default();
+        let Some(signal) = signal else {
+            return Ok(flag);
+        };
+
+        if signal.get_named_property::<bool>("aborted")? {
+            flag.cancel();
+            return Ok(flag);
+        }
+
+        let listener_flag = flag.clone();
+        let listener = env.create_function_from_closure("onAbort", move |ctx| {
+            listener_flag.cancel();
+            ctx.env.get_undefined()
+        })?;
+        let add_event_listener: JsFunction = signal.get_named_property("addEventListener")?;
+        add_event_listener.call(
+            Some(&signal),
+            &[
+                env.create_string("abort")?.into_unknown(),
+                listener.into_unknown(),
+            ],
+        )?;
+
+        Ok(flag)
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Err(AbortError) once cancelled, for use with `?` between units of work
+    pub fn check(&self) -> Result<()> {
+        if self.is_cancelled() {
+            Err(abort_error())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Same error the napi runtime uses when an AbortSignal rejects a task
+pub fn abort_error() -> Error {
+    Error::new(Status::Cancelled, "AbortError".to_string())
+}
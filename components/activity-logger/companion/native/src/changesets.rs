@@ -0,0 +1,113 @@
+/*!
+ * Atomic multi-file edit grouping
+ *
+ * A single composer/agent operation writes many files within a few hundred
+ * milliseconds. Grouping those writes into one change-set lets the timeline
+ * render them as a single AI action.
+ */
+
+use crate::events::{sorted_by_time, ActivityEvent};
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+
+const DEFAULT_WINDOW_MS: i64 = 1_500;
+
+/// One logical change spanning one or more file edits
+#[napi(object)]
+pub struct ChangeSet {
+    /// Id of the first edit in the set, or `changeset-<start>` when it has none
+    pub id: String,
+    pub start: i64,
+    pub end: i64,
+    pub event_ids: Vec<String>,
+    pub files: Vec<String>,
+    pub edit_count: u32,
+    pub lines_added: i64,
+    pub lines_removed: i64,
+    /// True when every edit in the set was AI-applied
+    pub ai_generated: bool,
+    pub is_multi_file: bool,
+}
+
+/**
+ * Cluster near-simultaneous file edits into change-sets
+ *
+ * Consecutive edits join the same set while each follows the previous one
+ * within `window_ms`, stays in the same session, and has the same AI/human
+ * origin. Every edit event belongs to exactly one change-set; non-edit
+ * events (no file path) are ignored.
+ *
+ * @param events - Activity events in any order
+ * @param window_ms - Maximum gap between edits of one operation (default: 1500)
+ * @returns Change-sets in chronological order
+ */
+#[napi]
+pub fn group_atomic_edits(
+    events: Vec<ActivityEvent>,
+    window_ms: Option<i64>,
+) -> Result<Vec<ChangeSet>> {
+    let window_ms = window_ms.unwrap_or(DEFAULT_WINDOW_MS);
+    let edits: Vec<&ActivityEvent> = sorted_by_time(&events)
+        .into_iter()
+        .filter(|e| e.file_path.is_some())
+        .collect();
+
+    let mut change_sets = Vec::new();
+    let mut group: Vec<&ActivityEvent> = Vec::new();
+
+    for edit in edits {
+        if let Some(last) = group.last() {
+            let joins = edit.timestamp - last.timestamp <= window_ms
+                && edit.session_id == last.session_id
+                && is_ai(edit) == is_ai(last);
+            if !joins {
+                change_sets.push(build_change_set(&group));
+                group.clear();
+            }
+        }
+        group.push(edit);
+    }
+    if !group.is_empty() {
+        change_sets.push(build_change_set(&group));
+    }
+
+    Ok(change_sets)
+}
+
+fn is_ai(event: &ActivityEvent) -> bool {
+    event.ai_generated == Some(true)
+}
+
+fn build_change_set(group: &[&ActivityEvent]) -> ChangeSet {
+    let start = group[0].timestamp;
+    let end = group[group.len() - 1].timestamp;
+
+    let mut files: Vec<String> = Vec::new();
+    for path in group.iter().filter_map(|e| e.file_path.as_ref()) {
+        if !files.contains(path) {
+            files.push(path.clone());
+        }
+    }
+
+    ChangeSet {
+        id: group[0]
+            .id
+            .clone()
+            .unwrap_or_else(|| format!("changeset-{}", start)),
+        start,
+        end,
+        event_ids: group.iter().filter_map(|e| e.id.clone()).collect(),
+        is_multi_file: files.len() > 1,
+        files,
+        edit_count: group.len() as u32,
+        lines_added: group
+            .iter()
+            .map(|e| e.lines_added.unwrap_or(0) as i64)
+            .sum(),
+        lines_removed: group
+            .iter()
+            .map(|e| e.lines_removed.unwrap_or(0) as i64)
+            .sum(),
+        ai_generated: group.iter().all(|e| is_ai(e)),
+    }
+}
@@ -0,0 +1,249 @@
+/*!
+ * Content-based language classifier
+ * Guesses a language from text alone, for extensionless scripts and snippets pasted into chat
+ *
+ * Each language has a table of weighted features: keywords, operators such
+ * as `:=` or `<-`, line-leading tokens such as `^FROM` or `^key:`, and a few
+ * normalized shapes (`$var`, `<div`, `px`). A text scores, per language, the
+ * weight of every feature it contains times ln(1 + occurrences), so repeated
+ * boilerplate saturates instead of swamping distinctive tokens. Scores are
+ * turned into probabilities with a softmax against a constant "something
+ * else" term, then scaled down when the evidence is thin, so a short snippet
+ * never reports the confidence of a full file.
+ */
+
+use crate::language::{LanguageAlternate, LanguageGuess};
+use ahash::AHashMap;
+use napi_derive::napi;
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// Feature weights per language as `token:weight`. Tokens starting with `^` only count at the start of a line.
+const MODEL: &[(&str, &str)] = &[
+    ("python", "def:3 elif:3 self:1.5 None:2 True:1.5 False:1.5 import:1 from:0.5 lambda:2 print:1 __name__:3 __init__:3 pass:2 yield:1 with:0.5 not:0.5 and:0.5 or:0.5 is:0.5 except:3 raise:2 ^@:0.5 range:0.5 len:1 __main__:3"),
+    ("javascript", "function:2 const:1.5 let:1 var:1.5 =>:1.5 ===:3 !==:3 console:3 require:2 module:1 exports:2 document:2 window:2 undefined:2 this:0.5 typeof:2 prototype:2 JSON:1 null:0.5 async:0.5 await:0.5 Promise:1.5 ^import:0.5 ^export:0.5"),
+    ("typescript", "interface:2.5 type:1 implements:1 readonly:2 string:1.5 number:2 boolean:2 any:1.5 keyof:3 never:2 Record:2 Partial:3 namespace:1 enum:1 const:1 =>:1 ===:2 console:1.5 let:0.8 ^import:0.5 ^export:1 private:0.5 void:0.5"),
+    ("rust", "fn:3 let:1 mut:3 impl:3 :::1 pub:1.5 use:0.5 crate:3 Self:1.5 match:1 Some:2 None:1 Ok:1.5 Err:1.5 unwrap:3 Vec:2 String:0.5 println:2 ->:1 #[:3 enum:1 trait:2 struct:1 u32:2 usize:3 i32:1 u8:2 macro_rules:3 mod:1.5"),
+    ("go", "func:3 package:2 :=:2.5 chan:3 defer:3 go:1 fmt:2.5 err:1.5 nil:2 iota:3 struct:1 interface:0.5 range:1 Println:1 Printf:1 Sprintf:2 ^import:0.5 string:0.3 int:0.3"),
+    ("java", "public:1.5 private:1 protected:1 class:1 static:1 void:1.5 System:3 println:1 String:1 extends:1.5 implements:1.5 final:1.5 throws:3 package:1 new:0.5 @Override:3 ArrayList:2 boolean:1 int:0.5 this:0.5 ^import:0.5"),
+    ("c", "#include:3 int:1 char:1.5 void:1 printf:3 malloc:3 free:1.5 sizeof:2 struct:1.5 unsigned:2 NULL:2 typedef:2 ->:1 #define:2 #ifdef:2 #endif:2 stdio:3 stdlib:3 return:0.3"),
+    ("cpp", "#include:3 std:3 :::1.5 cout:3 endl:3 namespace:2 template:3 typename:3 class:1 public:1 virtual:2 auto:1 nullptr:3 vector:2 override:1 unique_ptr:3 size_t:1 int:0.5 #define:1"),
+    ("csharp", "using:2 namespace:2 public:1 class:1 void:1 string:1 var:1 Console:3 WriteLine:3 get:1.5 set:1 Task:2 override:1 readonly:1 foreach:2 internal:2 sealed:2 partial:1.5 async:0.5 =>:0.5"),
+    ("ruby", "def:2 end:3 puts:3 require:1 unless:3 elsif:3 attr_accessor:3 attr_reader:3 do:1 module:1 nil:2 yield:1 each:2 ||=:2 initialize:3 @:0.5 =>:0.5 class:0.5"),
+    ("php", "<?php:6 $var:2 $this:3 echo:1.5 function:1 array:2 ->:1.5 namespace:1 foreach:1.5 isset:3 public:0.5 :::0.5"),
+    ("shell", "echo:2 fi:3 then:2 esac:3 done:2 do:1 elif:1 export:2 local:1 $var:1.5 ${:2.5 $(:2.5 [[:2 ]]:2 sudo:2 apt-get:3 grep:2 awk:1 sed:2 cd:1.5 mkdir:2 rm:1.5 chmod:3 curl:1.5 exit:1 source:1 &&:0.5 ^echo:1 ^cd:1 ^export:1 ^sudo:1 ^if:0.3"),
+    ("sql", "~select:3 ~from:1 ~where:2 ~insert:2 ~into:2 ~values:2 ~update:1 ~delete:1 ~create:1 ~table:2 ~join:2 ~inner:2 ~group:1 ~order:1 ~by:1.5 ~primary:2 ~key:1 ~null:0.5 ~varchar:3 ~integer:1 ~alter:2 ~drop:1 ~index:1 ~distinct:2 ~having:3 ~foreign:2 ~references:2 ~on:0.5 ~as:0.3 ~count:1"),
+    ("kotlin", "fun:3 val:2 var:1 println:1 data:1 object:1 companion:3 when:1 override:1 suspend:3 lateinit:3 listOf:3 mutableListOf:3 it:1 ?::2 class:0.5"),
+    ("swift", "func:2 let:1 var:1 guard:3 struct:1 protocol:2 extension:2 print:1 UIKit:3 SwiftUI:3 Foundation:2 init:1 nil:1 some:1 @State:3 ^import:0.5"),
+    ("lua", "local:2 function:1 end:2 then:1.5 elseif:3 nil:1.5 require:1 pairs:3 ipairs:3 ..:2 ~=:3 print:0.5"),
+    ("perl", "my:3 sub:2 use:1 strict:3 warnings:2 $var:1 foreach:1 print:1 =~:3 qw:3 chomp:3 unless:1 elsif:2"),
+    ("r", "<-:3 library:3 function:1 TRUE:2 FALSE:2 NULL:1 ggplot:3 %>%:3 data:0.5 frame:1"),
+    ("powershell", "Write-Host:3 Write-Output:3 Get-ChildItem:3 Set-Location:3 ForEach-Object:3 Where-Object:3 $_:3 $true:3 $false:3 $null:3 param:2 $var:1 eq:1 ne:1 function:0.5"),
+    ("dockerfile", "^FROM:3 ^RUN:3 ^COPY:2 ^WORKDIR:3 ^ENV:1.5 ^EXPOSE:3 ^CMD:2 ^ENTRYPOINT:3 ^ARG:1.5 ^ADD:1.5"),
+    ("makefile", "PHONY:4 $(:1 CC:1 CFLAGS:3 LDFLAGS:3 $@:3 $<:3 ^\t:0.5 ^all::2"),
+    ("yaml", "^key::1.5 ^-:1 ---:2 true:0.3 false:0.3"),
+    ("toml", "^[:1.5 ^key=:1 [[:1 ]]:1 true:0.3"),
+    ("json", "\"::2 ^{:1 ^[:0.5 true:0.5 false:0.5 null:0.5"),
+    ("html", "<!DOCTYPE:5 <html:3 <head:2 <body:2 <div:3 <span:2 <p:1 <a:1 <script:1.5 <meta:3 <link:2 <ul:2 <li:2 href:2 </:1"),
+    ("xml", "<?xml:6 </:1 />:1 xmlns:3"),
+    ("css", "px:2 em:1 rem:2 margin:2 padding:2 color:1.5 display:2 background:2 font-size:3 border:2 width:1 height:1 position:1 flex:1.5 @media:3 @import:1"),
+    ("markdown", "^#:1 ^##:2 ^###:2 ^-:0.5 ^*:0.5 ^>:1 ```:3 ](:2 **:1.5"),
+];
+
+/// Below this score there is too little to go on and the text is "unknown"
+const MIN_SCORE: f64 = 2.0;
+/// Score at which evidence counts as substantial; confidence is scaled by 1 - e^(-score / EVIDENCE_SCALE)
+const EVIDENCE_SCALE: f64 = 3.0;
+
+struct Model {
+    languages: Vec<&'static str>,
+    /// Feature -> (language index, weight)
+    features: AHashMap<&'static str, Vec<(usize, f64)>>,
+    /// Case-insensitive features, keyed in lowercase
+    folded: AHashMap<&'static str, Vec<(usize, f64)>>,
+}
+
+static MODEL_TABLES: LazyLock<Model> = LazyLock::new(|| {
+    let mut model = Model {
+        languages: Vec::new(),
+        features: AHashMap::new(),
+        folded: AHashMap::new(),
+    };
+    for (index, (language, features)) in MODEL.iter().enumerate() {
+        model.languages.push(language);
+        for feature in features.split(' ') {
+            let (token, weight) = feature.rsplit_once(':').unwrap();
+            let weight: f64 = weight.parse().unwrap();
+            match token
+                .strip_prefix('~')
+                .filter(|folded| folded.starts_with(char::is_alphabetic))
+            {
+                Some(folded) => model
+                    .folded
+                    .entry(folded)
+                    .or_default()
+                    .push((index, weight)),
+                None => model
+                    .features
+                    .entry(token)
+                    .or_default()
+                    .push((index, weight)),
+            }
+        }
+    }
+    model
+});
+
+static TOKEN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"<\?php|<\?xml|<!DOCTYPE|</?[A-Za-z][\w-]*|/>|#include|#define|#ifdef|#endif|#\[|\$[({]|\$[@<_]|\$\w+|@\w+|[A-Za-z_][\w-]*\w|[A-Za-z_]|\d+(?:px|em|rem|vh|vw)|%>%|::|->|=>|:=|<-|\?:|\|\|=|=~|~=|===|!==|&&|\|\||\.\.|\[\[|\]\]|```|\*\*|\]\(|":|#+|\S"#,
+    )
+    .unwrap()
+});
+
+/// Features of one line: its tokens, plus `^`-prefixed forms of how it starts
+fn line_features(line: &str, out: &mut Vec<String>) {
+    let mut first = true;
+    if line.starts_with('\t') {
+        out.push("^\t".to_string());
+    }
+    let trimmed = line.trim_start();
+    for token in TOKEN.find_iter(trimmed) {
+        let text = normalize(token.as_str());
+        if first {
+            first = false;
+            let rest = &trimmed[token.end()..];
+            let is_word = text
+                .chars()
+                .next()
+                .is_some_and(|c| c.is_alphanumeric() || c == '_');
+            if is_word && (rest.starts_with(": ") || rest == ":") {
+                out.push("^key:".to_string());
+            } else if is_word
+                && rest.trim_start().starts_with('=')
+                && !rest.trim_start().starts_with("==")
+            {
+                out.push("^key=".to_string());
+            }
+            if text == "all" && rest.starts_with(':') {
+                out.push("^all:".to_string());
+            }
+            out.push(format!("^{}", text));
+        }
+        out.push(text);
+    }
+}
+
+/// Collapse tokens that only matter by shape: `$name` -> `$var`, `</div` -> `</`, `12px` -> `px`
+fn normalize(token: &str) -> String {
+    if let Some(name) = token.strip_prefix('$') {
+        return match name {
+            "this" | "_" | "true" | "false" | "null" | "@" | "<" | "(" | "{" => token.to_string(),
+            _ => "$var".to_string(),
+        };
+    }
+    if token.starts_with("</") {
+        return "</".to_string();
+    }
+    if token.starts_with(|c: char| c.is_ascii_digit()) {
+        return token
+            .trim_start_matches(|c: char| c.is_ascii_digit())
+            .to_string();
+    }
+    if token.starts_with('<')
+        && token.len() > 1
+        && !token.starts_with("<?")
+        && !token.starts_with("<!")
+    {
+        return token.to_ascii_lowercase();
+    }
+    token.to_string()
+}
+
+/// Score of each language for `content`
+fn scores(content: &str) -> Vec<f64> {
+    let model = &*MODEL_TABLES;
+    let mut counts: AHashMap<String, u32> = AHashMap::new();
+    let mut features = Vec::new();
+    // Long inputs are classified on their first 64 KiB; that is plenty of evidence
+    let end = (0..=content.len().min(64 * 1024))
+        .rev()
+        .find(|&i| content.is_char_boundary(i))
+        .unwrap_or(0);
+    for line in content[..end].lines() {
+        features.clear();
+        line_features(line, &mut features);
+        for feature in features.drain(..) {
+            *counts.entry(feature).or_insert(0) += 1;
+        }
+    }
+
+    let mut scores = vec![0.0; model.languages.len()];
+    for (feature, count) in &counts {
+        let damped = (1.0 + *count as f64).ln();
+        if let Some(weights) = model.features.get(feature.as_str()) {
+            for &(language, weight) in weights {
+                scores[language] += weight * damped;
+            }
+        }
+        if let Some(weights) = model.folded.get(feature.to_ascii_lowercase().as_str()) {
+            for &(language, weight) in weights {
+                scores[language] += weight * damped;
+            }
+        }
+    }
+    scores
+}
+
+/// Classify `content` by its features, returning "unknown" when the evidence is too thin
+pub(crate) fn classify(content: &str) -> LanguageGuess {
+    let model = &*MODEL_TABLES;
+    let scores = scores(content);
+    let mut ranked: Vec<(usize, f64)> = scores
+        .iter()
+        .copied()
+        .enumerate()
+        .filter(|(_, score)| *score > 0.0)
+        .collect();
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+    let best = ranked.first().map_or(0.0, |(_, score)| *score);
+    if best < MIN_SCORE {
+        return LanguageGuess::unknown();
+    }
+
+    // Softmax relative to the best score, against a constant term for "none of these"
+    let exp = |score: f64| (score - best).exp();
+    let total: f64 = ranked.iter().map(|(_, score)| exp(*score)).sum::<f64>() + exp(0.0);
+    let evidence = 1.0 - (-best / EVIDENCE_SCALE).exp();
+    let probability = |score: f64| exp(score) / total * evidence;
+
+    let (top, top_score) = ranked[0];
+    let mut guess = LanguageGuess::new(model.languages[top], probability(top_score), "content");
+    guess.alternates = ranked[1..]
+        .iter()
+        .take(3)
+        .map(|&(language, score)| LanguageAlternate {
+            language: model.languages[language].to_string(),
+            confidence: probability(score),
+        })
+        .filter(|alternate| alternate.confidence >= 0.01)
+        .collect();
+    guess
+}
+
+/**
+ * Guess the language of a snippet from its content alone
+ *
+ * Scores the text against weighted keyword and token features for 28
+ * languages, including shell, SQL, Dockerfile, Makefile, YAML, JSON,
+ * HTML, CSS, and Markdown, which keyword checks tend to miss. The
+ * confidence is a probability that shrinks for short inputs; below a
+ * minimum amount of evidence the result is "unknown" with confidence 0.
+ *
+ * @param content - Text to classify
+ * @returns Most likely language, its confidence, and up to three alternates
+ */
+#[napi]
+pub fn classify_language(content: String) -> LanguageGuess {
+    classify(&content)
+}
@@ -0,0 +1,308 @@
+/*!
+ * Clone detection
+ * Duplicated blocks across a set of files, by token shingling
+ */
+
+use crate::comments::strip_comments_and_strings;
+use crate::functions::{line_of, line_starts};
+use crate::loc::language_from_path;
+use ahash::{AHashMap, AHashSet, RandomState};
+use napi_derive::napi;
+use rayon::prelude::*;
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// A file to scan for clones
+#[napi(object)]
+pub struct CloneSource {
+    pub path: String,
+    pub content: String,
+    /// Language name as returned by detect_language; inferred from the path when omitted
+    pub language: Option<String>,
+}
+
+/// Options for detect_clones
+#[napi(object)]
+pub struct CloneOptions {
+    /// Smallest clone reported, in tokens (default: 50)
+    pub min_tokens: Option<u32>,
+    /// Lowest similarity reported, 0-1 (default: 0.8)
+    pub min_similarity: Option<f64>,
+    /// Treat identifiers and literals as equal, so renamed copies match (default: false)
+    pub normalize_identifiers: Option<bool>,
+    /// Also report blocks duplicated within one file (default: false)
+    pub same_file: Option<bool>,
+}
+
+/// One side of a clone
+#[napi(object)]
+pub struct CloneLocation {
+    pub path: String,
+    /// 1-based, inclusive
+    pub start_line: u32,
+    pub end_line: u32,
+}
+
+/// A block duplicated between two locations
+#[napi(object)]
+pub struct ClonePair {
+    pub a: CloneLocation,
+    pub b: CloneLocation,
+    /// Tokens in the longer side
+    pub tokens: u32,
+    /// Fraction of the block's tokens inside windows shared by both sides, 0-1
+    pub similarity: f64,
+}
+
+/// Result of detect_clones
+#[napi(object)]
+pub struct CloneReport {
+    /// Largest clones first
+    pub clones: Vec<ClonePair>,
+    pub files_scanned: u32,
+    pub tokens_scanned: u32,
+}
+
+/// Tokens per shingle; clones shorter than this can't be found
+const SHINGLE: usize = 12;
+/// Shingles seen more often are boilerplate (license headers, imports) and aren't paired up
+const MAX_OCCURRENCES: usize = 32;
+
+struct Tokens {
+    ids: Vec<u32>,
+    lines: Vec<u32>,
+}
+
+fn tokenize(
+    source: &CloneSource,
+    normalize: bool,
+    keywords: &AHashSet<&str>,
+) -> (Vec<String>, Vec<u32>) {
+    static TOKEN: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r#"[A-Za-z_$][\w$]*|\d[\w.]*|"+|'+|`+|\S"#).unwrap());
+    let language = source
+        .language
+        .clone()
+        .or_else(|| language_from_path(&source.path).map(str::to_string))
+        .unwrap_or_default();
+    let code = strip_comments_and_strings(&source.content, &language);
+    let starts = line_starts(&code);
+
+    let mut words = Vec::new();
+    let mut lines = Vec::new();
+    for token in TOKEN.find_iter(&code) {
+        let text = token.as_str();
+        let first = text.as_bytes()[0];
+        let word = if normalize && (first.is_ascii_alphabetic() || first == b'_' || first == b'$') {
+            if keywords.contains(text) {
+                text.to_string()
+            } else {
+                "\u{0}id".to_string()
+            }
+        } else if normalize && first.is_ascii_digit() {
+            "\u{0}num".to_string()
+        } else {
+            text.to_string()
+        };
+        words.push(word);
+        lines.push(line_of(&starts, token.start()));
+    }
+    (words, lines)
+}
+
+/// Keywords kept as themselves when identifiers are normalized, so control flow still has to match
+const KEYWORDS: &str =
+    "if else for while do switch case break continue return try catch finally throw \
+    function class const let var new def elif in not and or is with yield async await \
+    fn impl match loop mut pub use struct enum trait func go defer select import from \
+    export static void public private protected this self true false null None nil";
+
+/// A run of shared shingles between two files, along one diagonal
+struct Run {
+    a: usize,
+    b: usize,
+    start_a: usize,
+    start_b: usize,
+    /// Tokens of side a inside a shared shingle; fewer than the span when the run bridged edits
+    covered: usize,
+    end_a: usize,
+    end_b: usize,
+}
+
+/**
+ * Find blocks of code duplicated across a set of files
+ *
+ * Files are tokenized with comments and string contents removed, and
+ * every window of 12 tokens is hashed. Windows shared between files are
+ * chained into blocks, bridging short edits, so a copy with a few changed
+ * tokens is still one clone with similarity below 1. With
+ * `normalizeIdentifiers`, copies that only rename variables or change
+ * literals match too. Windows shared by more than 32 places (license
+ * headers, import lists) are ignored.
+ *
+ * @param files - Paths and contents to compare
+ * @param options - Minimum size, minimum similarity, renaming tolerance, same-file clones
+ * @returns Clone pairs with both locations, size, and similarity, largest first
+ */
+#[napi]
+pub fn detect_clones(files: Vec<CloneSource>, options: Option<CloneOptions>) -> CloneReport {
+    let min_tokens = options
+        .as_ref()
+        .and_then(|o| o.min_tokens)
+        .unwrap_or(50)
+        .max(SHINGLE as u32) as usize;
+    let min_similarity = options
+        .as_ref()
+        .and_then(|o| o.min_similarity)
+        .unwrap_or(0.8);
+    let normalize = options
+        .as_ref()
+        .and_then(|o| o.normalize_identifiers)
+        .unwrap_or(false);
+    let same_file = options.as_ref().and_then(|o| o.same_file).unwrap_or(false);
+
+    let keywords: AHashSet<&str> = KEYWORDS.split_whitespace().collect();
+    let tokenized: Vec<(Vec<String>, Vec<u32>)> = files
+        .par_iter()
+        .map(|file| tokenize(file, normalize, &keywords))
+        .collect();
+
+    // Intern tokens so shingles compare as integer slices
+    let mut interner: AHashMap<String, u32> = AHashMap::new();
+    let tokens: Vec<Tokens> = tokenized
+        .into_iter()
+        .map(|(words, lines)| {
+            let ids = words
+                .into_iter()
+                .map(|word| {
+                    let next = interner.len() as u32;
+                    *interner.entry(word).or_insert(next)
+                })
+                .collect();
+            Tokens { ids, lines }
+        })
+        .collect();
+
+    let hasher = RandomState::with_seeds(1, 2, 3, 4);
+    let mut index: AHashMap<u64, Vec<(usize, usize)>> = AHashMap::new();
+    for (file, t) in tokens.iter().enumerate() {
+        for (pos, window) in t.ids.windows(SHINGLE).enumerate() {
+            index
+                .entry(hasher.hash_one(window))
+                .or_default()
+                .push((file, pos));
+        }
+    }
+
+    // Shared shingles grouped by file pair and diagonal (offset between positions)
+    let mut diagonals: AHashMap<(usize, usize, isize), Vec<(usize, usize)>> = AHashMap::new();
+    for places in index.values() {
+        if places.len() < 2 || places.len() > MAX_OCCURRENCES {
+            continue;
+        }
+        for (i, &(file_a, pos_a)) in places.iter().enumerate() {
+            for &(file_b, pos_b) in &places[i + 1..] {
+                if file_a == file_b && (!same_file || pos_b < pos_a + min_tokens) {
+                    continue;
+                }
+                let window_a = &tokens[file_a].ids[pos_a..pos_a + SHINGLE];
+                let window_b = &tokens[file_b].ids[pos_b..pos_b + SHINGLE];
+                if window_a != window_b {
+                    continue;
+                }
+                diagonals
+                    .entry((file_a, file_b, pos_b as isize - pos_a as isize))
+                    .or_default()
+                    .push((pos_a, pos_b));
+            }
+        }
+    }
+
+    // Chain shingles along each diagonal, bridging gaps shorter than a shingle
+    let mut runs: Vec<Run> = Vec::new();
+    for ((a, b, _), mut matches) in diagonals {
+        matches.sort_unstable();
+        let mut current: Option<Run> = None;
+        for (pos_a, pos_b) in matches {
+            match current.as_mut() {
+                Some(run) if pos_a <= run.end_a + SHINGLE => {
+                    run.covered += SHINGLE.min(pos_a + SHINGLE - run.end_a);
+                    run.end_a = pos_a + SHINGLE;
+                    run.end_b = pos_b + SHINGLE;
+                }
+                _ => {
+                    runs.extend(current.take());
+                    current = Some(Run {
+                        a,
+                        b,
+                        start_a: pos_a,
+                        start_b: pos_b,
+                        covered: SHINGLE,
+                        end_a: pos_a + SHINGLE,
+                        end_b: pos_b + SHINGLE,
+                    });
+                }
+            }
+        }
+        runs.extend(current);
+    }
+
+    // Join runs split by an insertion or deletion, which shifts the diagonal
+    runs.sort_unstable_by_key(|run| (run.a, run.b, run.start_a, run.start_b));
+    let mut blocks: Vec<Run> = Vec::new();
+    for run in runs {
+        if let Some(last) = blocks.last_mut() {
+            if last.a == run.a
+                && last.b == run.b
+                && run.start_a >= last.start_a
+                && run.start_a <= last.end_a + SHINGLE
+                && run.start_b >= last.start_b
+                && run.start_b <= last.end_b + SHINGLE
+            {
+                let overlap = last.end_a.saturating_sub(run.start_a);
+                last.covered += run.covered.saturating_sub(overlap);
+                last.end_a = last.end_a.max(run.end_a);
+                last.end_b = last.end_b.max(run.end_b);
+                continue;
+            }
+        }
+        blocks.push(run);
+    }
+
+    let mut clones: Vec<ClonePair> = blocks
+        .into_iter()
+        .filter_map(|block| {
+            let span = (block.end_a - block.start_a).max(block.end_b - block.start_b);
+            if span < min_tokens {
+                return None;
+            }
+            let similarity = (block.covered as f64 / span as f64).min(1.0);
+            if similarity < min_similarity {
+                return None;
+            }
+            let location = |file: usize, start: usize, end: usize| CloneLocation {
+                path: files[file].path.clone(),
+                start_line: tokens[file].lines[start],
+                end_line: tokens[file].lines[end - 1],
+            };
+            Some(ClonePair {
+                a: location(block.a, block.start_a, block.end_a),
+                b: location(block.b, block.start_b, block.end_b),
+                tokens: span as u32,
+                similarity,
+            })
+        })
+        .collect();
+    clones.sort_by(|x, y| {
+        y.tokens
+            .cmp(&x.tokens)
+            .then_with(|| x.a.path.cmp(&y.a.path))
+            .then_with(|| x.a.start_line.cmp(&y.a.start_line))
+    });
+
+    CloneReport {
+        clones,
+        files_scanned: files.len() as u32,
+        tokens_scanned: tokens.iter().map(|t| t.ids.len() as u32).sum(),
+    }
+}
@@ -0,0 +1,464 @@
+/*!
+ * Vector clustering
+ * k-means and HDBSCAN over TF-IDF or embedding vectors, for grouping similar sessions
+ */
+
+use crate::minhash::splitmix64;
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use rayon::prelude::*;
+
+/// Options for cluster_vectors
+#[napi(object)]
+pub struct ClusterOptions {
+    /// "kmeans" (default) or "hdbscan"
+    pub method: Option<String>,
+    /// "cosine" (default) compares directions, "euclidean" raw positions
+    pub metric: Option<String>,
+    /// Clusters to find with k-means (default: 8, capped at the number of vectors)
+    pub k: Option<u32>,
+    /// k-means rounds before giving up on convergence (default: 100)
+    pub max_iterations: Option<u32>,
+    /// Seed for k-means++ initialization (default: 42)
+    pub seed: Option<u32>,
+    /// Smallest HDBSCAN cluster; smaller groups are noise (default: 5)
+    pub min_cluster_size: Option<u32>,
+    /// Neighbors defining a dense point for HDBSCAN (default: min_cluster_size)
+    pub min_samples: Option<u32>,
+}
+
+/// One cluster found by cluster_vectors
+#[napi(object)]
+pub struct Cluster {
+    pub label: i32,
+    pub size: u32,
+    /// Mean of the members
+    pub centroid: Vec<f64>,
+    /// Index of the member nearest the centroid, a representative session
+    pub exemplar: u32,
+}
+
+/// Result of cluster_vectors
+#[napi(object)]
+pub struct Clustering {
+    /// Cluster per input vector; -1 marks HDBSCAN noise
+    pub labels: Vec<i32>,
+    /// Ordered by label
+    pub clusters: Vec<Cluster>,
+    /// k-means rounds run; 0 for HDBSCAN
+    pub iterations: u32,
+    /// k-means sum of squared distances to the assigned centroid; 0 for HDBSCAN
+    pub inertia: f64,
+}
+
+fn squared_distance(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(x, y)| (x - y) * (x - y)).sum()
+}
+
+/// The vector nearest `point`, with its squared distance
+fn nearest(point: &[f64], centroids: &[Vec<f64>]) -> (usize, f64) {
+    centroids
+        .iter()
+        .enumerate()
+        .map(|(i, centroid)| (i, squared_distance(point, centroid)))
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .unwrap_or((0, 0.0))
+}
+
+/// Uniform draw in [0, 1)
+fn uniform(state: &mut u64) -> f64 {
+    (splitmix64(state) >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Lloyd's algorithm from k-means++ seeds: (labels, iterations, inertia)
+fn kmeans(
+    points: &[Vec<f64>],
+    k: usize,
+    max_iterations: usize,
+    seed: u64,
+) -> (Vec<usize>, usize, f64) {
+    let mut state = seed;
+    let mut centroids =
+        vec![points[(splitmix64(&mut state) % points.len() as u64) as usize].clone()];
+    let mut distances: Vec<f64> = points
+        .iter()
+        .map(|p| squared_distance(p, &centroids[0]))
+        .collect();
+    while centroids.len() < k {
+        // Next seed drawn with probability proportional to squared distance
+        let total: f64 = distances.iter().sum();
+        let next = if total > 0.0 {
+            let mut target = uniform(&mut state) * total;
+            distances
+                .iter()
+                .position(|d| {
+                    target -= d;
+                    target < 0.0
+                })
+                .unwrap_or(points.len() - 1)
+        } else {
+            centroids.len()
+        };
+        centroids.push(points[next].clone());
+        for (distance, point) in distances.iter_mut().zip(points) {
+            *distance = distance.min(squared_distance(point, &points[next]));
+        }
+    }
+
+    let dimensions = points[0].len();
+    let mut labels = vec![usize::MAX; points.len()];
+    let mut iterations = 0;
+    loop {
+        iterations += 1;
+        let assigned: Vec<(usize, f64)> =
+            points.par_iter().map(|p| nearest(p, &centroids)).collect();
+        let changed = assigned
+            .iter()
+            .zip(&labels)
+            .any(|((cluster, _), label)| cluster != label);
+        labels = assigned.iter().map(|(cluster, _)| *cluster).collect();
+        if !changed || iterations >= max_iterations {
+            let inertia = assigned.iter().map(|(_, d)| d).sum();
+            return (labels, iterations, inertia);
+        }
+        let mut sums = vec![vec![0.0; dimensions]; k];
+        let mut counts = vec![0usize; k];
+        for (point, label) in points.iter().zip(&labels) {
+            counts[*label] += 1;
+            for (sum, value) in sums[*label].iter_mut().zip(point) {
+                *sum += value;
+            }
+        }
+        for (cluster, (sum, count)) in sums.into_iter().zip(&counts).enumerate() {
+            if *count > 0 {
+                centroids[cluster] = sum.into_iter().map(|s| s / *count as f64).collect();
+            } else {
+                // Reseed an empty cluster with the point farthest from its centroid
+                let (farthest, _) = assigned
+                    .iter()
+                    .enumerate()
+                    .max_by(|a, b| a.1 .1.total_cmp(&b.1 .1))
+                    .unwrap_or((0, &(0, 0.0)));
+                centroids[cluster] = points[farthest].clone();
+            }
+        }
+    }
+}
+
+/// HDBSCAN* with excess-of-mass cluster selection; None marks noise
+fn hdbscan(points: &[Vec<f64>], min_cluster_size: usize, min_samples: usize) -> Vec<Option<usize>> {
+    let n = points.len();
+    let distance = |a: usize, b: usize| squared_distance(&points[a], &points[b]).sqrt();
+    // Core distance: to the min_samples-th nearest point, counting the point itself
+    let core: Vec<f64> = (0..n)
+        .into_par_iter()
+        .map(|i| {
+            let mut row: Vec<f64> = (0..n).map(|j| distance(i, j)).collect();
+            let kth = (min_samples.max(1) - 1).min(n - 1);
+            *row.select_nth_unstable_by(kth, f64::total_cmp).1
+        })
+        .collect();
+
+    // Prim's minimum spanning tree under mutual reachability distance
+    let mut in_tree = vec![false; n];
+    let mut best = vec![f64::INFINITY; n];
+    let mut parent = vec![0usize; n];
+    let mut edges: Vec<(f64, usize, usize)> = Vec::with_capacity(n.saturating_sub(1));
+    let mut current = 0;
+    in_tree[0] = true;
+    for _ in 1..n {
+        for j in 0..n {
+            if !in_tree[j] {
+                let reach = distance(current, j).max(core[current]).max(core[j]);
+                if reach < best[j] {
+                    best[j] = reach;
+                    parent[j] = current;
+                }
+            }
+        }
+        let next = (0..n)
+            .filter(|j| !in_tree[*j])
+            .min_by(|a, b| best[*a].total_cmp(&best[*b]))
+            .unwrap_or(0);
+        in_tree[next] = true;
+        edges.push((best[next], parent[next], next));
+        current = next;
+    }
+    edges.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    // Single-linkage dendrogram: node n + i merges two nodes at edges[i]
+    let mut union: Vec<usize> = (0..2 * n).collect();
+    fn find(union: &mut [usize], mut x: usize) -> usize {
+        while union[x] != x {
+            union[x] = union[union[x]];
+            x = union[x];
+        }
+        x
+    }
+    let mut children: Vec<(usize, usize, f64)> = Vec::with_capacity(n);
+    let mut size = vec![1usize; 2 * n];
+    for (i, (weight, a, b)) in edges.iter().enumerate() {
+        let (ra, rb) = (find(&mut union, *a), find(&mut union, *b));
+        let node = n + i;
+        union[ra] = node;
+        union[rb] = node;
+        size[node] = size[ra] + size[rb];
+        children.push((ra, rb, *weight));
+    }
+
+    // Condense: walk down from the root, keeping splits where both sides are big enough
+    let lambda_of = |weight: f64| 1.0 / weight.max(1e-12);
+    let leaves = |node: usize| -> Vec<usize> {
+        let mut stack = vec![node];
+        let mut out = Vec::new();
+        while let Some(node) = stack.pop() {
+            if node < n {
+                out.push(node);
+            } else {
+                let (a, b, _) = children[node - n];
+                stack.push(a);
+                stack.push(b);
+            }
+        }
+        out
+    };
+    // Per condensed cluster: parent, birth lambda; and, per point, (cluster, lambda) where it fell out
+    let mut cluster_parent: Vec<Option<usize>> = vec![None];
+    let mut birth = vec![0.0];
+    let mut child_lambdas: Vec<Vec<(f64, usize)>> = vec![Vec::new()];
+    let mut fell_out = vec![(0usize, 0.0f64); n];
+    let root = 2 * n - 2;
+    let mut stack = vec![(root, 0usize)];
+    while let Some((node, cluster)) = stack.pop() {
+        if node < n {
+            fell_out[node] = (cluster, birth[cluster]);
+            continue;
+        }
+        let (a, b, weight) = children[node - n];
+        let lambda = lambda_of(weight);
+        let big_a = size[a] >= min_cluster_size;
+        let big_b = size[b] >= min_cluster_size;
+        if big_a && big_b {
+            for child in [a, b] {
+                let label = cluster_parent.len();
+                cluster_parent.push(Some(cluster));
+                birth.push(lambda);
+                child_lambdas.push(Vec::new());
+                child_lambdas[cluster].push((lambda, size[child]));
+                stack.push((child, label));
+            }
+        } else {
+            for (child, big) in [(a, big_a), (b, big_b)] {
+                if big {
+                    stack.push((child, cluster));
+                } else {
+                    for point in leaves(child) {
+                        fell_out[point] = (cluster, lambda);
+                        child_lambdas[cluster].push((lambda, 1));
+                    }
+                }
+            }
+        }
+    }
+
+    // Excess of mass: keep a cluster unless its children together are more stable
+    let clusters = cluster_parent.len();
+    let mut stability: Vec<f64> = (0..clusters)
+        .map(|c| {
+            child_lambdas[c]
+                .iter()
+                .map(|(lambda, count)| (lambda - birth[c]) * *count as f64)
+                .sum()
+        })
+        .collect();
+    let mut selected = vec![false; clusters];
+    let mut child_total = vec![0.0; clusters];
+    let mut has_children = vec![false; clusters];
+    // Children always have larger labels than their parents
+    for c in (1..clusters).rev() {
+        if has_children[c] && child_total[c] > stability[c] {
+            stability[c] = child_total[c];
+        } else {
+            selected[c] = true;
+        }
+        if let Some(p) = cluster_parent[c] {
+            child_total[p] += stability[c];
+            has_children[p] = true;
+        }
+    }
+    // A selected cluster absorbs everything beneath it
+    let selected_ancestor = |mut c: usize| -> Option<usize> {
+        let mut found = None;
+        while c != 0 {
+            if selected[c] {
+                found = Some(c);
+            }
+            c = cluster_parent[c]?;
+        }
+        found
+    };
+    fell_out
+        .iter()
+        .map(|(cluster, _)| selected_ancestor(*cluster))
+        .collect()
+}
+
+/**
+ * Group vectors into clusters
+ *
+ * k-means finds exactly `k` clusters from k-means++ seeds and assigns every
+ * vector. HDBSCAN needs no cluster count: it finds dense groups of at least
+ * `min_cluster_size` vectors and labels outliers -1, at O(n²) time, so it
+ * suits up to a few thousand sessions. With the cosine metric, vectors are
+ * normalized to unit length first. Labels are numbered in order of each
+ * cluster's first member, so reruns on the same input agree.
+ *
+ * @param vectors - One vector per session, e.g. from EmbeddingModel.embed; all the same length
+ * @param options - Method, metric, and method parameters
+ * @returns Labels per vector, and each cluster's size, centroid, and exemplar
+ */
+#[napi(
+    ts_args_type = "vectors: Array<Float32Array | number[]>, options?: ClusterOptions | undefined | null"
+)]
+pub fn cluster_vectors(
+    vectors: Vec<Either<Float32Array, Vec<f64>>>,
+    options: Option<ClusterOptions>,
+) -> Result<Clustering> {
+    let cosine = match options
+        .as_ref()
+        .and_then(|o| o.metric.as_deref())
+        .unwrap_or("cosine")
+    {
+        "cosine" => true,
+        "euclidean" => false,
+        other => {
+            return Err(Error::new(
+                Status::InvalidArg,
+                format!("Unknown metric '{}' (expected cosine or euclidean)", other),
+            ))
+        }
+    };
+    let mut points: Vec<Vec<f64>> = vectors
+        .into_iter()
+        .map(|vector| match vector {
+            Either::A(typed) => typed.iter().map(|v| *v as f64).collect(),
+            Either::B(values) => values,
+        })
+        .collect();
+    if let Some(first) = points.first() {
+        let dimensions = first.len();
+        if let Some(index) = points.iter().position(|p| p.len() != dimensions) {
+            return Err(Error::new(
+                Status::InvalidArg,
+                format!(
+                    "Vector {} has {} dimensions, expected {}",
+                    index,
+                    points[index].len(),
+                    dimensions
+                ),
+            ));
+        }
+    }
+    if cosine {
+        for point in &mut points {
+            let norm = point.iter().map(|v| v * v).sum::<f64>().sqrt();
+            if norm > 0.0 {
+                point.iter_mut().for_each(|v| *v /= norm);
+            }
+        }
+    }
+
+    let (raw, iterations, inertia): (Vec<Option<usize>>, usize, f64) = match options
+        .as_ref()
+        .and_then(|o| o.method.as_deref())
+        .unwrap_or("kmeans")
+    {
+        _ if points.is_empty() => (Vec::new(), 0, 0.0),
+        "kmeans" => {
+            let k = options.as_ref().and_then(|o| o.k).unwrap_or(8).max(1) as usize;
+            let max_iterations = options
+                .as_ref()
+                .and_then(|o| o.max_iterations)
+                .unwrap_or(100)
+                .max(1) as usize;
+            let seed = options.as_ref().and_then(|o| o.seed).unwrap_or(42) as u64;
+            let (labels, iterations, inertia) =
+                kmeans(&points, k.min(points.len()), max_iterations, seed);
+            (labels.into_iter().map(Some).collect(), iterations, inertia)
+        }
+        "hdbscan" => {
+            let min_cluster_size = options
+                .as_ref()
+                .and_then(|o| o.min_cluster_size)
+                .unwrap_or(5)
+                .max(2) as usize;
+            let min_samples = options
+                .as_ref()
+                .and_then(|o| o.min_samples)
+                .map_or(min_cluster_size, |m| m as usize);
+            (hdbscan(&points, min_cluster_size, min_samples), 0, 0.0)
+        }
+        other => {
+            return Err(Error::new(
+                Status::InvalidArg,
+                format!("Unknown method '{}' (expected kmeans or hdbscan)", other),
+            ))
+        }
+    };
+
+    // Renumber by first member and gather members per cluster
+    let mut renumbered: Vec<Option<usize>> = Vec::new();
+    let mut members: Vec<Vec<usize>> = Vec::new();
+    let labels: Vec<i32> = raw
+        .iter()
+        .enumerate()
+        .map(|(index, cluster)| {
+            let Some(cluster) = cluster else {
+                return -1;
+            };
+            if renumbered.len() <= *cluster {
+                renumbered.resize(cluster + 1, None);
+            }
+            let label = *renumbered[*cluster].get_or_insert_with(|| {
+                members.push(Vec::new());
+                members.len() - 1
+            });
+            members[label].push(index);
+            label as i32
+        })
+        .collect();
+    let clusters = members
+        .iter()
+        .enumerate()
+        .map(|(label, members)| {
+            let dimensions = points[members[0]].len();
+            let mut centroid = vec![0.0; dimensions];
+            for member in members {
+                for (sum, value) in centroid.iter_mut().zip(&points[*member]) {
+                    *sum += value;
+                }
+            }
+            centroid.iter_mut().for_each(|v| *v /= members.len() as f64);
+            let exemplar = members
+                .iter()
+                .copied()
+                .min_by(|a, b| {
+                    squared_distance(&points[*a], &centroid)
+                        .total_cmp(&squared_distance(&points[*b], &centroid))
+                })
+                .unwrap_or(members[0]);
+            Cluster {
+                label: label as i32,
+                size: members.len() as u32,
+                centroid,
+                exemplar: exemplar as u32,
+            }
+        })
+        .collect();
+    Ok(Clustering {
+        labels,
+        clusters,
+        iterations: iterations as u32,
+        inertia,
+    })
+}
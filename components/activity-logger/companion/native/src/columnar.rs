@@ -0,0 +1,261 @@
+/*!
+ * Columnar export
+ * Stored events as Arrow record batches, written to Parquet files for
+ * pandas, DuckDB, and other analysis tools, or to Arrow IPC buffers for
+ * Arrow JS and pyarrow
+ */
+
+use crate::ingest::{details_object, event_field};
+use crate::store::{empty_query, EventQuery, EventStore, Row};
+use arrow::array::{ArrayRef, Int64Array, StringArray, TimestampMillisecondArray};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use arrow::ipc::writer::{FileWriter, StreamWriter};
+use arrow::record_batch::RecordBatch;
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::{Compression, ZstdLevel};
+use parquet::file::properties::WriterProperties;
+use serde_json::Value;
+use std::fs::{self, File};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Rows per record batch; also the smallest row group written
+pub(crate) const BATCH_ROWS: usize = 8192;
+const DEFAULT_ROW_GROUP_ROWS: u32 = 128 * 1024;
+
+/// Options for EventStore.export_parquet
+#[napi(object)]
+pub struct ParquetOptions {
+    /// "zstd" (default), "snappy", or "none"
+    pub compression: Option<String>,
+    /// Rows per row group; larger groups compress better, smaller ones read selectively (default: 131072)
+    pub row_group_size: Option<u32>,
+}
+
+/// Result of EventStore.export_parquet
+#[napi(object)]
+pub struct ParquetExport {
+    pub path: String,
+    pub rows: u32,
+    pub row_groups: u32,
+    /// Size of the written file
+    pub bytes: f64,
+}
+
+/// Options for EventStore.export_arrow
+#[napi(object)]
+pub struct ArrowOptions {
+    /// "stream" (default) or "file", the IPC stream or file (Feather v2) format
+    pub format: Option<String>,
+}
+
+pub(crate) fn arrow_error(error: impl std::fmt::Display) -> Error {
+    Error::new(Status::GenericFailure, format!("Arrow: {}", error))
+}
+
+/// Column layout shared by every columnar export
+pub(crate) fn event_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Utf8, false),
+        Field::new(
+            "timestamp",
+            DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into())),
+            false,
+        ),
+        Field::new("type", DataType::Utf8, false),
+        Field::new("session_id", DataType::Utf8, true),
+        Field::new("workspace_path", DataType::Utf8, true),
+        Field::new("file_path", DataType::Utf8, true),
+        Field::new("lines_added", DataType::Int64, true),
+        Field::new("lines_removed", DataType::Int64, true),
+        // The whole event as JSON, for fields without a column of their own
+        Field::new("event", DataType::Utf8, false),
+    ]))
+}
+
+/// One record batch from stored rows, in the order of event_schema
+pub(crate) fn record_batch(schema: &SchemaRef, rows: &[Row]) -> Result<RecordBatch> {
+    let mut lines_added = Vec::with_capacity(rows.len());
+    let mut lines_removed = Vec::with_capacity(rows.len());
+    for row in rows {
+        let event = serde_json::from_str::<Value>(&row.payload).unwrap_or(Value::Null);
+        let (added, removed) = match event.as_object() {
+            Some(fields) => {
+                let details = details_object(fields);
+                let count = |keys: &[&str]| event_field(fields, details.as_ref(), keys)?.as_i64();
+                (
+                    count(&["lines_added", "linesAdded"]),
+                    count(&["lines_removed", "linesRemoved"]),
+                )
+            }
+            None => (None, None),
+        };
+        lines_added.push(added);
+        lines_removed.push(removed);
+    }
+    let text = |pick: fn(&Row) -> Option<&str>| -> ArrayRef {
+        Arc::new(rows.iter().map(pick).collect::<StringArray>())
+    };
+    let columns: Vec<ArrayRef> = vec![
+        text(|row| Some(row.id.as_str())),
+        Arc::new(
+            TimestampMillisecondArray::from(
+                rows.iter().map(|row| row.timestamp).collect::<Vec<_>>(),
+            )
+            .with_timezone("UTC"),
+        ),
+        text(|row| Some(row.event_type.as_str())),
+        text(|row| row.session_id.as_deref()),
+        text(|row| row.workspace_path.as_deref()),
+        text(|row| row.file_path.as_deref()),
+        Arc::new(Int64Array::from(lines_added)),
+        Arc::new(Int64Array::from(lines_removed)),
+        text(|row| Some(row.payload.as_str())),
+    ];
+    RecordBatch::try_new(schema.clone(), columns).map_err(arrow_error)
+}
+
+/// Feed the rows matching `filter` to `write` a batch at a time
+pub(crate) fn scan_batches(
+    store: &EventStore,
+    filter: &EventQuery,
+    mut write: impl FnMut(RecordBatch) -> Result<()>,
+) -> Result<()> {
+    let schema = event_schema();
+    let mut rows = Vec::with_capacity(BATCH_ROWS);
+    store.scan(filter, |row| {
+        rows.push(row);
+        if rows.len() == BATCH_ROWS {
+            write(record_batch(&schema, &rows)?)?;
+            rows.clear();
+        }
+        Ok(())
+    })?;
+    if !rows.is_empty() {
+        write(record_batch(&schema, &rows)?)?;
+    }
+    Ok(())
+}
+
+fn compression_of(name: Option<&str>) -> Result<Compression> {
+    match name.unwrap_or("zstd") {
+        "zstd" => Ok(Compression::ZSTD(ZstdLevel::default())),
+        "snappy" => Ok(Compression::SNAPPY),
+        "none" => Ok(Compression::UNCOMPRESSED),
+        other => Err(Error::new(
+            Status::InvalidArg,
+            format!(
+                "Unknown compression '{}' (expected zstd, snappy, or none)",
+                other
+            ),
+        )),
+    }
+}
+
+#[napi]
+impl EventStore {
+    /**
+     * Write the events matching `filter` to a Parquet file at `path`
+     *
+     * Columns are id, timestamp (UTC milliseconds), type, session_id,
+     * workspace_path, file_path, lines_added, lines_removed, and the whole
+     * event as JSON in `event`. Rows are read from the store in batches, so
+     * memory stays flat however many match. The file is written next to
+     * `path` and renamed into place once complete.
+     */
+    #[napi]
+    pub fn export_parquet(
+        &self,
+        path: String,
+        filter: Option<EventQuery>,
+        options: Option<ParquetOptions>,
+    ) -> Result<ParquetExport> {
+        let filter = filter.unwrap_or_else(empty_query);
+        let properties = WriterProperties::builder()
+            .set_compression(compression_of(
+                options.as_ref().and_then(|o| o.compression.as_deref()),
+            )?)
+            .set_max_row_group_size(
+                options
+                    .as_ref()
+                    .and_then(|o| o.row_group_size)
+                    .unwrap_or(DEFAULT_ROW_GROUP_ROWS)
+                    .max(BATCH_ROWS as u32) as usize,
+            )
+            .build();
+        let target = Path::new(&path);
+        let temporary = target.with_extension("parquet.tmp");
+        let io_error = |action: &str, error: std::io::Error| {
+            Error::new(
+                Status::GenericFailure,
+                format!("Failed to {} {}: {}", action, temporary.display(), error),
+            )
+        };
+        let file = File::create(&temporary).map_err(|e| io_error("create", e))?;
+        let mut writer =
+            ArrowWriter::try_new(file, event_schema(), Some(properties)).map_err(arrow_error)?;
+        let written = scan_batches(self, &filter, |batch| {
+            writer.write(&batch).map_err(arrow_error)
+        })
+        .and_then(|_| writer.close().map_err(arrow_error));
+        let metadata = match written {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                let _ = fs::remove_file(&temporary);
+                return Err(e);
+            }
+        };
+        fs::rename(&temporary, target).map_err(|e| io_error("rename", e))?;
+        let bytes = fs::metadata(target).map_or(0, |m| m.len());
+        Ok(ParquetExport {
+            path,
+            rows: metadata.num_rows as u32,
+            row_groups: metadata.row_groups.len() as u32,
+            bytes: bytes as f64,
+        })
+    }
+
+    /**
+     * The events matching `filter` as an Arrow IPC buffer
+     *
+     * Same columns as export_parquet. Arrow JS reads the buffer with
+     * tableFromIPC and pyarrow with ipc.open_stream (or ipc.open_file for
+     * the "file" format), without converting each event to an object.
+     */
+    #[napi]
+    pub fn export_arrow(
+        &self,
+        filter: Option<EventQuery>,
+        options: Option<ArrowOptions>,
+    ) -> Result<Buffer> {
+        let filter = filter.unwrap_or_else(empty_query);
+        let schema = event_schema();
+        let bytes = match options.and_then(|o| o.format).as_deref() {
+            None | Some("stream") => {
+                let mut writer = StreamWriter::try_new(Vec::new(), &schema).map_err(arrow_error)?;
+                scan_batches(self, &filter, |batch| {
+                    writer.write(&batch).map_err(arrow_error)
+                })?;
+                writer.finish().map_err(arrow_error)?;
+                writer.into_inner().map_err(arrow_error)?
+            }
+            Some("file") => {
+                let mut writer = FileWriter::try_new(Vec::new(), &schema).map_err(arrow_error)?;
+                scan_batches(self, &filter, |batch| {
+                    writer.write(&batch).map_err(arrow_error)
+                })?;
+                writer.finish().map_err(arrow_error)?;
+                writer.into_inner().map_err(arrow_error)?
+            }
+            Some(other) => {
+                return Err(Error::new(
+                    Status::InvalidArg,
+                    format!("Unknown format '{}' (expected stream or file)", other),
+                ))
+            }
+        };
+        Ok(bytes.into())
+    }
+}
@@ -0,0 +1,400 @@
+/*!
+ * Language-aware comment detection
+ *
+ * Per-language comment syntax tables plus a line classifier that carries
+ * block comment state across lines, so block comment bodies, docstrings,
+ * and non-C comment styles are counted correctly.
+ */
+
+use std::ops::Range;
+
+/// Comment delimiters for one language
+pub struct CommentSyntax {
+    pub line: &'static [&'static str],
+    /// (open, close) pairs, checked before line comments so `--[[` wins over `--`
+    pub block: &'static [(&'static str, &'static str)],
+    /// Python-style triple-quoted docstrings opening a line count as comments
+    pub docstrings: bool,
+    /// YAML-style block scalars (`key: |`) whose body is string content, not comments
+    pub block_scalars: bool,
+}
+
+const C_STYLE: CommentSyntax = CommentSyntax {
+    line: &["//"],
+    block: &[("/*", "*/")],
+    docstrings: false,
+    block_scalars: false,
+};
+
+const HASH: CommentSyntax = CommentSyntax {
+    line: &["#"],
+    block: &[],
+    docstrings: false,
+    block_scalars: false,
+};
+
+/// Used when the language is unknown: the union of the common styles
+const GENERIC: CommentSyntax = CommentSyntax {
+    line: &["//", "#"],
+    block: &[("/*", "*/")],
+    docstrings: false,
+    block_scalars: false,
+};
+
+/// Comment syntax for a language name as returned by detect_language
+pub fn comment_syntax(language: &str) -> &'static CommentSyntax {
+    match language {
+        "rust" | "javascript" | "typescript" | "go" | "java" | "cpp" | "c" | "csharp" | "swift"
+        | "kotlin" | "scala" | "dart" | "objective-c" | "groovy" | "zig" | "d" | "solidity"
+        | "protobuf" | "glsl" | "hlsl" | "wgsl" | "cuda" | "stylus" | "verilog" => &C_STYLE,
+        "css" => &CommentSyntax {
+            line: &[],
+            block: &[("/*", "*/")],
+            docstrings: false,
+            block_scalars: false,
+        },
+        "scss" | "less" => &C_STYLE,
+        "php" => &CommentSyntax {
+            line: &["//", "#"],
+            block: &[("/*", "*/")],
+            docstrings: false,
+            block_scalars: false,
+        },
+        "python" => &CommentSyntax {
+            line: &["#"],
+            block: &[],
+            docstrings: true,
+            block_scalars: false,
+        },
+        "yaml" => &CommentSyntax {
+            line: &["#"],
+            block: &[],
+            docstrings: false,
+            block_scalars: true,
+        },
+        "shell" | "bash" | "ruby" | "perl" | "r" | "toml" | "makefile" | "dockerfile"
+        | "elixir" | "fish" | "powershell" | "julia" | "nim" | "crystal" | "tcl" | "awk"
+        | "cmake" | "nix" | "starlark" | "graphql" | "coffeescript" | "hcl" => &HASH,
+        "html" | "xml" | "markdown" | "vue" | "svelte" => &CommentSyntax {
+            line: &[],
+            block: &[("<!--", "-->")],
+            docstrings: false,
+            block_scalars: false,
+        },
+        "sql" => &CommentSyntax {
+            line: &["--"],
+            block: &[("/*", "*/")],
+            docstrings: false,
+            block_scalars: false,
+        },
+        "lua" => &CommentSyntax {
+            line: &["--"],
+            block: &[("--[[", "]]")],
+            docstrings: false,
+            block_scalars: false,
+        },
+        "ada" | "vhdl" => &CommentSyntax {
+            line: &["--"],
+            block: &[],
+            docstrings: false,
+            block_scalars: false,
+        },
+        "matlab" | "latex" | "erlang" | "prolog" => &CommentSyntax {
+            line: &["%"],
+            block: &[],
+            docstrings: false,
+            block_scalars: false,
+        },
+        "lisp" | "elisp" | "clojure" | "scheme" | "racket" | "assembly" => &CommentSyntax {
+            line: &[";"],
+            block: &[],
+            docstrings: false,
+            block_scalars: false,
+        },
+        "ini" => &CommentSyntax {
+            line: &[";", "#"],
+            block: &[],
+            docstrings: false,
+            block_scalars: false,
+        },
+        "haskell" | "elm" | "purescript" => &CommentSyntax {
+            line: &["--"],
+            block: &[("{-", "-}")],
+            docstrings: false,
+            block_scalars: false,
+        },
+        _ => &GENERIC,
+    }
+}
+
+/// What a single line contains
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LineKind {
+    Blank,
+    Comment,
+    Code,
+}
+
+/// Line classifier that remembers open block comments between lines
+pub struct CommentScanner {
+    syntax: &'static CommentSyntax,
+    /// Terminator of the currently open block comment or docstring
+    open_block: Option<&'static str>,
+    /// Indentation of a YAML key whose block scalar body we are inside
+    block_scalar_indent: Option<usize>,
+}
+
+impl CommentScanner {
+    pub fn new(syntax: &'static CommentSyntax) -> Self {
+        CommentScanner {
+            syntax,
+            open_block: None,
+            block_scalar_indent: None,
+        }
+    }
+
+    /// Classify the next line; a line is a comment when it holds comment text and no code
+    pub fn classify(&mut self, line: &str) -> LineKind {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            return LineKind::Blank;
+        }
+
+        if self.syntax.block_scalars {
+            let indent = line.len() - line.trim_start().len();
+            match self.block_scalar_indent {
+                Some(key_indent) if indent > key_indent => return LineKind::Code,
+                _ => self.block_scalar_indent = None,
+            }
+            if is_block_scalar_header(trimmed) {
+                self.block_scalar_indent = Some(indent);
+            }
+        }
+
+        let mut has_code = false;
+        let mut has_comment = false;
+        let mut rest = trimmed;
+
+        while !rest.is_empty() {
+            if let Some(close) = self.open_block {
+                has_comment = true;
+                match rest.find(close) {
+                    Some(end) => {
+                        rest = &rest[end + close.len()..];
+                        self.open_block = None;
+                        continue;
+                    }
+                    None => break,
+                }
+            }
+
+            rest = rest.trim_start();
+            if rest.is_empty() {
+                break;
+            }
+
+            if let Some((open, close)) = self
+                .syntax
+                .block
+                .iter()
+                .find(|(open, _)| rest.starts_with(open))
+            {
+                has_comment = true;
+                self.open_block = Some(close);
+                rest = &rest[open.len()..];
+                continue;
+            }
+
+            if self
+                .syntax
+                .line
+                .iter()
+                .any(|marker| rest.starts_with(marker))
+            {
+                has_comment = true;
+                break;
+            }
+
+            // Docstrings only when the triple quote starts the statement
+            if self.syntax.docstrings && !has_code {
+                if let Some(quote) = ["\"\"\"", "'''"].into_iter().find(|q| rest.starts_with(q)) {
+                    has_comment = true;
+                    self.open_block = Some(quote);
+                    rest = &rest[quote.len()..];
+                    continue;
+                }
+            }
+
+            has_code = true;
+            rest = skip_token(rest);
+        }
+
+        if has_comment && !has_code {
+            LineKind::Comment
+        } else {
+            LineKind::Code
+        }
+    }
+}
+
+/// `key: |`, `key: >-`, `- |` and similar YAML block scalar introducers
+fn is_block_scalar_header(trimmed: &str) -> bool {
+    let value = match trimmed.rsplit_once(':') {
+        Some((_, value)) => value.trim(),
+        None => trimmed.strip_prefix('-').unwrap_or("").trim(),
+    };
+    let mut chars = value.chars();
+    matches!(chars.next(), Some('|') | Some('>'))
+        && chars.all(|c| c == '-' || c == '+' || c.is_ascii_digit())
+}
+
+/// Advance past one code character, or a whole quoted string so quoted comment markers are ignored
+fn skip_token(rest: &str) -> &str {
+    let mut chars = rest.char_indices();
+    let Some((_, first)) = chars.next() else {
+        return rest;
+    };
+
+    if first == '"' || first == '\'' || first == '`' {
+        let mut escaped = false;
+        for (i, c) in chars {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == first {
+                return &rest[i + c.len_utf8()..];
+            }
+        }
+        return "";
+    }
+
+    &rest[first.len_utf8()..]
+}
+
+/**
+ * Blank out comments and string literal contents, preserving line structure
+ *
+ * Every removed character becomes a space (newlines are kept) and string
+ * quotes stay in place, so byte offsets and line numbers still line up with
+ * the original content. Analyses that look for keywords or braces run on
+ * this so they don't trip over `if` inside a comment or `{` inside a string.
+ */
+pub fn strip_comments_and_strings(content: &str, language: &str) -> String {
+    strip(content, language, &mut Vec::new())
+}
+
+/// Byte ranges of the comments in `content`, delimiters included
+pub fn comment_ranges(content: &str, language: &str) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    strip(content, language, &mut ranges);
+    ranges
+}
+
+/// strip_comments_and_strings, recording each comment's range in `comments`
+fn strip(content: &str, language: &str, comments: &mut Vec<Range<usize>>) -> String {
+    let syntax = comment_syntax(language);
+    let multiline_strings = matches!(language, "rust" | "go" | "javascript" | "typescript");
+    let mut out = String::with_capacity(content.len());
+    let mut rest = content;
+
+    while let Some(c) = rest.chars().next() {
+        if let Some((open, close)) = syntax.block.iter().find(|(open, _)| rest.starts_with(open)) {
+            let end = rest[open.len()..]
+                .find(close)
+                .map(|i| open.len() + i + close.len())
+                .unwrap_or(rest.len());
+            let start = content.len() - rest.len();
+            comments.push(start..start + end);
+            blank(&rest[..end], &mut out);
+            rest = &rest[end..];
+        } else if syntax.line.iter().any(|marker| rest.starts_with(marker)) {
+            let end = rest.find('\n').unwrap_or(rest.len());
+            let start = content.len() - rest.len();
+            comments.push(start..start + end);
+            blank(&rest[..end], &mut out);
+            rest = &rest[end..];
+        } else if let Some(quote) = triple_quote(rest, language) {
+            let end = rest[3..].find(quote).map(|i| i + 6).unwrap_or(rest.len());
+            let body_end = if end >= 6 && rest[..end].ends_with(quote) {
+                end - 3
+            } else {
+                end
+            };
+            out.push_str(quote);
+            blank(&rest[3..body_end], &mut out);
+            out.push_str(&rest[body_end..end]);
+            rest = &rest[end..];
+        } else if is_string_start(rest, c, language) {
+            let open = c.len_utf8();
+            let end = string_end(rest, c, multiline_strings || c == '`');
+            let body_end = if end > open && rest[..end].ends_with(c) {
+                end - open
+            } else {
+                end
+            };
+            out.push(c);
+            blank(&rest[open..body_end], &mut out);
+            out.push_str(&rest[body_end..end]);
+            rest = &rest[end..];
+        } else {
+            out.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+
+    out
+}
+
+fn blank(text: &str, out: &mut String) {
+    for c in text.chars() {
+        if c == '\n' {
+            out.push('\n');
+        } else {
+            for _ in 0..c.len_utf8() {
+                out.push(' ');
+            }
+        }
+    }
+}
+
+pub(crate) fn triple_quote(rest: &str, language: &str) -> Option<&'static str> {
+    if language != "python" {
+        return None;
+    }
+    ["\"\"\"", "'''"].into_iter().find(|q| rest.starts_with(q))
+}
+
+pub(crate) fn is_string_start(rest: &str, c: char, language: &str) -> bool {
+    match c {
+        '"' => true,
+        '`' => matches!(language, "javascript" | "typescript" | "go"),
+        // Rust lifetimes ('a) look like unterminated char literals
+        '\'' if language == "rust" => {
+            let mut chars = rest.chars().skip(1);
+            matches!(
+                (chars.next(), chars.next()),
+                (Some('\\'), _) | (Some(_), Some('\''))
+            )
+        }
+        '\'' => true,
+        _ => false,
+    }
+}
+
+/// Byte offset just past the closing quote (or where an unterminated string gives up)
+pub(crate) fn string_end(rest: &str, quote: char, multiline: bool) -> usize {
+    let mut escaped = false;
+    for (i, c) in rest.char_indices().skip(1) {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == quote {
+            return i + c.len_utf8();
+        } else if c == '\n' && !multiline {
+            return i;
+        }
+    }
+    rest.len()
+}
@@ -0,0 +1,251 @@
+/*!
+ * Session comparison
+ * Metric deltas, file overlap, and behavior differences between two sets of events
+ */
+
+use crate::events::{sorted_by_time, ActivityEvent};
+use crate::experiments::{unit_metrics, METRICS};
+use ahash::{AHashMap, AHashSet};
+use napi_derive::napi;
+
+/// Transition differences reported, largest first
+const MAX_TRANSITIONS: usize = 10;
+
+/// One metric measured on both sessions
+#[napi(object)]
+pub struct MetricDelta {
+    pub metric: String,
+    pub a: Option<f64>,
+    pub b: Option<f64>,
+    /// b − a, when both sessions have the metric
+    pub difference: Option<f64>,
+    /// difference / a, when a is non-zero
+    pub relative_difference: Option<f64>,
+}
+
+/// A file touched in both sessions
+#[napi(object)]
+pub struct SharedFile {
+    pub file_path: String,
+    pub events_a: u32,
+    pub events_b: u32,
+}
+
+/// Which files the sessions have in common
+#[napi(object)]
+pub struct FileOverlap {
+    /// Shared files, most combined events first
+    pub shared: Vec<SharedFile>,
+    pub only_a: Vec<String>,
+    pub only_b: Vec<String>,
+    /// |shared| / |all files touched|, 0 when neither session touched a file
+    pub jaccard: f64,
+}
+
+/// How much of each session one behavior accounts for
+#[napi(object)]
+pub struct PatternDifference {
+    /// Event type (e.g. "prompt") or transition (e.g. "prompt → code_change")
+    pub pattern: String,
+    /// Share of the session's events (or transitions) matching the pattern
+    pub share_a: f64,
+    pub share_b: f64,
+    /// share_b − share_a
+    pub difference: f64,
+}
+
+/// Result of compare_sessions
+#[napi(object)]
+pub struct SessionComparison {
+    pub events_a: u32,
+    pub events_b: u32,
+    /// events, churn, ai_ratio, acceptance_rate, errors, active_minutes,
+    /// files_touched, and file_switches_per_hour
+    pub metrics: Vec<MetricDelta>,
+    pub files: FileOverlap,
+    /// Event type mix, largest absolute difference first
+    pub event_types: Vec<PatternDifference>,
+    /// Consecutive event type pairs with the largest absolute share differences
+    pub transitions: Vec<PatternDifference>,
+}
+
+/// Events per file path
+fn file_counts<'a>(events: &[&'a ActivityEvent]) -> AHashMap<&'a str, u32> {
+    let mut counts = AHashMap::new();
+    for event in events {
+        if let Some(path) = event.file_path.as_deref() {
+            *counts.entry(path).or_insert(0) += 1;
+        }
+    }
+    counts
+}
+
+/// Moves between different files per active hour, None without active time
+fn file_switches_per_hour(events: &[&ActivityEvent], active_minutes: Option<f64>) -> Option<f64> {
+    let minutes = active_minutes.filter(|m| *m > 0.0)?;
+    let mut switches = 0u32;
+    let mut previous: Option<&str> = None;
+    for path in events.iter().filter_map(|e| e.file_path.as_deref()) {
+        if previous.is_some_and(|p| p != path) {
+            switches += 1;
+        }
+        previous = Some(path);
+    }
+    Some(switches as f64 / (minutes / 60.0))
+}
+
+fn shares(patterns: impl Iterator<Item = String>) -> AHashMap<String, f64> {
+    let mut counts: AHashMap<String, f64> = AHashMap::new();
+    let mut total = 0.0;
+    for pattern in patterns {
+        *counts.entry(pattern).or_insert(0.0) += 1.0;
+        total += 1.0;
+    }
+    for count in counts.values_mut() {
+        *count /= total;
+    }
+    counts
+}
+
+fn pattern_differences(
+    a: AHashMap<String, f64>,
+    mut b: AHashMap<String, f64>,
+) -> Vec<PatternDifference> {
+    let mut differences: Vec<PatternDifference> = a
+        .into_iter()
+        .map(|(pattern, share_a)| {
+            let share_b = b.remove(&pattern).unwrap_or(0.0);
+            PatternDifference {
+                pattern,
+                share_a,
+                share_b,
+                difference: share_b - share_a,
+            }
+        })
+        .collect();
+    differences.extend(b.into_iter().map(|(pattern, share_b)| PatternDifference {
+        pattern,
+        share_a: 0.0,
+        share_b,
+        difference: share_b,
+    }));
+    differences.sort_by(|x, y| {
+        y.difference
+            .abs()
+            .total_cmp(&x.difference.abs())
+            .then_with(|| x.pattern.cmp(&y.pattern))
+    });
+    differences
+}
+
+fn transition_shares(events: &[&ActivityEvent]) -> AHashMap<String, f64> {
+    shares(
+        events
+            .windows(2)
+            .map(|pair| format!("{} → {}", pair[0].event_type, pair[1].event_type)),
+    )
+}
+
+/**
+ * Compare two sessions (or days) of activity
+ *
+ * Reports the same per-unit metrics as analyze_experiment plus files
+ * touched and file switching rate for each side, which files both sessions
+ * worked on, and how the mix of event types and type-to-type transitions
+ * differs, e.g. contrasting "a good day" with "a bad day".
+ *
+ * @param session_a - Events of the baseline session
+ * @param session_b - Events of the session compared against it
+ * @returns Metric deltas (b − a), file overlap, and behavior differences
+ */
+#[napi]
+pub fn compare_sessions(
+    session_a: Vec<ActivityEvent>,
+    session_b: Vec<ActivityEvent>,
+) -> SessionComparison {
+    let a = sorted_by_time(&session_a);
+    let b = sorted_by_time(&session_b);
+    let files_a = file_counts(&a);
+    let files_b = file_counts(&b);
+
+    let metrics_of = |events: &[&ActivityEvent], files: &AHashMap<&str, u32>| {
+        let mut values = unit_metrics(events).to_vec();
+        let active_minutes = values[METRICS.len() - 1];
+        values.push(Some(files.len() as f64));
+        values.push(file_switches_per_hour(events, active_minutes));
+        values
+    };
+    let names = METRICS
+        .iter()
+        .copied()
+        .chain(["files_touched", "file_switches_per_hour"]);
+    let metrics = names
+        .zip(
+            metrics_of(&a, &files_a)
+                .into_iter()
+                .zip(metrics_of(&b, &files_b)),
+        )
+        .map(|(metric, (a, b))| {
+            let difference = a.zip(b).map(|(a, b)| b - a);
+            MetricDelta {
+                metric: metric.to_string(),
+                a,
+                b,
+                difference,
+                relative_difference: difference.zip(a.filter(|a| *a != 0.0)).map(|(d, a)| d / a),
+            }
+        })
+        .collect();
+
+    let mut shared: Vec<SharedFile> = files_a
+        .iter()
+        .filter_map(|(path, events_a)| {
+            files_b.get(path).map(|events_b| SharedFile {
+                file_path: path.to_string(),
+                events_a: *events_a,
+                events_b: *events_b,
+            })
+        })
+        .collect();
+    shared.sort_by(|x, y| {
+        (y.events_a + y.events_b)
+            .cmp(&(x.events_a + x.events_b))
+            .then_with(|| x.file_path.cmp(&y.file_path))
+    });
+    let only = |from: &AHashMap<&str, u32>, other: &AHashMap<&str, u32>| {
+        let mut paths: Vec<String> = from
+            .keys()
+            .filter(|path| !other.contains_key(*path))
+            .map(|path| path.to_string())
+            .collect();
+        paths.sort_unstable();
+        paths
+    };
+    let all_files: AHashSet<&str> = files_a.keys().chain(files_b.keys()).copied().collect();
+    let files = FileOverlap {
+        jaccard: if all_files.is_empty() {
+            0.0
+        } else {
+            shared.len() as f64 / all_files.len() as f64
+        },
+        only_a: only(&files_a, &files_b),
+        only_b: only(&files_b, &files_a),
+        shared,
+    };
+
+    let event_types = pattern_differences(
+        shares(a.iter().map(|e| e.event_type.clone())),
+        shares(b.iter().map(|e| e.event_type.clone())),
+    );
+    let mut transitions = pattern_differences(transition_shares(&a), transition_shares(&b));
+    transitions.truncate(MAX_TRANSITIONS);
+
+    SessionComparison {
+        events_a: a.len() as u32,
+        events_b: b.len() as u32,
+        metrics,
+        files,
+        event_types,
+        transitions,
+    }
+}
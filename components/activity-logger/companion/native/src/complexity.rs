@@ -0,0 +1,600 @@
+/*!
+ * Code complexity metrics
+ * Cyclomatic and cognitive complexity per function and per file
+ */
+
+use crate::comments::strip_comments_and_strings;
+use crate::functions::{direct_children, find_functions, FunctionSpan};
+use ahash::{AHashMap, AHashSet};
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use similar::{ChangeTag, TextDiff};
+
+/// Cyclomatic complexity of one function
+#[napi(object)]
+pub struct FunctionComplexity {
+    pub name: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    /// 1 + decision points in the body, excluding nested functions
+    pub cyclomatic: u32,
+    /// Sonar-style cognitive complexity: structures weighted by nesting depth
+    pub cognitive: u32,
+}
+
+/// Complexity of a whole file
+#[napi(object)]
+pub struct ComplexityReport {
+    pub language: String,
+    pub functions: Vec<FunctionComplexity>,
+    /// Sum over functions plus decision points in top-level code
+    pub total_cyclomatic: u32,
+    pub max_cyclomatic: u32,
+    pub average_cyclomatic: f64,
+    /// Sum of per-function cognitive complexity
+    pub total_cognitive: u32,
+    pub max_cognitive: u32,
+}
+
+/**
+ * Calculate cyclomatic complexity per function and per file
+ *
+ * Counts decision points (if/elif, loops, case arms, catch/except, boolean
+ * operators, ternaries) in comment- and string-stripped code. Functions are
+ * located heuristically, see `find_functions` for supported languages.
+ *
+ * @param content - Source code
+ * @param language - Language name as returned by detect_language
+ * @returns ComplexityReport with per-function and per-file values
+ */
+#[napi]
+pub fn calculate_complexity(content: String, language: String) -> Result<ComplexityReport> {
+    let code = strip_comments_and_strings(&content, &language);
+    let spans = find_functions(&code, &language);
+
+    let functions: Vec<FunctionComplexity> = (0..spans.len())
+        .map(|i| FunctionComplexity {
+            name: spans[i].name.clone(),
+            start_line: spans[i].start_line,
+            end_line: spans[i].end_line,
+            cyclomatic: 1 + own_decision_points(&code, &spans, i, &language),
+            cognitive: cognitive_complexity(&code, &spans, i, &language),
+        })
+        .collect();
+
+    let in_functions: u32 = top_level(&spans)
+        .map(|span| decision_points(&code[span.start..span.end], &language))
+        .sum();
+    let top_level_decisions = decision_points(&code, &language).saturating_sub(in_functions);
+
+    let total_cyclomatic =
+        functions.iter().map(|f| f.cyclomatic).sum::<u32>() + top_level_decisions;
+    let max_cyclomatic = functions.iter().map(|f| f.cyclomatic).max().unwrap_or(0);
+    let average_cyclomatic = if functions.is_empty() {
+        0.0
+    } else {
+        functions.iter().map(|f| f.cyclomatic as f64).sum::<f64>() / functions.len() as f64
+    };
+
+    let total_cognitive = functions.iter().map(|f| f.cognitive).sum();
+    let max_cognitive = functions.iter().map(|f| f.cognitive).max().unwrap_or(0);
+
+    Ok(ComplexityReport {
+        language,
+        functions,
+        total_cyclomatic,
+        max_cyclomatic,
+        average_cyclomatic,
+        total_cognitive,
+        max_cognitive,
+    })
+}
+
+/// How one function's complexity changed between two versions of a file
+#[napi(object)]
+pub struct FunctionComplexityChange {
+    pub name: String,
+    /// "added", "removed", or "modified"
+    pub change: String,
+    /// Line range in the new version, or the old one for removed functions
+    pub start_line: u32,
+    pub end_line: u32,
+    pub before_cyclomatic: Option<u32>,
+    pub after_cyclomatic: Option<u32>,
+    /// After minus before, treating a missing side as 0
+    pub cyclomatic_delta: i32,
+    pub before_cognitive: Option<u32>,
+    pub after_cognitive: Option<u32>,
+    pub cognitive_delta: i32,
+    /// Lines of the function inserted or deleted by the edit
+    pub lines_changed: u32,
+}
+
+/// Result of compare_complexity
+#[napi(object)]
+pub struct ComplexityDelta {
+    pub language: String,
+    /// Changed functions, largest cyclomatic change first
+    pub functions: Vec<FunctionComplexityChange>,
+    pub before_total_cyclomatic: u32,
+    pub after_total_cyclomatic: u32,
+    pub cyclomatic_delta: i32,
+    pub before_total_cognitive: u32,
+    pub after_total_cognitive: u32,
+    pub cognitive_delta: i32,
+    /// One sentence per function whose cyclomatic complexity changed, e.g. "complexity of parse_config rose from 8 to 19"
+    pub summary: Vec<String>,
+}
+
+/// Whether each 0-based line of a version was inserted or deleted by the edit
+fn changed_lines(before: &str, after: &str) -> (Vec<bool>, Vec<bool>) {
+    // A final line gaining or losing its newline isn't an edit to that line
+    let terminated = |text: &str| {
+        if text.is_empty() || text.ends_with('\n') {
+            text.to_string()
+        } else {
+            format!("{}\n", text)
+        }
+    };
+    let (before, after) = (terminated(before), terminated(after));
+    let diff = TextDiff::from_lines(&before, &after);
+    let mut old = vec![false; before.lines().count() + 1];
+    let mut new = vec![false; after.lines().count() + 1];
+    for change in diff.iter_all_changes() {
+        match change.tag() {
+            ChangeTag::Delete => change
+                .old_index()
+                .and_then(|i| old.get_mut(i))
+                .map(|c| *c = true),
+            ChangeTag::Insert => change
+                .new_index()
+                .and_then(|i| new.get_mut(i))
+                .map(|c| *c = true),
+            ChangeTag::Equal => None,
+        };
+    }
+    (old, new)
+}
+
+fn count_changed(changed: &[bool], function: &FunctionComplexity) -> u32 {
+    let start = function.start_line.saturating_sub(1) as usize;
+    let end = (function.end_line as usize).min(changed.len());
+    changed
+        .get(start..end)
+        .map_or(0, |lines| lines.iter().filter(|c| **c).count() as u32)
+}
+
+/// Functions keyed by name and occurrence, so two `constructor`s in one file pair up in order
+fn by_occurrence(functions: &[FunctionComplexity]) -> Vec<((&str, usize), &FunctionComplexity)> {
+    let mut seen: AHashMap<&str, usize> = AHashMap::new();
+    functions
+        .iter()
+        .map(|function| {
+            let occurrence = seen.entry(function.name.as_str()).or_insert(0);
+            *occurrence += 1;
+            ((function.name.as_str(), *occurrence), function)
+        })
+        .collect()
+}
+
+/**
+ * Compare per-function complexity between two versions of a file
+ *
+ * Both versions are measured like calculate_complexity and functions are
+ * paired by name (in order, for repeated names). A function is "modified"
+ * when its complexity changed or the line diff touched its body; untouched
+ * functions are left out. A renamed function shows as removed plus added.
+ *
+ * @param before - Source before the edit
+ * @param after - Source after the edit
+ * @param language - Language name as returned by detect_language
+ * @returns Changed functions, file totals on both sides, and one summary sentence per complexity change
+ */
+#[napi]
+pub fn compare_complexity(
+    before: String,
+    after: String,
+    language: String,
+) -> Result<ComplexityDelta> {
+    let (old_changed, new_changed) = changed_lines(&before, &after);
+    let old = calculate_complexity(before, language.clone())?;
+    let new = calculate_complexity(after, language)?;
+
+    let old_functions = by_occurrence(&old.functions);
+    let new_functions = by_occurrence(&new.functions);
+    let old_index: AHashMap<(&str, usize), &FunctionComplexity> =
+        old_functions.iter().copied().collect();
+    let new_keys: AHashSet<(&str, usize)> = new_functions.iter().map(|(key, _)| *key).collect();
+
+    let mut functions = Vec::new();
+    for (key, after_fn) in &new_functions {
+        let before_fn = old_index.get(key).copied();
+        let lines_changed = count_changed(&new_changed, after_fn)
+            + before_fn.map_or(0, |f| count_changed(&old_changed, f));
+        let change = match before_fn {
+            None => "added",
+            Some(f)
+                if f.cyclomatic == after_fn.cyclomatic
+                    && f.cognitive == after_fn.cognitive
+                    && lines_changed == 0 =>
+            {
+                continue
+            }
+            Some(_) => "modified",
+        };
+        functions.push(FunctionComplexityChange {
+            name: after_fn.name.clone(),
+            change: change.to_string(),
+            start_line: after_fn.start_line,
+            end_line: after_fn.end_line,
+            before_cyclomatic: before_fn.map(|f| f.cyclomatic),
+            after_cyclomatic: Some(after_fn.cyclomatic),
+            cyclomatic_delta: after_fn.cyclomatic as i32
+                - before_fn.map_or(0, |f| f.cyclomatic as i32),
+            before_cognitive: before_fn.map(|f| f.cognitive),
+            after_cognitive: Some(after_fn.cognitive),
+            cognitive_delta: after_fn.cognitive as i32
+                - before_fn.map_or(0, |f| f.cognitive as i32),
+            lines_changed,
+        });
+    }
+    for (key, before_fn) in &old_functions {
+        if new_keys.contains(key) {
+            continue;
+        }
+        functions.push(FunctionComplexityChange {
+            name: before_fn.name.clone(),
+            change: "removed".to_string(),
+            start_line: before_fn.start_line,
+            end_line: before_fn.end_line,
+            before_cyclomatic: Some(before_fn.cyclomatic),
+            after_cyclomatic: None,
+            cyclomatic_delta: -(before_fn.cyclomatic as i32),
+            before_cognitive: Some(before_fn.cognitive),
+            after_cognitive: None,
+            cognitive_delta: -(before_fn.cognitive as i32),
+            lines_changed: count_changed(&old_changed, before_fn),
+        });
+    }
+    functions.sort_by(|a, b| {
+        b.cyclomatic_delta
+            .abs()
+            .cmp(&a.cyclomatic_delta.abs())
+            .then_with(|| a.start_line.cmp(&b.start_line))
+    });
+
+    let summary = functions
+        .iter()
+        .filter_map(|f| match (f.before_cyclomatic, f.after_cyclomatic) {
+            (Some(a), Some(b)) if b > a => {
+                Some(format!("complexity of {} rose from {} to {}", f.name, a, b))
+            }
+            (Some(a), Some(b)) if b < a => {
+                Some(format!("complexity of {} fell from {} to {}", f.name, a, b))
+            }
+            (None, Some(b)) => Some(format!("{} added with complexity {}", f.name, b)),
+            (Some(a), None) => Some(format!("{} removed (complexity {})", f.name, a)),
+            _ => None,
+        })
+        .collect();
+
+    Ok(ComplexityDelta {
+        language: new.language,
+        functions,
+        before_total_cyclomatic: old.total_cyclomatic,
+        after_total_cyclomatic: new.total_cyclomatic,
+        cyclomatic_delta: new.total_cyclomatic as i32 - old.total_cyclomatic as i32,
+        before_total_cognitive: old.total_cognitive,
+        after_total_cognitive: new.total_cognitive,
+        cognitive_delta: new.total_cognitive as i32 - old.total_cognitive as i32,
+        summary,
+    })
+}
+
+/// Spans not nested inside any other span
+pub(crate) fn top_level(spans: &[FunctionSpan]) -> impl Iterator<Item = &FunctionSpan> {
+    spans.iter().filter(move |span| {
+        !spans
+            .iter()
+            .any(|other| other.start < span.start && span.end <= other.end)
+    })
+}
+
+/// Decision points inside a function's own body, not counting nested functions
+fn own_decision_points(code: &str, spans: &[FunctionSpan], index: usize, language: &str) -> u32 {
+    let span = &spans[index];
+    let total = decision_points(&code[span.start..span.end], language);
+    let nested: u32 = direct_children(spans, index)
+        .into_iter()
+        .map(|child| decision_points(&code[spans[child].start..spans[child].end], language))
+        .sum();
+    total.saturating_sub(nested)
+}
+
+/// Count branching constructs in stripped code
+pub(crate) fn decision_points(code: &str, language: &str) -> u32 {
+    let keywords: &[&str] = match language {
+        "python" => &["if", "elif", "for", "while", "except", "and", "or", "case"],
+        "rust" => &["if", "for", "while"],
+        "go" => &["if", "for", "case"],
+        "ruby" => &[
+            "if", "elsif", "unless", "for", "while", "until", "when", "rescue", "and", "or",
+        ],
+        _ => &["if", "for", "while", "case", "catch"],
+    };
+    let bytes = code.as_bytes();
+    let mut count = 0;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let b = bytes[i];
+        if b.is_ascii_alphabetic() || b == b'_' {
+            let start = i;
+            while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_') {
+                i += 1;
+            }
+            let preceded_by_dot = start > 0 && bytes[start - 1] == b'.';
+            if !preceded_by_dot && keywords.contains(&&code[start..i]) {
+                count += 1;
+            }
+            continue;
+        }
+
+        let next = bytes.get(i + 1).copied();
+        match (b, next) {
+            (b'&', Some(b'&')) | (b'|', Some(b'|')) => {
+                count += 1;
+                i += 2;
+                continue;
+            }
+            // Each match arm is a branch in Rust
+            (b'=', Some(b'>')) if language == "rust" => count += 1,
+            // Ternary, excluding ?. ?? and Rust's ? operator
+            (b'?', _) if !matches!(language, "rust" | "python" | "go") => {
+                let prev = if i > 0 { bytes[i - 1] } else { b' ' };
+                if !matches!(next, Some(b'.') | Some(b'?') | Some(b':')) && prev != b'?' {
+                    count += 1;
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+
+    count
+}
+
+/// A function's own body with nested function bodies blanked out
+pub(crate) fn own_body(code: &str, spans: &[FunctionSpan], index: usize) -> String {
+    let span = &spans[index];
+    let mut body = code[span.start..span.end].to_string();
+    for child in direct_children(spans, index) {
+        let (start, end) = (
+            spans[child].start - span.start,
+            spans[child].end - span.start,
+        );
+        let blanked: String = body[start..end]
+            .chars()
+            .map(|c| if c == '\n' { '\n' } else { ' ' })
+            .collect();
+        body.replace_range(start..end, &blanked);
+    }
+    body
+}
+
+/// Cognitive complexity of one function, excluding nested functions
+fn cognitive_complexity(code: &str, spans: &[FunctionSpan], index: usize, language: &str) -> u32 {
+    let body = own_body(code, spans, index);
+    let score = if language == "python" {
+        cognitive_indented(&body)
+    } else {
+        cognitive_braced(&body, language)
+    };
+    score + u32::from(is_recursive(&body, &spans[index].name))
+}
+
+/// Whether the function's name is called again after its definition
+fn is_recursive(body: &str, name: &str) -> bool {
+    let mut calls = 0;
+    let mut rest = body;
+    while let Some(pos) = rest.find(name) {
+        let before = rest[..pos].chars().last();
+        let after = rest[pos + name.len()..].trim_start();
+        let is_word_start = !before.is_some_and(|c| c.is_alphanumeric() || c == '_');
+        if is_word_start && after.starts_with('(') {
+            calls += 1;
+        }
+        rest = &rest[pos + name.len()..];
+    }
+    // The first match is the definition's own signature
+    calls > 1
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Block {
+    Plain,
+    Structure,
+    DoLoop,
+}
+
+/// Cognitive complexity for brace-delimited languages
+fn cognitive_braced(body: &str, language: &str) -> u32 {
+    let bytes = body.as_bytes();
+    let mut score = 0u32;
+    let mut stack: Vec<Block> = Vec::new();
+    // Paren depth at which a control keyword is waiting for its `{`
+    let mut pending: Option<(i32, Block)> = None;
+    let mut paren_depth = 0i32;
+    let mut last_boolean: Option<u8> = None;
+    let mut last_closed = Block::Plain;
+    let mut previous_word = "";
+    let mut i = 0;
+    let ternary = !matches!(language, "rust" | "go");
+
+    while i < bytes.len() {
+        let b = bytes[i];
+        // The function's own braces are not nesting
+        let nesting = stack.iter().skip(1).filter(|b| **b != Block::Plain).count() as u32;
+
+        if b.is_ascii_alphabetic() || b == b'_' {
+            let start = i;
+            while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_') {
+                i += 1;
+            }
+            let word = &body[start..i];
+            let follows_dot = start > 0 && bytes[start - 1] == b'.';
+            if follows_dot {
+                previous_word = "";
+                continue;
+            }
+            match word {
+                "if" if previous_word == "else" => {}
+                "else" => {
+                    score += 1;
+                    pending = Some((paren_depth, Block::Structure));
+                }
+                "do" => {
+                    score += 1 + nesting;
+                    pending = Some((paren_depth, Block::DoLoop));
+                }
+                "while" if last_closed == Block::DoLoop => last_closed = Block::Plain,
+                "if" | "for" | "while" | "switch" | "catch" => {
+                    score += 1 + nesting;
+                    pending = Some((paren_depth, Block::Structure));
+                }
+                "match" | "loop" if language == "rust" => {
+                    score += 1 + nesting;
+                    pending = Some((paren_depth, Block::Structure));
+                }
+                "select" if language == "go" => {
+                    score += 1 + nesting;
+                    pending = Some((paren_depth, Block::Structure));
+                }
+                "goto" => score += 1,
+                "break" | "continue" => {
+                    let next = body[i..].trim_start_matches([' ', '\t']).chars().next();
+                    if next.is_some_and(|c| c.is_alphabetic() || c == '\'') {
+                        score += 1;
+                    }
+                }
+                _ => {}
+            }
+            previous_word = word;
+            continue;
+        }
+
+        if !b.is_ascii_whitespace() {
+            previous_word = "";
+        }
+        let next = bytes.get(i + 1).copied();
+        match b {
+            b'(' => paren_depth += 1,
+            b')' => paren_depth -= 1,
+            b'{' => {
+                let block = match pending {
+                    Some((depth, block)) if depth == paren_depth => {
+                        pending = None;
+                        block
+                    }
+                    _ => Block::Plain,
+                };
+                stack.push(block);
+                last_boolean = None;
+            }
+            b'}' => {
+                last_closed = stack.pop().unwrap_or(Block::Plain);
+                last_boolean = None;
+            }
+            b';' => {
+                if pending.is_some_and(|(depth, _)| depth == paren_depth) {
+                    pending = None;
+                }
+                last_boolean = None;
+            }
+            b'&' | b'|' if next == Some(b) => {
+                if last_boolean != Some(b) {
+                    score += 1;
+                }
+                last_boolean = Some(b);
+                i += 2;
+                continue;
+            }
+            b'?' if ternary => {
+                let prev = if i > 0 { bytes[i - 1] } else { b' ' };
+                if !matches!(next, Some(b'.') | Some(b'?') | Some(b':')) && prev != b'?' {
+                    score += 1 + nesting;
+                }
+            }
+            _ => {}
+        }
+        if b != b'}' && !b.is_ascii_whitespace() {
+            last_closed = Block::Plain;
+        }
+        i += 1;
+    }
+
+    score
+}
+
+/// Cognitive complexity for Python, using indentation for nesting
+fn cognitive_indented(body: &str) -> u32 {
+    let mut score = 0u32;
+    // (indent, increases nesting) for each open block
+    let mut stack: Vec<(usize, bool)> = Vec::new();
+
+    for (line_index, line) in body.lines().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        let indent = line.len() - line.trim_start().len();
+        while stack.last().is_some_and(|(open, _)| *open >= indent) {
+            stack.pop();
+        }
+        let nesting = stack.iter().filter(|(_, nests)| *nests).count() as u32;
+        let first_word: String = trimmed
+            .chars()
+            .take_while(|c| c.is_alphanumeric() || *c == '_')
+            .collect();
+        let opens_block = trimmed.ends_with(':');
+
+        let nests = match first_word.as_str() {
+            // The function's own signature
+            "def" | "async" if line_index == 0 => false,
+            "if" | "for" | "while" | "except" | "match" => {
+                score += 1 + nesting;
+                true
+            }
+            "elif" | "else" => {
+                score += 1;
+                true
+            }
+            "def" | "async" | "lambda" => true,
+            _ => false,
+        };
+        if opens_block {
+            stack.push((indent, nests));
+        }
+
+        // Conditional expressions inside the line, e.g. `x if cond else y`
+        let inline_if = trimmed
+            .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .skip(1)
+            .filter(|w| *w == "if")
+            .count() as u32;
+        score += inline_if * (1 + nesting);
+
+        // Each run of like boolean operators costs 1
+        let mut last_operator = "";
+        for word in trimmed.split(|c: char| !(c.is_alphanumeric() || c == '_')) {
+            if word == "and" || word == "or" {
+                if word != last_operator {
+                    score += 1;
+                }
+                last_operator = word;
+            }
+        }
+    }
+
+    score
+}
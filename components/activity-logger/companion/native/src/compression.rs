@@ -0,0 +1,271 @@
+/*!
+ * Payload compression
+ * Zstandard, optionally with a dictionary trained on past events, whole
+ * buffers at a time or as a stream
+ */
+
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use std::io::{Read, Write};
+use zstd::stream::raw::{InBuffer, Operation, OutBuffer};
+
+const DEFAULT_LEVEL: i32 = 3;
+/// zstd's own default dictionary size
+const DEFAULT_DICTIONARY_BYTES: u32 = 112_640;
+const DEFAULT_MAX_OUTPUT_BYTES: u32 = 256 * 1024 * 1024;
+
+/// Options for compress and ZstdCompressStream
+#[napi(object)]
+pub struct CompressOptions {
+    /// 1 (fastest) to 22 (smallest); negative levels trade ratio for speed (default: 3)
+    pub level: Option<i32>,
+    /// Dictionary from train_dictionary; the same one is needed to decompress
+    pub dictionary: Option<Buffer>,
+}
+
+/// Options for decompress and ZstdDecompressStream
+#[napi(object)]
+pub struct DecompressOptions {
+    /// Dictionary the data was compressed with
+    pub dictionary: Option<Buffer>,
+    /// Refuse to inflate past this many bytes (default: 256 MiB)
+    pub max_output_bytes: Option<u32>,
+}
+
+fn compression_error(action: &str, error: std::io::Error) -> Error {
+    Error::new(
+        Status::GenericFailure,
+        format!("Failed to {}: {}", action, error),
+    )
+}
+
+fn level_of(options: Option<&CompressOptions>) -> Result<i32> {
+    let level = options.and_then(|o| o.level).unwrap_or(DEFAULT_LEVEL);
+    let range = zstd::compression_level_range();
+    if !range.contains(&level) {
+        return Err(Error::new(
+            Status::InvalidArg,
+            format!(
+                "Compression level {} is outside {}..={}",
+                level,
+                range.start(),
+                range.end()
+            ),
+        ));
+    }
+    Ok(level)
+}
+
+/**
+ * Compress `data` into a single zstd frame
+ *
+ * With a dictionary, small payloads such as single events compress far
+ * better, since the repeated field names and values live in the
+ * dictionary instead of each frame.
+ *
+ * @param data - Bytes to compress
+ * @param options - Level and dictionary
+ * @returns The zstd frame
+ */
+#[napi]
+pub fn compress(data: Buffer, options: Option<CompressOptions>) -> Result<Buffer> {
+    let level = level_of(options.as_ref())?;
+    let dictionary = options.as_ref().and_then(|o| o.dictionary.as_deref());
+    let compressed = match dictionary {
+        Some(dictionary) => zstd::bulk::Compressor::with_dictionary(level, dictionary)
+            .and_then(|mut compressor| compressor.compress(&data)),
+        None => zstd::bulk::compress(&data, level),
+    };
+    compressed
+        .map(Buffer::from)
+        .map_err(|e| compression_error("compress", e))
+}
+
+/**
+ * Decompress zstd data, which may be several concatenated frames
+ *
+ * Throws if the data is corrupt, was compressed with a different
+ * dictionary, or would inflate past max_output_bytes.
+ *
+ * @param data - Output of compress or a compression stream
+ * @param options - Dictionary and output limit
+ * @returns The original bytes
+ */
+#[napi]
+pub fn decompress(data: Buffer, options: Option<DecompressOptions>) -> Result<Buffer> {
+    let max_output_bytes = options
+        .as_ref()
+        .and_then(|o| o.max_output_bytes)
+        .unwrap_or(DEFAULT_MAX_OUTPUT_BYTES) as u64;
+    let dictionary = options
+        .as_ref()
+        .and_then(|o| o.dictionary.as_deref())
+        .unwrap_or_default();
+    let mut output = Vec::new();
+    zstd::stream::read::Decoder::with_dictionary(&data[..], dictionary)
+        .and_then(|decoder| decoder.take(max_output_bytes + 1).read_to_end(&mut output))
+        .map_err(|e| compression_error("decompress", e))?;
+    if output.len() as u64 > max_output_bytes {
+        return Err(Error::new(
+            Status::GenericFailure,
+            format!(
+                "Decompressed data exceeds max_output_bytes ({})",
+                max_output_bytes
+            ),
+        ));
+    }
+    Ok(output.into())
+}
+
+/**
+ * Train a compression dictionary on sample payloads
+ *
+ * Samples should look like what will be compressed, e.g. a few thousand
+ * serialized events. zstd needs a reasonable number of samples and
+ * throws when there are too few to learn from.
+ *
+ * @param samples - Representative payloads
+ * @param maxBytes - Largest dictionary to produce (default: 112640)
+ * @returns The dictionary, to pass to compress and decompress
+ */
+#[napi]
+pub fn train_dictionary(samples: Vec<Buffer>, max_bytes: Option<u32>) -> Result<Buffer> {
+    if samples.is_empty() {
+        return Err(Error::new(
+            Status::InvalidArg,
+            "train_dictionary needs at least one sample",
+        ));
+    }
+    let samples: Vec<&[u8]> = samples.iter().map(|sample| &sample[..]).collect();
+    zstd::dict::from_samples(
+        &samples,
+        max_bytes.unwrap_or(DEFAULT_DICTIONARY_BYTES) as usize,
+    )
+    .map(Buffer::from)
+    .map_err(|e| compression_error("train dictionary", e))
+}
+
+/// Incremental zstd compression, one frame across many writes
+#[napi]
+pub struct ZstdCompressStream {
+    /// None once finished
+    encoder: Option<zstd::stream::write::Encoder<'static, Vec<u8>>>,
+}
+
+#[napi]
+impl ZstdCompressStream {
+    #[napi(constructor)]
+    pub fn new(options: Option<CompressOptions>) -> Result<Self> {
+        let level = level_of(options.as_ref())?;
+        let dictionary = options
+            .as_ref()
+            .and_then(|o| o.dictionary.as_deref())
+            .unwrap_or_default();
+        let encoder = zstd::stream::write::Encoder::with_dictionary(Vec::new(), level, dictionary)
+            .map_err(|e| compression_error("start compression", e))?;
+        Ok(ZstdCompressStream {
+            encoder: Some(encoder),
+        })
+    }
+
+    fn encoder(&mut self) -> Result<&mut zstd::stream::write::Encoder<'static, Vec<u8>>> {
+        self.encoder
+            .as_mut()
+            .ok_or_else(|| Error::new(Status::GenericFailure, "Compression stream is finished"))
+    }
+
+    /// Feed `chunk`; returns whatever compressed output is ready, possibly nothing
+    #[napi]
+    pub fn write(&mut self, chunk: Buffer) -> Result<Buffer> {
+        let encoder = self.encoder()?;
+        encoder
+            .write_all(&chunk)
+            .map_err(|e| compression_error("compress", e))?;
+        Ok(std::mem::take(encoder.get_mut()).into())
+    }
+
+    /// Emit everything written so far, so the reader can decode it without waiting for finish
+    #[napi]
+    pub fn flush(&mut self) -> Result<Buffer> {
+        let encoder = self.encoder()?;
+        encoder
+            .flush()
+            .map_err(|e| compression_error("compress", e))?;
+        Ok(std::mem::take(encoder.get_mut()).into())
+    }
+
+    /// End the frame; returns the remaining output. Later writes throw
+    #[napi]
+    pub fn finish(&mut self) -> Result<Buffer> {
+        self.encoder()?;
+        self.encoder
+            .take()
+            .unwrap()
+            .finish()
+            .map(Buffer::from)
+            .map_err(|e| compression_error("compress", e))
+    }
+}
+
+/// Incremental zstd decompression of data arriving in chunks
+#[napi]
+pub struct ZstdDecompressStream {
+    decoder: zstd::stream::raw::Decoder<'static>,
+    max_output_bytes: u64,
+    /// Bytes returned so far
+    produced: u64,
+}
+
+#[napi]
+impl ZstdDecompressStream {
+    #[napi(constructor)]
+    pub fn new(options: Option<DecompressOptions>) -> Result<Self> {
+        let dictionary = options
+            .as_ref()
+            .and_then(|o| o.dictionary.as_deref())
+            .unwrap_or_default();
+        let decoder = zstd::stream::raw::Decoder::with_dictionary(dictionary)
+            .map_err(|e| compression_error("start decompression", e))?;
+        Ok(ZstdDecompressStream {
+            decoder,
+            max_output_bytes: options
+                .and_then(|o| o.max_output_bytes)
+                .unwrap_or(DEFAULT_MAX_OUTPUT_BYTES) as u64,
+            produced: 0,
+        })
+    }
+
+    /// Feed `chunk`, which may be empty; returns whatever decompressed output is ready, possibly nothing
+    #[napi]
+    pub fn write(&mut self, chunk: Buffer) -> Result<Buffer> {
+        // zstd counts calls without input or output as stalls and eventually fails them
+        if chunk.is_empty() {
+            return Ok(Vec::new().into());
+        }
+        let mut input = InBuffer::around(&chunk);
+        let mut output = Vec::new();
+        let mut buffer = vec![0u8; zstd::zstd_safe::DCtx::out_size()];
+        loop {
+            let mut out = OutBuffer::around(&mut buffer[..]);
+            self.decoder
+                .run(&mut input, &mut out)
+                .map_err(|e| compression_error("decompress", e))?;
+            let written = out.pos();
+            self.produced += written as u64;
+            if self.produced > self.max_output_bytes {
+                return Err(Error::new(
+                    Status::GenericFailure,
+                    format!(
+                        "Decompressed data exceeds max_output_bytes ({})",
+                        self.max_output_bytes
+                    ),
+                ));
+            }
+            output.extend_from_slice(&buffer[..written]);
+            // Room left in the buffer means the decoder had nothing more to give for this input
+            if input.pos() == chunk.len() && written < buffer.len() {
+                return Ok(output.into());
+            }
+        }
+    }
+}
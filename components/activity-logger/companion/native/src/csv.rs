@@ -0,0 +1,291 @@
+/*!
+ * CSV export
+ * Stored events as spreadsheet rows, streamed from the store to a file
+ */
+
+use crate::events::{MS_PER_DAY, MS_PER_HOUR, MS_PER_MINUTE};
+use crate::ingest::{details_object, event_field};
+use crate::schema::camel;
+use crate::sessions::civil_from_days;
+use crate::store::{empty_query, EventQuery, EventStore, Row};
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use serde_json::Value;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+const DEFAULT_COLUMNS: [&str; 8] = [
+    "id",
+    "timestamp",
+    "type",
+    "session_id",
+    "workspace_path",
+    "file_path",
+    "lines_added",
+    "lines_removed",
+];
+/// Bytes buffered before a write to the file
+const CHUNK_BYTES: usize = 256 * 1024;
+
+/// Options for EventStore.export_csv
+#[napi(object)]
+#[derive(Default)]
+pub struct CsvOptions {
+    /// Columns in order (default: id, timestamp, type, session_id, workspace_path, file_path, lines_added, lines_removed); "event" is the whole event as JSON, and any other name is read from the event or its details
+    pub columns: Option<Vec<String>>,
+    /// Field separator, a single character (default: ",")
+    pub delimiter: Option<String>,
+    /// Write a header row of column names (default: true)
+    pub header: Option<bool>,
+    /// "iso" (default) for ISO-8601 UTC, or "epoch" for epoch milliseconds
+    pub timestamp_format: Option<String>,
+    /// Start the file with a UTF-8 byte order mark, so Excel reads non-ASCII text correctly (default: false)
+    pub bom: Option<bool>,
+}
+
+/// Result of EventStore.export_csv
+#[napi(object)]
+pub struct CsvExport {
+    pub path: String,
+    /// Events written, not counting the header
+    pub rows: u32,
+    /// Size of the written file
+    pub bytes: f64,
+}
+
+/// Where a column's value comes from
+enum Column {
+    Id,
+    Timestamp,
+    Type,
+    SessionId,
+    WorkspacePath,
+    FilePath,
+    Event,
+    /// A field of the event, at the top level or in `details`, under either spelling
+    Field(String, String),
+}
+
+impl Column {
+    fn of(name: &str) -> Self {
+        match name {
+            "id" => Column::Id,
+            "timestamp" => Column::Timestamp,
+            "type" => Column::Type,
+            "session_id" | "sessionId" => Column::SessionId,
+            "workspace_path" | "workspacePath" => Column::WorkspacePath,
+            "file_path" | "filePath" => Column::FilePath,
+            "event" => Column::Event,
+            field => Column::Field(field.to_string(), camel(field)),
+        }
+    }
+}
+
+/// ISO-8601 UTC with milliseconds, e.g. 2024-05-01T09:30:00.000Z
+fn iso_timestamp(ms: i64) -> String {
+    let (year, month, day) = civil_from_days(ms.div_euclid(MS_PER_DAY));
+    let time = ms.rem_euclid(MS_PER_DAY);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        time / MS_PER_HOUR,
+        time % MS_PER_HOUR / MS_PER_MINUTE,
+        time % MS_PER_MINUTE / 1000,
+        time % 1000
+    )
+}
+
+/// Write one field, quoted when it holds the delimiter, a quote, a line
+/// break, or edge whitespace a spreadsheet would trim
+fn write_field(out: &mut String, text: &str, delimiter: char) {
+    let quote = text.contains([delimiter, '"', '\n', '\r'])
+        || text.starts_with(char::is_whitespace)
+        || text.ends_with(char::is_whitespace);
+    if quote {
+        out.push('"');
+        out.push_str(&text.replace('"', "\"\""));
+        out.push('"');
+    } else {
+        out.push_str(text);
+    }
+}
+
+struct CsvFormat {
+    columns: Vec<Column>,
+    delimiter: char,
+    iso: bool,
+    /// Some column reads a field of the event
+    needs_event: bool,
+}
+
+impl CsvFormat {
+    fn of(options: &CsvOptions) -> Result<Self> {
+        let invalid = |message: String| Error::new(Status::InvalidArg, message);
+        let names: Vec<&str> = match &options.columns {
+            Some(names) if names.is_empty() => {
+                return Err(invalid("columns must name at least one column".to_string()))
+            }
+            Some(names) => names.iter().map(String::as_str).collect(),
+            None => DEFAULT_COLUMNS.to_vec(),
+        };
+        let delimiter = match options.delimiter.as_deref() {
+            None => ',',
+            Some(text) => {
+                let mut chars = text.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) if !matches!(c, '"' | '\n' | '\r') => c,
+                    _ => {
+                        return Err(invalid(format!(
+                            "delimiter '{}' must be a single character other than a quote or line break",
+                            text
+                        )))
+                    }
+                }
+            }
+        };
+        let iso = match options.timestamp_format.as_deref() {
+            None | Some("iso") => true,
+            Some("epoch") => false,
+            Some(other) => {
+                return Err(invalid(format!(
+                    "Unknown timestamp_format '{}' (expected iso or epoch)",
+                    other
+                )))
+            }
+        };
+        let columns: Vec<Column> = names.into_iter().map(Column::of).collect();
+        let needs_event = columns.iter().any(|c| matches!(c, Column::Field(..)));
+        Ok(CsvFormat {
+            columns,
+            delimiter,
+            iso,
+            needs_event,
+        })
+    }
+
+    fn header(&self, names: &[&str], out: &mut String) {
+        for (i, name) in names.iter().enumerate() {
+            if i > 0 {
+                out.push(self.delimiter);
+            }
+            write_field(out, name, self.delimiter);
+        }
+        out.push_str("\r\n");
+    }
+
+    fn row(&self, row: &Row, out: &mut String) {
+        let event = if self.needs_event {
+            serde_json::from_str::<Value>(&row.payload).unwrap_or(Value::Null)
+        } else {
+            Value::Null
+        };
+        let fields = event.as_object();
+        let details = fields.and_then(details_object);
+        for (i, column) in self.columns.iter().enumerate() {
+            if i > 0 {
+                out.push(self.delimiter);
+            }
+            let text = match column {
+                Column::Id => row.id.clone(),
+                Column::Timestamp if self.iso => iso_timestamp(row.timestamp),
+                Column::Timestamp => row.timestamp.to_string(),
+                Column::Type => row.event_type.clone(),
+                Column::SessionId => row.session_id.clone().unwrap_or_default(),
+                Column::WorkspacePath => row.workspace_path.clone().unwrap_or_default(),
+                Column::FilePath => row.file_path.clone().unwrap_or_default(),
+                Column::Event => row.payload.clone(),
+                Column::Field(name, camel_name) => match fields.and_then(|fields| {
+                    event_field(
+                        fields,
+                        details.as_ref(),
+                        &[name.as_str(), camel_name.as_str()],
+                    )
+                }) {
+                    None | Some(Value::Null) => String::new(),
+                    Some(Value::String(text)) => text,
+                    Some(value) => value.to_string(),
+                },
+            };
+            write_field(out, &text, self.delimiter);
+        }
+        out.push_str("\r\n");
+    }
+}
+
+#[napi]
+impl EventStore {
+    /**
+     * Write the events matching `filter` to a CSV file at `path`
+     *
+     * Rows are read from the store and written a chunk at a time, so memory
+     * stays flat however many match. Fields are quoted per RFC 4180 and rows
+     * end in CRLF. The file is written next to `path` and renamed into place
+     * once complete.
+     */
+    #[napi]
+    pub fn export_csv(
+        &self,
+        path: String,
+        filter: Option<EventQuery>,
+        options: Option<CsvOptions>,
+    ) -> Result<CsvExport> {
+        let filter = filter.unwrap_or_else(empty_query);
+        let options = options.unwrap_or_default();
+        let format = CsvFormat::of(&options)?;
+        let target = Path::new(&path);
+        let temporary = target.with_extension("csv.tmp");
+        let io_error = |action: &str, error: std::io::Error| {
+            Error::new(
+                Status::GenericFailure,
+                format!("Failed to {} {}: {}", action, temporary.display(), error),
+            )
+        };
+        let file = File::create(&temporary).map_err(|e| io_error("create", e))?;
+        let mut writer = BufWriter::with_capacity(CHUNK_BYTES, file);
+        let mut chunk = String::with_capacity(CHUNK_BYTES);
+        if options.bom.unwrap_or(false) {
+            chunk.push('\u{feff}');
+        }
+        if options.header.unwrap_or(true) {
+            let names: Vec<&str> = match &options.columns {
+                Some(names) => names.iter().map(String::as_str).collect(),
+                None => DEFAULT_COLUMNS.to_vec(),
+            };
+            format.header(&names, &mut chunk);
+        }
+        let mut rows = 0u32;
+        let written = self
+            .scan(&filter, |row| {
+                format.row(&row, &mut chunk);
+                rows += 1;
+                if chunk.len() >= CHUNK_BYTES {
+                    writer
+                        .write_all(chunk.as_bytes())
+                        .map_err(|e| io_error("write", e))?;
+                    chunk.clear();
+                }
+                Ok(())
+            })
+            .and_then(|_| {
+                writer
+                    .write_all(chunk.as_bytes())
+                    .and_then(|_| writer.flush())
+                    .map_err(|e| io_error("write", e))
+            });
+        drop(writer);
+        if let Err(e) = written {
+            let _ = fs::remove_file(&temporary);
+            return Err(e);
+        }
+        fs::rename(&temporary, target).map_err(|e| io_error("rename", e))?;
+        let bytes = fs::metadata(target).map_or(0, |m| m.len());
+        Ok(CsvExport {
+            path,
+            rows,
+            bytes: bytes as f64,
+        })
+    }
+}
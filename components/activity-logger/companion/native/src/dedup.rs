@@ -0,0 +1,218 @@
+/*!
+ * Streaming deduplication
+ * A Bloom filter of strings already seen, optionally persisted across restarts
+ */
+
+use crate::experiments::fnv1a;
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+
+/// Leading bytes of a saved filter, bumped when the layout changes
+const MAGIC: &[u8; 8] = b"CTBLOOM1";
+/// Magic, bit count, hash count, and item count
+const HEADER_LEN: usize = 8 + 8 + 4 + 8;
+
+/// Options for a Deduper
+#[napi(object)]
+pub struct DeduperOptions {
+    /// Distinct strings the filter is sized for; past it, false positives rise (default: 1,000,000)
+    pub capacity: Option<u32>,
+    /// Chance that a new string is reported as seen, at capacity (default: 0.001)
+    pub false_positive_rate: Option<f64>,
+    /// File to load the filter from, if it exists, and to save it to
+    pub path: Option<String>,
+}
+
+/// Bloom filter for dropping duplicate strings as they stream past
+#[napi]
+pub struct Deduper {
+    bits: Vec<u64>,
+    bit_count: u64,
+    hashes: u32,
+    count: i64,
+    path: Option<String>,
+}
+
+impl Deduper {
+    /// Bit positions of `value`, by double hashing two stable 64-bit hashes
+    fn positions(&self, value: &str) -> impl Iterator<Item = u64> {
+        let h1 = fnv1a(&[value]);
+        let h2 = fnv1a(&["dedup", value]) | 1;
+        let bit_count = self.bit_count;
+        (0..self.hashes as u64).map(move |i| h1.wrapping_add(i.wrapping_mul(h2)) % bit_count)
+    }
+
+    fn contains(&self, value: &str) -> bool {
+        self.positions(value)
+            .all(|bit| self.bits[(bit / 64) as usize] & 1 << (bit % 64) != 0)
+    }
+
+    /// Set the bits of `value`; returns whether any was unset
+    fn insert(&mut self, value: &str) -> bool {
+        let positions: Vec<u64> = self.positions(value).collect();
+        let mut new = false;
+        for bit in positions {
+            let word = &mut self.bits[(bit / 64) as usize];
+            let mask = 1 << (bit % 64);
+            new |= *word & mask == 0;
+            *word |= mask;
+        }
+        if new {
+            self.count += 1;
+        }
+        new
+    }
+
+    fn read(path: &str) -> Result<Option<Self>> {
+        let bytes = match std::fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => {
+                return Err(Error::new(
+                    Status::GenericFailure,
+                    format!("Failed to read {}: {}", path, e),
+                ))
+            }
+        };
+        let invalid = || Error::new(Status::InvalidArg, format!("Invalid dedup filter {}", path));
+        if bytes.len() < HEADER_LEN || &bytes[..8] != MAGIC {
+            return Err(invalid());
+        }
+        let bit_count = u64::from_le_bytes(bytes[8..16].try_into().unwrap());
+        let hashes = u32::from_le_bytes(bytes[16..20].try_into().unwrap());
+        let count = i64::from_le_bytes(bytes[20..28].try_into().unwrap());
+        let words = bit_count.div_ceil(64) as usize;
+        if bit_count == 0 || hashes == 0 || bytes.len() != HEADER_LEN + words * 8 {
+            return Err(invalid());
+        }
+        let bits = bytes[HEADER_LEN..]
+            .chunks_exact(8)
+            .map(|word| u64::from_le_bytes(word.try_into().unwrap()))
+            .collect();
+        Ok(Some(Deduper {
+            bits,
+            bit_count,
+            hashes,
+            count,
+            path: Some(path.to_string()),
+        }))
+    }
+}
+
+#[napi]
+impl Deduper {
+    /**
+     * Create a filter, or load the one saved at `path`
+     *
+     * A loaded filter keeps the size it was saved with; `capacity` and
+     * `false_positive_rate` only size a new one.
+     */
+    #[napi(constructor)]
+    pub fn new(options: Option<DeduperOptions>) -> Result<Self> {
+        let capacity = options
+            .as_ref()
+            .and_then(|o| o.capacity)
+            .unwrap_or(1_000_000);
+        let rate = options
+            .as_ref()
+            .and_then(|o| o.false_positive_rate)
+            .unwrap_or(0.001);
+        if !(rate > 0.0 && rate < 1.0) {
+            return Err(Error::new(
+                Status::InvalidArg,
+                format!("falsePositiveRate must be between 0 and 1, got {}", rate),
+            ));
+        }
+        let path = options.and_then(|o| o.path);
+        if let Some(path) = &path {
+            if let Some(loaded) = Deduper::read(path)? {
+                return Ok(loaded);
+            }
+        }
+        // Optimal sizing: m = -n ln p / (ln 2)^2 bits and k = m/n ln 2 hashes
+        let n = capacity.max(1) as f64;
+        let ln2 = std::f64::consts::LN_2;
+        let bit_count = (-n * rate.ln() / (ln2 * ln2)).ceil().max(64.0) as u64;
+        let hashes = ((bit_count as f64 / n) * ln2).round().clamp(1.0, 32.0) as u32;
+        Ok(Deduper {
+            bits: vec![0; bit_count.div_ceil(64) as usize],
+            bit_count,
+            hashes,
+            count: 0,
+            path,
+        })
+    }
+
+    /// Whether `value` was added before; false positives are possible, false negatives are not
+    #[napi]
+    pub fn seen(&self, value: String) -> bool {
+        self.contains(&value)
+    }
+
+    /// Record `value`; returns true if it is new, false if it was (probably) seen before
+    #[napi]
+    pub fn add(&mut self, value: String) -> bool {
+        self.insert(&value)
+    }
+
+    /// Record each value and return the new ones in order, dropping repeats within the batch too
+    #[napi]
+    pub fn filter(&mut self, values: Vec<String>) -> Vec<String> {
+        values
+            .into_iter()
+            .filter(|value| self.insert(value))
+            .collect()
+    }
+
+    /// Distinct values added, not counting those taken for false positives
+    #[napi(getter)]
+    pub fn count(&self) -> i64 {
+        self.count
+    }
+
+    /// Chance, at the current fill, that a new value is reported as seen
+    #[napi(getter)]
+    pub fn false_positive_rate(&self) -> f64 {
+        let set: u64 = self.bits.iter().map(|word| word.count_ones() as u64).sum();
+        (set as f64 / self.bit_count as f64).powi(self.hashes as i32)
+    }
+
+    /**
+     * Write the filter to `path`, or to the path it was created with
+     *
+     * The file is written to a temporary file first and renamed into place.
+     */
+    #[napi]
+    pub fn save(&self, path: Option<String>) -> Result<()> {
+        let Some(path) = path.or_else(|| self.path.clone()) else {
+            return Err(Error::new(
+                Status::InvalidArg,
+                "No path given and the Deduper was created without one",
+            ));
+        };
+        let mut bytes = Vec::with_capacity(HEADER_LEN + self.bits.len() * 8);
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&self.bit_count.to_le_bytes());
+        bytes.extend_from_slice(&self.hashes.to_le_bytes());
+        bytes.extend_from_slice(&self.count.to_le_bytes());
+        for word in &self.bits {
+            bytes.extend_from_slice(&word.to_le_bytes());
+        }
+        let temporary = format!("{}.tmp", path);
+        std::fs::write(&temporary, bytes)
+            .and_then(|_| std::fs::rename(&temporary, &path))
+            .map_err(|e| {
+                Error::new(
+                    Status::GenericFailure,
+                    format!("Failed to write {}: {}", path, e),
+                )
+            })
+    }
+
+    /// Forget every value
+    #[napi]
+    pub fn clear(&mut self) {
+        self.bits.fill(0);
+        self.count = 0;
+    }
+}
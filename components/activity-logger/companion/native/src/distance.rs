@@ -0,0 +1,227 @@
+/*!
+ * Edit distances
+ * Levenshtein, Damerau-Levenshtein, and Jaro-Winkler for short strings
+ */
+
+use ahash::AHashMap;
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use rayon::prelude::*;
+
+/// String metric selected by name, shared by the batch APIs
+#[derive(Clone, Copy)]
+pub(crate) enum Metric {
+    Levenshtein,
+    Damerau,
+    JaroWinkler,
+}
+
+impl Metric {
+    pub(crate) fn parse(name: Option<&str>) -> Result<Metric> {
+        match name.unwrap_or("levenshtein") {
+            "levenshtein" => Ok(Metric::Levenshtein),
+            "damerau" => Ok(Metric::Damerau),
+            "jaro-winkler" => Ok(Metric::JaroWinkler),
+            other => Err(Error::new(
+                Status::InvalidArg,
+                format!(
+                    "Unknown metric '{}' (expected levenshtein, damerau, or jaro-winkler)",
+                    other
+                ),
+            )),
+        }
+    }
+
+    /// Edit count for the distances, 0-1 similarity for Jaro-Winkler
+    pub(crate) fn apply(self, a: &str, b: &str) -> f64 {
+        let (a, b): (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+        match self {
+            Metric::Levenshtein => levenshtein_chars(&a, &b) as f64,
+            Metric::Damerau => damerau_chars(&a, &b) as f64,
+            Metric::JaroWinkler => jaro_winkler_chars(&a, &b),
+        }
+    }
+
+    /// 0-1 similarity: distances become 1 - edits / longer length
+    pub(crate) fn similarity(self, a: &[char], b: &[char]) -> f64 {
+        let edits = match self {
+            Metric::Levenshtein => levenshtein_chars(a, b),
+            Metric::Damerau => damerau_chars(a, b),
+            Metric::JaroWinkler => return jaro_winkler_chars(a, b),
+        };
+        match a.len().max(b.len()) {
+            0 => 1.0,
+            longest => 1.0 - edits as f64 / longest as f64,
+        }
+    }
+}
+
+/// Drop the common prefix and suffix, which never change a distance
+fn trim_common<'a>(a: &'a [char], b: &'a [char]) -> (&'a [char], &'a [char]) {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let (a, b) = (&a[prefix..], &b[prefix..]);
+    let suffix = a
+        .iter()
+        .rev()
+        .zip(b.iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    (&a[..a.len() - suffix], &b[..b.len() - suffix])
+}
+
+fn levenshtein_chars(a: &[char], b: &[char]) -> u32 {
+    let (a, b) = trim_common(a, b);
+    if a.is_empty() || b.is_empty() {
+        return a.len().max(b.len()) as u32;
+    }
+    let mut row: Vec<u32> = (0..=b.len() as u32).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i as u32 + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + (ca != cb) as u32;
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// Unrestricted Damerau-Levenshtein (Lowrance-Wagner), so edits may touch a transposed pair
+fn damerau_chars(a: &[char], b: &[char]) -> u32 {
+    let (a, b) = trim_common(a, b);
+    if a.is_empty() || b.is_empty() {
+        return a.len().max(b.len()) as u32;
+    }
+    let (n, m) = (a.len(), b.len());
+    let width = m + 2;
+    let max = (n + m) as u32;
+    // d[(i + 1) * width + (j + 1)] is the distance between a[..i] and b[..j]; row and column 0 hold max
+    let mut d = vec![0u32; (n + 2) * width];
+    d[0] = max;
+    for i in 0..=n {
+        d[(i + 1) * width] = max;
+        d[(i + 1) * width + 1] = i as u32;
+    }
+    for j in 0..=m {
+        d[j + 1] = max;
+        d[width + j + 1] = j as u32;
+    }
+    // Last row of a in which each character was seen
+    let mut last_row: AHashMap<char, usize> = AHashMap::new();
+    for i in 1..=n {
+        let mut last_column = 0;
+        for j in 1..=m {
+            let k = last_row.get(&b[j - 1]).copied().unwrap_or(0);
+            let l = last_column;
+            let cost = if a[i - 1] == b[j - 1] {
+                last_column = j;
+                0
+            } else {
+                1
+            };
+            let transposition = d[k * width + l] + (i - k - 1) as u32 + 1 + (j - l - 1) as u32;
+            d[(i + 1) * width + j + 1] = (d[i * width + j] + cost)
+                .min(d[(i + 1) * width + j] + 1)
+                .min(d[i * width + j + 1] + 1)
+                .min(transposition);
+        }
+        last_row.insert(a[i - 1], i);
+    }
+    d[(n + 1) * width + m + 1]
+}
+
+fn jaro_chars(a: &[char], b: &[char]) -> f64 {
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    let window = (a.len().max(b.len()) / 2).saturating_sub(1);
+    let mut b_matched = vec![false; b.len()];
+    let mut a_matches = Vec::new();
+    for (i, ca) in a.iter().enumerate() {
+        let from = i.saturating_sub(window);
+        let to = (i + window + 1).min(b.len());
+        for j in from..to {
+            if !b_matched[j] && b[j] == *ca {
+                b_matched[j] = true;
+                a_matches.push(*ca);
+                break;
+            }
+        }
+    }
+    if a_matches.is_empty() {
+        return 0.0;
+    }
+    let b_matches = b
+        .iter()
+        .zip(&b_matched)
+        .filter(|(_, matched)| **matched)
+        .map(|(c, _)| c);
+    let transpositions = a_matches
+        .iter()
+        .zip(b_matches)
+        .filter(|(x, y)| x != y)
+        .count() as f64
+        / 2.0;
+    let matches = a_matches.len() as f64;
+    (matches / a.len() as f64 + matches / b.len() as f64 + (matches - transpositions) / matches)
+        / 3.0
+}
+
+fn jaro_winkler_chars(a: &[char], b: &[char]) -> f64 {
+    let jaro = jaro_chars(a, b);
+    // Winkler's boost, only for strings already alike
+    if jaro <= 0.7 {
+        return jaro;
+    }
+    let prefix = a.iter().zip(b).take(4).take_while(|(x, y)| x == y).count() as f64;
+    jaro + prefix * 0.1 * (1.0 - jaro)
+}
+
+/// Fewest single-character insertions, deletions, and substitutions turning `a` into `b`
+#[napi]
+pub fn levenshtein(a: String, b: String) -> u32 {
+    Metric::Levenshtein.apply(&a, &b) as u32
+}
+
+/// Levenshtein distance that also counts swapping two adjacent characters as one edit
+#[napi]
+pub fn damerau_levenshtein(a: String, b: String) -> u32 {
+    Metric::Damerau.apply(&a, &b) as u32
+}
+
+/**
+ * Jaro-Winkler similarity
+ *
+ * Counts characters matching within a window and their transpositions,
+ * then boosts strings sharing a prefix of up to four characters, which
+ * suits identifiers and filenames that differ in a suffix.
+ *
+ * @returns 0 (nothing in common) to 1 (identical)
+ */
+#[napi]
+pub fn jaro_winkler(a: String, b: String) -> f64 {
+    Metric::JaroWinkler.apply(&a, &b)
+}
+
+/**
+ * One metric over many string pairs, in parallel
+ *
+ * Strings are compared by Unicode character, so pairs of short strings
+ * (identifiers, filenames, prompts) are the intended workload.
+ *
+ * @param pairs - [a, b] pairs
+ * @param metric - "levenshtein" (default), "damerau", or "jaro-winkler"
+ * @returns Edit counts for the distances, 0-1 similarities for jaro-winkler, in pair order
+ */
+#[napi(ts_args_type = "pairs: Array<[string, string]>, metric?: string | undefined | null")]
+pub fn batch_edit_distance(
+    pairs: Vec<(String, String)>,
+    metric: Option<String>,
+) -> Result<Vec<f64>> {
+    let metric = Metric::parse(metric.as_deref())?;
+    Ok(pairs.par_iter().map(|(a, b)| metric.apply(a, b)).collect())
+}
@@ -0,0 +1,446 @@
+/*!
+ * Local text embeddings
+ *
+ * Runs static embedding models in the model2vec format (for example
+ * minishlab/potion-base-8M) entirely on device: a WordPiece `tokenizer.json`
+ * and a `model.safetensors` table of one vector per token. A text's
+ * embedding is the mean of its token vectors, as model2vec computes it.
+ * Transformer models exported to ONNX need an inference runtime, which this
+ * build does not bundle; the vectors ignore word order and context in
+ * exchange, and rank below a small transformer's on sentence-level tasks.
+ */
+
+use ahash::AHashMap;
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use rayon::prelude::*;
+use serde_json::Value;
+use std::path::Path;
+
+/// Options for EmbeddingModel.load
+#[napi(object)]
+pub struct EmbeddingModelOptions {
+    /// Tokens embedded per text; the rest are ignored (default: 512)
+    pub max_tokens: Option<u32>,
+}
+
+/// Accented Latin-1 letters and their base letters, for tokenizers that strip accents
+const ACCENTED: &str = "àáâãäåçèéêëìíîïñòóôõöùúûüýÿÀÁÂÃÄÅÇÈÉÊËÌÍÎÏÑÒÓÔÕÖÙÚÛÜÝ";
+const UNACCENTED: &str = "aaaaaaceeeeiiiinooooouuuuyyAAAAAACEEEEIIIINOOOOOUUUUY";
+
+fn strip_accent(c: char) -> char {
+    ACCENTED
+        .chars()
+        .position(|a| a == c)
+        .and_then(|i| UNACCENTED.chars().nth(i))
+        .unwrap_or(c)
+}
+
+/// CJK ideographs, which BERT tokenizers split into single characters
+fn is_cjk(c: char) -> bool {
+    matches!(c as u32,
+        0x4E00..=0x9FFF | 0x3400..=0x4DBF | 0x20000..=0x2A6DF | 0x2A700..=0x2B73F
+        | 0x2B740..=0x2B81F | 0x2B820..=0x2CEAF | 0xF900..=0xFAFF | 0x2F800..=0x2FA1F)
+}
+
+fn is_punctuation(c: char) -> bool {
+    c.is_ascii_punctuation() || (!c.is_ascii() && !c.is_alphanumeric() && !c.is_whitespace())
+}
+
+/// (lowercase, strip accents) from a tokenizer.json normalizer, including Sequence normalizers
+fn normalizer_flags(normalizer: &Value, flags: &mut (bool, bool)) {
+    match normalizer.get("type").and_then(Value::as_str) {
+        Some("BertNormalizer") => {
+            let lowercase = normalizer
+                .get("lowercase")
+                .and_then(Value::as_bool)
+                .unwrap_or(true);
+            flags.0 |= lowercase;
+            // strip_accents: null follows lowercase
+            flags.1 |= normalizer
+                .get("strip_accents")
+                .and_then(Value::as_bool)
+                .unwrap_or(lowercase);
+        }
+        Some("Lowercase") => flags.0 = true,
+        Some("StripAccents") => flags.1 = true,
+        Some("Sequence") => {
+            for inner in normalizer
+                .get("normalizers")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+            {
+                normalizer_flags(inner, flags);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn invalid(path: &Path, message: impl std::fmt::Display) -> Error {
+    Error::new(
+        Status::InvalidArg,
+        format!("Invalid embedding model {}: {}", path.display(), message),
+    )
+}
+
+fn read(path: &Path) -> Result<Vec<u8>> {
+    std::fs::read(path).map_err(|e| {
+        Error::new(
+            Status::GenericFailure,
+            format!("Failed to read {}: {}", path.display(), e),
+        )
+    })
+}
+
+/// The token table of a safetensors file: (rows, dimensions, row-major f32 values)
+fn read_safetensors(path: &Path) -> Result<(usize, usize, Vec<f32>)> {
+    let bytes = read(path)?;
+    let header_len = bytes
+        .get(..8)
+        .map(|b| u64::from_le_bytes(b.try_into().unwrap()) as usize)
+        .filter(|len| 8 + len <= bytes.len())
+        .ok_or_else(|| invalid(path, "truncated safetensors header"))?;
+    let header: serde_json::Map<String, Value> =
+        serde_json::from_slice(&bytes[8..8 + header_len]).map_err(|e| invalid(path, e))?;
+    let data = &bytes[8 + header_len..];
+    let tensors: Vec<(&String, &Value)> = header
+        .iter()
+        .filter(|(k, _)| *k != "__metadata__")
+        .collect();
+    // model2vec names its table "embeddings"; otherwise accept a file holding one tensor
+    let (_, tensor) = tensors
+        .iter()
+        .find(|(name, _)| *name == "embeddings")
+        .or_else(|| (tensors.len() == 1).then(|| &tensors[0]))
+        .ok_or_else(|| invalid(path, "no 'embeddings' tensor"))?;
+    let shape: Vec<usize> = tensor
+        .get("shape")
+        .and_then(Value::as_array)
+        .map(|dims| {
+            dims.iter()
+                .filter_map(Value::as_u64)
+                .map(|d| d as usize)
+                .collect()
+        })
+        .unwrap_or_default();
+    let [rows, dimensions] = shape[..] else {
+        return Err(invalid(path, "the embeddings tensor must be 2-dimensional"));
+    };
+    let offsets: Vec<usize> = tensor
+        .get("data_offsets")
+        .and_then(Value::as_array)
+        .map(|o| {
+            o.iter()
+                .filter_map(Value::as_u64)
+                .map(|o| o as usize)
+                .collect()
+        })
+        .unwrap_or_default();
+    let [start, end] = offsets[..] else {
+        return Err(invalid(path, "missing data_offsets"));
+    };
+    let raw = data
+        .get(start..end)
+        .ok_or_else(|| invalid(path, "data_offsets out of range"))?;
+    let dtype = tensor.get("dtype").and_then(Value::as_str).unwrap_or("");
+    let values: Vec<f32> = match dtype {
+        "F32" => raw
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes(b.try_into().unwrap()))
+            .collect(),
+        "F16" => raw
+            .chunks_exact(2)
+            .map(|b| f16_to_f32(u16::from_le_bytes([b[0], b[1]])))
+            .collect(),
+        "BF16" => raw
+            .chunks_exact(2)
+            .map(|b| f32::from_bits((u16::from_le_bytes([b[0], b[1]]) as u32) << 16))
+            .collect(),
+        other => {
+            return Err(invalid(
+                path,
+                format!("unsupported dtype '{}' (expected F32, F16, or BF16)", other),
+            ))
+        }
+    };
+    if values.len() != rows * dimensions {
+        return Err(invalid(path, "tensor size doesn't match its shape"));
+    }
+    Ok((rows, dimensions, values))
+}
+
+fn f16_to_f32(half: u16) -> f32 {
+    let negative = half >> 15 == 1;
+    let exponent = ((half >> 10) & 0x1f) as u32;
+    let mantissa = (half & 0x3ff) as u32;
+    let sign = (negative as u32) << 31;
+    match exponent {
+        // Zero and subnormals: mantissa * 2^-24
+        0 => {
+            let magnitude = mantissa as f32 * 2f32.powi(-24);
+            if negative {
+                -magnitude
+            } else {
+                magnitude
+            }
+        }
+        0x1f => f32::from_bits(sign | 0x7f80_0000 | mantissa << 13),
+        _ => f32::from_bits(sign | (exponent + 112) << 23 | mantissa << 13),
+    }
+}
+
+/// A static embedding model loaded from disk
+#[napi]
+pub struct EmbeddingModel {
+    vocabulary: AHashMap<String, u32>,
+    unknown: Option<u32>,
+    prefix: String,
+    max_word_chars: usize,
+    lowercase: bool,
+    strip_accents: bool,
+    max_tokens: usize,
+    normalize: bool,
+    dimensions: usize,
+    vectors: Vec<f32>,
+}
+
+impl EmbeddingModel {
+    /// Words after BERT normalization and pre-tokenization
+    fn words(&self, text: &str) -> Vec<String> {
+        let mut words = Vec::new();
+        let mut word = String::new();
+        for c in text.chars() {
+            if c.is_control() && !c.is_whitespace() {
+                continue;
+            }
+            if c.is_whitespace() {
+                if !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+                continue;
+            }
+            let c = if self.strip_accents {
+                strip_accent(c)
+            } else {
+                c
+            };
+            if is_punctuation(c) || is_cjk(c) {
+                if !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+                words.push(c.to_string());
+                continue;
+            }
+            if self.lowercase {
+                word.extend(c.to_lowercase());
+            } else {
+                word.push(c);
+            }
+        }
+        if !word.is_empty() {
+            words.push(word);
+        }
+        words
+    }
+
+    /// Greedy longest-match-first WordPiece ids, with unknown words left out
+    fn token_ids(&self, text: &str) -> Vec<u32> {
+        let mut ids = Vec::new();
+        for word in self.words(text) {
+            let chars: Vec<char> = word.chars().collect();
+            if chars.len() > self.max_word_chars {
+                continue;
+            }
+            let mut pieces = Vec::new();
+            let mut start = 0;
+            while start < chars.len() {
+                let mut end = chars.len();
+                let mut found = None;
+                while end > start {
+                    let piece: String = chars[start..end].iter().collect();
+                    let piece = if start > 0 {
+                        format!("{}{}", self.prefix, piece)
+                    } else {
+                        piece
+                    };
+                    if let Some(id) = self.vocabulary.get(&piece) {
+                        found = Some(*id);
+                        break;
+                    }
+                    end -= 1;
+                }
+                let Some(id) = found else {
+                    pieces.clear();
+                    break;
+                };
+                pieces.push(id);
+                start = end;
+            }
+            ids.extend(pieces.into_iter().filter(|id| Some(*id) != self.unknown));
+            if ids.len() >= self.max_tokens {
+                ids.truncate(self.max_tokens);
+                break;
+            }
+        }
+        ids
+    }
+
+    fn embed_text(&self, text: &str) -> Vec<f32> {
+        let mut sum = vec![0f32; self.dimensions];
+        let ids = self.token_ids(text);
+        for id in &ids {
+            let row = *id as usize * self.dimensions;
+            for (total, value) in sum
+                .iter_mut()
+                .zip(&self.vectors[row..row + self.dimensions])
+            {
+                *total += value;
+            }
+        }
+        if !ids.is_empty() {
+            let count = ids.len() as f32;
+            sum.iter_mut().for_each(|v| *v /= count);
+        }
+        if self.normalize {
+            let norm = sum.iter().map(|v| v * v).sum::<f32>().sqrt();
+            if norm > 0.0 {
+                sum.iter_mut().for_each(|v| *v /= norm);
+            }
+        }
+        sum
+    }
+}
+
+#[napi]
+impl EmbeddingModel {
+    /**
+     * Load a model2vec model directory
+     *
+     * Reads `tokenizer.json` (a WordPiece tokenizer), `model.safetensors`
+     * (an F32, F16, or BF16 `embeddings` table), and, if present,
+     * `config.json` for its `normalize` flag. Nothing is downloaded.
+     */
+    #[napi(factory)]
+    pub fn load(path: String, options: Option<EmbeddingModelOptions>) -> Result<Self> {
+        let root = Path::new(&path);
+        let tokenizer_path = root.join("tokenizer.json");
+        let tokenizer: Value = serde_json::from_slice(&read(&tokenizer_path)?)
+            .map_err(|e| invalid(&tokenizer_path, e))?;
+        let model = tokenizer.get("model").unwrap_or(&Value::Null);
+        match model.get("type").and_then(Value::as_str) {
+            Some("WordPiece") => {}
+            other => {
+                return Err(invalid(
+                    &tokenizer_path,
+                    format!(
+                        "unsupported tokenizer model '{}' (expected WordPiece)",
+                        other.unwrap_or("none")
+                    ),
+                ))
+            }
+        }
+        let vocabulary: AHashMap<String, u32> = model
+            .get("vocab")
+            .and_then(Value::as_object)
+            .ok_or_else(|| invalid(&tokenizer_path, "missing model.vocab"))?
+            .iter()
+            .filter_map(|(token, id)| Some((token.clone(), id.as_u64()? as u32)))
+            .collect();
+        let unknown = model
+            .get("unk_token")
+            .and_then(Value::as_str)
+            .and_then(|token| vocabulary.get(token).copied());
+        let mut flags = (false, false);
+        if let Some(normalizer) = tokenizer.get("normalizer") {
+            normalizer_flags(normalizer, &mut flags);
+        }
+
+        let (rows, dimensions, vectors) = read_safetensors(&root.join("model.safetensors"))?;
+        if let Some(id) = vocabulary.values().find(|id| **id as usize >= rows) {
+            return Err(invalid(
+                root,
+                format!("token id {} has no row in the {}-row table", id, rows),
+            ));
+        }
+        let config_path = root.join("config.json");
+        let normalize = match std::fs::read(&config_path) {
+            Ok(bytes) => serde_json::from_slice::<Value>(&bytes)
+                .map_err(|e| invalid(&config_path, e))?
+                .get("normalize")
+                .and_then(Value::as_bool)
+                .unwrap_or(false),
+            Err(_) => false,
+        };
+        Ok(EmbeddingModel {
+            vocabulary,
+            unknown,
+            prefix: model
+                .get("continuing_subword_prefix")
+                .and_then(Value::as_str)
+                .unwrap_or("##")
+                .to_string(),
+            max_word_chars: model
+                .get("max_input_chars_per_word")
+                .and_then(Value::as_u64)
+                .unwrap_or(100) as usize,
+            lowercase: flags.0,
+            strip_accents: flags.1,
+            max_tokens: options.and_then(|o| o.max_tokens).unwrap_or(512) as usize,
+            normalize,
+            dimensions,
+            vectors,
+        })
+    }
+
+    /// Embed each text, in parallel; a text without known tokens gets the zero vector
+    #[napi]
+    pub fn embed(&self, texts: Vec<String>) -> Vec<Float32Array> {
+        let vectors: Vec<Vec<f32>> = texts.par_iter().map(|text| self.embed_text(text)).collect();
+        vectors.into_iter().map(Float32Array::new).collect()
+    }
+
+    /// Cosine similarity of two texts' embeddings, 0 when either is the zero vector
+    #[napi]
+    pub fn similarity(&self, a: String, b: String) -> f64 {
+        cosine(&self.embed_text(&a), &self.embed_text(&b))
+    }
+
+    /// Length of each embedding
+    #[napi(getter)]
+    pub fn dimensions(&self) -> u32 {
+        self.dimensions as u32
+    }
+
+    /// Tokens in the vocabulary
+    #[napi(getter)]
+    pub fn vocabulary_size(&self) -> u32 {
+        self.vocabulary.len() as u32
+    }
+}
+
+fn cosine(a: &[f32], b: &[f32]) -> f64 {
+    let (mut dot, mut a_norm, mut b_norm) = (0f64, 0f64, 0f64);
+    for (x, y) in a.iter().zip(b) {
+        dot += *x as f64 * *y as f64;
+        a_norm += *x as f64 * *x as f64;
+        b_norm += *y as f64 * *y as f64;
+    }
+    if a_norm == 0.0 || b_norm == 0.0 {
+        return 0.0;
+    }
+    dot / (a_norm.sqrt() * b_norm.sqrt())
+}
+
+/// Cosine similarity of two stored embeddings of the same length
+#[napi]
+pub fn cosine_similarity(a: Float32Array, b: Float32Array) -> Result<f64> {
+    if a.len() != b.len() {
+        return Err(Error::new(
+            Status::InvalidArg,
+            format!("Embeddings differ in length: {} and {}", a.len(), b.len()),
+        ));
+    }
+    Ok(cosine(&a, &b))
+}
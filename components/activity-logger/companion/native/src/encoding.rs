@@ -0,0 +1,239 @@
+/*!
+ * Text encoding detection and decoding
+ * BOMs, UTF-16 without a BOM, UTF-8, and single-byte Latin-1/Windows-1252 fallbacks
+ */
+
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+
+/// Result of detect_encoding
+#[napi(object)]
+pub struct EncodingDetection {
+    /// "utf-8", "utf-16le", "utf-16be", "utf-32le", "utf-32be", "windows-1252", or "iso-8859-1"
+    pub encoding: String,
+    /// 0-1; 1 for a BOM or valid non-ASCII UTF-8
+    pub confidence: f64,
+    /// The input starts with a byte order mark
+    pub bom: bool,
+}
+
+/// Result of decode_to_utf8
+#[napi(object)]
+pub struct DecodedText {
+    pub text: String,
+    pub encoding: String,
+    pub bom: bool,
+    /// Some bytes were invalid in the detected encoding and became U+FFFD
+    pub lossy: bool,
+}
+
+/// Bytes sampled when guessing UTF-16 without a BOM
+const SAMPLE: usize = 4096;
+
+/// Windows-1252 characters for bytes 0x80-0x9F; the undefined ones map to their C1 control, as browsers do
+const WINDOWS_1252_HIGH: [char; 32] = [
+    '\u{20AC}', '\u{81}', '\u{201A}', '\u{0192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{02C6}', '\u{2030}', '\u{0160}', '\u{2039}', '\u{0152}', '\u{8D}', '\u{017D}', '\u{8F}',
+    '\u{90}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{02DC}', '\u{2122}', '\u{0161}', '\u{203A}', '\u{0153}', '\u{9D}', '\u{017E}', '\u{0178}',
+];
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Utf32Le,
+    Utf32Be,
+    Windows1252,
+    Latin1,
+}
+
+impl Encoding {
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Encoding::Utf8 => "utf-8",
+            Encoding::Utf16Le => "utf-16le",
+            Encoding::Utf16Be => "utf-16be",
+            Encoding::Utf32Le => "utf-32le",
+            Encoding::Utf32Be => "utf-32be",
+            Encoding::Windows1252 => "windows-1252",
+            Encoding::Latin1 => "iso-8859-1",
+        }
+    }
+}
+
+/// Encoding, BOM length, and confidence of `bytes`
+pub(crate) fn detect(bytes: &[u8]) -> (Encoding, usize, f64) {
+    // UTF-32LE's BOM starts with UTF-16LE's, so it is checked first
+    let boms: [(&[u8], Encoding); 5] = [
+        (&[0xEF, 0xBB, 0xBF], Encoding::Utf8),
+        (&[0xFF, 0xFE, 0x00, 0x00], Encoding::Utf32Le),
+        (&[0x00, 0x00, 0xFE, 0xFF], Encoding::Utf32Be),
+        (&[0xFF, 0xFE], Encoding::Utf16Le),
+        (&[0xFE, 0xFF], Encoding::Utf16Be),
+    ];
+    if let Some((bom, encoding)) = boms.iter().find(|(bom, _)| bytes.starts_with(bom)) {
+        return (*encoding, bom.len(), 1.0);
+    }
+
+    // UTF-16 text that is mostly ASCII has a zero in every other byte
+    let sample = &bytes[..bytes.len().min(SAMPLE)];
+    if sample.len() >= 4 {
+        let pairs = sample.len() / 2;
+        let even_zeros = sample.iter().step_by(2).filter(|b| **b == 0).count();
+        let odd_zeros = sample
+            .iter()
+            .skip(1)
+            .step_by(2)
+            .filter(|b| **b == 0)
+            .count();
+        let share = |zeros: usize| zeros as f64 / pairs as f64;
+        if share(odd_zeros) > 0.3 && share(even_zeros) < 0.05 {
+            return (Encoding::Utf16Le, 0, share(odd_zeros).min(0.95));
+        }
+        if share(even_zeros) > 0.3 && share(odd_zeros) < 0.05 {
+            return (Encoding::Utf16Be, 0, share(even_zeros).min(0.95));
+        }
+    }
+
+    match std::str::from_utf8(bytes) {
+        Ok(text) => {
+            // Pure ASCII is valid in every candidate, so it says little about the encoding
+            let confidence = if text.is_ascii() { 0.8 } else { 1.0 };
+            (Encoding::Utf8, 0, confidence)
+        }
+        // Cut off mid-character at the end, e.g. a truncated read
+        Err(error) if error.error_len().is_none() && bytes.len() - error.valid_up_to() < 4 => {
+            (Encoding::Utf8, 0, 0.9)
+        }
+        Err(_) => {
+            if bytes.iter().any(|b| (0x80..=0x9F).contains(b)) {
+                (Encoding::Windows1252, 0, 0.6)
+            } else {
+                (Encoding::Latin1, 0, 0.6)
+            }
+        }
+    }
+}
+
+fn decode_utf16(bytes: &[u8], little_endian: bool) -> (String, bool) {
+    let units = bytes.chunks(2).map(|pair| match (pair, little_endian) {
+        ([a, b], true) => u16::from_le_bytes([*a, *b]),
+        ([a, b], false) => u16::from_be_bytes([*a, *b]),
+        // A dangling odd byte can't be a code unit
+        _ => 0xFFFD,
+    });
+    let mut lossy = !bytes.len().is_multiple_of(2);
+    let text = char::decode_utf16(units)
+        .map(|unit| {
+            unit.unwrap_or_else(|_| {
+                lossy = true;
+                char::REPLACEMENT_CHARACTER
+            })
+        })
+        .collect();
+    (text, lossy)
+}
+
+fn decode_utf32(bytes: &[u8], little_endian: bool) -> (String, bool) {
+    let mut lossy = !bytes.len().is_multiple_of(4);
+    let text = bytes
+        .chunks_exact(4)
+        .map(|quad| {
+            let quad = [quad[0], quad[1], quad[2], quad[3]];
+            let value = if little_endian {
+                u32::from_le_bytes(quad)
+            } else {
+                u32::from_be_bytes(quad)
+            };
+            char::from_u32(value).unwrap_or_else(|| {
+                lossy = true;
+                char::REPLACEMENT_CHARACTER
+            })
+        })
+        .collect();
+    (text, lossy)
+}
+
+/// Decode `bytes` to UTF-8: the detected encoding, the BOM flag, the text, and whether any bytes were replaced
+pub(crate) fn decode(bytes: &[u8]) -> (Encoding, bool, String, bool) {
+    let (encoding, bom_len, _) = detect(bytes);
+    let body = &bytes[bom_len..];
+    let (text, lossy) = match encoding {
+        Encoding::Utf8 => match String::from_utf8_lossy(body) {
+            std::borrow::Cow::Borrowed(text) => (text.to_string(), false),
+            std::borrow::Cow::Owned(text) => (text, true),
+        },
+        Encoding::Utf16Le => decode_utf16(body, true),
+        Encoding::Utf16Be => decode_utf16(body, false),
+        Encoding::Utf32Le => decode_utf32(body, true),
+        Encoding::Utf32Be => decode_utf32(body, false),
+        Encoding::Windows1252 => (
+            body.iter()
+                .map(|&b| match b {
+                    0x80..=0x9F => WINDOWS_1252_HIGH[(b - 0x80) as usize],
+                    _ => b as char,
+                })
+                .collect(),
+            false,
+        ),
+        // Latin-1 bytes are the first 256 code points
+        Encoding::Latin1 => (body.iter().map(|&b| b as char).collect(), false),
+    };
+    (encoding, bom_len > 0, text, lossy)
+}
+
+/// Text of a string-or-bytes argument, decoding bytes with decode_to_utf8's rules
+pub(crate) fn text_input(input: Either<String, Buffer>) -> (String, &'static str) {
+    match input {
+        Either::A(text) => (text, Encoding::Utf8.name()),
+        Either::B(bytes) => {
+            let (encoding, _, text, _) = decode(&bytes);
+            (text, encoding.name())
+        }
+    }
+}
+
+/**
+ * Detect the text encoding of raw bytes
+ *
+ * A byte order mark decides outright (UTF-8, UTF-16LE/BE, UTF-32LE/BE).
+ * Without one, UTF-16 is recognized by the zero byte in every other
+ * position that mostly-ASCII text has, then the bytes are checked as
+ * UTF-8. Anything else is treated as a single-byte Western encoding:
+ * Windows-1252 when bytes 0x80-0x9F appear, ISO-8859-1 otherwise.
+ * Multi-byte legacy encodings (Shift_JIS, GBK, EUC-KR) are not detected.
+ *
+ * @param bytes - Raw file content
+ * @returns Encoding name, confidence, and whether a BOM is present
+ */
+#[napi]
+pub fn detect_encoding(bytes: Buffer) -> EncodingDetection {
+    let (encoding, bom_len, confidence) = detect(&bytes);
+    EncodingDetection {
+        encoding: encoding.name().to_string(),
+        confidence,
+        bom: bom_len > 0,
+    }
+}
+
+/**
+ * Decode raw bytes to a UTF-8 string
+ *
+ * Uses the encoding detect_encoding picks and strips any BOM. Invalid
+ * sequences become U+FFFD and set `lossy`.
+ *
+ * @param bytes - Raw file content
+ * @returns Decoded text, the encoding used, and BOM and lossy flags
+ */
+#[napi]
+pub fn decode_to_utf8(bytes: Buffer) -> DecodedText {
+    let (encoding, bom, text, lossy) = decode(&bytes);
+    DecodedText {
+        text,
+        encoding: encoding.name().to_string(),
+        bom,
+        lossy,
+    }
+}
@@ -0,0 +1,128 @@
+/*!
+ * Stateful diff engine with a content-addressed result cache
+ *
+ * The logger frequently diffs identical content twice (retries, duplicate
+ * save events). Results are cached by content hash so repeats return
+ * without recomputing.
+ */
+
+use crate::budget::{Budget, InputBudget};
+use crate::{calculate_diff, DiffResult};
+use ahash::RandomState;
+use lru::LruCache;
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use std::num::NonZeroUsize;
+
+const DEFAULT_CACHE_CAPACITY: u32 = 256;
+
+/// Cache key: content hashes and lengths of both sides plus the diff options and budget
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct DiffCacheKey {
+    before_hash: u64,
+    before_len: usize,
+    after_hash: u64,
+    after_len: usize,
+    threshold: Option<i32>,
+    include_unified: bool,
+    normalize_eol: bool,
+    budget: Option<Budget>,
+}
+
+/// Cache counters for a DiffEngine
+#[napi(object)]
+pub struct DiffCacheStats {
+    pub hits: u32,
+    pub misses: u32,
+    pub size: u32,
+    pub capacity: u32,
+}
+
+/// Diff calculator that remembers recent results
+#[napi]
+pub struct DiffEngine {
+    cache: LruCache<DiffCacheKey, DiffResult>,
+    hasher: RandomState,
+    hits: u32,
+    misses: u32,
+}
+
+#[napi]
+impl DiffEngine {
+    /**
+     * Create an engine caching up to `capacity` results (default: 256)
+     */
+    #[napi(constructor)]
+    pub fn new(capacity: Option<u32>) -> Self {
+        let capacity = NonZeroUsize::new(capacity.unwrap_or(DEFAULT_CACHE_CAPACITY) as usize)
+            .unwrap_or(NonZeroUsize::MIN);
+        DiffEngine {
+            cache: LruCache::new(capacity),
+            hasher: RandomState::new(),
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /**
+     * Same as calculate_diff, returning a cached result when this exact
+     * content and option combination was diffed recently
+     */
+    #[napi]
+    pub fn calculate_diff(
+        &mut self,
+        text1: String,
+        text2: String,
+        threshold: Option<i32>,
+        include_unified: Option<bool>,
+        normalize_eol: Option<bool>,
+        budget: Option<InputBudget>,
+    ) -> Result<DiffResult> {
+        let key = DiffCacheKey {
+            before_hash: self.hasher.hash_one(&text1),
+            before_len: text1.len(),
+            after_hash: self.hasher.hash_one(&text2),
+            after_len: text2.len(),
+            threshold,
+            include_unified: include_unified.unwrap_or(false),
+            normalize_eol: normalize_eol.unwrap_or(false),
+            budget: Budget::parse(budget.as_ref())?,
+        };
+
+        if let Some(result) = self.cache.get(&key) {
+            self.hits += 1;
+            return Ok(result.clone());
+        }
+
+        self.misses += 1;
+        let result = calculate_diff(
+            Either::A(text1),
+            Either::A(text2),
+            threshold,
+            include_unified,
+            normalize_eol,
+            budget,
+        )?;
+        self.cache.put(key, result.clone());
+        Ok(result)
+    }
+
+    /// Hit/miss counters and current occupancy
+    #[napi]
+    pub fn cache_stats(&self) -> DiffCacheStats {
+        DiffCacheStats {
+            hits: self.hits,
+            misses: self.misses,
+            size: self.cache.len() as u32,
+            capacity: self.cache.cap().get() as u32,
+        }
+    }
+
+    /// Drop all cached results and reset counters
+    #[napi]
+    pub fn clear_cache(&mut self) {
+        self.cache.clear();
+        self.hits = 0;
+        self.misses = 0;
+    }
+}
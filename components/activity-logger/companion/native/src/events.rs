@@ -0,0 +1,66 @@
+/*!
+ * Telemetry event model shared by the analysis APIs
+ */
+
+use napi_derive::napi;
+
+pub const MS_PER_MINUTE: i64 = 60_000;
+pub const MS_PER_HOUR: i64 = 60 * MS_PER_MINUTE;
+pub const MS_PER_DAY: i64 = 24 * MS_PER_HOUR;
+
+/// Gap between events that ends a session unless the caller overrides it
+pub const DEFAULT_IDLE_GAP_MINUTES: u32 = 30;
+
+/// A single activity event as recorded by the logger
+#[napi(object)]
+#[derive(Clone, Default)]
+pub struct ActivityEvent {
+    pub id: Option<String>,
+    pub session_id: Option<String>,
+    /// Milliseconds since the Unix epoch (JS `Date.now()`)
+    pub timestamp: i64,
+    /// Event kind, e.g. "code_change", "prompt", "terminal_command", "error"
+    #[napi(js_name = "type")]
+    pub event_type: String,
+    pub file_path: Option<String>,
+    pub lines_added: Option<i32>,
+    pub lines_removed: Option<i32>,
+    pub ai_generated: Option<bool>,
+    /// Free-form description (error message, prompt text, command line)
+    pub details: Option<String>,
+    /// For error events: whether the error was later resolved
+    pub resolved: Option<bool>,
+    /// Git branch checked out when the event was captured
+    pub branch: Option<String>,
+    /// Agent run that produced the event, when the capture layer knows it
+    pub run_id: Option<String>,
+    /// Outcome of a tool call or test run: "success" or "failure"
+    pub status: Option<String>,
+    /// User verdict on an AI edit: "good", "bad", or "reverted"
+    pub label: Option<String>,
+    /// Model that produced the event, e.g. "claude-sonnet" or "gpt-4o"
+    pub model: Option<String>,
+    /// How the prompt was phrased or issued, e.g. "chat", "inline", "agent"
+    pub prompt_style: Option<String>,
+    /// For AI suggestions: whether the user accepted it
+    pub accepted: Option<bool>,
+    /// Time from request to model response
+    pub latency_ms: Option<f64>,
+    /// Billed cost of the model call in US dollars
+    pub cost_usd: Option<f64>,
+    /// Of lines_added, how many were still present at the last survival check
+    pub lines_surviving: Option<i32>,
+}
+
+impl ActivityEvent {
+    pub fn is_error(&self) -> bool {
+        self.event_type.contains("error")
+    }
+}
+
+/// Return events ordered by timestamp without mutating the caller's array
+pub fn sorted_by_time(events: &[ActivityEvent]) -> Vec<&ActivityEvent> {
+    let mut sorted: Vec<&ActivityEvent> = events.iter().collect();
+    sorted.sort_by_key(|event| event.timestamp);
+    sorted
+}
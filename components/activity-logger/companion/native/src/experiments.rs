@@ -0,0 +1,254 @@
+/*!
+ * A/B experiments
+ * Deterministic bucketing of units into variants and two-sample comparisons of their metrics
+ */
+
+use crate::events::{sorted_by_time, ActivityEvent, DEFAULT_IDLE_GAP_MINUTES, MS_PER_MINUTE};
+use crate::sessions::segment;
+use crate::stats::{cohens_d, mean, welch_t_test};
+use ahash::AHashMap;
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+
+const DEFAULT_VARIANTS: [&str; 2] = ["control", "treatment"];
+const SIGNIFICANCE_LEVEL: f64 = 0.05;
+pub(crate) const METRICS: [&str; 6] = [
+    "events",
+    "churn",
+    "ai_ratio",
+    "acceptance_rate",
+    "errors",
+    "active_minutes",
+];
+
+/// Experiment definition shared by assign_bucket and analyze_experiment
+#[napi(object)]
+pub struct ExperimentConfig {
+    pub name: String,
+    /// Variant names; the first is the control (default: ["control", "treatment"])
+    pub variants: Option<Vec<String>>,
+    /// Extra hash input so the same experiment name can be re-run with fresh assignments
+    pub salt: Option<String>,
+    /// What gets bucketed: "session" (default), "run", or "branch"
+    pub unit: Option<String>,
+}
+
+/// Per-variant sample size
+#[napi(object)]
+pub struct VariantSummary {
+    pub variant: String,
+    pub units: u32,
+    pub events: u32,
+}
+
+/// One metric of one variant compared against the control
+#[napi(object)]
+pub struct MetricComparison {
+    pub metric: String,
+    pub variant: String,
+    pub control_mean: f64,
+    pub variant_mean: f64,
+    pub difference: f64,
+    /// difference / control_mean, when the control mean is non-zero
+    pub relative_difference: Option<f64>,
+    /// Welch's t statistic (variant − control)
+    pub t_statistic: f64,
+    pub degrees_of_freedom: f64,
+    /// Two-sided p-value
+    pub p_value: f64,
+    /// Cohen's d
+    pub effect_size: f64,
+    /// p_value < 0.05
+    pub significant: bool,
+}
+
+/// Result of analyze_experiment
+#[napi(object)]
+pub struct ExperimentAnalysis {
+    pub experiment: String,
+    pub control: String,
+    pub variants: Vec<VariantSummary>,
+    /// Metrics where both groups have at least two units, in variant then metric order
+    pub comparisons: Vec<MetricComparison>,
+}
+
+/// 64-bit FNV-1a, stable across platforms and releases unlike the std hasher
+pub(crate) fn fnv1a(parts: &[&str]) -> u64 {
+    let mut hash = 0xcbf2_9ce4_8422_2325u64;
+    for (i, part) in parts.iter().enumerate() {
+        if i > 0 {
+            hash = (hash ^ 0x1f).wrapping_mul(0x0100_0000_01b3);
+        }
+        for byte in part.bytes() {
+            hash = (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    // Finalize so nearby unit ids land far apart
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    hash ^ (hash >> 33)
+}
+
+fn variant_index(unit_id: &str, experiment: &str, salt: &str, variants: usize) -> usize {
+    ((fnv1a(&[salt, experiment, unit_id]) as u128 * variants as u128) >> 64) as usize
+}
+
+fn variant_names(variants: Option<Vec<String>>) -> Result<Vec<String>> {
+    let variants =
+        variants.unwrap_or_else(|| DEFAULT_VARIANTS.iter().map(|v| v.to_string()).collect());
+    if variants.len() < 2 {
+        return Err(Error::new(
+            Status::InvalidArg,
+            "An experiment needs at least two variants".to_string(),
+        ));
+    }
+    Ok(variants)
+}
+
+/**
+ * Assign a unit to an experiment variant
+ *
+ * Hashes salt, experiment, and unit id, so the same unit always lands in
+ * the same variant and different experiments are independent.
+ *
+ * @param unit_id - Session, run, or user id being bucketed
+ * @param experiment - Experiment name
+ * @param salt - Optional salt to reshuffle assignments
+ * @param variants - Variant names (default: ["control", "treatment"])
+ * @returns The assigned variant name
+ */
+#[napi]
+pub fn assign_bucket(
+    unit_id: String,
+    experiment: String,
+    salt: Option<String>,
+    variants: Option<Vec<String>>,
+) -> Result<String> {
+    let mut variants = variant_names(variants)?;
+    let index = variant_index(
+        &unit_id,
+        &experiment,
+        salt.as_deref().unwrap_or(""),
+        variants.len(),
+    );
+    Ok(variants.swap_remove(index))
+}
+
+/// Per-unit metric values in METRICS order, None when a unit has no data for that metric
+pub(crate) fn unit_metrics(events: &[&ActivityEvent]) -> [Option<f64>; 6] {
+    let count = events.len() as f64;
+    let churn: i64 = events
+        .iter()
+        .map(|e| e.lines_added.unwrap_or(0) as i64 + e.lines_removed.unwrap_or(0) as i64)
+        .sum();
+    let ai = events
+        .iter()
+        .filter(|e| e.ai_generated == Some(true))
+        .count() as f64;
+    let decided: Vec<bool> = events.iter().filter_map(|e| e.accepted).collect();
+    let acceptance = (!decided.is_empty())
+        .then(|| decided.iter().filter(|a| **a).count() as f64 / decided.len() as f64);
+    let errors = events.iter().filter(|e| e.is_error()).count() as f64;
+    let idle_gap_ms = DEFAULT_IDLE_GAP_MINUTES as i64 * MS_PER_MINUTE;
+    let active_ms: i64 = segment(events, idle_gap_ms)
+        .iter()
+        .map(|s| s.duration_ms)
+        .sum();
+
+    [
+        Some(count),
+        Some(churn as f64),
+        (count > 0.0).then(|| ai / count),
+        acceptance,
+        Some(errors),
+        Some(active_ms as f64 / MS_PER_MINUTE as f64),
+    ]
+}
+
+/**
+ * Analyze an A/B experiment
+ *
+ * Buckets each unit with assign_bucket's hashing, computes per-unit metrics
+ * (events, churn, AI ratio, acceptance rate, errors, active minutes), and
+ * compares every variant against the control with Welch's t-test and
+ * Cohen's d. Events without a unit id are ignored.
+ */
+#[napi]
+pub fn analyze_experiment(
+    events: Vec<ActivityEvent>,
+    experiment: ExperimentConfig,
+) -> Result<ExperimentAnalysis> {
+    let variants = variant_names(experiment.variants)?;
+    let salt = experiment.salt.unwrap_or_default();
+    let unit_of: fn(&ActivityEvent) -> Option<&str> = match experiment.unit.as_deref() {
+        None | Some("session") => |e| e.session_id.as_deref(),
+        Some("run") => |e| e.run_id.as_deref(),
+        Some("branch") => |e| e.branch.as_deref(),
+        Some(other) => {
+            return Err(Error::new(
+                Status::InvalidArg,
+                format!("Unknown unit '{}', expected session, run, or branch", other),
+            ))
+        }
+    };
+
+    let mut units: AHashMap<&str, Vec<&ActivityEvent>> = AHashMap::new();
+    for event in sorted_by_time(&events) {
+        if let Some(unit) = unit_of(event) {
+            units.entry(unit).or_default().push(event);
+        }
+    }
+
+    // samples[variant][metric] = per-unit values
+    let mut samples = vec![vec![Vec::new(); METRICS.len()]; variants.len()];
+    let mut summaries: Vec<VariantSummary> = variants
+        .iter()
+        .map(|variant| VariantSummary {
+            variant: variant.clone(),
+            units: 0,
+            events: 0,
+        })
+        .collect();
+    for (unit, unit_events) in &units {
+        let index = variant_index(unit, &experiment.name, &salt, variants.len());
+        summaries[index].units += 1;
+        summaries[index].events += unit_events.len() as u32;
+        for (metric, value) in unit_metrics(unit_events).into_iter().enumerate() {
+            if let Some(value) = value {
+                samples[index][metric].push(value);
+            }
+        }
+    }
+
+    let mut comparisons = Vec::new();
+    for (index, variant) in variants.iter().enumerate().skip(1) {
+        for (metric, name) in METRICS.iter().enumerate() {
+            let (control, treated) = (&samples[0][metric], &samples[index][metric]);
+            let Some(test) = welch_t_test(control, treated) else {
+                continue;
+            };
+            let (control_mean, variant_mean) = (mean(control), mean(treated));
+            let difference = variant_mean - control_mean;
+            comparisons.push(MetricComparison {
+                metric: name.to_string(),
+                variant: variant.clone(),
+                control_mean,
+                variant_mean,
+                difference,
+                relative_difference: (control_mean != 0.0).then(|| difference / control_mean),
+                t_statistic: test.t,
+                degrees_of_freedom: test.degrees_of_freedom,
+                p_value: test.p_value,
+                effect_size: cohens_d(control, treated),
+                significant: test.p_value < SIGNIFICANCE_LEVEL,
+            });
+        }
+    }
+
+    Ok(ExperimentAnalysis {
+        experiment: experiment.name,
+        control: variants[0].clone(),
+        variants: summaries,
+        comparisons,
+    })
+}
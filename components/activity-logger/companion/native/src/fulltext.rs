@@ -0,0 +1,821 @@
+/*!
+ * Full-text session search
+ * BM25-ranked inverted index over session events and snippets, with phrase queries and highlighting
+ */
+
+use ahash::{AHashMap, AHashSet};
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Write};
+
+/// BM25 term frequency saturation
+const K1: f64 = 1.2;
+/// BM25 length normalization
+const B: f64 = 0.75;
+/// Snippet length, in characters
+const SNIPPET_CHARS: usize = 160;
+/// Vocabulary terms a `prefix*` query term expands to at most
+const PREFIX_EXPANSIONS: usize = 64;
+/// Format version written by save; version 1 files still load
+const FORMAT_VERSION: u32 = 2;
+/// save rewrites the file instead of appending once it would hold this
+/// many times more entries than there are documents
+const COMPACT_RATIO: usize = 2;
+/// Entries a saved file may hold before COMPACT_RATIO applies
+const COMPACT_MIN_ENTRIES: usize = 1024;
+
+/// A piece of session text to index: an event summary, a prompt, a diff hunk
+#[napi(object)]
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchDocument {
+    /// Unique per document; indexing the same id again replaces it
+    pub id: String,
+    pub session_id: String,
+    pub text: String,
+    /// Milliseconds since the epoch
+    pub timestamp: Option<f64>,
+    /// Event type or source, e.g. "code_change", "prompt"
+    pub kind: Option<String>,
+}
+
+/// Options for SearchIndex.search
+#[napi(object)]
+pub struct SearchOptions {
+    /// Hits returned (default: 10)
+    pub limit: Option<u32>,
+    /// Only search documents of this session
+    pub session_id: Option<String>,
+    /// Return only the best hit per session (default: false)
+    pub group_by_session: Option<bool>,
+}
+
+/// Character range to highlight within a snippet
+#[napi(object)]
+pub struct HighlightRange {
+    pub start: u32,
+    pub end: u32,
+}
+
+/// A ranked document
+#[napi(object)]
+pub struct SearchHit {
+    pub id: String,
+    pub session_id: String,
+    /// BM25 score; higher is better
+    pub score: f64,
+    pub timestamp: Option<f64>,
+    pub kind: Option<String>,
+    /// Excerpt around the densest cluster of matched terms
+    pub snippet: String,
+    /// Matched terms within the snippet, in characters
+    pub highlights: Vec<HighlightRange>,
+}
+
+/// Size counters for a SearchIndex
+#[napi(object)]
+pub struct SearchIndexStats {
+    pub documents: u32,
+    pub sessions: u32,
+    /// Distinct indexed terms
+    pub terms: u32,
+}
+
+/// The first line of a saved index; all of it in version 1
+#[derive(Serialize, Deserialize)]
+struct Header {
+    version: u32,
+}
+
+/// A version 1 file: one JSON object with every document
+#[derive(Deserialize)]
+struct SavedIndex {
+    documents: Vec<SearchDocument>,
+}
+
+/// A line after the header of a version 2 file; later lines win
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum Entry<D = SearchDocument> {
+    Add(D),
+    Remove(String),
+}
+
+/// An indexed term occurrence: the term, its token position, and its byte range in the text
+struct Token {
+    term: String,
+    position: u32,
+    start: usize,
+    end: usize,
+}
+
+/// Byte ranges of the words in an identifier: `retryLogic` -> retry, Logic; `max_retries` -> max, retries
+fn identifier_parts(word: &str) -> Vec<(usize, usize)> {
+    let mut parts = Vec::new();
+    let chars: Vec<(usize, char)> = word.char_indices().collect();
+    let mut start = None;
+    for (i, &(offset, c)) in chars.iter().enumerate() {
+        if c == '_' {
+            if let Some(s) = start.take() {
+                parts.push((s, offset));
+            }
+            continue;
+        }
+        if let Some(s) = start {
+            let previous = chars[i - 1].1;
+            let boundary = (previous.is_lowercase() && c.is_uppercase())
+                || (previous.is_uppercase()
+                    && c.is_uppercase()
+                    && chars
+                        .get(i + 1)
+                        .is_some_and(|(_, next)| next.is_lowercase()))
+                || previous.is_ascii_digit() != c.is_ascii_digit();
+            if boundary {
+                parts.push((s, offset));
+                start = Some(offset);
+            }
+        } else {
+            start = Some(offset);
+        }
+    }
+    if let Some(s) = start {
+        parts.push((s, word.len()));
+    }
+    parts
+}
+
+/// Lowercased terms with positions and offsets
+///
+/// An identifier is indexed whole and as its words, the first word sharing
+/// the identifier's position, so `retryLogic` matches both `retrylogic` and
+/// the phrase "retry logic".
+fn tokenize(text: &str) -> Vec<Token> {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    let mut tokens = Vec::new();
+    let mut position = 0;
+    let mut rest = text;
+    let mut offset = 0;
+    while let Some(skip) = rest.find(is_word) {
+        let start = offset + skip;
+        let word_len = text[start..]
+            .find(|c: char| !is_word(c))
+            .unwrap_or(text.len() - start);
+        let word = &text[start..start + word_len];
+        offset = start + word_len;
+        rest = &text[offset..];
+        if word.trim_matches('_').is_empty() {
+            continue;
+        }
+        let parts = identifier_parts(word);
+        if parts.len() > 1 {
+            tokens.push(Token {
+                term: word.to_lowercase().replace('_', ""),
+                position,
+                start,
+                end: offset,
+            });
+        }
+        for (from, to) in parts {
+            tokens.push(Token {
+                term: word[from..to].to_lowercase(),
+                position,
+                start: start + from,
+                end: start + to,
+            });
+            position += 1;
+        }
+    }
+    tokens
+}
+
+/// Lowercased query words of a piece of query text, split like tokenize but without identifier splitting
+fn query_terms(text: &str) -> Vec<String> {
+    text.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .map(|word| word.trim_matches('_').to_lowercase().replace('_', ""))
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Occur {
+    Should,
+    Must,
+    MustNot,
+}
+
+enum Clause {
+    /// Single term or a phrase of consecutive terms
+    Terms(Vec<String>),
+    /// Every vocabulary term starting with this
+    Prefix(String),
+}
+
+/// Parse `retry +backoff -flaky "exponential backoff" retr*`
+fn parse_query(query: &str) -> Vec<(Occur, Clause)> {
+    let mut clauses = Vec::new();
+    let mut rest = query.trim_start();
+    while !rest.is_empty() {
+        let (occur, body) = match rest.as_bytes()[0] {
+            b'+' => (Occur::Must, &rest[1..]),
+            b'-' => (Occur::MustNot, &rest[1..]),
+            _ => (Occur::Should, rest),
+        };
+        let (raw, remainder, quoted) = if let Some(inner) = body.strip_prefix('"') {
+            match inner.find('"') {
+                Some(close) => (&inner[..close], &inner[close + 1..], true),
+                None => (inner, "", true),
+            }
+        } else {
+            let end = body.find(char::is_whitespace).unwrap_or(body.len());
+            (&body[..end], &body[end..], false)
+        };
+        rest = remainder.trim_start();
+        if !quoted && raw.len() > 1 && raw.ends_with('*') {
+            let prefix = query_terms(&raw[..raw.len() - 1]).concat();
+            if !prefix.is_empty() {
+                clauses.push((occur, Clause::Prefix(prefix)));
+            }
+            continue;
+        }
+        let terms = query_terms(raw);
+        if !terms.is_empty() {
+            clauses.push((occur, Clause::Terms(terms)));
+        }
+    }
+    clauses
+}
+
+struct Indexed {
+    document: SearchDocument,
+    /// Token count, for length normalization
+    length: u32,
+}
+
+/// A document in a term's posting list
+///
+/// Positions aren't kept: phrases are checked against the text of the
+/// documents that have every word, which keeps a posting at 8 bytes.
+#[derive(Clone, Copy)]
+struct Posting {
+    slot: u32,
+    /// Occurrences of the term in the document
+    frequency: u32,
+}
+
+/// Full-text index over session documents, ranked with BM25
+#[napi]
+pub struct SearchIndex {
+    documents: Vec<Option<Indexed>>,
+    slots: AHashMap<String, u32>,
+    free: Vec<u32>,
+    /// Term -> documents containing it, by slot
+    postings: AHashMap<String, Vec<Posting>>,
+    total_length: u64,
+    /// The file save last wrote and how many entries it holds
+    journal: Option<(String, usize)>,
+    /// Ids added, replaced, or removed since that save
+    changed: AHashSet<String>,
+}
+
+impl SearchIndex {
+    fn insert(&mut self, document: SearchDocument) {
+        self.remove_document(&document.id);
+        let tokens = tokenize(&document.text);
+        let slot = match self.free.pop() {
+            Some(slot) => slot,
+            None => {
+                self.documents.push(None);
+                self.documents.len() as u32 - 1
+            }
+        };
+        let mut frequencies: AHashMap<&str, u32> = AHashMap::new();
+        for token in &tokens {
+            *frequencies.entry(token.term.as_str()).or_insert(0) += 1;
+        }
+        for (term, frequency) in frequencies {
+            let posting = match self.postings.get_mut(term) {
+                Some(posting) => posting,
+                None => self.postings.entry(term.to_string()).or_default(),
+            };
+            // A new slot is the largest, so bulk indexing only appends
+            let at = posting.partition_point(|p| p.slot < slot);
+            posting.insert(at, Posting { slot, frequency });
+        }
+        let length = tokens.last().map_or(0, |t| t.position + 1);
+        self.total_length += length as u64;
+        self.changed.insert(document.id.clone());
+        self.slots.insert(document.id.clone(), slot);
+        self.documents[slot as usize] = Some(Indexed { document, length });
+    }
+
+    fn remove_document(&mut self, id: &str) -> bool {
+        let Some(slot) = self.slots.remove(id) else {
+            return false;
+        };
+        self.changed.insert(id.to_string());
+        if let Some(indexed) = self.documents[slot as usize].take() {
+            self.total_length -= indexed.length as u64;
+            let terms: AHashSet<String> = tokenize(&indexed.document.text)
+                .into_iter()
+                .map(|token| token.term)
+                .collect();
+            for term in terms {
+                if let Some(posting) = self.postings.get_mut(&term) {
+                    if let Ok(at) = posting.binary_search_by_key(&slot, |p| p.slot) {
+                        posting.remove(at);
+                    }
+                    if posting.is_empty() {
+                        self.postings.remove(&term);
+                    }
+                }
+            }
+        }
+        self.free.push(slot);
+        true
+    }
+
+    fn bm25(&self, document_frequency: usize, frequency: usize, length: u32) -> f64 {
+        let documents = self.slots.len() as f64;
+        let df = document_frequency as f64;
+        let idf = (1.0 + (documents - df + 0.5) / (df + 0.5)).ln();
+        let average = (self.total_length as f64 / documents.max(1.0)).max(1.0);
+        let tf = frequency as f64;
+        idf * tf * (K1 + 1.0) / (tf + K1 * (1.0 - B + B * length as f64 / average))
+    }
+
+    /// Documents matching a clause, with its score for each
+    fn clause_scores(&self, clause: &Clause) -> AHashMap<u32, f64> {
+        let mut scores: AHashMap<u32, f64> = AHashMap::new();
+        match clause {
+            Clause::Prefix(prefix) => {
+                for (_, posting) in self.expansions(prefix) {
+                    for p in posting {
+                        *scores.entry(p.slot).or_insert(0.0) +=
+                            self.bm25(posting.len(), p.frequency as usize, self.length_of(p.slot));
+                    }
+                }
+            }
+            Clause::Terms(terms) => {
+                let Some(lists) = terms
+                    .iter()
+                    .map(|term| self.postings.get(term))
+                    .collect::<Option<Vec<_>>>()
+                else {
+                    return scores;
+                };
+                let shortest = lists.iter().min_by_key(|list| list.len()).unwrap();
+                for p in shortest.iter() {
+                    let frequency = if lists.len() == 1 {
+                        p.frequency as usize
+                    } else if lists
+                        .iter()
+                        .all(|list| list.binary_search_by_key(&p.slot, |q| q.slot).is_ok())
+                    {
+                        self.documents[p.slot as usize]
+                            .as_ref()
+                            .map_or(0, |indexed| phrase_count(&indexed.document.text, terms))
+                    } else {
+                        0
+                    };
+                    if frequency == 0 {
+                        continue;
+                    }
+                    let length = self.length_of(p.slot);
+                    let score = lists
+                        .iter()
+                        .map(|list| self.bm25(list.len(), frequency, length))
+                        .sum();
+                    scores.insert(p.slot, score);
+                }
+            }
+        }
+        scores
+    }
+
+    /// The first PREFIX_EXPANSIONS vocabulary terms, in order, starting with `prefix`
+    fn expansions(&self, prefix: &str) -> Vec<(&String, &Vec<Posting>)> {
+        let mut expansions: Vec<_> = self
+            .postings
+            .iter()
+            .filter(|(term, _)| term.starts_with(prefix))
+            .collect();
+        expansions.sort_unstable_by_key(|(term, _)| *term);
+        expansions.truncate(PREFIX_EXPANSIONS);
+        expansions
+    }
+
+    /// Write every document to `path` through a temporary file, starting a new journal there
+    fn rewrite(&mut self, path: &str) -> std::io::Result<()> {
+        let temporary = format!("{}.tmp", path);
+        let mut file = std::io::BufWriter::new(std::fs::File::create(&temporary)?);
+        serde_json::to_writer(
+            &mut file,
+            &Header {
+                version: FORMAT_VERSION,
+            },
+        )?;
+        file.write_all(b"\n")?;
+        for indexed in self.documents.iter().flatten() {
+            serde_json::to_writer(&mut file, &Entry::Add(&indexed.document))?;
+            file.write_all(b"\n")?;
+        }
+        file.into_inner()?.sync_all()?;
+        std::fs::rename(&temporary, path)?;
+        self.journal = Some((path.to_string(), self.slots.len()));
+        Ok(())
+    }
+
+    /// Append what changed since the last save to the journal at `path`
+    fn append(&mut self, path: &str) -> std::io::Result<()> {
+        let mut lines = Vec::new();
+        for id in &self.changed {
+            let entry = match self.slots.get(id) {
+                Some(&slot) => match &self.documents[slot as usize] {
+                    Some(indexed) => Entry::Add(&indexed.document),
+                    None => continue,
+                },
+                None => Entry::Remove(id.clone()),
+            };
+            serde_json::to_writer(&mut lines, &entry)?;
+            lines.push(b'\n');
+        }
+        // No create: a journal deleted since the last save has lost its header
+        let mut file = std::fs::OpenOptions::new().append(true).open(path)?;
+        file.write_all(&lines)?;
+        file.sync_data()?;
+        if let Some((_, entries)) = &mut self.journal {
+            *entries += self.changed.len();
+        }
+        Ok(())
+    }
+
+    fn length_of(&self, slot: u32) -> u32 {
+        self.documents[slot as usize]
+            .as_ref()
+            .map_or(0, |indexed| indexed.length)
+    }
+
+    /// Terms a parsed query can highlight
+    fn highlight_terms(&self, clauses: &[(Occur, Clause)]) -> AHashSet<String> {
+        let mut terms = AHashSet::new();
+        for (occur, clause) in clauses {
+            if *occur == Occur::MustNot {
+                continue;
+            }
+            match clause {
+                Clause::Terms(words) => terms.extend(words.iter().cloned()),
+                Clause::Prefix(prefix) => terms.extend(
+                    self.expansions(prefix)
+                        .into_iter()
+                        .map(|(term, _)| term.clone()),
+                ),
+            }
+        }
+        terms
+    }
+}
+
+/// Occurrences in `text` of `terms` at consecutive positions
+fn phrase_count(text: &str, terms: &[String]) -> usize {
+    let tokens = tokenize(text);
+    let at: AHashSet<(u32, &str)> = tokens
+        .iter()
+        .map(|token| (token.position, token.term.as_str()))
+        .collect();
+    tokens
+        .iter()
+        .filter(|token| token.term == terms[0])
+        .filter(|token| {
+            terms[1..].iter().enumerate().all(|(offset, term)| {
+                at.contains(&(token.position + offset as u32 + 1, term.as_str()))
+            })
+        })
+        .count()
+}
+
+/// Excerpt of `text` around the densest run of matched terms, with highlight ranges in characters
+fn snippet(text: &str, terms: &AHashSet<String>) -> (String, Vec<HighlightRange>) {
+    let mut matches: Vec<(usize, usize)> = tokenize(text)
+        .into_iter()
+        .filter(|token| terms.contains(&token.term))
+        .map(|token| (token.start, token.end))
+        .collect();
+    matches.sort_unstable();
+    // Whole identifiers and their parts overlap; keep the outermost
+    matches.dedup_by(|next, kept| next.0 < kept.1);
+
+    let budget = SNIPPET_CHARS;
+    let boundaries: Vec<usize> = text.char_indices().map(|(i, _)| i).collect();
+    let char_at = |byte: usize| boundaries.partition_point(|&b| b < byte);
+    let matches: Vec<(usize, usize)> = matches
+        .into_iter()
+        .map(|(start, end)| (char_at(start), char_at(end)))
+        .collect();
+    let mut best = (0, 0);
+    for (i, &(start, _)) in matches.iter().enumerate() {
+        let inside = matches[i..]
+            .iter()
+            .take_while(|(_, end)| end - start <= budget)
+            .count();
+        if inside > best.1 {
+            best = (i, inside);
+        }
+    }
+
+    let chars: Vec<char> = text.chars().collect();
+    let anchor = matches.get(best.0).map_or(0, |m| m.0);
+    // Start a little before the first match, at a word break when one is close
+    let mut from = anchor
+        .saturating_sub(budget / 8)
+        .min(chars.len().saturating_sub(budget));
+    while from > 0 && from < anchor && !chars[from - 1].is_whitespace() {
+        from += 1;
+    }
+    let to = (from + budget).min(chars.len());
+    let excerpt: String = chars[from..to].iter().collect();
+    let highlights = matches
+        .iter()
+        .filter(|&&(start, end)| start >= from && end <= to)
+        .map(|&(start, end)| HighlightRange {
+            start: (start - from) as u32,
+            end: (end - from) as u32,
+        })
+        .collect();
+    (excerpt, highlights)
+}
+
+#[napi]
+impl SearchIndex {
+    /// Create an empty index
+    #[napi(constructor)]
+    pub fn new() -> Self {
+        SearchIndex {
+            documents: Vec::new(),
+            slots: AHashMap::new(),
+            free: Vec::new(),
+            postings: AHashMap::new(),
+            total_length: 0,
+            journal: None,
+            changed: AHashSet::new(),
+        }
+    }
+
+    /**
+     * Load an index written by save
+     *
+     * A torn last line, left by a crash during save, is skipped; the next
+     * save then rewrites the file.
+     *
+     * @param path - File written by save
+     */
+    #[napi(factory)]
+    pub fn load(path: String) -> Result<Self> {
+        let read_error = |e: std::io::Error| {
+            Error::new(
+                Status::GenericFailure,
+                format!("Failed to read {}: {}", path, e),
+            )
+        };
+        let invalid = |line: usize, e: serde_json::Error| {
+            Error::new(
+                Status::InvalidArg,
+                format!("Invalid search index {} at line {}: {}", path, line, e),
+            )
+        };
+        let mut reader = BufReader::new(std::fs::File::open(&path).map_err(read_error)?);
+        let mut line = String::new();
+        reader.read_line(&mut line).map_err(read_error)?;
+        let header: Header = serde_json::from_str(&line).map_err(|e| invalid(1, e))?;
+        let mut index = SearchIndex::new();
+        match header.version {
+            1 => {
+                let saved: SavedIndex = serde_json::from_str(&line).map_err(|e| invalid(1, e))?;
+                index.add_documents(saved.documents);
+            }
+            FORMAT_VERSION => {
+                let mut entries = 0;
+                let mut torn = false;
+                loop {
+                    line.clear();
+                    if reader.read_line(&mut line).map_err(read_error)? == 0 {
+                        break;
+                    }
+                    let entry = match serde_json::from_str::<Entry>(&line) {
+                        Ok(entry) => entry,
+                        Err(_) if !line.ends_with('\n') => {
+                            torn = true;
+                            break;
+                        }
+                        Err(e) => return Err(invalid(entries + 2, e)),
+                    };
+                    match entry {
+                        Entry::Add(document) => index.insert(document),
+                        Entry::Remove(id) => {
+                            index.remove_document(&id);
+                        }
+                    }
+                    entries += 1;
+                }
+                if !torn {
+                    index.journal = Some((path.clone(), entries));
+                }
+            }
+            version => {
+                return Err(Error::new(
+                    Status::InvalidArg,
+                    format!("Unsupported search index version {}", version),
+                ))
+            }
+        }
+        index.changed.clear();
+        Ok(index)
+    }
+
+    /**
+     * Write the indexed documents to `path`
+     *
+     * The file is a journal of added and removed documents, not postings,
+     * which load rebuilds. Saving again to the same path appends only what
+     * changed since the last save; once the journal holds more than twice
+     * as many entries as there are documents, it is rewritten through a
+     * temporary file and renamed into place.
+     */
+    #[napi]
+    pub fn save(&mut self, path: String) -> Result<()> {
+        let live = self.slots.len();
+        let appending = self.journal.as_ref().is_some_and(|(saved, entries)| {
+            *saved == path
+                && entries + self.changed.len() <= (COMPACT_RATIO * live).max(COMPACT_MIN_ENTRIES)
+        });
+        let written = if appending {
+            match self.append(&path) {
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => self.rewrite(&path),
+                appended => appended,
+            }
+        } else {
+            self.rewrite(&path)
+        };
+        written.map_err(|e| {
+            // A partly appended line would be followed by the next save's
+            self.journal = None;
+            Error::new(
+                Status::GenericFailure,
+                format!("Failed to write {}: {}", path, e),
+            )
+        })?;
+        self.changed.clear();
+        Ok(())
+    }
+
+    /// Index documents, replacing any already indexed under the same id
+    #[napi]
+    pub fn add_documents(&mut self, documents: Vec<SearchDocument>) {
+        for document in documents {
+            self.insert(document);
+        }
+    }
+
+    /// Drop a document; returns whether it was indexed
+    #[napi]
+    pub fn remove(&mut self, id: String) -> bool {
+        self.remove_document(&id)
+    }
+
+    /// Drop every document of a session; returns how many were removed
+    #[napi]
+    pub fn remove_session(&mut self, session_id: String) -> u32 {
+        let ids: Vec<String> = self
+            .documents
+            .iter()
+            .flatten()
+            .filter(|indexed| indexed.document.session_id == session_id)
+            .map(|indexed| indexed.document.id.clone())
+            .collect();
+        for id in &ids {
+            self.remove_document(id);
+        }
+        ids.len() as u32
+    }
+
+    /**
+     * Ranked full-text search
+     *
+     * Words are matched case-insensitively against whole words and the
+     * words inside identifiers, so `retry logic` finds `retryLogic`. Any
+     * word may match; `+word` must match, `-word` must not, `"a b"` matches
+     * the words in sequence, and `retr*` matches words starting with
+     * `retr`. Hits are ranked by BM25 and come with a highlighted snippet.
+     *
+     * @param query - Query text
+     * @param options - Result limit, session filter, one hit per session
+     * @returns Hits, best first
+     */
+    #[napi]
+    pub fn search(&self, query: String, options: Option<SearchOptions>) -> Vec<SearchHit> {
+        let limit = options.as_ref().and_then(|o| o.limit).unwrap_or(10) as usize;
+        let session = options.as_ref().and_then(|o| o.session_id.clone());
+        let group = options
+            .as_ref()
+            .and_then(|o| o.group_by_session)
+            .unwrap_or(false);
+        let clauses = parse_query(&query);
+
+        let mut scores: Option<AHashMap<u32, f64>> = None;
+        let mut optional: AHashMap<u32, f64> = AHashMap::new();
+        let mut excluded: AHashSet<u32> = AHashSet::new();
+        for (occur, clause) in &clauses {
+            let matched = self.clause_scores(clause);
+            match occur {
+                Occur::MustNot => excluded.extend(matched.into_keys()),
+                Occur::Should => {
+                    for (slot, score) in matched {
+                        *optional.entry(slot).or_insert(0.0) += score;
+                    }
+                }
+                Occur::Must => {
+                    scores = Some(match scores {
+                        None => matched,
+                        Some(previous) => previous
+                            .into_iter()
+                            .filter_map(|(slot, score)| {
+                                matched.get(&slot).map(|extra| (slot, score + extra))
+                            })
+                            .collect(),
+                    });
+                }
+            }
+        }
+        let scores: AHashMap<u32, f64> = match scores {
+            // Optional words only add to documents that have the required ones
+            Some(mut required) => {
+                for (slot, score) in required.iter_mut() {
+                    *score += optional.get(slot).copied().unwrap_or(0.0);
+                }
+                required
+            }
+            None => optional,
+        };
+
+        let mut ranked: Vec<(u32, f64)> = scores
+            .into_iter()
+            .filter(|(slot, _)| !excluded.contains(slot))
+            .filter(|(slot, _)| {
+                session.as_ref().is_none_or(|session| {
+                    self.documents[*slot as usize]
+                        .as_ref()
+                        .is_some_and(|indexed| &indexed.document.session_id == session)
+                })
+            })
+            .collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+
+        let terms = self.highlight_terms(&clauses);
+        let mut seen_sessions = AHashSet::new();
+        ranked
+            .into_iter()
+            .filter_map(|(slot, score)| {
+                let document = &self.documents[slot as usize].as_ref()?.document;
+                if group && !seen_sessions.insert(document.session_id.as_str()) {
+                    return None;
+                }
+                Some((document, score))
+            })
+            .take(limit)
+            .map(|(document, score)| {
+                let (snippet, highlights) = snippet(&document.text, &terms);
+                SearchHit {
+                    id: document.id.clone(),
+                    session_id: document.session_id.clone(),
+                    score,
+                    timestamp: document.timestamp,
+                    kind: document.kind.clone(),
+                    snippet,
+                    highlights,
+                }
+            })
+            .collect()
+    }
+
+    /// Document, session, and term counts
+    #[napi]
+    pub fn stats(&self) -> SearchIndexStats {
+        let sessions: AHashSet<&str> = self
+            .documents
+            .iter()
+            .flatten()
+            .map(|indexed| indexed.document.session_id.as_str())
+            .collect();
+        SearchIndexStats {
+            documents: self.slots.len() as u32,
+            sessions: sessions.len() as u32,
+            terms: self.postings.len() as u32,
+        }
+    }
+}
+
+impl Default for SearchIndex {
+    fn default() -> Self {
+        SearchIndex::new()
+    }
+}
@@ -0,0 +1,234 @@
+/*!
+ * Function span detection
+ *
+ * Locates function definitions and the extent of their bodies so
+ * per-function metrics (complexity, length, nesting) can be computed.
+ * Runs on comment- and string-stripped code from `strip_comments_and_strings`.
+ */
+
+use regex::Regex;
+
+/// Names that look like calls/definitions to the C-style patterns but are control flow
+pub const NOT_FUNCTION_NAMES: &[&str] = &[
+    "if", "for", "while", "switch", "catch", "return", "else", "new", "function", "do", "sizeof",
+    "typeof", "match", "loop", "with", "elif",
+];
+
+/// A function definition located in source
+#[derive(Clone, Debug)]
+pub struct FunctionSpan {
+    pub name: String,
+    /// Byte offset where the definition starts
+    pub start: usize,
+    /// Byte offset just past the end of the body
+    pub end: usize,
+    /// 1-based line numbers
+    pub start_line: u32,
+    pub end_line: u32,
+}
+
+enum BodyStyle {
+    /// `{ ... }` body after the signature
+    Braces,
+    /// Arrow function: braces if present, otherwise the expression up to the end of line
+    Arrow,
+    /// Python: lines indented deeper than the `def`
+    Indent,
+}
+
+/**
+ * Find function definitions in stripped code
+ *
+ * Supports javascript, typescript, python, rust, go, java, c, cpp, and csharp.
+ * Other languages yield no spans. Results are ordered by start offset and
+ * may nest (closures, inner functions, methods inside impl blocks).
+ */
+pub fn find_functions(code: &str, language: &str) -> Vec<FunctionSpan> {
+    let patterns: Vec<(&str, BodyStyle)> = match language {
+        "javascript" | "typescript" => vec![
+            (
+                r"(?m)^[ \t]*(?:export\s+)?(?:default\s+)?(?:async\s+)?function\s*\*?\s*(\w+)",
+                BodyStyle::Braces,
+            ),
+            (
+                r"(?m)^[ \t]*(?:export\s+)?(?:const|let|var)\s+(\w+)\s*(?::[^=\n]+)?=\s*(?:async\s+)?function\b",
+                BodyStyle::Braces,
+            ),
+            (
+                r"(?m)^[ \t]*(?:export\s+)?(?:const|let|var)\s+(\w+)\s*(?::[^=\n]+)?=\s*(?:async\s+)?(?:\([^)]*\)|\w+)\s*(?::\s*[^=\n]+)?=>",
+                BodyStyle::Arrow,
+            ),
+            (
+                r"(?m)^[ \t]*(?:(?:public|private|protected|static|readonly)\s+)*(\w+)\s*=\s*(?:async\s+)?(?:\([^)]*\)|\w+)\s*=>",
+                BodyStyle::Arrow,
+            ),
+            (
+                r"(?m)^[ \t]*(?:(?:public|private|protected|static|async|get|set|override)\s+)*\*?(\w+)\s*(?:<[^>\n]*>)?\s*\([^)]*\)\s*(?::\s*[^{\n]+)?\{",
+                BodyStyle::Braces,
+            ),
+        ],
+        "python" => vec![(
+            r"(?m)^[ \t]*(?:async\s+)?def\s+(\w+)\s*\(",
+            BodyStyle::Indent,
+        )],
+        "rust" => vec![(
+            r#"(?m)^[ \t]*(?:pub(?:\([^)]*\))?\s+)?(?:default\s+)?(?:const\s+)?(?:async\s+)?(?:unsafe\s+)?(?:extern\s+"[^"]*"\s+)?fn\s+(\w+)"#,
+            BodyStyle::Braces,
+        )],
+        "go" => vec![(r"(?m)^func\s+(?:\([^)]*\)\s*)?(\w+)", BodyStyle::Braces)],
+        "java" | "c" | "cpp" | "csharp" => vec![(
+            r"(?m)^[ \t]*(?:[\w:<>\[\],\*&~]+[ \t]+)+[\*&]*(\w+)\s*\([^;{)]*\)[^;{]*\{",
+            BodyStyle::Braces,
+        )],
+        _ => vec![],
+    };
+
+    let line_starts = line_starts(code);
+    let mut spans: Vec<FunctionSpan> = Vec::new();
+
+    for (pattern, style) in patterns {
+        let re = Regex::new(pattern).unwrap();
+        for cap in re.captures_iter(code) {
+            let (Some(whole), Some(name)) = (cap.get(0), cap.get(1)) else {
+                continue;
+            };
+            if NOT_FUNCTION_NAMES.contains(&name.as_str()) {
+                continue;
+            }
+            let start = whole.start() + (whole.as_str().len() - whole.as_str().trim_start().len());
+            if spans.iter().any(|s| s.start == start) {
+                continue;
+            }
+            let body_end = match style {
+                BodyStyle::Braces => brace_body_end(code, name.end()),
+                BodyStyle::Arrow => arrow_body_end(code, whole.end()),
+                BodyStyle::Indent => Some(indent_body_end(code, start)),
+            };
+            let Some(end) = body_end else {
+                continue;
+            };
+            spans.push(FunctionSpan {
+                name: name.as_str().to_string(),
+                start,
+                end,
+                start_line: line_of(&line_starts, start),
+                end_line: line_of(&line_starts, end.saturating_sub(1).max(start)),
+            });
+        }
+    }
+
+    spans.sort_by_key(|s| s.start);
+    spans
+}
+
+/// End of the `{ ... }` block opening after `from`, or None for a bodiless declaration
+fn brace_body_end(code: &str, from: usize) -> Option<usize> {
+    let bytes = code.as_bytes();
+    let mut i = from;
+    let mut paren_depth = 0i32;
+
+    // Find the opening brace, skipping the parameter list
+    while i < bytes.len() {
+        match bytes[i] {
+            b'(' => paren_depth += 1,
+            b')' => paren_depth -= 1,
+            b';' if paren_depth <= 0 => return None,
+            b'{' if paren_depth <= 0 => break,
+            _ => {}
+        }
+        i += 1;
+    }
+    if i >= bytes.len() {
+        return None;
+    }
+
+    Some(matching_brace(bytes, i))
+}
+
+/// Offset just past the brace matching the `{` at `open`
+pub fn matching_brace(bytes: &[u8], open: usize) -> usize {
+    let mut depth = 0i32;
+    for (offset, &b) in bytes[open..].iter().enumerate() {
+        match b {
+            b'{' => depth += 1,
+            b'}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return open + offset + 1;
+                }
+            }
+            _ => {}
+        }
+    }
+    bytes.len()
+}
+
+fn arrow_body_end(code: &str, arrow_end: usize) -> Option<usize> {
+    let rest = &code[arrow_end..];
+    let skipped = rest.len() - rest.trim_start().len();
+    if rest[skipped..].starts_with('{') {
+        return Some(matching_brace(code.as_bytes(), arrow_end + skipped));
+    }
+    Some(arrow_end + rest.find('\n').unwrap_or(rest.len()))
+}
+
+/// End of the block indented deeper than the line at `def_start`
+pub fn indent_body_end(code: &str, def_start: usize) -> usize {
+    let line_start = code[..def_start].rfind('\n').map(|i| i + 1).unwrap_or(0);
+    let def_indent = def_start - line_start;
+    let mut end = code[def_start..]
+        .find('\n')
+        .map(|i| def_start + i)
+        .unwrap_or(code.len());
+
+    let mut offset = end;
+    while offset < code.len() {
+        let line_end = code[offset + 1..]
+            .find('\n')
+            .map(|i| offset + 1 + i)
+            .unwrap_or(code.len());
+        let line = &code[(offset + 1).min(code.len())..line_end];
+        if !line.trim().is_empty() {
+            let indent = line.len() - line.trim_start().len();
+            if indent <= def_indent {
+                break;
+            }
+            end = line_end;
+        }
+        offset = line_end;
+    }
+
+    end
+}
+
+/// Byte offset of the start of every line
+pub fn line_starts(code: &str) -> Vec<usize> {
+    std::iter::once(0)
+        .chain(code.match_indices('\n').map(|(i, _)| i + 1))
+        .collect()
+}
+
+/// 1-based line containing `offset`
+pub fn line_of(line_starts: &[usize], offset: usize) -> u32 {
+    match line_starts.binary_search(&offset) {
+        Ok(index) => index as u32 + 1,
+        Err(index) => index as u32,
+    }
+}
+
+/// Direct children of each span: nested spans not contained in another nested span
+pub fn direct_children(spans: &[FunctionSpan], parent: usize) -> Vec<usize> {
+    let outer = &spans[parent];
+    let nested: Vec<usize> = (0..spans.len())
+        .filter(|&i| i != parent && spans[i].start > outer.start && spans[i].end <= outer.end)
+        .collect();
+    nested
+        .iter()
+        .copied()
+        .filter(|&i| {
+            !nested
+                .iter()
+                .any(|&j| j != i && spans[j].start < spans[i].start && spans[i].end <= spans[j].end)
+        })
+        .collect()
+}
@@ -0,0 +1,262 @@
+/*!
+ * Fuzzy matching
+ * fzf-style subsequence scoring for file and session pickers
+ */
+
+use napi_derive::napi;
+use rayon::prelude::*;
+
+/// Options for fuzzy_match
+#[napi(object)]
+pub struct FuzzyOptions {
+    /// Matches returned, best first (default: all)
+    pub limit: Option<u32>,
+    /// Match case exactly; by default a query with an uppercase letter is case-sensitive and one without is not
+    pub case_sensitive: Option<bool>,
+}
+
+/// A candidate that matched the query
+#[napi(object)]
+pub struct FuzzyMatch {
+    pub candidate: String,
+    /// Position in the candidates array
+    pub index: u32,
+    pub score: i32,
+    /// Character indices of the matched query characters, ascending
+    pub positions: Vec<u32>,
+}
+
+// fzf's scoring constants (algo.go)
+const SCORE_MATCH: i32 = 16;
+const SCORE_GAP_START: i32 = -3;
+const SCORE_GAP_EXTENSION: i32 = -1;
+const BONUS_BOUNDARY: i32 = SCORE_MATCH / 2;
+const BONUS_NON_WORD: i32 = SCORE_MATCH / 2;
+const BONUS_CAMEL_123: i32 = BONUS_BOUNDARY + SCORE_GAP_EXTENSION;
+const BONUS_CONSECUTIVE: i32 = -(SCORE_GAP_START + SCORE_GAP_EXTENSION);
+const BONUS_FIRST_CHAR_MULTIPLIER: i32 = 2;
+const BONUS_BOUNDARY_WHITE: i32 = BONUS_BOUNDARY + 2;
+const BONUS_BOUNDARY_DELIMITER: i32 = BONUS_BOUNDARY + 1;
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum CharClass {
+    White,
+    NonWord,
+    Delimiter,
+    Lower,
+    Upper,
+    Letter,
+    Number,
+}
+
+fn class_of(c: char) -> CharClass {
+    if c.is_ascii_lowercase() {
+        CharClass::Lower
+    } else if c.is_ascii_uppercase() {
+        CharClass::Upper
+    } else if c.is_ascii_digit() {
+        CharClass::Number
+    } else if c.is_whitespace() {
+        CharClass::White
+    } else if matches!(c, '/' | '\\' | ',' | ':' | ';' | '|') {
+        CharClass::Delimiter
+    } else if c.is_lowercase() {
+        CharClass::Lower
+    } else if c.is_uppercase() {
+        CharClass::Upper
+    } else if c.is_alphanumeric() {
+        CharClass::Letter
+    } else {
+        CharClass::NonWord
+    }
+}
+
+/// Bonus for matching a character of class `current` right after one of class `previous`
+fn bonus_for(previous: CharClass, current: CharClass) -> i32 {
+    let word = current > CharClass::Delimiter;
+    if word {
+        match previous {
+            CharClass::White => return BONUS_BOUNDARY_WHITE,
+            CharClass::Delimiter => return BONUS_BOUNDARY_DELIMITER,
+            CharClass::NonWord => return BONUS_BOUNDARY,
+            _ => {}
+        }
+    }
+    if (previous == CharClass::Lower && current == CharClass::Upper)
+        || (previous != CharClass::Number && current == CharClass::Number)
+    {
+        return BONUS_CAMEL_123;
+    }
+    match current {
+        CharClass::NonWord | CharClass::Delimiter => BONUS_NON_WORD,
+        CharClass::White => BONUS_BOUNDARY_WHITE,
+        _ => 0,
+    }
+}
+
+fn fold(c: char, case_sensitive: bool) -> char {
+    if case_sensitive {
+        c
+    } else {
+        c.to_lowercase().next().unwrap_or(c)
+    }
+}
+
+/// Best alignment of `pattern` in `text`: score and matched character indices
+///
+/// fzf's v2 algorithm: a Smith-Waterman style table over pattern x text
+/// where gaps cost a start penalty plus a per-character extension, and
+/// consecutive matches keep the bonus of the chunk's first character.
+fn score_term(pattern: &[char], text: &[char], case_sensitive: bool) -> Option<(i32, Vec<u32>)> {
+    let (m, n) = (pattern.len(), text.len());
+    // Subsequence check, which also bounds where the first match can start
+    let mut first = None;
+    let mut next = 0;
+    for (j, c) in text.iter().enumerate() {
+        if next < m && fold(*c, case_sensitive) == pattern[next] {
+            first.get_or_insert(j);
+            next += 1;
+        }
+    }
+    if next < m {
+        return None;
+    }
+    let first = first.unwrap_or(0);
+    let folded: Vec<char> = text.iter().map(|c| fold(*c, case_sensitive)).collect();
+    let mut bonus = Vec::with_capacity(n);
+    let mut previous = CharClass::White;
+    for c in text {
+        let class = class_of(*c);
+        bonus.push(bonus_for(previous, class));
+        previous = class;
+    }
+
+    const NONE: i32 = i32::MIN / 2;
+    // score[i][j]: best with pattern[i] matched at text[j]; chunk[i][j]: bonus of that consecutive run's start
+    let mut score = vec![NONE; m * n];
+    let mut chunk = vec![0; m * n];
+    let mut from = vec![usize::MAX; m * n];
+    for i in 0..m {
+        // Best predecessor more than one character back, with its gap penalty applied
+        let mut gapped = NONE;
+        let mut gapped_from = usize::MAX;
+        for j in first..n {
+            if i > 0 && j >= 2 {
+                let candidate = score[(i - 1) * n + j - 2] + SCORE_GAP_START;
+                if gapped != NONE {
+                    gapped += SCORE_GAP_EXTENSION;
+                }
+                if candidate > gapped {
+                    gapped = candidate;
+                    gapped_from = j - 2;
+                }
+            }
+            if folded[j] != pattern[i] {
+                continue;
+            }
+            let cell = i * n + j;
+            if i == 0 {
+                score[cell] = SCORE_MATCH + bonus[j] * BONUS_FIRST_CHAR_MULTIPLIER;
+                chunk[cell] = bonus[j];
+                continue;
+            }
+            let mut best = NONE;
+            if gapped != NONE {
+                best = gapped + SCORE_MATCH + bonus[j];
+                chunk[cell] = bonus[j];
+                from[cell] = gapped_from;
+            }
+            if j >= 1 && score[cell - n - 1] != NONE {
+                let run_bonus = chunk[cell - n - 1].max(bonus[j]).max(BONUS_CONSECUTIVE);
+                let consecutive = score[cell - n - 1] + SCORE_MATCH + run_bonus;
+                if consecutive >= best {
+                    best = consecutive;
+                    chunk[cell] = run_bonus;
+                    from[cell] = j - 1;
+                }
+            }
+            score[cell] = best;
+        }
+    }
+
+    let last = (m - 1) * n;
+    let (end, best) = (first..n)
+        .map(|j| (j, score[last + j]))
+        .filter(|(_, s)| *s != NONE)
+        .max_by_key(|(j, s)| (*s, std::cmp::Reverse(*j)))?;
+    let mut positions = vec![0u32; m];
+    let mut j = end;
+    for i in (0..m).rev() {
+        positions[i] = j as u32;
+        j = from[i * n + j];
+    }
+    Some((best, positions))
+}
+
+/**
+ * Rank candidates against a fuzzy query, fzf style
+ *
+ * Every query character must appear in order. Matches score higher at
+ * word boundaries (after `/`, `_`, `-`, a space, or a lower-to-upper
+ * camelCase step) and in consecutive runs, and lose points for gaps;
+ * the best-scoring alignment is reported. Whitespace separates terms that
+ * must all match. Ties go to the shorter candidate, then input order.
+ * Candidates are scored in parallel.
+ *
+ * @param query - Search text; empty matches everything with score 0
+ * @param candidates - Paths, session titles, or other strings
+ * @param options - Result limit and case sensitivity (smart case by default)
+ * @returns Matches, best first, with matched character positions
+ */
+#[napi]
+pub fn fuzzy_match(
+    query: String,
+    candidates: Vec<String>,
+    options: Option<FuzzyOptions>,
+) -> Vec<FuzzyMatch> {
+    let limit = options
+        .as_ref()
+        .and_then(|o| o.limit)
+        .map_or(usize::MAX, |limit| limit as usize);
+    let case_sensitive = options
+        .as_ref()
+        .and_then(|o| o.case_sensitive)
+        .unwrap_or_else(|| query.chars().any(char::is_uppercase));
+    let terms: Vec<Vec<char>> = query
+        .split_whitespace()
+        .map(|term| term.chars().map(|c| fold(c, case_sensitive)).collect())
+        .collect();
+
+    let mut matches: Vec<(i32, usize, usize, Vec<u32>)> = candidates
+        .par_iter()
+        .enumerate()
+        .filter_map(|(index, candidate)| {
+            let text: Vec<char> = candidate.chars().collect();
+            let mut total = 0;
+            let mut positions = Vec::new();
+            for term in &terms {
+                let (score, found) = score_term(term, &text, case_sensitive)?;
+                total += score;
+                positions.extend(found);
+            }
+            positions.sort_unstable();
+            positions.dedup();
+            // Without terms everything matches equally and keeps input order
+            let length = if terms.is_empty() { 0 } else { text.len() };
+            Some((total, length, index, positions))
+        })
+        .collect();
+    matches.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)).then(a.2.cmp(&b.2)));
+    matches.truncate(limit);
+
+    let mut candidates: Vec<Option<String>> = candidates.into_iter().map(Some).collect();
+    matches
+        .into_iter()
+        .map(|(score, _, index, positions)| FuzzyMatch {
+            candidate: candidates[index].take().unwrap_or_default(),
+            index: index as u32,
+            score,
+            positions,
+        })
+        .collect()
+}
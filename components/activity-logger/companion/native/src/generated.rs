@@ -0,0 +1,199 @@
+/*!
+ * Generated and minified file detection
+ * Build output, codegen, and lockfiles, which skew diff and stat numbers when treated as hand-written code
+ */
+
+use napi_derive::napi;
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// Result of detect_generated
+#[napi(object)]
+pub struct GeneratedDetection {
+    /// Produced by a tool rather than written by hand; true for every minified file
+    pub is_generated: bool,
+    pub is_minified: bool,
+    /// Signals that fired: "marker", "filename", "sourcemap", "long-lines", "low-whitespace"
+    pub reasons: Vec<String>,
+    /// Header text or file pattern behind the "marker" or "filename" reason
+    pub evidence: Option<String>,
+    /// Longest line, in bytes
+    pub longest_line: u32,
+    /// Mean length of non-blank lines, in bytes
+    pub average_line_length: f64,
+    /// Whitespace bytes over all bytes, 0-1
+    pub whitespace_ratio: f64,
+}
+
+/// Only the top of a file is searched for generator markers, so code that mentions them isn't flagged
+const HEADER_LINES: usize = 30;
+/// Files smaller than this are never called minified
+const MIN_MINIFIED_BYTES: usize = 256;
+/// Mean non-blank line length past which a file reads as minified (the cutoff GitHub Linguist uses)
+const MINIFIED_AVERAGE_LINE: f64 = 110.0;
+/// A single line this long with almost no whitespace is a bundle on its own
+const MINIFIED_LONGEST_LINE: usize = 500;
+
+static MARKER: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"@generated\b|DO NOT EDIT|(?i:\bcode generated by\b|\bauto-?generated\b|\bautomatically generated\b|\bgenerated by (?:the )?[\w.-]+|<auto-generated)",
+    )
+    .unwrap()
+});
+
+static SOURCEMAP: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?m)^\s*(?://|/\*)[#@] sourceMappingURL=").unwrap());
+
+/// Name patterns of generated files, matched against the file name
+const GENERATED_NAMES: &[(&str, &str)] = &[
+    (".min.js", "*.min.js"),
+    (".min.css", "*.min.css"),
+    (".min.mjs", "*.min.mjs"),
+    (".bundle.js", "*.bundle.js"),
+    (".pb.go", "*.pb.go"),
+    ("_pb2.py", "*_pb2.py"),
+    ("_pb2_grpc.py", "*_pb2_grpc.py"),
+    (".pb.h", "*.pb.h"),
+    (".pb.cc", "*.pb.cc"),
+    (".g.dart", "*.g.dart"),
+    (".freezed.dart", "*.freezed.dart"),
+    (".designer.cs", "*.Designer.cs"),
+    (".g.cs", "*.g.cs"),
+    (".js.map", "*.js.map"),
+    (".css.map", "*.css.map"),
+];
+
+const LOCKFILES: &[&str] = &[
+    "package-lock.json",
+    "npm-shrinkwrap.json",
+    "yarn.lock",
+    "pnpm-lock.yaml",
+    "bun.lockb",
+    "cargo.lock",
+    "poetry.lock",
+    "pipfile.lock",
+    "uv.lock",
+    "go.sum",
+    "gemfile.lock",
+    "composer.lock",
+    "packages.lock.json",
+];
+
+/// Generated-file pattern matched by `filename`
+fn generated_name(filename: &str) -> Option<&'static str> {
+    let base = filename.rsplit(['/', '\\']).next().unwrap_or(filename);
+    let lower = base.to_ascii_lowercase();
+    if let Some(lockfile) = LOCKFILES.iter().find(|name| **name == lower) {
+        return Some(lockfile);
+    }
+    if lower.contains(".generated.") || lower.contains(".gen.") {
+        return Some("*.generated.*");
+    }
+    GENERATED_NAMES
+        .iter()
+        .find(|(suffix, _)| lower.ends_with(suffix))
+        .map(|(_, pattern)| *pattern)
+}
+
+/**
+ * Detect generated and minified files
+ *
+ * A file is generated when its first 30 lines carry a generator marker
+ * (`@generated`, `DO NOT EDIT`, `Code generated by`, `auto-generated`,
+ * `<auto-generated>`), its name matches a codegen or build-output pattern
+ * (`*.min.js`, `*.pb.go`, `*_pb2.py`, `*.g.dart`, lockfiles, ...), or it
+ * references a source map. It is minified when it is at least 256 bytes and
+ * either its non-blank lines average over 110 bytes with little whitespace,
+ * or one line runs past 500 bytes with almost none. Minified files also
+ * count as generated.
+ *
+ * @param content - File content
+ * @param filename - File path, for name patterns
+ * @returns Both flags, the signals behind them, and the line measurements used
+ */
+#[napi]
+pub fn detect_generated(content: String, filename: Option<String>) -> GeneratedDetection {
+    let mut reasons = Vec::new();
+    let mut evidence = None;
+
+    let header_end = content
+        .match_indices('\n')
+        .nth(HEADER_LINES - 1)
+        .map_or(content.len(), |(i, _)| i);
+    if let Some(marker) = MARKER.find(&content[..header_end]) {
+        reasons.push("marker");
+        evidence = Some(marker.as_str().to_string());
+    }
+    let name_pattern = filename.as_deref().and_then(generated_name);
+    if let Some(pattern) = name_pattern {
+        reasons.push("filename");
+        evidence.get_or_insert_with(|| pattern.to_string());
+    }
+    if SOURCEMAP.is_match(&content) {
+        reasons.push("sourcemap");
+    }
+
+    let mut longest_line = 0;
+    let mut longest_whitespace = 0;
+    let mut non_blank = 0usize;
+    let mut non_blank_bytes = 0usize;
+    for line in content.lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        non_blank += 1;
+        non_blank_bytes += line.len();
+        if line.len() > longest_line {
+            longest_line = line.len();
+            longest_whitespace = line.bytes().filter(u8::is_ascii_whitespace).count();
+        }
+    }
+    let whitespace = content.bytes().filter(u8::is_ascii_whitespace).count();
+    let whitespace_ratio = if content.is_empty() {
+        0.0
+    } else {
+        whitespace as f64 / content.len() as f64
+    };
+    let average_line_length = if non_blank == 0 {
+        0.0
+    } else {
+        non_blank_bytes as f64 / non_blank as f64
+    };
+
+    let minified_name = name_pattern.is_some_and(|pattern| pattern.starts_with("*.min."));
+    let mut is_minified = minified_name;
+    if content.len() >= MIN_MINIFIED_BYTES {
+        if average_line_length > MINIFIED_AVERAGE_LINE && whitespace_ratio < 0.2 {
+            reasons.push("long-lines");
+            is_minified = true;
+        }
+        if longest_line >= MINIFIED_LONGEST_LINE
+            && (longest_whitespace as f64) < longest_line as f64 * 0.1
+        {
+            reasons.push("low-whitespace");
+            is_minified = true;
+        }
+    }
+
+    GeneratedDetection {
+        is_generated: is_minified || !reasons.is_empty(),
+        is_minified,
+        reasons: reasons.into_iter().map(str::to_string).collect(),
+        evidence,
+        longest_line: longest_line as u32,
+        average_line_length,
+        whitespace_ratio,
+    }
+}
+
+/// Whether `content` was produced by a tool; see detect_generated
+#[napi]
+pub fn is_generated(content: String, filename: Option<String>) -> bool {
+    detect_generated(content, filename).is_generated
+}
+
+/// Whether `content` is minified; see detect_generated
+#[napi]
+pub fn is_minified(content: String, filename: Option<String>) -> bool {
+    detect_generated(content, filename).is_minified
+}
@@ -0,0 +1,434 @@
+/*!
+ * Directory grep
+ * ripgrep-style parallel search of a tree, honoring .gitignore and friends,
+ * and chunked search of single files too large to hand over as a string
+ */
+
+use crate::cancel::CancelFlag;
+use crate::encoding;
+use crate::search::PatternSet;
+use ignore::overrides::OverrideBuilder;
+use ignore::{WalkBuilder, WalkState};
+use napi::bindgen_prelude::*;
+use napi::JsObject;
+use napi_derive::napi;
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc;
+
+/// Bytes read per chunk by search_file (default)
+const DEFAULT_CHUNK_SIZE: u32 = 1 << 20;
+
+/// Bytes checked for a NUL when deciding a file is binary, as ripgrep does
+const BINARY_SNIFF: usize = 8192;
+
+/// Options for grep_directory
+#[napi(object)]
+pub struct GrepOptions {
+    /// ripgrep `-g` globs: plain globs restrict the search to matching files, `!glob` excludes
+    pub globs: Option<Vec<String>>,
+    /// Search hidden files and directories (default: false)
+    pub hidden: Option<bool>,
+    /// Ignore .gitignore, .ignore, and git exclude files (default: false)
+    pub no_ignore: Option<bool>,
+    /// Skip files larger than this many bytes
+    pub max_file_size: Option<i64>,
+    /// Stop after this many matches in total
+    pub max_matches: Option<u32>,
+}
+
+/// One match in a file
+#[napi(object)]
+pub struct GrepMatch {
+    pub path: String,
+    pub pattern: String,
+    /// 1-based
+    pub line: u32,
+    /// 1-based, in characters
+    pub column: u32,
+    /// Byte offsets into the decoded file content
+    pub start: u32,
+    pub end: u32,
+    pub text: String,
+    /// The whole line the match starts on, without its terminator
+    pub line_text: String,
+}
+
+/// Result of grep_directory
+#[napi(object)]
+pub struct GrepResult {
+    /// Ordered by path, then position
+    pub matches: Vec<GrepMatch>,
+    pub files_searched: i64,
+    pub files_matched: i64,
+    /// Binary and unreadable files; files over max_file_size are left out without counting
+    pub skipped_files: i64,
+    /// max_matches was reached and the search stopped early
+    pub truncated: bool,
+}
+
+enum FileOutcome {
+    Searched(Vec<GrepMatch>),
+    Skipped,
+}
+
+fn grep_file(path: &Path, set: &PatternSet, limit: usize) -> FileOutcome {
+    let Ok(bytes) = std::fs::read(path) else {
+        return FileOutcome::Skipped;
+    };
+    let (_, _, content, _) = encoding::decode(&bytes);
+    // UTF-16 files have NULs in their raw bytes, so look after decoding
+    let sniff = content.len().min(BINARY_SNIFF);
+    if content.as_bytes()[..sniff].contains(&0) {
+        return FileOutcome::Skipped;
+    }
+    let display = path.to_string_lossy();
+    let matches = set
+        .find_in(&content, limit)
+        .into_iter()
+        .map(|m| {
+            let line_start = content[..m.start as usize].rfind('\n').map_or(0, |i| i + 1);
+            let line_end = content[m.start as usize..]
+                .find('\n')
+                .map_or(content.len(), |i| m.start as usize + i);
+            GrepMatch {
+                path: display.to_string(),
+                pattern: m.pattern,
+                line: m.line,
+                column: m.column,
+                start: m.start,
+                end: m.end,
+                text: m.text,
+                line_text: content[line_start..line_end]
+                    .trim_end_matches('\r')
+                    .to_string(),
+            }
+        })
+        .collect();
+    FileOutcome::Searched(matches)
+}
+
+/**
+ * Search every file under a directory for regex patterns
+ *
+ * Walks the tree in parallel like ripgrep: `.gitignore`, `.ignore`, and
+ * hidden files are skipped unless the options say otherwise, and files
+ * are searched on the walker's threads as they are found. Files are
+ * decoded like decode_to_utf8; binary files (a NUL in the first 8 KiB)
+ * are skipped.
+ *
+ * @param root - Directory to search
+ * @param patterns - Regex patterns; an invalid pattern fails the call
+ * @param options - Globs, hidden files, ignore files, size and match limits
+ * @returns Matches with locations and line text, plus file counts
+ */
+#[napi]
+pub fn grep_directory(
+    root: String,
+    patterns: Vec<String>,
+    options: Option<GrepOptions>,
+) -> Result<GrepResult> {
+    let path = Path::new(&root);
+    if !path.is_dir() {
+        return Err(Error::new(
+            Status::InvalidArg,
+            format!("Not a directory: {}", root),
+        ));
+    }
+    let set = PatternSet::compile(patterns, false)?;
+    let hidden = options.as_ref().and_then(|o| o.hidden).unwrap_or(false);
+    let no_ignore = options.as_ref().and_then(|o| o.no_ignore).unwrap_or(false);
+    let max_file_size = options
+        .as_ref()
+        .and_then(|o| o.max_file_size)
+        .map(|size| size.max(0) as u64);
+    let limit = options
+        .as_ref()
+        .and_then(|o| o.max_matches)
+        .map_or(usize::MAX, |max| max as usize);
+
+    let mut overrides = OverrideBuilder::new(path);
+    for glob in options.and_then(|o| o.globs).unwrap_or_default() {
+        overrides.add(&glob).map_err(|e| {
+            Error::new(
+                Status::InvalidArg,
+                format!("Invalid glob '{}': {}", glob, e),
+            )
+        })?;
+    }
+    let overrides = overrides
+        .build()
+        .map_err(|e| Error::new(Status::InvalidArg, e.to_string()))?;
+
+    let found = AtomicUsize::new(0);
+    let stopped = AtomicBool::new(false);
+    let (sender, receiver) = mpsc::channel::<FileOutcome>();
+    WalkBuilder::new(path)
+        .overrides(overrides)
+        .hidden(!hidden)
+        .git_ignore(!no_ignore)
+        .git_global(!no_ignore)
+        .git_exclude(!no_ignore)
+        .ignore(!no_ignore)
+        .parents(!no_ignore)
+        .max_filesize(max_file_size)
+        .build_parallel()
+        .run(|| {
+            let sender = sender.clone();
+            let set = &set;
+            let found = &found;
+            let stopped = &stopped;
+            Box::new(move |entry| {
+                if found.load(Ordering::Relaxed) >= limit {
+                    stopped.store(true, Ordering::Relaxed);
+                    return WalkState::Quit;
+                }
+                if let Ok(entry) = entry {
+                    if entry.file_type().is_some_and(|t| t.is_file()) {
+                        // One past the limit shows whether a file alone overflows it
+                        let outcome = grep_file(entry.path(), set, limit.saturating_add(1));
+                        if let FileOutcome::Searched(matches) = &outcome {
+                            found.fetch_add(matches.len(), Ordering::Relaxed);
+                        }
+                        let _ = sender.send(outcome);
+                    }
+                }
+                WalkState::Continue
+            })
+        });
+    drop(sender);
+
+    let mut result = GrepResult {
+        matches: Vec::new(),
+        files_searched: 0,
+        files_matched: 0,
+        skipped_files: 0,
+        truncated: false,
+    };
+    for outcome in receiver {
+        match outcome {
+            FileOutcome::Searched(matches) => {
+                result.files_searched += 1;
+                if !matches.is_empty() {
+                    result.files_matched += 1;
+                }
+                result.matches.extend(matches);
+            }
+            FileOutcome::Skipped => result.skipped_files += 1,
+        }
+    }
+    result
+        .matches
+        .sort_by(|a, b| a.path.cmp(&b.path).then(a.start.cmp(&b.start)));
+    result.truncated = stopped.into_inner() || result.matches.len() > limit;
+    result.matches.truncate(limit);
+    Ok(result)
+}
+
+/// Options for search_file
+#[napi(object)]
+pub struct FileSearchOptions {
+    /// Bytes read at a time (default: 1 MiB); lines longer than this are buffered whole
+    pub chunk_size: Option<u32>,
+    /// Matches returned at most; counts keep covering the whole file (default: unlimited)
+    pub max_matches: Option<u32>,
+}
+
+/// One match in a streamed file
+#[napi(object)]
+pub struct FileSearchMatch {
+    pub pattern: String,
+    /// 1-based
+    pub line: i64,
+    /// 1-based, in characters
+    pub column: u32,
+    /// Byte offset of the match in the file
+    pub offset: i64,
+    pub text: String,
+    /// The whole line the match starts on, without its terminator
+    pub line_text: String,
+}
+
+/// Result of search_file
+#[napi(object)]
+pub struct FileSearchResult {
+    pub path: String,
+    /// Ordered by position
+    pub matches: Vec<FileSearchMatch>,
+    /// Matches per pattern over the whole file, including any past max_matches
+    pub counts: HashMap<String, i64>,
+    pub bytes_read: i64,
+    pub lines: i64,
+    /// max_matches cut matches off
+    pub truncated: bool,
+}
+
+/// Scan `path` chunk by chunk, handing each run of whole lines to the matcher
+fn stream_file(
+    path: &str,
+    set: &PatternSet,
+    options: Option<&FileSearchOptions>,
+    cancel: &CancelFlag,
+) -> Result<FileSearchResult> {
+    let chunk_size = options
+        .and_then(|o| o.chunk_size)
+        .unwrap_or(DEFAULT_CHUNK_SIZE)
+        .max(1) as usize;
+    let limit = options
+        .and_then(|o| o.max_matches)
+        .map_or(usize::MAX, |max| max as usize);
+    let mut file = std::fs::File::open(path).map_err(|e| {
+        Error::new(
+            Status::GenericFailure,
+            format!("Failed to open {}: {}", path, e),
+        )
+    })?;
+
+    let mut result = FileSearchResult {
+        path: path.to_string(),
+        matches: Vec::new(),
+        counts: HashMap::new(),
+        bytes_read: 0,
+        lines: 0,
+        truncated: false,
+    };
+    let mut counts = vec![0i64; set.pattern_list().len()];
+    let mut pending: Vec<u8> = Vec::with_capacity(chunk_size * 2);
+    let mut chunk = vec![0u8; chunk_size];
+    // File offset of pending[0]
+    let mut base = 0i64;
+    let mut at_end = false;
+    while !at_end {
+        cancel.check()?;
+        let read = file.read(&mut chunk).map_err(|e| {
+            Error::new(
+                Status::GenericFailure,
+                format!("Failed to read {}: {}", path, e),
+            )
+        })?;
+        result.bytes_read += read as i64;
+        pending.extend_from_slice(&chunk[..read]);
+        at_end = read == 0;
+        // Search only whole lines; the partial last line waits for the next chunk
+        let cut = if at_end {
+            pending.len()
+        } else {
+            match memchr::memrchr(b'\n', &pending) {
+                Some(newline) => newline + 1,
+                None => continue,
+            }
+        };
+        if cut == 0 {
+            continue;
+        }
+        let text = String::from_utf8_lossy(&pending[..cut]);
+        let chunk_counts = set.counts_in(&text);
+        for (total, count) in counts.iter_mut().zip(&chunk_counts) {
+            *total += *count as i64;
+        }
+        let room = limit.saturating_sub(result.matches.len());
+        if room > 0 {
+            // One past the room shows whether this chunk overflows it
+            let found = set.find_in(&text, room.saturating_add(1));
+            result.truncated |= found.len() > room;
+            for m in found.into_iter().take(room) {
+                let start = m.start as usize;
+                let line_start = text[..start].rfind('\n').map_or(0, |i| i + 1);
+                let line_end = text[start..].find('\n').map_or(text.len(), |i| start + i);
+                result.matches.push(FileSearchMatch {
+                    pattern: m.pattern,
+                    line: result.lines + m.line as i64,
+                    column: m.column,
+                    offset: base + m.start as i64,
+                    text: m.text,
+                    line_text: text[line_start..line_end]
+                        .trim_end_matches('\r')
+                        .to_string(),
+                });
+            }
+        } else if chunk_counts.iter().any(|count| *count > 0) {
+            result.truncated = true;
+        }
+        result.lines += memchr::memchr_iter(b'\n', &pending[..cut]).count() as i64;
+        if at_end && !pending[..cut].ends_with(b"\n") {
+            // An unterminated last line still counts
+            result.lines += 1;
+        }
+        base += cut as i64;
+        pending.drain(..cut);
+    }
+    result.counts = set.pattern_list().iter().cloned().zip(counts).collect();
+    Ok(result)
+}
+
+/**
+ * Search a file for regex patterns without loading it whole
+ *
+ * The file is read in chunks cut at line boundaries, so memory stays at
+ * about one chunk plus the longest line however large the file is.
+ * Matches therefore never span lines. Content is read as UTF-8 with
+ * invalid bytes replaced.
+ *
+ * @param path - File to search
+ * @param patterns - Regex patterns; an invalid pattern fails the call
+ * @param options - Chunk size and match cap
+ * @returns Matches with line, column, and file offset, plus per-pattern counts
+ */
+#[napi]
+pub fn search_file(
+    path: String,
+    patterns: Vec<String>,
+    options: Option<FileSearchOptions>,
+) -> Result<FileSearchResult> {
+    let set = PatternSet::compile(patterns, false)?;
+    stream_file(&path, &set, options.as_ref(), &CancelFlag::default())
+}
+
+/// Background task behind search_file_async
+pub struct FileSearchTask {
+    path: String,
+    set: PatternSet,
+    options: Option<FileSearchOptions>,
+    cancel: CancelFlag,
+}
+
+impl Task for FileSearchTask {
+    type Output = FileSearchResult;
+    type JsValue = FileSearchResult;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        stream_file(&self.path, &self.set, self.options.as_ref(), &self.cancel)
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(output)
+    }
+}
+
+/**
+ * search_file off the main thread
+ * Same result, returned as a Promise
+ *
+ * Aborting the optional signal stops reading at the next chunk and
+ * rejects with an AbortError.
+ */
+#[napi(
+    ts_args_type = "path: string, patterns: Array<string>, options?: FileSearchOptions | undefined | null, signal?: AbortSignal | undefined | null",
+    ts_return_type = "Promise<FileSearchResult>"
+)]
+pub fn search_file_async(
+    env: Env,
+    path: String,
+    patterns: Vec<String>,
+    options: Option<FileSearchOptions>,
+    signal: Option<JsObject>,
+) -> Result<AsyncTask<FileSearchTask>> {
+    Ok(AsyncTask::new(FileSearchTask {
+        path,
+        set: PatternSet::compile(patterns, false)?,
+        options,
+        cancel: CancelFlag::from_signal(&env, signal)?,
+    }))
+}
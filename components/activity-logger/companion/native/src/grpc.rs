@@ -0,0 +1,352 @@
+/*!
+ * gRPC telemetry stream
+ * Event batches streamed to a collector over one long-lived bidirectional
+ * call, with per-batch acknowledgements bounding how many are in flight
+ */
+
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{mpsc, watch, OwnedSemaphorePermit, Semaphore};
+use tokio::task::JoinHandle;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::codec::ProstCodec;
+use tonic::codegen::http::uri::PathAndQuery;
+use tonic::metadata::{Ascii, MetadataKey, MetadataValue};
+use tonic::transport::{ClientTlsConfig, Endpoint};
+
+/// The one RPC of the Collector service in proto/telemetry.proto
+const STREAM_EVENTS_PATH: &str = "/cursor.telemetry.v1.Collector/StreamEvents";
+const DEFAULT_MAX_IN_FLIGHT: u32 = 64;
+const DEFAULT_CONNECT_TIMEOUT_MS: u32 = 10_000;
+
+/// A batch of events, each as JSON, numbered so the collector can acknowledge it
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct EventBatch {
+    #[prost(uint64, tag = "1")]
+    pub sequence: u64,
+    #[prost(string, repeated, tag = "2")]
+    pub events: Vec<String>,
+}
+
+/// The collector's answer to one batch
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Ack {
+    #[prost(uint64, tag = "1")]
+    pub sequence: u64,
+    #[prost(uint32, tag = "2")]
+    pub accepted: u32,
+    #[prost(uint32, tag = "3")]
+    pub rejected: u32,
+    #[prost(string, tag = "4")]
+    pub error: String,
+}
+
+/// Options for connect_grpc
+#[napi(object)]
+pub struct GrpcOptions {
+    /// Request metadata, e.g. { authorization: "Bearer …" }
+    pub headers: Option<HashMap<String, String>>,
+    /// Batches sent but not yet acknowledged before send waits (default: 64)
+    pub max_in_flight: Option<u32>,
+    /// (default: 10000)
+    pub connect_timeout_ms: Option<u32>,
+}
+
+/// Counters for a GrpcStream
+#[napi(object)]
+pub struct GrpcStreamStats {
+    /// Batches sent
+    pub sent: u32,
+    /// Batches the collector acknowledged
+    pub acknowledged: u32,
+    /// Batches sent and not yet acknowledged
+    pub in_flight: u32,
+    /// Events the collector reported accepting
+    pub accepted: u32,
+    /// Events the collector reported rejecting
+    pub rejected: u32,
+    /// The collector's last non-empty error message
+    pub last_error: Option<String>,
+}
+
+/// State shared with the task reading acknowledgements
+struct Shared {
+    in_flight: Arc<Semaphore>,
+    /// Permit held by each unacknowledged batch, by sequence
+    permits: Mutex<BTreeMap<u64, OwnedSemaphorePermit>>,
+    sent: AtomicU64,
+    /// Batches acknowledged so far; flush waits on it
+    acknowledged: watch::Sender<u64>,
+    accepted: AtomicU64,
+    rejected: AtomicU64,
+    last_error: Mutex<Option<String>>,
+    /// Why the stream ended early; later calls fail with it
+    failure: Mutex<Option<String>>,
+}
+
+fn grpc_error(message: String) -> Error {
+    Error::new(Status::GenericFailure, message)
+}
+
+impl Shared {
+    fn acknowledge(&self, ack: &Ack) {
+        // Dropping the permit lets a waiting send through
+        if self.permits.lock().unwrap().remove(&ack.sequence).is_none() {
+            return;
+        }
+        self.accepted
+            .fetch_add(ack.accepted as u64, Ordering::Relaxed);
+        self.rejected
+            .fetch_add(ack.rejected as u64, Ordering::Relaxed);
+        if !ack.error.is_empty() {
+            *self.last_error.lock().unwrap() = Some(ack.error.clone());
+        }
+        self.acknowledged.send_modify(|count| *count += 1);
+    }
+
+    /// Stop accepting batches, recording why when the stream ended early
+    fn end(&self, failure: Option<String>) {
+        if let Some(message) = failure {
+            self.failure.lock().unwrap().get_or_insert(message);
+        }
+        self.in_flight.close();
+        // Wake flush so it sees the stream has ended
+        self.acknowledged.send_modify(|_| {});
+    }
+
+    fn error(&self) -> Error {
+        grpc_error(
+            self.failure
+                .lock()
+                .unwrap()
+                .clone()
+                .unwrap_or_else(|| "The stream is closed".to_string()),
+        )
+    }
+}
+
+/// A bidirectional stream of event batches to a collector
+#[napi]
+pub struct GrpcStream {
+    endpoint: String,
+    /// Dropped by close, which ends the request stream
+    batches: Mutex<Option<mpsc::Sender<EventBatch>>>,
+    reader: Mutex<Option<JoinHandle<()>>>,
+    next_sequence: AtomicU64,
+    shared: Arc<Shared>,
+}
+
+#[napi]
+impl GrpcStream {
+    /**
+     * Send a batch of events; resolves with its sequence number
+     *
+     * Waits while max_in_flight batches are unacknowledged, so a slow
+     * collector slows the sender instead of letting batches pile up in
+     * memory. Rejects once the stream has failed or been closed.
+     */
+    #[napi(ts_args_type = "events: Array<Record<string, any>>")]
+    pub async fn send(&self, events: Vec<Value>) -> Result<i64> {
+        let permit = self
+            .shared
+            .in_flight
+            .clone()
+            .acquire_owned()
+            .await
+            .map_err(|_| self.shared.error())?;
+        let batches = self
+            .batches
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| self.shared.error())?;
+        let sequence = self.next_sequence.fetch_add(1, Ordering::Relaxed);
+        self.shared.permits.lock().unwrap().insert(sequence, permit);
+        self.shared.sent.fetch_add(1, Ordering::Relaxed);
+        let batch = EventBatch {
+            sequence,
+            events: events.iter().map(Value::to_string).collect(),
+        };
+        batches.send(batch).await.map_err(|_| self.shared.error())?;
+        Ok(sequence as i64)
+    }
+
+    /// Resolve once every batch sent so far is acknowledged; rejects if the stream fails first
+    #[napi]
+    pub async fn flush(&self) -> Result<()> {
+        let target = self.shared.sent.load(Ordering::Relaxed);
+        let mut acknowledged = self.shared.acknowledged.subscribe();
+        loop {
+            if *acknowledged.borrow_and_update() >= target {
+                return Ok(());
+            }
+            if self.shared.failure.lock().unwrap().is_some() {
+                return Err(self.shared.error());
+            }
+            if acknowledged.changed().await.is_err() {
+                return Err(self.shared.error());
+            }
+        }
+    }
+
+    /**
+     * End the stream and wait for the collector to finish it
+     *
+     * Batches already sent are still acknowledged; rejects if the
+     * collector ends the call with some unacknowledged.
+     */
+    #[napi]
+    pub async fn close(&self) -> Result<()> {
+        self.batches.lock().unwrap().take();
+        let reader = self.reader.lock().unwrap().take();
+        if let Some(reader) = reader {
+            let _ = reader.await;
+        }
+        match self.shared.failure.lock().unwrap().clone() {
+            Some(failure) => Err(grpc_error(failure)),
+            None => Ok(()),
+        }
+    }
+
+    #[napi]
+    pub fn stats(&self) -> GrpcStreamStats {
+        let sent = self.shared.sent.load(Ordering::Relaxed);
+        let acknowledged = *self.shared.acknowledged.borrow();
+        GrpcStreamStats {
+            sent: sent as u32,
+            acknowledged: acknowledged as u32,
+            in_flight: self.shared.permits.lock().unwrap().len() as u32,
+            accepted: self.shared.accepted.load(Ordering::Relaxed) as u32,
+            rejected: self.shared.rejected.load(Ordering::Relaxed) as u32,
+            last_error: self.shared.last_error.lock().unwrap().clone(),
+        }
+    }
+
+    /// The collector address connected to
+    #[napi(getter)]
+    pub fn endpoint(&self) -> String {
+        self.endpoint.clone()
+    }
+}
+
+/**
+ * Open a stream of event batches to a gRPC collector
+ *
+ * Calls Collector.StreamEvents from proto/telemetry.proto, a
+ * bidirectional stream: batches go out as they are sent, and the
+ * collector answers each with an Ack carrying its sequence number and
+ * accepted and rejected counts. "https://" endpoints use TLS with the
+ * system's root certificates.
+ *
+ * @param endpoint - Collector address, e.g. "http://collector.internal:50051"
+ * @param options - Metadata, in-flight limit, and connect timeout
+ * @returns The open stream
+ */
+#[napi]
+pub async fn connect_grpc(endpoint: String, options: Option<GrpcOptions>) -> Result<GrpcStream> {
+    let options = options.unwrap_or(GrpcOptions {
+        headers: None,
+        max_in_flight: None,
+        connect_timeout_ms: None,
+    });
+    let invalid = |message: String| Error::new(Status::InvalidArg, message);
+    let mut target = Endpoint::from_shared(endpoint.clone())
+        .map_err(|e| invalid(format!("Invalid endpoint '{}': {}", endpoint, e)))?
+        .connect_timeout(Duration::from_millis(
+            options
+                .connect_timeout_ms
+                .unwrap_or(DEFAULT_CONNECT_TIMEOUT_MS) as u64,
+        ));
+    if endpoint.starts_with("https://") {
+        target = target
+            .tls_config(ClientTlsConfig::new().with_native_roots())
+            .map_err(|e| invalid(format!("TLS setup for {} failed: {}", endpoint, e)))?;
+    }
+    let max_in_flight = options
+        .max_in_flight
+        .unwrap_or(DEFAULT_MAX_IN_FLIGHT)
+        .max(1) as usize;
+    let (batches, outgoing) = mpsc::channel(max_in_flight);
+    let mut request = tonic::Request::new(ReceiverStream::new(outgoing));
+    for (name, value) in options.headers.into_iter().flatten() {
+        let key = MetadataKey::<Ascii>::from_bytes(name.to_lowercase().as_bytes())
+            .map_err(|_| invalid(format!("Invalid metadata key '{}'", name)))?;
+        let value: MetadataValue<Ascii> = value
+            .parse()
+            .map_err(|_| invalid(format!("Invalid metadata value for '{}'", name)))?;
+        request.metadata_mut().insert(key, value);
+    }
+    let channel = target
+        .connect()
+        .await
+        .map_err(|e| grpc_error(format!("Failed to connect to {}: {}", endpoint, e)))?;
+    let mut client = tonic::client::Grpc::new(channel);
+    client
+        .ready()
+        .await
+        .map_err(|e| grpc_error(format!("{} is not ready: {}", endpoint, e)))?;
+    let mut acks = client
+        .streaming(
+            request,
+            PathAndQuery::from_static(STREAM_EVENTS_PATH),
+            ProstCodec::<EventBatch, Ack>::default(),
+        )
+        .await
+        .map_err(|status| {
+            grpc_error(format!(
+                "{} refused the stream: {}",
+                endpoint,
+                status.message()
+            ))
+        })?
+        .into_inner();
+    let shared = Arc::new(Shared {
+        in_flight: Arc::new(Semaphore::new(max_in_flight)),
+        permits: Mutex::new(BTreeMap::new()),
+        sent: AtomicU64::new(0),
+        acknowledged: watch::channel(0).0,
+        accepted: AtomicU64::new(0),
+        rejected: AtomicU64::new(0),
+        last_error: Mutex::new(None),
+        failure: Mutex::new(None),
+    });
+    let reader = {
+        let shared = shared.clone();
+        tokio::spawn(async move {
+            loop {
+                match acks.message().await {
+                    Ok(Some(ack)) => shared.acknowledge(&ack),
+                    Ok(None) => {
+                        let unacknowledged = shared.permits.lock().unwrap().len();
+                        shared.end(Some(unacknowledged).filter(|n| *n > 0).map(|n| {
+                            format!(
+                                "Collector ended the stream with {} batch(es) unacknowledged",
+                                n
+                            )
+                        }));
+                        break;
+                    }
+                    Err(status) => {
+                        shared.end(Some(format!(
+                            "Collector stream failed: {}",
+                            status.message()
+                        )));
+                        break;
+                    }
+                }
+            }
+        })
+    };
+    Ok(GrpcStream {
+        endpoint,
+        batches: Mutex::new(Some(batches)),
+        reader: Mutex::new(Some(reader)),
+        next_sequence: AtomicU64::new(0),
+        shared,
+    })
+}
@@ -0,0 +1,314 @@
+/*!
+ * Halstead metrics and maintainability index
+ * Classical size/effort metrics computed from tokenized source
+ */
+
+use crate::comments::{comment_syntax, strip_comments_and_strings, CommentScanner, LineKind};
+use crate::complexity::decision_points;
+use ahash::AHashMap;
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+
+/// Words counted as operators rather than operands
+pub(crate) const KEYWORDS: &[&str] = &[
+    "and",
+    "as",
+    "assert",
+    "async",
+    "await",
+    "break",
+    "case",
+    "catch",
+    "chan",
+    "class",
+    "const",
+    "continue",
+    "def",
+    "default",
+    "defer",
+    "del",
+    "delete",
+    "do",
+    "elif",
+    "else",
+    "enum",
+    "except",
+    "export",
+    "extends",
+    "finally",
+    "fn",
+    "for",
+    "from",
+    "func",
+    "function",
+    "global",
+    "go",
+    "goto",
+    "if",
+    "impl",
+    "implements",
+    "import",
+    "in",
+    "instanceof",
+    "interface",
+    "is",
+    "lambda",
+    "let",
+    "loop",
+    "match",
+    "mod",
+    "move",
+    "mut",
+    "new",
+    "nonlocal",
+    "not",
+    "of",
+    "or",
+    "package",
+    "pass",
+    "pub",
+    "raise",
+    "ref",
+    "return",
+    "select",
+    "sizeof",
+    "static",
+    "struct",
+    "switch",
+    "throw",
+    "throws",
+    "trait",
+    "try",
+    "type",
+    "typeof",
+    "unsafe",
+    "use",
+    "var",
+    "void",
+    "where",
+    "while",
+    "with",
+    "yield",
+];
+
+/// Multi-character operators, longest first so the scan can take the first match
+pub(crate) const OPERATORS: &[&str] = &[
+    ">>>=", "<<=", ">>=", "===", "!==", "**=", "//=", "...", "..=", "->", "=>", "::", "==", "!=",
+    "<=", ">=", "&&", "||", "++", "--", "+=", "-=", "*=", "/=", "%=", "&=", "|=", "^=", "<<", ">>",
+    "**", "//", "?.", "??", ":=", "..",
+];
+
+/// Halstead counts, derived measures, and maintainability index for one file
+#[napi(object)]
+pub struct HalsteadMetrics {
+    pub language: String,
+    /// Distinct operators (n1)
+    pub distinct_operators: u32,
+    /// Distinct operands (n2)
+    pub distinct_operands: u32,
+    /// Operator occurrences (N1)
+    pub total_operators: u32,
+    /// Operand occurrences (N2)
+    pub total_operands: u32,
+    /// n1 + n2
+    pub vocabulary: u32,
+    /// N1 + N2
+    pub length: u32,
+    /// N × log2(n)
+    pub volume: f64,
+    /// (n1 / 2) × (N2 / n2)
+    pub difficulty: f64,
+    /// Difficulty × volume
+    pub effort: f64,
+    /// Effort / 18, Halstead's estimate of seconds to write the code
+    pub time_seconds: f64,
+    /// Volume / 3000, Halstead's delivered-bugs estimate
+    pub estimated_bugs: f64,
+    /// Non-blank, non-comment lines
+    pub code_lines: u32,
+    /// File-level cyclomatic complexity (1 + decision points)
+    pub cyclomatic: u32,
+    /// 171 − 5.2 ln(V) − 0.23 G − 16.2 ln(LOC), rescaled to 0–100
+    pub maintainability_index: f64,
+}
+
+/**
+ * Calculate Halstead metrics and maintainability index
+ *
+ * Tokenizes comment-stripped code into operators (punctuation, keywords,
+ * opening brackets) and operands (identifiers, numbers, string literals),
+ * then combines volume, cyclomatic complexity, and code lines into the
+ * rescaled maintainability index used by Visual Studio (100 = trivial).
+ *
+ * @param content - Source code to measure
+ * @param language - Language name as returned by detect_language
+ * @returns Halstead counts and derived measures
+ */
+#[napi]
+pub fn calculate_halstead(content: String, language: String) -> Result<HalsteadMetrics> {
+    let code = strip_comments_and_strings(&content, &language);
+    let mut operators: AHashMap<&str, u32> = AHashMap::new();
+    let mut operands: AHashMap<&str, u32> = AHashMap::new();
+
+    for token in tokenize(&content, &code, &language) {
+        let (text, kind) = token;
+        let counts = match kind {
+            TokenKind::Operator => &mut operators,
+            TokenKind::Operand => &mut operands,
+        };
+        *counts.entry(text).or_insert(0) += 1;
+    }
+
+    let n1 = operators.len() as u32;
+    let n2 = operands.len() as u32;
+    let total1: u32 = operators.values().sum();
+    let total2: u32 = operands.values().sum();
+    let vocabulary = n1 + n2;
+    let length = total1 + total2;
+    let volume = if vocabulary > 0 {
+        length as f64 * (vocabulary as f64).log2()
+    } else {
+        0.0
+    };
+    let difficulty = if n2 > 0 {
+        (n1 as f64 / 2.0) * (total2 as f64 / n2 as f64)
+    } else {
+        0.0
+    };
+    let effort = difficulty * volume;
+
+    let mut scanner = CommentScanner::new(comment_syntax(&language));
+    let code_lines = content
+        .lines()
+        .filter(|line| scanner.classify(line) == LineKind::Code)
+        .count() as u32;
+    let cyclomatic = 1 + decision_points(&code, &language);
+    let maintainability_index = maintainability_index(volume, cyclomatic, code_lines);
+
+    Ok(HalsteadMetrics {
+        language,
+        distinct_operators: n1,
+        distinct_operands: n2,
+        total_operators: total1,
+        total_operands: total2,
+        vocabulary,
+        length,
+        volume,
+        difficulty,
+        effort,
+        time_seconds: effort / 18.0,
+        estimated_bugs: volume / 3000.0,
+        code_lines,
+        cyclomatic,
+        maintainability_index,
+    })
+}
+
+/// Maintainability index rescaled to 0–100; empty code is perfectly maintainable
+fn maintainability_index(volume: f64, cyclomatic: u32, code_lines: u32) -> f64 {
+    if volume <= 0.0 || code_lines == 0 {
+        return 100.0;
+    }
+    let raw =
+        171.0 - 5.2 * volume.ln() - 0.23 * cyclomatic as f64 - 16.2 * (code_lines as f64).ln();
+    (raw * 100.0 / 171.0).clamp(0.0, 100.0)
+}
+
+#[derive(Clone, Copy)]
+enum TokenKind {
+    Operator,
+    Operand,
+}
+
+/// Split stripped code into tokens; string literals are read back from the original
+fn tokenize<'a>(original: &'a str, code: &'a str, language: &str) -> Vec<(&'a str, TokenKind)> {
+    let bytes = code.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let b = bytes[i];
+        if b.is_ascii_whitespace() || matches!(b, b')' | b']' | b'}') {
+            // Closing brackets are the other half of an already counted operator
+            i += 1;
+        } else if b.is_ascii_alphabetic() || b == b'_' || b == b'$' || b >= 0x80 {
+            let start = i;
+            while i < bytes.len()
+                && (bytes[i].is_ascii_alphanumeric()
+                    || matches!(bytes[i], b'_' | b'$')
+                    || bytes[i] >= 0x80)
+            {
+                i += 1;
+            }
+            let word = &code[start..i];
+            let kind = if KEYWORDS.contains(&word) {
+                TokenKind::Operator
+            } else {
+                TokenKind::Operand
+            };
+            tokens.push((word, kind));
+        } else if b.is_ascii_digit() {
+            let start = i;
+            while i < bytes.len()
+                && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_' || bytes[i] == b'.')
+            {
+                i += 1;
+            }
+            tokens.push((&code[start..i], TokenKind::Operand));
+        } else if matches!(b, b'"' | b'\'' | b'`') {
+            let end = literal_end(bytes, i, language);
+            let kind = if end == i + 1 {
+                TokenKind::Operator
+            } else {
+                TokenKind::Operand
+            };
+            tokens.push((&original[i..end], kind));
+            i = end;
+        } else {
+            let rest = &code[i..];
+            let op = OPERATORS
+                .iter()
+                .find(|op| rest.starts_with(**op))
+                .copied()
+                .unwrap_or(&rest[..rest.chars().next().map_or(1, char::len_utf8)]);
+            tokens.push((op, TokenKind::Operator));
+            i += op.len();
+        }
+    }
+
+    tokens
+}
+
+/// End of the string literal (or Rust lifetime) starting at `start` in stripped code
+///
+/// Stripping blanks literal contents, so the closing quote is simply the
+/// next matching quote run.
+fn literal_end(bytes: &[u8], start: usize, language: &str) -> usize {
+    let quote = bytes[start];
+    let next = bytes.get(start + 1).copied();
+    if language == "rust"
+        && quote == b'\''
+        && next.is_some_and(|c| c.is_ascii_alphabetic() || c == b'_')
+    {
+        let mut end = start + 1;
+        while end < bytes.len() && (bytes[end].is_ascii_alphanumeric() || bytes[end] == b'_') {
+            end += 1;
+        }
+        return end;
+    }
+
+    let triple = language == "python" && bytes[start..].starts_with(&[quote; 3]);
+    let open = if triple { 3 } else { 1 };
+    let mut end = start + open;
+    while end < bytes.len() {
+        if bytes[end] == b'\n' && !triple && quote != b'`' && language != "rust" && language != "go"
+        {
+            return end;
+        }
+        if bytes[end] == quote && (!triple || bytes[end..].starts_with(&[quote; 3])) {
+            return end + open;
+        }
+        end += 1;
+    }
+    bytes.len()
+}
@@ -0,0 +1,228 @@
+/*!
+ * Identifier extraction
+ * Vocabulary of a file from the lexer's identifier tokens, split into declarations and usages
+ */
+
+use crate::functions::{line_of, line_starts};
+use crate::lexer::{lex, Lexeme, TokenKind};
+use ahash::{AHashMap, AHashSet};
+use napi_derive::napi;
+use std::collections::HashMap;
+
+/// One identifier and how it is used
+#[napi(object)]
+pub struct IdentifierStat {
+    pub name: String,
+    /// All occurrences
+    pub count: u32,
+    /// Occurrences that introduce the name: `let x`, `def f`, `class C`, Python/Ruby `x = ...`, Go `x := ...`
+    pub declarations: u32,
+    /// count - declarations
+    pub usages: u32,
+    /// 1-based line of the first occurrence
+    pub first_line: u32,
+    /// "camelCase", "PascalCase", "snake_case", "SCREAMING_CASE", "kebab-case", or "lowercase"
+    pub style: String,
+}
+
+/// Result of extract_identifiers
+#[napi(object)]
+pub struct IdentifierReport {
+    /// Most frequent first, then by name
+    pub identifiers: Vec<IdentifierStat>,
+    pub total: u32,
+    pub distinct: u32,
+    /// Names declared in this text
+    pub declared: u32,
+    /// Share of distinct names per naming style, 0-1
+    pub styles: HashMap<String, f64>,
+}
+
+/// Vocabulary change between two versions, from compare_identifiers
+#[napi(object)]
+pub struct IdentifierDelta {
+    /// Names in after but not before, sorted
+    pub introduced: Vec<String>,
+    /// Names in before but not after, sorted
+    pub removed: Vec<String>,
+    /// Names in both
+    pub kept: u32,
+    /// (introduced + removed) / names in either, 0-1
+    pub churn: f64,
+}
+
+/// Keywords whose next identifier is the name being declared
+const DECLARING: &[&str] = &[
+    "let",
+    "const",
+    "var",
+    "val",
+    "fn",
+    "fun",
+    "func",
+    "def",
+    "function",
+    "class",
+    "struct",
+    "enum",
+    "trait",
+    "interface",
+    "type",
+    "mod",
+    "module",
+    "impl",
+    "static",
+    "sub",
+    "local",
+];
+
+/// Words skipped between a declaring keyword and the name: `let mut x`, `pub fn`, `async function`
+const MODIFIERS: &[&str] = &["mut", "ref", "async", "unsafe", "pub"];
+
+fn style(name: &str) -> &'static str {
+    let has_lower = name.chars().any(char::is_lowercase);
+    let has_upper = name.chars().any(char::is_uppercase);
+    if name.contains('-') {
+        "kebab-case"
+    } else if !has_lower && has_upper {
+        "SCREAMING_CASE"
+    } else if name.trim_matches('_').contains('_') {
+        "snake_case"
+    } else if name.starts_with(char::is_uppercase) {
+        "PascalCase"
+    } else if has_upper {
+        "camelCase"
+    } else {
+        "lowercase"
+    }
+}
+
+/// Identifier tokens of `content`, each flagged when it declares the name
+fn scan<'a>(content: &'a str, language: &str) -> Vec<(&'a str, bool, usize)> {
+    let tokens: Vec<Lexeme> = lex(content, language)
+        .into_iter()
+        .filter(|token| !matches!(token.kind, TokenKind::Comment | TokenKind::String))
+        .collect();
+    let text = |token: &Lexeme| &content[token.start..token.end];
+    let assigns_by_statement = matches!(language, "python" | "ruby" | "lua" | "r" | "shell");
+
+    let mut out = Vec::new();
+    let mut declaring = false;
+    let mut line_start = true;
+    for (i, token) in tokens.iter().enumerate() {
+        let word = text(token);
+        match token.kind {
+            TokenKind::Whitespace => {
+                line_start |= word.contains('\n');
+                continue;
+            }
+            TokenKind::Keyword | TokenKind::Identifier if DECLARING.contains(&word) => {
+                declaring = true;
+            }
+            TokenKind::Keyword if MODIFIERS.contains(&word) => {}
+            TokenKind::Identifier => {
+                let next = tokens[i + 1..]
+                    .iter()
+                    .find(|t| t.kind != TokenKind::Whitespace)
+                    .map(text);
+                let declares = declaring
+                    || next == Some(":=")
+                    || (assigns_by_statement && line_start && next == Some("="));
+                out.push((word, declares, token.start));
+                declaring = false;
+            }
+            _ => declaring = false,
+        }
+        line_start = false;
+    }
+    out
+}
+
+/**
+ * Extract identifiers with their counts
+ *
+ * Identifiers come from the tokenizer (tokenize_code), so names inside
+ * comments and strings are ignored. An occurrence counts as a declaration
+ * when it follows a declaring keyword (`let`, `const`, `fn`, `def`,
+ * `class`, `struct`, `type`, ...), is assigned with Go's `:=`, or, in
+ * Python, Ruby, Lua, R, and shell, is assigned at the start of a line.
+ *
+ * @param content - Source code
+ * @param language - Language name as returned by detect_language
+ * @returns Identifiers with counts, declaration/usage split, first line, and naming style
+ */
+#[napi]
+pub fn extract_identifiers(content: String, language: String) -> IdentifierReport {
+    let line_starts = line_starts(&content);
+    let mut stats: AHashMap<&str, IdentifierStat> = AHashMap::new();
+    let occurrences = scan(&content, &language);
+    for &(name, declares, offset) in &occurrences {
+        let stat = stats.entry(name).or_insert_with(|| IdentifierStat {
+            name: name.to_string(),
+            count: 0,
+            declarations: 0,
+            usages: 0,
+            first_line: line_of(&line_starts, offset),
+            style: style(name).to_string(),
+        });
+        stat.count += 1;
+        if declares {
+            stat.declarations += 1;
+        } else {
+            stat.usages += 1;
+        }
+    }
+
+    let mut identifiers: Vec<IdentifierStat> = stats.into_values().collect();
+    identifiers.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.name.cmp(&b.name)));
+    let mut styles: HashMap<String, f64> = HashMap::new();
+    for identifier in &identifiers {
+        *styles.entry(identifier.style.clone()).or_insert(0.0) += 1.0;
+    }
+    for share in styles.values_mut() {
+        *share /= identifiers.len() as f64;
+    }
+    IdentifierReport {
+        total: occurrences.len() as u32,
+        distinct: identifiers.len() as u32,
+        declared: identifiers.iter().filter(|i| i.declarations > 0).count() as u32,
+        identifiers,
+        styles,
+    }
+}
+
+/**
+ * Compare the vocabulary of two versions of a file
+ *
+ * @param before - Earlier content
+ * @param after - Later content
+ * @param language - Language name as returned by detect_language
+ * @returns Names introduced and removed, names kept, and the churn ratio
+ */
+#[napi]
+pub fn compare_identifiers(before: String, after: String, language: String) -> IdentifierDelta {
+    let names = |content: &str| -> AHashSet<String> {
+        scan(content, &language)
+            .into_iter()
+            .map(|(name, _, _)| name.to_string())
+            .collect()
+    };
+    let before = names(&before);
+    let after = names(&after);
+    let mut introduced: Vec<String> = after.difference(&before).cloned().collect();
+    let mut removed: Vec<String> = before.difference(&after).cloned().collect();
+    introduced.sort();
+    removed.sort();
+    let kept = before.intersection(&after).count();
+    let union = introduced.len() + removed.len() + kept;
+    IdentifierDelta {
+        churn: if union == 0 {
+            0.0
+        } else {
+            (introduced.len() + removed.len()) as f64 / union as f64
+        },
+        introduced,
+        removed,
+        kept: kept as u32,
+    }
+}
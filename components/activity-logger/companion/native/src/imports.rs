@@ -0,0 +1,313 @@
+/*!
+ * Import extraction
+ * Modules and packages a file depends on, for "which libraries did this session touch" reports
+ */
+
+use crate::comments::strip_comments_and_strings;
+use crate::functions::{line_of, line_starts};
+use napi_derive::napi;
+use regex::{Captures, Regex};
+use std::sync::LazyLock;
+
+/// One import statement (or one module of a multi-module statement)
+#[napi(object)]
+pub struct Import {
+    /// Module path as written, e.g. "./util", "os.path", "std::collections", "net/http"
+    pub module: String,
+    /// Top-level package or library, e.g. "@scope/pkg", "os", "serde"; None for relative imports
+    pub package: Option<String>,
+    /// Names imported from the module when listed ("*" for wildcards); empty for whole-module imports
+    pub names: Vec<String>,
+    /// Imports of the project's own files (./x, from . import, crate::, #include "x")
+    pub relative: bool,
+    /// 1-based line of the statement
+    pub line: u32,
+}
+
+/// Modules named by one statement, with the names imported from each
+type Parse = fn(&Captures) -> Vec<(String, Vec<String>)>;
+
+struct Pattern {
+    regex: Regex,
+    parse: Parse,
+}
+
+fn pattern(regex: &str, parse: Parse) -> Pattern {
+    Pattern {
+        regex: Regex::new(regex).unwrap(),
+        parse,
+    }
+}
+
+/// Comma-separated names, without `as` aliases, type-only markers, or braces
+fn name_list(list: &str) -> Vec<String> {
+    list.split(',')
+        .map(|name| {
+            let name = name.trim().trim_start_matches("type ");
+            let name = name.split(" as ").next().unwrap_or(name);
+            name.trim_matches(|c: char| c == '{' || c == '}' || c == '(' || c == ')')
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+fn group(caps: &Captures, index: usize) -> String {
+    caps.get(index)
+        .map(|m| m.as_str().to_string())
+        .unwrap_or_default()
+}
+
+/// Split `a.b.C` into module `a.b` and name `C`
+fn split_last(path: &str, separator: &str) -> (String, Vec<String>) {
+    match path.rsplit_once(separator) {
+        Some((module, name)) => (module.to_string(), vec![name.to_string()]),
+        None => (path.to_string(), Vec::new()),
+    }
+}
+
+static JAVASCRIPT: LazyLock<Vec<Pattern>> = LazyLock::new(|| {
+    vec![
+        pattern(
+            r#"(?m)^[ \t]*import\s+(?:type\s+)?([\w$*{}\s,]+?)\s+from\s*['"]([^'"\n]+)['"]"#,
+            |caps| {
+                let clause = group(caps, 1);
+                let names = clause
+                    .split_inclusive(['{', '}', ','])
+                    .flat_map(|part| name_list(part.trim_end_matches(['{', '}'])))
+                    .map(|name| {
+                        if name.starts_with('*') {
+                            "*".to_string()
+                        } else {
+                            name
+                        }
+                    })
+                    .collect();
+                vec![(group(caps, 2), names)]
+            },
+        ),
+        pattern(r#"(?m)^[ \t]*import\s*['"]([^'"\n]+)['"]"#, |caps| {
+            vec![(group(caps, 1), Vec::new())]
+        }),
+        pattern(
+            r#"(?m)^[ \t]*export\s+(?:type\s+)?(\*(?:\s+as\s+\w+)?|\{[^}]*\})\s*from\s*['"]([^'"\n]+)['"]"#,
+            |caps| {
+                let clause = group(caps, 1);
+                let names = if clause.starts_with('*') {
+                    vec!["*".to_string()]
+                } else {
+                    name_list(&clause)
+                };
+                vec![(group(caps, 2), names)]
+            },
+        ),
+        pattern(
+            r#"\b(?:require|import)\s*\(\s*['"]([^'"\n]+)['"]\s*\)"#,
+            |caps| vec![(group(caps, 1), Vec::new())],
+        ),
+    ]
+});
+
+static PYTHON: LazyLock<Vec<Pattern>> = LazyLock::new(|| {
+    vec![
+        pattern(
+            r"(?m)^[ \t]*from\s+(\.*[\w.]*)\s+import\s+(\([^)]*\)|[^\n#]+)",
+            |caps| vec![(group(caps, 1), name_list(&group(caps, 2)))],
+        ),
+        pattern(r"(?m)^[ \t]*import\s+([^\n#;]+)", |caps| {
+            name_list(&group(caps, 1))
+                .into_iter()
+                .map(|module| (module, Vec::new()))
+                .collect()
+        }),
+    ]
+});
+
+static RUST: LazyLock<Vec<Pattern>> = LazyLock::new(|| {
+    vec![
+        pattern(
+            r"(?m)^[ \t]*(?:pub(?:\([^)]*\))?\s+)?use\s+([\w:]+?)(?:::\{([^;]*)\}|::(\*))?\s*(?:as\s+\w+\s*)?;",
+            |caps| {
+                let path = group(caps, 1);
+                if let Some(list) = caps.get(2) {
+                    return vec![(path, name_list(list.as_str()))];
+                }
+                if caps.get(3).is_some() {
+                    return vec![(path, vec!["*".to_string()])];
+                }
+                vec![split_last(&path, "::")]
+            },
+        ),
+        pattern(r"(?m)^[ \t]*extern\s+crate\s+(\w+)", |caps| {
+            vec![(group(caps, 1), Vec::new())]
+        }),
+    ]
+});
+
+static GO: LazyLock<Vec<Pattern>> = LazyLock::new(|| {
+    vec![
+        pattern(r#"(?m)^[ \t]*import\s+(?:[\w.]+\s+)?"([^"\n]+)""#, |caps| {
+            vec![(group(caps, 1), Vec::new())]
+        }),
+        pattern(r"(?ms)^[ \t]*import\s*\(([^)]*)\)", |caps| {
+            static SPEC: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#""([^"\n]+)""#).unwrap());
+            SPEC.captures_iter(&group(caps, 1))
+                .map(|spec| (group(&spec, 1), Vec::new()))
+                .collect()
+        }),
+    ]
+});
+
+static JAVA: LazyLock<Vec<Pattern>> = LazyLock::new(|| {
+    vec![pattern(
+        r"(?m)^[ \t]*import\s+(?:static\s+)?([\w.]+(?:\.\*)?)\s*;",
+        |caps| vec![split_last(&group(caps, 1), ".")],
+    )]
+});
+
+static CSHARP: LazyLock<Vec<Pattern>> = LazyLock::new(|| {
+    vec![pattern(
+        r"(?m)^[ \t]*(?:global\s+)?using\s+(?:static\s+)?(?:\w+\s*=\s*)?([A-Z][\w.]*)\s*;",
+        |caps| vec![(group(caps, 1), Vec::new())],
+    )]
+});
+
+static C: LazyLock<Vec<Pattern>> = LazyLock::new(|| {
+    vec![pattern(
+        r#"(?m)^[ \t]*#\s*include\s*([<"])([^>"\n]+)[>"]"#,
+        |caps| {
+            // Mark quoted includes so they are treated as local headers
+            let module = group(caps, 2);
+            if group(caps, 1) == "\"" {
+                vec![(format!("\"{}", module), Vec::new())]
+            } else {
+                vec![(module, Vec::new())]
+            }
+        },
+    )]
+});
+
+static RUBY: LazyLock<Vec<Pattern>> = LazyLock::new(|| {
+    vec![pattern(
+        r#"(?m)^[ \t]*(require|require_relative|load)\s*\(?\s*['"]([^'"\n]+)['"]"#,
+        |caps| {
+            let module = group(caps, 2);
+            if group(caps, 1) == "require_relative" && !module.starts_with('.') {
+                vec![(format!("./{}", module), Vec::new())]
+            } else {
+                vec![(module, Vec::new())]
+            }
+        },
+    )]
+});
+
+/// Whether `module` refers to the project's own code, and its package otherwise
+fn classify(module: &str, language: &str) -> (bool, Option<String>) {
+    let relative = match language {
+        "python" => module.starts_with('.'),
+        "rust" => ["crate", "self", "super"]
+            .iter()
+            .any(|root| module == *root || module.starts_with(&format!("{}::", root))),
+        "c" | "cpp" => module.starts_with('"'),
+        _ => module.starts_with('.') || module.starts_with('/'),
+    };
+    if relative {
+        return (true, None);
+    }
+    let package = match language {
+        "javascript" | "typescript" => {
+            let module = module.strip_prefix("node:").unwrap_or(module);
+            let segments = if module.starts_with('@') { 2 } else { 1 };
+            module
+                .split('/')
+                .take(segments)
+                .collect::<Vec<_>>()
+                .join("/")
+        }
+        "python" | "csharp" => module.split('.').next().unwrap_or(module).to_string(),
+        // Reverse-domain packages: org.junit, com.google
+        "java" => module.split('.').take(2).collect::<Vec<_>>().join("."),
+        "rust" => module.split("::").next().unwrap_or(module).to_string(),
+        // Module paths with a host (github.com/org/repo) are versioned at the repository root
+        "go" if module
+            .split('/')
+            .next()
+            .is_some_and(|host| host.contains('.')) =>
+        {
+            module.split('/').take(3).collect::<Vec<_>>().join("/")
+        }
+        "ruby" => module.split('/').next().unwrap_or(module).to_string(),
+        _ => module.to_string(),
+    };
+    (false, Some(package))
+}
+
+/**
+ * Extract imported modules and packages from a file
+ *
+ * Recognizes import statements in javascript/typescript (import, export
+ * from, require, dynamic import), python, rust (use, extern crate), go,
+ * java, csharp (using), c/cpp (#include), and ruby (require). Statements
+ * inside comments and strings are ignored. Each import reports the module
+ * as written, its top-level package for grouping by library, the names
+ * imported, and whether it refers to the project's own files.
+ *
+ * @param content - Source code
+ * @param language - Language name as returned by detect_language
+ * @returns Imports in source order
+ */
+#[napi]
+pub fn extract_imports(content: String, language: String) -> Vec<Import> {
+    let patterns: &[Pattern] = match language.as_str() {
+        "javascript" | "typescript" => &JAVASCRIPT,
+        "python" => &PYTHON,
+        "rust" => &RUST,
+        "go" => &GO,
+        "java" => &JAVA,
+        "csharp" => &CSHARP,
+        "c" | "cpp" => &C,
+        "ruby" => &RUBY,
+        _ => return Vec::new(),
+    };
+    // Offsets line up with the original, and comments and strings are blanked out,
+    // so a match whose first character was blanked starts inside one
+    let code = strip_comments_and_strings(&content, &language);
+    let starts = line_starts(&content);
+
+    let mut found: Vec<(usize, Import)> = Vec::new();
+    for pattern in patterns {
+        for caps in pattern.regex.captures_iter(&content) {
+            let whole = caps.get(0).unwrap();
+            let start = whole.start() + (whole.as_str().len() - whole.as_str().trim_start().len());
+            if code
+                .as_bytes()
+                .get(start)
+                .is_none_or(|b| b.is_ascii_whitespace())
+            {
+                continue;
+            }
+            // A statement already matched by an earlier, more specific pattern
+            if found.iter().any(|(offset, _)| *offset == start) {
+                continue;
+            }
+            for (module, names) in (pattern.parse)(&caps) {
+                let (relative, package) = classify(&module, &language);
+                found.push((
+                    start,
+                    Import {
+                        module: module.trim_start_matches('"').to_string(),
+                        package,
+                        names,
+                        relative,
+                        line: line_of(&starts, start),
+                    },
+                ));
+            }
+        }
+    }
+
+    found.sort_by_key(|(offset, _)| *offset);
+    found.into_iter().map(|(_, import)| import).collect()
+}
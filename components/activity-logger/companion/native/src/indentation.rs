@@ -0,0 +1,179 @@
+/*!
+ * Indentation style detection
+ * Tabs vs spaces, indent width, and style drift between file versions
+ */
+
+use napi_derive::napi;
+
+/// Space indent widths considered when inferring the file's unit
+const CANDIDATE_WIDTHS: [u32; 4] = [2, 4, 8, 3];
+
+/// Dominant indentation style of a file
+#[napi(object)]
+pub struct IndentationStyle {
+    /// "spaces", "tabs", or "none" when no line is indented
+    pub style: String,
+    /// Spaces per indent level (0 for tabs or none)
+    pub width: u32,
+    pub indented_lines: u32,
+    pub space_lines: u32,
+    pub tab_lines: u32,
+    /// Lines whose leading whitespace contains both tabs and spaces
+    pub mixed_lines: u32,
+    /// Lines indented against the dominant style (minority character or mixed)
+    pub inconsistent_lines: u32,
+    /// Space-indented lines whose depth is not a multiple of the width
+    pub misaligned_lines: u32,
+}
+
+/// Indentation changes between two versions of a file
+#[napi(object)]
+pub struct IndentationDrift {
+    pub before: IndentationStyle,
+    pub after: IndentationStyle,
+    /// Dominant style flipped between tabs and spaces
+    pub style_changed: bool,
+    /// Both versions use spaces but with different widths
+    pub width_changed: bool,
+    /// Increase in inconsistent lines (0 if the new version is no worse)
+    pub new_inconsistent_lines: u32,
+    /// Whether the new version drifted from the established style
+    pub drifted: bool,
+}
+
+/**
+ * Detect a file's indentation style
+ *
+ * The width is the most common increase in leading spaces between
+ * consecutive indented lines. Blank lines and block comment continuation
+ * lines (` * ...`) are ignored.
+ *
+ * @param content - File content
+ * @returns Dominant style, width, and inconsistency counts
+ */
+#[napi]
+pub fn detect_indentation(content: String) -> IndentationStyle {
+    analyze(&content)
+}
+
+/**
+ * Compare indentation before and after an edit
+ *
+ * Used to flag AI apply operations that introduce a different indentation
+ * style or width, or new inconsistently indented lines, into a file.
+ *
+ * @param before - Content before the edit
+ * @param after - Content after the edit
+ * @returns Both styles and what changed
+ */
+#[napi]
+pub fn detect_indentation_drift(before: String, after: String) -> IndentationDrift {
+    let before = analyze(&before);
+    let after = analyze(&after);
+    let established = before.style != "none" && after.style != "none";
+    let style_changed = established && before.style != after.style;
+    let width_changed = established
+        && before.style == "spaces"
+        && after.style == "spaces"
+        && before.width != after.width;
+    let new_inconsistent_lines = after
+        .inconsistent_lines
+        .saturating_sub(before.inconsistent_lines);
+
+    IndentationDrift {
+        drifted: style_changed || width_changed || new_inconsistent_lines > 0,
+        before,
+        after,
+        style_changed,
+        width_changed,
+        new_inconsistent_lines,
+    }
+}
+
+fn analyze(content: &str) -> IndentationStyle {
+    let mut space_lines = 0u32;
+    let mut tab_lines = 0u32;
+    let mut mixed_lines = 0u32;
+    let mut space_depths: Vec<u32> = Vec::new();
+    let mut width_votes = [0u32; CANDIDATE_WIDTHS.len()];
+    let mut previous_spaces = 0u32;
+
+    for line in content.lines() {
+        let trimmed = line.trim_start_matches([' ', '\t']);
+        if trimmed.trim().is_empty() || trimmed.starts_with('*') {
+            continue;
+        }
+        let leading = &line[..line.len() - trimmed.len()];
+        let has_tab = leading.contains('\t');
+        let has_space = leading.contains(' ');
+        let spaces = if has_tab { 0 } else { leading.len() as u32 };
+
+        match (has_tab, has_space) {
+            (true, true) => mixed_lines += 1,
+            (true, false) => tab_lines += 1,
+            (false, true) => {
+                space_lines += 1;
+                space_depths.push(spaces);
+            }
+            (false, false) => {}
+        }
+        if !has_tab && spaces > previous_spaces {
+            let delta = spaces - previous_spaces;
+            if let Some(i) = CANDIDATE_WIDTHS.iter().position(|w| *w == delta) {
+                width_votes[i] += 1;
+            }
+        }
+        if !has_tab {
+            previous_spaces = spaces;
+        }
+    }
+
+    let indented_lines = space_lines + tab_lines + mixed_lines;
+    let style = if indented_lines == 0 {
+        "none"
+    } else if tab_lines > space_lines {
+        "tabs"
+    } else {
+        "spaces"
+    };
+    let width = if style == "spaces" {
+        // max_by_key keeps the last maximum, so scan in reverse to let ties go to
+        // the earlier (more common) candidate
+        let (best, votes) = width_votes
+            .iter()
+            .enumerate()
+            .rev()
+            .max_by_key(|(_, votes)| **votes)
+            .map(|(i, votes)| (i, *votes))
+            .unwrap_or((0, 0));
+        if votes > 0 {
+            CANDIDATE_WIDTHS[best]
+        } else {
+            space_depths.iter().copied().min().unwrap_or(0)
+        }
+    } else {
+        0
+    };
+    let inconsistent_lines = mixed_lines
+        + match style {
+            "tabs" => space_lines,
+            "spaces" => tab_lines,
+            _ => 0,
+        };
+    let misaligned_lines = if width > 0 {
+        space_depths.iter().filter(|d| *d % width != 0).count() as u32
+    } else {
+        0
+    };
+
+    IndentationStyle {
+        style: style.to_string(),
+        width,
+        indented_lines,
+        space_lines,
+        tab_lines,
+        mixed_lines,
+        inconsistent_lines,
+        misaligned_lines,
+    }
+}
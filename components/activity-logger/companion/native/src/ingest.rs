@@ -0,0 +1,539 @@
+/*!
+ * Micro-batch ingestion
+ * Validates, redacts, and coalesces a batch of raw events before it is persisted
+ */
+
+use crate::events::{MS_PER_DAY, MS_PER_MINUTE};
+use crate::sessions::days_from_civil;
+use ahash::AHashMap;
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use regex::Regex;
+use serde_json::{Map, Value};
+use std::sync::LazyLock;
+
+pub(crate) const OUTCOME_LABELS: [&str; 3] = ["good", "bad", "reverted"];
+/// Top-level fields that identify an event and are never redacted
+const IDENTITY_FIELDS: [&str; 12] = [
+    "id",
+    "type",
+    "timestamp",
+    "session_id",
+    "sessionId",
+    "workspace_path",
+    "workspacePath",
+    "file_path",
+    "filePath",
+    "run_id",
+    "runId",
+    "label",
+];
+pub(crate) const EDIT_TYPES: [&str; 2] = ["code_change", "file_change"];
+
+/// Secrets that must never reach the store, with their replacement
+static SECRET_PATTERNS: LazyLock<Vec<(Regex, &'static str)>> = LazyLock::new(|| {
+    [
+        (
+            r"-----BEGIN [A-Z ]*PRIVATE KEY-----[\s\S]*?-----END [A-Z ]*PRIVATE KEY-----",
+            "[PRIVATE_KEY]",
+        ),
+        (
+            r"\beyJ[A-Za-z0-9_-]+\.eyJ[A-Za-z0-9_-]+\.[A-Za-z0-9_-]{10,}",
+            "[JWT_TOKEN]",
+        ),
+        (r"(?i)\b(bearer\s+)[A-Za-z0-9._~+/=-]{16,}", "${1}[BEARER_TOKEN]"),
+        (r"\b(?:AKIA|ASIA)[0-9A-Z]{16}\b", "[AWS_KEY]"),
+        (
+            r"\b(?:gh[pousr]_[A-Za-z0-9]{36,}|xox[abpr]-[A-Za-z0-9-]{10,}|sk-[A-Za-z0-9_-]{20,})",
+            "[API_KEY]",
+        ),
+        (
+            r#"(?i)\b(password|passwd|secret|api[_-]?key|access[_-]?token|auth[_-]?token)(\\?['"]?\s*[:=]\s*\\?['"]?)[^\s'"\\]{8,}"#,
+            "${1}${2}[SECRET]",
+        ),
+    ]
+    .into_iter()
+    .map(|(pattern, replacement)| (Regex::new(pattern).unwrap(), replacement))
+    .collect()
+});
+
+/// Options for ingest_batch
+#[napi(object)]
+pub struct IngestOptions {
+    /// "atomic" (default): any invalid event rejects the whole batch;
+    /// "partial": valid events are accepted and invalid ones reported
+    pub mode: Option<String>,
+    /// Replace secrets (keys, tokens, passwords) in event text (default: true)
+    pub redact: Option<bool>,
+    /// Merge consecutive edits to the same file in the same session that are
+    /// at most this far apart (default: 0, no coalescing)
+    pub coalesce_window_ms: Option<u32>,
+}
+
+/// An event that failed validation
+#[napi(object)]
+pub struct IngestRejection {
+    /// Position in the input batch
+    pub index: u32,
+    pub id: Option<String>,
+    /// "InvalidEvent", "MissingType", "InvalidTimestamp", "InvalidId", "InvalidCount", or "InvalidLabel"
+    pub code: String,
+    pub message: String,
+}
+
+/// Result of ingest_batch
+#[napi(object)]
+pub struct IngestBatch {
+    /// True when every event was accepted
+    pub ok: bool,
+    pub mode: String,
+    pub received: u32,
+    /// Events to persist, in input order; empty when an atomic batch had rejections
+    #[napi(ts_type = "Array<Record<string, any>>")]
+    pub accepted: Vec<Value>,
+    pub rejected: Vec<IngestRejection>,
+    /// Events folded into a later edit of the same file
+    pub coalesced: u32,
+    /// Events dropped because a later event in the batch has the same id
+    pub duplicates: u32,
+    /// Secrets replaced across all accepted events
+    pub redactions: u32,
+}
+
+/// A validated event waiting to be coalesced
+struct Staged {
+    event: Map<String, Value>,
+    timestamp_ms: f64,
+    /// Number of input events this one now stands for
+    merged: u32,
+}
+
+fn string_field<'a>(event: &'a Map<String, Value>, keys: &[&str]) -> Option<&'a str> {
+    keys.iter().find_map(|key| event.get(*key)?.as_str())
+}
+
+/// `details` as an object, parsing it when it is stored as a JSON string
+pub(crate) fn details_object(event: &Map<String, Value>) -> Option<Map<String, Value>> {
+    match event.get("details")? {
+        Value::Object(details) => Some(details.clone()),
+        Value::String(text) => match serde_json::from_str(text) {
+            Ok(Value::Object(details)) => Some(details),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// A field that may live at the top level or inside `details`
+pub(crate) fn event_field(
+    event: &Map<String, Value>,
+    details: Option<&Map<String, Value>>,
+    keys: &[&str],
+) -> Option<Value> {
+    keys.iter()
+        .find_map(|key| event.get(*key))
+        .or_else(|| details.and_then(|d| keys.iter().find_map(|key| d.get(*key))))
+        .cloned()
+}
+
+/// Epoch milliseconds from a number or an ISO-8601 string
+pub(crate) fn parse_timestamp(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64().filter(|ms| ms.is_finite() && *ms >= 0.0),
+        Value::String(text) => parse_iso8601(text),
+        _ => None,
+    }
+}
+
+/// YYYY-MM-DD[(T| )HH:MM[:SS[.fff]]][Z|±HH:MM]
+fn parse_iso8601(text: &str) -> Option<f64> {
+    static ISO: LazyLock<Regex> = LazyLock::new(|| {
+        Regex::new(
+            r"^(\d{4})-(\d{2})-(\d{2})(?:[T ](\d{2}):(\d{2})(?::(\d{2})(\.\d+)?)?)?(Z|[+-]\d{2}:?\d{2})?$",
+        )
+        .unwrap()
+    });
+    let caps = ISO.captures(text.trim())?;
+    let number = |i: usize| -> i64 {
+        caps.get(i)
+            .and_then(|m| m.as_str().parse().ok())
+            .unwrap_or(0)
+    };
+    let (month, day) = (number(2), number(3));
+    let (hour, minute, second) = (number(4), number(5), number(6));
+    if !(1..=12).contains(&month)
+        || !(1..=31).contains(&day)
+        || hour > 23
+        || minute > 59
+        || second > 60
+    {
+        return None;
+    }
+    let fraction: f64 = caps
+        .get(7)
+        .and_then(|m| format!("0{}", m.as_str()).parse().ok())
+        .unwrap_or(0.0);
+    let offset_minutes = match caps.get(8).map(|m| m.as_str()) {
+        None | Some("Z") => 0,
+        Some(offset) => {
+            let digits: String = offset[1..].chars().filter(|c| c.is_ascii_digit()).collect();
+            let minutes =
+                digits[..2].parse::<i64>().ok()? * 60 + digits[2..].parse::<i64>().ok()?;
+            if offset.starts_with('-') {
+                -minutes
+            } else {
+                minutes
+            }
+        }
+    };
+    let ms = days_from_civil(number(1), month, day) * MS_PER_DAY
+        + ((hour * 60 + minute - offset_minutes) * MS_PER_MINUTE)
+        + second * 1000;
+    Some(ms as f64 + (fraction * 1000.0).round())
+}
+
+fn reject(index: usize, id: Option<String>, code: &str, message: String) -> IngestRejection {
+    IngestRejection {
+        index: index as u32,
+        id,
+        code: code.to_string(),
+        message,
+    }
+}
+
+/// Check one event, returning its timestamp in epoch milliseconds
+fn validate(index: usize, value: &Value) -> std::result::Result<f64, IngestRejection> {
+    let Value::Object(event) = value else {
+        return Err(reject(
+            index,
+            None,
+            "InvalidEvent",
+            "Event must be an object".to_string(),
+        ));
+    };
+    let id = match event.get("id") {
+        None | Some(Value::Null) => None,
+        Some(Value::String(id)) => Some(id.clone()),
+        Some(Value::Number(id)) => Some(id.to_string()),
+        Some(_) => {
+            return Err(reject(
+                index,
+                None,
+                "InvalidId",
+                "id must be a string or number".to_string(),
+            ))
+        }
+    };
+    if event
+        .get("type")
+        .and_then(Value::as_str)
+        .is_none_or(|t| t.trim().is_empty())
+    {
+        return Err(reject(
+            index,
+            id,
+            "MissingType",
+            "Event has no type".to_string(),
+        ));
+    }
+    let Some(timestamp) = event.get("timestamp").and_then(parse_timestamp) else {
+        return Err(reject(
+            index,
+            id,
+            "InvalidTimestamp",
+            "timestamp must be epoch milliseconds or an ISO-8601 string".to_string(),
+        ));
+    };
+
+    let details = details_object(event);
+    for keys in [
+        ["lines_added", "linesAdded"],
+        ["lines_removed", "linesRemoved"],
+    ] {
+        match event_field(event, details.as_ref(), &keys) {
+            None | Some(Value::Null) => {}
+            Some(Value::Number(n)) if n.as_u64().is_some() => {}
+            Some(other) => {
+                return Err(reject(
+                    index,
+                    id,
+                    "InvalidCount",
+                    format!("{} must be a non-negative integer, got {}", keys[0], other),
+                ))
+            }
+        }
+    }
+    match event.get("label") {
+        None | Some(Value::Null) => {}
+        Some(Value::String(label)) if OUTCOME_LABELS.contains(&label.as_str()) => {}
+        Some(other) => {
+            return Err(reject(
+                index,
+                id,
+                "InvalidLabel",
+                format!("label must be one of good, bad, reverted, got {}", other),
+            ))
+        }
+    }
+    Ok(timestamp)
+}
+
+fn redact_text(text: &mut String) -> u32 {
+    let mut count = 0;
+    for (pattern, replacement) in SECRET_PATTERNS.iter() {
+        let matches = pattern.find_iter(text).count() as u32;
+        if matches > 0 {
+            *text = pattern.replace_all(text, *replacement).into_owned();
+            count += matches;
+        }
+    }
+    count
+}
+
+fn redact_value(value: &mut Value) -> u32 {
+    match value {
+        Value::String(text) => redact_text(text),
+        Value::Array(items) => items.iter_mut().map(redact_value).sum(),
+        Value::Object(fields) => fields.values_mut().map(redact_value).sum(),
+        _ => 0,
+    }
+}
+
+fn redact_event(event: &mut Map<String, Value>) -> u32 {
+    event
+        .iter_mut()
+        .filter(|(key, _)| !IDENTITY_FIELDS.contains(&key.as_str()))
+        .map(|(_, value)| redact_value(value))
+        .sum()
+}
+
+/// Key of the edit stream an event belongs to, if it can be coalesced
+fn edit_stream(event: &Map<String, Value>) -> Option<(String, String)> {
+    let event_type = string_field(event, &["type"])?;
+    if !EDIT_TYPES.contains(&event_type) {
+        return None;
+    }
+    let session = string_field(event, &["session_id", "sessionId"]).unwrap_or("");
+    let details = details_object(event);
+    let file = match event_field(event, details.as_ref(), &["file_path", "filePath"])? {
+        Value::String(file) => file,
+        _ => return None,
+    };
+    Some((session.to_string(), file))
+}
+
+fn count_field(event: &Map<String, Value>, keys: &[&str]) -> u64 {
+    let details = details_object(event);
+    event_field(event, details.as_ref(), keys)
+        .and_then(|v| v.as_u64())
+        .unwrap_or(0)
+}
+
+/// Write a field back where the event keeps it (top level or `details`)
+fn set_field(event: &mut Map<String, Value>, keys: &[&str], value: Value) {
+    if let Some(key) = keys.iter().find(|key| event.contains_key(**key)) {
+        event.insert(key.to_string(), value);
+        return;
+    }
+    let Some(details) = details_object(event) else {
+        event.insert(keys[0].to_string(), value);
+        return;
+    };
+    let key = keys
+        .iter()
+        .find(|key| details.contains_key(**key))
+        .unwrap_or(&keys[0]);
+    set_detail(event, key, value);
+}
+
+/// Write a field into `details`, keeping it a JSON string if that is how it is stored
+fn set_detail(event: &mut Map<String, Value>, key: &str, value: Value) {
+    let mut details = details_object(event).unwrap_or_default();
+    details.insert(key.to_string(), value);
+    let details = Value::Object(details);
+    let stored = if event.get("details").is_some_and(Value::is_string) {
+        Value::String(details.to_string())
+    } else {
+        details
+    };
+    event.insert("details".to_string(), stored);
+}
+
+/// Ids of the input events a staged event stands for, earliest first
+fn merged_ids(event: &Map<String, Value>) -> Vec<Value> {
+    if let Some(Value::Array(ids)) =
+        details_object(event).and_then(|d| d.get("merged_ids").cloned())
+    {
+        return ids;
+    }
+    event
+        .get("id")
+        .filter(|id| !id.is_null())
+        .cloned()
+        .into_iter()
+        .collect()
+}
+
+/// Fold `earlier` into `later`: line counts add up, the later content wins
+/// but the earlier before-state is kept, and the merged event keeps the
+/// earlier id so references to it stay valid. `details.merged_ids` lists
+/// every id it now stands for.
+fn merge_into(later: &mut Staged, earlier: Staged) {
+    for keys in [
+        ["lines_added", "linesAdded"],
+        ["lines_removed", "linesRemoved"],
+    ] {
+        let total = count_field(&later.event, &keys) + count_field(&earlier.event, &keys);
+        set_field(&mut later.event, &keys, Value::from(total));
+    }
+    let earlier_details = details_object(&earlier.event);
+    for keys in [
+        ["before_code", "beforeCode"],
+        ["before_content", "beforeContent"],
+    ] {
+        if let Some(before) = event_field(&earlier.event, earlier_details.as_ref(), &keys) {
+            set_field(&mut later.event, &keys, before);
+        }
+    }
+    let mut ids = merged_ids(&earlier.event);
+    ids.extend(merged_ids(&later.event));
+    set_detail(&mut later.event, "merged_ids", Value::Array(ids));
+    if let Some(id) = earlier.event.get("id").filter(|id| !id.is_null()) {
+        later.event.insert("id".to_string(), id.clone());
+    }
+    later.merged += earlier.merged;
+    later
+        .event
+        .insert("coalesced".to_string(), Value::from(later.merged));
+}
+
+/**
+ * Prepare a batch of raw events for persistence
+ *
+ * Validates each event (object with a type, a timestamp in epoch ms or
+ * ISO-8601, non-negative line counts, and a known outcome label), replaces
+ * secrets in event text, drops earlier copies of repeated ids, and, given a
+ * coalescing window, merges consecutive edits of the same file. Fields may be
+ * snake_case or camelCase, and line counts and file paths may sit inside
+ * `details`.
+ *
+ * In "atomic" mode a single invalid event rejects the batch so the caller
+ * persists nothing; "partial" mode returns the valid events alongside the
+ * rejections.
+ *
+ * @param events - Raw events as captured
+ * @param options - Mode, redaction, and coalescing window
+ * @returns Events ready to write in one transaction, plus any rejections
+ */
+#[napi(
+    ts_args_type = "events: Array<Record<string, any>>, options?: IngestOptions | undefined | null"
+)]
+pub fn ingest_batch(events: Vec<Value>, options: Option<IngestOptions>) -> Result<IngestBatch> {
+    let options = options.unwrap_or(IngestOptions {
+        mode: None,
+        redact: None,
+        coalesce_window_ms: None,
+    });
+    let mode = options.mode.unwrap_or_else(|| "atomic".to_string());
+    if !matches!(mode.as_str(), "atomic" | "partial") {
+        return Err(Error::new(
+            Status::InvalidArg,
+            format!("Unknown mode '{}', expected atomic or partial", mode),
+        ));
+    }
+    let redact = options.redact.unwrap_or(true);
+    let window_ms = options.coalesce_window_ms.unwrap_or(0) as f64;
+    let received = events.len() as u32;
+
+    let mut staged: Vec<Staged> = Vec::with_capacity(events.len());
+    let mut rejected = Vec::new();
+    for (index, value) in events.into_iter().enumerate() {
+        match validate(index, &value) {
+            Ok(timestamp_ms) => {
+                if let Value::Object(event) = value {
+                    staged.push(Staged {
+                        event,
+                        timestamp_ms,
+                        merged: 1,
+                    });
+                }
+            }
+            Err(rejection) => rejected.push(rejection),
+        }
+    }
+
+    if mode == "atomic" && !rejected.is_empty() {
+        return Ok(IngestBatch {
+            ok: false,
+            mode,
+            received,
+            accepted: Vec::new(),
+            rejected,
+            coalesced: 0,
+            duplicates: 0,
+            redactions: 0,
+        });
+    }
+
+    // The store upserts by id, so only the last copy of an id would survive anyway
+    let mut last_by_id: AHashMap<String, usize> = AHashMap::new();
+    for (i, staged) in staged.iter().enumerate() {
+        if let Some(id) = staged.event.get("id").filter(|id| !id.is_null()) {
+            last_by_id.insert(id.to_string(), i);
+        }
+    }
+    let before_dedup = staged.len();
+    let staged: Vec<Staged> = staged
+        .into_iter()
+        .enumerate()
+        .filter(
+            |(i, staged)| match staged.event.get("id").filter(|id| !id.is_null()) {
+                Some(id) => last_by_id.get(&id.to_string()) == Some(i),
+                None => true,
+            },
+        )
+        .map(|(_, staged)| staged)
+        .collect();
+    let duplicates = (before_dedup - staged.len()) as u32;
+
+    let mut redactions = 0;
+    let mut accepted: Vec<Staged> = Vec::with_capacity(staged.len());
+    // Index into `accepted` of the latest edit per (session, file)
+    let mut open_streams: AHashMap<(String, String), usize> = AHashMap::new();
+    let mut coalesced = 0;
+    for mut item in staged {
+        if redact {
+            redactions += redact_event(&mut item.event);
+        }
+        let stream = (window_ms > 0.0)
+            .then(|| edit_stream(&item.event))
+            .flatten();
+        if let Some(stream) = stream {
+            if let Some(&previous) = open_streams.get(&stream) {
+                let is_latest = previous == accepted.len() - 1;
+                let gap = item.timestamp_ms - accepted[previous].timestamp_ms;
+                if is_latest && (0.0..=window_ms).contains(&gap) {
+                    let earlier = accepted.pop().unwrap();
+                    merge_into(&mut item, earlier);
+                    coalesced += 1;
+                }
+            }
+            open_streams.insert(stream, accepted.len());
+        }
+        accepted.push(item);
+    }
+
+    Ok(IngestBatch {
+        ok: rejected.is_empty(),
+        mode,
+        received,
+        accepted: accepted
+            .into_iter()
+            .map(|staged| Value::Object(staged.event))
+            .collect(),
+        rejected,
+        coalesced,
+        duplicates,
+        redactions,
+    })
+}
@@ -0,0 +1,622 @@
+/*!
+ * Append-only event log
+ * Newline-delimited JSON segments with batched fsync, size and age
+ * rotation, and compaction of sealed segments
+ */
+
+use crate::ingest::parse_timestamp;
+use crate::retention::{
+    aggregate_record, Cutoffs, Fate, RetentionPolicy, RetentionProgress, Rollup, AGGREGATE_TYPE,
+};
+use ahash::AHashMap;
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use serde_json::Value;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+const DEFAULT_PREFIX: &str = "events";
+const DEFAULT_MAX_SEGMENT_BYTES: u32 = 64 * 1024 * 1024;
+const DEFAULT_MAX_SEGMENT_AGE_MS: u32 = 24 * 60 * 60 * 1000;
+const DEFAULT_FSYNC_EVERY: u32 = 100;
+const DEFAULT_FSYNC_INTERVAL_MS: u32 = 1_000;
+
+/// Options for a JsonlLog
+#[napi(object)]
+pub struct JsonlLogOptions {
+    /// Segment file name prefix, `<prefix>-<sequence>-<created ms>.jsonl` (default: "events")
+    pub prefix: Option<String>,
+    /// Start a new segment once the active one reaches this size (default: 64 MiB)
+    pub max_segment_bytes: Option<u32>,
+    /// Start a new segment once the active one is this old; 0 disables (default: 24 hours)
+    pub max_segment_age_ms: Option<u32>,
+    /// Fsync after this many unsynced events (default: 100)
+    pub fsync_every: Option<u32>,
+    /// Fsync when the last one was at least this long ago (default: 1000)
+    pub fsync_interval_ms: Option<u32>,
+}
+
+/// One segment file
+#[napi(object)]
+pub struct JsonlSegment {
+    pub path: String,
+    pub sequence: u32,
+    /// When the segment was started, in epoch milliseconds
+    pub created_ms: f64,
+    pub bytes: f64,
+    /// The segment currently appended to
+    pub active: bool,
+}
+
+/// Result of JsonlLog.append
+#[napi(object)]
+pub struct JsonlAppend {
+    /// Events written
+    pub written: u32,
+    /// Whether this append ended with an fsync
+    pub synced: bool,
+    /// Segment the events went to
+    pub segment: String,
+}
+
+/// Options for JsonlLog.compact
+#[napi(object)]
+pub struct CompactOptions {
+    /// Only compact segments started at least this long ago (default: 0, every sealed segment)
+    pub older_than_ms: Option<u32>,
+    /// Drop events with a timestamp earlier than this, in epoch milliseconds
+    pub drop_before: Option<f64>,
+    /// Keep only the last copy of each event id (default: true)
+    pub dedupe: Option<bool>,
+}
+
+/// Result of JsonlLog.compact
+#[napi(object)]
+pub struct CompactResult {
+    /// Sealed segments merged; 0 when there was nothing to compact
+    pub segments_merged: u32,
+    /// The merged segment, or none when every event was dropped
+    pub path: Option<String>,
+    pub lines_kept: u32,
+    /// Older copies of an id that a later line replaced
+    pub duplicates_dropped: u32,
+    /// Events before drop_before
+    pub expired_dropped: u32,
+    /// Torn or unparsable lines
+    pub corrupt_dropped: u32,
+    pub bytes_before: f64,
+    pub bytes_after: f64,
+}
+
+/// Events read back from the log
+#[napi(object)]
+pub struct JsonlRead {
+    #[napi(ts_type = "Array<Record<string, any>>")]
+    pub events: Vec<Value>,
+    /// Lines skipped because they weren't valid JSON
+    pub corrupt_lines: u32,
+}
+
+struct Active {
+    file: File,
+    sequence: u32,
+    created_ms: f64,
+    bytes: u64,
+}
+
+/// Append-only JSONL log split into rotating segments
+#[napi]
+pub struct JsonlLog {
+    dir: PathBuf,
+    prefix: String,
+    max_segment_bytes: u64,
+    max_segment_age_ms: f64,
+    fsync_every: u32,
+    fsync_interval_ms: u128,
+    active: Option<Active>,
+    /// Events written since the last fsync
+    unsynced: u32,
+    last_sync: Instant,
+    /// Sequence apply_retention resumes from, mid-pass
+    retention_cursor: Option<u32>,
+}
+
+fn io_error(action: &str, path: &Path, error: std::io::Error) -> Error {
+    Error::new(
+        Status::GenericFailure,
+        format!("Failed to {} {}: {}", action, path.display(), error),
+    )
+}
+
+fn now_ms() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0.0, |d| d.as_millis() as f64)
+}
+
+/// (sequence, created ms) from `<prefix>-<sequence>-<created>.jsonl`
+fn parse_segment_name(prefix: &str, name: &str) -> Option<(u32, f64)> {
+    let rest = name.strip_prefix(prefix)?.strip_prefix('-')?;
+    let (sequence, created) = rest.strip_suffix(".jsonl")?.split_once('-')?;
+    Some((sequence.parse().ok()?, created.parse::<u64>().ok()? as f64))
+}
+
+/// Parse each line, skipping blank ones and counting the ones that fail
+fn parse_lines(text: &str) -> (Vec<Value>, u32) {
+    let mut corrupt = 0;
+    let events = text
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| match serde_json::from_str(line) {
+            Ok(event) => Some(event),
+            Err(_) => {
+                corrupt += 1;
+                None
+            }
+        })
+        .collect();
+    (events, corrupt)
+}
+
+/// One JSON line per event
+fn to_lines(events: &[Value]) -> Result<Vec<u8>> {
+    let mut buffer = Vec::with_capacity(events.len() * 256);
+    for event in events {
+        serde_json::to_writer(&mut buffer, event).map_err(|e| {
+            Error::new(
+                Status::InvalidArg,
+                format!("Failed to serialize event: {}", e),
+            )
+        })?;
+        buffer.push(b'\n');
+    }
+    Ok(buffer)
+}
+
+/// Write `buffer` to a temporary file, fsync it, and rename it over `target`
+fn replace_file(target: &Path, buffer: &[u8]) -> Result<()> {
+    let temporary = target.with_extension("jsonl.tmp");
+    File::create(&temporary)
+        .and_then(|mut file| file.write_all(buffer).and_then(|_| file.sync_all()))
+        .and_then(|_| fs::rename(&temporary, target))
+        .map_err(|e| io_error("write", &temporary, e))
+}
+
+/// An event's id as a string, for deduplication
+fn id_of(event: &Value) -> Option<String> {
+    match event.get("id")? {
+        Value::String(id) => Some(id.clone()),
+        Value::Number(id) => Some(id.to_string()),
+        _ => None,
+    }
+}
+
+impl JsonlLog {
+    fn segment_path(&self, sequence: u32, created_ms: f64) -> PathBuf {
+        self.dir.join(format!(
+            "{}-{:08}-{}.jsonl",
+            self.prefix, sequence, created_ms as u64
+        ))
+    }
+
+    /// Segments on disk, oldest first
+    fn list(&self) -> Result<Vec<(u32, f64, PathBuf)>> {
+        let entries = fs::read_dir(&self.dir).map_err(|e| io_error("read", &self.dir, e))?;
+        let mut segments: Vec<(u32, f64, PathBuf)> = entries
+            .filter_map(|entry| {
+                let entry = entry.ok()?;
+                let name = entry.file_name();
+                let (sequence, created) = parse_segment_name(&self.prefix, name.to_str()?)?;
+                Some((sequence, created, entry.path()))
+            })
+            .collect();
+        segments.sort_by_key(|segment| segment.0);
+        Ok(segments)
+    }
+
+    /// Open a segment for appending, cutting off a torn last line left by a crash
+    fn open(&self, sequence: u32, created_ms: f64) -> Result<Active> {
+        let path = self.segment_path(sequence, created_ms);
+        let contents = match fs::read(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(io_error("read", &path, e)),
+        };
+        let complete = contents
+            .iter()
+            .rposition(|b| *b == b'\n')
+            .map_or(0, |i| i + 1);
+        if complete < contents.len() {
+            OpenOptions::new()
+                .write(true)
+                .open(&path)
+                .and_then(|file| file.set_len(complete as u64).and_then(|_| file.sync_data()))
+                .map_err(|e| io_error("repair", &path, e))?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| io_error("open", &path, e))?;
+        Ok(Active {
+            file,
+            sequence,
+            created_ms,
+            bytes: complete as u64,
+        })
+    }
+
+    /// Make sure a segment is open with room, rotating when the active one is full or too old
+    fn rotate_if_needed(&mut self, incoming: u64) -> Result<()> {
+        let rotate = self.active.as_ref().is_some_and(|active| {
+            active.bytes > 0
+                && (active.bytes + incoming > self.max_segment_bytes
+                    || (self.max_segment_age_ms > 0.0
+                        && now_ms() - active.created_ms >= self.max_segment_age_ms))
+        });
+        if rotate || self.active.is_none() {
+            let sequence = self.active.as_ref().map_or(1, |active| active.sequence + 1);
+            self.sync()?;
+            self.active = Some(self.open(sequence, now_ms())?);
+        }
+        Ok(())
+    }
+
+    fn sync(&mut self) -> Result<()> {
+        if let Some(active) = &self.active {
+            if self.unsynced > 0 {
+                active.file.sync_data().map_err(|e| {
+                    io_error(
+                        "sync",
+                        &self.segment_path(active.sequence, active.created_ms),
+                        e,
+                    )
+                })?;
+            }
+        }
+        self.unsynced = 0;
+        self.last_sync = Instant::now();
+        Ok(())
+    }
+}
+
+#[napi]
+impl JsonlLog {
+    /**
+     * Open the log in `dir`, creating the directory if needed
+     *
+     * Appends resume in the newest existing segment. If the process died
+     * mid-write, the partial last line is cut off so the segment stays
+     * valid JSONL.
+     */
+    #[napi(constructor)]
+    pub fn new(dir: String, options: Option<JsonlLogOptions>) -> Result<Self> {
+        let options = options.unwrap_or(JsonlLogOptions {
+            prefix: None,
+            max_segment_bytes: None,
+            max_segment_age_ms: None,
+            fsync_every: None,
+            fsync_interval_ms: None,
+        });
+        let prefix = options.prefix.unwrap_or_else(|| DEFAULT_PREFIX.to_string());
+        if prefix.is_empty() || prefix.contains(['/', '\\']) {
+            return Err(Error::new(
+                Status::InvalidArg,
+                format!("Invalid segment prefix '{}'", prefix),
+            ));
+        }
+        let dir = PathBuf::from(dir);
+        fs::create_dir_all(&dir).map_err(|e| io_error("create", &dir, e))?;
+        let mut log = JsonlLog {
+            dir,
+            prefix,
+            max_segment_bytes: options
+                .max_segment_bytes
+                .unwrap_or(DEFAULT_MAX_SEGMENT_BYTES)
+                .max(1) as u64,
+            max_segment_age_ms: options
+                .max_segment_age_ms
+                .unwrap_or(DEFAULT_MAX_SEGMENT_AGE_MS) as f64,
+            fsync_every: options.fsync_every.unwrap_or(DEFAULT_FSYNC_EVERY).max(1),
+            fsync_interval_ms: options
+                .fsync_interval_ms
+                .unwrap_or(DEFAULT_FSYNC_INTERVAL_MS) as u128,
+            active: None,
+            unsynced: 0,
+            last_sync: Instant::now(),
+            retention_cursor: None,
+        };
+        if let Some((sequence, created, _)) = log.list()?.pop() {
+            log.active = Some(log.open(sequence, created)?);
+        }
+        Ok(log)
+    }
+
+    /**
+     * Append `events`, one JSON line each, in a single write
+     *
+     * The write reaches the OS before this returns, so it survives the
+     * process crashing. It is fsynced, surviving power loss, once
+     * fsync_every events or fsync_interval_ms have gone by since the last
+     * fsync. A batch is never split across segments.
+     */
+    #[napi(ts_args_type = "events: Array<Record<string, any>>")]
+    pub fn append(&mut self, events: Vec<Value>) -> Result<JsonlAppend> {
+        let buffer = to_lines(&events)?;
+        self.rotate_if_needed(buffer.len() as u64)?;
+        let active = self.active.as_mut().unwrap();
+        let segment = self.dir.join(format!(
+            "{}-{:08}-{}.jsonl",
+            self.prefix, active.sequence, active.created_ms as u64
+        ));
+        active
+            .file
+            .write_all(&buffer)
+            .map_err(|e| io_error("append to", &segment, e))?;
+        active.bytes += buffer.len() as u64;
+        self.unsynced += events.len() as u32;
+        let synced = self.unsynced >= self.fsync_every
+            || self.last_sync.elapsed().as_millis() >= self.fsync_interval_ms;
+        if synced {
+            self.sync()?;
+        }
+        Ok(JsonlAppend {
+            written: events.len() as u32,
+            synced,
+            segment: segment.to_string_lossy().into_owned(),
+        })
+    }
+
+    /// Fsync everything appended so far
+    #[napi]
+    pub fn flush(&mut self) -> Result<()> {
+        self.sync()
+    }
+
+    /// Seal the active segment and start a new one; returns the new segment's path
+    #[napi]
+    pub fn rotate(&mut self) -> Result<String> {
+        let sequence = match &self.active {
+            Some(active) => active.sequence + 1,
+            None => self.list()?.last().map_or(1, |segment| segment.0 + 1),
+        };
+        self.sync()?;
+        let created = now_ms();
+        self.active = Some(self.open(sequence, created)?);
+        Ok(self
+            .segment_path(sequence, created)
+            .to_string_lossy()
+            .into_owned())
+    }
+
+    /// Segment files, oldest first
+    #[napi]
+    pub fn segments(&self) -> Result<Vec<JsonlSegment>> {
+        let active = self.active.as_ref().map(|active| active.sequence);
+        self.list()?
+            .into_iter()
+            .map(|(sequence, created_ms, path)| {
+                let bytes = fs::metadata(&path)
+                    .map_err(|e| io_error("stat", &path, e))?
+                    .len();
+                Ok(JsonlSegment {
+                    path: path.to_string_lossy().into_owned(),
+                    sequence,
+                    created_ms,
+                    bytes: bytes as f64,
+                    active: active == Some(sequence),
+                })
+            })
+            .collect()
+    }
+
+    /// Every event in the log, oldest segment first; unparsable lines are skipped and counted
+    #[napi]
+    pub fn read_all(&self) -> Result<JsonlRead> {
+        let mut events = Vec::new();
+        let mut corrupt_lines = 0;
+        for (_, _, path) in self.list()? {
+            let text = fs::read_to_string(&path).map_err(|e| io_error("read", &path, e))?;
+            let (parsed, corrupt) = parse_lines(&text);
+            events.extend(parsed);
+            corrupt_lines += corrupt;
+        }
+        Ok(JsonlRead {
+            events,
+            corrupt_lines,
+        })
+    }
+
+    /**
+     * Merge sealed segments into one, dropping what is no longer needed
+     *
+     * The active segment is never touched. Corrupt lines are always
+     * dropped, expired events when `drop_before` is set, and earlier copies
+     * of an id unless `dedupe` is false. The merged segment is written to a
+     * temporary file, fsynced, and renamed over the oldest input before the
+     * other inputs are deleted, so a crash leaves at worst duplicates that
+     * the next compaction removes.
+     */
+    #[napi]
+    pub fn compact(&mut self, options: Option<CompactOptions>) -> Result<CompactResult> {
+        let older_than_ms = options.as_ref().and_then(|o| o.older_than_ms).unwrap_or(0) as f64;
+        let drop_before = options.as_ref().and_then(|o| o.drop_before);
+        let dedupe = options.and_then(|o| o.dedupe).unwrap_or(true);
+        let active = self.active.as_ref().map(|active| active.sequence);
+        let cutoff = now_ms() - older_than_ms;
+        let inputs: Vec<(u32, f64, PathBuf)> = self
+            .list()?
+            .into_iter()
+            .filter(|(sequence, created, _)| Some(*sequence) != active && *created <= cutoff)
+            .collect();
+        let mut result = CompactResult {
+            segments_merged: 0,
+            path: None,
+            lines_kept: 0,
+            duplicates_dropped: 0,
+            expired_dropped: 0,
+            corrupt_dropped: 0,
+            bytes_before: 0.0,
+            bytes_after: 0.0,
+        };
+        if inputs.is_empty() {
+            return Ok(result);
+        }
+
+        let mut events = Vec::new();
+        for (_, _, path) in &inputs {
+            let text = fs::read_to_string(path).map_err(|e| io_error("read", path, e))?;
+            result.bytes_before += text.len() as f64;
+            let (parsed, corrupt) = parse_lines(&text);
+            events.extend(parsed);
+            result.corrupt_dropped += corrupt;
+        }
+        if let Some(before) = drop_before {
+            let count = events.len();
+            events.retain(|event| {
+                event
+                    .get("timestamp")
+                    .and_then(parse_timestamp)
+                    .is_none_or(|ms| ms >= before)
+            });
+            result.expired_dropped = (count - events.len()) as u32;
+        }
+        if dedupe {
+            let mut last: AHashMap<String, usize> = AHashMap::new();
+            for (index, event) in events.iter().enumerate() {
+                if let Some(id) = id_of(event) {
+                    last.insert(id, index);
+                }
+            }
+            let count = events.len();
+            let mut index = 0;
+            events.retain(|event| {
+                let keep = id_of(event).is_none_or(|id| last[&id] == index);
+                index += 1;
+                keep
+            });
+            result.duplicates_dropped = (count - events.len()) as u32;
+        }
+
+        let (sequence, created, target) = &inputs[0];
+        if !events.is_empty() {
+            let buffer = to_lines(&events)?;
+            replace_file(target, &buffer)?;
+            result.bytes_after = buffer.len() as f64;
+            result.path = Some(
+                self.segment_path(*sequence, *created)
+                    .to_string_lossy()
+                    .into_owned(),
+            );
+        } else {
+            fs::remove_file(target).map_err(|e| io_error("remove", target, e))?;
+        }
+        for (_, _, path) in &inputs[1..] {
+            fs::remove_file(path).map_err(|e| io_error("remove", path, e))?;
+        }
+        result.segments_merged = inputs.len() as u32;
+        result.lines_kept = events.len() as u32;
+        Ok(result)
+    }
+
+    /**
+     * Apply a retention policy to the sealed segments
+     *
+     * Segments are visited oldest first, until batch_size events have been
+     * examined. In each, events within aggregate_ms are replaced by
+     * per-bucket rollup records (`type: "aggregate"`, timestamped at the
+     * bucket start), older events are dropped, and so are rollups whose
+     * newest event is past aggregate_ms. A changed segment is rewritten in
+     * place, or deleted once empty; one with nothing expired is left as is.
+     * Call repeatedly until `done`; the next call after that starts a new
+     * pass. Rollups of the same bucket may be split across segments, so
+     * sum them when reading.
+     */
+    #[napi]
+    pub fn apply_retention(&mut self, policy: RetentionPolicy) -> Result<RetentionProgress> {
+        let cutoffs = Cutoffs::of(&policy)?;
+        let active = self.active.as_ref().map(|active| active.sequence);
+        let cursor = self.retention_cursor.unwrap_or(0);
+        let candidates: Vec<(u32, f64, PathBuf)> = self
+            .list()?
+            .into_iter()
+            .filter(|(sequence, _, _)| Some(*sequence) != active && *sequence >= cursor)
+            .collect();
+        let mut progress = RetentionProgress::default();
+        let mut visited = 0;
+        for (_, _, path) in &candidates {
+            if progress.processed as usize >= cutoffs.batch_size {
+                break;
+            }
+            visited += 1;
+            let text = fs::read_to_string(path).map_err(|e| io_error("read", path, e))?;
+            let (events, corrupt) = parse_lines(&text);
+            progress.processed += events.len() as u32;
+            let mut changed = corrupt > 0;
+            let mut kept = Vec::with_capacity(events.len());
+            let mut rollup = Rollup::default();
+            for event in events {
+                let Value::Object(fields) = &event else {
+                    kept.push(event);
+                    continue;
+                };
+                if fields.get("type").and_then(Value::as_str) == Some(AGGREGATE_TYPE) {
+                    let last_seen = fields.get("lastSeen").and_then(Value::as_f64);
+                    if last_seen.is_some_and(|ms| ms < cutoffs.aggregate) {
+                        progress.aggregates_deleted += 1;
+                        changed = true;
+                    } else {
+                        kept.push(event);
+                    }
+                    continue;
+                }
+                let timestamp = fields.get("timestamp").and_then(parse_timestamp);
+                match timestamp.map(|ms| (ms, cutoffs.fate(ms))) {
+                    Some((ms, Fate::Aggregate)) => {
+                        rollup.add(fields, ms, cutoffs.bucket_ms);
+                        progress.aggregated += 1;
+                        changed = true;
+                    }
+                    Some((_, Fate::Delete)) => {
+                        progress.deleted += 1;
+                        changed = true;
+                    }
+                    _ => kept.push(event),
+                }
+            }
+            if !changed {
+                continue;
+            }
+            let mut records: Vec<Value> = rollup
+                .into_aggregates()
+                .iter()
+                .map(|aggregate| aggregate_record(aggregate, cutoffs.bucket_ms))
+                .collect();
+            progress.buckets += records.len() as u32;
+            records.extend(kept);
+            if records.is_empty() {
+                fs::remove_file(path).map_err(|e| io_error("remove", path, e))?;
+            } else {
+                replace_file(path, &to_lines(&records)?)?;
+            }
+        }
+        progress.remaining = (candidates.len() - visited) as u32;
+        progress.done = progress.remaining == 0;
+        self.retention_cursor = candidates.get(visited).map(|candidate| candidate.0);
+        Ok(progress)
+    }
+
+    /// Directory the segments live in
+    #[napi(getter)]
+    pub fn dir(&self) -> String {
+        self.dir.to_string_lossy().into_owned()
+    }
+}
+
+impl Drop for JsonlLog {
+    fn drop(&mut self) {
+        let _ = self.sync();
+    }
+}
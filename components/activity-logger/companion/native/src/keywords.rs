@@ -0,0 +1,270 @@
+/*!
+ * Keyword extraction
+ * Top terms per session from diff hunks and chat prompts, for topical session labels
+ */
+
+use crate::halstead::KEYWORDS;
+use ahash::{AHashMap, AHashSet};
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use std::sync::LazyLock;
+
+/// A piece of session text: a diff hunk, a prompt, a commit message
+#[napi(object)]
+pub struct KeywordDocument {
+    pub session_id: String,
+    pub text: String,
+}
+
+/// Options for extract_keywords
+#[napi(object)]
+pub struct KeywordOptions {
+    /// Terms returned per session (default: 10)
+    pub top_n: Option<u32>,
+    /// "tfidf" (default) scores single terms against the other sessions; "rake" scores multi-word phrases within each session
+    pub method: Option<String>,
+    /// Shortest term kept, in characters (default: 3)
+    pub min_length: Option<u32>,
+    /// Terms in the label (default: 3)
+    pub label_terms: Option<u32>,
+}
+
+/// A ranked term
+#[napi(object)]
+pub struct Keyword {
+    pub term: String,
+    pub score: f64,
+    /// Occurrences in the session
+    pub count: u32,
+}
+
+/// Keywords for one session
+#[napi(object)]
+pub struct SessionKeywords {
+    pub session_id: String,
+    /// Highest score first
+    pub keywords: Vec<Keyword>,
+    /// Top terms joined with ", ", for a session title
+    pub label: String,
+}
+
+/// English function words plus code noise that says nothing about a session's topic
+const STOPWORDS: &str = "a about above after again all also am an and any are as at be because been before \
+    being below between both but by can could did do does doing done down during each few for from \
+    further get got had has have having he her here hers him his how i if in into is it its itself \
+    just let like make me more most my no nor not now of off on once only or other our out over own \
+    please same she should so some such than that the their them then there these they this those \
+    through to too under until up use used using very was we were what when where which while who \
+    why will with would you your yours add fix \
+    args arg tmp todo fixme value values item items data result results str int bool err error \
+    new old line lines file files code true false null none nil undefined self this return";
+
+static STOP: LazyLock<AHashSet<&'static str>> = LazyLock::new(|| {
+    STOPWORDS
+        .split_whitespace()
+        .chain(KEYWORDS.iter().copied())
+        .collect()
+});
+
+/// Diff headers carry paths and hashes rather than topic words
+fn is_diff_header(line: &str) -> bool {
+    line.starts_with("@@")
+        || line.starts_with("diff --git")
+        || line.starts_with("index ")
+        || line.starts_with("+++")
+        || line.starts_with("---")
+}
+
+/// Split an identifier into lowercase words: `parseHTTPConfig` -> parse, http, config; `load_file` -> load, file
+fn split_identifier(word: &str, out: &mut Vec<String>) {
+    for part in word.split(['_', '-']) {
+        let chars: Vec<char> = part.chars().collect();
+        let mut start = 0;
+        for i in 1..chars.len() {
+            let lower_to_upper = chars[i - 1].is_lowercase() && chars[i].is_uppercase();
+            // The last capital of an acronym starts the next word: HTTPConfig -> HTTP, Config
+            let acronym_end = chars[i - 1].is_uppercase()
+                && chars[i].is_uppercase()
+                && chars.get(i + 1).is_some_and(|c| c.is_lowercase());
+            let digit_boundary = chars[i - 1].is_ascii_digit() != chars[i].is_ascii_digit();
+            if lower_to_upper || (acronym_end && i > start) || digit_boundary {
+                out.push(chars[start..i].iter().collect::<String>().to_lowercase());
+                start = i;
+            }
+        }
+        if start < chars.len() {
+            out.push(chars[start..].iter().collect::<String>().to_lowercase());
+        }
+    }
+}
+
+/// Words of `text`, each `None` where a phrase boundary (punctuation, stopword) falls
+pub(crate) fn words(text: &str, min_length: usize) -> Vec<Option<String>> {
+    let mut out = Vec::new();
+    let mut split = Vec::new();
+    for line in text.lines() {
+        if is_diff_header(line) {
+            continue;
+        }
+        let line = line.strip_prefix(['+', '-']).unwrap_or(line);
+        let mut rest = line;
+        while !rest.is_empty() {
+            let start = rest
+                .find(|c: char| c.is_alphanumeric() || c == '_')
+                .unwrap_or(rest.len());
+            if rest[..start].chars().any(|c| !c.is_whitespace()) {
+                out.push(None);
+            }
+            rest = &rest[start..];
+            let end = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-'))
+                .unwrap_or(rest.len());
+            let word = rest[..end].trim_end_matches('-');
+            rest = &rest[word.len().max(1).min(rest.len())..];
+            if word.is_empty() {
+                continue;
+            }
+            split.clear();
+            split_identifier(word, &mut split);
+            for part in split.drain(..) {
+                let useful = part.chars().count() >= min_length
+                    && !part.chars().all(|c| c.is_ascii_digit())
+                    && !STOP.contains(part.as_str());
+                out.push(useful.then_some(part));
+            }
+        }
+        out.push(None);
+    }
+    out
+}
+
+/// Term counts, for TF-IDF
+pub(crate) fn term_counts(words: &[Option<String>]) -> AHashMap<&str, u32> {
+    let mut counts = AHashMap::new();
+    for word in words.iter().flatten() {
+        *counts.entry(word.as_str()).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// RAKE: phrases are runs of content words; a word scores degree / frequency, a phrase the sum of its words
+fn rake(words: &[Option<String>]) -> Vec<(String, f64, u32)> {
+    let phrases: Vec<&[Option<String>]> = words
+        .split(Option::is_none)
+        .filter(|phrase| !phrase.is_empty() && phrase.len() <= 4)
+        .collect();
+    let mut frequency: AHashMap<&str, f64> = AHashMap::new();
+    let mut degree: AHashMap<&str, f64> = AHashMap::new();
+    for phrase in &phrases {
+        for word in phrase.iter().flatten() {
+            *frequency.entry(word).or_insert(0.0) += 1.0;
+            *degree.entry(word).or_insert(0.0) += phrase.len() as f64;
+        }
+    }
+    let mut scored: AHashMap<String, (f64, u32)> = AHashMap::new();
+    for phrase in &phrases {
+        let terms: Vec<&str> = phrase.iter().flatten().map(String::as_str).collect();
+        let score = terms.iter().map(|w| degree[w] / frequency[w]).sum();
+        let entry = scored.entry(terms.join(" ")).or_insert((score, 0));
+        entry.1 += 1;
+    }
+    scored
+        .into_iter()
+        .map(|(phrase, (score, count))| (phrase, score, count))
+        .collect()
+}
+
+/**
+ * Extract the top keywords of each session
+ *
+ * Documents are grouped by session. Diff headers and `+`/`-` markers are
+ * dropped, identifiers are split into words (`parseConfig` -> parse,
+ * config), and stopwords and language keywords are ignored. TF-IDF ranks a
+ * session's terms by how much more often they appear there than in the
+ * other sessions, so pass several sessions together for distinctive
+ * labels. RAKE ranks multi-word phrases within each session on its own.
+ *
+ * @param documents - Session texts; a session may have many
+ * @param options - Terms per session, method, minimum term length, label size
+ * @returns Keywords and a label per session, in order of first appearance
+ */
+#[napi]
+pub fn extract_keywords(
+    documents: Vec<KeywordDocument>,
+    options: Option<KeywordOptions>,
+) -> Result<Vec<SessionKeywords>> {
+    let top_n = options.as_ref().and_then(|o| o.top_n).unwrap_or(10) as usize;
+    let min_length = options.as_ref().and_then(|o| o.min_length).unwrap_or(3) as usize;
+    let label_terms = options.as_ref().and_then(|o| o.label_terms).unwrap_or(3) as usize;
+    let method = options
+        .as_ref()
+        .and_then(|o| o.method.clone())
+        .unwrap_or_else(|| "tfidf".to_string());
+    if method != "tfidf" && method != "rake" {
+        return Err(Error::new(
+            Status::InvalidArg,
+            format!("Unknown method '{}' (expected tfidf or rake)", method),
+        ));
+    }
+
+    let mut order: Vec<String> = Vec::new();
+    let mut sessions: AHashMap<String, Vec<Option<String>>> = AHashMap::new();
+    for document in &documents {
+        let words = sessions
+            .entry(document.session_id.clone())
+            .or_insert_with(|| {
+                order.push(document.session_id.clone());
+                Vec::new()
+            });
+        words.extend(self::words(&document.text, min_length));
+        words.push(None);
+    }
+
+    let counts: AHashMap<&str, AHashMap<&str, u32>> = sessions
+        .iter()
+        .map(|(id, words)| (id.as_str(), term_counts(words)))
+        .collect();
+    let mut document_frequency: AHashMap<&str, u32> = AHashMap::new();
+    for terms in counts.values() {
+        for term in terms.keys() {
+            *document_frequency.entry(term).or_insert(0) += 1;
+        }
+    }
+    let sessions_total = sessions.len() as f64;
+
+    Ok(order
+        .iter()
+        .map(|id| {
+            let mut ranked: Vec<(String, f64, u32)> = if method == "rake" {
+                rake(&sessions[id])
+            } else {
+                let terms = &counts[id.as_str()];
+                let total: u32 = terms.values().sum();
+                terms
+                    .iter()
+                    .map(|(term, &count)| {
+                        let tf = count as f64 / total as f64;
+                        let df = document_frequency[term] as f64;
+                        let idf = ((1.0 + sessions_total) / (1.0 + df)).ln() + 1.0;
+                        (term.to_string(), tf * idf, count)
+                    })
+                    .collect()
+            };
+            ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+            ranked.truncate(top_n);
+            SessionKeywords {
+                session_id: id.clone(),
+                label: ranked
+                    .iter()
+                    .take(label_terms)
+                    .map(|(term, _, _)| term.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                keywords: ranked
+                    .into_iter()
+                    .map(|(term, score, count)| Keyword { term, score, count })
+                    .collect(),
+            }
+        })
+        .collect())
+}
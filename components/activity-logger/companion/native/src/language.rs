@@ -0,0 +1,256 @@
+/*!
+ * Language detection
+ * Modelines, file names, shebangs, extensions, and content classification, with a confidence for each guess
+ */
+
+use crate::classifier::classify;
+use crate::loc::{language_from_filename, language_from_path};
+use napi_derive::napi;
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// A less likely language for the same input
+#[napi(object)]
+#[derive(Clone)]
+pub struct LanguageAlternate {
+    pub language: String,
+    pub confidence: f64,
+}
+
+/// Result of detect_language
+#[napi(object)]
+#[derive(Clone)]
+pub struct LanguageGuess {
+    /// Language name, or "unknown"
+    pub language: String,
+    /// 0-1; 0 when nothing matched
+    pub confidence: f64,
+    /// What decided it: "modeline", "filename", "shebang", "extension", "content", or "none"
+    pub source: String,
+    /// Other plausible languages, most likely first
+    pub alternates: Vec<LanguageAlternate>,
+}
+
+impl LanguageGuess {
+    pub(crate) fn new(language: &str, confidence: f64, source: &str) -> Self {
+        LanguageGuess {
+            language: language.to_string(),
+            confidence,
+            source: source.to_string(),
+            alternates: Vec::new(),
+        }
+    }
+
+    pub(crate) fn unknown() -> Self {
+        LanguageGuess::new("unknown", 0.0, "none")
+    }
+}
+
+/// Canonical language name for a modeline, shebang, or editor mode name
+fn canonical(name: &str) -> Option<&'static str> {
+    let name = name.to_ascii_lowercase();
+    let name = name.trim_end_matches("-mode").trim_end_matches("-ts");
+    let language = match name {
+        "sh" | "bash" | "zsh" | "ksh" | "dash" | "ash" | "shell-script" => "shell",
+        "js" | "javascript" | "node" | "nodejs" | "js2" | "rjsx" => "javascript",
+        "ts" | "typescript" | "ts-node" | "deno" | "tsx" => "typescript",
+        "py" | "python" | "python2" | "python3" | "pypy" | "pypy3" => "python",
+        "rb" | "ruby" | "jruby" => "ruby",
+        "pl" | "perl" | "cperl" => "perl",
+        "c" => "c",
+        "cpp" | "c++" => "cpp",
+        "cs" | "csharp" => "csharp",
+        "objc" | "objective-c" => "objective-c",
+        "rust" | "rs" => "rust",
+        "go" | "golang" => "go",
+        "java" => "java",
+        "kotlin" | "kt" => "kotlin",
+        "scala" => "scala",
+        "groovy" => "groovy",
+        "swift" => "swift",
+        "php" => "php",
+        "lua" | "luajit" => "lua",
+        "r" | "rscript" => "r",
+        "julia" => "julia",
+        "elixir" => "elixir",
+        "erlang" | "escript" => "erlang",
+        "haskell" | "runghc" | "runhaskell" => "haskell",
+        "ocaml" | "tuareg" => "ocaml",
+        "fish" => "fish",
+        "pwsh" | "powershell" | "ps1" => "powershell",
+        "tcl" | "tclsh" | "wish" => "tcl",
+        "awk" | "gawk" | "mawk" | "nawk" => "awk",
+        "make" | "makefile" | "gmake" => "makefile",
+        "dockerfile" => "dockerfile",
+        "markdown" | "md" | "gfm" => "markdown",
+        "yaml" | "yml" => "yaml",
+        "json" | "jsonc" => "json",
+        "toml" | "conf-toml" => "toml",
+        "html" | "mhtml" | "web" => "html",
+        "xml" | "nxml" => "xml",
+        "css" => "css",
+        "scss" => "scss",
+        "sql" => "sql",
+        "lisp" | "common-lisp" | "sbcl" => "lisp",
+        "emacs-lisp" | "elisp" => "elisp",
+        "scheme" | "guile" => "scheme",
+        "racket" => "racket",
+        "clojure" | "bb" => "clojure",
+        "vim" => "vim",
+        "tex" | "latex" => "latex",
+        "crystal" => "crystal",
+        "nim" => "nim",
+        "zig" => "zig",
+        "dart" => "dart",
+        _ => return None,
+    };
+    Some(language)
+}
+
+static VIM_MODELINE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?:^|\s)(?:vim?|ex):.*?\b(?:ft|filetype|syntax)=([\w+-]+)").unwrap()
+});
+static EMACS_MODELINE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"-\*-\s*(?:.*?\bmode:\s*([\w+-]+)|([\w+-]+))\s*(?:;[^\n]*?)?-\*-").unwrap()
+});
+
+/// Language set by a Vim modeline in the first or last five lines, or an Emacs mode line in the first two
+fn modeline(content: &str) -> Option<&'static str> {
+    let lines: Vec<&str> = content.lines().collect();
+    let head = &lines[..lines.len().min(5)];
+    let tail = &lines[lines.len().saturating_sub(5)..];
+    let vim = head.iter().chain(tail).find_map(|line| {
+        let caps = VIM_MODELINE.captures(line)?;
+        canonical(&caps[1])
+    });
+    vim.or_else(|| {
+        lines.iter().take(2).find_map(|line| {
+            let caps = EMACS_MODELINE.captures(line)?;
+            canonical(caps.get(1).or_else(|| caps.get(2))?.as_str())
+        })
+    })
+}
+
+/// Interpreter named by a `#!` line: `#!/bin/bash`, `#!/usr/bin/env -S python3 -u`
+fn shebang(content: &str) -> Option<&'static str> {
+    let line = content.strip_prefix("#!")?.lines().next()?;
+    let mut words = line.split_whitespace();
+    let mut program = words.next()?.rsplit('/').next()?;
+    if program == "env" {
+        program = words.find(|word| !word.starts_with('-'))?;
+    }
+    // python3.11, ruby2.7, node18
+    let program = program.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+    canonical(program)
+}
+
+/// Extensions shared by several languages, with content hints that pick between them
+fn disambiguate(extension: &str, content: &str) -> Option<LanguageGuess> {
+    let has = |needles: &[&str]| needles.iter().any(|needle| content.contains(needle));
+    let (ranked, confidence): (&[&str], f64) = match extension {
+        "h" => {
+            if has(&["@interface", "@end", "#import"]) {
+                (&["objective-c", "cpp", "c"], 0.9)
+            } else if has(&[
+                "class ",
+                "namespace ",
+                "template<",
+                "template <",
+                "std::",
+                "public:",
+            ]) {
+                (&["cpp", "c", "objective-c"], 0.85)
+            } else {
+                (&["c", "cpp", "objective-c"], 0.7)
+            }
+        }
+        "m" => {
+            if has(&["@interface", "@implementation", "#import", "@end"]) {
+                (&["objective-c", "matlab"], 0.9)
+            } else if has(&["function ", "end\n", "%"]) {
+                (&["matlab", "objective-c"], 0.8)
+            } else {
+                (&["objective-c", "matlab"], 0.55)
+            }
+        }
+        "pl" => {
+            if has(&[":-"]) && !has(&["my ", "use strict", "sub "]) {
+                (&["prolog", "perl"], 0.8)
+            } else {
+                (&["perl", "prolog"], 0.85)
+            }
+        }
+        "v" => {
+            if has(&["Theorem", "Proof.", "Qed."]) {
+                (&["coq", "verilog"], 0.9)
+            } else {
+                (&["verilog", "coq"], 0.75)
+            }
+        }
+        _ => return None,
+    };
+    let mut guess = LanguageGuess::new(ranked[0], confidence, "extension");
+    let rest = (1.0 - confidence) / (ranked.len() - 1) as f64;
+    guess.alternates = ranked[1..]
+        .iter()
+        .map(|language| LanguageAlternate {
+            language: language.to_string(),
+            confidence: rest,
+        })
+        .collect();
+    Some(guess)
+}
+
+/// Best guess for `content`, checking the strongest signals first
+pub(crate) fn guess_language(content: &str, filename: Option<&str>) -> LanguageGuess {
+    if let Some(language) = modeline(content) {
+        return LanguageGuess::new(language, 0.99, "modeline");
+    }
+    let name = filename.map(|path| path.rsplit(['/', '\\']).next().unwrap_or(path));
+    if let Some(language) = name.and_then(language_from_filename) {
+        return LanguageGuess::new(language, 0.98, "filename");
+    }
+    let extension = name
+        .and_then(|name| name.rsplit_once('.'))
+        .map(|(_, ext)| ext.to_ascii_lowercase());
+    let by_extension = filename.and_then(language_from_path);
+    if let Some(language) = shebang(content) {
+        let mut guess = LanguageGuess::new(language, 0.95, "shebang");
+        if let Some(other) = by_extension.filter(|other| *other != language) {
+            guess.alternates.push(LanguageAlternate {
+                language: other.to_string(),
+                confidence: 0.05,
+            });
+        }
+        return guess;
+    }
+    if let Some(guess) = extension
+        .as_deref()
+        .and_then(|ext| disambiguate(ext, content))
+    {
+        return guess;
+    }
+    if let Some(language) = by_extension {
+        return LanguageGuess::new(language, 0.95, "extension");
+    }
+    classify(content)
+}
+
+/**
+ * Detect the language of a file
+ *
+ * Checks, in order: a Vim (`vim: ft=python`) or Emacs (`-*- mode: ruby -*-`)
+ * modeline, well-known file names (Makefile, Dockerfile, CMakeLists.txt,
+ * Gemfile, BUILD, ...), a `#!` interpreter line, the extension (over 60
+ * languages), and finally the content itself (see classify_language). Extensions shared between
+ * languages (`.h`, `.m`, `.pl`, `.v`) are settled by content hints and
+ * report the other candidates as alternates.
+ *
+ * @param content - File content
+ * @param filename - File path, when known
+ * @returns Language name ("unknown" when nothing matched), confidence, deciding signal, and alternates
+ */
+#[napi]
+pub fn detect_language(content: String, filename: Option<String>) -> LanguageGuess {
+    guess_language(&content, filename.as_deref())
+}
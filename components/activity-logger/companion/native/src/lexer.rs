@@ -0,0 +1,245 @@
+/*!
+ * Lexical tokenizer
+ * One language-aware pass that splits source into keywords, identifiers, literals, comments, and operators
+ */
+
+use crate::comments::{comment_syntax, is_string_start, string_end, triple_quote};
+use crate::halstead::{KEYWORDS, OPERATORS};
+use napi_derive::napi;
+
+/// Keywords that are literal values rather than control flow
+const LITERAL_KEYWORDS: &[&str] = &[
+    "true",
+    "false",
+    "null",
+    "nil",
+    "undefined",
+    "None",
+    "True",
+    "False",
+    "this",
+    "self",
+    "Self",
+    "super",
+];
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum TokenKind {
+    Keyword,
+    Identifier,
+    Number,
+    String,
+    Comment,
+    Operator,
+    Punctuation,
+    Whitespace,
+    /// Anything else: stray characters, Rust lifetimes' quote
+    Other,
+}
+
+impl TokenKind {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            TokenKind::Keyword => "keyword",
+            TokenKind::Identifier => "identifier",
+            TokenKind::Number => "number",
+            TokenKind::String => "string",
+            TokenKind::Comment => "comment",
+            TokenKind::Operator => "operator",
+            TokenKind::Punctuation => "punctuation",
+            TokenKind::Whitespace => "whitespace",
+            TokenKind::Other => "other",
+        }
+    }
+}
+
+/// A token as a byte range of the source
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Lexeme {
+    pub(crate) kind: TokenKind,
+    pub(crate) start: usize,
+    pub(crate) end: usize,
+}
+
+fn is_identifier_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_' || c == '$'
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '$'
+}
+
+/// Byte length of the number at the start of `rest`: `42`, `0x1F`, `1_000`, `3.14e-2f`
+fn number_len(rest: &str) -> usize {
+    let bytes = rest.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        let b = bytes[i];
+        let exponent_sign = matches!(b, b'+' | b'-')
+            && i > 0
+            && matches!(bytes[i - 1], b'e' | b'E')
+            && !rest.starts_with("0x")
+            && !rest.starts_with("0X");
+        // A dot continues the number only before a digit, so `1..5` and `x.0.len()` split
+        let decimal_point = b == b'.' && bytes.get(i + 1).is_some_and(u8::is_ascii_digit);
+        if b.is_ascii_alphanumeric() || b == b'_' || exponent_sign || decimal_point {
+            i += 1;
+        } else {
+            break;
+        }
+    }
+    i
+}
+
+/**
+ * Split `content` into tokens
+ *
+ * Comment and string rules come from the language's comment syntax, so the
+ * tokens agree with strip_comments_and_strings. The tokens cover the whole
+ * input with no gaps or overlaps.
+ */
+pub(crate) fn lex(content: &str, language: &str) -> Vec<Lexeme> {
+    let syntax = comment_syntax(language);
+    let multiline_strings = matches!(language, "rust" | "go" | "javascript" | "typescript");
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while let Some(c) = content[i..].chars().next() {
+        let rest = &content[i..];
+        let (kind, len) = if c.is_whitespace() {
+            let len = rest
+                .find(|c: char| !c.is_whitespace())
+                .unwrap_or(rest.len());
+            (TokenKind::Whitespace, len)
+        } else if let Some((open, close)) =
+            syntax.block.iter().find(|(open, _)| rest.starts_with(open))
+        {
+            let len = rest[open.len()..]
+                .find(close)
+                .map(|end| open.len() + end + close.len())
+                .unwrap_or(rest.len());
+            (TokenKind::Comment, len)
+        } else if syntax.line.iter().any(|marker| rest.starts_with(marker)) {
+            (TokenKind::Comment, rest.find('\n').unwrap_or(rest.len()))
+        } else if let Some(quote) = triple_quote(rest, language) {
+            let len = rest[3..]
+                .find(quote)
+                .map(|end| end + 6)
+                .unwrap_or(rest.len());
+            (TokenKind::String, len)
+        } else if is_string_start(rest, c, language) {
+            let len = string_end(rest, c, multiline_strings || c == '`');
+            (TokenKind::String, len)
+        } else if c.is_ascii_digit() {
+            (TokenKind::Number, number_len(rest))
+        } else if is_identifier_start(c) {
+            let len = rest
+                .find(|c: char| !is_identifier_char(c))
+                .unwrap_or(rest.len());
+            let word = &rest[..len];
+            let kind = if KEYWORDS.contains(&word) || LITERAL_KEYWORDS.contains(&word) {
+                TokenKind::Keyword
+            } else {
+                TokenKind::Identifier
+            };
+            (kind, len)
+        } else if let Some(operator) = OPERATORS.iter().find(|op| rest.starts_with(*op)) {
+            (TokenKind::Operator, operator.len())
+        } else if matches!(c, '(' | ')' | '[' | ']' | '{' | '}' | ',' | ';' | '.' | ':') {
+            (TokenKind::Punctuation, 1)
+        } else if matches!(
+            c,
+            '+' | '-' | '*' | '/' | '%' | '=' | '<' | '>' | '!' | '&' | '|' | '^' | '~' | '?' | '@'
+        ) {
+            (TokenKind::Operator, 1)
+        } else {
+            (TokenKind::Other, c.len_utf8())
+        };
+        tokens.push(Lexeme {
+            kind,
+            start: i,
+            end: i + len,
+        });
+        i += len;
+    }
+
+    tokens
+}
+
+/// A lexical token
+#[napi(object)]
+pub struct CodeToken {
+    /// "keyword", "identifier", "number", "string", "comment", "operator", "punctuation", "whitespace", or "other"
+    pub kind: String,
+    pub text: String,
+    /// UTF-8 byte offsets, end exclusive
+    pub start: u32,
+    pub end: u32,
+    /// 1-based line of the first character
+    pub line: u32,
+    /// 1-based column of the first character, in characters
+    pub column: u32,
+}
+
+/// Options for tokenize_code
+#[napi(object)]
+pub struct TokenizeOptions {
+    /// Include comment tokens (default: true)
+    pub comments: Option<bool>,
+    /// Include whitespace tokens (default: false)
+    pub whitespace: Option<bool>,
+}
+
+/**
+ * Split source code into lexical tokens
+ *
+ * Recognizes the language's comment styles, string literals (including
+ * Python triple quotes, template literals, and multi-line strings where
+ * the language allows them), numbers (`0x1F`, `1_000`, `3.14e-2`), keywords,
+ * identifiers, and multi-character operators. Unknown languages use the
+ * common `//`, `#`, and `/* */` comment styles.
+ *
+ * @param content - Source code
+ * @param language - Language name as returned by detect_language
+ * @param options - Whether to include comment and whitespace tokens
+ * @returns Tokens in source order with their kind, text, and position
+ */
+#[napi]
+pub fn tokenize_code(
+    content: String,
+    language: String,
+    options: Option<TokenizeOptions>,
+) -> Vec<CodeToken> {
+    let comments = options.as_ref().and_then(|o| o.comments).unwrap_or(true);
+    let whitespace = options.as_ref().and_then(|o| o.whitespace).unwrap_or(false);
+    let mut line = 1;
+    let mut column = 1;
+    let mut out = Vec::new();
+    for token in lex(&content, &language) {
+        let text = &content[token.start..token.end];
+        let keep = match token.kind {
+            TokenKind::Comment => comments,
+            TokenKind::Whitespace => whitespace,
+            _ => true,
+        };
+        if keep {
+            out.push(CodeToken {
+                kind: token.kind.as_str().to_string(),
+                text: text.to_string(),
+                start: token.start as u32,
+                end: token.end as u32,
+                line,
+                column,
+            });
+        }
+        // Advance the position past this token
+        match text.rfind('\n') {
+            Some(newline) => {
+                line += text.matches('\n').count() as u32;
+                column = text[newline + 1..].chars().count() as u32 + 1;
+            }
+            None => column += text.chars().count() as u32,
+        }
+    }
+    out
+}
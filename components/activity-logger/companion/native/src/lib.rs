@@ -7,25 +7,140 @@
 
 #![deny(clippy::all)]
 
+pub mod agents;
+pub mod aggregation;
+pub mod ann;
+pub mod annotations;
+pub mod attribution;
+pub mod baseline;
+#[cfg(feature = "tiktoken")]
+pub mod bpe;
+pub mod budget;
+pub mod callgraph;
+pub mod cancel;
+pub mod changesets;
+pub mod classifier;
+pub mod clones;
+pub mod clustering;
+#[cfg(feature = "columnar")]
+pub mod columnar;
+pub mod comments;
+pub mod comparison;
+pub mod complexity;
+pub mod compression;
+pub mod csv;
+pub mod dedup;
+pub mod distance;
+#[cfg(feature = "embeddings")]
+pub mod embeddings;
+pub mod encoding;
+pub mod engine;
+pub mod events;
+pub mod experiments;
+#[cfg(feature = "full-text")]
+pub mod fulltext;
+pub mod functions;
+pub mod fuzzy;
+pub mod generated;
+pub mod grep;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod halstead;
+pub mod identifiers;
+pub mod imports;
+pub mod indentation;
+pub mod ingest;
+pub mod jsonl;
+pub mod keywords;
+pub mod language;
+pub mod lexer;
+pub mod loc;
+pub mod manifests;
+pub mod minhash;
+pub mod models;
+pub mod msgpack;
+pub mod otlp;
+pub mod outcomes;
+pub mod prometheus;
+pub mod prose;
+pub mod provenance;
+pub mod queue;
+pub mod refactor;
+pub mod replay;
+pub mod retention;
+pub mod ring;
+#[cfg(feature = "http")]
+pub mod s3;
+pub mod sampling;
+pub mod schema;
+pub mod search;
+pub mod server;
+pub mod sessions;
+pub mod shape;
+pub mod simhash;
+pub mod similarity;
+pub mod stats;
+pub mod store;
+pub mod symbols;
+#[cfg(feature = "tree-sitter")]
+pub mod syntax;
+pub mod testfiles;
+pub mod tfidf;
+pub mod tokens;
+pub mod trigram;
+#[cfg(feature = "http")]
+pub mod upload;
+pub mod vcs;
+pub mod vscdb;
+#[cfg(feature = "websocket")]
+pub mod websocket;
+
 use napi::bindgen_prelude::*;
+use napi::JsObject;
+use napi::threadsafe_function::{ErrorStrategy, ThreadsafeFunction, ThreadsafeFunctionCallMode};
 use napi_derive::napi;
 use similar::{ChangeTag, TextDiff};
 use rayon::prelude::*;
+use std::any::Any;
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::panic::{self, AssertUnwindSafe};
+use unicode_segmentation::UnicodeSegmentation;
+use std::sync::atomic::{AtomicU32, Ordering};
 use ahash::AHashMap;
+use budget::{Budget, InputBudget, Truncation};
+use cancel::CancelFlag;
+use comments::{comment_syntax, CommentScanner, LineKind};
+
+/// Progress callback invoked as (completed, total)
+type ProgressCallback = ThreadsafeFunction<(u32, u32), ErrorStrategy::Fatal>;
 
 /// Diff result structure
 #[napi(object)]
+#[derive(Clone)]
 pub struct DiffResult {
-    pub diff_size: i32,
+    /// Difference in length, in Unicode code points
+    pub diff_size: i64,
     pub is_significant: bool,
     pub summary: String,
-    pub lines_added: i32,
-    pub lines_removed: i32,
-    pub chars_added: i32,
-    pub chars_deleted: i32,
+    pub lines_added: i64,
+    pub lines_removed: i64,
+    /// Changed lines that are moves or renames (counted once per line pair)
+    pub refactor_lines: i64,
+    /// Net code points added / deleted
+    pub chars_added: i64,
+    pub chars_deleted: i64,
+    /// Net user-perceived characters (grapheme clusters) added / deleted
+    pub graphemes_added: i64,
+    pub graphemes_deleted: i64,
+    /// Net UTF-8 bytes added / deleted
+    pub bytes_added: i64,
+    pub bytes_deleted: i64,
     pub after_content: String,
     pub unified_diff: Option<String>,
+    /// An input was cut down to fit the call's budget
+    pub truncated: bool,
+    pub truncations: Vec<Truncation>,
 }
 
 /// Line change information
@@ -36,14 +151,93 @@ pub struct LineChange {
     pub content: String,
 }
 
+/// Error details for a single failed item in a batch call
+#[napi(object)]
+pub struct BatchItemError {
+    pub code: String,
+    pub message: String,
+}
+
+/// Per-pair outcome of a batch diff; exactly one of result/error is set
+#[napi(object)]
+pub struct DiffBatchItem {
+    pub ok: bool,
+    pub result: Option<DiffResult>,
+    pub error: Option<BatchItemError>,
+}
+
+impl From<std::result::Result<DiffResult, BatchItemError>> for DiffBatchItem {
+    fn from(outcome: std::result::Result<DiffResult, BatchItemError>) -> Self {
+        match outcome {
+            Ok(result) => DiffBatchItem { ok: true, result: Some(result), error: None },
+            Err(error) => DiffBatchItem { ok: false, result: None, error: Some(error) },
+        }
+    }
+}
+
+/**
+ * Run a single batch item, converting both errors and panics into a BatchItemError
+ * 
+ * Batch APIs must never let one bad item abort the whole native call,
+ * so every per-item closure goes through here.
+ */
+fn run_batch_item<T, F>(f: F) -> std::result::Result<T, BatchItemError>
+where
+    F: FnOnce() -> Result<T>,
+{
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(value)) => Ok(value),
+        Ok(Err(err)) => Err(BatchItemError {
+            code: err.status.as_ref().to_string(),
+            message: err.reason,
+        }),
+        Err(payload) => Err(BatchItemError {
+            code: "Panic".to_string(),
+            message: panic_message(payload.as_ref()),
+        }),
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
 /// File statistics
 #[napi(object)]
 pub struct FileStats {
-    pub lines: i32,
-    pub chars: i32,
-    pub words: i32,
-    pub blank_lines: i32,
-    pub comment_lines: i32,
+    pub lines: i64,
+    /// Unicode code points
+    pub chars: i64,
+    /// User-perceived characters (extended grapheme clusters), e.g. one per emoji
+    pub graphemes: i64,
+    /// UTF-8 encoded size
+    pub bytes: i64,
+    pub words: i64,
+    pub blank_lines: i64,
+    pub comment_lines: i64,
+    /// Non-blank lines of natural language: document text outside code blocks, and comments that read as sentences
+    pub prose_lines: i64,
+    /// Language whose comment syntax was applied
+    pub language: String,
+    /// Encoding the content was decoded from; "utf-8" for string input
+    pub encoding: String,
+    /// Line terminators by kind
+    pub lf_lines: i64,
+    pub crlf_lines: i64,
+    pub cr_lines: i64,
+    /// Most common terminator: "lf", "crlf", "cr", or "none" for a single unterminated line
+    pub line_ending: String,
+    /// More than one kind of line terminator is present
+    pub mixed_line_endings: bool,
+    /// The content was cut down to fit the call's budget; counts cover the kept part
+    pub truncated: bool,
+    pub truncations: Vec<Truncation>,
 }
 
 /**
@@ -52,53 +246,67 @@ pub struct FileStats {
  * This is 5-10x faster than the JavaScript 'diff' library
  * Uses the 'similar' crate which implements Myers' diff algorithm in Rust
  * 
- * @param text1 - Original text
- * @param text2 - Modified text
+ * @param text1 - Original text, or raw bytes decoded like decode_to_utf8
+ * @param text2 - Modified text, or raw bytes decoded like decode_to_utf8
  * @param threshold - Minimum change size to be considered significant
  * @param include_unified - Whether to include unified diff format
+ * @param normalize_eol - Treat CRLF and CR line endings as LF, so an editor
+ *   rewriting line endings doesn't show up as every line changed
+ * @param budget - Line and byte limits applied to each side before diffing
  * @returns DiffResult with detailed change information
  */
 #[napi]
 pub fn calculate_diff(
-    text1: String,
-    text2: String,
+    text1: Either<String, Buffer>,
+    text2: Either<String, Buffer>,
     threshold: Option<i32>,
     include_unified: Option<bool>,
+    normalize_eol: Option<bool>,
+    budget: Option<InputBudget>,
 ) -> Result<DiffResult> {
     let diff_threshold = threshold.unwrap_or(10);
     let include_unified_diff = include_unified.unwrap_or(false);
+    let budget = Budget::parse(budget.as_ref())?;
+    let (text1, _) = encoding::text_input(text1);
+    let (text2, _) = encoding::text_input(text2);
+    let mut truncations = Vec::new();
+    let text1 = budget::limit(budget.as_ref(), text1, "before", &mut truncations)?;
+    let after_content = budget::limit(budget.as_ref(), text2, "after", &mut truncations)?;
+    let (text1, text2) = if normalize_eol.unwrap_or(false) {
+        (normalize_line_endings(&text1), normalize_line_endings(&after_content))
+    } else {
+        (Cow::Owned(text1), Cow::Borrowed(after_content.as_str()))
+    };
 
     // Calculate character-level diff size
-    let diff_size = (text2.len() as i32 - text1.len() as i32).abs();
-    let is_significant = diff_size >= diff_threshold;
-
-    let mut lines_added = 0;
-    let mut lines_removed = 0;
+    let before = TextLength::of(&text1);
+    let after = TextLength::of(&text2);
+    let diff_size = (after.chars - before.chars).abs();
+    let is_significant = diff_size >= diff_threshold as i64;
 
     // Use similar's TextDiff for fast diffing
-    let diff = TextDiff::from_lines(&text1, &text2);
+    let diff = TextDiff::from_lines(text1.as_ref(), text2.as_ref());
 
     // Count changes
+    let mut inserted = Vec::new();
+    let mut deleted = Vec::new();
     for change in diff.iter_all_changes() {
         match change.tag() {
-            ChangeTag::Insert => lines_added += 1,
-            ChangeTag::Delete => lines_removed += 1,
+            ChangeTag::Insert => inserted.push(change.value()),
+            ChangeTag::Delete => deleted.push(change.value()),
             ChangeTag::Equal => {}
         }
     }
+    let lines_added = inserted.len() as i64;
+    let lines_removed = deleted.len() as i64;
+
+    // Separate moved/renamed lines so refactors don't read as new code
+    let refactor = refactor::count_refactor_lines(&deleted, &inserted);
+    let refactor_lines = (refactor.moved + refactor.renamed) as i64;
 
     // Character counts
-    let chars_added = if text2.len() > text1.len() {
-        (text2.len() - text1.len()) as i32
-    } else {
-        0
-    };
-    
-    let chars_deleted = if text1.len() > text2.len() {
-        (text1.len() - text2.len()) as i32
-    } else {
-        0
-    };
+    let chars_added = (after.chars - before.chars).max(0);
+    let chars_deleted = (before.chars - after.chars).max(0);
 
     // Generate summary
     let summary = if chars_added > 0 {
@@ -122,13 +330,117 @@ pub fn calculate_diff(
         summary,
         lines_added,
         lines_removed,
+        refactor_lines,
         chars_added,
         chars_deleted,
-        after_content: text2,
+        graphemes_added: (after.graphemes - before.graphemes).max(0),
+        graphemes_deleted: (before.graphemes - after.graphemes).max(0),
+        bytes_added: (after.bytes - before.bytes).max(0),
+        bytes_deleted: (before.bytes - after.bytes).max(0),
+        after_content,
         unified_diff,
+        truncated: !truncations.is_empty(),
+        truncations,
     })
 }
 
+/// Text length measured in code points, grapheme clusters, and UTF-8 bytes
+struct TextLength {
+    chars: i64,
+    graphemes: i64,
+    bytes: i64,
+}
+
+impl TextLength {
+    fn of(text: &str) -> Self {
+        TextLength {
+            chars: text.chars().count() as i64,
+            graphemes: text.graphemes(true).count() as i64,
+            bytes: text.len() as i64,
+        }
+    }
+}
+
+/// Rewrite CRLF and lone CR line endings as LF, borrowing when there are none
+fn normalize_line_endings(text: &str) -> Cow<'_, str> {
+    if text.contains('\r') {
+        Cow::Owned(text.replace("\r\n", "\n").replace('\r', "\n"))
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+/// Background task behind calculate_diff_async
+pub struct DiffTask {
+    text1: String,
+    text2: String,
+    threshold: Option<i32>,
+    include_unified: Option<bool>,
+    normalize_eol: Option<bool>,
+    budget: Option<InputBudget>,
+    cancel: CancelFlag,
+}
+
+impl Task for DiffTask {
+    type Output = DiffResult;
+    type JsValue = DiffResult;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        // Work queued behind a busy thread pool may be aborted before it starts
+        self.cancel.check()?;
+        calculate_diff(
+            Either::A(std::mem::take(&mut self.text1)),
+            Either::A(std::mem::take(&mut self.text2)),
+            self.threshold,
+            self.include_unified,
+            self.normalize_eol,
+            self.budget.take(),
+        )
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        self.cancel.check()?;
+        Ok(output)
+    }
+}
+
+/**
+ * Calculate a diff off the main thread
+ * Same result as calculate_diff, returned as a Promise
+ * 
+ * Aborting the optional signal (e.g. when the file changes again) skips
+ * work that has not started yet and rejects with an AbortError.
+ * normalize_eol comes last so existing (signal, budget) callers keep working.
+ */
+#[napi(
+    ts_args_type = "text1: string | Buffer, text2: string | Buffer, threshold?: number | undefined | null, includeUnified?: boolean | undefined | null, signal?: AbortSignal | undefined | null, budget?: InputBudget | undefined | null, normalizeEol?: boolean | undefined | null",
+    ts_return_type = "Promise<DiffResult>"
+)]
+// Positional to mirror calculate_diff's JS signature
+#[allow(clippy::too_many_arguments)]
+pub fn calculate_diff_async(
+    env: Env,
+    text1: Either<String, Buffer>,
+    text2: Either<String, Buffer>,
+    threshold: Option<i32>,
+    include_unified: Option<bool>,
+    signal: Option<JsObject>,
+    budget: Option<InputBudget>,
+    normalize_eol: Option<bool>,
+) -> Result<AsyncTask<DiffTask>> {
+    // Reject a bad budget synchronously, like batch_calculate_diffs_async
+    Budget::parse(budget.as_ref())?;
+    Ok(AsyncTask::new(DiffTask {
+        text1: encoding::text_input(text1).0,
+        text2: encoding::text_input(text2).0,
+        threshold,
+        include_unified,
+        normalize_eol,
+        budget,
+        cancel: CancelFlag::from_signal(&env, signal)?,
+    }))
+}
+
 /**
  * Get detailed line-by-line changes
  * Useful for showing exact changes in the UI
@@ -168,127 +480,528 @@ pub fn get_line_changes(text1: String, text2: String) -> Result<Vec<LineChange>>
 /**
  * Calculate file statistics
  * Fast analysis of code files
+ * 
+ * Comment lines use the comment syntax of `language` (as returned by
+ * detect_language); when omitted the language is detected from content.
+ * Block comment bodies and Python docstrings count as comment lines;
+ * prose lines are document text and comments that read as sentences.
+ * Raw bytes are decoded like decode_to_utf8 and the encoding is reported.
+ * With a budget, content over its limits is cut down first and the
+ * result is flagged as truncated.
  */
 #[napi]
-pub fn calculate_file_stats(content: String) -> Result<FileStats> {
+pub fn calculate_file_stats(
+    content: Either<String, Buffer>,
+    language: Option<String>,
+    budget: Option<InputBudget>,
+) -> Result<FileStats> {
+    let budget = Budget::parse(budget.as_ref())?;
+    let (content, encoding) = encoding::text_input(content);
+    let mut truncations = Vec::new();
+    let content = budget::limit(budget.as_ref(), content, "content", &mut truncations)?;
+    let language = match language {
+        Some(language) => language,
+        None => language::guess_language(&content, None).language,
+    };
     let lines: Vec<&str> = content.lines().collect();
-    let total_lines = lines.len() as i32;
+    let total_lines = lines.len() as i64;
     
     let mut blank_lines = 0;
     let mut comment_lines = 0;
     let mut words = 0;
+    let mut scanner = CommentScanner::new(comment_syntax(&language));
 
     for line in &lines {
-        let trimmed = line.trim();
-        
-        if trimmed.is_empty() {
-            blank_lines += 1;
-        } else if trimmed.starts_with("//") || trimmed.starts_with("#") || trimmed.starts_with("/*") {
-            comment_lines += 1;
+        match scanner.classify(line) {
+            LineKind::Blank => blank_lines += 1,
+            LineKind::Comment => comment_lines += 1,
+            LineKind::Code => {}
         }
         
-        words += trimmed.split_whitespace().count();
+        words += line.split_whitespace().count();
     }
 
+    let prose_lines = prose::classify_lines(&content, &language)
+        .into_iter()
+        .filter(|kind| *kind == prose::TextKind::Prose)
+        .count() as i64;
+    let length = TextLength::of(&content);
+    let endings = count_line_endings(&content);
+    let kinds_present = [endings.lf, endings.crlf, endings.cr]
+        .iter()
+        .filter(|count| **count > 0)
+        .count();
+
     Ok(FileStats {
         lines: total_lines,
-        chars: content.len() as i32,
-        words: words as i32,
+        chars: length.chars,
+        graphemes: length.graphemes,
+        bytes: length.bytes,
+        words: words as i64,
         blank_lines,
         comment_lines,
+        prose_lines,
+        language,
+        encoding: encoding.to_string(),
+        lf_lines: endings.lf,
+        crlf_lines: endings.crlf,
+        cr_lines: endings.cr,
+        line_ending: endings.dominant().to_string(),
+        mixed_line_endings: kinds_present > 1,
+        truncated: !truncations.is_empty(),
+        truncations,
     })
 }
 
+struct LineEndingCounts {
+    lf: i64,
+    crlf: i64,
+    cr: i64,
+}
+
+impl LineEndingCounts {
+    fn dominant(&self) -> &'static str {
+        if self.lf + self.crlf + self.cr == 0 {
+            "none"
+        } else if self.crlf > self.lf && self.crlf >= self.cr {
+            "crlf"
+        } else if self.cr > self.lf && self.cr > self.crlf {
+            "cr"
+        } else {
+            "lf"
+        }
+    }
+}
+
+fn count_line_endings(content: &str) -> LineEndingCounts {
+    let bytes = content.as_bytes();
+    let mut counts = LineEndingCounts { lf: 0, crlf: 0, cr: 0 };
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\r' if bytes.get(i + 1) == Some(&b'\n') => {
+                counts.crlf += 1;
+                i += 1;
+            }
+            b'\r' => counts.cr += 1,
+            b'\n' => counts.lf += 1,
+            _ => {}
+        }
+        i += 1;
+    }
+    counts
+}
+
+/// Background task behind calculate_file_stats_async
+pub struct FileStatsTask {
+    content: String,
+    language: Option<String>,
+    budget: Option<InputBudget>,
+    cancel: CancelFlag,
+}
+
+impl Task for FileStatsTask {
+    type Output = FileStats;
+    type JsValue = FileStats;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        self.cancel.check()?;
+        calculate_file_stats(
+            Either::A(std::mem::take(&mut self.content)),
+            self.language.take(),
+            self.budget.take(),
+        )
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        self.cancel.check()?;
+        Ok(output)
+    }
+}
+
+/**
+ * Calculate file statistics off the main thread
+ * Same result as calculate_file_stats, returned as a Promise
+ */
+#[napi(
+    ts_args_type = "content: string | Buffer, language?: string | undefined | null, signal?: AbortSignal | undefined | null, budget?: InputBudget | undefined | null",
+    ts_return_type = "Promise<FileStats>"
+)]
+pub fn calculate_file_stats_async(
+    env: Env,
+    content: Either<String, Buffer>,
+    language: Option<String>,
+    signal: Option<JsObject>,
+    budget: Option<InputBudget>,
+) -> Result<AsyncTask<FileStatsTask>> {
+    Ok(AsyncTask::new(FileStatsTask {
+        content: encoding::text_input(content).0,
+        language,
+        budget,
+        cancel: CancelFlag::from_signal(&env, signal)?,
+    }))
+}
+
 /**
  * Batch diff calculation for multiple files
  * Uses parallel processing with Rayon for maximum performance
  * 
- * This can process hundreds of files simultaneously
+ * This can process hundreds of files simultaneously.
+ * Each pair gets its own DiffBatchItem, so a failing pair is reported
+ * in place instead of failing the whole batch.
  */
 #[napi]
 pub fn batch_calculate_diffs(
     pairs: Vec<(String, String)>, // Vec of (before, after) pairs
     threshold: Option<i32>,
-) -> Result<Vec<DiffResult>> {
+    budget: Option<InputBudget>,
+) -> Result<Vec<DiffBatchItem>> {
     let diff_threshold = threshold.unwrap_or(10);
+    // An invalid budget fails the call rather than every item
+    Budget::parse(budget.as_ref())?;
 
     // Process in parallel using Rayon
-    let results: Vec<DiffResult> = pairs
+    let results: Vec<DiffBatchItem> = pairs
         .par_iter()
-        .map(|(text1, text2)| {
-            calculate_diff(
-                text1.clone(),
-                text2.clone(),
-                Some(diff_threshold),
-                Some(false),
-            )
-            .unwrap()
-        })
+        .map(|(text1, text2)| diff_batch_item(text1, text2, diff_threshold, budget.as_ref()))
         .collect();
 
     Ok(results)
 }
 
+fn diff_batch_item(
+    text1: &str,
+    text2: &str,
+    threshold: i32,
+    budget: Option<&InputBudget>,
+) -> DiffBatchItem {
+    run_batch_item(|| {
+        calculate_diff(
+            Either::A(text1.to_string()),
+            Either::A(text2.to_string()),
+            Some(threshold),
+            Some(false),
+            None,
+            budget.cloned(),
+        )
+    })
+    .into()
+}
+
+/// Background task behind batch_calculate_diffs_async
+pub struct BatchDiffTask {
+    pairs: Vec<(String, String)>,
+    threshold: i32,
+    budget: Option<InputBudget>,
+    on_progress: Option<ProgressCallback>,
+    cancel: CancelFlag,
+}
+
+impl Task for BatchDiffTask {
+    type Output = Vec<DiffBatchItem>;
+    type JsValue = Vec<DiffBatchItem>;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        let total = self.pairs.len() as u32;
+        let completed = AtomicU32::new(0);
+        let on_progress = self.on_progress.as_ref();
+        let cancel = &self.cancel;
+
+        // Collecting into Result stops scheduling new pairs once cancelled
+        self.pairs
+            .par_iter()
+            .map(|(text1, text2)| {
+                cancel.check()?;
+                let result = diff_batch_item(text1, text2, self.threshold, self.budget.as_ref());
+
+                if let Some(callback) = on_progress {
+                    let done = completed.fetch_add(1, Ordering::Relaxed) + 1;
+                    callback.call((done, total), ThreadsafeFunctionCallMode::NonBlocking);
+                }
+
+                Ok(result)
+            })
+            .collect()
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(output)
+    }
+}
+
+/**
+ * Batch diff calculation that runs off the main thread
+ * Same results as batch_calculate_diffs, returned as a Promise
+ * 
+ * The optional callback is invoked with (completed, total) as each pair
+ * finishes, so long sessions can show progress and estimate time remaining.
+ * The synchronous variant blocks the event loop, so it cannot report progress.
+ * 
+ * Aborting the optional signal stops scheduling the remaining pairs and
+ * rejects the Promise with an AbortError.
+ */
+#[napi(
+    ts_args_type = "pairs: Array<[string, string]>, threshold?: number | undefined | null, onProgress?: ((completed: number, total: number) => void) | undefined | null, signal?: AbortSignal | undefined | null, budget?: InputBudget | undefined | null",
+    ts_return_type = "Promise<Array<DiffBatchItem>>"
+)]
+pub fn batch_calculate_diffs_async(
+    env: Env,
+    pairs: Vec<(String, String)>,
+    threshold: Option<i32>,
+    on_progress: Option<ProgressCallback>,
+    signal: Option<JsObject>,
+    budget: Option<InputBudget>,
+) -> Result<AsyncTask<BatchDiffTask>> {
+    Budget::parse(budget.as_ref())?;
+    Ok(AsyncTask::new(BatchDiffTask {
+        pairs,
+        threshold: threshold.unwrap_or(10),
+        budget,
+        on_progress,
+        cancel: CancelFlag::from_signal(&env, signal)?,
+    }))
+}
+
+/// Per-path outcome of a batch file stats call; exactly one of result/error is set
+#[napi(object)]
+pub struct FileStatsBatchItem {
+    pub path: String,
+    pub ok: bool,
+    pub result: Option<FileStats>,
+    pub error: Option<BatchItemError>,
+}
+
+fn file_stats_batch_item(path: &str, budget: Option<&InputBudget>) -> FileStatsBatchItem {
+    let outcome = run_batch_item(|| {
+        let bytes = std::fs::read(path).map_err(|e| {
+            Error::new(Status::GenericFailure, format!("Failed to read {}: {}", path, e))
+        })?;
+        let (encoding, _, content, _) = encoding::decode(&bytes);
+        // Text in any supported encoding decodes without NULs; images and archives don't
+        if content.contains('\0') {
+            return Err(Error::new(
+                Status::InvalidArg,
+                format!("{} is a binary file", path),
+            ));
+        }
+        let language = language::guess_language(&content, Some(path)).language;
+        let mut stats = calculate_file_stats(Either::A(content), Some(language), budget.cloned())?;
+        stats.encoding = encoding.name().to_string();
+        Ok(stats)
+    });
+    let (ok, result, error) = match outcome {
+        Ok(result) => (true, Some(result), None),
+        Err(error) => (false, None, Some(error)),
+    };
+    FileStatsBatchItem { path: path.to_string(), ok, result, error }
+}
+
+/**
+ * Read and analyze many files in parallel
+ * 
+ * Each file is read from disk and measured like calculate_file_stats on
+ * the Rayon pool, so a workspace scan doesn't pay for a JS read plus a
+ * native call per file. The language comes from the file extension, then
+ * from content. Files are decoded like decode_to_utf8, so UTF-16 and
+ * Latin-1 files are measured as text; unreadable and binary files are
+ * reported in place.
+ */
+#[napi]
+pub fn batch_file_stats(
+    paths: Vec<String>,
+    budget: Option<InputBudget>,
+) -> Result<Vec<FileStatsBatchItem>> {
+    Budget::parse(budget.as_ref())?;
+    Ok(paths
+        .par_iter()
+        .map(|path| file_stats_batch_item(path, budget.as_ref()))
+        .collect())
+}
+
+/// Background task behind batch_file_stats_async
+pub struct BatchFileStatsTask {
+    paths: Vec<String>,
+    budget: Option<InputBudget>,
+    on_progress: Option<ProgressCallback>,
+    cancel: CancelFlag,
+}
+
+impl Task for BatchFileStatsTask {
+    type Output = Vec<FileStatsBatchItem>;
+    type JsValue = Vec<FileStatsBatchItem>;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        let total = self.paths.len() as u32;
+        let completed = AtomicU32::new(0);
+        let on_progress = self.on_progress.as_ref();
+        let cancel = &self.cancel;
+
+        self.paths
+            .par_iter()
+            .map(|path| {
+                cancel.check()?;
+                let item = file_stats_batch_item(path, self.budget.as_ref());
+
+                if let Some(callback) = on_progress {
+                    let done = completed.fetch_add(1, Ordering::Relaxed) + 1;
+                    callback.call((done, total), ThreadsafeFunctionCallMode::NonBlocking);
+                }
+
+                Ok(item)
+            })
+            .collect()
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(output)
+    }
+}
+
+/**
+ * Batch file stats that run off the main thread
+ * Same results as batch_file_stats, returned as a Promise
+ * 
+ * The optional callback is invoked with (completed, total) as each file
+ * finishes. Aborting the optional signal stops reading the remaining files
+ * and rejects the Promise with an AbortError.
+ */
+#[napi(
+    ts_args_type = "paths: Array<string>, onProgress?: ((completed: number, total: number) => void) | undefined | null, signal?: AbortSignal | undefined | null, budget?: InputBudget | undefined | null",
+    ts_return_type = "Promise<Array<FileStatsBatchItem>>"
+)]
+pub fn batch_file_stats_async(
+    env: Env,
+    paths: Vec<String>,
+    on_progress: Option<ProgressCallback>,
+    signal: Option<JsObject>,
+    budget: Option<InputBudget>,
+) -> Result<AsyncTask<BatchFileStatsTask>> {
+    Budget::parse(budget.as_ref())?;
+    Ok(AsyncTask::new(BatchFileStatsTask {
+        paths,
+        budget,
+        on_progress,
+        cancel: CancelFlag::from_signal(&env, signal)?,
+    }))
+}
+
+/// Options for search_patterns
+#[napi(object)]
+#[derive(Default)]
+pub struct SearchPatternOptions {
+    /// Match regardless of case, Unicode-aware (default: false)
+    pub case_insensitive: Option<bool>,
+    /// Only count matches that start and end at word boundaries (default: false)
+    pub whole_word: Option<bool>,
+    /// Treat every pattern as plain text rather than a regex (default: false)
+    pub literal: Option<bool>,
+    /// Let `^` and `$` match at line breaks, not just the ends of the content (default: false)
+    pub multiline: Option<bool>,
+}
+
+/// Result of search_patterns_with_budget
+#[napi(object)]
+pub struct PatternCounts {
+    /// Matches per pattern
+    pub counts: HashMap<String, i32>,
+    /// The content was cut down to fit the budget; counts cover the kept part
+    pub truncated: bool,
+    pub truncations: Vec<Truncation>,
+}
+
 /**
  * Fast text search with multiple patterns
  * Uses parallel regex matching for speed
+ *
+ * Patterns without regex metacharacters are counted together in a single
+ * Aho-Corasick pass (see search_literals). An invalid pattern fails the
+ * call, naming the pattern, so a count of 0 always means no matches.
  */
 #[napi]
 pub fn search_patterns(
     content: String,
     patterns: Vec<String>,
+    options: Option<SearchPatternOptions>,
 ) -> Result<HashMap<String, i32>> {
-    let mut results = HashMap::new();
-
-    for pattern in patterns {
-        if let Ok(re) = regex::Regex::new(&pattern) {
-            let count = re.find_iter(&content).count() as i32;
-            results.insert(pattern, count);
-        }
-    }
-
-    Ok(results)
+    count_patterns(&content, patterns, &options.unwrap_or_default())
 }
 
 /**
- * Detect language from file content
- * Fast heuristic-based language detection
+ * search_patterns over at most a budget of the content
+ * Counts come back with the truncation flag, which a bare map can't carry
+ * Options come after the budget so existing callers keep working
  */
 #[napi]
-pub fn detect_language(content: String, filename: Option<String>) -> Result<String> {
-    // Check file extension first
-    if let Some(name) = filename {
-        if name.ends_with(".rs") {
-            return Ok("rust".to_string());
-        } else if name.ends_with(".js") || name.ends_with(".jsx") {
-            return Ok("javascript".to_string());
-        } else if name.ends_with(".ts") || name.ends_with(".tsx") {
-            return Ok("typescript".to_string());
-        } else if name.ends_with(".py") {
-            return Ok("python".to_string());
-        } else if name.ends_with(".go") {
-            return Ok("go".to_string());
-        } else if name.ends_with(".java") {
-            return Ok("java".to_string());
-        } else if name.ends_with(".cpp") || name.ends_with(".cc") || name.ends_with(".cxx") {
-            return Ok("cpp".to_string());
-        } else if name.ends_with(".c") || name.ends_with(".h") {
-            return Ok("c".to_string());
+pub fn search_patterns_with_budget(
+    content: String,
+    patterns: Vec<String>,
+    budget: Option<InputBudget>,
+    options: Option<SearchPatternOptions>,
+) -> Result<PatternCounts> {
+    let budget = Budget::parse(budget.as_ref())?;
+    let mut truncations = Vec::new();
+    let content = budget::limit(budget.as_ref(), content, "content", &mut truncations)?;
+    Ok(PatternCounts {
+        counts: count_patterns(&content, patterns, &options.unwrap_or_default())?,
+        truncated: !truncations.is_empty(),
+        truncations,
+    })
+}
+
+/// Characters that make a pattern more than a literal
+const REGEX_META: &[char] = &['\\', '.', '+', '*', '?', '(', ')', '|', '[', ']', '{', '}', '^', '$'];
+
+fn count_patterns(
+    content: &str,
+    patterns: Vec<String>,
+    options: &SearchPatternOptions,
+) -> Result<HashMap<String, i32>> {
+    let case_insensitive = options.case_insensitive.unwrap_or(false);
+    let whole_word = options.whole_word.unwrap_or(false);
+    let literal = options.literal.unwrap_or(false);
+    let mut results = HashMap::new();
+
+    // Plain literals share one Aho-Corasick scan; only real regexes get their own pass.
+    // That matcher folds ASCII case only and has its own idea of a word, so the
+    // case and word options go through the regex engine for consistent counts.
+    let (literals, patterns): (Vec<String>, Vec<String>) = if case_insensitive || whole_word {
+        (Vec::new(), patterns)
+    } else {
+        patterns
+            .into_iter()
+            .partition(|pattern| !pattern.is_empty() && (literal || !pattern.contains(REGEX_META)))
+    };
+    if let Ok(matcher) = search::LiteralMatcher::new(literals, false, false) {
+        let counts = matcher.count(content);
+        for (literal, count) in matcher.literals().iter().zip(counts) {
+            results.insert(literal.clone(), count as i32);
         }
     }
 
-    // Fallback to content-based detection
-    if content.contains("fn main()") || content.contains("impl ") {
-        Ok("rust".to_string())
-    } else if content.contains("def ") && content.contains("import ") {
-        Ok("python".to_string())
-    } else if content.contains("function ") || content.contains("const ") || content.contains("=>") {
-        Ok("javascript".to_string())
-    } else if content.contains("package main") {
-        Ok("go".to_string())
-    } else {
-        Ok("unknown".to_string())
+    for pattern in patterns {
+        let mut source = if literal {
+            regex::escape(&pattern)
+        } else {
+            pattern.clone()
+        };
+        if whole_word {
+            source = format!(r"\b(?:{})\b", source);
+        }
+        let re = regex::RegexBuilder::new(&source)
+            .case_insensitive(case_insensitive)
+            .multi_line(options.multiline.unwrap_or(false))
+            .build()
+            .map_err(|e| {
+                Error::new(
+                    Status::InvalidArg,
+                    format!("Invalid pattern '{}': {}", pattern, e),
+                )
+            })?;
+        let count = re.find_iter(content).count() as i32;
+        results.insert(pattern, count);
     }
+
+    Ok(results)
 }
 
 /**
@@ -303,54 +1016,15 @@ pub fn calculate_similarity(text1: String, text2: String) -> Result<f64> {
 }
 
 /**
- * Extract function signatures from code
- * Fast parsing for common languages
+ * Extract function names from code
+ * Names of the function and method symbols found by extract_symbols
  */
 #[napi]
 pub fn extract_functions(content: String, language: String) -> Result<Vec<String>> {
-    let mut functions = Vec::new();
-
-    match language.as_str() {
-        "javascript" | "typescript" => {
-            // Match: function name() { } or const name = () => { }
-            let re = regex::Regex::new(r"(?m)^\s*(?:function|const|let|var)\s+(\w+)\s*[=\(]").unwrap();
-            for cap in re.captures_iter(&content) {
-                if let Some(name) = cap.get(1) {
-                    functions.push(name.as_str().to_string());
-                }
-            }
-        }
-        "python" => {
-            // Match: def name():
-            let re = regex::Regex::new(r"(?m)^\s*def\s+(\w+)\s*\(").unwrap();
-            for cap in re.captures_iter(&content) {
-                if let Some(name) = cap.get(1) {
-                    functions.push(name.as_str().to_string());
-                }
-            }
-        }
-        "rust" => {
-            // Match: fn name() { }
-            let re = regex::Regex::new(r"(?m)^\s*(?:pub\s+)?fn\s+(\w+)\s*[<\(]").unwrap();
-            for cap in re.captures_iter(&content) {
-                if let Some(name) = cap.get(1) {
-                    functions.push(name.as_str().to_string());
-                }
-            }
-        }
-        "go" => {
-            // Match: func name() { }
-            let re = regex::Regex::new(r"(?m)^\s*func\s+(?:\([^)]*\)\s+)?(\w+)\s*\(").unwrap();
-            for cap in re.captures_iter(&content) {
-                if let Some(name) = cap.get(1) {
-                    functions.push(name.as_str().to_string());
-                }
-            }
-        }
-        _ => {}
-    }
-
-    Ok(functions)
+    Ok(symbols::function_symbols(&content, &language)
+        .into_iter()
+        .map(|symbol| symbol.name)
+        .collect())
 }
 
 /**
@@ -374,17 +1048,11 @@ pub fn deduplicate_strings(strings: Vec<String>) -> Result<Vec<String>> {
 
 /**
  * Calculate token count estimate
- * Fast approximation without calling external APIs
+ * Exact when the encoding (default cl100k_base) is loaded, see tokens::count_tokens;
+ * otherwise a fast approximation without calling external APIs
  */
 #[napi]
-pub fn estimate_tokens(text: String) -> Result<i32> {
-    // Rough estimation: ~4 chars per token on average
-    // More accurate than word count for code
-    let words = text.split_whitespace().count();
-    let chars = text.len();
-    
-    // Hybrid approach: average of word count and char count / 4
-    let estimate = ((words as f64 * 1.3) + (chars as f64 / 4.0)) / 2.0;
-    
-    Ok(estimate.ceil() as i32)
+pub fn estimate_tokens(text: String, encoding: Option<String>) -> Result<i32> {
+    let encoding = encoding.as_deref().unwrap_or(tokens::DEFAULT_ENCODING);
+    Ok(tokens::count(&text, encoding).0 as i32)
 }
@@ -0,0 +1,299 @@
+/*!
+ * Lines of code by language
+ * tokei-style code/comment/blank breakdown of a directory tree
+ */
+
+use crate::comments::{comment_syntax, CommentScanner, LineKind};
+use ahash::AHashMap;
+use ignore::overrides::OverrideBuilder;
+use ignore::{WalkBuilder, WalkState};
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use std::path::Path;
+use std::sync::mpsc;
+
+/// Line counts for one language across a directory
+#[napi(object)]
+pub struct LanguageBreakdown {
+    pub language: String,
+    pub files: i64,
+    pub lines: i64,
+    pub code_lines: i64,
+    pub comment_lines: i64,
+    pub blank_lines: i64,
+    pub bytes: i64,
+}
+
+/// Result of analyze_directory
+#[napi(object)]
+pub struct DirectoryAnalysis {
+    pub root: String,
+    pub files: i64,
+    pub lines: i64,
+    pub code_lines: i64,
+    pub comment_lines: i64,
+    pub blank_lines: i64,
+    pub bytes: i64,
+    /// Files skipped as unrecognized, binary, or unreadable
+    pub skipped_files: i64,
+    /// Per-language totals, most code lines first
+    pub languages: Vec<LanguageBreakdown>,
+}
+
+/// Language name for a file path, using the same names as detect_language
+pub(crate) fn language_from_path(path: &str) -> Option<&'static str> {
+    let name = path.rsplit(['/', '\\']).next().unwrap_or(path);
+    if let Some(language) = language_from_filename(name) {
+        return Some(language);
+    }
+    let extension = name.rsplit_once('.')?.1.to_ascii_lowercase();
+    let language = match extension.as_str() {
+        "rs" => "rust",
+        "js" | "jsx" | "mjs" | "cjs" => "javascript",
+        "ts" | "tsx" | "mts" | "cts" => "typescript",
+        "py" | "pyi" | "pyw" => "python",
+        "go" => "go",
+        "java" => "java",
+        "cpp" | "cc" | "cxx" | "c++" | "hpp" | "hh" | "hxx" | "ipp" => "cpp",
+        "c" | "h" => "c",
+        "m" | "mm" => "objective-c",
+        "cs" | "csx" => "csharp",
+        "fs" | "fsi" | "fsx" => "fsharp",
+        "vb" => "vb",
+        "rb" | "rake" | "gemspec" => "ruby",
+        "swift" => "swift",
+        "kt" | "kts" => "kotlin",
+        "scala" | "sc" => "scala",
+        "groovy" | "gradle" => "groovy",
+        "clj" | "cljs" | "cljc" | "edn" => "clojure",
+        "dart" => "dart",
+        "php" => "php",
+        "css" => "css",
+        "scss" => "scss",
+        "sass" => "sass",
+        "less" => "less",
+        "styl" => "stylus",
+        "html" | "htm" | "xhtml" => "html",
+        "xml" | "svg" | "xsd" | "xsl" | "plist" | "csproj" => "xml",
+        "vue" => "vue",
+        "svelte" => "svelte",
+        "astro" => "astro",
+        "hbs" | "handlebars" => "handlebars",
+        "md" | "markdown" | "mdx" => "markdown",
+        "rst" => "restructuredtext",
+        "adoc" | "asciidoc" => "asciidoc",
+        "tex" | "sty" | "cls" => "latex",
+        "yaml" | "yml" => "yaml",
+        "toml" => "toml",
+        "json" | "jsonc" | "json5" => "json",
+        "ini" | "cfg" => "ini",
+        "sql" => "sql",
+        "graphql" | "gql" => "graphql",
+        "proto" => "protobuf",
+        "sh" | "bash" | "zsh" | "ksh" => "shell",
+        "fish" => "fish",
+        "ps1" | "psm1" | "psd1" => "powershell",
+        "bat" | "cmd" => "batch",
+        "lua" => "lua",
+        "hs" | "lhs" => "haskell",
+        "purs" => "purescript",
+        "elm" => "elm",
+        "ml" | "mli" => "ocaml",
+        "ex" | "exs" => "elixir",
+        "erl" | "hrl" => "erlang",
+        "pl" | "pm" => "perl",
+        "r" | "rmd" => "r",
+        "jl" => "julia",
+        "nim" => "nim",
+        "zig" => "zig",
+        "cr" => "crystal",
+        "d" => "d",
+        "pas" | "pp" => "pascal",
+        "f" | "f90" | "f95" | "f03" | "for" => "fortran",
+        "cob" | "cbl" => "cobol",
+        "adb" | "ads" => "ada",
+        "lisp" | "lsp" => "lisp",
+        "el" => "elisp",
+        "scm" | "ss" => "scheme",
+        "rkt" => "racket",
+        "pro" => "prolog",
+        "tcl" => "tcl",
+        "awk" => "awk",
+        "vim" => "vim",
+        "asm" | "s" | "nasm" => "assembly",
+        "v" | "sv" | "svh" => "verilog",
+        "vhd" | "vhdl" => "vhdl",
+        "sol" => "solidity",
+        "tf" | "tfvars" | "hcl" => "hcl",
+        "nix" => "nix",
+        "bzl" | "star" => "starlark",
+        "cmake" => "cmake",
+        "mk" => "makefile",
+        "dockerfile" => "dockerfile",
+        "glsl" | "vert" | "frag" => "glsl",
+        "hlsl" => "hlsl",
+        "wgsl" => "wgsl",
+        "cu" | "cuh" => "cuda",
+        "coffee" => "coffeescript",
+        "matlab" => "matlab",
+        _ => return None,
+    };
+    Some(language)
+}
+
+/// Language of files recognized by their whole name rather than an extension
+pub(crate) fn language_from_filename(name: &str) -> Option<&'static str> {
+    let language = match name {
+        "Makefile" | "makefile" | "GNUmakefile" => "makefile",
+        "Dockerfile" | "Containerfile" => "dockerfile",
+        "CMakeLists.txt" => "cmake",
+        "Rakefile" | "Gemfile" | "Podfile" | "Vagrantfile" | "Brewfile" | "Guardfile" => "ruby",
+        "Jenkinsfile" => "groovy",
+        "BUILD" | "BUILD.bazel" | "WORKSPACE" | "MODULE.bazel" | "Tiltfile" => "starlark",
+        "Pipfile" | "Cargo.lock" | "poetry.lock" => "toml",
+        ".bashrc" | ".bash_profile" | ".zshrc" | ".profile" | ".zprofile" | ".envrc" => "shell",
+        ".vimrc" | ".gvimrc" => "vim",
+        ".editorconfig" | ".gitconfig" => "ini",
+        _ if name.starts_with("Dockerfile.") => "dockerfile",
+        _ => return None,
+    };
+    Some(language)
+}
+
+/// Counts for a single file
+struct FileCounts {
+    language: &'static str,
+    lines: i64,
+    code: i64,
+    comments: i64,
+    blanks: i64,
+    bytes: i64,
+}
+
+fn count_file(path: &Path) -> Option<FileCounts> {
+    let language = language_from_path(path.to_str()?)?;
+    let bytes = std::fs::read(path).ok()?;
+    if bytes.contains(&0) {
+        return None;
+    }
+    let content = String::from_utf8(bytes).ok()?;
+    let mut scanner = CommentScanner::new(comment_syntax(language));
+    let mut counts = FileCounts {
+        language,
+        lines: 0,
+        code: 0,
+        comments: 0,
+        blanks: 0,
+        bytes: content.len() as i64,
+    };
+    for line in content.lines() {
+        counts.lines += 1;
+        match scanner.classify(line) {
+            LineKind::Blank => counts.blanks += 1,
+            LineKind::Comment => counts.comments += 1,
+            LineKind::Code => counts.code += 1,
+        }
+    }
+    Some(counts)
+}
+
+/**
+ * Count code, comment, and blank lines per language under a directory
+ *
+ * The tree is walked in parallel. `.gitignore`, `.ignore`, and hidden files
+ * are skipped, so build output and dependencies stay out of the totals.
+ * Languages are recognized by file extension; other files, binary files,
+ * and non-UTF-8 files count as skipped.
+ *
+ * @param path - Root directory
+ * @param ignore_globs - Extra gitignore-style globs to exclude, e.g. `["dist/", "*.min.js"]`
+ * @returns Totals and per-language breakdown
+ */
+#[napi]
+pub fn analyze_directory(
+    path: String,
+    ignore_globs: Option<Vec<String>>,
+) -> Result<DirectoryAnalysis> {
+    let root = Path::new(&path);
+    if !root.is_dir() {
+        return Err(Error::new(
+            Status::InvalidArg,
+            format!("Not a directory: {}", path),
+        ));
+    }
+
+    let mut overrides = OverrideBuilder::new(root);
+    for glob in ignore_globs.unwrap_or_default() {
+        overrides.add(&format!("!{}", glob)).map_err(|e| {
+            Error::new(
+                Status::InvalidArg,
+                format!("Invalid glob '{}': {}", glob, e),
+            )
+        })?;
+    }
+    let overrides = overrides
+        .build()
+        .map_err(|e| Error::new(Status::InvalidArg, e.to_string()))?;
+
+    let (sender, receiver) = mpsc::channel::<Option<FileCounts>>();
+    WalkBuilder::new(root)
+        .overrides(overrides)
+        .build_parallel()
+        .run(|| {
+            let sender = sender.clone();
+            Box::new(move |entry| {
+                if let Ok(entry) = entry {
+                    if entry.file_type().is_some_and(|t| t.is_file()) {
+                        let _ = sender.send(count_file(entry.path()));
+                    }
+                }
+                WalkState::Continue
+            })
+        });
+    drop(sender);
+
+    let mut by_language: AHashMap<&'static str, LanguageBreakdown> = AHashMap::new();
+    let mut skipped_files = 0i64;
+    for counts in receiver {
+        let Some(counts) = counts else {
+            skipped_files += 1;
+            continue;
+        };
+        let entry = by_language
+            .entry(counts.language)
+            .or_insert_with(|| LanguageBreakdown {
+                language: counts.language.to_string(),
+                files: 0,
+                lines: 0,
+                code_lines: 0,
+                comment_lines: 0,
+                blank_lines: 0,
+                bytes: 0,
+            });
+        entry.files += 1;
+        entry.lines += counts.lines;
+        entry.code_lines += counts.code;
+        entry.comment_lines += counts.comments;
+        entry.blank_lines += counts.blanks;
+        entry.bytes += counts.bytes;
+    }
+
+    let mut languages: Vec<LanguageBreakdown> = by_language.into_values().collect();
+    languages.sort_by(|a, b| {
+        b.code_lines
+            .cmp(&a.code_lines)
+            .then_with(|| a.language.cmp(&b.language))
+    });
+
+    Ok(DirectoryAnalysis {
+        root: path,
+        files: languages.iter().map(|l| l.files).sum(),
+        lines: languages.iter().map(|l| l.lines).sum(),
+        code_lines: languages.iter().map(|l| l.code_lines).sum(),
+        comment_lines: languages.iter().map(|l| l.comment_lines).sum(),
+        blank_lines: languages.iter().map(|l| l.blank_lines).sum(),
+        bytes: languages.iter().map(|l| l.bytes).sum(),
+        skipped_files,
+        languages,
+    })
+}
@@ -0,0 +1,703 @@
+/*!
+ * Dependency manifests
+ * Declared dependencies of package.json, Cargo.toml, pyproject.toml, and go.mod, and how they changed between versions
+ */
+
+use ahash::AHashMap;
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use regex::Regex;
+use std::cmp::Ordering;
+use std::sync::LazyLock;
+
+/// One declared dependency
+#[napi(object)]
+#[derive(Clone)]
+pub struct Dependency {
+    pub name: String,
+    /// Requirement as declared, e.g. "^1.2.0", ">=2.31", "v0.4.1"; "path:..", "git:..", or "workspace" for non-registry sources
+    pub version: String,
+    /// "normal", "dev", "build", "peer", "optional", or "indirect"
+    pub kind: String,
+}
+
+/// Dependencies declared by one manifest
+#[napi(object)]
+pub struct Manifest {
+    /// "package.json", "Cargo.toml", "pyproject.toml", or "go.mod"
+    pub format: String,
+    /// Package or module name, when declared
+    pub name: Option<String>,
+    pub version: Option<String>,
+    /// In declaration order
+    pub dependencies: Vec<Dependency>,
+}
+
+/// One dependency that differs between two versions of a manifest
+#[napi(object)]
+pub struct DependencyChange {
+    pub name: String,
+    pub kind: String,
+    /// "added", "removed", "upgraded", "downgraded", or "changed" (same version numbers, different requirement or source)
+    pub change: String,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+/// Result of diff_manifests
+#[napi(object)]
+pub struct ManifestDiff {
+    pub format: String,
+    /// Sorted by kind, then name
+    pub changes: Vec<DependencyChange>,
+    pub added: u32,
+    pub removed: u32,
+    pub upgraded: u32,
+    pub downgraded: u32,
+    pub changed: u32,
+}
+
+#[derive(Clone, Copy)]
+enum Format {
+    PackageJson,
+    CargoToml,
+    PyprojectToml,
+    GoMod,
+}
+
+impl Format {
+    fn from_filename(filename: &str) -> Result<Format> {
+        let base = filename.rsplit(['/', '\\']).next().unwrap_or(filename);
+        match base.to_ascii_lowercase().as_str() {
+            "package.json" => Ok(Format::PackageJson),
+            "cargo.toml" => Ok(Format::CargoToml),
+            "pyproject.toml" => Ok(Format::PyprojectToml),
+            "go.mod" => Ok(Format::GoMod),
+            _ => Err(Error::new(
+                Status::InvalidArg,
+                format!(
+                    "Unsupported manifest '{}' (expected package.json, Cargo.toml, pyproject.toml, or go.mod)",
+                    base
+                ),
+            )),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Format::PackageJson => "package.json",
+            Format::CargoToml => "Cargo.toml",
+            Format::PyprojectToml => "pyproject.toml",
+            Format::GoMod => "go.mod",
+        }
+    }
+}
+
+fn dependency(name: &str, version: impl Into<String>, kind: &str) -> Dependency {
+    Dependency {
+        name: name.to_string(),
+        version: version.into(),
+        kind: kind.to_string(),
+    }
+}
+
+fn parse_package_json(content: &str, manifest: &mut Manifest) -> Result<()> {
+    let value: serde_json::Value = serde_json::from_str(content)
+        .map_err(|e| Error::new(Status::InvalidArg, format!("Invalid package.json: {}", e)))?;
+    let text = |key: &str| value.get(key).and_then(|v| v.as_str()).map(str::to_string);
+    manifest.name = text("name");
+    manifest.version = text("version");
+    for (key, kind) in [
+        ("dependencies", "normal"),
+        ("devDependencies", "dev"),
+        ("peerDependencies", "peer"),
+        ("optionalDependencies", "optional"),
+    ] {
+        let Some(table) = value.get(key).and_then(|v| v.as_object()) else {
+            continue;
+        };
+        for (name, version) in table {
+            let version = version.as_str().unwrap_or_default();
+            manifest.dependencies.push(dependency(name, version, kind));
+        }
+    }
+    Ok(())
+}
+
+/// The subset of TOML values dependency tables use
+enum TomlValue {
+    Str(String),
+    Array(Vec<TomlValue>),
+    Table(Vec<(String, TomlValue)>),
+    /// Numbers, booleans, dates: kept as written
+    Bare(String),
+}
+
+impl TomlValue {
+    fn get(&self, key: &str) -> Option<&TomlValue> {
+        match self {
+            TomlValue::Table(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            TomlValue::Str(text) | TomlValue::Bare(text) => Some(text),
+            _ => None,
+        }
+    }
+}
+
+/// Recursive-descent reader for one TOML value
+struct TomlReader<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+}
+
+impl TomlReader<'_> {
+    fn skip_space(&mut self) {
+        while let Some(&c) = self.chars.peek() {
+            if c == '#' {
+                while self.chars.next_if(|&c| c != '\n').is_some() {}
+            } else if c.is_whitespace() || c == ',' {
+                self.chars.next();
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn string(&mut self, quote: char) -> String {
+        let mut text = String::new();
+        while let Some(c) = self.chars.next() {
+            match c {
+                '\\' if quote == '"' => {
+                    if let Some(escaped) = self.chars.next() {
+                        text.push(match escaped {
+                            'n' => '\n',
+                            't' => '\t',
+                            other => other,
+                        });
+                    }
+                }
+                c if c == quote => break,
+                c => text.push(c),
+            }
+        }
+        text
+    }
+
+    fn key(&mut self) -> String {
+        self.skip_space();
+        match self.chars.peek() {
+            Some(&q) if q == '"' || q == '\'' => {
+                self.chars.next();
+                self.string(q)
+            }
+            _ => {
+                let mut key = String::new();
+                while let Some(c) = self
+                    .chars
+                    .next_if(|c| !matches!(c, '=' | '}') && !c.is_whitespace())
+                {
+                    key.push(c);
+                }
+                key
+            }
+        }
+    }
+
+    fn value(&mut self) -> TomlValue {
+        self.skip_space();
+        match self.chars.peek().copied() {
+            Some(q @ ('"' | '\'')) => {
+                self.chars.next();
+                TomlValue::Str(self.string(q))
+            }
+            Some('[') => {
+                self.chars.next();
+                let mut items = Vec::new();
+                loop {
+                    self.skip_space();
+                    match self.chars.peek() {
+                        None => break,
+                        Some(']') => {
+                            self.chars.next();
+                            break;
+                        }
+                        _ => items.push(self.value()),
+                    }
+                }
+                TomlValue::Array(items)
+            }
+            Some('{') => {
+                self.chars.next();
+                let mut entries = Vec::new();
+                loop {
+                    self.skip_space();
+                    match self.chars.peek() {
+                        None => break,
+                        Some('}') => {
+                            self.chars.next();
+                            break;
+                        }
+                        _ => {
+                            let key = self.key();
+                            self.skip_space();
+                            self.chars.next_if_eq(&'=');
+                            let value = self.value();
+                            insert_dotted(&mut entries, &key, value);
+                        }
+                    }
+                }
+                TomlValue::Table(entries)
+            }
+            _ => {
+                let mut bare = String::new();
+                while let Some(c) = self
+                    .chars
+                    .next_if(|c| !matches!(c, ',' | ']' | '}' | '#' | '\n'))
+                {
+                    bare.push(c);
+                }
+                if bare.is_empty() {
+                    // A stray closing bracket in malformed input; step over it so callers make progress
+                    self.chars.next();
+                }
+                TomlValue::Bare(bare.trim().to_string())
+            }
+        }
+    }
+}
+
+/// Insert `key = value`, expanding a dotted key such as `serde.workspace` into nested tables
+fn insert_dotted(entries: &mut Vec<(String, TomlValue)>, key: &str, value: TomlValue) {
+    match key.split_once('.') {
+        None => entries.push((key.to_string(), value)),
+        Some((head, rest)) => {
+            let index = match entries.iter().position(|(k, _)| k == head) {
+                Some(index) => index,
+                None => {
+                    entries.push((head.to_string(), TomlValue::Table(Vec::new())));
+                    entries.len() - 1
+                }
+            };
+            if let TomlValue::Table(inner) = &mut entries[index].1 {
+                insert_dotted(inner, rest, value);
+            }
+        }
+    }
+}
+
+/// Section path of a `[header]` line, with quoted parts unquoted: `target.'cfg(unix)'.dependencies` -> [target, cfg(unix), dependencies]
+fn section_path(header: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut quote = None;
+    for c in header.chars() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), c) if c == q => quote = None,
+            (None, '.') => parts.push(std::mem::take(&mut current).trim().to_string()),
+            _ => current.push(c),
+        }
+    }
+    parts.push(current.trim().to_string());
+    parts
+}
+
+/// A `[section]` path and its `key = value` entries
+type TomlSection = (Vec<String>, Vec<(String, TomlValue)>);
+
+/// Top-level `key = value` entries of a TOML document, grouped by section path
+fn read_toml(content: &str) -> Vec<TomlSection> {
+    static HEADER: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"^\[\[?\s*([^\]]+?)\s*\]\]?\s*(?:#.*)?$").unwrap());
+    let mut sections: Vec<TomlSection> = vec![(Vec::new(), Vec::new())];
+    let mut lines = content.lines();
+    while let Some(line) = lines.next() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        if let Some(caps) = HEADER.captures(trimmed) {
+            sections.push((section_path(&caps[1]), Vec::new()));
+            continue;
+        }
+        if !trimmed.contains('=') {
+            continue;
+        }
+        // Arrays may span lines; gather them until the brackets balance
+        let mut entry = line.to_string();
+        while depth(&entry) > 0 {
+            let Some(next) = lines.next() else { break };
+            entry.push('\n');
+            entry.push_str(next);
+        }
+        let mut reader = TomlReader {
+            chars: entry.chars().peekable(),
+        };
+        let key = reader.key();
+        reader.skip_space();
+        reader.chars.next_if_eq(&'=');
+        let value = reader.value();
+        if let Some((_, entries)) = sections.last_mut() {
+            insert_dotted(entries, &key, value);
+        }
+    }
+    sections
+}
+
+/// Open brackets and braces outside strings and comments
+fn depth(text: &str) -> i32 {
+    let mut depth = 0;
+    let mut quote = None;
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some('"'), '\\') => {
+                chars.next();
+            }
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '#') => {
+                chars.find(|&c| c == '\n');
+            }
+            (None, '[' | '{') => depth += 1,
+            (None, ']' | '}') => depth -= 1,
+            _ => {}
+        }
+    }
+    depth
+}
+
+/// Requirement of a Cargo or Poetry dependency value
+fn toml_requirement(value: &TomlValue) -> String {
+    if let Some(version) = value.as_str() {
+        return version.to_string();
+    }
+    let field = |key: &str| value.get(key).and_then(TomlValue::as_str);
+    if let Some(version) = field("version") {
+        version.to_string()
+    } else if let Some(path) = field("path") {
+        format!("path:{}", path)
+    } else if let Some(git) = field("git") {
+        let reference = field("rev").or(field("tag")).or(field("branch"));
+        match reference {
+            Some(reference) => format!("git:{}#{}", git, reference),
+            None => format!("git:{}", git),
+        }
+    } else if value.get("workspace").is_some() {
+        "workspace".to_string()
+    } else {
+        "*".to_string()
+    }
+}
+
+fn parse_cargo_toml(content: &str, manifest: &mut Manifest) {
+    for (path, entries) in read_toml(content) {
+        let last = path.last().map(String::as_str).unwrap_or("");
+        let kind = match last {
+            "dependencies" => "normal",
+            "dev-dependencies" => "dev",
+            "build-dependencies" => "build",
+            _ => {
+                if path == ["package"] {
+                    let field = |key: &str| {
+                        entries
+                            .iter()
+                            .find(|(k, _)| k == key)
+                            .and_then(|(_, v)| v.as_str())
+                            .map(str::to_string)
+                    };
+                    manifest.name = field("name");
+                    manifest.version = field("version");
+                }
+                // [dependencies.serde] style: one table per dependency
+                let parent = path.len().checked_sub(2).map(|i| path[i].as_str());
+                let kind = match parent {
+                    Some("dependencies") => "normal",
+                    Some("dev-dependencies") => "dev",
+                    Some("build-dependencies") => "build",
+                    _ => continue,
+                };
+                let table = TomlValue::Table(entries);
+                manifest
+                    .dependencies
+                    .push(dependency(last, toml_requirement(&table), kind));
+                continue;
+            }
+        };
+        for (name, value) in &entries {
+            manifest
+                .dependencies
+                .push(dependency(name, toml_requirement(value), kind));
+        }
+    }
+}
+
+/// Split a PEP 508 requirement such as `requests[socks]>=2.31; python_version > "3.8"` into name and version constraint
+fn pep508(requirement: &str) -> Option<(String, String)> {
+    static REQUIREMENT: LazyLock<Regex> = LazyLock::new(|| {
+        Regex::new(r"^\s*([A-Za-z0-9][A-Za-z0-9._-]*)\s*(?:\[[^\]]*\])?\s*(?:@\s*(\S+)|([^;]*))")
+            .unwrap()
+    });
+    let caps = REQUIREMENT.captures(requirement)?;
+    let version = caps
+        .get(2)
+        .map(|url| format!("url:{}", url.as_str()))
+        .or_else(|| caps.get(3).map(|m| m.as_str().trim().replace(' ', "")))
+        .filter(|v| !v.is_empty())
+        .unwrap_or_else(|| "*".to_string());
+    Some((caps[1].to_string(), version))
+}
+
+fn parse_pyproject(content: &str, manifest: &mut Manifest) {
+    let push_requirements = |manifest: &mut Manifest, value: &TomlValue, kind: &str| {
+        if let TomlValue::Array(items) = value {
+            for item in items.iter().filter_map(TomlValue::as_str) {
+                if let Some((name, version)) = pep508(item) {
+                    manifest.dependencies.push(dependency(&name, version, kind));
+                }
+            }
+        }
+    };
+    for (path, entries) in read_toml(content) {
+        let path: Vec<&str> = path.iter().map(String::as_str).collect();
+        match path.as_slice() {
+            ["project"] => {
+                for (key, value) in &entries {
+                    match key.as_str() {
+                        "name" => manifest.name = value.as_str().map(str::to_string),
+                        "version" => manifest.version = value.as_str().map(str::to_string),
+                        "dependencies" => push_requirements(manifest, value, "normal"),
+                        _ => {}
+                    }
+                }
+            }
+            ["project", "optional-dependencies"] => {
+                for (_, value) in &entries {
+                    push_requirements(manifest, value, "optional");
+                }
+            }
+            ["dependency-groups"] => {
+                for (_, value) in &entries {
+                    push_requirements(manifest, value, "dev");
+                }
+            }
+            ["build-system"] => {
+                if let Some((_, value)) = entries.iter().find(|(k, _)| k == "requires") {
+                    push_requirements(manifest, value, "build");
+                }
+            }
+            ["tool", "poetry"] => {
+                for (key, value) in &entries {
+                    match key.as_str() {
+                        "name" if manifest.name.is_none() => {
+                            manifest.name = value.as_str().map(str::to_string)
+                        }
+                        "version" if manifest.version.is_none() => {
+                            manifest.version = value.as_str().map(str::to_string)
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            ["tool", "poetry", "dependencies"]
+            | ["tool", "poetry", "dev-dependencies"]
+            | ["tool", "poetry", "group", _, "dependencies"] => {
+                let kind = if path[2] == "dependencies" {
+                    "normal"
+                } else {
+                    "dev"
+                };
+                for (name, value) in &entries {
+                    // The interpreter constraint, not a package
+                    if name == "python" {
+                        continue;
+                    }
+                    manifest
+                        .dependencies
+                        .push(dependency(name, toml_requirement(value), kind));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn parse_go_mod(content: &str, manifest: &mut Manifest) {
+    static REQUIRE: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"^(\S+)\s+(\S+)(?:\s*//\s*indirect)?").unwrap());
+    let mut in_block = false;
+    for line in content.lines() {
+        let line = line.trim();
+        if let Some(module) = line.strip_prefix("module ") {
+            manifest.name = Some(module.trim().trim_matches('"').to_string());
+            continue;
+        }
+        let spec = if in_block {
+            if line.starts_with(')') {
+                in_block = false;
+                continue;
+            }
+            line
+        } else if let Some(rest) = line.strip_prefix("require") {
+            let rest = rest.trim();
+            if rest.starts_with('(') {
+                in_block = true;
+                continue;
+            }
+            rest
+        } else {
+            continue;
+        };
+        if spec.is_empty() || spec.starts_with("//") {
+            continue;
+        }
+        if let Some(caps) = REQUIRE.captures(spec) {
+            let kind = if spec.contains("// indirect") {
+                "indirect"
+            } else {
+                "normal"
+            };
+            manifest
+                .dependencies
+                .push(dependency(&caps[1], &caps[2], kind));
+        }
+    }
+}
+
+fn parse(content: &str, format: Format) -> Result<Manifest> {
+    let mut manifest = Manifest {
+        format: format.name().to_string(),
+        name: None,
+        version: None,
+        dependencies: Vec::new(),
+    };
+    // A manifest that doesn't exist yet (or was deleted) has no dependencies
+    if content.trim().is_empty() {
+        return Ok(manifest);
+    }
+    match format {
+        Format::PackageJson => parse_package_json(content, &mut manifest)?,
+        Format::CargoToml => parse_cargo_toml(content, &mut manifest),
+        Format::PyprojectToml => parse_pyproject(content, &mut manifest),
+        Format::GoMod => parse_go_mod(content, &mut manifest),
+    }
+    Ok(manifest)
+}
+
+/// Numeric release components of a requirement: "^1.2.3" -> [1, 2, 3]
+fn version_numbers(requirement: &str) -> Option<Vec<u64>> {
+    static NUMBERS: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\d+(?:\.\d+)*").unwrap());
+    let found = NUMBERS.find(requirement)?;
+    found
+        .as_str()
+        .split('.')
+        .map(|part| part.parse().ok())
+        .collect()
+}
+
+/**
+ * Parse the dependencies declared by a manifest
+ *
+ * The format comes from the file name: package.json (dependencies, dev,
+ * peer, and optional), Cargo.toml (normal, dev, and build dependencies,
+ * including target-specific tables), pyproject.toml (PEP 621 project
+ * dependencies and extras, dependency groups, build requirements, and Poetry
+ * tables), or go.mod (require lines, with `// indirect` ones marked). Empty
+ * content parses as a manifest with no dependencies.
+ *
+ * @param content - Manifest text
+ * @param filename - Manifest path; only the file name is used
+ * @returns Package name and version, and dependencies in declaration order
+ */
+#[napi]
+pub fn parse_manifest(content: String, filename: String) -> Result<Manifest> {
+    parse(&content, Format::from_filename(&filename)?)
+}
+
+/**
+ * Compare two versions of a manifest
+ *
+ * Dependencies are matched by name and kind. A dependency whose leading
+ * version numbers went up is "upgraded", down is "downgraded", and one
+ * whose requirement text changed otherwise (a new range operator, a path or
+ * git source) is "changed". Moving a package from dev to normal shows as a
+ * removal and an addition.
+ *
+ * @param before - Manifest text before the change; empty for a new file
+ * @param after - Manifest text after the change; empty for a deleted file
+ * @param filename - Manifest path; only the file name is used
+ * @returns Changed dependencies and counts per kind of change
+ */
+#[napi]
+pub fn diff_manifests(before: String, after: String, filename: String) -> Result<ManifestDiff> {
+    let format = Format::from_filename(&filename)?;
+    let old = parse(&before, format)?;
+    let new = parse(&after, format)?;
+
+    let old_versions: AHashMap<(&str, &str), &str> = old
+        .dependencies
+        .iter()
+        .map(|d| ((d.kind.as_str(), d.name.as_str()), d.version.as_str()))
+        .collect();
+    let new_versions: AHashMap<(&str, &str), &str> = new
+        .dependencies
+        .iter()
+        .map(|d| ((d.kind.as_str(), d.name.as_str()), d.version.as_str()))
+        .collect();
+
+    let mut changes = Vec::new();
+    for (&(kind, name), &after_version) in &new_versions {
+        let change = match old_versions.get(&(kind, name)) {
+            None => "added",
+            Some(&before_version) if before_version == after_version => continue,
+            Some(&before_version) => {
+                match (
+                    version_numbers(before_version),
+                    version_numbers(after_version),
+                ) {
+                    (Some(a), Some(b)) => match b.cmp(&a) {
+                        Ordering::Greater => "upgraded",
+                        Ordering::Less => "downgraded",
+                        Ordering::Equal => "changed",
+                    },
+                    _ => "changed",
+                }
+            }
+        };
+        changes.push(DependencyChange {
+            name: name.to_string(),
+            kind: kind.to_string(),
+            change: change.to_string(),
+            before: old_versions.get(&(kind, name)).map(|v| v.to_string()),
+            after: Some(after_version.to_string()),
+        });
+    }
+    for (&(kind, name), &before_version) in &old_versions {
+        if !new_versions.contains_key(&(kind, name)) {
+            changes.push(DependencyChange {
+                name: name.to_string(),
+                kind: kind.to_string(),
+                change: "removed".to_string(),
+                before: Some(before_version.to_string()),
+                after: None,
+            });
+        }
+    }
+    changes.sort_by(|a, b| a.kind.cmp(&b.kind).then_with(|| a.name.cmp(&b.name)));
+
+    let count = |change: &str| changes.iter().filter(|c| c.change == change).count() as u32;
+    Ok(ManifestDiff {
+        format: format.name().to_string(),
+        added: count("added"),
+        removed: count("removed"),
+        upgraded: count("upgraded"),
+        downgraded: count("downgraded"),
+        changed: count("changed"),
+        changes,
+    })
+}
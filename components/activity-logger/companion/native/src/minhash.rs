@@ -0,0 +1,253 @@
+/*!
+ * MinHash index
+ * Locality-sensitive hashing to find the earlier documents most similar to a new one
+ */
+
+use crate::experiments::fnv1a;
+use crate::simhash::tokens;
+use ahash::{AHashMap, AHashSet, RandomState};
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+
+/// Mersenne prime 2^61 - 1, the modulus of the hash permutations
+const PRIME: u64 = (1 << 61) - 1;
+
+/// Options for a MinHashIndex
+#[napi(object)]
+pub struct MinHashIndexOptions {
+    /// Signature length; more is more accurate and slower (default: 128)
+    pub num_hashes: Option<u32>,
+    /// LSH bands the signature is split into; must divide num_hashes (default: 32)
+    pub bands: Option<u32>,
+    /// Consecutive tokens per shingle (default: 3)
+    pub shingle_size: Option<u32>,
+}
+
+/// An indexed document similar to the query
+#[napi(object)]
+pub struct MinHashMatch {
+    pub id: String,
+    /// Estimated Jaccard similarity of the token shingle sets, 0-1
+    pub similarity: f64,
+}
+
+/// splitmix64, for reproducible permutation coefficients
+pub(crate) fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Index of MinHash signatures bucketed by LSH band
+#[napi]
+pub struct MinHashIndex {
+    shingle_size: usize,
+    rows: usize,
+    /// (a, b) of each permutation h(x) = (a * x + b) mod PRIME
+    permutations: Vec<(u64, u64)>,
+    hasher: RandomState,
+    /// Document slot per id
+    slots: AHashMap<String, u32>,
+    /// Id and signature per slot; None once removed
+    documents: Vec<Option<(String, Vec<u64>)>>,
+    free: Vec<u32>,
+    /// Per band, slots by band hash
+    buckets: Vec<AHashMap<u64, Vec<u32>>>,
+}
+
+impl MinHashIndex {
+    /// Signature of `text`, or None if it has no tokens
+    fn signature(&self, text: &str) -> Option<Vec<u64>> {
+        let tokens = tokens(text);
+        if tokens.is_empty() {
+            return None;
+        }
+        let size = self.shingle_size.clamp(1, tokens.len());
+        let shingles: AHashSet<u64> = tokens
+            .windows(size)
+            .map(|shingle| fnv1a(shingle) % PRIME)
+            .collect();
+        Some(
+            self.permutations
+                .iter()
+                .map(|&(a, b)| {
+                    shingles
+                        .iter()
+                        .map(|&x| ((a as u128 * x as u128 + b as u128) % PRIME as u128) as u64)
+                        .min()
+                        .unwrap_or(u64::MAX)
+                })
+                .collect(),
+        )
+    }
+
+    fn band_hashes<'a>(&'a self, signature: &'a [u64]) -> impl Iterator<Item = u64> + 'a {
+        signature
+            .chunks(self.rows)
+            .map(|rows| self.hasher.hash_one(rows))
+    }
+}
+
+#[napi]
+impl MinHashIndex {
+    /// Create an empty index; throws if `bands` doesn't divide `num_hashes`
+    #[napi(constructor)]
+    pub fn new(options: Option<MinHashIndexOptions>) -> Result<Self> {
+        let num_hashes = options.as_ref().and_then(|o| o.num_hashes).unwrap_or(128);
+        let bands = options.as_ref().and_then(|o| o.bands).unwrap_or(32);
+        if num_hashes == 0 || bands == 0 || !num_hashes.is_multiple_of(bands) {
+            return Err(Error::new(
+                Status::InvalidArg,
+                format!(
+                    "bands ({}) must be a positive divisor of numHashes ({})",
+                    bands, num_hashes
+                ),
+            ));
+        }
+        let mut state = 0x6d69_6e68_6173_6800;
+        let permutations = (0..num_hashes)
+            .map(|_| {
+                (
+                    splitmix64(&mut state) % (PRIME - 1) + 1,
+                    splitmix64(&mut state) % PRIME,
+                )
+            })
+            .collect();
+        Ok(MinHashIndex {
+            shingle_size: options.and_then(|o| o.shingle_size).unwrap_or(3) as usize,
+            rows: (num_hashes / bands) as usize,
+            permutations,
+            hasher: RandomState::with_seeds(1, 2, 3, 4),
+            slots: AHashMap::new(),
+            documents: Vec::new(),
+            free: Vec::new(),
+            buckets: (0..bands).map(|_| AHashMap::new()).collect(),
+        })
+    }
+
+    /// Index `text` under `id`, replacing any document already stored there
+    ///
+    /// Text without tokens is stored but never matches.
+    #[napi]
+    pub fn insert(&mut self, id: String, text: String) {
+        self.remove(id.clone());
+        let signature = self.signature(&text).unwrap_or_default();
+        let slot = match self.free.pop() {
+            Some(slot) => slot,
+            None => {
+                self.documents.push(None);
+                self.documents.len() as u32 - 1
+            }
+        };
+        let hashes: Vec<u64> = self.band_hashes(&signature).collect();
+        for (bucket, hash) in self.buckets.iter_mut().zip(hashes) {
+            bucket.entry(hash).or_default().push(slot);
+        }
+        self.documents[slot as usize] = Some((id.clone(), signature));
+        self.slots.insert(id, slot);
+    }
+
+    /// Drop a document; returns whether it was indexed
+    #[napi]
+    pub fn remove(&mut self, id: String) -> bool {
+        let Some(slot) = self.slots.remove(&id) else {
+            return false;
+        };
+        if let Some((_, signature)) = self.documents[slot as usize].take() {
+            let hashes: Vec<u64> = self.band_hashes(&signature).collect();
+            for (bucket, hash) in self.buckets.iter_mut().zip(hashes) {
+                if let Some(slots) = bucket.get_mut(&hash) {
+                    slots.retain(|s| *s != slot);
+                    if slots.is_empty() {
+                        bucket.remove(&hash);
+                    }
+                }
+            }
+        }
+        self.free.push(slot);
+        true
+    }
+
+    /**
+     * Indexed documents whose estimated similarity to `text` is at least `threshold`
+     *
+     * Only documents sharing a whole band with the query are compared, so
+     * lookups stay fast however many documents are indexed. With the
+     * default 32 bands of 4 rows, documents above about 0.6 similarity are
+     * found almost always and those near 0.4 about half the time; use more
+     * bands for lower thresholds.
+     *
+     * @param text - Text to look up
+     * @param threshold - Lowest similarity returned, 0-1 (default: 0.5)
+     * @param limit - Most matches to return
+     * @returns Matches, most similar first, ties by id
+     */
+    #[napi]
+    pub fn query(
+        &self,
+        text: String,
+        threshold: Option<f64>,
+        limit: Option<u32>,
+    ) -> Vec<MinHashMatch> {
+        let threshold = threshold.unwrap_or(0.5);
+        let limit = limit.map_or(usize::MAX, |limit| limit as usize);
+        let Some(signature) = self.signature(&text) else {
+            return Vec::new();
+        };
+        let mut candidates = AHashSet::new();
+        for (bucket, hash) in self.buckets.iter().zip(self.band_hashes(&signature)) {
+            if let Some(slots) = bucket.get(&hash) {
+                candidates.extend(slots.iter().copied());
+            }
+        }
+        let mut matches: Vec<MinHashMatch> = candidates
+            .into_iter()
+            .filter_map(|slot| self.documents[slot as usize].as_ref())
+            .filter(|(_, stored)| !stored.is_empty())
+            .map(|(id, stored)| {
+                let equal = stored
+                    .iter()
+                    .zip(&signature)
+                    .filter(|(a, b)| a == b)
+                    .count();
+                MinHashMatch {
+                    id: id.clone(),
+                    similarity: equal as f64 / signature.len() as f64,
+                }
+            })
+            .filter(|m| m.similarity >= threshold)
+            .collect();
+        matches.sort_by(|a, b| {
+            b.similarity
+                .total_cmp(&a.similarity)
+                .then_with(|| a.id.cmp(&b.id))
+        });
+        matches.truncate(limit);
+        matches
+    }
+
+    /// Whether a document is indexed under `id`
+    #[napi]
+    pub fn has(&self, id: String) -> bool {
+        self.slots.contains_key(&id)
+    }
+
+    /// Number of indexed documents
+    #[napi(getter)]
+    pub fn size(&self) -> u32 {
+        self.slots.len() as u32
+    }
+
+    /// Drop every document
+    #[napi]
+    pub fn clear(&mut self) {
+        self.slots.clear();
+        self.documents.clear();
+        self.free.clear();
+        for bucket in &mut self.buckets {
+            bucket.clear();
+        }
+    }
+}
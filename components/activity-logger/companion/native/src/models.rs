@@ -0,0 +1,247 @@
+/*!
+ * Model scorecard
+ * Per-model comparison of acceptance, survival, feedback, latency, and cost
+ */
+
+use crate::events::ActivityEvent;
+use crate::stats::{
+    bootstrap_ratio, validate_bootstrap, Interval, DEFAULT_BOOTSTRAP_ITERATIONS,
+    DEFAULT_CONFIDENCE, DEFAULT_SEED,
+};
+use ahash::AHashMap;
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+
+/// An explicit good/bad/reverted label counts this many times an implicit accept/reject
+const EXPLICIT_LABEL_WEIGHT: f64 = 2.0;
+
+/// Half-open time window `[start, end)` in epoch milliseconds; open ends are unbounded
+#[napi(object)]
+pub struct TimeRange {
+    pub start: Option<i64>,
+    pub end: Option<i64>,
+}
+
+/// Options for compare_models
+#[napi(object)]
+pub struct ModelComparisonOptions {
+    /// Bootstrap resamples per metric (default: 1000)
+    pub iterations: Option<u32>,
+    /// Confidence level of the intervals (default: 0.95)
+    pub confidence: Option<f64>,
+    /// Seed for the resampling, so reports are reproducible (default: fixed)
+    pub seed: Option<u32>,
+}
+
+/// A metric value with a bootstrap confidence interval
+#[napi(object)]
+pub struct Estimate {
+    pub value: f64,
+    pub lower: f64,
+    pub upper: f64,
+    /// Events contributing to the estimate
+    pub samples: u32,
+}
+
+/// Comparison row for one model
+#[napi(object)]
+pub struct ModelScorecard {
+    pub model: String,
+    pub events: u32,
+    /// Suggestions with a known accept/reject outcome
+    pub suggestions: u32,
+    pub accepted: u32,
+    /// accepted / suggestions
+    pub acceptance: Option<Estimate>,
+    /// Surviving AI-added lines / AI-added lines, over events with a survival check
+    pub survival: Option<Estimate>,
+    pub good: u32,
+    pub bad: u32,
+    pub reverted: u32,
+    /// Weighted share of positive outcomes: labels count double, accept/reject once
+    pub feedback: Option<Estimate>,
+    /// Mean response latency
+    pub latency_ms: Option<Estimate>,
+    /// Mean cost per call
+    pub cost_usd: Option<Estimate>,
+    pub total_cost_usd: f64,
+}
+
+/// Result of compare_models
+#[napi(object)]
+pub struct ModelComparison {
+    pub start: Option<i64>,
+    pub end: Option<i64>,
+    pub confidence: f64,
+    /// Scorecards ordered by feedback score, best first; models without feedback last
+    pub models: Vec<ModelScorecard>,
+    pub best_model: Option<String>,
+    /// Whether the best model's feedback interval lies entirely above the runner-up's
+    pub best_is_significant: bool,
+}
+
+/// Per-model samples gathered before resampling
+#[derive(Default)]
+struct ModelSamples {
+    events: u32,
+    accepted: u32,
+    good: u32,
+    bad: u32,
+    reverted: u32,
+    acceptance: Vec<(f64, f64)>,
+    survival: Vec<(f64, f64)>,
+    feedback: Vec<(f64, f64)>,
+    latency: Vec<(f64, f64)>,
+    cost: Vec<(f64, f64)>,
+}
+
+impl ModelSamples {
+    fn add(&mut self, event: &ActivityEvent) {
+        self.events += 1;
+        let as_score = |positive: bool| if positive { 1.0 } else { 0.0 };
+
+        if let Some(accepted) = event.accepted {
+            self.accepted += accepted as u32;
+            self.acceptance.push((as_score(accepted), 1.0));
+        }
+        if let (Some(surviving), Some(added)) = (event.lines_surviving, event.lines_added) {
+            if added > 0 {
+                self.survival
+                    .push((surviving.clamp(0, added) as f64, added as f64));
+            }
+        }
+        let label = match event.label.as_deref() {
+            Some("good") => {
+                self.good += 1;
+                Some(true)
+            }
+            Some("bad") => {
+                self.bad += 1;
+                Some(false)
+            }
+            Some("reverted") => {
+                self.reverted += 1;
+                Some(false)
+            }
+            _ => None,
+        };
+        if let Some(positive) = label {
+            self.feedback.push((
+                as_score(positive) * EXPLICIT_LABEL_WEIGHT,
+                EXPLICIT_LABEL_WEIGHT,
+            ));
+        } else if let Some(accepted) = event.accepted {
+            self.feedback.push((as_score(accepted), 1.0));
+        }
+        if let Some(latency) = event.latency_ms.filter(|v| v.is_finite() && *v >= 0.0) {
+            self.latency.push((latency, 1.0));
+        }
+        if let Some(cost) = event.cost_usd.filter(|v| v.is_finite() && *v >= 0.0) {
+            self.cost.push((cost, 1.0));
+        }
+    }
+}
+
+/// Stable per-model, per-metric seed so intervals don't share resample draws
+fn metric_seed(seed: u32, model: &str, metric: u64) -> u64 {
+    let hash = model.bytes().fold(0xcbf2_9ce4_8422_2325u64, |h, b| {
+        (h ^ b as u64).wrapping_mul(0x0100_0000_01b3)
+    });
+    (seed as u64) ^ hash ^ metric.wrapping_mul(0x9e37_79b9_7f4a_7c15)
+}
+
+/**
+ * Compare models across acceptance, survival, feedback, latency, and cost
+ *
+ * Considers events with a `model` inside `range`. Every rate and mean comes
+ * with a percentile bootstrap confidence interval so small samples are not
+ * over-read in the model scorecard.
+ */
+#[napi]
+pub fn compare_models(
+    events: Vec<ActivityEvent>,
+    range: Option<TimeRange>,
+    options: Option<ModelComparisonOptions>,
+) -> Result<ModelComparison> {
+    let (start, end) = range.map_or((None, None), |r| (r.start, r.end));
+    let options = options.unwrap_or(ModelComparisonOptions {
+        iterations: None,
+        confidence: None,
+        seed: None,
+    });
+    let iterations = options.iterations.unwrap_or(DEFAULT_BOOTSTRAP_ITERATIONS);
+    let confidence = options.confidence.unwrap_or(DEFAULT_CONFIDENCE);
+    let seed = options.seed.unwrap_or(DEFAULT_SEED);
+    validate_bootstrap(iterations, confidence)?;
+
+    let in_range =
+        |timestamp: i64| start.is_none_or(|s| timestamp >= s) && end.is_none_or(|e| timestamp < e);
+    let mut by_model: AHashMap<&str, ModelSamples> = AHashMap::new();
+    for event in &events {
+        let Some(model) = event.model.as_deref() else {
+            continue;
+        };
+        if in_range(event.timestamp) {
+            by_model.entry(model).or_default().add(event);
+        }
+    }
+
+    let mut models: Vec<ModelScorecard> = by_model
+        .into_iter()
+        .map(|(model, samples)| {
+            let estimate = |values: &[(f64, f64)], metric: u64| {
+                bootstrap_ratio(
+                    values,
+                    iterations,
+                    confidence,
+                    metric_seed(seed, model, metric),
+                )
+                .map(|interval: Interval| Estimate {
+                    value: interval.value,
+                    lower: interval.lower,
+                    upper: interval.upper,
+                    samples: values.len() as u32,
+                })
+            };
+            ModelScorecard {
+                model: model.to_string(),
+                events: samples.events,
+                suggestions: samples.acceptance.len() as u32,
+                accepted: samples.accepted,
+                acceptance: estimate(&samples.acceptance, 0),
+                survival: estimate(&samples.survival, 1),
+                good: samples.good,
+                bad: samples.bad,
+                reverted: samples.reverted,
+                feedback: estimate(&samples.feedback, 2),
+                latency_ms: estimate(&samples.latency, 3),
+                cost_usd: estimate(&samples.cost, 4),
+                total_cost_usd: samples.cost.iter().map(|(cost, _)| cost).sum(),
+            }
+        })
+        .collect();
+
+    let score = |card: &ModelScorecard| card.feedback.as_ref().map(|f| f.value);
+    models.sort_by(|a, b| match (score(a), score(b)) {
+        (Some(x), Some(y)) => y.total_cmp(&x).then_with(|| a.model.cmp(&b.model)),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => a.model.cmp(&b.model),
+    });
+
+    let ranked: Vec<&Estimate> = models.iter().filter_map(|m| m.feedback.as_ref()).collect();
+    let best_model = ranked.first().map(|_| models[0].model.clone());
+    let best_is_significant = match ranked.as_slice() {
+        [best, runner_up, ..] => best.lower > runner_up.upper,
+        _ => false,
+    };
+
+    Ok(ModelComparison {
+        start,
+        end,
+        confidence,
+        models,
+        best_model,
+        best_is_significant,
+    })
+}
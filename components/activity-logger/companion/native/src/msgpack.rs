@@ -0,0 +1,67 @@
+/*!
+ * MessagePack event batches
+ * A compact binary form for batches passed between the extension,
+ * companion, and dashboard
+ */
+
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use serde_json::Value;
+
+/// First byte of every encoded batch, bumped when the layout changes
+const FORMAT_VERSION: u8 = 1;
+
+/**
+ * Encode events as a MessagePack batch
+ *
+ * The batch is one version byte followed by a MessagePack array of the
+ * events, with their field names kept, so it decodes without a schema.
+ * Typically several times smaller than the same events as JSON.
+ *
+ * @param events - Events as plain objects
+ * @returns The encoded batch
+ */
+#[napi(ts_args_type = "events: Array<Record<string, any>>")]
+pub fn encode_events(events: Vec<Value>) -> Result<Buffer> {
+    Ok(encode_batch(&events)?.into())
+}
+
+/// The bytes of encode_events, for callers that don't need a Buffer
+pub(crate) fn encode_batch(events: &[Value]) -> Result<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(events.len() * 128 + 1);
+    bytes.push(FORMAT_VERSION);
+    rmp_serde::encode::write(&mut bytes, events).map_err(|e| {
+        Error::new(
+            Status::InvalidArg,
+            format!("Failed to encode events: {}", e),
+        )
+    })?;
+    Ok(bytes)
+}
+
+/**
+ * Decode a batch written by encode_events
+ *
+ * Throws on a batch from a newer format version rather than guessing at
+ * its layout, and on truncated or malformed input.
+ *
+ * @param bytes - An encoded batch
+ * @returns The events, in the order they were encoded
+ */
+#[napi(ts_return_type = "Array<Record<string, any>>")]
+pub fn decode_events(bytes: Buffer) -> Result<Vec<Value>> {
+    let Some((&version, body)) = bytes.split_first() else {
+        return Err(Error::new(Status::InvalidArg, "Empty event batch"));
+    };
+    if version == 0 || version > FORMAT_VERSION {
+        return Err(Error::new(
+            Status::InvalidArg,
+            format!(
+                "Event batch has format version {}, this module reads up to {}",
+                version, FORMAT_VERSION
+            ),
+        ));
+    }
+    rmp_serde::from_slice(body)
+        .map_err(|e| Error::new(Status::InvalidArg, format!("Invalid event batch: {}", e)))
+}
@@ -0,0 +1,486 @@
+/*!
+ * OpenTelemetry export
+ * Event batches as OTLP logs and metrics, in the OTLP/HTTP JSON encoding,
+ * optionally posted to a collector
+ */
+
+use crate::ingest::{details_object, event_field, parse_timestamp, EDIT_TYPES};
+use ahash::AHashMap;
+#[cfg(feature = "http")]
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+
+const DEFAULT_SERVICE_NAME: &str = "cursor-telemetry";
+/// Instrumentation scope every record is reported under
+const SCOPE_NAME: &str = "cursor-telemetry-native";
+/// SeverityNumber INFO
+const SEVERITY_INFO: u32 = 9;
+/// AggregationTemporality DELTA: each export counts only its own batch
+const TEMPORALITY_DELTA: u32 = 1;
+#[cfg(feature = "http")]
+const DEFAULT_TIMEOUT_MS: u32 = 10_000;
+
+/// Resource description shared by the conversions and export_otlp
+#[napi(object)]
+pub struct OtlpResourceOptions {
+    /// `service.name` resource attribute (default: "cursor-telemetry")
+    pub service_name: Option<String>,
+    /// More resource attributes, e.g. { "host.name": "laptop", "team": "web" }
+    pub resource_attributes: Option<HashMap<String, String>>,
+}
+
+/// Options for export_otlp
+#[napi(object)]
+pub struct OtlpExportOptions {
+    /// Collector base URL for OTLP/HTTP, e.g. "http://localhost:4318"; /v1/logs and /v1/metrics are appended
+    pub endpoint: String,
+    /// Extra request headers, e.g. an API key
+    pub headers: Option<HashMap<String, String>>,
+    pub service_name: Option<String>,
+    pub resource_attributes: Option<HashMap<String, String>>,
+    /// Send events as log records (default: true)
+    pub logs: Option<bool>,
+    /// Send per-type counts and line totals as metrics (default: true)
+    pub metrics: Option<bool>,
+    /// Per-request timeout (default: 10000)
+    pub timeout_ms: Option<u32>,
+}
+
+/// Outcome of export_otlp
+#[napi(object)]
+pub struct OtlpExportResult {
+    /// Log records sent
+    pub log_records: u32,
+    /// Metric data points sent
+    pub data_points: u32,
+    /// Log records the collector reported rejecting
+    pub rejected_log_records: u32,
+    /// Data points the collector reported rejecting
+    pub rejected_data_points: u32,
+    /// Collector's explanation of a partial rejection
+    pub error_message: Option<String>,
+}
+
+/// An OTLP AnyValue for a JSON value; integers are strings, as int64 is in OTLP JSON
+fn any_value(value: &Value) -> Value {
+    match value {
+        Value::Null => json!({}),
+        Value::Bool(flag) => json!({ "boolValue": flag }),
+        Value::Number(n) if n.is_i64() || n.is_u64() => json!({ "intValue": n.to_string() }),
+        Value::Number(n) => json!({ "doubleValue": n.as_f64() }),
+        Value::String(text) => json!({ "stringValue": text }),
+        Value::Array(items) => {
+            json!({ "arrayValue": { "values": items.iter().map(any_value).collect::<Vec<_>>() } })
+        }
+        Value::Object(fields) => json!({ "kvlistValue": { "values": key_values(fields) } }),
+    }
+}
+
+fn key_values(fields: &Map<String, Value>) -> Vec<Value> {
+    fields
+        .iter()
+        .map(|(key, value)| json!({ "key": key, "value": any_value(value) }))
+        .collect()
+}
+
+fn attribute(key: &str, value: &str) -> Value {
+    json!({ "key": key, "value": { "stringValue": value } })
+}
+
+/// Epoch milliseconds as the decimal nanosecond string OTLP JSON expects
+fn unix_nanos(ms: f64) -> String {
+    let whole = ms.trunc() as u64;
+    let fraction = ((ms - ms.trunc()) * 1_000_000.0).round() as u64;
+    (whole * 1_000_000 + fraction).to_string()
+}
+
+fn resource(service_name: Option<&str>, extra: Option<&HashMap<String, String>>) -> Value {
+    let mut attributes = vec![attribute(
+        "service.name",
+        service_name.unwrap_or(DEFAULT_SERVICE_NAME),
+    )];
+    if let Some(extra) = extra {
+        let mut extra: Vec<_> = extra.iter().collect();
+        extra.sort();
+        attributes.extend(extra.into_iter().map(|(key, value)| attribute(key, value)));
+    }
+    json!({ "attributes": attributes })
+}
+
+fn scope() -> Value {
+    json!({ "name": SCOPE_NAME, "version": env!("CARGO_PKG_VERSION") })
+}
+
+/// Columns of an event that become attributes, under OpenTelemetry-style names
+struct Fields {
+    event_type: String,
+    timestamp: Option<f64>,
+    attributes: Vec<Value>,
+    workspace_path: String,
+    lines: Option<(i64, i64)>,
+}
+
+fn fields_of(event: &Map<String, Value>) -> Fields {
+    let details = details_object(event);
+    let text = |keys: &[&str]| match event_field(event, details.as_ref(), keys) {
+        Some(Value::String(text)) if !text.is_empty() => Some(text),
+        Some(Value::Number(n)) => Some(n.to_string()),
+        _ => None,
+    };
+    let event_type = text(&["type"]).unwrap_or_default();
+    let workspace_path = text(&["workspace_path", "workspacePath"]).unwrap_or_default();
+    let mut attributes = vec![attribute("event.type", &event_type)];
+    for (name, keys) in [
+        ("event.id", &["id"][..]),
+        ("session.id", &["session_id", "sessionId"][..]),
+        ("workspace.path", &["workspace_path", "workspacePath"][..]),
+        ("code.filepath", &["file_path", "filePath", "file"][..]),
+    ] {
+        if let Some(value) = text(keys) {
+            attributes.push(attribute(name, &value));
+        }
+    }
+    let count = |keys: &[&str]| event_field(event, details.as_ref(), keys)?.as_i64();
+    let lines = match (
+        count(&["lines_added", "linesAdded"]),
+        count(&["lines_removed", "linesRemoved"]),
+    ) {
+        (None, None) => None,
+        (added, removed) => Some((added.unwrap_or(0), removed.unwrap_or(0))),
+    };
+    Fields {
+        timestamp: event.get("timestamp").and_then(parse_timestamp),
+        event_type,
+        attributes,
+        workspace_path,
+        lines,
+    }
+}
+
+fn objects(events: &[Value]) -> impl Iterator<Item = &Map<String, Value>> {
+    events.iter().filter_map(Value::as_object)
+}
+
+fn logs_request(
+    events: &[Value],
+    service_name: Option<&str>,
+    extra: Option<&HashMap<String, String>>,
+) -> (Value, u32) {
+    let records: Vec<Value> = objects(events)
+        .map(|event| {
+            let fields = fields_of(event);
+            let mut record = json!({
+                "severityNumber": SEVERITY_INFO,
+                "severityText": "INFO",
+                "body": any_value(&Value::Object(event.clone())),
+                "attributes": fields.attributes,
+            });
+            if let Some(ms) = fields.timestamp {
+                record["timeUnixNano"] = json!(unix_nanos(ms));
+            }
+            record
+        })
+        .collect();
+    let count = records.len() as u32;
+    let request = json!({
+        "resourceLogs": [{
+            "resource": resource(service_name, extra),
+            "scopeLogs": [{ "scope": scope(), "logRecords": records }],
+        }],
+    });
+    (request, count)
+}
+
+fn metrics_request(
+    events: &[Value],
+    service_name: Option<&str>,
+    extra: Option<&HashMap<String, String>>,
+) -> (Value, u32) {
+    // (type, workspace) -> (events, lines added, lines removed, has line counts)
+    let mut groups: AHashMap<(String, String), (i64, i64, i64, bool)> = AHashMap::new();
+    let (mut start, mut end) = (f64::INFINITY, f64::NEG_INFINITY);
+    for event in objects(events) {
+        let fields = fields_of(event);
+        if let Some(ms) = fields.timestamp {
+            start = start.min(ms);
+            end = end.max(ms);
+        }
+        let group = groups
+            .entry((fields.event_type.clone(), fields.workspace_path))
+            .or_default();
+        group.0 += 1;
+        if let Some((added, removed)) = fields.lines {
+            group.1 += added;
+            group.2 += removed;
+            group.3 = true;
+        } else if EDIT_TYPES.contains(&fields.event_type.as_str()) {
+            group.3 = true;
+        }
+    }
+    if start > end {
+        start = 0.0;
+        end = 0.0;
+    }
+    let mut groups: Vec<_> = groups.into_iter().collect();
+    groups.sort_by(|a, b| a.0.cmp(&b.0));
+    let point = |(event_type, workspace_path): &(String, String), value: i64| {
+        let mut attributes = vec![attribute("event.type", event_type)];
+        if !workspace_path.is_empty() {
+            attributes.push(attribute("workspace.path", workspace_path));
+        }
+        json!({
+            "attributes": attributes,
+            "startTimeUnixNano": unix_nanos(start),
+            "timeUnixNano": unix_nanos(end),
+            "asInt": value.to_string(),
+        })
+    };
+    let sum = |name: &str, unit: &str, description: &str, points: Vec<Value>| {
+        json!({
+            "name": name,
+            "unit": unit,
+            "description": description,
+            "sum": {
+                "aggregationTemporality": TEMPORALITY_DELTA,
+                "isMonotonic": true,
+                "dataPoints": points,
+            },
+        })
+    };
+    let edits: Vec<_> = groups.iter().filter(|(_, group)| group.3).collect();
+    let metrics = vec![
+        sum(
+            "cursor.events",
+            "{event}",
+            "Events captured, by type and workspace",
+            groups
+                .iter()
+                .map(|(key, group)| point(key, group.0))
+                .collect(),
+        ),
+        sum(
+            "cursor.lines_added",
+            "{line}",
+            "Lines added by edit events",
+            edits
+                .iter()
+                .map(|(key, group)| point(key, group.1))
+                .collect(),
+        ),
+        sum(
+            "cursor.lines_removed",
+            "{line}",
+            "Lines removed by edit events",
+            edits
+                .iter()
+                .map(|(key, group)| point(key, group.2))
+                .collect(),
+        ),
+    ];
+    let count = (groups.len() + 2 * edits.len()) as u32;
+    let request = json!({
+        "resourceMetrics": [{
+            "resource": resource(service_name, extra),
+            "scopeMetrics": [{ "scope": scope(), "metrics": metrics }],
+        }],
+    });
+    (request, count)
+}
+
+/**
+ * Events as an OTLP ExportLogsServiceRequest in the OTLP/HTTP JSON encoding
+ *
+ * Each event becomes one log record: its timestamp, the whole event as
+ * the body, and event.type, event.id, session.id, workspace.path, and
+ * code.filepath attributes when present.
+ *
+ * @param events - Events with at least a type and timestamp
+ * @param options - service.name and other resource attributes
+ * @returns The request body, ready to POST to a collector's /v1/logs
+ */
+#[napi(
+    ts_args_type = "events: Array<Record<string, any>>, options?: OtlpResourceOptions | undefined | null",
+    ts_return_type = "Record<string, any>"
+)]
+pub fn to_otlp_logs(events: Vec<Value>, options: Option<OtlpResourceOptions>) -> Value {
+    let options = options.as_ref();
+    logs_request(
+        &events,
+        options.and_then(|o| o.service_name.as_deref()),
+        options.and_then(|o| o.resource_attributes.as_ref()),
+    )
+    .0
+}
+
+/**
+ * Event counts and line totals as an OTLP ExportMetricsServiceRequest
+ *
+ * Three monotonic delta sums, with a data point per event type and
+ * workspace: cursor.events, and cursor.lines_added and
+ * cursor.lines_removed for groups with line counts. Every point spans
+ * the batch's earliest to latest timestamp.
+ *
+ * @param events - Events with at least a type and timestamp
+ * @param options - service.name and other resource attributes
+ * @returns The request body, ready to POST to a collector's /v1/metrics
+ */
+#[napi(
+    ts_args_type = "events: Array<Record<string, any>>, options?: OtlpResourceOptions | undefined | null",
+    ts_return_type = "Record<string, any>"
+)]
+pub fn to_otlp_metrics(events: Vec<Value>, options: Option<OtlpResourceOptions>) -> Value {
+    let options = options.as_ref();
+    metrics_request(
+        &events,
+        options.and_then(|o| o.service_name.as_deref()),
+        options.and_then(|o| o.resource_attributes.as_ref()),
+    )
+    .0
+}
+
+/// POST one OTLP request; returns the count the collector's partialSuccess rejected
+#[cfg(feature = "http")]
+fn post(
+    agent: &ureq::Agent,
+    url: &str,
+    headers: Option<&HashMap<String, String>>,
+    body: &Value,
+    rejected_key: &str,
+) -> Result<(u32, Option<String>)> {
+    let mut request = agent.post(url).set("Content-Type", "application/json");
+    for (name, value) in headers.into_iter().flatten() {
+        request = request.set(name, value);
+    }
+    let response = request
+        .send_string(&body.to_string())
+        .map_err(|e| match e {
+            ureq::Error::Status(status, response) => Error::new(
+                Status::GenericFailure,
+                format!(
+                    "Collector {} returned {}: {}",
+                    url,
+                    status,
+                    response.into_string().unwrap_or_default()
+                ),
+            ),
+            other => Error::new(
+                Status::GenericFailure,
+                format!("Failed to reach collector {}: {}", url, other),
+            ),
+        })?;
+    // An empty body, or one without partialSuccess, means everything was accepted
+    let reply: Value = response
+        .into_string()
+        .ok()
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or(Value::Null);
+    let partial = &reply["partialSuccess"];
+    let rejected = match &partial[rejected_key] {
+        Value::String(text) => text.parse().unwrap_or(0),
+        value => value.as_u64().unwrap_or(0) as u32,
+    };
+    let message = partial["errorMessage"]
+        .as_str()
+        .filter(|message| !message.is_empty())
+        .map(String::from);
+    Ok((rejected, message))
+}
+
+#[cfg(feature = "http")]
+pub struct OtlpExportTask {
+    events: Vec<Value>,
+    options: OtlpExportOptions,
+}
+
+#[cfg(feature = "http")]
+impl Task for OtlpExportTask {
+    type Output = OtlpExportResult;
+    type JsValue = OtlpExportResult;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        let options = &self.options;
+        let base = options.endpoint.trim_end_matches('/');
+        let agent = ureq::AgentBuilder::new()
+            .timeout(std::time::Duration::from_millis(
+                options.timeout_ms.unwrap_or(DEFAULT_TIMEOUT_MS) as u64,
+            ))
+            .build();
+        let service_name = options.service_name.as_deref();
+        let extra = options.resource_attributes.as_ref();
+        let mut result = OtlpExportResult {
+            log_records: 0,
+            data_points: 0,
+            rejected_log_records: 0,
+            rejected_data_points: 0,
+            error_message: None,
+        };
+        if options.logs.unwrap_or(true) {
+            let (request, count) = logs_request(&self.events, service_name, extra);
+            let (rejected, message) = post(
+                &agent,
+                &format!("{}/v1/logs", base),
+                options.headers.as_ref(),
+                &request,
+                "rejectedLogRecords",
+            )?;
+            result.log_records = count;
+            result.rejected_log_records = rejected;
+            result.error_message = message;
+        }
+        if options.metrics.unwrap_or(true) {
+            let (request, count) = metrics_request(&self.events, service_name, extra);
+            let (rejected, message) = post(
+                &agent,
+                &format!("{}/v1/metrics", base),
+                options.headers.as_ref(),
+                &request,
+                "rejectedDataPoints",
+            )?;
+            result.data_points = count;
+            result.rejected_data_points = rejected;
+            result.error_message = result.error_message.or(message);
+        }
+        Ok(result)
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(output)
+    }
+}
+
+/**
+ * Send events to an OpenTelemetry collector over OTLP/HTTP
+ *
+ * Posts to_otlp_logs to `<endpoint>/v1/logs` and to_otlp_metrics to
+ * `<endpoint>/v1/metrics` as JSON, off the main thread. Rejects when the
+ * collector can't be reached or answers with an error status; records
+ * the collector accepted only in part are counted in the result instead.
+ * gRPC collectors need their OTLP/HTTP receiver (port 4318 by default)
+ * enabled.
+ *
+ * @param events - Events to send
+ * @param options - Collector endpoint, headers, and what to send
+ * @returns Counts sent and rejected
+ */
+#[cfg(feature = "http")]
+#[napi(
+    ts_args_type = "events: Array<Record<string, any>>, options: OtlpExportOptions",
+    ts_return_type = "Promise<OtlpExportResult>"
+)]
+pub fn export_otlp(
+    events: Vec<Value>,
+    options: OtlpExportOptions,
+) -> Result<AsyncTask<OtlpExportTask>> {
+    if !options.endpoint.starts_with("http://") && !options.endpoint.starts_with("https://") {
+        return Err(Error::new(
+            Status::InvalidArg,
+            format!(
+                "Collector endpoint must be an http(s) URL, got '{}'",
+                options.endpoint
+            ),
+        ));
+    }
+    Ok(AsyncTask::new(OtlpExportTask { events, options }))
+}
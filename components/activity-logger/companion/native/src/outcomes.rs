@@ -0,0 +1,116 @@
+/*!
+ * Outcome quality
+ * Acceptance rates for labeled AI edits, broken down by model, language, or prompt style
+ */
+
+use crate::events::ActivityEvent;
+use crate::loc::language_from_path;
+use ahash::AHashMap;
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+
+const UNKNOWN_KEY: &str = "unknown";
+
+/// Outcome counts for one group of labeled events
+#[napi(object)]
+pub struct OutcomeQuality {
+    pub key: String,
+    /// Events carrying a recognised label
+    pub labeled: u32,
+    pub good: u32,
+    pub bad: u32,
+    pub reverted: u32,
+    /// good / labeled
+    pub acceptance_rate: f64,
+    /// reverted / labeled
+    pub revert_rate: f64,
+}
+
+/// Dimension that acceptance_quality groups by
+#[derive(Clone, Copy)]
+enum OutcomeGroupBy {
+    Model,
+    Language,
+    PromptStyle,
+}
+
+impl OutcomeGroupBy {
+    fn parse(value: &str) -> Result<Self> {
+        match value {
+            "model" => Ok(OutcomeGroupBy::Model),
+            "language" => Ok(OutcomeGroupBy::Language),
+            "prompt_style" => Ok(OutcomeGroupBy::PromptStyle),
+            other => Err(Error::new(
+                Status::InvalidArg,
+                format!(
+                    "Unknown group_by '{}', expected model, language, or prompt_style",
+                    other
+                ),
+            )),
+        }
+    }
+
+    fn key<'a>(&self, event: &'a ActivityEvent) -> &'a str {
+        let key = match self {
+            OutcomeGroupBy::Model => event.model.as_deref(),
+            OutcomeGroupBy::Language => event.file_path.as_deref().and_then(language_from_path),
+            OutcomeGroupBy::PromptStyle => event.prompt_style.as_deref(),
+        };
+        key.unwrap_or(UNKNOWN_KEY)
+    }
+}
+
+/**
+ * Compute acceptance quality of labeled AI edits
+ *
+ * Only events labeled "good", "bad", or "reverted" are counted; groups are
+ * ordered by number of labeled events, most first.
+ *
+ * @param events - Activity events with `label` set by the outcome labeling API
+ * @param group_by - "model", "language" (from the file extension), or "prompt_style"
+ * @returns Outcome counts and rates per group
+ */
+#[napi]
+pub fn acceptance_quality(
+    events: Vec<ActivityEvent>,
+    group_by: String,
+) -> Result<Vec<OutcomeQuality>> {
+    let group_by = OutcomeGroupBy::parse(&group_by)?;
+    let mut groups: AHashMap<&str, OutcomeQuality> = AHashMap::new();
+
+    for event in &events {
+        let Some(label) = event.label.as_deref() else {
+            continue;
+        };
+        if !matches!(label, "good" | "bad" | "reverted") {
+            continue;
+        }
+        let key = group_by.key(event);
+        let quality = groups.entry(key).or_insert_with(|| OutcomeQuality {
+            key: key.to_string(),
+            labeled: 0,
+            good: 0,
+            bad: 0,
+            reverted: 0,
+            acceptance_rate: 0.0,
+            revert_rate: 0.0,
+        });
+        quality.labeled += 1;
+        match label {
+            "good" => quality.good += 1,
+            "bad" => quality.bad += 1,
+            _ => quality.reverted += 1,
+        }
+    }
+
+    let mut result: Vec<OutcomeQuality> = groups
+        .into_values()
+        .map(|mut quality| {
+            quality.acceptance_rate = quality.good as f64 / quality.labeled as f64;
+            quality.revert_rate = quality.reverted as f64 / quality.labeled as f64;
+            quality
+        })
+        .collect();
+    result.sort_by(|a, b| b.labeled.cmp(&a.labeled).then_with(|| a.key.cmp(&b.key)));
+    Ok(result)
+}
@@ -0,0 +1,445 @@
+/*!
+ * Prometheus metrics
+ * Session activity as counters, gauges, and histograms in the Prometheus
+ * text exposition format, with an optional scrape endpoint
+ */
+
+use crate::ingest::{details_object, event_field, EDIT_TYPES};
+use ahash::{AHashMap, AHashSet};
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// Upper bounds for the per-edit line histogram
+const EDIT_SIZE_BUCKETS: [f64; 8] = [1.0, 5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 1000.0];
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+/// How often the scrape server checks whether it was stopped
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// One labeled value of a metric
+#[napi(object)]
+#[derive(Clone)]
+pub struct PrometheusSample {
+    pub labels: Option<HashMap<String, String>>,
+    /// The value, for counters and gauges
+    pub value: Option<f64>,
+    /// Raw observations, for histograms; bucketed when rendered
+    pub observations: Option<Vec<f64>>,
+}
+
+/// A metric family: a name, its type, and its samples
+#[napi(object)]
+#[derive(Clone)]
+pub struct PrometheusMetric {
+    pub name: String,
+    pub help: Option<String>,
+    /// "counter", "gauge", or "histogram"
+    #[napi(js_name = "type")]
+    pub metric_type: String,
+    pub samples: Vec<PrometheusSample>,
+    /// Histogram bucket upper bounds, ascending; +Inf is always added
+    pub buckets: Option<Vec<f64>>,
+}
+
+fn valid_name(name: &str, colons: bool) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || (colons && c == ':'))
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || (colons && c == ':'))
+}
+
+fn format_value(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".to_string()
+    } else if value == f64::INFINITY {
+        "+Inf".to_string()
+    } else if value == f64::NEG_INFINITY {
+        "-Inf".to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+/// `{a="1",b="2"}` with keys sorted, plus an optional extra label such as `le`
+fn format_labels(labels: Option<&HashMap<String, String>>, extra: Option<(&str, &str)>) -> String {
+    let mut pairs: Vec<(&str, &str)> = labels
+        .into_iter()
+        .flatten()
+        .map(|(key, value)| (key.as_str(), value.as_str()))
+        .collect();
+    pairs.sort();
+    pairs.extend(extra);
+    if pairs.is_empty() {
+        return String::new();
+    }
+    let body: Vec<String> = pairs
+        .iter()
+        .map(|(key, value)| {
+            let escaped = value
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n");
+            format!("{}=\"{}\"", key, escaped)
+        })
+        .collect();
+    format!("{{{}}}", body.join(","))
+}
+
+fn check(metric: &PrometheusMetric) -> Result<()> {
+    let invalid = |message: String| Error::new(Status::InvalidArg, message);
+    if !valid_name(&metric.name, true) {
+        return Err(invalid(format!("Invalid metric name '{}'", metric.name)));
+    }
+    if !matches!(
+        metric.metric_type.as_str(),
+        "counter" | "gauge" | "histogram"
+    ) {
+        return Err(invalid(format!(
+            "Metric {} has unknown type '{}' (expected counter, gauge, or histogram)",
+            metric.name, metric.metric_type
+        )));
+    }
+    for sample in &metric.samples {
+        for key in sample.labels.iter().flat_map(|labels| labels.keys()) {
+            if !valid_name(key, false) || key.starts_with("__") || key == "le" {
+                return Err(invalid(format!(
+                    "Metric {} has invalid label name '{}'",
+                    metric.name, key
+                )));
+            }
+        }
+        let histogram = metric.metric_type == "histogram";
+        if (histogram && sample.observations.is_none()) || (!histogram && sample.value.is_none()) {
+            return Err(invalid(format!(
+                "Metric {} needs {} on every sample",
+                metric.name,
+                if histogram { "observations" } else { "a value" }
+            )));
+        }
+    }
+    Ok(())
+}
+
+/**
+ * Render metric families in the Prometheus text exposition format (0.0.4)
+ *
+ * Counters and gauges print one line per sample. Histograms bucket each
+ * sample's observations into cumulative `_bucket{le="..."}` lines plus
+ * `_sum` and `_count`. Label values are escaped and label keys sorted, so
+ * the output is stable for a given input.
+ *
+ * @param metrics - Metric families, e.g. from activity_metrics
+ * @returns The exposition text, ending in a newline
+ */
+#[napi]
+pub fn render_prometheus(metrics: Vec<PrometheusMetric>) -> Result<String> {
+    let mut out = String::new();
+    for metric in &metrics {
+        check(metric)?;
+        if let Some(help) = &metric.help {
+            let help = help.replace('\\', "\\\\").replace('\n', "\\n");
+            let _ = writeln!(out, "# HELP {} {}", metric.name, help);
+        }
+        let _ = writeln!(out, "# TYPE {} {}", metric.name, metric.metric_type);
+        for sample in &metric.samples {
+            let labels = sample.labels.as_ref();
+            let Some(observations) = &sample.observations else {
+                let _ = writeln!(
+                    out,
+                    "{}{} {}",
+                    metric.name,
+                    format_labels(labels, None),
+                    format_value(sample.value.unwrap_or_default())
+                );
+                continue;
+            };
+            let mut bounds = metric.buckets.clone().unwrap_or_default();
+            bounds.retain(|bound| bound.is_finite());
+            bounds.sort_by(f64::total_cmp);
+            bounds.dedup();
+            bounds.push(f64::INFINITY);
+            for bound in bounds {
+                let count = observations.iter().filter(|o| **o <= bound).count();
+                let le = format_value(bound);
+                let _ = writeln!(
+                    out,
+                    "{}_bucket{} {}",
+                    metric.name,
+                    format_labels(labels, Some(("le", &le))),
+                    count
+                );
+            }
+            let _ = writeln!(
+                out,
+                "{}_sum{} {}",
+                metric.name,
+                format_labels(labels, None),
+                format_value(observations.iter().sum())
+            );
+            let _ = writeln!(
+                out,
+                "{}_count{} {}",
+                metric.name,
+                format_labels(labels, None),
+                observations.len()
+            );
+        }
+    }
+    Ok(out)
+}
+
+/**
+ * Standard activity metrics for a set of events
+ *
+ * cursor_events_total and cursor_lines_{added,removed}_total counters by
+ * type and workspace, a cursor_sessions gauge of distinct sessions per
+ * workspace, and a cursor_edit_size_lines histogram of lines touched per
+ * edit. Pass the result to render_prometheus or a PrometheusServer.
+ *
+ * @param events - Events with a type, and optionally workspace, session, and line counts
+ * @returns Metric families
+ */
+#[napi(ts_args_type = "events: Array<Record<string, any>>")]
+pub fn activity_metrics(events: Vec<Value>) -> Vec<PrometheusMetric> {
+    // (type, workspace) -> (events, lines added, lines removed)
+    let mut counts: AHashMap<(String, String), (f64, f64, f64)> = AHashMap::new();
+    let mut sessions: AHashMap<String, AHashSet<String>> = AHashMap::new();
+    let mut edit_sizes: AHashMap<String, Vec<f64>> = AHashMap::new();
+    for event in events.iter().filter_map(Value::as_object) {
+        let details = details_object(event);
+        let text = |keys: &[&str]| match event_field(event, details.as_ref(), keys) {
+            Some(Value::String(text)) => text,
+            _ => String::new(),
+        };
+        let number = |keys: &[&str]| event_field(event, details.as_ref(), keys)?.as_f64();
+        let event_type = text(&["type"]);
+        let workspace = text(&["workspace_path", "workspacePath"]);
+        let session = text(&["session_id", "sessionId"]);
+        let added = number(&["lines_added", "linesAdded"]);
+        let removed = number(&["lines_removed", "linesRemoved"]);
+        if !session.is_empty() {
+            sessions
+                .entry(workspace.clone())
+                .or_default()
+                .insert(session);
+        }
+        if EDIT_TYPES.contains(&event_type.as_str()) && (added.is_some() || removed.is_some()) {
+            edit_sizes
+                .entry(workspace.clone())
+                .or_default()
+                .push(added.unwrap_or(0.0) + removed.unwrap_or(0.0));
+        }
+        let entry = counts.entry((event_type, workspace)).or_default();
+        entry.0 += 1.0;
+        entry.1 += added.unwrap_or(0.0);
+        entry.2 += removed.unwrap_or(0.0);
+    }
+
+    let labels = |pairs: &[(&str, &str)]| -> Option<HashMap<String, String>> {
+        Some(
+            pairs
+                .iter()
+                .filter(|(_, value)| !value.is_empty())
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+        )
+    };
+    let mut counts: Vec<_> = counts.into_iter().collect();
+    counts.sort_by(|a, b| a.0.cmp(&b.0));
+    let counter = |name: &str, help: &str, pick: fn(&(f64, f64, f64)) -> f64| PrometheusMetric {
+        name: name.to_string(),
+        help: Some(help.to_string()),
+        metric_type: "counter".to_string(),
+        samples: counts
+            .iter()
+            .map(|((event_type, workspace), values)| PrometheusSample {
+                labels: labels(&[
+                    ("type", event_type.as_str()),
+                    ("workspace", workspace.as_str()),
+                ]),
+                value: Some(pick(values)),
+                observations: None,
+            })
+            .collect(),
+        buckets: None,
+    };
+    let mut sessions: Vec<_> = sessions.into_iter().collect();
+    sessions.sort_by(|a, b| a.0.cmp(&b.0));
+    let mut edit_sizes: Vec<_> = edit_sizes.into_iter().collect();
+    edit_sizes.sort_by(|a, b| a.0.cmp(&b.0));
+    vec![
+        counter("cursor_events_total", "Events captured", |v| v.0),
+        counter("cursor_lines_added_total", "Lines added", |v| v.1),
+        counter("cursor_lines_removed_total", "Lines removed", |v| v.2),
+        PrometheusMetric {
+            name: "cursor_sessions".to_string(),
+            help: Some("Distinct sessions seen".to_string()),
+            metric_type: "gauge".to_string(),
+            samples: sessions
+                .into_iter()
+                .map(|(workspace, ids)| PrometheusSample {
+                    labels: labels(&[("workspace", &workspace)]),
+                    value: Some(ids.len() as f64),
+                    observations: None,
+                })
+                .collect(),
+            buckets: None,
+        },
+        PrometheusMetric {
+            name: "cursor_edit_size_lines".to_string(),
+            help: Some("Lines added plus removed per edit".to_string()),
+            metric_type: "histogram".to_string(),
+            samples: edit_sizes
+                .into_iter()
+                .map(|(workspace, sizes)| PrometheusSample {
+                    labels: labels(&[("workspace", &workspace)]),
+                    value: None,
+                    observations: Some(sizes),
+                })
+                .collect(),
+            buckets: Some(EDIT_SIZE_BUCKETS.to_vec()),
+        },
+    ]
+}
+
+/// Answer one request: the current text at /metrics, 404 elsewhere
+fn serve(mut stream: TcpStream, body: &Mutex<String>) {
+    let _ = stream.set_read_timeout(Some(Duration::from_secs(5)));
+    let mut request_line = String::new();
+    let mut reader = BufReader::new(match stream.try_clone() {
+        Ok(stream) => stream,
+        Err(_) => return,
+    });
+    if reader.read_line(&mut request_line).is_err() {
+        return;
+    }
+    // Drain headers; the request has no body worth reading
+    let mut header = String::new();
+    while reader.read_line(&mut header).is_ok_and(|n| n > 2) {
+        header.clear();
+    }
+    let mut parts = request_line.split_whitespace();
+    let (method, target) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+    let path = target.split('?').next().unwrap_or("");
+    let response = if method != "GET" && method != "HEAD" {
+        "HTTP/1.1 405 Method Not Allowed\r\nAllow: GET, HEAD\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string()
+    } else if path != "/metrics" {
+        "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string()
+    } else {
+        let body = body.lock().map(|body| body.clone()).unwrap_or_default();
+        let mut response = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            CONTENT_TYPE,
+            body.len()
+        );
+        if method == "GET" {
+            response.push_str(&body);
+        }
+        response
+    };
+    let _ = stream.write_all(response.as_bytes());
+}
+
+/// A minimal HTTP server answering Prometheus scrapes at /metrics
+#[napi]
+pub struct PrometheusServer {
+    body: Arc<Mutex<String>>,
+    stopped: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+    port: u32,
+}
+
+#[napi]
+impl PrometheusServer {
+    /**
+     * Listen on `host:port` (default host 127.0.0.1) and serve /metrics
+     *
+     * Port 0 picks a free port; read it back from `port`. Requests are
+     * answered one at a time on a background thread with whatever was last
+     * passed to update, so scrapes never wait on the event loop.
+     */
+    #[napi(factory)]
+    pub fn start(port: u32, host: Option<String>) -> Result<Self> {
+        let host = host.unwrap_or_else(|| "127.0.0.1".to_string());
+        let address = format!("{}:{}", host, port);
+        let fail = |action: &str, error: std::io::Error| {
+            Error::new(
+                Status::GenericFailure,
+                format!("Failed to {} {}: {}", action, address, error),
+            )
+        };
+        let listener = TcpListener::bind(&address).map_err(|e| fail("listen on", e))?;
+        listener
+            .set_nonblocking(true)
+            .map_err(|e| fail("configure", e))?;
+        let port = listener
+            .local_addr()
+            .map_err(|e| fail("listen on", e))?
+            .port() as u32;
+        let body = Arc::new(Mutex::new(String::new()));
+        let stopped = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let body = body.clone();
+            let stopped = stopped.clone();
+            std::thread::spawn(move || {
+                while !stopped.load(Ordering::Relaxed) {
+                    match listener.accept() {
+                        Ok((stream, _)) => {
+                            let _ = stream.set_nonblocking(false);
+                            serve(stream, &body);
+                        }
+                        // WouldBlock when idle; other accept errors are per-connection
+                        Err(_) => std::thread::sleep(POLL_INTERVAL),
+                    }
+                }
+            })
+        };
+        Ok(PrometheusServer {
+            body,
+            stopped,
+            thread: Some(thread),
+            port,
+        })
+    }
+
+    /// Replace what scrapes return with these metrics
+    #[napi]
+    pub fn update(&self, metrics: Vec<PrometheusMetric>) -> Result<()> {
+        let text = render_prometheus(metrics)?;
+        *self
+            .body
+            .lock()
+            .map_err(|_| Error::new(Status::GenericFailure, "Metrics lock poisoned"))? = text;
+        Ok(())
+    }
+
+    /// The port being listened on
+    #[napi(getter)]
+    pub fn port(&self) -> u32 {
+        self.port
+    }
+
+    /// Stop listening; waits for a request in progress to finish
+    #[napi]
+    pub fn stop(&mut self) {
+        self.stopped.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for PrometheusServer {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
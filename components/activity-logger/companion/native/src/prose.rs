@@ -0,0 +1,254 @@
+/*!
+ * Prose vs code classification
+ * Which lines of a file, comment, or chat message are natural language and which are code
+ */
+
+use crate::comments::{comment_syntax, CommentScanner, LineKind};
+use napi_derive::napi;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum TextKind {
+    Prose,
+    Code,
+    Blank,
+}
+
+impl TextKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            TextKind::Prose => "prose",
+            TextKind::Code => "code",
+            TextKind::Blank => "blank",
+        }
+    }
+}
+
+/// A run of lines of one kind
+#[napi(object)]
+pub struct TextSegment {
+    /// "prose" or "code"
+    pub kind: String,
+    /// 1-based, inclusive
+    pub start_line: u32,
+    pub end_line: u32,
+}
+
+/// Result of classify_prose
+#[napi(object)]
+pub struct ProseAnalysis {
+    /// Language whose rules were applied, or "text"
+    pub language: String,
+    /// Prose characters over non-blank characters, 0-1
+    pub prose_fraction: f64,
+    pub prose_lines: u32,
+    pub code_lines: u32,
+    pub blank_lines: u32,
+    pub prose_chars: i64,
+    pub code_chars: i64,
+    /// Consecutive lines of the same kind, blank lines absorbed into the run around them
+    pub segments: Vec<TextSegment>,
+}
+
+/// Languages whose text is prose unless marked up as code
+fn is_document(language: &str) -> bool {
+    matches!(
+        language,
+        "markdown" | "restructuredtext" | "asciidoc" | "latex" | "org"
+    )
+}
+
+/// Sentence-like text: mostly alphabetic words and few code symbols
+pub(crate) fn reads_as_prose(line: &str) -> bool {
+    let text = line.trim();
+    let words: Vec<&str> = text.split_whitespace().collect();
+    if words.is_empty() {
+        return false;
+    }
+    let letters = text.chars().filter(|c| c.is_alphabetic()).count();
+    let symbols = text
+        .chars()
+        .filter(|c| {
+            matches!(
+                c,
+                '{' | '}' | '(' | ')' | '[' | ']' | ';' | '=' | '<' | '>' | '$' | '&' | '|' | '\\'
+            )
+        })
+        .count();
+    let non_space = text.chars().filter(|c| !c.is_whitespace()).count().max(1);
+    let wordlike = words
+        .iter()
+        .filter(|w| {
+            let w = w.trim_matches(|c: char| {
+                matches!(c, ',' | '.' | ':' | '!' | '?' | '"' | '\'' | '(' | ')')
+            });
+            !w.is_empty()
+                && w.chars()
+                    .all(|c| c.is_alphabetic() || c == '-' || c == '\'')
+        })
+        .count();
+    let code_shape = text.ends_with(';')
+        || text.ends_with('{')
+        || text.ends_with("=>")
+        || text.contains("::")
+        || text.contains("()")
+        || text.contains(" = ")
+        || text.contains("==")
+        || text.contains("->");
+    let word_ratio = wordlike as f64 / words.len() as f64;
+    let symbol_ratio = symbols as f64 / non_space as f64;
+    let letter_ratio = letters as f64 / non_space as f64;
+
+    if words.len() >= 3 {
+        word_ratio >= 0.6 && symbol_ratio < 0.08 && !(code_shape && word_ratio < 0.8)
+    } else {
+        // Short lines: a heading, a label, "Thanks!"; not `}` or `x += 1`
+        !code_shape && symbol_ratio == 0.0 && letter_ratio >= 0.7 && wordlike == words.len()
+    }
+}
+
+/// Text of a comment line without its markers: `// note`, `# note`, ` * note`
+fn comment_text<'a>(line: &'a str, language: &str) -> &'a str {
+    let syntax = comment_syntax(language);
+    let mut text = line.trim();
+    for marker in syntax.line {
+        if let Some(rest) = text.strip_prefix(marker) {
+            text = rest;
+            break;
+        }
+    }
+    for (open, close) in syntax.block {
+        text = text.strip_prefix(open).unwrap_or(text);
+        text = text.strip_suffix(close).unwrap_or(text);
+    }
+    text.trim_start_matches(['*', '/', '!', '#', '-'])
+        .trim_start_matches("\"\"\"")
+        .trim_end_matches("\"\"\"")
+}
+
+/// Kind of each line of `content`
+pub(crate) fn classify_lines(content: &str, language: &str) -> Vec<TextKind> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut kinds = Vec::with_capacity(lines.len());
+    let mut fence: Option<&str> = None;
+    let document = is_document(language);
+    let free_text = matches!(language, "text" | "plaintext" | "unknown" | "");
+    let code_language = !document && !free_text;
+    let mut scanner = CommentScanner::new(comment_syntax(language));
+    let mut previous_blank = true;
+
+    for line in &lines {
+        let trimmed = line.trim_start();
+        if let Some(marker) = fence {
+            if trimmed.starts_with(marker) {
+                fence = None;
+            }
+            kinds.push(TextKind::Code);
+            continue;
+        }
+        if !code_language && (trimmed.starts_with("```") || trimmed.starts_with("~~~")) {
+            fence = Some(&trimmed[..3]);
+            kinds.push(TextKind::Code);
+            continue;
+        }
+        if line.trim().is_empty() {
+            if code_language {
+                scanner.classify(line);
+            }
+            previous_blank = true;
+            kinds.push(TextKind::Blank);
+            continue;
+        }
+        let kind = if code_language {
+            match scanner.classify(line) {
+                LineKind::Comment => {
+                    let text = comment_text(line, language);
+                    if text.trim().is_empty() || reads_as_prose(text) {
+                        TextKind::Prose
+                    } else {
+                        // Commented-out code
+                        TextKind::Code
+                    }
+                }
+                _ => TextKind::Code,
+            }
+        } else if document {
+            let indented = line.starts_with("    ") || line.starts_with('\t');
+            let in_indented_block = kinds.last() == Some(&TextKind::Code) || previous_blank;
+            let list_item = trimmed.starts_with(['-', '*', '+'])
+                || trimmed.starts_with(|c: char| c.is_ascii_digit());
+            if language == "markdown" && indented && in_indented_block && !list_item {
+                TextKind::Code
+            } else {
+                TextKind::Prose
+            }
+        } else if reads_as_prose(line) {
+            TextKind::Prose
+        } else {
+            TextKind::Code
+        };
+        previous_blank = false;
+        kinds.push(kind);
+    }
+    kinds
+}
+
+/**
+ * Estimate how much of a text is prose and how much is code
+ *
+ * Markdown and other document formats are prose except fenced and
+ * indented code blocks. In source code, comment lines that read as
+ * sentences are prose and everything else, commented-out code included,
+ * is code. Text with no known language (chat messages, snippets) is
+ * judged line by line from word shapes and code punctuation, with fenced
+ * blocks always code.
+ *
+ * @param content - Text to classify
+ * @param language - Language name as returned by detect_language; omit for chat and other free text
+ * @returns Prose fraction by characters, line counts, and prose/code segments
+ */
+#[napi]
+pub fn classify_prose(content: String, language: Option<String>) -> ProseAnalysis {
+    let language = language.unwrap_or_else(|| "text".to_string());
+    let kinds = classify_lines(&content, &language);
+    let mut analysis = ProseAnalysis {
+        language,
+        prose_fraction: 0.0,
+        prose_lines: 0,
+        code_lines: 0,
+        blank_lines: 0,
+        prose_chars: 0,
+        code_chars: 0,
+        segments: Vec::new(),
+    };
+    for (index, (line, kind)) in content.lines().zip(&kinds).enumerate() {
+        let chars = line.trim().chars().count() as i64;
+        match kind {
+            TextKind::Prose => {
+                analysis.prose_lines += 1;
+                analysis.prose_chars += chars;
+            }
+            TextKind::Code => {
+                analysis.code_lines += 1;
+                analysis.code_chars += chars;
+            }
+            TextKind::Blank => {
+                analysis.blank_lines += 1;
+                continue;
+            }
+        }
+        let line_number = index as u32 + 1;
+        match analysis.segments.last_mut() {
+            Some(segment) if segment.kind == kind.as_str() => segment.end_line = line_number,
+            _ => analysis.segments.push(TextSegment {
+                kind: kind.as_str().to_string(),
+                start_line: line_number,
+                end_line: line_number,
+            }),
+        }
+    }
+    let total = analysis.prose_chars + analysis.code_chars;
+    if total > 0 {
+        analysis.prose_fraction = analysis.prose_chars as f64 / total as f64;
+    }
+    analysis
+}
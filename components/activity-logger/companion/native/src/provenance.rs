@@ -0,0 +1,372 @@
+/*!
+ * Prompt provenance
+ * Links each file change to the AI interaction most likely to have caused
+ * it, by matching inserted lines against the code in responses and by how
+ * soon after the response the change landed
+ */
+
+use crate::ingest::{details_object, event_field, parse_timestamp, EDIT_TYPES};
+use crate::similarity::{contained, shingles};
+use ahash::AHashSet;
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use serde_json::{Map, Value};
+use similar::{ChangeTag, TextDiff};
+
+const DEFAULT_WINDOW_MS: f64 = 10.0 * 60_000.0;
+const DEFAULT_SHINGLE_SIZE: u32 = 3;
+const DEFAULT_CONTENT_WEIGHT: f64 = 0.8;
+const DEFAULT_MIN_CONFIDENCE: f64 = 0.3;
+/// Best confidence a link can reach on timing alone, without code to compare
+const TIMING_ONLY_CAP: f64 = 0.5;
+
+/// Options for link_prompts_to_diffs
+#[napi(object)]
+#[derive(Default)]
+pub struct ProvenanceOptions {
+    /// How long after an interaction's last response a change can still be linked to it (default: 600000)
+    pub window_ms: Option<f64>,
+    /// Tokens per shingle when comparing code (default: 3)
+    pub shingle_size: Option<u32>,
+    /// Share of confidence from content, the rest from timing, 0-1 (default: 0.8)
+    pub content_weight: Option<f64>,
+    /// Lowest confidence reported as a link (default: 0.3)
+    pub min_confidence: Option<f64>,
+}
+
+/// Where one file change most likely came from
+#[napi(object)]
+pub struct DiffProvenance {
+    pub diff_id: Option<String>,
+    pub timestamp: f64,
+    pub file_path: Option<String>,
+    /// The prompt, or the first response when no prompt was captured; None when unlinked
+    pub prompt_id: Option<String>,
+    /// Response events of the interaction, in time order
+    pub response_ids: Vec<String>,
+    /// 0-1
+    pub confidence: f64,
+    /// Share of the inserted code found in the interaction's responses, 0-1
+    pub content_match: f64,
+    /// Time from the interaction's last response before the change
+    pub gap_ms: Option<f64>,
+    /// "content", "timing" (no code to compare), or "none" (unlinked)
+    pub method: String,
+    /// Interactions within the window that were considered
+    pub candidates: u32,
+}
+
+/// A prompt and the responses that followed it
+pub(crate) struct Interaction {
+    pub prompt_id: Option<String>,
+    pub response_ids: Vec<String>,
+    pub session: Option<String>,
+    pub workspace: Option<String>,
+    pub start: f64,
+    /// Time of the last event in the interaction
+    pub last: f64,
+    /// Code from the responses: fenced blocks, or whole texts without fences
+    pub code: String,
+}
+
+/// The flattened fields of one event
+pub(crate) struct Fields {
+    pub fields: Map<String, Value>,
+    pub details: Option<Map<String, Value>>,
+    pub timestamp: f64,
+}
+
+impl Fields {
+    pub fn of(event: &Value) -> Option<Self> {
+        let Value::Object(fields) = event else {
+            return None;
+        };
+        let timestamp = parse_timestamp(fields.get("timestamp")?)?;
+        Some(Fields {
+            details: details_object(fields),
+            fields: fields.clone(),
+            timestamp,
+        })
+    }
+
+    pub fn text(&self, keys: &[&str]) -> Option<String> {
+        match event_field(&self.fields, self.details.as_ref(), keys)? {
+            Value::String(text) if !text.is_empty() => Some(text),
+            Value::Number(n) => Some(n.to_string()),
+            _ => None,
+        }
+    }
+
+    pub fn kind(&self) -> &str {
+        self.fields
+            .get("type")
+            .and_then(Value::as_str)
+            .unwrap_or("")
+    }
+
+    pub fn id(&self) -> Option<String> {
+        self.text(&["id"])
+    }
+
+    pub fn session(&self) -> Option<String> {
+        self.text(&["session_id", "sessionId"])
+    }
+
+    pub fn workspace(&self) -> Option<String> {
+        self.text(&["workspace_path", "workspacePath"])
+    }
+
+    pub fn file(&self) -> Option<String> {
+        self.text(&["file_path", "filePath", "file"])
+    }
+
+    /// Free text, including a `details` that is a plain description rather than JSON
+    fn body(&self, keys: &[&str]) -> Option<String> {
+        self.text(keys).or_else(|| {
+            self.details
+                .is_none()
+                .then(|| self.fields.get("details")?.as_str().map(str::to_string))
+                .flatten()
+        })
+    }
+}
+
+/// Code in a response: the contents of its ``` fences, or all of it when it has none
+pub(crate) fn code_of(text: &str) -> String {
+    let mut code = String::new();
+    let mut fenced = false;
+    let mut inside = false;
+    for line in text.lines() {
+        if line.trim_start().starts_with("```") {
+            fenced = true;
+            inside = !inside;
+            continue;
+        }
+        if inside {
+            code.push_str(line);
+            code.push('\n');
+        }
+    }
+    if fenced {
+        code
+    } else {
+        text.to_string()
+    }
+}
+
+/// Lines an edit inserted, with their 1-based line numbers in the new content when known
+pub(crate) fn inserted_lines(event: &Fields) -> Vec<(Option<u32>, String)> {
+    if let Some(text) = event.text(&["added_text", "addedText", "inserted_text", "insertedText"]) {
+        return text.lines().map(|line| (None, line.to_string())).collect();
+    }
+    let Some(after) = event.text(&["after_content", "afterContent"]) else {
+        return Vec::new();
+    };
+    let before = event
+        .text(&["before_content", "beforeContent"])
+        .unwrap_or_default();
+    TextDiff::from_lines(&before, &after)
+        .iter_all_changes()
+        .filter(|change| change.tag() == ChangeTag::Insert)
+        .map(|change| {
+            (
+                change.new_index().map(|i| i as u32 + 1),
+                change.value().trim_end_matches(['\n', '\r']).to_string(),
+            )
+        })
+        .collect()
+}
+
+pub(crate) fn is_edit(event: &Fields) -> bool {
+    EDIT_TYPES.contains(&event.kind())
+}
+
+/// Group prompts and responses into interactions, oldest first
+///
+/// A prompt opens an interaction; responses, assistant chat messages, and
+/// AI suggestions join the latest one in the same session, or open their
+/// own when there is none.
+pub(crate) fn interactions(events: &[Fields]) -> Vec<Interaction> {
+    let mut interactions: Vec<Interaction> = Vec::new();
+    for event in events {
+        let role = event.text(&["role"]);
+        let is_prompt = match event.kind() {
+            "prompt" => true,
+            "chat" => role.as_deref() == Some("user"),
+            _ => false,
+        };
+        let is_response = match event.kind() {
+            "response" | "ai_suggestion" => true,
+            "chat" => role.as_deref() == Some("assistant"),
+            _ => false,
+        };
+        let session = event.session();
+        if is_prompt {
+            interactions.push(Interaction {
+                prompt_id: event.id(),
+                response_ids: Vec::new(),
+                session,
+                workspace: event.workspace(),
+                start: event.timestamp,
+                last: event.timestamp,
+                code: String::new(),
+            });
+            continue;
+        }
+        if !is_response {
+            continue;
+        }
+        let code = event
+            .body(&["code", "response", "text", "content"])
+            .map(|text| code_of(&text))
+            .unwrap_or_default();
+        let open = interactions
+            .iter()
+            .rposition(|i| i.session == session || i.session.is_none() || session.is_none());
+        let index = match open {
+            Some(index) => index,
+            None => {
+                interactions.push(Interaction {
+                    prompt_id: None,
+                    response_ids: Vec::new(),
+                    session,
+                    workspace: event.workspace(),
+                    start: event.timestamp,
+                    last: event.timestamp,
+                    code: String::new(),
+                });
+                interactions.len() - 1
+            }
+        };
+        let interaction = &mut interactions[index];
+        if let Some(id) = event.id() {
+            interaction.prompt_id.get_or_insert_with(|| id.clone());
+            interaction.response_ids.push(id);
+        }
+        interaction.last = interaction.last.max(event.timestamp);
+        interaction.code.push_str(&code);
+        interaction.code.push('\n');
+    }
+    interactions
+}
+
+/// Time from an interaction's last event to an edit it could have caused
+///
+/// None when the interaction started after the edit, ended more than
+/// `window_ms` before it, or belongs to another workspace. A response
+/// still streaming when the edit landed counts as immediate.
+pub(crate) fn gap_before(
+    interaction: &Interaction,
+    edit: &Fields,
+    workspace: Option<&str>,
+    window_ms: f64,
+) -> Option<f64> {
+    let gap = edit.timestamp - interaction.last;
+    let elsewhere = matches!(
+        (workspace, interaction.workspace.as_deref()),
+        (Some(a), Some(b)) if a != b
+    );
+    (interaction.start <= edit.timestamp && gap <= window_ms && !elsewhere).then(|| gap.max(0.0))
+}
+
+/// Flattened events with a timestamp, oldest first
+pub(crate) fn sorted_fields(events: &[Value]) -> Vec<Fields> {
+    let mut fields: Vec<Fields> = events.iter().filter_map(Fields::of).collect();
+    fields.sort_by(|a, b| a.timestamp.total_cmp(&b.timestamp));
+    fields
+}
+
+/**
+ * Link each file change to the prompt most likely to have caused it
+ *
+ * Prompts and the responses after them (`response` events, `chat` events
+ * by role, and `ai_suggestion` events) form interactions. Each edit
+ * (`code_change` or `file_change`) is compared with the interactions that
+ * started before it and whose last response is at most `window_ms`
+ * earlier, in the same workspace when both record one. The lines it
+ * inserted, from `added_text` or by diffing `before_content` and
+ * `after_content`, are matched against the code in the responses (fenced
+ * blocks, or whole texts without fences) by shingle containment; the match
+ * is weighted with how soon the change followed. When there is no code to
+ * compare, timing alone links the change, with confidence at most 0.5.
+ *
+ * @param events - Chat, suggestion, and edit events, in any order
+ * @param options - Window, weights, and the confidence needed for a link
+ * @returns One entry per edit, in time order; unlinked edits have no prompt_id
+ */
+#[napi(
+    ts_args_type = "events: Array<Record<string, any>>, options?: ProvenanceOptions | undefined | null"
+)]
+pub fn link_prompts_to_diffs(
+    events: Vec<Value>,
+    options: Option<ProvenanceOptions>,
+) -> Result<Vec<DiffProvenance>> {
+    let options = options.unwrap_or_default();
+    let window_ms = options.window_ms.unwrap_or(DEFAULT_WINDOW_MS);
+    let content_weight = options.content_weight.unwrap_or(DEFAULT_CONTENT_WEIGHT);
+    if window_ms.is_nan() || window_ms <= 0.0 || !(0.0..=1.0).contains(&content_weight) {
+        return Err(Error::new(
+            Status::InvalidArg,
+            "window_ms must be positive and content_weight between 0 and 1".to_string(),
+        ));
+    }
+    let size = options.shingle_size.unwrap_or(DEFAULT_SHINGLE_SIZE) as usize;
+    let min_confidence = options.min_confidence.unwrap_or(DEFAULT_MIN_CONFIDENCE);
+
+    let events = sorted_fields(&events);
+    let interactions = interactions(&events);
+    let code: Vec<AHashSet<u64>> = interactions
+        .iter()
+        .map(|i| shingles(&i.code, size))
+        .collect();
+
+    Ok(events
+        .iter()
+        .filter(|event| is_edit(event))
+        .map(|edit| {
+            let inserted: Vec<String> = inserted_lines(edit).into_iter().map(|(_, l)| l).collect();
+            let inserted = shingles(&inserted.join("\n"), size);
+            let workspace = edit.workspace();
+            let mut candidates = 0;
+            // (confidence, content match, gap, method, interaction)
+            let mut best: Option<(f64, f64, f64, &str, usize)> = None;
+            for (index, interaction) in interactions.iter().enumerate() {
+                let Some(gap) = gap_before(interaction, edit, workspace.as_deref(), window_ms)
+                else {
+                    continue;
+                };
+                candidates += 1;
+                let timing = 1.0 - gap / window_ms;
+                let (confidence, matched, method) = if inserted.is_empty() || code[index].is_empty()
+                {
+                    (TIMING_ONLY_CAP * timing, 0.0, "timing")
+                } else {
+                    let matched = contained(&inserted, &code[index]);
+                    (
+                        content_weight * matched + (1.0 - content_weight) * timing,
+                        matched,
+                        "content",
+                    )
+                };
+                // Later interactions win ties, being the likelier cause
+                if best.is_none_or(|(b, ..)| confidence >= b) {
+                    best = Some((confidence, matched, gap, method, index));
+                }
+            }
+            let linked = best.filter(|(confidence, ..)| *confidence >= min_confidence);
+            let interaction = linked.map(|(.., index)| &interactions[index]);
+            DiffProvenance {
+                diff_id: edit.id(),
+                timestamp: edit.timestamp,
+                file_path: edit.file(),
+                prompt_id: interaction.and_then(|i| i.prompt_id.clone()),
+                response_ids: interaction.map_or_else(Vec::new, |i| i.response_ids.clone()),
+                confidence: linked.map_or(0.0, |(confidence, ..)| confidence),
+                content_match: linked.map_or(0.0, |(_, matched, ..)| matched),
+                gap_ms: linked.map(|(_, _, gap, ..)| gap),
+                method: linked.map_or("none", |(.., method, _)| method).to_string(),
+                candidates,
+            }
+        })
+        .collect())
+}
@@ -0,0 +1,463 @@
+/*!
+ * Persistent disk queue
+ * Records in checksummed segment files plus an index of the acknowledged
+ * position, so a crash between capture and upload loses nothing
+ */
+
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+const DEFAULT_MAX_SEGMENT_BYTES: u32 = 16 * 1024 * 1024;
+const INDEX_FILE: &str = "queue.idx";
+/// Length and CRC-32 before each record's payload
+const HEADER_BYTES: u64 = 8;
+
+/// CRC-32 (IEEE) lookup table
+const CRC_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0u32, |crc, b| {
+        CRC_TABLE[((crc ^ *b as u32) & 0xFF) as usize] ^ (crc >> 8)
+    })
+}
+
+/// Options for a DiskQueue
+#[napi(object)]
+pub struct DiskQueueOptions {
+    /// Start a new segment file once the current one reaches this size (default: 16 MiB)
+    pub max_segment_bytes: Option<u32>,
+    /// Fsync every enqueue before returning (default: true)
+    pub fsync: Option<bool>,
+    /// Hand an unacknowledged record out again after this long; 0 waits for nack or a restart (default: 0)
+    pub visibility_timeout_ms: Option<u32>,
+}
+
+/// A record handed out by dequeue
+#[napi(object)]
+pub struct QueueItem {
+    /// Pass to ack once the record is safely uploaded
+    pub id: i64,
+    pub data: Buffer,
+    /// Times this record has been handed out since the queue was opened, this one included
+    pub attempts: u32,
+}
+
+/// Counts for a DiskQueue
+#[napi(object)]
+pub struct QueueStats {
+    /// Records waiting to be dequeued
+    pub pending: u32,
+    /// Records dequeued but not yet acknowledged
+    pub in_flight: u32,
+    pub segments: u32,
+    /// Size of all segment files
+    pub bytes: f64,
+    /// Bytes of torn or corrupt records cut off when the queue was opened
+    pub recovered_bytes: f64,
+}
+
+/// Where a record lives
+#[derive(Clone, Copy)]
+struct Position {
+    segment: u32,
+    offset: u64,
+    length: u32,
+    attempts: u32,
+}
+
+/// Crash-safe FIFO of byte records with acknowledgement
+#[napi]
+pub struct DiskQueue {
+    dir: PathBuf,
+    max_segment_bytes: u64,
+    fsync: bool,
+    visibility_timeout_ms: u128,
+    /// Segment being appended to, its number and size
+    writer: File,
+    write_segment: u32,
+    write_offset: u64,
+    next_id: u64,
+    pending: BTreeMap<u64, Position>,
+    in_flight: BTreeMap<u64, (Position, Instant)>,
+    recovered_bytes: u64,
+}
+
+fn io_error(action: &str, path: &Path, error: std::io::Error) -> Error {
+    Error::new(
+        Status::GenericFailure,
+        format!("Failed to {} {}: {}", action, path.display(), error),
+    )
+}
+
+fn segment_name(segment: u32) -> String {
+    format!("queue-{:08}.seg", segment)
+}
+
+/// Segment numbers on disk, ascending
+fn list_segments(dir: &Path) -> Result<Vec<u32>> {
+    let mut segments: Vec<u32> = fs::read_dir(dir)
+        .map_err(|e| io_error("read", dir, e))?
+        .filter_map(|entry| {
+            let name = entry.ok()?.file_name();
+            name.to_str()?
+                .strip_prefix("queue-")?
+                .strip_suffix(".seg")?
+                .parse()
+                .ok()
+        })
+        .collect();
+    segments.sort_unstable();
+    Ok(segments)
+}
+
+fn open_segment(dir: &Path, segment: u32) -> Result<File> {
+    let path = dir.join(segment_name(segment));
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| io_error("open", &path, e))
+}
+
+/// Valid records in a segment from `offset` on, and where the valid part ends
+fn scan_segment(bytes: &[u8], mut offset: u64) -> (Vec<(u64, u32)>, u64) {
+    let mut records = Vec::new();
+    while offset + HEADER_BYTES <= bytes.len() as u64 {
+        let at = offset as usize;
+        let length = u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap());
+        let checksum = u32::from_le_bytes(bytes[at + 4..at + 8].try_into().unwrap());
+        let end = offset + HEADER_BYTES + length as u64;
+        if end > bytes.len() as u64 || crc32(&bytes[at + 8..end as usize]) != checksum {
+            break;
+        }
+        records.push((offset, length));
+        offset = end;
+    }
+    (records, offset)
+}
+
+impl DiskQueue {
+    fn segment_path(&self, segment: u32) -> PathBuf {
+        self.dir.join(segment_name(segment))
+    }
+
+    fn read(&self, position: &Position) -> Result<Vec<u8>> {
+        let path = self.segment_path(position.segment);
+        let mut data = vec![0u8; position.length as usize];
+        File::open(&path)
+            .and_then(|mut file| {
+                file.seek(SeekFrom::Start(position.offset + HEADER_BYTES))?;
+                file.read_exact(&mut data)
+            })
+            .map_err(|e| io_error("read", &path, e))?;
+        Ok(data)
+    }
+
+    /// Record the oldest unacknowledged position and drop segments wholly before it
+    fn commit(&self) -> Result<()> {
+        let oldest = self
+            .pending
+            .iter()
+            .map(|(id, position)| (*id, *position))
+            .chain(
+                self.in_flight
+                    .iter()
+                    .map(|(id, (position, _))| (*id, *position)),
+            )
+            .min_by_key(|(id, _)| *id);
+        let (id, segment, offset) = match oldest {
+            Some((id, position)) => (id, position.segment, position.offset),
+            None => (self.next_id, self.write_segment, self.write_offset),
+        };
+        let index = self.dir.join(INDEX_FILE);
+        let temporary = self.dir.join(format!("{}.tmp", INDEX_FILE));
+        let contents = json!({ "id": id, "segment": segment, "offset": offset }).to_string();
+        File::create(&temporary)
+            .and_then(|mut file| {
+                file.write_all(contents.as_bytes())?;
+                file.sync_all()
+            })
+            .and_then(|_| fs::rename(&temporary, &index))
+            .map_err(|e| io_error("write", &index, e))?;
+        for old in list_segments(&self.dir)?
+            .into_iter()
+            .filter(|old| *old < segment)
+        {
+            let path = self.segment_path(old);
+            fs::remove_file(&path).map_err(|e| io_error("remove", &path, e))?;
+        }
+        Ok(())
+    }
+
+    /// Move in-flight records whose visibility timeout passed back to pending
+    fn expire(&mut self) {
+        if self.visibility_timeout_ms == 0 {
+            return;
+        }
+        let expired: Vec<u64> = self
+            .in_flight
+            .iter()
+            .filter(|(_, (_, since))| since.elapsed().as_millis() >= self.visibility_timeout_ms)
+            .map(|(id, _)| *id)
+            .collect();
+        for id in expired {
+            if let Some((position, _)) = self.in_flight.remove(&id) {
+                self.pending.insert(id, position);
+            }
+        }
+    }
+}
+
+#[napi]
+impl DiskQueue {
+    /**
+     * Open the queue in `dir`, creating it if needed
+     *
+     * Records that were enqueued but never acknowledged come back as
+     * pending, including ones dequeued before a crash. A record torn by a
+     * crash mid-write fails its checksum and is cut off, along with
+     * anything after it in that segment.
+     */
+    #[napi(constructor)]
+    pub fn new(dir: String, options: Option<DiskQueueOptions>) -> Result<Self> {
+        let dir = PathBuf::from(dir);
+        fs::create_dir_all(&dir).map_err(|e| io_error("create", &dir, e))?;
+        let index_path = dir.join(INDEX_FILE);
+        let index: serde_json::Value = match fs::read_to_string(&index_path) {
+            Ok(text) => serde_json::from_str(&text).map_err(|_| {
+                Error::new(
+                    Status::GenericFailure,
+                    format!("Invalid queue index {}", index_path.display()),
+                )
+            })?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => json!({}),
+            Err(e) => return Err(io_error("read", &index_path, e)),
+        };
+        let segments = list_segments(&dir)?;
+        let committed_segment = index["segment"]
+            .as_u64()
+            .map_or(segments.first().copied().unwrap_or(1), |s| s as u32);
+        let mut next_id = index["id"].as_u64().unwrap_or(0);
+        let mut pending = BTreeMap::new();
+        let mut recovered_bytes = 0;
+        let mut write = (committed_segment, 0);
+        for &segment in segments.iter().filter(|s| **s >= committed_segment) {
+            let path = dir.join(segment_name(segment));
+            let bytes = fs::read(&path).map_err(|e| io_error("read", &path, e))?;
+            let start = if segment == committed_segment {
+                index["offset"].as_u64().unwrap_or(0)
+            } else {
+                0
+            };
+            let (records, valid) = scan_segment(&bytes, start.min(bytes.len() as u64));
+            for (offset, length) in records {
+                pending.insert(
+                    next_id,
+                    Position {
+                        segment,
+                        offset,
+                        length,
+                        attempts: 0,
+                    },
+                );
+                next_id += 1;
+            }
+            if valid < bytes.len() as u64 {
+                recovered_bytes += bytes.len() as u64 - valid;
+                OpenOptions::new()
+                    .write(true)
+                    .open(&path)
+                    .and_then(|file| file.set_len(valid).and_then(|_| file.sync_all()))
+                    .map_err(|e| io_error("repair", &path, e))?;
+            }
+            write = (segment, valid);
+        }
+        let options = options.unwrap_or(DiskQueueOptions {
+            max_segment_bytes: None,
+            fsync: None,
+            visibility_timeout_ms: None,
+        });
+        let queue = DiskQueue {
+            writer: open_segment(&dir, write.0)?,
+            dir,
+            max_segment_bytes: options
+                .max_segment_bytes
+                .unwrap_or(DEFAULT_MAX_SEGMENT_BYTES)
+                .max(1) as u64,
+            fsync: options.fsync.unwrap_or(true),
+            visibility_timeout_ms: options.visibility_timeout_ms.unwrap_or(0) as u128,
+            write_segment: write.0,
+            write_offset: write.1,
+            next_id,
+            pending,
+            in_flight: BTreeMap::new(),
+            recovered_bytes,
+        };
+        queue.commit()?;
+        Ok(queue)
+    }
+
+    /**
+     * Append records; returns their ids
+     *
+     * The records are written in one write and, unless fsync is off,
+     * flushed to disk before this returns.
+     */
+    #[napi]
+    pub fn enqueue(&mut self, records: Vec<Buffer>) -> Result<Vec<i64>> {
+        if self.write_offset >= self.max_segment_bytes {
+            self.write_segment += 1;
+            self.write_offset = 0;
+            self.writer = open_segment(&self.dir, self.write_segment)?;
+        }
+        let mut bytes =
+            Vec::with_capacity(records.iter().map(|r| r.len()).sum::<usize>() + records.len() * 8);
+        let mut ids = Vec::with_capacity(records.len());
+        for record in &records {
+            let length = u32::try_from(record.len())
+                .map_err(|_| Error::new(Status::InvalidArg, "Records are limited to 4 GiB"))?;
+            let position = Position {
+                segment: self.write_segment,
+                offset: self.write_offset + bytes.len() as u64,
+                length,
+                attempts: 0,
+            };
+            bytes.extend_from_slice(&length.to_le_bytes());
+            bytes.extend_from_slice(&crc32(record).to_le_bytes());
+            bytes.extend_from_slice(record);
+            ids.push((self.next_id + ids.len() as u64, position));
+        }
+        let path = self.segment_path(self.write_segment);
+        self.writer
+            .write_all(&bytes)
+            .and_then(|_| {
+                if self.fsync {
+                    self.writer.sync_data()
+                } else {
+                    Ok(())
+                }
+            })
+            .map_err(|e| io_error("append to", &path, e))?;
+        self.write_offset += bytes.len() as u64;
+        self.next_id += ids.len() as u64;
+        Ok(ids
+            .into_iter()
+            .map(|(id, position)| {
+                self.pending.insert(id, position);
+                id as i64
+            })
+            .collect())
+    }
+
+    /**
+     * Hand out up to `max` records, oldest first (default: 1)
+     *
+     * Each stays on disk until acknowledged. Until then it is in flight:
+     * not handed out again unless nacked, its visibility timeout passes,
+     * or the queue is reopened.
+     */
+    #[napi]
+    pub fn dequeue(&mut self, max: Option<u32>) -> Result<Vec<QueueItem>> {
+        self.expire();
+        let ids: Vec<u64> = self
+            .pending
+            .keys()
+            .take(max.unwrap_or(1) as usize)
+            .copied()
+            .collect();
+        let mut items = Vec::with_capacity(ids.len());
+        for id in ids {
+            let mut position = self.pending[&id];
+            let data = self.read(&position)?;
+            position.attempts += 1;
+            self.pending.remove(&id);
+            self.in_flight.insert(id, (position, Instant::now()));
+            items.push(QueueItem {
+                id: id as i64,
+                data: data.into(),
+                attempts: position.attempts,
+            });
+        }
+        Ok(items)
+    }
+
+    /**
+     * Confirm records are handled; returns how many were in flight
+     *
+     * The index only advances past the oldest unacknowledged record, so
+     * records acknowledged out of order can be handed out again after a
+     * crash. Delivery is at least once: make uploads idempotent by id.
+     */
+    #[napi]
+    pub fn ack(&mut self, ids: Vec<i64>) -> Result<u32> {
+        let acked = ids
+            .iter()
+            .filter(|id| self.in_flight.remove(&(**id as u64)).is_some())
+            .count();
+        if acked > 0 {
+            self.commit()?;
+        }
+        Ok(acked as u32)
+    }
+
+    /// Return in-flight records to the front of the queue; returns how many were in flight
+    #[napi]
+    pub fn nack(&mut self, ids: Vec<i64>) -> u32 {
+        let mut returned = 0;
+        for id in ids {
+            if let Some((position, _)) = self.in_flight.remove(&(id as u64)) {
+                self.pending.insert(id as u64, position);
+                returned += 1;
+            }
+        }
+        returned
+    }
+
+    /// Records not yet acknowledged, pending or in flight
+    #[napi(getter)]
+    pub fn length(&self) -> u32 {
+        (self.pending.len() + self.in_flight.len()) as u32
+    }
+
+    /// Pending and in-flight counts and disk usage
+    #[napi]
+    pub fn stats(&self) -> Result<QueueStats> {
+        let segments = list_segments(&self.dir)?;
+        let bytes = segments
+            .iter()
+            .filter_map(|segment| fs::metadata(self.segment_path(*segment)).ok())
+            .map(|metadata| metadata.len())
+            .sum::<u64>();
+        Ok(QueueStats {
+            pending: self.pending.len() as u32,
+            in_flight: self.in_flight.len() as u32,
+            segments: segments.len() as u32,
+            bytes: bytes as f64,
+            recovered_bytes: self.recovered_bytes as f64,
+        })
+    }
+}
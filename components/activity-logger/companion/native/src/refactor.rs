@@ -0,0 +1,214 @@
+/*!
+ * Refactor-aware churn
+ *
+ * A line that is deleted in one place and inserted verbatim elsewhere was
+ * moved, and a line that differs only by a consistent identifier swap was
+ * renamed. Counting these as refactor lines keeps refactoring days from
+ * looking like massive code production.
+ */
+
+use ahash::AHashMap;
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use similar::{ChangeTag, TextDiff};
+
+/// Distinct identifier substitutions a line may contain and still count as renamed
+const MAX_RENAMES_PER_LINE: usize = 2;
+
+/// Line churn split into real additions/removals and refactoring
+#[napi(object)]
+pub struct ChurnStats {
+    /// Raw inserted lines from the line diff
+    pub lines_added: i32,
+    /// Raw deleted lines from the line diff
+    pub lines_removed: i32,
+    pub moved_lines: i32,
+    pub renamed_lines: i32,
+    /// moved_lines + renamed_lines
+    pub refactor_lines: i32,
+    /// Inserted lines that are neither moves nor renames
+    pub net_lines_added: i32,
+    /// Deleted lines that are neither moves nor renames
+    pub net_lines_removed: i32,
+    /// True when every changed line is a move or rename
+    pub is_pure_refactor: bool,
+}
+
+/// Moved and renamed line-pair counts between deleted and inserted lines
+pub(crate) struct RefactorCounts {
+    pub moved: i32,
+    pub renamed: i32,
+}
+
+/**
+ * Calculate churn with moved and renamed lines reported separately
+ *
+ * @param text1 - Original text
+ * @param text2 - Modified text
+ * @returns ChurnStats where net_lines_* exclude refactoring
+ */
+#[napi]
+pub fn calculate_churn(text1: String, text2: String) -> Result<ChurnStats> {
+    let diff = TextDiff::from_lines(&text1, &text2);
+    let mut deleted = Vec::new();
+    let mut inserted = Vec::new();
+
+    for change in diff.iter_all_changes() {
+        match change.tag() {
+            ChangeTag::Delete => deleted.push(change.value()),
+            ChangeTag::Insert => inserted.push(change.value()),
+            ChangeTag::Equal => {}
+        }
+    }
+
+    let counts = count_refactor_lines(&deleted, &inserted);
+    let refactor_lines = counts.moved + counts.renamed;
+    let net_lines_added = inserted.len() as i32 - refactor_lines;
+    let net_lines_removed = deleted.len() as i32 - refactor_lines;
+
+    Ok(ChurnStats {
+        lines_added: inserted.len() as i32,
+        lines_removed: deleted.len() as i32,
+        moved_lines: counts.moved,
+        renamed_lines: counts.renamed,
+        refactor_lines,
+        net_lines_added,
+        net_lines_removed,
+        is_pure_refactor: refactor_lines > 0 && net_lines_added == 0 && net_lines_removed == 0,
+    })
+}
+
+/**
+ * Pair deleted lines with inserted lines that are moves or renames
+ *
+ * Blank lines never pair, since they carry no content.
+ */
+pub(crate) fn count_refactor_lines(deleted: &[&str], inserted: &[&str]) -> RefactorCounts {
+    // Moves: identical trimmed content on both sides
+    let mut deleted_by_content: AHashMap<&str, i32> = AHashMap::new();
+    for line in deleted.iter().map(|l| l.trim()).filter(|l| !l.is_empty()) {
+        *deleted_by_content.entry(line).or_insert(0) += 1;
+    }
+
+    let mut moved = 0;
+    let mut unmatched_inserted = Vec::new();
+    for line in inserted.iter().map(|l| l.trim()).filter(|l| !l.is_empty()) {
+        match deleted_by_content.get_mut(line) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                moved += 1;
+            }
+            _ => unmatched_inserted.push(line),
+        }
+    }
+
+    // Renames: same token shape, differing only in a few identifiers
+    let mut deleted_by_shape: AHashMap<String, Vec<Vec<Token>>> = AHashMap::new();
+    for line in deleted.iter().map(|l| l.trim()).filter(|l| !l.is_empty()) {
+        if let Some(count) = deleted_by_content.get_mut(line) {
+            if *count > 0 {
+                *count -= 1;
+                let tokens = tokenize(line);
+                deleted_by_shape
+                    .entry(shape(&tokens))
+                    .or_default()
+                    .push(tokens);
+            }
+        }
+    }
+
+    let mut renamed = 0;
+    for line in unmatched_inserted {
+        let tokens = tokenize(line);
+        let Some(candidates) = deleted_by_shape.get_mut(&shape(&tokens)) else {
+            continue;
+        };
+        if let Some(position) = candidates.iter().position(|old| is_rename(old, &tokens)) {
+            candidates.swap_remove(position);
+            renamed += 1;
+        }
+    }
+
+    RefactorCounts { moved, renamed }
+}
+
+#[derive(PartialEq)]
+enum TokenKind {
+    Identifier,
+    Other,
+}
+
+struct Token<'a> {
+    kind: TokenKind,
+    text: &'a str,
+}
+
+/// Split a line into identifier, number, and single-character punctuation tokens
+fn tokenize(line: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut chars = line.char_indices().peekable();
+
+    while let Some((start, c)) = chars.next() {
+        if c.is_whitespace() {
+            continue;
+        }
+        let is_word = |ch: char| ch.is_alphanumeric() || ch == '_' || ch == '$';
+        if is_word(c) {
+            let mut end = start + c.len_utf8();
+            while let Some(&(i, next)) = chars.peek() {
+                if !is_word(next) {
+                    break;
+                }
+                end = i + next.len_utf8();
+                chars.next();
+            }
+            let text = &line[start..end];
+            let kind = if c.is_ascii_digit() {
+                TokenKind::Other
+            } else {
+                TokenKind::Identifier
+            };
+            tokens.push(Token { kind, text });
+        } else {
+            tokens.push(Token {
+                kind: TokenKind::Other,
+                text: &line[start..start + c.len_utf8()],
+            });
+        }
+    }
+
+    tokens
+}
+
+/// Line shape with identifiers erased, used to bucket rename candidates
+fn shape(tokens: &[Token]) -> String {
+    tokens
+        .iter()
+        .map(|t| match t.kind {
+            TokenKind::Identifier => "\u{0}",
+            TokenKind::Other => t.text,
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Same shape, at least one shared identifier, and few distinct substitutions
+fn is_rename(old: &[Token], new: &[Token]) -> bool {
+    let mut substitutions: Vec<(&str, &str)> = Vec::new();
+    let mut shared = 0;
+
+    for (a, b) in old.iter().zip(new) {
+        if a.kind != TokenKind::Identifier {
+            continue;
+        }
+        if a.text == b.text {
+            shared += 1;
+        } else if !substitutions.contains(&(a.text, b.text)) {
+            substitutions.push((a.text, b.text));
+        }
+    }
+
+    !substitutions.is_empty()
+        && substitutions.len() <= MAX_RENAMES_PER_LINE
+        && (shared > 0 || old.len() == 1)
+}
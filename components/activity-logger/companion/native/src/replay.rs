@@ -0,0 +1,245 @@
+/*!
+ * Event replay pacing
+ * Streams historical events to the dashboard at an accelerated, bounded rate
+ */
+
+use crate::cancel::CancelFlag;
+use crate::events::ActivityEvent;
+use napi::bindgen_prelude::*;
+use napi::threadsafe_function::{
+    ErrorStrategy, ThreadSafeCallContext, ThreadsafeFunction, ThreadsafeFunctionCallMode,
+};
+use napi::{JsFunction, JsObject};
+use napi_derive::napi;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const DEFAULT_MAX_EVENTS_PER_SECOND: u32 = 500;
+const DEFAULT_MAX_GAP_MS: f64 = 2_000.0;
+const DEFAULT_FRAME_MS: u32 = 50;
+const DEFAULT_MAX_IN_FLIGHT: u32 = 2;
+/// Longest single sleep, so an abort is noticed promptly during long gaps
+const MAX_SLEEP: Duration = Duration::from_millis(100);
+
+/// Pacing limits for replay_at_speed
+#[napi(object)]
+pub struct ReplayOptions {
+    /// Upper bound on delivered events per second of wall time (default: 500)
+    pub max_events_per_second: Option<u32>,
+    /// Longest wall-clock pause between consecutive events after speedup, so idle
+    /// stretches don't stall the replay (default: 2000)
+    pub max_gap_ms: Option<f64>,
+    /// Events due within the same frame are delivered in one callback (default: 50)
+    pub frame_ms: Option<u32>,
+    /// Batches queued on the JS thread before the replay waits for the renderer (default: 2)
+    pub max_in_flight: Option<u32>,
+}
+
+/// Outcome of a completed replay
+#[napi(object)]
+pub struct ReplaySummary {
+    pub events: u32,
+    /// Number of callback invocations
+    pub batches: u32,
+    /// Time between the first and last event, in event time
+    pub span_ms: f64,
+    /// Wall time the replay took
+    pub duration_ms: f64,
+    /// Wall time spent waiting for the JS thread to drain queued batches
+    pub stalled_ms: f64,
+    /// Events delivered after their scheduled time because of the rate limit
+    pub rate_limited_events: u32,
+}
+
+/// Pacing limits with defaults applied
+struct Pacing {
+    speedup: f64,
+    max_gap_ms: f64,
+    frame: Duration,
+    frame_budget: usize,
+    max_in_flight: u32,
+}
+
+/// Batches handed to the JS thread but not yet passed to the callback
+#[derive(Default)]
+struct InFlight {
+    count: Mutex<u32>,
+    drained: Condvar,
+}
+
+impl InFlight {
+    fn finish(&self) {
+        let mut count = self.count.lock().unwrap();
+        *count = count.saturating_sub(1);
+        self.drained.notify_all();
+    }
+
+    /// Block until fewer than `limit` batches are queued; returns the time spent waiting
+    fn wait_below(&self, limit: u32, cancel: &CancelFlag) -> Result<Duration> {
+        let started = Instant::now();
+        let mut count = self.count.lock().unwrap();
+        while *count >= limit {
+            cancel.check()?;
+            count = self.drained.wait_timeout(count, MAX_SLEEP).unwrap().0;
+        }
+        *count += 1;
+        Ok(started.elapsed())
+    }
+}
+
+/**
+ * Replay historical events at an accelerated but bounded rate
+ *
+ * Events are sorted by timestamp and delivered to `callback` in batches,
+ * one batch per frame, with gaps shrunk by `speedup` and capped at
+ * `max_gap_ms`. At most `max_events_per_second` are delivered; events over
+ * the limit slip to later frames. When the renderer falls behind and
+ * `max_in_flight` batches are already queued on the JS thread, the replay
+ * clock pauses until it catches up, so a slow frame never turns into a burst.
+ *
+ * Pacing runs on its own thread. Aborting the signal stops the replay and
+ * rejects the Promise with an AbortError.
+ *
+ * @param events - Events to replay
+ * @param speedup - Event-time milliseconds per wall-clock millisecond, e.g. 60 for a minute per second
+ * @param callback - Called with each batch of events
+ * @returns Promise resolving to a summary once every event is delivered
+ */
+#[napi(
+    ts_args_type = "events: Array<ActivityEvent>, speedup: number, callback: (events: Array<ActivityEvent>) => void, options?: ReplayOptions | undefined | null, signal?: AbortSignal | undefined | null",
+    ts_return_type = "Promise<ReplaySummary>"
+)]
+pub fn replay_at_speed(
+    env: Env,
+    mut events: Vec<ActivityEvent>,
+    speedup: f64,
+    callback: JsFunction,
+    options: Option<ReplayOptions>,
+    signal: Option<JsObject>,
+) -> Result<JsObject> {
+    if !speedup.is_finite() || speedup <= 0.0 {
+        return Err(Error::new(
+            Status::InvalidArg,
+            "speedup must be a positive number".to_string(),
+        ));
+    }
+    let options = options.unwrap_or(ReplayOptions {
+        max_events_per_second: None,
+        max_gap_ms: None,
+        frame_ms: None,
+        max_in_flight: None,
+    });
+    let frame_ms = options.frame_ms.unwrap_or(DEFAULT_FRAME_MS).max(1);
+    let max_events_per_second = options
+        .max_events_per_second
+        .unwrap_or(DEFAULT_MAX_EVENTS_PER_SECOND)
+        .max(1);
+    let pacing = Pacing {
+        speedup,
+        max_gap_ms: options.max_gap_ms.unwrap_or(DEFAULT_MAX_GAP_MS).max(0.0),
+        frame: Duration::from_millis(frame_ms as u64),
+        frame_budget: ((max_events_per_second as u64 * frame_ms as u64) / 1000).max(1) as usize,
+        max_in_flight: options
+            .max_in_flight
+            .unwrap_or(DEFAULT_MAX_IN_FLIGHT)
+            .max(1),
+    };
+    let cancel = CancelFlag::from_signal(&env, signal)?;
+
+    // The JS-side callback marks a batch as delivered just before the renderer runs
+    let in_flight = Arc::new(InFlight::default());
+    let delivered = in_flight.clone();
+    let tsfn: ThreadsafeFunction<Vec<ActivityEvent>, ErrorStrategy::Fatal> = callback
+        .create_threadsafe_function(0, move |ctx: ThreadSafeCallContext<Vec<ActivityEvent>>| {
+            delivered.finish();
+            Ok(vec![ctx.value])
+        })?;
+
+    let (deferred, promise) = env.create_deferred()?;
+    events.sort_by_key(|e| e.timestamp);
+    thread::spawn(
+        move || match replay(events, &pacing, &tsfn, &in_flight, &cancel) {
+            Ok(summary) => deferred.resolve(move |_env| Ok(summary)),
+            Err(err) => deferred.reject(err),
+        },
+    );
+
+    Ok(promise)
+}
+
+fn replay(
+    events: Vec<ActivityEvent>,
+    pacing: &Pacing,
+    tsfn: &ThreadsafeFunction<Vec<ActivityEvent>, ErrorStrategy::Fatal>,
+    in_flight: &InFlight,
+    cancel: &CancelFlag,
+) -> Result<ReplaySummary> {
+    let started = Instant::now();
+    let total = events.len() as u32;
+    let frame_ms = pacing.frame.as_secs_f64() * 1000.0;
+    let span_ms = match (events.first(), events.last()) {
+        (Some(first), Some(last)) => (last.timestamp - first.timestamp) as f64,
+        _ => 0.0,
+    };
+
+    // Wall-clock offset (ms from start) at which each event is due
+    let mut schedule = Vec::with_capacity(events.len());
+    let mut due = 0.0;
+    for (i, event) in events.iter().enumerate() {
+        if i > 0 {
+            let gap = (event.timestamp - events[i - 1].timestamp).max(0) as f64 / pacing.speedup;
+            due += gap.min(pacing.max_gap_ms);
+        }
+        schedule.push(due);
+    }
+
+    let mut stalled = Duration::ZERO;
+    let mut batches = 0u32;
+    let mut rate_limited_events = 0u32;
+    let mut remaining = events.into_iter().zip(schedule).peekable();
+
+    while let Some((_, next_due)) = remaining.peek() {
+        cancel.check()?;
+        let now = (started.elapsed() - stalled).as_secs_f64() * 1000.0;
+        if *next_due > now {
+            let wait = Duration::from_secs_f64((next_due - now) / 1000.0);
+            thread::sleep(wait.min(MAX_SLEEP));
+            continue;
+        }
+
+        let frame_started = Instant::now();
+        let mut batch = Vec::new();
+        while batch.len() < pacing.frame_budget {
+            match remaining.next_if(|(_, due)| *due <= now) {
+                Some((event, due)) => {
+                    // Due before this frame could have picked it up: held back by earlier frames' budget
+                    if now - due > frame_ms {
+                        rate_limited_events += 1;
+                    }
+                    batch.push(event);
+                }
+                None => break,
+            }
+        }
+
+        stalled += in_flight.wait_below(pacing.max_in_flight, cancel)?;
+        tsfn.call(batch, ThreadsafeFunctionCallMode::NonBlocking);
+        batches += 1;
+
+        if remaining.peek().is_some() {
+            if let Some(rest) = pacing.frame.checked_sub(frame_started.elapsed()) {
+                thread::sleep(rest);
+            }
+        }
+    }
+
+    Ok(ReplaySummary {
+        events: total,
+        batches,
+        span_ms,
+        duration_ms: started.elapsed().as_secs_f64() * 1000.0,
+        stalled_ms: stalled.as_secs_f64() * 1000.0,
+        rate_limited_events,
+    })
+}
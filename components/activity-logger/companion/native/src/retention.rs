@@ -0,0 +1,222 @@
+/*!
+ * Retention
+ * How long events are kept in full, as rollups, or at all, applied a
+ * batch at a time to the event store and the JSONL log
+ */
+
+use crate::experiments::fnv1a;
+use crate::ingest::{details_object, event_field};
+use ahash::AHashMap;
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use serde_json::{json, Map, Value};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// `type` of the rollup records written into the JSONL log
+pub(crate) const AGGREGATE_TYPE: &str = "aggregate";
+const DEFAULT_BUCKET_MS: f64 = 60.0 * 60.0 * 1000.0;
+const DEFAULT_BATCH_SIZE: u32 = 10_000;
+
+/// How long events are kept, and in what detail
+#[napi(object)]
+pub struct RetentionPolicy {
+    /// Keep every event this long, e.g. 30 days
+    pub full_detail_ms: f64,
+    /// Keep rollups of older events until they are this old, e.g. 1 year; unset deletes them outright
+    pub aggregate_ms: Option<f64>,
+    /// Width of a rollup bucket (default: 1 hour)
+    pub bucket_ms: Option<f64>,
+    /// Most events handled per call (default: 10000)
+    pub batch_size: Option<u32>,
+    /// The time the cutoffs are measured back from, in epoch milliseconds (default: now)
+    pub now: Option<f64>,
+}
+
+/// What one retention call did, and whether more is left
+#[napi(object)]
+#[derive(Default)]
+pub struct RetentionProgress {
+    /// Nothing past the full-detail cutoff is left to process
+    pub done: bool,
+    /// Events examined in this call
+    pub processed: u32,
+    /// Events folded into rollups
+    pub aggregated: u32,
+    /// Events deleted without a rollup, being past aggregate_ms
+    pub deleted: u32,
+    /// Rollups written or added to
+    pub buckets: u32,
+    /// Rollups deleted for being past aggregate_ms
+    pub aggregates_deleted: u32,
+    /// Work left: expired events in the store, segments not yet examined in the log
+    pub remaining: u32,
+}
+
+/// Events of one type, workspace, and file within one bucket
+#[napi(object)]
+#[derive(Clone)]
+pub struct EventAggregate {
+    /// Start of the bucket, in epoch milliseconds
+    pub bucket_start: i64,
+    #[napi(js_name = "type")]
+    pub event_type: String,
+    pub workspace_path: Option<String>,
+    pub file_path: Option<String>,
+    pub count: u32,
+    pub lines_added: i64,
+    pub lines_removed: i64,
+    pub first_seen: i64,
+    pub last_seen: i64,
+}
+
+/// What happens to an event under a policy
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum Fate {
+    Keep,
+    Aggregate,
+    Delete,
+}
+
+/// A policy resolved to absolute times
+pub(crate) struct Cutoffs {
+    /// Events at or after this are kept in full
+    pub detail: f64,
+    /// Events between this and `detail` are rolled up, earlier ones deleted
+    pub aggregate: f64,
+    pub bucket_ms: f64,
+    pub batch_size: usize,
+}
+
+fn now_ms() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0.0, |d| d.as_millis() as f64)
+}
+
+impl Cutoffs {
+    pub(crate) fn of(policy: &RetentionPolicy) -> Result<Self> {
+        let invalid = |message: String| Error::new(Status::InvalidArg, message);
+        let valid = |ms: f64| ms.is_finite() && ms >= 0.0;
+        if !valid(policy.full_detail_ms) {
+            return Err(invalid(format!(
+                "full_detail_ms {} must be a non-negative number",
+                policy.full_detail_ms
+            )));
+        }
+        if let Some(aggregate_ms) = policy.aggregate_ms {
+            if !valid(aggregate_ms) || aggregate_ms < policy.full_detail_ms {
+                return Err(invalid(format!(
+                    "aggregate_ms {} must be at least full_detail_ms",
+                    aggregate_ms
+                )));
+            }
+        }
+        let bucket_ms = policy.bucket_ms.unwrap_or(DEFAULT_BUCKET_MS);
+        if !valid(bucket_ms) || bucket_ms < 1.0 {
+            return Err(invalid(format!("bucket_ms {} must be positive", bucket_ms)));
+        }
+        let now = policy.now.unwrap_or_else(now_ms);
+        let detail = now - policy.full_detail_ms;
+        Ok(Cutoffs {
+            detail,
+            aggregate: policy.aggregate_ms.map_or(detail, |ms| now - ms),
+            bucket_ms,
+            batch_size: policy.batch_size.unwrap_or(DEFAULT_BATCH_SIZE).max(1) as usize,
+        })
+    }
+
+    pub(crate) fn fate(&self, timestamp: f64) -> Fate {
+        if timestamp >= self.detail {
+            Fate::Keep
+        } else if timestamp >= self.aggregate {
+            Fate::Aggregate
+        } else {
+            Fate::Delete
+        }
+    }
+}
+
+/// Rollups being built from a batch of events
+#[derive(Default)]
+pub(crate) struct Rollup {
+    groups: AHashMap<(i64, String, String, String), EventAggregate>,
+}
+
+impl Rollup {
+    pub(crate) fn add(&mut self, event: &Map<String, Value>, timestamp: f64, bucket_ms: f64) {
+        let details = details_object(event);
+        let text = |keys: &[&str]| match event_field(event, details.as_ref(), keys) {
+            Some(Value::String(text)) => text,
+            _ => String::new(),
+        };
+        let count = |keys: &[&str]| {
+            event_field(event, details.as_ref(), keys)
+                .and_then(|value| value.as_i64())
+                .unwrap_or(0)
+        };
+        let bucket_start = ((timestamp / bucket_ms).floor() * bucket_ms) as i64;
+        let event_type = text(&["type"]);
+        let workspace_path = text(&["workspace_path", "workspacePath"]);
+        let file_path = text(&["file_path", "filePath", "file"]);
+        let timestamp = timestamp.round() as i64;
+        let aggregate = self
+            .groups
+            .entry((
+                bucket_start,
+                event_type.clone(),
+                workspace_path.clone(),
+                file_path.clone(),
+            ))
+            .or_insert_with(|| EventAggregate {
+                bucket_start,
+                event_type,
+                workspace_path: Some(workspace_path).filter(|path| !path.is_empty()),
+                file_path: Some(file_path).filter(|path| !path.is_empty()),
+                count: 0,
+                lines_added: 0,
+                lines_removed: 0,
+                first_seen: timestamp,
+                last_seen: timestamp,
+            });
+        aggregate.count += 1;
+        aggregate.lines_added += count(&["lines_added", "linesAdded"]);
+        aggregate.lines_removed += count(&["lines_removed", "linesRemoved"]);
+        aggregate.first_seen = aggregate.first_seen.min(timestamp);
+        aggregate.last_seen = aggregate.last_seen.max(timestamp);
+    }
+
+    /// The rollups, ordered by bucket, type, workspace, and file
+    pub(crate) fn into_aggregates(self) -> Vec<EventAggregate> {
+        let mut groups: Vec<_> = self.groups.into_iter().collect();
+        groups.sort_by(|a, b| a.0.cmp(&b.0));
+        groups.into_iter().map(|(_, aggregate)| aggregate).collect()
+    }
+}
+
+/// A rollup as a JSONL record, timestamped at the start of its bucket
+pub(crate) fn aggregate_record(aggregate: &EventAggregate, bucket_ms: f64) -> Value {
+    json!({
+        "id": format!(
+            "{}-{}-{:016x}-{}",
+            AGGREGATE_TYPE,
+            aggregate.bucket_start,
+            fnv1a(&[
+                &aggregate.event_type,
+                aggregate.workspace_path.as_deref().unwrap_or_default(),
+                aggregate.file_path.as_deref().unwrap_or_default(),
+            ]),
+            aggregate.first_seen
+        ),
+        "type": AGGREGATE_TYPE,
+        "timestamp": aggregate.bucket_start,
+        "bucketMs": bucket_ms,
+        "eventType": aggregate.event_type,
+        "workspacePath": aggregate.workspace_path,
+        "filePath": aggregate.file_path,
+        "count": aggregate.count,
+        "linesAdded": aggregate.lines_added,
+        "linesRemoved": aggregate.lines_removed,
+        "firstSeen": aggregate.first_seen,
+        "lastSeen": aggregate.last_seen,
+    })
+}
@@ -0,0 +1,423 @@
+/*!
+ * Shared-memory ring buffer
+ * A single-producer, single-consumer queue of byte records in a
+ * memory-mapped file, so the extension host can hand events to the
+ * companion without a round trip through JSON IPC
+ */
+
+use memmap2::MmapMut;
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use std::fs::OpenOptions;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Leading bytes of a ring file, bumped when the layout changes
+const MAGIC: &[u8; 8] = b"CTRING01";
+/// Header offsets; head and tail sit on their own cache lines so the two
+/// processes don't contend for one
+const CAPACITY_OFFSET: usize = 8;
+const HEAD_OFFSET: usize = 64;
+const TAIL_OFFSET: usize = 128;
+const DROPPED_OFFSET: usize = 192;
+const DATA_OFFSET: usize = 256;
+/// Length prefix of a record that means "the rest of the buffer is padding"
+const WRAP_MARKER: u32 = u32::MAX;
+const MIN_CAPACITY: u32 = 1024;
+
+/// The mapped file and a pointer into it that both handles write through
+struct Mapping {
+    _map: MmapMut,
+    base: *mut u8,
+    capacity: u64,
+    path: String,
+}
+
+// The pointer stays valid for as long as `_map` lives, and every access to
+// shared state goes through atomics or the head/tail protocol below
+unsafe impl Send for Mapping {}
+unsafe impl Sync for Mapping {}
+
+impl Mapping {
+    fn atomic(&self, offset: usize) -> &AtomicU64 {
+        // SAFETY: offset is one of the 8-byte-aligned header words, inside the page-aligned map
+        unsafe { &*(self.base.add(offset) as *const AtomicU64) }
+    }
+
+    fn head(&self) -> &AtomicU64 {
+        self.atomic(HEAD_OFFSET)
+    }
+
+    fn tail(&self) -> &AtomicU64 {
+        self.atomic(TAIL_OFFSET)
+    }
+
+    fn data(&self, position: u64) -> *mut u8 {
+        // SAFETY: position is reduced modulo capacity, so it stays inside the data region
+        unsafe {
+            self.base
+                .add(DATA_OFFSET + (position % self.capacity) as usize)
+        }
+    }
+
+    fn read_u32(&self, position: u64) -> u32 {
+        let mut bytes = [0u8; 4];
+        // SAFETY: records are 8-byte aligned and capacity is a multiple of 8, so all 4 bytes are in range
+        unsafe { std::ptr::copy_nonoverlapping(self.data(position), bytes.as_mut_ptr(), 4) };
+        u32::from_le_bytes(bytes)
+    }
+
+    fn write_u32(&self, position: u64, value: u32) {
+        // SAFETY: as in read_u32
+        unsafe {
+            std::ptr::copy_nonoverlapping(value.to_le_bytes().as_ptr(), self.data(position), 4)
+        };
+    }
+
+    fn corrupt(&self, detail: String) -> Error {
+        Error::new(
+            Status::GenericFailure,
+            format!("Ring file {} is corrupt: {}", self.path, detail),
+        )
+    }
+
+    /// Head and tail from the shared header, checked to be record boundaries
+    /// no more than a ring apart
+    fn positions(&self, head_order: Ordering, tail_order: Ordering) -> Result<(u64, u64)> {
+        let head = self.head().load(head_order);
+        let tail = self.tail().load(tail_order);
+        if head < tail || head - tail > self.capacity {
+            return Err(self.corrupt(format!("head {} and tail {} are out of range", head, tail)));
+        }
+        if !head.is_multiple_of(8) {
+            return Err(self.corrupt(format!("head {} is not a record boundary", head)));
+        }
+        Ok((head, tail))
+    }
+}
+
+fn io_error(action: &str, path: &str, error: std::io::Error) -> Error {
+    Error::new(
+        Status::GenericFailure,
+        format!("Failed to {} {}: {}", action, path, error),
+    )
+}
+
+/// Bytes a record takes: length prefix plus payload, padded to 8
+fn record_size(length: usize) -> u64 {
+    ((4 + length as u64) + 7) & !7
+}
+
+/// Map the ring file at `path`, or with `create`, a new file of that capacity
+/// that must not exist yet
+fn map(path: &str, create: Option<u64>) -> Result<MmapMut> {
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create_new(create.is_some())
+        .open(path)
+        .map_err(|e| io_error("open", path, e))?;
+    if let Some(capacity) = create {
+        file.set_len(DATA_OFFSET as u64 + capacity)
+            .map_err(|e| io_error("size", path, e))?;
+    }
+    // SAFETY: the file is only ever modified through a mapping that follows
+    // the ring protocol; truncating it from outside would be a caller bug
+    unsafe { MmapMut::map_mut(&file) }.map_err(|e| io_error("map", path, e))
+}
+
+/// Usage of a ring, read from its shared header
+#[napi(object)]
+pub struct RingStats {
+    /// Bytes available for records
+    pub capacity: u32,
+    /// Bytes currently holding unread records, including padding
+    pub used: u32,
+    /// Records the producer couldn't fit and discarded
+    pub dropped: f64,
+}
+
+/// A memory-mapped ring file, shared by one producer and one consumer process
+#[napi]
+pub struct SharedRing {
+    mapping: Arc<Mapping>,
+}
+
+/// Writing end of a SharedRing
+#[napi]
+pub struct RingProducer {
+    mapping: Arc<Mapping>,
+}
+
+/// Reading end of a SharedRing
+#[napi]
+pub struct RingConsumer {
+    mapping: Arc<Mapping>,
+}
+
+fn stats_of(mapping: &Mapping) -> RingStats {
+    let head = mapping.head().load(Ordering::Acquire);
+    let tail = mapping.tail().load(Ordering::Acquire);
+    RingStats {
+        capacity: mapping.capacity as u32,
+        used: head.saturating_sub(tail) as u32,
+        dropped: mapping.atomic(DROPPED_OFFSET).load(Ordering::Relaxed) as f64,
+    }
+}
+
+#[napi]
+impl SharedRing {
+    /**
+     * Create (or replace) a ring file at `path` with room for `capacity` bytes of records
+     *
+     * Each record takes its length plus 4 bytes, rounded up to a multiple
+     * of 8. Put the file on a RAM-backed filesystem (/dev/shm on Linux)
+     * to keep it off disk.
+     *
+     * The new ring is built in a temporary file and renamed over `path`,
+     * so a process still attached to a ring it replaces keeps a valid
+     * mapping of the old file; it has to open the new one to see it.
+     */
+    #[napi(factory)]
+    pub fn create(path: String, capacity: u32) -> Result<Self> {
+        if capacity < MIN_CAPACITY {
+            return Err(Error::new(
+                Status::InvalidArg,
+                format!("capacity must be at least {} bytes", MIN_CAPACITY),
+            ));
+        }
+        let capacity = (capacity as u64 + 7) & !7;
+        let temp = format!("{}.{}.tmp", path, std::process::id());
+        // Left behind by an earlier create from this process that failed midway
+        let _ = std::fs::remove_file(&temp);
+        let built = map(&temp, Some(capacity)).and_then(|mut map| {
+            map[..8].copy_from_slice(MAGIC);
+            map[CAPACITY_OFFSET..CAPACITY_OFFSET + 8].copy_from_slice(&capacity.to_le_bytes());
+            map.flush().map_err(|e| io_error("write", &temp, e))?;
+            std::fs::rename(&temp, &path).map_err(|e| io_error("replace", &path, e))?;
+            Ok(map)
+        });
+        match built {
+            Ok(map) => Ok(Self::wrap(map, capacity, path)),
+            Err(error) => {
+                let _ = std::fs::remove_file(&temp);
+                Err(error)
+            }
+        }
+    }
+
+    /// Attach to a ring file made by create, typically from the other process
+    #[napi(factory)]
+    pub fn open(path: String) -> Result<Self> {
+        let map = map(&path, None)?;
+        let invalid = || Error::new(Status::InvalidArg, format!("Invalid ring file {}", path));
+        if map.len() < DATA_OFFSET || &map[..8] != MAGIC {
+            return Err(invalid());
+        }
+        let capacity = u64::from_le_bytes(
+            map[CAPACITY_OFFSET..CAPACITY_OFFSET + 8]
+                .try_into()
+                .unwrap(),
+        );
+        if capacity < MIN_CAPACITY as u64
+            || !capacity.is_multiple_of(8)
+            || map.len() as u64 != DATA_OFFSET as u64 + capacity
+        {
+            return Err(invalid());
+        }
+        Ok(Self::wrap(map, capacity, path))
+    }
+
+    fn wrap(mut map: MmapMut, capacity: u64, path: String) -> Self {
+        let base = map.as_mut_ptr();
+        SharedRing {
+            mapping: Arc::new(Mapping {
+                _map: map,
+                base,
+                capacity,
+                path,
+            }),
+        }
+    }
+
+    /// The writing end; use it from exactly one process at a time
+    #[napi]
+    pub fn producer(&self) -> RingProducer {
+        RingProducer {
+            mapping: self.mapping.clone(),
+        }
+    }
+
+    /// The reading end; use it from exactly one process at a time
+    #[napi]
+    pub fn consumer(&self) -> RingConsumer {
+        RingConsumer {
+            mapping: self.mapping.clone(),
+        }
+    }
+
+    /// Capacity, bytes in use, and records dropped so far
+    #[napi]
+    pub fn stats(&self) -> RingStats {
+        stats_of(&self.mapping)
+    }
+
+    /// The ring file
+    #[napi(getter)]
+    pub fn path(&self) -> String {
+        self.mapping.path.clone()
+    }
+}
+
+impl RingProducer {
+    /// Write one record if it fits; never blocks
+    fn push_bytes(&self, bytes: &[u8]) -> Result<bool> {
+        let mapping = &self.mapping;
+        let size = record_size(bytes.len());
+        if size > mapping.capacity {
+            return Err(Error::new(
+                Status::InvalidArg,
+                format!(
+                    "Record of {} bytes can never fit a ring of {} bytes",
+                    bytes.len(),
+                    mapping.capacity
+                ),
+            ));
+        }
+        // The header is shared with another process, so it is checked before any arithmetic on it
+        let (head, tail) = mapping.positions(Ordering::Relaxed, Ordering::Acquire)?;
+        let to_end = mapping.capacity - head % mapping.capacity;
+        // A record never straddles the end; the gap before it is skipped
+        let skip = if size > to_end { to_end } else { 0 };
+        if mapping.capacity - (head - tail) < skip + size {
+            mapping
+                .atomic(DROPPED_OFFSET)
+                .fetch_add(1, Ordering::Relaxed);
+            return Ok(false);
+        }
+        if skip > 0 {
+            mapping.write_u32(head, WRAP_MARKER);
+        }
+        let start = head + skip;
+        mapping.write_u32(start, bytes.len() as u32);
+        // SAFETY: the record fits between start and the end of the data region, in space the consumer has released
+        unsafe {
+            std::ptr::copy_nonoverlapping(bytes.as_ptr(), mapping.data(start + 4), bytes.len())
+        };
+        mapping.head().store(start + size, Ordering::Release);
+        Ok(true)
+    }
+}
+
+#[napi]
+impl RingProducer {
+    /// Append a record; returns false, counting it as dropped, when the ring is full. Throws if the ring file is corrupt
+    #[napi]
+    pub fn push(&self, data: Buffer) -> Result<bool> {
+        self.push_bytes(&data)
+    }
+
+    /// Append records in order until one doesn't fit; returns how many were written. Throws if the ring file is corrupt
+    #[napi]
+    pub fn push_batch(&self, records: Vec<Buffer>) -> Result<u32> {
+        let mut written = 0;
+        for record in &records {
+            if !self.push_bytes(record)? {
+                // Count the rest too: none of them will be delivered
+                self.mapping.atomic(DROPPED_OFFSET).fetch_add(
+                    (records.len() - written as usize - 1) as u64,
+                    Ordering::Relaxed,
+                );
+                break;
+            }
+            written += 1;
+        }
+        Ok(written)
+    }
+
+    /// Capacity, bytes in use, and records dropped so far
+    #[napi]
+    pub fn stats(&self) -> RingStats {
+        stats_of(&self.mapping)
+    }
+}
+
+impl RingConsumer {
+    /// The oldest unread record, after checking it lies within what the producer published
+    ///
+    /// Lengths and positions come from the shared file, which another
+    /// process (or a stale or foreign file) may have left inconsistent;
+    /// nothing is copied until they are known to stay inside the mapping.
+    fn pop_bytes(&self) -> Result<Option<Vec<u8>>> {
+        let mapping = &self.mapping;
+        let (head, mut tail) = mapping.positions(Ordering::Acquire, Ordering::Relaxed)?;
+        loop {
+            if tail == head {
+                return Ok(None);
+            }
+            if !tail.is_multiple_of(8) {
+                return Err(mapping.corrupt(format!("tail {} is not a record boundary", tail)));
+            }
+            let to_end = mapping.capacity - tail % mapping.capacity;
+            let length = mapping.read_u32(tail);
+            if length == WRAP_MARKER {
+                if tail + to_end > head {
+                    return Err(
+                        mapping.corrupt(format!("padding at {} runs past head {}", tail, head))
+                    );
+                }
+                tail += to_end;
+                continue;
+            }
+            let size = record_size(length as usize);
+            if 4 + length as u64 > to_end || tail + size > head {
+                return Err(mapping.corrupt(format!(
+                    "record of {} bytes at {} runs past the end of the ring or head {}",
+                    length, tail, head
+                )));
+            }
+            let mut bytes = vec![0u8; length as usize];
+            // SAFETY: checked above that the record lies between tail and head and
+            // doesn't cross the end of the data region; the producer published it
+            // before moving head past it
+            unsafe {
+                std::ptr::copy_nonoverlapping(
+                    mapping.data(tail + 4),
+                    bytes.as_mut_ptr(),
+                    bytes.len(),
+                )
+            };
+            mapping.tail().store(tail + size, Ordering::Release);
+            return Ok(Some(bytes));
+        }
+    }
+}
+
+#[napi]
+impl RingConsumer {
+    /// The oldest unread record, or null when the ring is empty; never blocks. Throws if the ring file is corrupt
+    #[napi]
+    pub fn pop(&self) -> Result<Option<Buffer>> {
+        Ok(self.pop_bytes()?.map(Buffer::from))
+    }
+
+    /// Up to `max` unread records, oldest first (default: all). Throws if the ring file is corrupt
+    #[napi]
+    pub fn drain(&self, max: Option<u32>) -> Result<Vec<Buffer>> {
+        let max = max.map_or(usize::MAX, |max| max as usize);
+        let mut records = Vec::new();
+        while records.len() < max {
+            match self.pop_bytes()? {
+                Some(bytes) => records.push(Buffer::from(bytes)),
+                None => break,
+            }
+        }
+        Ok(records)
+    }
+
+    /// Capacity, bytes in use, and records dropped so far
+    #[napi]
+    pub fn stats(&self) -> RingStats {
+        stats_of(&self.mapping)
+    }
+}
@@ -0,0 +1,595 @@
+/*!
+ * S3-compatible upload
+ * Session bundles and exports put into object storage (AWS S3, MinIO, R2,
+ * and other services speaking the S3 API), signed with SigV4, in parts
+ * when large
+ */
+
+use crate::sessions::civil_from_days;
+use hmac::{Hmac, Mac};
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// S3's smallest allowed part, except the last
+const MIN_PART_SIZE: u32 = 5 * 1024 * 1024;
+const DEFAULT_PART_SIZE: u32 = 8 * 1024 * 1024;
+/// S3's limit on parts per upload
+const MAX_PARTS: u64 = 10_000;
+const DEFAULT_REGION: &str = "us-east-1";
+const DEFAULT_TIMEOUT_MS: u32 = 60_000;
+const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+const RETRY_BACKOFF_MS: u64 = 250;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Where and how to upload; unset fields fall back to the usual environment variables
+#[napi(object)]
+#[derive(Default)]
+pub struct S3Options {
+    /// Service URL, e.g. "http://localhost:9000" for MinIO (default: $AWS_ENDPOINT_URL_S3, $AWS_ENDPOINT_URL, or $S3_ENDPOINT, else AWS in `region`)
+    pub endpoint: Option<String>,
+    /// (default: $S3_BUCKET)
+    pub bucket: Option<String>,
+    /// (default: $AWS_REGION, $AWS_DEFAULT_REGION, or "us-east-1")
+    pub region: Option<String>,
+    /// (default: $AWS_ACCESS_KEY_ID)
+    pub access_key_id: Option<String>,
+    /// (default: $AWS_SECRET_ACCESS_KEY)
+    pub secret_access_key: Option<String>,
+    /// Token for temporary credentials (default: $AWS_SESSION_TOKEN)
+    pub session_token: Option<String>,
+    /// Address the bucket in the path, endpoint/bucket/key, rather than as a subdomain (default: true with a custom endpoint, false for AWS)
+    pub path_style: Option<bool>,
+    /// Bytes per part of a multipart upload, at least 5 MiB; files up to this size go in one request (default: 8 MiB)
+    pub part_size: Option<u32>,
+    /// Content-Type stored with the object (default: "application/octet-stream")
+    pub content_type: Option<String>,
+    /// Tries per request, the first included; network errors, 429, and 5xx are retried (default: 3)
+    pub max_attempts: Option<u32>,
+    /// Per-request timeout (default: 60000)
+    pub timeout_ms: Option<u32>,
+}
+
+/// Result of upload_to_s3
+#[napi(object)]
+pub struct S3UploadResult {
+    pub bucket: String,
+    pub key: String,
+    /// URL of the object
+    pub url: String,
+    /// The object's ETag, without quotes
+    pub etag: String,
+    pub bytes: f64,
+    /// Parts uploaded; 1 for a single request
+    pub parts: u32,
+}
+
+fn s3_error(message: String) -> Error {
+    Error::new(Status::GenericFailure, message)
+}
+
+fn env(names: &[&str]) -> Option<String> {
+    names
+        .iter()
+        .find_map(|name| std::env::var(name).ok().filter(|value| !value.is_empty()))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    hex(&Sha256::digest(bytes))
+}
+
+fn hmac(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Percent-encode all but unreserved characters, and '/' when `keep_slash`, as SigV4 expects
+fn uri_encode(text: &str, keep_slash: bool) -> String {
+    let mut out = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                out.push(byte as char)
+            }
+            b'/' if keep_slash => out.push('/'),
+            _ => out.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    out
+}
+
+/// YYYYMMDD'T'HHMMSS'Z' for a time in epoch seconds
+fn amz_date(seconds: i64) -> String {
+    let (year, month, day) = civil_from_days(seconds.div_euclid(86_400));
+    let time = seconds.rem_euclid(86_400);
+    format!(
+        "{:04}{:02}{:02}T{:02}{:02}{:02}Z",
+        year,
+        month,
+        day,
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    )
+}
+
+/// Text of the first `<tag>` element in an S3 XML response
+fn xml_text(body: &str, tag: &str) -> Option<String> {
+    let start = body.find(&format!("<{}>", tag))? + tag.len() + 2;
+    let end = start + body[start..].find(&format!("</{}>", tag))?;
+    Some(
+        body[start..end]
+            .replace("&quot;", "\"")
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&amp;", "&"),
+    )
+}
+
+/// A bucket, resolved from options and environment, with the credentials to sign for it
+struct Bucket {
+    name: String,
+    region: String,
+    access_key_id: String,
+    secret_access_key: String,
+    session_token: Option<String>,
+    /// "http" or "https"
+    scheme: String,
+    /// Host and non-default port requests go to, as signed
+    host: String,
+    /// Path before the key: "/bucket" in path style, "" otherwise
+    prefix: String,
+    max_attempts: u32,
+    timeout_ms: u64,
+}
+
+impl Bucket {
+    fn of(options: &S3Options) -> Result<Self> {
+        let missing = |what: &str, variable: &str| {
+            Error::new(
+                Status::InvalidArg,
+                format!("No S3 {}: pass it in options or set {}", what, variable),
+            )
+        };
+        let name = options
+            .bucket
+            .clone()
+            .or_else(|| env(&["S3_BUCKET"]))
+            .ok_or_else(|| missing("bucket", "S3_BUCKET"))?;
+        let region = options
+            .region
+            .clone()
+            .or_else(|| env(&["AWS_REGION", "AWS_DEFAULT_REGION"]))
+            .unwrap_or_else(|| DEFAULT_REGION.to_string());
+        let access_key_id = options
+            .access_key_id
+            .clone()
+            .or_else(|| env(&["AWS_ACCESS_KEY_ID"]))
+            .ok_or_else(|| missing("access key", "AWS_ACCESS_KEY_ID"))?;
+        let secret_access_key = options
+            .secret_access_key
+            .clone()
+            .or_else(|| env(&["AWS_SECRET_ACCESS_KEY"]))
+            .ok_or_else(|| missing("secret key", "AWS_SECRET_ACCESS_KEY"))?;
+        let session_token = options
+            .session_token
+            .clone()
+            .or_else(|| env(&["AWS_SESSION_TOKEN"]));
+        let endpoint = options
+            .endpoint
+            .clone()
+            .or_else(|| env(&["AWS_ENDPOINT_URL_S3", "AWS_ENDPOINT_URL", "S3_ENDPOINT"]));
+        let path_style = options.path_style.unwrap_or(endpoint.is_some());
+        let endpoint = endpoint.unwrap_or_else(|| format!("https://s3.{}.amazonaws.com", region));
+        let (scheme, authority) = endpoint
+            .trim_end_matches('/')
+            .split_once("://")
+            .filter(|(scheme, authority)| {
+                matches!(*scheme, "http" | "https") && !authority.is_empty()
+            })
+            .map(|(scheme, authority)| (scheme.to_string(), authority.to_string()))
+            .ok_or_else(|| {
+                Error::new(
+                    Status::InvalidArg,
+                    format!("endpoint '{}' must be an http or https URL", endpoint),
+                )
+            })?;
+        if authority.contains('/') {
+            return Err(Error::new(
+                Status::InvalidArg,
+                format!("endpoint '{}' must not have a path", endpoint),
+            ));
+        }
+        // The Host header leaves out a default port, and the signature has to match it
+        let default_port = if scheme == "https" { ":443" } else { ":80" };
+        let host = authority
+            .strip_suffix(default_port)
+            .unwrap_or(&authority)
+            .to_string();
+        let (host, prefix) = if path_style {
+            (host, format!("/{}", uri_encode(&name, false)))
+        } else {
+            (format!("{}.{}", name, host), String::new())
+        };
+        Ok(Bucket {
+            name,
+            region,
+            access_key_id,
+            secret_access_key,
+            session_token,
+            scheme,
+            host,
+            prefix,
+            max_attempts: options.max_attempts.unwrap_or(DEFAULT_MAX_ATTEMPTS).max(1),
+            timeout_ms: options.timeout_ms.unwrap_or(DEFAULT_TIMEOUT_MS) as u64,
+        })
+    }
+
+    fn object_path(&self, key: &str) -> String {
+        format!("{}/{}", self.prefix, uri_encode(key, true))
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}://{}{}", self.scheme, self.host, path)
+    }
+
+    /// SigV4 headers for a request, Authorization included
+    fn sign(
+        &self,
+        method: &str,
+        path: &str,
+        query: &str,
+        payload_hash: &str,
+        seconds: i64,
+    ) -> Vec<(&'static str, String)> {
+        let date_time = amz_date(seconds);
+        let date = &date_time[..8];
+        let mut headers = vec![
+            ("host", self.host.clone()),
+            ("x-amz-content-sha256", payload_hash.to_string()),
+            ("x-amz-date", date_time.clone()),
+        ];
+        if let Some(token) = &self.session_token {
+            headers.push(("x-amz-security-token", token.clone()));
+        }
+        let canonical_headers: String = headers
+            .iter()
+            .map(|(name, value)| format!("{}:{}\n", name, value.trim()))
+            .collect();
+        let signed_headers = headers
+            .iter()
+            .map(|(name, _)| *name)
+            .collect::<Vec<_>>()
+            .join(";");
+        let canonical_request = format!(
+            "{}\n{}\n{}\n{}\n{}\n{}",
+            method, path, query, canonical_headers, signed_headers, payload_hash
+        );
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            date_time,
+            scope,
+            sha256_hex(canonical_request.as_bytes())
+        );
+        let key = [date, self.region.as_str(), "s3", "aws4_request"]
+            .iter()
+            .fold(
+                format!("AWS4{}", self.secret_access_key).into_bytes(),
+                |key, part| hmac(&key, part),
+            );
+        let signature = hex(&hmac(&key, &string_to_sign));
+        headers.retain(|(name, _)| *name != "host");
+        headers.push((
+            "authorization",
+            format!(
+                "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+                self.access_key_id, scope, signed_headers, signature
+            ),
+        ));
+        headers
+    }
+
+    /// A signed request, retried on network errors, 429, and 5xx; returns the response's ETag header and body
+    fn send(
+        &self,
+        agent: &ureq::Agent,
+        method: &str,
+        path: &str,
+        query: &[(&str, String)],
+        body: &[u8],
+        content_type: Option<&str>,
+    ) -> Result<(Option<String>, String)> {
+        let mut pairs: Vec<String> = query
+            .iter()
+            .map(|(name, value)| {
+                format!("{}={}", uri_encode(name, false), uri_encode(value, false))
+            })
+            .collect();
+        pairs.sort();
+        let query = pairs.join("&");
+        let url = if query.is_empty() {
+            self.url(path)
+        } else {
+            format!("{}?{}", self.url(path), query)
+        };
+        let payload_hash = sha256_hex(body);
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let seconds = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs() as i64);
+            let mut request = agent.request(method, &url);
+            for (name, value) in self.sign(method, path, &query, &payload_hash, seconds) {
+                request = request.set(name, &value);
+            }
+            if let Some(content_type) = content_type {
+                request = request.set("Content-Type", content_type);
+            }
+            let (message, retry) = match request.send_bytes(body) {
+                Ok(response) => {
+                    let etag = response
+                        .header("ETag")
+                        .map(|tag| tag.trim_matches('"').to_string());
+                    let text = response.into_string().map_err(|e| {
+                        s3_error(format!("Failed to read response from {}: {}", url, e))
+                    })?;
+                    // CompleteMultipartUpload can fail after a 200, with the error in the body
+                    if !text.contains("<Error>") {
+                        return Ok((etag, text));
+                    }
+                    (describe(&url, 200, &text), true)
+                }
+                Err(ureq::Error::Status(status, response)) => {
+                    let text = response.into_string().unwrap_or_default();
+                    (
+                        describe(&url, status, &text),
+                        status == 429 || status >= 500,
+                    )
+                }
+                Err(other) => (format!("Failed to reach {}: {}", url, other), true),
+            };
+            if !retry || attempt >= self.max_attempts {
+                return Err(s3_error(message));
+            }
+            std::thread::sleep(Duration::from_millis(
+                RETRY_BACKOFF_MS << (attempt - 1).min(8),
+            ));
+        }
+    }
+}
+
+/// An S3 error response as a message, e.g. "… returned 403 SignatureDoesNotMatch: …"
+fn describe(url: &str, status: u16, body: &str) -> String {
+    match (xml_text(body, "Code"), xml_text(body, "Message")) {
+        (Some(code), Some(message)) => {
+            format!("{} returned {} {}: {}", url, status, code, message)
+        }
+        (Some(code), None) => format!("{} returned {} {}", url, status, code),
+        _ => format!("{} returned {}", url, status),
+    }
+}
+
+pub struct S3UploadTask {
+    path: String,
+    key: String,
+    bucket: Bucket,
+    part_size: u32,
+    content_type: String,
+}
+
+impl S3UploadTask {
+    fn read_error(&self, error: std::io::Error) -> Error {
+        s3_error(format!("Failed to read {}: {}", self.path, error))
+    }
+
+    fn multipart(
+        &self,
+        agent: &ureq::Agent,
+        file: &mut File,
+        path: &str,
+        upload_id: &str,
+    ) -> Result<(String, u32)> {
+        let mut etags = Vec::new();
+        let mut part = Vec::with_capacity(self.part_size as usize);
+        loop {
+            part.clear();
+            file.by_ref()
+                .take(self.part_size as u64)
+                .read_to_end(&mut part)
+                .map_err(|e| self.read_error(e))?;
+            if part.is_empty() {
+                break;
+            }
+            let number = etags.len() + 1;
+            let (etag, _) = self.bucket.send(
+                agent,
+                "PUT",
+                path,
+                &[
+                    ("partNumber", number.to_string()),
+                    ("uploadId", upload_id.to_string()),
+                ],
+                &part,
+                None,
+            )?;
+            etags.push(etag.ok_or_else(|| {
+                s3_error(format!(
+                    "Part {} of {} came back without an ETag",
+                    number, self.key
+                ))
+            })?);
+            if part.len() < self.part_size as usize {
+                break;
+            }
+        }
+        let manifest: String = etags
+            .iter()
+            .enumerate()
+            .map(|(i, etag)| {
+                format!(
+                    "<Part><PartNumber>{}</PartNumber><ETag>\"{}\"</ETag></Part>",
+                    i + 1,
+                    etag
+                )
+            })
+            .collect();
+        let (_, body) = self.bucket.send(
+            agent,
+            "POST",
+            path,
+            &[("uploadId", upload_id.to_string())],
+            format!(
+                "<CompleteMultipartUpload>{}</CompleteMultipartUpload>",
+                manifest
+            )
+            .as_bytes(),
+            Some("application/xml"),
+        )?;
+        let etag = xml_text(&body, "ETag")
+            .map(|etag| etag.trim_matches('"').to_string())
+            .unwrap_or_default();
+        Ok((etag, etags.len() as u32))
+    }
+}
+
+impl Task for S3UploadTask {
+    type Output = S3UploadResult;
+    type JsValue = S3UploadResult;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        let mut file = File::open(&self.path).map_err(|e| self.read_error(e))?;
+        let bytes = file.metadata().map_err(|e| self.read_error(e))?.len();
+        if bytes > self.part_size as u64 * MAX_PARTS {
+            return Err(Error::new(
+                Status::InvalidArg,
+                format!(
+                    "{} is {} bytes, more than {} parts of {}; raise part_size",
+                    self.path, bytes, MAX_PARTS, self.part_size
+                ),
+            ));
+        }
+        let agent = ureq::AgentBuilder::new()
+            .timeout(Duration::from_millis(self.bucket.timeout_ms))
+            .build();
+        let path = self.bucket.object_path(&self.key);
+        let content_type = Some(self.content_type.as_str());
+        let (etag, parts) = if bytes <= self.part_size as u64 {
+            let mut body = Vec::with_capacity(bytes as usize);
+            file.read_to_end(&mut body)
+                .map_err(|e| self.read_error(e))?;
+            let (etag, _) = self
+                .bucket
+                .send(&agent, "PUT", &path, &[], &body, content_type)?;
+            (etag.unwrap_or_default(), 1)
+        } else {
+            let (_, body) = self.bucket.send(
+                &agent,
+                "POST",
+                &path,
+                &[("uploads", String::new())],
+                &[],
+                content_type,
+            )?;
+            let upload_id = xml_text(&body, "UploadId").ok_or_else(|| {
+                s3_error(format!(
+                    "Multipart upload of {} started without an UploadId",
+                    self.key
+                ))
+            })?;
+            match self.multipart(&agent, &mut file, &path, &upload_id) {
+                Ok(done) => done,
+                Err(e) => {
+                    // Best effort: parts of an abandoned upload are billed until aborted
+                    let _ = self.bucket.send(
+                        &agent,
+                        "DELETE",
+                        &path,
+                        &[("uploadId", upload_id)],
+                        &[],
+                        None,
+                    );
+                    return Err(e);
+                }
+            }
+        };
+        Ok(S3UploadResult {
+            bucket: self.bucket.name.clone(),
+            key: self.key.clone(),
+            url: self.bucket.url(&path),
+            etag,
+            bytes: bytes as f64,
+            parts,
+        })
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(output)
+    }
+}
+
+/**
+ * Upload a file to S3-compatible object storage
+ *
+ * Requests are signed with AWS Signature Version 4, so the same call
+ * works against AWS S3, MinIO, Cloudflare R2, and other services speaking
+ * the S3 API. Files larger than part_size go up as a multipart upload,
+ * read a part at a time, and an upload that fails partway is aborted so
+ * its parts don't linger. Runs off the main thread.
+ *
+ * @param path - File to upload, e.g. a session bundle or a Parquet export
+ * @param key - Object key, e.g. "sessions/2024-05-01/abc.parquet"
+ * @param options - Endpoint, bucket, and credentials, each defaulting to the environment
+ * @returns The object's bucket, key, URL, ETag, size, and part count
+ */
+#[napi(ts_return_type = "Promise<S3UploadResult>")]
+pub fn upload_to_s3(
+    path: String,
+    key: String,
+    options: Option<S3Options>,
+) -> Result<AsyncTask<S3UploadTask>> {
+    let options = options.unwrap_or_default();
+    let key = key.trim_start_matches('/').to_string();
+    if key.is_empty() {
+        return Err(Error::new(
+            Status::InvalidArg,
+            "Object key must not be empty",
+        ));
+    }
+    let part_size = options.part_size.unwrap_or(DEFAULT_PART_SIZE);
+    if part_size < MIN_PART_SIZE {
+        return Err(Error::new(
+            Status::InvalidArg,
+            format!(
+                "part_size {} is below S3's minimum of {} bytes",
+                part_size, MIN_PART_SIZE
+            ),
+        ));
+    }
+    if !Path::new(&path).is_file() {
+        return Err(Error::new(
+            Status::InvalidArg,
+            format!("{} is not a file", path),
+        ));
+    }
+    Ok(AsyncTask::new(S3UploadTask {
+        bucket: Bucket::of(&options)?,
+        content_type: options
+            .content_type
+            .unwrap_or_else(|| "application/octet-stream".to_string()),
+        path,
+        key,
+        part_size,
+    }))
+}
@@ -0,0 +1,396 @@
+/*!
+ * Event sampling, deduplication, and rate limiting
+ * Per-type rules that drop repeats, keep a seeded, reproducible fraction
+ * of events, and cap bursts per file or session, applied before events
+ * are stored
+ */
+
+use crate::experiments::fnv1a;
+use crate::ingest::{details_object, event_field, parse_timestamp};
+use crate::schema::camel;
+use ahash::AHashMap;
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use serde_json::Value;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Rule for any type without one of its own
+const WILDCARD: &str = "*";
+/// Rate-limit buckets or dedup hashes kept before stale ones are forgotten
+const MAX_BUCKETS: usize = 10_000;
+const DEFAULT_DEDUPE_WINDOW_MS: f64 = 10_000.0;
+
+/// How one event type is thinned out
+#[napi(object)]
+pub struct SamplingRule {
+    /// Event type the rule applies to, or "*" for every type without its own rule
+    #[napi(js_name = "type")]
+    pub event_type: String,
+    /// Fraction of events to keep, 0 to 1 (default: 1)
+    pub rate: Option<f64>,
+    /// Keep one event in this many; shorthand for rate = 1 / keep_one_in
+    pub keep_one_in: Option<u32>,
+    /// Most events kept per second, per value of `per`
+    pub max_per_second: Option<f64>,
+    /// Events allowed at once before max_per_second applies (default: max_per_second, at least 1)
+    pub burst: Option<f64>,
+    /// Field the rate limit is counted by, e.g. "file_path"; unset counts the whole type together
+    pub per: Option<String>,
+    /// Fields whose values make two events duplicates, e.g. ["file_path", "diff"]
+    pub dedupe_fields: Option<Vec<String>>,
+    /// How long after an event its duplicates are dropped; each repeat extends it (default: 10000)
+    pub dedupe_window_ms: Option<f64>,
+}
+
+/// Options for a Sampler
+#[napi(object)]
+pub struct SamplerOptions {
+    /// Changes which events a rate keeps; the same seed keeps the same events (default: "")
+    pub seed: Option<String>,
+}
+
+/// Result of Sampler.filter
+#[napi(object)]
+pub struct SampleResult {
+    /// Events that passed, in input order
+    #[napi(ts_type = "Array<Record<string, any>>")]
+    pub kept: Vec<Value>,
+    /// Events dropped by a rule's rate
+    pub sampled_out: u32,
+    /// Events dropped by a rule's max_per_second
+    pub rate_limited: u32,
+    /// Events dropped as repeats within a rule's dedupe window
+    pub duplicates: u32,
+}
+
+/// Running counts for one event type
+#[napi(object)]
+pub struct SamplerTypeStats {
+    #[napi(js_name = "type")]
+    pub event_type: String,
+    pub seen: f64,
+    pub kept: f64,
+    pub sampled_out: f64,
+    pub rate_limited: f64,
+    pub duplicates: f64,
+}
+
+/// Why an event was dropped, if it was
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum Decision {
+    Keep,
+    SampledOut,
+    RateLimited,
+    Duplicate,
+}
+
+struct Rule {
+    rate: f64,
+    /// Tokens per millisecond and bucket size, when rate limited
+    limit: Option<(f64, f64)>,
+    per: Option<String>,
+    /// Fields to hash and the window, when deduplicating
+    dedupe: Option<(Vec<String>, f64)>,
+}
+
+struct Bucket {
+    tokens: f64,
+    last_ms: f64,
+}
+
+#[derive(Default)]
+struct Counts {
+    seen: u64,
+    kept: u64,
+    sampled_out: u64,
+    rate_limited: u64,
+    duplicates: u64,
+}
+
+/// Per-type sampling and rate limiting
+#[napi]
+pub struct Sampler {
+    rules: AHashMap<String, Rule>,
+    seed: String,
+    /// Token bucket per (type, per-field value)
+    buckets: AHashMap<(String, String), Bucket>,
+    /// When each (type, content hash) was last seen
+    recent: AHashMap<(String, u64), f64>,
+    counts: AHashMap<String, Counts>,
+}
+
+fn now_ms() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0.0, |d| d.as_millis() as f64)
+}
+
+/// A field as a string key, from the top level or `details`, snake_case or camelCase
+fn field_key(event: &serde_json::Map<String, Value>, field: &str) -> String {
+    let details = details_object(event);
+    let camel_field = camel(field);
+    match event_field(event, details.as_ref(), &[field, camel_field.as_str()]) {
+        Some(Value::String(text)) => text,
+        Some(Value::Null) | None => String::new(),
+        Some(other) => other.to_string(),
+    }
+}
+
+/// Stable hash of the named fields, telling a missing field from an empty one
+fn content_hash(event: &serde_json::Map<String, Value>, names: &[String]) -> u64 {
+    let details = details_object(event);
+    let values: Vec<String> = names
+        .iter()
+        .map(|name| {
+            let camel_name = camel(name);
+            event_field(
+                event,
+                details.as_ref(),
+                &[name.as_str(), camel_name.as_str()],
+            )
+            .map(|value| value.to_string())
+            .unwrap_or_default()
+        })
+        .collect();
+    let parts: Vec<&str> = values.iter().map(String::as_str).collect();
+    fnv1a(&parts)
+}
+
+fn compile(rule: &SamplingRule) -> Result<Rule> {
+    let invalid = |message: String| {
+        Error::new(
+            Status::InvalidArg,
+            format!("Sampling rule for '{}': {}", rule.event_type, message),
+        )
+    };
+    let rate = match (rule.rate, rule.keep_one_in) {
+        (Some(_), Some(_)) => return Err(invalid("set rate or keep_one_in, not both".into())),
+        (Some(rate), None) if !(0.0..=1.0).contains(&rate) => {
+            return Err(invalid(format!("rate {} is outside 0..1", rate)))
+        }
+        (Some(rate), None) => rate,
+        (None, Some(0)) => return Err(invalid("keep_one_in must be positive".into())),
+        (None, Some(n)) => 1.0 / n as f64,
+        (None, None) => 1.0,
+    };
+    let limit = match rule.max_per_second {
+        Some(per_second) if per_second.is_nan() || per_second <= 0.0 => {
+            return Err(invalid(format!(
+                "max_per_second {} must be positive",
+                per_second
+            )))
+        }
+        Some(per_second) => Some((
+            per_second / 1000.0,
+            rule.burst.unwrap_or(per_second).max(1.0),
+        )),
+        None => None,
+    };
+    let dedupe = match (&rule.dedupe_fields, rule.dedupe_window_ms) {
+        (None, Some(_)) => return Err(invalid("dedupe_window_ms needs dedupe_fields".into())),
+        (Some(fields), _) if fields.is_empty() => {
+            return Err(invalid("dedupe_fields must not be empty".into()))
+        }
+        (Some(_), Some(window)) if window.is_nan() || window <= 0.0 => {
+            return Err(invalid(format!(
+                "dedupe_window_ms {} must be positive",
+                window
+            )))
+        }
+        (Some(fields), window) => {
+            Some((fields.clone(), window.unwrap_or(DEFAULT_DEDUPE_WINDOW_MS)))
+        }
+        (None, None) => None,
+    };
+    Ok(Rule {
+        rate,
+        limit,
+        per: rule.per.clone(),
+        dedupe,
+    })
+}
+
+impl Sampler {
+    /// Decide one event and update the counts and buckets
+    pub(crate) fn decide(&mut self, event: &Value) -> Decision {
+        let Value::Object(fields) = event else {
+            return Decision::Keep;
+        };
+        let event_type = fields
+            .get("type")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string();
+        let decision = match self
+            .rules
+            .get(&event_type)
+            .or_else(|| self.rules.get(WILDCARD))
+        {
+            None => Decision::Keep,
+            Some(rule) => {
+                // Event time rather than wall time, so replays dedupe and limit the same way
+                let at = fields
+                    .get("timestamp")
+                    .and_then(parse_timestamp)
+                    .unwrap_or_else(now_ms);
+                let id = match fields.get("id") {
+                    Some(Value::String(id)) => id.clone(),
+                    Some(Value::Number(id)) => id.to_string(),
+                    _ => event.to_string(),
+                };
+                let duplicate = rule.dedupe.as_ref().is_some_and(|(names, window)| {
+                    if self.recent.len() >= MAX_BUCKETS {
+                        // Each entry expires after its own type's window, not this rule's
+                        let rules = &self.rules;
+                        self.recent.retain(|(recent_type, _), last| {
+                            rules
+                                .get(recent_type)
+                                .or_else(|| rules.get(WILDCARD))
+                                .and_then(|rule| rule.dedupe.as_ref())
+                                .is_some_and(|(_, window)| at - *last < *window)
+                        });
+                    }
+                    let last = self
+                        .recent
+                        .insert((event_type.clone(), content_hash(fields, names)), at);
+                    last.is_some_and(|last| (at - last).abs() < *window)
+                });
+                // Hash to a uniform [0, 1); the same seed and id always land in the same place
+                let draw =
+                    (fnv1a(&[&self.seed, &event_type, &id]) >> 11) as f64 / (1u64 << 53) as f64;
+                if duplicate {
+                    Decision::Duplicate
+                } else if draw >= rule.rate {
+                    Decision::SampledOut
+                } else if let Some((per_ms, burst)) = rule.limit {
+                    let key = rule
+                        .per
+                        .as_deref()
+                        .map(|per| field_key(fields, per))
+                        .unwrap_or_default();
+                    if self.buckets.len() >= MAX_BUCKETS {
+                        self.buckets.retain(|_, bucket| {
+                            bucket.tokens + (at - bucket.last_ms).max(0.0) * per_ms < burst
+                        });
+                    }
+                    let bucket = self
+                        .buckets
+                        .entry((event_type.clone(), key))
+                        .or_insert(Bucket {
+                            tokens: burst,
+                            last_ms: at,
+                        });
+                    bucket.tokens =
+                        (bucket.tokens + (at - bucket.last_ms).max(0.0) * per_ms).min(burst);
+                    bucket.last_ms = bucket.last_ms.max(at);
+                    if bucket.tokens >= 1.0 {
+                        bucket.tokens -= 1.0;
+                        Decision::Keep
+                    } else {
+                        Decision::RateLimited
+                    }
+                } else {
+                    Decision::Keep
+                }
+            }
+        };
+        let counts = self.counts.entry(event_type).or_default();
+        counts.seen += 1;
+        match decision {
+            Decision::Keep => counts.kept += 1,
+            Decision::SampledOut => counts.sampled_out += 1,
+            Decision::RateLimited => counts.rate_limited += 1,
+            Decision::Duplicate => counts.duplicates += 1,
+        }
+        decision
+    }
+}
+
+#[napi]
+impl Sampler {
+    /**
+     * Build a sampler from per-type rules
+     *
+     * Types without a rule, and without a "*" rule, are always kept.
+     * Each rule drops duplicates first, then samples, then rate limits.
+     * Sampling hashes the seed, type, and event id, so a given event is
+     * kept or dropped the same way on every run. Rate limits use token
+     * buckets driven by event timestamps, so replaying a session limits it
+     * the same way too.
+     */
+    #[napi(constructor)]
+    pub fn new(rules: Vec<SamplingRule>, options: Option<SamplerOptions>) -> Result<Self> {
+        let mut compiled = AHashMap::new();
+        for rule in &rules {
+            if compiled
+                .insert(rule.event_type.clone(), compile(rule)?)
+                .is_some()
+            {
+                return Err(Error::new(
+                    Status::InvalidArg,
+                    format!("Duplicate sampling rule for '{}'", rule.event_type),
+                ));
+            }
+        }
+        Ok(Sampler {
+            rules: compiled,
+            seed: options.and_then(|o| o.seed).unwrap_or_default(),
+            buckets: AHashMap::new(),
+            recent: AHashMap::new(),
+            counts: AHashMap::new(),
+        })
+    }
+
+    /// Whether to keep one event; counts toward rate limits and stats
+    #[napi(ts_args_type = "event: Record<string, any>")]
+    pub fn keep(&mut self, event: Value) -> bool {
+        self.decide(&event) == Decision::Keep
+    }
+
+    /// The events to keep from a batch, in order
+    #[napi(ts_args_type = "events: Array<Record<string, any>>")]
+    pub fn filter(&mut self, events: Vec<Value>) -> SampleResult {
+        let mut result = SampleResult {
+            kept: Vec::with_capacity(events.len()),
+            sampled_out: 0,
+            rate_limited: 0,
+            duplicates: 0,
+        };
+        for event in events {
+            match self.decide(&event) {
+                Decision::Keep => result.kept.push(event),
+                Decision::SampledOut => result.sampled_out += 1,
+                Decision::RateLimited => result.rate_limited += 1,
+                Decision::Duplicate => result.duplicates += 1,
+            }
+        }
+        result
+    }
+
+    /// Counts per event type since the sampler was built or reset, by type
+    #[napi]
+    pub fn stats(&self) -> Vec<SamplerTypeStats> {
+        let mut stats: Vec<SamplerTypeStats> = self
+            .counts
+            .iter()
+            .map(|(event_type, counts)| SamplerTypeStats {
+                event_type: event_type.clone(),
+                seen: counts.seen as f64,
+                kept: counts.kept as f64,
+                sampled_out: counts.sampled_out as f64,
+                rate_limited: counts.rate_limited as f64,
+                duplicates: counts.duplicates as f64,
+            })
+            .collect();
+        stats.sort_by(|a, b| a.event_type.cmp(&b.event_type));
+        stats
+    }
+
+    /// Clear rate-limit buckets, remembered duplicates, and counts
+    #[napi]
+    pub fn reset(&mut self) {
+        self.buckets.clear();
+        self.recent.clear();
+        self.counts.clear();
+    }
+}
@@ -0,0 +1,458 @@
+/*!
+ * Event schema
+ * Typed shapes for the events the logger writes, and a validator that checks
+ * loosely-shaped records against them before they reach downstream consumers
+ */
+
+use crate::ingest::{details_object, event_field, parse_timestamp, EDIT_TYPES, OUTCOME_LABELS};
+use napi_derive::napi;
+use serde_json::{Map, Value};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const DEFAULT_MAX_EVENT_BYTES: u32 = 1024 * 1024;
+const DEFAULT_MAX_FIELD_BYTES: u32 = 256 * 1024;
+const DEFAULT_MAX_CLOCK_SKEW_MS: u32 = 5 * 60 * 1000;
+/// 2000-01-01T00:00:00Z; anything earlier is a unit mix-up or an unset clock
+const MIN_TIMESTAMP_MS: f64 = 946_684_800_000.0;
+const CHAT_TYPES: [&str; 3] = ["prompt", "response", "chat"];
+const CHAT_ROLES: [&str; 3] = ["user", "assistant", "system"];
+
+/// Fields every event carries
+#[napi(object)]
+pub struct Event {
+    pub id: String,
+    pub session_id: Option<String>,
+    /// Milliseconds since the Unix epoch (JS `Date.now()`)
+    pub timestamp: i64,
+    /// Event kind, e.g. "code_change", "prompt", "terminal_command"
+    #[napi(js_name = "type")]
+    pub event_type: String,
+    pub workspace_path: Option<String>,
+    /// Free-form payload, as an object or its JSON string
+    #[napi(ts_type = "Record<string, any> | string")]
+    pub details: Option<Value>,
+}
+
+/// A "code_change" or "file_change" event
+#[napi(object)]
+pub struct EditEvent {
+    pub id: String,
+    pub session_id: Option<String>,
+    pub timestamp: i64,
+    /// "code_change" or "file_change"
+    #[napi(js_name = "type")]
+    pub event_type: String,
+    pub workspace_path: Option<String>,
+    pub file_path: String,
+    pub lines_added: Option<u32>,
+    pub lines_removed: Option<u32>,
+    pub before_content: Option<String>,
+    pub after_content: Option<String>,
+    pub ai_generated: Option<bool>,
+    /// User verdict on an AI edit: "good", "bad", or "reverted"
+    pub label: Option<String>,
+    #[napi(ts_type = "Record<string, any> | string")]
+    pub details: Option<Value>,
+}
+
+/// A "prompt", "response", or "chat" event
+#[napi(object)]
+pub struct ChatEvent {
+    pub id: String,
+    pub session_id: Option<String>,
+    pub timestamp: i64,
+    /// "prompt", "response", or "chat"
+    #[napi(js_name = "type")]
+    pub event_type: String,
+    pub workspace_path: Option<String>,
+    pub text: String,
+    /// "user", "assistant", or "system"
+    pub role: Option<String>,
+    pub model: Option<String>,
+    pub conversation_id: Option<String>,
+    /// Time from request to model response
+    pub latency_ms: Option<f64>,
+    #[napi(ts_type = "Record<string, any> | string")]
+    pub details: Option<Value>,
+}
+
+/// Options for validate_event
+#[napi(object)]
+pub struct SchemaOptions {
+    /// Largest serialized event, in UTF-8 bytes (default: 1 MiB)
+    pub max_event_bytes: Option<u32>,
+    /// Largest single string value anywhere in the event, in UTF-8 bytes (default: 256 KiB)
+    pub max_field_bytes: Option<u32>,
+    /// How far past the current time a timestamp may be (default: 300000, five minutes)
+    pub max_clock_skew_ms: Option<u32>,
+}
+
+/// One way an event breaks the schema
+#[napi(object)]
+pub struct SchemaError {
+    /// snake_case field name, or a dotted path for nested values; empty for the whole event
+    pub field: String,
+    /// "InvalidEvent", "EventTooLarge", "MissingField", "InvalidField", "InvalidTimestamp",
+    /// "TimestampOutOfRange", "InvalidCount", "InvalidLabel", "InvalidRole", or "FieldTooLarge"
+    pub code: String,
+    pub message: String,
+}
+
+/// Result of validate_event
+#[napi(object)]
+pub struct EventValidation {
+    pub valid: bool,
+    /// Schema the event was checked against: "edit", "chat", or "event"
+    pub kind: String,
+    /// Serialized size in UTF-8 bytes
+    pub bytes: u32,
+    /// Epoch milliseconds, when the timestamp parsed
+    pub timestamp: Option<f64>,
+    /// Every problem found, in field order
+    pub errors: Vec<SchemaError>,
+}
+
+/// `lines_added` -> `linesAdded`
+pub(crate) fn camel(field: &str) -> String {
+    let mut out = String::with_capacity(field.len());
+    let mut upper = false;
+    for c in field.chars() {
+        if c == '_' {
+            upper = true;
+        } else if upper {
+            out.extend(c.to_uppercase());
+            upper = false;
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+struct Checker<'a> {
+    event: &'a Map<String, Value>,
+    details: Option<Map<String, Value>>,
+    errors: Vec<SchemaError>,
+}
+
+impl Checker<'_> {
+    fn fail(&mut self, field: &str, code: &str, message: String) {
+        self.errors.push(SchemaError {
+            field: field.to_string(),
+            code: code.to_string(),
+            message,
+        });
+    }
+
+    /// A field by its snake_case or camelCase name, at the top level or inside `details`
+    fn get(&self, field: &str) -> Option<Value> {
+        event_field(self.event, self.details.as_ref(), &[field, &camel(field)])
+            .filter(|value| !value.is_null())
+    }
+
+    fn required_string(&mut self, field: &str) {
+        match self.get(field) {
+            None => self.fail(field, "MissingField", format!("{} is required", field)),
+            Some(Value::String(text)) if !text.trim().is_empty() => {}
+            Some(Value::String(_)) => {
+                self.fail(field, "MissingField", format!("{} is empty", field))
+            }
+            Some(other) => self.fail(
+                field,
+                "InvalidField",
+                format!("{} must be a string, got {}", field, kind_of(&other)),
+            ),
+        }
+    }
+
+    fn optional_string(&mut self, field: &str) {
+        if let Some(other) = self.get(field).filter(|value| !value.is_string()) {
+            self.fail(
+                field,
+                "InvalidField",
+                format!("{} must be a string, got {}", field, kind_of(&other)),
+            );
+        }
+    }
+
+    fn optional_bool(&mut self, field: &str) {
+        if let Some(other) = self.get(field).filter(|value| !value.is_boolean()) {
+            self.fail(
+                field,
+                "InvalidField",
+                format!("{} must be a boolean, got {}", field, kind_of(&other)),
+            );
+        }
+    }
+
+    fn optional_count(&mut self, field: &str) {
+        match self.get(field) {
+            None => {}
+            Some(Value::Number(n)) if n.as_u64().is_some() => {}
+            Some(other) => self.fail(
+                field,
+                "InvalidCount",
+                format!("{} must be a non-negative integer, got {}", field, other),
+            ),
+        }
+    }
+
+    fn one_of(&mut self, field: &str, allowed: &[&str], code: &str) {
+        match self.get(field) {
+            None => {}
+            Some(Value::String(text)) if allowed.contains(&text.as_str()) => {}
+            Some(other) => self.fail(
+                field,
+                code,
+                format!(
+                    "{} must be one of {}, got {}",
+                    field,
+                    allowed.join(", "),
+                    other
+                ),
+            ),
+        }
+    }
+}
+
+fn kind_of(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
+
+/// Report every string value longer than `max_bytes`, by dotted path
+fn check_sizes(value: &Value, path: &mut String, max_bytes: usize, checker: &mut Checker) {
+    match value {
+        Value::String(text) if text.len() > max_bytes => {
+            let field = path.clone();
+            checker.fail(
+                &field,
+                "FieldTooLarge",
+                format!(
+                    "{} is {} bytes, over the limit of {}",
+                    field,
+                    text.len(),
+                    max_bytes
+                ),
+            );
+        }
+        Value::Array(items) => {
+            for (i, item) in items.iter().enumerate() {
+                let len = path.len();
+                path.push_str(&format!("[{}]", i));
+                check_sizes(item, path, max_bytes, checker);
+                path.truncate(len);
+            }
+        }
+        Value::Object(fields) => {
+            for (key, item) in fields {
+                let len = path.len();
+                if !path.is_empty() {
+                    path.push('.');
+                }
+                path.push_str(key);
+                check_sizes(item, path, max_bytes, checker);
+                path.truncate(len);
+            }
+        }
+        _ => {}
+    }
+}
+
+/**
+ * Check a raw event against the Event, EditEvent, or ChatEvent schema
+ *
+ * The schema is picked from `type`: "code_change" and "file_change" are
+ * edits, "prompt", "response", and "chat" are chat events, and anything
+ * else is checked as a plain Event. Fields may be snake_case or camelCase,
+ * and edit and chat fields may sit inside `details`, as ingest_batch
+ * accepts them.
+ *
+ * Every event needs an `id`, a `type`, and a `timestamp` (epoch ms or
+ * ISO-8601) no earlier than 2000 and no later than now plus the allowed
+ * clock skew; a timestamp in seconds is caught by the first bound. Edits
+ * need a `file_path` and chat events a `text`. All problems are reported,
+ * not just the first.
+ *
+ * @param event - The record as the JS side would write it
+ * @param options - Size limits and clock skew
+ * @returns Whether it is valid, which schema applied, and each error
+ */
+#[napi(ts_args_type = "event: any, options?: SchemaOptions | undefined | null")]
+pub fn validate_event(event: Value, options: Option<SchemaOptions>) -> EventValidation {
+    let max_event_bytes = options
+        .as_ref()
+        .and_then(|o| o.max_event_bytes)
+        .unwrap_or(DEFAULT_MAX_EVENT_BYTES) as usize;
+    let max_field_bytes = options
+        .as_ref()
+        .and_then(|o| o.max_field_bytes)
+        .unwrap_or(DEFAULT_MAX_FIELD_BYTES) as usize;
+    let max_clock_skew_ms = options
+        .and_then(|o| o.max_clock_skew_ms)
+        .unwrap_or(DEFAULT_MAX_CLOCK_SKEW_MS) as f64;
+
+    let bytes = event.to_string().len();
+    let Value::Object(fields) = &event else {
+        return EventValidation {
+            valid: false,
+            kind: "event".to_string(),
+            bytes: bytes as u32,
+            timestamp: None,
+            errors: vec![SchemaError {
+                field: String::new(),
+                code: "InvalidEvent".to_string(),
+                message: format!("Event must be an object, got {}", kind_of(&event)),
+            }],
+        };
+    };
+    let mut checker = Checker {
+        event: fields,
+        details: details_object(fields),
+        errors: Vec::new(),
+    };
+    if bytes > max_event_bytes {
+        checker.fail(
+            "",
+            "EventTooLarge",
+            format!(
+                "Event is {} bytes, over the limit of {}",
+                bytes, max_event_bytes
+            ),
+        );
+    }
+
+    match fields.get("id") {
+        None | Some(Value::Null) => checker.fail("id", "MissingField", "id is required".into()),
+        Some(Value::String(id)) if id.trim().is_empty() => {
+            checker.fail("id", "MissingField", "id is empty".into())
+        }
+        Some(Value::String(_) | Value::Number(_)) => {}
+        Some(other) => checker.fail(
+            "id",
+            "InvalidField",
+            format!("id must be a string or number, got {}", kind_of(other)),
+        ),
+    }
+
+    let event_type = fields.get("type").and_then(Value::as_str).map(str::trim);
+    match fields.get("type") {
+        None | Some(Value::Null) => checker.fail("type", "MissingField", "type is required".into()),
+        Some(Value::String(_)) if event_type == Some("") => {
+            checker.fail("type", "MissingField", "type is empty".into())
+        }
+        Some(Value::String(_)) => {}
+        Some(other) => checker.fail(
+            "type",
+            "InvalidField",
+            format!("type must be a string, got {}", kind_of(other)),
+        ),
+    }
+
+    let timestamp = match fields.get("timestamp") {
+        None | Some(Value::Null) => {
+            checker.fail("timestamp", "MissingField", "timestamp is required".into());
+            None
+        }
+        Some(value) => {
+            let parsed = parse_timestamp(value);
+            if parsed.is_none() {
+                checker.fail(
+                    "timestamp",
+                    "InvalidTimestamp",
+                    "timestamp must be epoch milliseconds or an ISO-8601 string".into(),
+                );
+            }
+            parsed
+        }
+    };
+    if let Some(ms) = timestamp {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0.0, |d| d.as_millis() as f64);
+        if ms < MIN_TIMESTAMP_MS {
+            let hint = if ms * 1000.0 >= MIN_TIMESTAMP_MS && ms * 1000.0 <= now {
+                " (it looks like seconds rather than milliseconds)"
+            } else {
+                ""
+            };
+            checker.fail(
+                "timestamp",
+                "TimestampOutOfRange",
+                format!("timestamp {} is before the year 2000{}", ms, hint),
+            );
+        } else if ms > now + max_clock_skew_ms {
+            checker.fail(
+                "timestamp",
+                "TimestampOutOfRange",
+                format!(
+                    "timestamp {} is {} ms in the future",
+                    ms,
+                    (ms - now).round()
+                ),
+            );
+        }
+    }
+
+    checker.optional_string("session_id");
+    checker.optional_string("workspace_path");
+    if let Some(other) = fields
+        .get("details")
+        .filter(|d| !d.is_null() && !d.is_string() && !d.is_object())
+    {
+        checker.fail(
+            "details",
+            "InvalidField",
+            format!(
+                "details must be an object or a string, got {}",
+                kind_of(other)
+            ),
+        );
+    }
+
+    let kind = match event_type {
+        Some(t) if EDIT_TYPES.contains(&t) => {
+            checker.required_string("file_path");
+            checker.optional_count("lines_added");
+            checker.optional_count("lines_removed");
+            checker.optional_string("before_content");
+            checker.optional_string("after_content");
+            checker.optional_bool("ai_generated");
+            checker.one_of("label", &OUTCOME_LABELS, "InvalidLabel");
+            "edit"
+        }
+        Some(t) if CHAT_TYPES.contains(&t) => {
+            checker.required_string("text");
+            checker.one_of("role", &CHAT_ROLES, "InvalidRole");
+            checker.optional_string("model");
+            checker.optional_string("conversation_id");
+            match checker.get("latency_ms") {
+                None => {}
+                Some(Value::Number(n)) if n.as_f64().is_some_and(|ms| ms >= 0.0) => {}
+                Some(other) => checker.fail(
+                    "latency_ms",
+                    "InvalidField",
+                    format!("latency_ms must be a non-negative number, got {}", other),
+                ),
+            }
+            "chat"
+        }
+        _ => "event",
+    };
+
+    check_sizes(&event, &mut String::new(), max_field_bytes, &mut checker);
+
+    EventValidation {
+        valid: checker.errors.is_empty(),
+        kind: kind.to_string(),
+        bytes: bytes as u32,
+        timestamp,
+        errors: checker.errors,
+    }
+}
@@ -0,0 +1,442 @@
+/*!
+ * Multi-pattern search
+ * One Aho-Corasick pass for plain keywords, regex sets compiled once and reused,
+ * and single-pass redaction
+ */
+
+use crate::functions::{line_of, line_starts};
+use ahash::AHashSet;
+use aho_corasick::{AhoCorasick, AhoCorasickBuilder, MatchKind};
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use regex::{Regex, RegexSet};
+use std::collections::HashMap;
+
+/// A single regex match, from find_patterns and PatternSet.find
+#[napi(object)]
+pub struct PatternMatch {
+    pub pattern: String,
+    /// Byte offsets into the UTF-8 content
+    pub start: u32,
+    pub end: u32,
+    /// 1-based line of the match start
+    pub line: u32,
+    /// 1-based column of the match start, in characters
+    pub column: u32,
+    pub text: String,
+}
+
+/// Result of find_patterns
+#[napi(object)]
+pub struct PatternMatches {
+    /// Ordered by position
+    pub matches: Vec<PatternMatch>,
+    /// More matches exist past the cap
+    pub truncated: bool,
+}
+
+/// Result of redact_patterns
+#[napi(object)]
+pub struct RedactedText {
+    pub text: String,
+    /// Matches replaced per pattern
+    pub counts: HashMap<String, i64>,
+    /// Total replacements
+    pub redactions: i64,
+}
+
+/// Options for search_literals
+#[napi(object)]
+pub struct LiteralSearchOptions {
+    /// Match ASCII letters regardless of case (default: false)
+    pub case_insensitive: Option<bool>,
+    /// Only count matches not touching a letter, digit, or underscore on either side (default: false)
+    pub whole_word: Option<bool>,
+}
+
+fn is_word_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'_' || byte >= 0x80
+}
+
+/// Compiled set of literals, counted per literal in one scan
+pub(crate) struct LiteralMatcher {
+    automaton: AhoCorasick,
+    literals: Vec<String>,
+    whole_word: bool,
+}
+
+impl LiteralMatcher {
+    /// Empty strings and repeats are dropped
+    pub(crate) fn new(
+        literals: Vec<String>,
+        case_insensitive: bool,
+        whole_word: bool,
+    ) -> Result<Self> {
+        let mut seen = AHashSet::new();
+        let unique: Vec<String> = literals
+            .into_iter()
+            .filter(|literal| !literal.is_empty() && seen.insert(literal.clone()))
+            .collect();
+        let automaton = AhoCorasickBuilder::new()
+            .match_kind(MatchKind::Standard)
+            .ascii_case_insensitive(case_insensitive)
+            .build(&unique)
+            .map_err(|e| {
+                Error::new(
+                    Status::InvalidArg,
+                    format!("Failed to build matcher: {}", e),
+                )
+            })?;
+        Ok(LiteralMatcher {
+            automaton,
+            literals: unique,
+            whole_word,
+        })
+    }
+
+    pub(crate) fn literals(&self) -> &[String] {
+        &self.literals
+    }
+
+    /// Non-overlapping occurrences of each literal, counted as a regex for that literal alone would
+    pub(crate) fn count(&self, content: &str) -> Vec<usize> {
+        let bytes = content.as_bytes();
+        let mut counts = vec![0; self.literals.len()];
+        // A literal's next match must start at or after the end of its previous one
+        let mut next_start = vec![0; self.literals.len()];
+        for found in self.automaton.find_overlapping_iter(content) {
+            let index = found.pattern().as_usize();
+            if found.start() < next_start[index] {
+                continue;
+            }
+            if self.whole_word {
+                let before = found.start() > 0 && is_word_byte(bytes[found.start() - 1]);
+                let after = bytes.get(found.end()).is_some_and(|b| is_word_byte(*b));
+                if before || after {
+                    continue;
+                }
+            }
+            counts[index] += 1;
+            next_start[index] = found.end();
+        }
+        counts
+    }
+}
+
+/**
+ * Count many plain-text keywords in one pass
+ *
+ * Builds an Aho-Corasick automaton from all literals, so the content is
+ * scanned once however many there are, where search_patterns runs one
+ * regex per pattern. Each literal is counted independently: overlapping
+ * keywords (`api`, `api_key`) both count, and repeats of one literal don't
+ * overlap, the same counts search_patterns gives for escaped patterns.
+ *
+ * @param content - Text to search
+ * @param literals - Keywords matched verbatim; empty strings are left out
+ * @param options - ASCII case-insensitivity and whole-word matching
+ * @returns Matches per literal
+ */
+#[napi]
+pub fn search_literals(
+    content: String,
+    literals: Vec<String>,
+    options: Option<LiteralSearchOptions>,
+) -> Result<HashMap<String, i32>> {
+    let case_insensitive = options
+        .as_ref()
+        .and_then(|o| o.case_insensitive)
+        .unwrap_or(false);
+    let whole_word = options.as_ref().and_then(|o| o.whole_word).unwrap_or(false);
+    let matcher = LiteralMatcher::new(literals, case_insensitive, whole_word)?;
+    let counts = matcher.count(&content);
+    Ok(matcher
+        .literals()
+        .iter()
+        .cloned()
+        .zip(counts.into_iter().map(|count| count as i32))
+        .collect())
+}
+
+/// Regexes compiled once and reused across searches
+#[napi]
+pub struct PatternSet {
+    patterns: Vec<String>,
+    set: RegexSet,
+    regexes: Vec<Regex>,
+}
+
+impl PatternSet {
+    /// Compile with repeats dropped; invalid patterns fail the build unless `skip_invalid`
+    pub(crate) fn compile(patterns: Vec<String>, skip_invalid: bool) -> Result<Self> {
+        let mut seen = AHashSet::new();
+        let mut kept = Vec::with_capacity(patterns.len());
+        let mut regexes = Vec::with_capacity(patterns.len());
+        for pattern in patterns {
+            if !seen.insert(pattern.clone()) {
+                continue;
+            }
+            match Regex::new(&pattern) {
+                Ok(regex) => {
+                    kept.push(pattern);
+                    regexes.push(regex);
+                }
+                Err(_) if skip_invalid => {}
+                Err(e) => {
+                    return Err(Error::new(
+                        Status::InvalidArg,
+                        format!("Invalid pattern '{}': {}", pattern, e),
+                    ))
+                }
+            }
+        }
+        let set = RegexSet::new(&kept).map_err(|e| {
+            Error::new(
+                Status::InvalidArg,
+                format!("Failed to build pattern set: {}", e),
+            )
+        })?;
+        Ok(PatternSet {
+            patterns: kept,
+            set,
+            regexes,
+        })
+    }
+
+    /// Matches per pattern, in pattern order
+    pub(crate) fn counts_in(&self, content: &str) -> Vec<usize> {
+        let mut counts = vec![0; self.patterns.len()];
+        for index in self.set.matches(content).into_iter() {
+            counts[index] = self.regexes[index].find_iter(content).count();
+        }
+        counts
+    }
+
+    pub(crate) fn pattern_list(&self) -> &[String] {
+        &self.patterns
+    }
+
+    /// Up to `limit` matches in position order
+    pub(crate) fn find_in(&self, content: &str, limit: usize) -> Vec<PatternMatch> {
+        let mut found: Vec<(usize, usize, usize)> = Vec::new();
+        for index in self.set.matches(content).into_iter() {
+            // The first `limit` overall are among each pattern's first `limit`
+            for m in self.regexes[index].find_iter(content).take(limit) {
+                found.push((m.start(), m.end(), index));
+            }
+        }
+        found.sort_unstable();
+        found.truncate(limit);
+
+        let starts = line_starts(content);
+        found
+            .into_iter()
+            .map(|(start, end, index)| {
+                let line = line_of(&starts, start);
+                let line_start = starts[line as usize - 1];
+                PatternMatch {
+                    pattern: self.patterns[index].clone(),
+                    start: start as u32,
+                    end: end as u32,
+                    line,
+                    column: content[line_start..start].chars().count() as u32 + 1,
+                    text: content[start..end].to_string(),
+                }
+            })
+            .collect()
+    }
+}
+
+#[napi]
+impl PatternSet {
+    /**
+     * Compile `patterns` (regex syntax) for repeated searches
+     *
+     * Fails on the first invalid pattern, naming it, as search_patterns
+     * does. Repeated patterns are kept once.
+     */
+    #[napi(constructor)]
+    pub fn new(patterns: Vec<String>) -> Result<Self> {
+        PatternSet::compile(patterns, false)
+    }
+
+    /// Compiled patterns, in the order given
+    #[napi(getter)]
+    pub fn patterns(&self) -> Vec<String> {
+        self.patterns.clone()
+    }
+
+    /// Whether any pattern matches
+    #[napi]
+    pub fn is_match(&self, content: String) -> bool {
+        self.set.is_match(&content)
+    }
+
+    /// Patterns that match at least once, in the order given
+    #[napi(js_name = "match")]
+    pub fn matching(&self, content: String) -> Vec<String> {
+        self.set
+            .matches(&content)
+            .into_iter()
+            .map(|index| self.patterns[index].clone())
+            .collect()
+    }
+
+    /**
+     * Matches per pattern, the same counts search_patterns returns
+     *
+     * One set scan finds which patterns occur; only those are run again
+     * to count, so the common no-match case costs a single pass.
+     */
+    #[napi]
+    pub fn count(&self, content: String) -> HashMap<String, i32> {
+        self.patterns
+            .iter()
+            .cloned()
+            .zip(
+                self.counts_in(&content)
+                    .into_iter()
+                    .map(|count| count as i32),
+            )
+            .collect()
+    }
+
+    /**
+     * Every match of every pattern, ordered by position, with line and column
+     *
+     * @param content - Text to search
+     * @param limit - Stop after this many matches in total
+     */
+    #[napi]
+    pub fn find(&self, content: String, limit: Option<u32>) -> Vec<PatternMatch> {
+        self.find_in(&content, limit.map_or(usize::MAX, |limit| limit as usize))
+    }
+}
+
+/**
+ * Find pattern matches with their locations
+ *
+ * The positional counterpart of search_patterns, for linking findings to
+ * a file location. Invalid patterns are left out; use PatternSet to have
+ * them reported.
+ *
+ * @param content - Text to search
+ * @param patterns - Regex patterns
+ * @param max_matches - Cap on matches returned in total (default: unlimited)
+ * @returns Matches ordered by position, and whether the cap cut any off
+ */
+#[napi]
+pub fn find_patterns(
+    content: String,
+    patterns: Vec<String>,
+    max_matches: Option<u32>,
+) -> Result<PatternMatches> {
+    let set = PatternSet::compile(patterns, true)?;
+    let limit = max_matches.map_or(usize::MAX, |max| max as usize);
+    // One extra match tells whether the cap was hit
+    let mut matches = set.find_in(&content, limit.saturating_add(1));
+    let truncated = matches.len() > limit;
+    matches.truncate(limit);
+    Ok(PatternMatches { matches, truncated })
+}
+
+/**
+ * Replace pattern matches, e.g. to scrub secrets before content is stored
+ *
+ * Every pattern is matched against the original content and the text is
+ * rebuilt in one pass, so one pattern's replacement is never rescanned by
+ * another. Where matches overlap, the one starting first wins, ties going
+ * to the earlier pattern. Empty matches are left alone.
+ *
+ * @param content - Text to redact
+ * @param patterns - Regex patterns; an invalid pattern fails the call
+ * @param replacement - One replacement for all patterns, or one per pattern;
+ *   `$1` and `${name}` expand to capture groups, `$$` is a literal `$`.
+ *   Omitted, each match is masked with one `*` per character, keeping line breaks
+ * @returns The redacted text and replacement counts
+ */
+#[napi]
+pub fn redact_patterns(
+    content: String,
+    patterns: Vec<String>,
+    replacement: Option<Either<String, Vec<String>>>,
+) -> Result<RedactedText> {
+    let replacements: Vec<Option<String>> = match replacement {
+        None => vec![None; patterns.len()],
+        Some(Either::A(replacement)) => vec![Some(replacement); patterns.len()],
+        Some(Either::B(list)) => {
+            if list.len() != patterns.len() {
+                return Err(Error::new(
+                    Status::InvalidArg,
+                    format!(
+                        "Expected {} replacements, one per pattern, got {}",
+                        patterns.len(),
+                        list.len()
+                    ),
+                ));
+            }
+            list.into_iter().map(Some).collect()
+        }
+    };
+    let regexes = patterns
+        .iter()
+        .map(|pattern| {
+            Regex::new(pattern).map_err(|e| {
+                Error::new(
+                    Status::InvalidArg,
+                    format!("Invalid pattern '{}': {}", pattern, e),
+                )
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    // (start, pattern index, end), so sorting orders by position then pattern
+    let mut found: Vec<(usize, usize, usize)> = Vec::new();
+    for (index, re) in regexes.iter().enumerate() {
+        found.extend(
+            re.find_iter(&content)
+                .filter(|m| !m.is_empty())
+                .map(|m| (m.start(), index, m.end())),
+        );
+    }
+    found.sort_unstable();
+
+    let mut counts = vec![0i64; patterns.len()];
+    let mut text = String::with_capacity(content.len());
+    let mut cursor = 0;
+    for (start, index, end) in found {
+        if start < cursor {
+            continue;
+        }
+        text.push_str(&content[cursor..start]);
+        match &replacements[index] {
+            None => text.extend(content[start..end].chars().map(|c| {
+                if c == '\n' || c == '\r' {
+                    c
+                } else {
+                    '*'
+                }
+            })),
+            Some(replacement) if replacement.contains('$') => {
+                // Searching again from the match start finds the same match, now with groups
+                if let Some(captures) = regexes[index].captures_at(&content, start) {
+                    captures.expand(replacement, &mut text);
+                }
+            }
+            Some(replacement) => text.push_str(replacement),
+        }
+        counts[index] += 1;
+        cursor = end;
+    }
+    text.push_str(&content[cursor..]);
+
+    let mut by_pattern: HashMap<String, i64> = HashMap::new();
+    for (pattern, count) in patterns.into_iter().zip(&counts) {
+        *by_pattern.entry(pattern).or_default() += count;
+    }
+    Ok(RedactedText {
+        text,
+        counts: by_pattern,
+        redactions: counts.iter().sum(),
+    })
+}
@@ -0,0 +1,496 @@
+/*!
+ * Local ingestion server
+ * One companion process stores telemetry for every editor instance on the
+ * machine: they POST batches to /ingest and read them back from /query,
+ * authenticated with a shared token
+ */
+
+use crate::store::{empty_query, EventQuery, EventStore};
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// How often the server checks whether it was stopped
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+const DEFAULT_MAX_BODY_BYTES: u32 = 8 * 1024 * 1024;
+const DEFAULT_MAX_QUERY_ROWS: u32 = 10_000;
+const DEFAULT_MAX_CONNECTIONS: u32 = 32;
+const DEFAULT_REQUEST_TIMEOUT_MS: u32 = 10_000;
+/// Most bytes in the request line and headers together
+const MAX_HEAD_BYTES: usize = 16 * 1024;
+/// Most header lines in one request
+const MAX_HEADERS: usize = 64;
+
+/// Options for IngestServer.start
+#[napi(object)]
+pub struct IngestServerOptions {
+    /// Shared secret clients send as `Authorization: Bearer <token>`
+    pub token: String,
+    /// Address to bind (default: "127.0.0.1")
+    pub host: Option<String>,
+    /// Largest /ingest body accepted (default: 8 MiB)
+    pub max_body_bytes: Option<u32>,
+    /// Most events one /query returns, whatever its limit (default: 10000)
+    pub max_query_rows: Option<u32>,
+    /// Connections served at once; more are answered 503 (default: 32)
+    pub max_connections: Option<u32>,
+    /// Time a client has to send its whole request before it is disconnected (default: 10000)
+    pub request_timeout_ms: Option<u32>,
+}
+
+/// Counters for an IngestServer
+#[napi(object)]
+pub struct IngestServerStats {
+    /// Requests answered, including refused ones
+    pub requests: f64,
+    /// Events written by /ingest
+    pub ingested: f64,
+    /// Requests refused for a missing or wrong token
+    pub unauthorized: f64,
+    /// Requests answered with another 4xx or 5xx status
+    pub failed: f64,
+}
+
+#[derive(Default)]
+struct Counters {
+    requests: AtomicU64,
+    ingested: AtomicU64,
+    unauthorized: AtomicU64,
+    failed: AtomicU64,
+}
+
+struct Limits {
+    token: String,
+    max_body_bytes: usize,
+    max_query_rows: u32,
+    request_timeout: Duration,
+}
+
+/// A response status, reason, and JSON body
+type Reply = (u16, &'static str, Value);
+
+fn error_reply(status: u16, reason: &'static str, message: String) -> Reply {
+    (status, reason, json!({ "error": message }))
+}
+
+/// Compare without stopping at the first difference, so response timing
+/// doesn't reveal how much of a guessed token was right
+fn same_token(given: &str, expected: &str) -> bool {
+    given.len() == expected.len()
+        && given
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// Decode %XX escapes and `+` in a query string component
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let hex = |byte: u8| (byte as char).to_digit(16).map(|digit| digit as u8);
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = match bytes.get(i + 1..i + 3) {
+            Some(&[high, low]) if bytes[i] == b'%' => hex(high).zip(hex(low)),
+            _ => None,
+        };
+        match (escaped, bytes[i]) {
+            (Some((high, low)), _) => {
+                decoded.push(high << 4 | low);
+                i += 2;
+            }
+            (None, b'+') => decoded.push(b' '),
+            (None, byte) => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// An EventQuery from /query parameters, in snake_case or camelCase
+fn query_of(query: &str, max_rows: u32) -> std::result::Result<EventQuery, String> {
+    let mut filter = empty_query();
+    for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+        let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+        let value = percent_decode(value);
+        let number = |value: &str| {
+            value
+                .parse::<i64>()
+                .map_err(|_| format!("{} must be an integer, got '{}'", name, value))
+        };
+        match name {
+            "start" => filter.start = Some(number(&value)?),
+            "end" => filter.end = Some(number(&value)?),
+            "session_id" | "sessionId" => filter.session_id = Some(value),
+            "workspace_path" | "workspacePath" => filter.workspace_path = Some(value),
+            "file_path" | "filePath" => filter.file_path = Some(value),
+            "type" => filter.event_type = Some(value),
+            "limit" => filter.limit = Some(number(&value)?.clamp(0, u32::MAX as i64) as u32),
+            "offset" => filter.offset = Some(number(&value)?.clamp(0, u32::MAX as i64) as u32),
+            "descending" => filter.descending = Some(value == "true" || value == "1"),
+            other => return Err(format!("Unknown query parameter '{}'", other)),
+        }
+    }
+    filter.limit = Some(filter.limit.map_or(max_rows, |limit| limit.min(max_rows)));
+    Ok(filter)
+}
+
+/// Events from an /ingest body: an array, or an object with an `events` array
+fn events_of(body: &[u8]) -> std::result::Result<Vec<Value>, String> {
+    match serde_json::from_slice::<Value>(body) {
+        Ok(Value::Array(events)) => Ok(events),
+        Ok(Value::Object(mut object)) => match object.remove("events") {
+            Some(Value::Array(events)) => Ok(events),
+            _ => Err("Expected an array of events or { events: [...] }".to_string()),
+        },
+        Ok(_) => Err("Expected an array of events or { events: [...] }".to_string()),
+        Err(e) => Err(format!("Invalid JSON: {}", e)),
+    }
+}
+
+fn write_reply(stream: &mut TcpStream, (status, reason, body): Reply) {
+    let body = body.to_string();
+    let response = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason,
+        body.len(),
+        body
+    );
+    let _ = stream.write_all(response.as_bytes());
+}
+
+/// Answer `reply` and close without reading anything more from the client
+///
+/// A client still sending a refused body may see a reset instead of
+/// the response; that costs it one retry, while reading on would let any
+/// client hold a thread for as long as it keeps sending.
+fn answer(mut stream: TcpStream, reply: Reply) {
+    write_reply(&mut stream, reply);
+    let _ = stream.shutdown(Shutdown::Write);
+}
+
+/// Reads from a connection until a fixed instant, however the bytes trickle in
+///
+/// A per-read timeout alone lets a client that sends a byte every few
+/// seconds hold its connection forever.
+struct Deadline {
+    stream: TcpStream,
+    until: Instant,
+}
+
+impl Read for Deadline {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let left = self.until.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            return Err(std::io::ErrorKind::TimedOut.into());
+        }
+        self.stream.set_read_timeout(Some(left))?;
+        self.stream.read(buf)
+    }
+}
+
+/// The request line and header lines, or None if they exceed
+/// MAX_HEAD_BYTES or MAX_HEADERS
+fn read_head(reader: &mut impl BufRead) -> std::io::Result<Option<Vec<String>>> {
+    let mut lines = Vec::new();
+    let mut left = MAX_HEAD_BYTES;
+    loop {
+        let mut line = Vec::new();
+        let read = reader
+            .by_ref()
+            .take(left as u64)
+            .read_until(b'\n', &mut line)?;
+        left -= read;
+        if !line.ends_with(b"\n") {
+            return match left {
+                0 => Ok(None),
+                _ => Err(std::io::ErrorKind::UnexpectedEof.into()),
+            };
+        }
+        let line = String::from_utf8_lossy(&line).trim_end().to_string();
+        match (line.is_empty(), lines.is_empty()) {
+            // Blank lines before the request line are allowed and skipped
+            (true, true) => continue,
+            (true, false) => return Ok(Some(lines)),
+            _ if lines.len() > MAX_HEADERS => return Ok(None),
+            _ => lines.push(line),
+        }
+    }
+}
+
+/// Read one request and answer it from `store`
+///
+/// The token is checked as soon as the headers are in, so a refused client
+/// never gets its body read.
+fn serve(stream: TcpStream, store: &Mutex<EventStore>, limits: &Limits, counters: &Counters) {
+    let _ = stream.set_nonblocking(false);
+    let _ = stream.set_write_timeout(Some(limits.request_timeout));
+    let mut reader = BufReader::new(match stream.try_clone() {
+        Ok(stream) => Deadline {
+            stream,
+            until: Instant::now() + limits.request_timeout,
+        },
+        Err(_) => return,
+    });
+    let head = match read_head(&mut reader) {
+        Ok(head) => head,
+        // Timed out, reset, or closed before the headers ended
+        Err(_) => return,
+    };
+    counters.requests.fetch_add(1, Ordering::Relaxed);
+    let Some(head) = head else {
+        counters.failed.fetch_add(1, Ordering::Relaxed);
+        let message = format!(
+            "Headers exceed {} bytes or {} lines",
+            MAX_HEAD_BYTES, MAX_HEADERS
+        );
+        return answer(
+            stream,
+            error_reply(431, "Request Header Fields Too Large", message),
+        );
+    };
+    let mut authorized = false;
+    let mut content_length = None;
+    for header in &head[1..] {
+        if let Some((name, value)) = header.split_once(':') {
+            let value = value.trim();
+            if name.eq_ignore_ascii_case("authorization") {
+                authorized = value
+                    .strip_prefix("Bearer ")
+                    .is_some_and(|token| same_token(token.trim(), &limits.token));
+            } else if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.parse::<usize>().ok();
+            }
+        }
+    }
+    if !authorized {
+        counters.unauthorized.fetch_add(1, Ordering::Relaxed);
+        let reply = error_reply(401, "Unauthorized", "Missing or wrong token".to_string());
+        return answer(stream, reply);
+    }
+    let mut parts = head[0].split_whitespace();
+    let (method, target) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let reply = match (method, path) {
+        ("POST", "/ingest") => match content_length {
+            None => error_reply(
+                411,
+                "Length Required",
+                "Content-Length is required".to_string(),
+            ),
+            Some(length) if length > limits.max_body_bytes => error_reply(
+                413,
+                "Payload Too Large",
+                format!(
+                    "Body of {} bytes exceeds {} bytes",
+                    length, limits.max_body_bytes
+                ),
+            ),
+            Some(length) => {
+                let mut body = vec![0; length];
+                if reader.read_exact(&mut body).is_err() {
+                    return;
+                }
+                ingest(&body, store, counters)
+            }
+        },
+        ("GET", "/query") => match query_of(query, limits.max_query_rows) {
+            Ok(filter) => match store.lock().unwrap().query(Some(filter)) {
+                Ok(events) => (200, "OK", Value::Array(events)),
+                Err(e) => error_reply(500, "Internal Server Error", e.reason),
+            },
+            Err(message) => error_reply(400, "Bad Request", message),
+        },
+        (_, "/ingest") | (_, "/query") => {
+            error_reply(405, "Method Not Allowed", format!("{} {}", method, path))
+        }
+        _ => error_reply(404, "Not Found", format!("No endpoint {}", path)),
+    };
+    if reply.0 >= 400 {
+        counters.failed.fetch_add(1, Ordering::Relaxed);
+    }
+    answer(stream, reply);
+}
+
+fn ingest(body: &[u8], store: &Mutex<EventStore>, counters: &Counters) -> Reply {
+    let events = match events_of(body) {
+        Ok(events) => events,
+        Err(message) => return error_reply(400, "Bad Request", message),
+    };
+    // insert_batch validates every event before writing any
+    match store.lock().unwrap().insert_batch(events) {
+        Ok(write) => {
+            counters
+                .ingested
+                .fetch_add((write.inserted + write.replaced) as u64, Ordering::Relaxed);
+            (
+                200,
+                "OK",
+                json!({ "inserted": write.inserted, "replaced": write.replaced }),
+            )
+        }
+        Err(e) if e.status == Status::InvalidArg => error_reply(400, "Bad Request", e.reason),
+        Err(e) => error_reply(500, "Internal Server Error", e.reason),
+    }
+}
+
+/// Frees a connection slot when its worker ends
+struct Slot(Arc<AtomicUsize>);
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// An HTTP server storing events from several editor instances in one store
+#[napi]
+pub struct IngestServer {
+    counters: Arc<Counters>,
+    stopped: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+    port: u32,
+}
+
+#[napi]
+impl IngestServer {
+    /**
+     * Open the event store at `store_path` and serve it on `host:port`
+     *
+     * `POST /ingest` takes a JSON array of events (or `{ events: [...] }`)
+     * and writes it with EventStore.insert_batch, answering
+     * `{ inserted, replaced }`. `GET /query` takes EventQuery fields as
+     * query parameters and answers the matching events. Every request needs
+     * `Authorization: Bearer <token>`. Each connection is read on its own
+     * thread, up to `max_connections`, and must send its whole request
+     * within `request_timeout_ms`; the threads share one connection to the
+     * store, so writes from all clients are serialized.
+     */
+    #[napi(factory)]
+    pub fn start(store_path: String, port: u32, options: IngestServerOptions) -> Result<Self> {
+        if options.token.is_empty() {
+            return Err(Error::new(
+                Status::InvalidArg,
+                "token must not be empty".to_string(),
+            ));
+        }
+        let store = Arc::new(Mutex::new(EventStore::new(store_path, None)?));
+        let host = options.host.unwrap_or_else(|| "127.0.0.1".to_string());
+        let address = format!("{}:{}", host, port);
+        let fail = |action: &str, error: std::io::Error| {
+            Error::new(
+                Status::GenericFailure,
+                format!("Failed to {} {}: {}", action, address, error),
+            )
+        };
+        let listener = TcpListener::bind(&address).map_err(|e| fail("listen on", e))?;
+        listener
+            .set_nonblocking(true)
+            .map_err(|e| fail("configure", e))?;
+        let port = listener
+            .local_addr()
+            .map_err(|e| fail("listen on", e))?
+            .port() as u32;
+        let max_connections = options
+            .max_connections
+            .unwrap_or(DEFAULT_MAX_CONNECTIONS)
+            .max(1) as usize;
+        let limits = Arc::new(Limits {
+            token: options.token,
+            max_body_bytes: options.max_body_bytes.unwrap_or(DEFAULT_MAX_BODY_BYTES) as usize,
+            max_query_rows: options.max_query_rows.unwrap_or(DEFAULT_MAX_QUERY_ROWS),
+            request_timeout: Duration::from_millis(
+                options
+                    .request_timeout_ms
+                    .unwrap_or(DEFAULT_REQUEST_TIMEOUT_MS) as u64,
+            ),
+        });
+        let counters = Arc::new(Counters::default());
+        let stopped = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let counters = counters.clone();
+            let stopped = stopped.clone();
+            std::thread::spawn(move || {
+                let connections = Arc::new(AtomicUsize::new(0));
+                let mut workers: Vec<JoinHandle<()>> = Vec::new();
+                while !stopped.load(Ordering::Relaxed) {
+                    match listener.accept() {
+                        Ok((stream, _)) => {
+                            if connections.load(Ordering::Relaxed) >= max_connections {
+                                counters.requests.fetch_add(1, Ordering::Relaxed);
+                                counters.failed.fetch_add(1, Ordering::Relaxed);
+                                let message = "Too many connections".to_string();
+                                answer(stream, error_reply(503, "Service Unavailable", message));
+                                continue;
+                            }
+                            connections.fetch_add(1, Ordering::Relaxed);
+                            let slot = Slot(connections.clone());
+                            let (store, limits, counters) =
+                                (store.clone(), limits.clone(), counters.clone());
+                            workers.retain(|worker| !worker.is_finished());
+                            workers.push(std::thread::spawn(move || {
+                                let _slot = slot;
+                                serve(stream, &store, &limits, &counters)
+                            }));
+                        }
+                        // WouldBlock when idle; other accept errors are per-connection
+                        Err(_) => std::thread::sleep(POLL_INTERVAL),
+                    }
+                }
+                // A stalled client holds its worker for request_timeout at
+                // most; after that this thread has the only store reference
+                for worker in workers {
+                    let _ = worker.join();
+                }
+                let _ = store.lock().unwrap().close();
+            })
+        };
+        Ok(IngestServer {
+            counters,
+            stopped,
+            thread: Some(thread),
+            port,
+        })
+    }
+
+    /// Request and event counts since start
+    #[napi]
+    pub fn stats(&self) -> IngestServerStats {
+        let load = |counter: &AtomicU64| counter.load(Ordering::Relaxed) as f64;
+        IngestServerStats {
+            requests: load(&self.counters.requests),
+            ingested: load(&self.counters.ingested),
+            unauthorized: load(&self.counters.unauthorized),
+            failed: load(&self.counters.failed),
+        }
+    }
+
+    /// The port being listened on
+    #[napi(getter)]
+    pub fn port(&self) -> u32 {
+        self.port
+    }
+
+    /// Stop listening and close the store; waits for requests in progress to finish
+    #[napi]
+    pub fn stop(&mut self) {
+        self.stopped.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for IngestServer {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
@@ -0,0 +1,292 @@
+/*!
+ * Session segmentation and daily summaries
+ */
+
+use crate::events::{
+    sorted_by_time, ActivityEvent, DEFAULT_IDLE_GAP_MINUTES, MS_PER_DAY, MS_PER_MINUTE,
+};
+use ahash::AHashMap;
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+
+const STANDUP_TOP_FILES: usize = 5;
+
+/// A contiguous stretch of activity with no idle gap longer than the threshold
+#[napi(object)]
+pub struct SessionSegment {
+    pub start: i64,
+    pub end: i64,
+    pub duration_ms: i64,
+    pub event_count: u32,
+    pub file_count: u32,
+}
+
+/// Per-file activity totals
+#[napi(object)]
+pub struct FileActivity {
+    pub file_path: String,
+    pub edits: u32,
+    pub lines_added: i64,
+    pub lines_removed: i64,
+}
+
+/// Structured standup summary plus a ready-to-paste text rendering
+#[napi(object)]
+pub struct StandupSummary {
+    pub date: String,
+    pub sessions: Vec<SessionSegment>,
+    pub active_minutes: i64,
+    pub files_touched: u32,
+    pub top_files: Vec<FileActivity>,
+    pub resolved_errors: Vec<String>,
+    pub text: String,
+}
+
+/**
+ * Split events into sessions wherever the gap between consecutive events
+ * exceeds the idle threshold
+ *
+ * @param events - Activity events in any order
+ * @param idle_gap_minutes - Gap that ends a session (default: 30)
+ * @returns Sessions in chronological order
+ */
+#[napi]
+pub fn segment_sessions(
+    events: Vec<ActivityEvent>,
+    idle_gap_minutes: Option<u32>,
+) -> Result<Vec<SessionSegment>> {
+    let sorted = sorted_by_time(&events);
+    let gap_ms = idle_gap_minutes.unwrap_or(DEFAULT_IDLE_GAP_MINUTES) as i64 * MS_PER_MINUTE;
+    Ok(segment(&sorted, gap_ms))
+}
+
+/// Segment events that are already sorted by timestamp
+pub(crate) fn segment(sorted: &[&ActivityEvent], gap_ms: i64) -> Vec<SessionSegment> {
+    let mut sessions = Vec::new();
+    let mut start_index = 0;
+
+    for i in 1..=sorted.len() {
+        let is_boundary =
+            i == sorted.len() || sorted[i].timestamp - sorted[i - 1].timestamp > gap_ms;
+        if is_boundary && i > start_index {
+            sessions.push(build_segment(&sorted[start_index..i]));
+            start_index = i;
+        }
+    }
+
+    sessions
+}
+
+fn build_segment(events: &[&ActivityEvent]) -> SessionSegment {
+    let start = events[0].timestamp;
+    let end = events[events.len() - 1].timestamp;
+    let mut files: Vec<&str> = events
+        .iter()
+        .filter_map(|e| e.file_path.as_deref())
+        .collect();
+    files.sort_unstable();
+    files.dedup();
+
+    SessionSegment {
+        start,
+        end,
+        duration_ms: end - start,
+        event_count: events.len() as u32,
+        file_count: files.len() as u32,
+    }
+}
+
+/**
+ * Generate a short "yesterday I…" standup summary for one day
+ *
+ * Combines session segmentation, the most-edited files, and errors that
+ * were resolved during the day into text that can be pasted into chat tools.
+ *
+ * @param events - Activity events (only those on `date` are used)
+ * @param date - Day to summarize as YYYY-MM-DD
+ * @param utc_offset_minutes - Local offset from UTC, e.g. `-new Date().getTimezoneOffset()` (default: 0)
+ * @returns StandupSummary with structured fields and rendered text
+ */
+#[napi]
+pub fn generate_standup(
+    events: Vec<ActivityEvent>,
+    date: String,
+    utc_offset_minutes: Option<i32>,
+) -> Result<StandupSummary> {
+    let offset_ms = utc_offset_minutes.unwrap_or(0) as i64 * MS_PER_MINUTE;
+    let day_start = parse_date(&date).ok_or_else(|| {
+        Error::new(
+            Status::InvalidArg,
+            format!("Invalid date '{}', expected YYYY-MM-DD", date),
+        )
+    })? - offset_ms;
+    let day_end = day_start + MS_PER_DAY;
+
+    let day_events: Vec<&ActivityEvent> = sorted_by_time(&events)
+        .into_iter()
+        .filter(|e| e.timestamp >= day_start && e.timestamp < day_end)
+        .collect();
+
+    let sessions = segment(&day_events, DEFAULT_IDLE_GAP_MINUTES as i64 * MS_PER_MINUTE);
+    let active_minutes = sessions.iter().map(|s| s.duration_ms).sum::<i64>() / MS_PER_MINUTE;
+
+    let mut by_file: AHashMap<&str, FileActivity> = AHashMap::new();
+    for event in &day_events {
+        if let Some(path) = event.file_path.as_deref() {
+            let entry = by_file.entry(path).or_insert_with(|| FileActivity {
+                file_path: path.to_string(),
+                edits: 0,
+                lines_added: 0,
+                lines_removed: 0,
+            });
+            entry.edits += 1;
+            entry.lines_added += event.lines_added.unwrap_or(0) as i64;
+            entry.lines_removed += event.lines_removed.unwrap_or(0) as i64;
+        }
+    }
+    let files_touched = by_file.len() as u32;
+    let mut top_files: Vec<FileActivity> = by_file.into_values().collect();
+    top_files.sort_by(|a, b| {
+        (b.lines_added + b.lines_removed, b.edits)
+            .cmp(&(a.lines_added + a.lines_removed, a.edits))
+            .then_with(|| a.file_path.cmp(&b.file_path))
+    });
+    top_files.truncate(STANDUP_TOP_FILES);
+
+    let mut resolved_errors: Vec<String> = day_events
+        .iter()
+        .filter(|e| e.is_error() && e.resolved == Some(true))
+        .map(|e| e.details.clone().unwrap_or_else(|| e.event_type.clone()))
+        .collect();
+    resolved_errors.dedup();
+
+    let text = render_standup(
+        &date,
+        &sessions,
+        active_minutes,
+        files_touched,
+        &top_files,
+        &resolved_errors,
+        offset_ms,
+    );
+
+    Ok(StandupSummary {
+        date,
+        sessions,
+        active_minutes,
+        files_touched,
+        top_files,
+        resolved_errors,
+        text,
+    })
+}
+
+fn render_standup(
+    date: &str,
+    sessions: &[SessionSegment],
+    active_minutes: i64,
+    files_touched: u32,
+    top_files: &[FileActivity],
+    resolved_errors: &[String],
+    offset_ms: i64,
+) -> String {
+    let mut lines = vec![format!("Yesterday ({}) I:", date)];
+
+    if sessions.is_empty() {
+        lines.push("- Had no recorded coding activity".to_string());
+        return lines.join("\n");
+    }
+
+    let spans: Vec<String> = sessions
+        .iter()
+        .map(|s| {
+            format!(
+                "{}–{}",
+                clock_time(s.start + offset_ms),
+                clock_time(s.end + offset_ms)
+            )
+        })
+        .collect();
+    lines.push(format!(
+        "- Worked {} across {} session{} ({})",
+        format_minutes(active_minutes),
+        sessions.len(),
+        if sessions.len() == 1 { "" } else { "s" },
+        spans.join(", ")
+    ));
+
+    if !top_files.is_empty() {
+        let files: Vec<String> = top_files
+            .iter()
+            .map(|f| format!("{} (+{}/-{})", f.file_path, f.lines_added, f.lines_removed))
+            .collect();
+        lines.push(format!(
+            "- Touched {} file{}, mostly {}",
+            files_touched,
+            if files_touched == 1 { "" } else { "s" },
+            files.join(", ")
+        ));
+    }
+
+    if !resolved_errors.is_empty() {
+        lines.push(format!(
+            "- Resolved {} error{}: {}",
+            resolved_errors.len(),
+            if resolved_errors.len() == 1 { "" } else { "s" },
+            resolved_errors.join("; ")
+        ));
+    }
+
+    lines.join("\n")
+}
+
+fn format_minutes(minutes: i64) -> String {
+    if minutes >= 60 {
+        format!("{}h {}m", minutes / 60, minutes % 60)
+    } else {
+        format!("{}m", minutes)
+    }
+}
+
+/// HH:MM of a (local-shifted) epoch-ms timestamp
+fn clock_time(timestamp: i64) -> String {
+    let minutes_of_day = timestamp.rem_euclid(MS_PER_DAY) / MS_PER_MINUTE;
+    format!("{:02}:{:02}", minutes_of_day / 60, minutes_of_day % 60)
+}
+
+/// Parse YYYY-MM-DD into epoch milliseconds at UTC midnight
+pub(crate) fn parse_date(date: &str) -> Option<i64> {
+    let mut parts = date.trim().splitn(3, '-');
+    let year: i64 = parts.next()?.parse().ok()?;
+    let month: i64 = parts.next()?.parse().ok()?;
+    let day: i64 = parts.next()?.parse().ok()?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    Some(days_from_civil(year, month, day) * MS_PER_DAY)
+}
+
+/// Days since 1970-01-01 for a proleptic Gregorian date (Howard Hinnant's algorithm)
+pub(crate) fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// (year, month, day) of a count of days since 1970-01-01; the inverse of days_from_civil
+pub(crate) fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
@@ -0,0 +1,176 @@
+/*!
+ * Code shape statistics
+ * Nesting depth and function length distribution for code health charts
+ */
+
+use crate::comments::{comment_syntax, strip_comments_and_strings, CommentScanner, LineKind};
+use crate::functions::find_functions;
+use crate::language::guess_language;
+use crate::stats::quantile;
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+
+/// Upper bounds (inclusive) of the function length histogram buckets; the last bucket is open
+const LENGTH_BUCKETS: [u32; 4] = [10, 25, 50, 100];
+
+/// Number of functions whose length falls in one range of lines
+#[napi(object)]
+pub struct LengthBucket {
+    /// e.g. "1-10" or "101+"
+    pub label: String,
+    pub min: u32,
+    /// Inclusive upper bound; absent for the open-ended last bucket
+    pub max: Option<u32>,
+    pub count: u32,
+}
+
+/// Nesting and function length statistics for one file
+#[napi(object)]
+pub struct CodeShapeStats {
+    pub language: String,
+    /// Deepest block nesting of any code line (0 = top level)
+    pub max_nesting_depth: u32,
+    /// Mean block nesting over code lines
+    pub average_nesting_depth: f64,
+    pub function_count: u32,
+    /// Function lengths in lines; all zero when no functions were found
+    pub min_function_length: u32,
+    pub max_function_length: u32,
+    pub mean_function_length: f64,
+    pub median_function_length: f64,
+    pub p90_function_length: f64,
+    /// Function counts for 1-10, 11-25, 26-50, 51-100, and 101+ lines
+    pub function_length_histogram: Vec<LengthBucket>,
+}
+
+/// Whether nesting follows `{}` blocks rather than indentation
+fn uses_braces(language: &str) -> bool {
+    matches!(
+        language,
+        "rust"
+            | "javascript"
+            | "typescript"
+            | "go"
+            | "java"
+            | "cpp"
+            | "c"
+            | "csharp"
+            | "swift"
+            | "kotlin"
+            | "scala"
+            | "dart"
+            | "php"
+            | "css"
+            | "scss"
+            | "less"
+    )
+}
+
+/**
+ * Calculate nesting depth and function length statistics
+ *
+ * Nesting is measured at the start of each code line: open `{}` blocks for
+ * brace languages, indentation levels otherwise. Functions are located the
+ * same way as calculate_complexity (nested functions are counted too).
+ *
+ * @param content - Source code to measure
+ * @param language - Language name (detected from content if not provided)
+ * @returns Nesting and function length statistics
+ */
+#[napi]
+pub fn calculate_code_shape(content: String, language: Option<String>) -> Result<CodeShapeStats> {
+    let language = match language {
+        Some(language) => language,
+        None => guess_language(&content, None).language,
+    };
+    let code = strip_comments_and_strings(&content, &language);
+    let braces = uses_braces(&language);
+    let mut scanner = CommentScanner::new(comment_syntax(&language));
+
+    let mut brace_depth: i64 = 0;
+    let mut indents: Vec<usize> = Vec::new();
+    let mut max_depth = 0u32;
+    let mut depth_sum = 0u64;
+    let mut code_lines = 0u64;
+
+    for (original, stripped) in content.lines().zip(code.lines()) {
+        let is_code = scanner.classify(original) == LineKind::Code;
+        let trimmed = stripped.trim_start();
+        let depth = if braces {
+            // A line that starts by closing a block sits at the outer level
+            let leading_closes = trimmed.bytes().take_while(|b| *b == b'}').count() as i64;
+            let depth = (brace_depth - leading_closes).max(0) as u32;
+            for b in stripped.bytes() {
+                match b {
+                    b'{' => brace_depth += 1,
+                    b'}' => brace_depth = (brace_depth - 1).max(0),
+                    _ => {}
+                }
+            }
+            depth
+        } else {
+            let indent = original.len() - original.trim_start().len();
+            if is_code {
+                while indents.last().is_some_and(|open| *open >= indent) {
+                    indents.pop();
+                }
+                let depth = indents.len() as u32;
+                indents.push(indent);
+                depth
+            } else {
+                0
+            }
+        };
+        if is_code {
+            code_lines += 1;
+            depth_sum += depth as u64;
+            max_depth = max_depth.max(depth);
+        }
+    }
+
+    let mut lengths: Vec<u32> = find_functions(&code, &language)
+        .iter()
+        .map(|span| span.end_line - span.start_line + 1)
+        .collect();
+    lengths.sort_unstable();
+    let sorted: Vec<f64> = lengths.iter().map(|l| *l as f64).collect();
+
+    let mut histogram: Vec<LengthBucket> = Vec::with_capacity(LENGTH_BUCKETS.len() + 1);
+    let mut min = 1;
+    for max in LENGTH_BUCKETS {
+        histogram.push(LengthBucket {
+            label: format!("{}-{}", min, max),
+            min,
+            max: Some(max),
+            count: lengths.iter().filter(|l| (min..=max).contains(*l)).count() as u32,
+        });
+        min = max + 1;
+    }
+    histogram.push(LengthBucket {
+        label: format!("{}+", min),
+        min,
+        max: None,
+        count: lengths.iter().filter(|l| **l >= min).count() as u32,
+    });
+
+    Ok(CodeShapeStats {
+        language,
+        max_nesting_depth: max_depth,
+        average_nesting_depth: if code_lines > 0 {
+            depth_sum as f64 / code_lines as f64
+        } else {
+            0.0
+        },
+        function_count: lengths.len() as u32,
+        min_function_length: lengths.first().copied().unwrap_or(0),
+        max_function_length: lengths.last().copied().unwrap_or(0),
+        mean_function_length: if sorted.is_empty() {
+            0.0
+        } else {
+            sorted.iter().sum::<f64>() / sorted.len() as f64
+        },
+        median_function_length: quantile(&sorted, 0.5),
+        p90_function_length: quantile(&sorted, 0.9),
+        function_length_histogram: histogram,
+    })
+}
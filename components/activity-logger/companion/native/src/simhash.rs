@@ -0,0 +1,167 @@
+/*!
+ * SimHash fingerprints
+ * 64-bit locality-sensitive hashes for spotting near-duplicate snapshots
+ */
+
+use crate::experiments::fnv1a;
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+
+/// Options for simhash
+#[napi(object)]
+pub struct SimHashOptions {
+    /// Consecutive tokens hashed together as one feature (default: 3)
+    pub shingle_size: Option<u32>,
+    /// Fold letters to lowercase before hashing (default: false)
+    pub ignore_case: Option<bool>,
+}
+
+/// A candidate within range of the fingerprint, from find_near_duplicates
+#[napi(object)]
+pub struct NearDuplicate {
+    /// Position in the candidates array
+    pub index: u32,
+    pub fingerprint: String,
+    /// Differing bits, 0-64
+    pub distance: u32,
+}
+
+/// Words (letter, digit, and underscore runs) and single punctuation characters
+pub(crate) fn tokens(content: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut word_start = None;
+    for (i, c) in content.char_indices() {
+        if c.is_alphanumeric() || c == '_' {
+            word_start.get_or_insert(i);
+            continue;
+        }
+        if let Some(start) = word_start.take() {
+            tokens.push(&content[start..i]);
+        }
+        if !c.is_whitespace() {
+            tokens.push(&content[i..i + c.len_utf8()]);
+        }
+    }
+    if let Some(start) = word_start {
+        tokens.push(&content[start..]);
+    }
+    tokens
+}
+
+/// Charikar's SimHash over token shingles
+pub(crate) fn fingerprint(content: &str, shingle_size: usize, ignore_case: bool) -> u64 {
+    let folded;
+    let content = if ignore_case {
+        folded = content.to_lowercase();
+        &folded
+    } else {
+        content
+    };
+    let tokens = tokens(content);
+    if tokens.is_empty() {
+        return 0;
+    }
+    // Content shorter than one shingle is a single feature
+    let size = shingle_size.clamp(1, tokens.len());
+    let mut weights = [0i64; 64];
+    for shingle in tokens.windows(size) {
+        let hash = fnv1a(shingle);
+        for (bit, weight) in weights.iter_mut().enumerate() {
+            if hash >> bit & 1 == 1 {
+                *weight += 1;
+            } else {
+                *weight -= 1;
+            }
+        }
+    }
+    weights
+        .iter()
+        .enumerate()
+        .filter(|(_, weight)| **weight > 0)
+        .fold(0u64, |hash, (bit, _)| hash | 1 << bit)
+}
+
+fn parse_fingerprint(fingerprint: &str) -> Result<u64> {
+    if fingerprint.is_empty() || fingerprint.len() > 16 {
+        return Err(invalid_fingerprint(fingerprint));
+    }
+    u64::from_str_radix(fingerprint, 16).map_err(|_| invalid_fingerprint(fingerprint))
+}
+
+fn invalid_fingerprint(fingerprint: &str) -> Error {
+    Error::new(
+        Status::InvalidArg,
+        format!(
+            "Invalid fingerprint '{}': expected up to 16 hex digits",
+            fingerprint
+        ),
+    )
+}
+
+/**
+ * SimHash fingerprint of a text
+ *
+ * Each run of `shingle_size` tokens (words and punctuation, whitespace
+ * ignored) is hashed, and every bit of the fingerprint takes the majority
+ * vote of those hashes. Texts that share most of their shingles, such as
+ * two snapshots differing only in a timestamp comment, get fingerprints a
+ * few bits apart; unrelated texts differ in about half the bits. The hash
+ * is stable across platforms and releases, so fingerprints can be stored.
+ *
+ * @param content - Text to fingerprint
+ * @param options - Shingle size and case folding
+ * @returns 16 lowercase hex digits; all zeros for content without tokens
+ */
+#[napi]
+pub fn simhash(content: String, options: Option<SimHashOptions>) -> String {
+    let shingle_size = options.as_ref().and_then(|o| o.shingle_size).unwrap_or(3);
+    let ignore_case = options
+        .as_ref()
+        .and_then(|o| o.ignore_case)
+        .unwrap_or(false);
+    format!(
+        "{:016x}",
+        fingerprint(&content, shingle_size as usize, ignore_case)
+    )
+}
+
+/// Number of bits that differ between two simhash fingerprints
+#[napi]
+pub fn hamming_distance(a: String, b: String) -> Result<u32> {
+    Ok((parse_fingerprint(&a)? ^ parse_fingerprint(&b)?).count_ones())
+}
+
+/**
+ * Stored fingerprints within `max_distance` bits of a new one
+ *
+ * A linear scan, but only an XOR and a popcount per candidate, so checking
+ * a snapshot against thousands of stored fingerprints before saving it is
+ * cheap. About 3 bits suits whole-file snapshots; short texts need more.
+ *
+ * @param fingerprint - Fingerprint from simhash
+ * @param candidates - Fingerprints to compare against
+ * @param max_distance - Most differing bits to report (default: 3)
+ * @returns Matches, closest first, ties in input order
+ */
+#[napi]
+pub fn find_near_duplicates(
+    fingerprint: String,
+    candidates: Vec<String>,
+    max_distance: Option<u32>,
+) -> Result<Vec<NearDuplicate>> {
+    let target = parse_fingerprint(&fingerprint)?;
+    let max_distance = max_distance.unwrap_or(3);
+    let mut found = Vec::new();
+    for (index, candidate) in candidates.into_iter().enumerate() {
+        let distance = (target ^ parse_fingerprint(&candidate)?).count_ones();
+        if distance <= max_distance {
+            found.push(NearDuplicate {
+                index: index as u32,
+                fingerprint: candidate,
+                distance,
+            });
+        }
+    }
+    found.sort_by_key(|duplicate| (duplicate.distance, duplicate.index));
+    Ok(found)
+}
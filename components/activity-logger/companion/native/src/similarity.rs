@@ -0,0 +1,261 @@
+/*!
+ * Pairwise similarity
+ * All-pairs similarity of many texts in one call, for clustering sessions by content,
+ * and one-sided shingle containment for tracing where pasted text came from
+ */
+
+use crate::distance::Metric;
+use crate::experiments::fnv1a;
+use crate::simhash::tokens;
+use ahash::AHashSet;
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use rayon::prelude::*;
+use similar::TextDiff;
+
+/// Two texts at least as similar as the threshold
+#[napi(object)]
+pub struct SimilarPair {
+    /// Indices into the texts array, a < b
+    pub a: u32,
+    pub b: u32,
+    pub similarity: f64,
+}
+
+/// Result of similarity_matrix
+#[napi(object)]
+pub struct SimilarityMatrix {
+    pub size: u32,
+    /// Row per text, symmetric with 1 on the diagonal; left out when a threshold is given
+    pub matrix: Option<Vec<Vec<f64>>>,
+    /// Pairs meeting the threshold, most similar first; empty without a threshold
+    pub pairs: Vec<SimilarPair>,
+}
+
+/// Options for containment and find_sources
+#[napi(object)]
+pub struct ContainmentOptions {
+    /// Consecutive tokens per shingle; smaller catches shorter pastes but more coincidences (default: 3)
+    pub shingle_size: Option<u32>,
+    /// find_sources: lowest containment reported (default: 0.5)
+    pub min_containment: Option<f64>,
+    /// find_sources: most sources returned (default: all)
+    pub limit: Option<u32>,
+}
+
+/// A candidate source holding much of the pasted text
+#[napi(object)]
+pub struct ContainmentMatch {
+    /// Position in the candidates array
+    pub index: u32,
+    /// Fraction of the text's shingles found in the candidate, 0-1
+    pub containment: f64,
+}
+
+/// Each text in the form its metric compares
+enum Prepared<'a> {
+    Text(&'a str),
+    Shingles(AHashSet<u64>),
+    Chars(Vec<char>),
+}
+
+/// Token shingles, hashed as in MinHashIndex
+pub(crate) fn shingles(text: &str, size: usize) -> AHashSet<u64> {
+    let tokens = tokens(text);
+    let size = size.clamp(1, tokens.len().max(1));
+    tokens.windows(size).map(fnv1a).collect()
+}
+
+fn jaccard(a: &AHashSet<u64>, b: &AHashSet<u64>) -> f64 {
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+    let (small, large) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    let shared = small.iter().filter(|x| large.contains(*x)).count();
+    shared as f64 / (a.len() + b.len() - shared) as f64
+}
+
+/// Share of `a`'s shingles that `b` has; 0 when `a` has none
+pub(crate) fn contained(a: &AHashSet<u64>, b: &AHashSet<u64>) -> f64 {
+    if a.is_empty() {
+        return 0.0;
+    }
+    a.iter().filter(|x| b.contains(*x)).count() as f64 / a.len() as f64
+}
+
+fn compare(a: &Prepared, b: &Prepared, metric: Option<Metric>) -> f64 {
+    match (a, b, metric) {
+        (Prepared::Text(a), Prepared::Text(b), _) => TextDiff::from_chars(*a, *b).ratio() as f64,
+        (Prepared::Shingles(a), Prepared::Shingles(b), _) => jaccard(a, b),
+        (Prepared::Chars(a), Prepared::Chars(b), Some(metric)) => metric.similarity(a, b),
+        _ => 0.0,
+    }
+}
+
+/**
+ * Similarity of every pair of texts, computed in parallel
+ *
+ * Each pair is compared once. "ratio" is calculate_similarity's character
+ * diff ratio, exact but slow on long texts; "jaccard" compares sets of
+ * token 3-shingles and suits whole files and sessions; the edit metrics
+ * are for short strings, normalized to 1 - edits / longer length.
+ *
+ * @param texts - Texts to compare
+ * @param metric - "ratio" (default), "jaccard", "levenshtein", "damerau", or "jaro-winkler"
+ * @param threshold - Report only pairs at least this similar, as a sparse list
+ * @returns The full matrix, or the pairs meeting the threshold
+ */
+#[napi]
+pub fn similarity_matrix(
+    texts: Vec<String>,
+    metric: Option<String>,
+    threshold: Option<f64>,
+) -> Result<SimilarityMatrix> {
+    let (metric, prepared): (Option<Metric>, Vec<Prepared>) = match metric.as_deref() {
+        None | Some("ratio") => (None, texts.iter().map(|t| Prepared::Text(t)).collect()),
+        Some("jaccard") => (
+            None,
+            texts
+                .par_iter()
+                .map(|t| Prepared::Shingles(shingles(t, 3)))
+                .collect(),
+        ),
+        Some(other) => {
+            let metric = Metric::parse(Some(other)).map_err(|_| {
+                Error::new(
+                    Status::InvalidArg,
+                    format!(
+                        "Unknown metric '{}' (expected ratio, jaccard, levenshtein, damerau, or jaro-winkler)",
+                        other
+                    ),
+                )
+            })?;
+            (
+                Some(metric),
+                texts
+                    .iter()
+                    .map(|t| Prepared::Chars(t.chars().collect()))
+                    .collect(),
+            )
+        }
+    };
+
+    let n = prepared.len();
+    // Upper triangle, row by row
+    let rows: Vec<Vec<f64>> = (0..n)
+        .into_par_iter()
+        .map(|i| {
+            (i + 1..n)
+                .map(|j| compare(&prepared[i], &prepared[j], metric))
+                .collect()
+        })
+        .collect();
+
+    if let Some(threshold) = threshold {
+        let mut pairs: Vec<SimilarPair> = rows
+            .iter()
+            .enumerate()
+            .flat_map(|(i, row)| {
+                row.iter()
+                    .enumerate()
+                    .map(move |(offset, similarity)| SimilarPair {
+                        a: i as u32,
+                        b: (i + 1 + offset) as u32,
+                        similarity: *similarity,
+                    })
+            })
+            .filter(|pair| pair.similarity >= threshold)
+            .collect();
+        pairs.sort_by(|x, y| {
+            y.similarity
+                .total_cmp(&x.similarity)
+                .then(x.a.cmp(&y.a))
+                .then(x.b.cmp(&y.b))
+        });
+        return Ok(SimilarityMatrix {
+            size: n as u32,
+            matrix: None,
+            pairs,
+        });
+    }
+
+    let mut matrix = vec![vec![1.0; n]; n];
+    for (i, row) in rows.iter().enumerate() {
+        for (offset, similarity) in row.iter().enumerate() {
+            let j = i + 1 + offset;
+            matrix[i][j] = *similarity;
+            matrix[j][i] = *similarity;
+        }
+    }
+    Ok(SimilarityMatrix {
+        size: n as u32,
+        matrix: Some(matrix),
+        pairs: Vec::new(),
+    })
+}
+
+/**
+ * Fraction of `a`'s token shingles that also appear in `b`
+ *
+ * One-sided, unlike Jaccard: a block pasted into a much larger file
+ * scores near 1 against that file, where a symmetric score is pulled
+ * toward 0 by everything else in it. Whitespace and formatting don't
+ * change tokens, so reindented pastes still match.
+ *
+ * @param a - The smaller text, e.g. a pasted block
+ * @param b - The text it may have come from
+ * @param options - Shingle size
+ * @returns 0-1; 0 when `a` has no tokens
+ */
+#[napi]
+pub fn containment(a: String, b: String, options: Option<ContainmentOptions>) -> f64 {
+    let size = options.and_then(|o| o.shingle_size).unwrap_or(3) as usize;
+    contained(&shingles(&a, size), &shingles(&b, size))
+}
+
+/**
+ * Candidates that contain most of `text`, for copy-paste provenance
+ *
+ * Scores every candidate (earlier files, chat responses, clipboard
+ * captures) by containment of `text` in parallel.
+ *
+ * @param text - The pasted block
+ * @param candidates - Possible sources
+ * @param options - Shingle size, containment floor, and result limit
+ * @returns Matches, highest containment first, ties in input order
+ */
+#[napi]
+pub fn find_sources(
+    text: String,
+    candidates: Vec<String>,
+    options: Option<ContainmentOptions>,
+) -> Vec<ContainmentMatch> {
+    let size = options.as_ref().and_then(|o| o.shingle_size).unwrap_or(3) as usize;
+    let min_containment = options
+        .as_ref()
+        .and_then(|o| o.min_containment)
+        .unwrap_or(0.5);
+    let limit = options
+        .and_then(|o| o.limit)
+        .map_or(usize::MAX, |limit| limit as usize);
+    let pasted = shingles(&text, size);
+    if pasted.is_empty() {
+        return Vec::new();
+    }
+    let mut matches: Vec<ContainmentMatch> = candidates
+        .par_iter()
+        .enumerate()
+        .map(|(index, candidate)| ContainmentMatch {
+            index: index as u32,
+            containment: contained(&pasted, &shingles(candidate, size)),
+        })
+        .filter(|m| m.containment > 0.0 && m.containment >= min_containment)
+        .collect();
+    matches.sort_by(|a, b| {
+        b.containment
+            .total_cmp(&a.containment)
+            .then(a.index.cmp(&b.index))
+    });
+    matches.truncate(limit);
+    matches
+}
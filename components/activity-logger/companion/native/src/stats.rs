@@ -0,0 +1,664 @@
+/*!
+ * Statistics utilities
+ * Bootstrap confidence intervals, two-sample tests, and effect sizes over metric arrays
+ */
+
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+
+pub(crate) const DEFAULT_BOOTSTRAP_ITERATIONS: u32 = 1000;
+pub(crate) const DEFAULT_CONFIDENCE: f64 = 0.95;
+pub(crate) const DEFAULT_SEED: u32 = 0x5eed;
+const MAX_BOOTSTRAP_ITERATIONS: u32 = 100_000;
+
+/// Small deterministic PRNG (SplitMix64) so results are reproducible per seed
+pub(crate) struct SplitMix64(u64);
+
+impl SplitMix64 {
+    pub(crate) fn new(seed: u64) -> Self {
+        SplitMix64(seed)
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform index in 0..n
+    pub(crate) fn below(&mut self, n: usize) -> usize {
+        ((self.next_u64() as u128 * n as u128) >> 64) as usize
+    }
+}
+
+/// A point estimate with a two-sided confidence interval
+pub(crate) struct Interval {
+    pub value: f64,
+    pub lower: f64,
+    pub upper: f64,
+}
+
+/// Linear-interpolated quantile of an ascending slice
+pub(crate) fn quantile(sorted: &[f64], q: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let position = q.clamp(0.0, 1.0) * (sorted.len() - 1) as f64;
+    let below = position.floor() as usize;
+    let above = position.ceil() as usize;
+    let fraction = position - below as f64;
+    sorted[below] + (sorted[above] - sorted[below]) * fraction
+}
+
+/// Percentile bootstrap for a ratio of sums over (numerator, denominator) samples
+///
+/// With every denominator 1 this is the bootstrap of the mean. Returns None
+/// when there are no samples or the denominators sum to zero.
+pub(crate) fn bootstrap_ratio(
+    samples: &[(f64, f64)],
+    iterations: u32,
+    confidence: f64,
+    seed: u64,
+) -> Option<Interval> {
+    let ratio =
+        |numerator: f64, denominator: f64| (denominator != 0.0).then(|| numerator / denominator);
+    let (numerator, denominator) = samples
+        .iter()
+        .fold((0.0, 0.0), |(n, d), (x, w)| (n + x, d + w));
+    let value = ratio(numerator, denominator)?;
+
+    let mut rng = SplitMix64::new(seed);
+    let estimates: Vec<f64> = (0..iterations)
+        .filter_map(|_| {
+            let (mut n, mut d) = (0.0, 0.0);
+            for _ in 0..samples.len() {
+                let (x, w) = samples[rng.below(samples.len())];
+                n += x;
+                d += w;
+            }
+            ratio(n, d)
+        })
+        .collect();
+    Some(percentile_interval(value, estimates, confidence))
+}
+
+/// Percentile bootstrap for the median
+pub(crate) fn bootstrap_median(
+    values: &[f64],
+    iterations: u32,
+    confidence: f64,
+    seed: u64,
+) -> Option<Interval> {
+    if values.is_empty() {
+        return None;
+    }
+    let median = |sample: &mut Vec<f64>| {
+        sample.sort_by(|a, b| a.total_cmp(b));
+        quantile(sample, 0.5)
+    };
+    let value = median(&mut values.to_vec());
+
+    let mut rng = SplitMix64::new(seed);
+    let mut sample = Vec::with_capacity(values.len());
+    let estimates: Vec<f64> = (0..iterations)
+        .map(|_| {
+            sample.clear();
+            sample.extend((0..values.len()).map(|_| values[rng.below(values.len())]));
+            median(&mut sample)
+        })
+        .collect();
+    Some(percentile_interval(value, estimates, confidence))
+}
+
+fn percentile_interval(value: f64, mut estimates: Vec<f64>, confidence: f64) -> Interval {
+    if estimates.is_empty() {
+        return Interval {
+            value,
+            lower: value,
+            upper: value,
+        };
+    }
+    estimates.sort_by(|a, b| a.total_cmp(b));
+
+    let tail = (1.0 - confidence) / 2.0;
+    Interval {
+        value,
+        lower: quantile(&estimates, tail),
+        upper: quantile(&estimates, 1.0 - tail),
+    }
+}
+
+/// Reject iteration counts and confidence levels the bootstrap can't honour
+pub(crate) fn validate_bootstrap(iterations: u32, confidence: f64) -> Result<()> {
+    if iterations == 0 || iterations > MAX_BOOTSTRAP_ITERATIONS {
+        return Err(Error::new(
+            Status::InvalidArg,
+            format!(
+                "iterations must be between 1 and {}",
+                MAX_BOOTSTRAP_ITERATIONS
+            ),
+        ));
+    }
+    if !(confidence > 0.0 && confidence < 1.0) {
+        return Err(Error::new(
+            Status::InvalidArg,
+            "confidence must be between 0 and 1".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+pub(crate) fn mean(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+/// Sample variance (n − 1 denominator)
+pub(crate) fn variance(values: &[f64]) -> f64 {
+    if values.len() < 2 {
+        return 0.0;
+    }
+    let m = mean(values);
+    values.iter().map(|v| (v - m).powi(2)).sum::<f64>() / (values.len() - 1) as f64
+}
+
+/// Trimmed fraction used by the trimmed mean when the caller doesn't choose one
+pub(crate) const DEFAULT_TRIM: f64 = 0.1;
+
+/// How a set of values is summarized into one central value
+#[derive(Clone, Copy)]
+pub(crate) enum Estimator {
+    Mean,
+    /// Mean after dropping this fraction of values from each end
+    TrimmedMean(f64),
+    Median,
+}
+
+impl Estimator {
+    pub(crate) fn parse(name: &str, trim: Option<f64>) -> Result<Self> {
+        match name {
+            "mean" => Ok(Estimator::Mean),
+            "median" => Ok(Estimator::Median),
+            "trimmed_mean" => {
+                let trim = trim.unwrap_or(DEFAULT_TRIM);
+                if !(0.0..0.5).contains(&trim) {
+                    return Err(Error::new(
+                        Status::InvalidArg,
+                        "trim must be at least 0 and below 0.5".to_string(),
+                    ));
+                }
+                Ok(Estimator::TrimmedMean(trim))
+            }
+            other => Err(Error::new(
+                Status::InvalidArg,
+                format!(
+                    "Unknown estimator '{}', expected mean, trimmed_mean, or median",
+                    other
+                ),
+            )),
+        }
+    }
+
+    /// Central value of `values`, which is sorted in place; 0 when empty
+    pub(crate) fn estimate(&self, values: &mut [f64]) -> f64 {
+        if values.is_empty() {
+            return 0.0;
+        }
+        match self {
+            Estimator::Mean => mean(values),
+            Estimator::Median => {
+                values.sort_by(|a, b| a.total_cmp(b));
+                quantile(values, 0.5)
+            }
+            Estimator::TrimmedMean(trim) => {
+                values.sort_by(|a, b| a.total_cmp(b));
+                let cut = (values.len() as f64 * trim).floor() as usize;
+                mean(&values[cut..values.len() - cut])
+            }
+        }
+    }
+}
+
+/// Median absolute deviation from the median (unscaled); 0 when empty
+pub(crate) fn median_absolute_deviation(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let median = quantile(&sorted, 0.5);
+    let mut deviations: Vec<f64> = sorted.iter().map(|v| (v - median).abs()).collect();
+    deviations.sort_by(|a, b| a.total_cmp(b));
+    quantile(&deviations, 0.5)
+}
+
+/// Outcome of Welch's unequal-variance two-sample t-test
+pub(crate) struct TTest {
+    pub t: f64,
+    pub degrees_of_freedom: f64,
+    /// Two-sided p-value
+    pub p_value: f64,
+}
+
+/// Welch's t-test of mean(b) − mean(a); None when either sample has fewer than two values
+pub(crate) fn welch_t_test(a: &[f64], b: &[f64]) -> Option<TTest> {
+    if a.len() < 2 || b.len() < 2 {
+        return None;
+    }
+    let (na, nb) = (a.len() as f64, b.len() as f64);
+    let (va, vb) = (variance(a) / na, variance(b) / nb);
+    let difference = mean(b) - mean(a);
+    let standard_error = (va + vb).sqrt();
+    if standard_error == 0.0 {
+        // Identical constant samples show no difference; distinct constants are certain
+        let p_value = if difference == 0.0 { 1.0 } else { 0.0 };
+        let t = if difference == 0.0 {
+            0.0
+        } else {
+            difference.signum() * f64::INFINITY
+        };
+        return Some(TTest {
+            t,
+            degrees_of_freedom: na + nb - 2.0,
+            p_value,
+        });
+    }
+    let t = difference / standard_error;
+    let degrees_of_freedom =
+        (va + vb).powi(2) / (va.powi(2) / (na - 1.0) + vb.powi(2) / (nb - 1.0));
+    Some(TTest {
+        t,
+        degrees_of_freedom,
+        p_value: student_t_two_sided(t, degrees_of_freedom),
+    })
+}
+
+/// Cohen's d of b relative to a, using the pooled standard deviation
+pub(crate) fn cohens_d(a: &[f64], b: &[f64]) -> f64 {
+    if a.len() < 2 || b.len() < 2 {
+        return 0.0;
+    }
+    let (na, nb) = (a.len() as f64, b.len() as f64);
+    let pooled = (((na - 1.0) * variance(a) + (nb - 1.0) * variance(b)) / (na + nb - 2.0)).sqrt();
+    if pooled == 0.0 {
+        return 0.0;
+    }
+    (mean(b) - mean(a)) / pooled
+}
+
+/// Two-sided tail probability P(|T| ≥ |t|) for Student's t with `df` degrees of freedom
+pub(crate) fn student_t_two_sided(t: f64, df: f64) -> f64 {
+    if !t.is_finite() {
+        return 0.0;
+    }
+    let x = df / (df + t * t);
+    regularized_incomplete_beta(x, df / 2.0, 0.5).clamp(0.0, 1.0)
+}
+
+/// ln Γ(x) via the Lanczos approximation
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 6] = [
+        76.180_091_729_471_46,
+        -86.505_320_329_416_77,
+        24.014_098_240_830_91,
+        -1.231_739_572_450_155,
+        0.001_208_650_973_866_179,
+        -0.000_005_395_239_384_953,
+    ];
+    let tmp = x + 5.5 - (x + 0.5) * (x + 5.5).ln();
+    let series = COEFFICIENTS
+        .iter()
+        .enumerate()
+        .fold(1.000_000_000_190_015, |sum, (i, c)| {
+            sum + c / (x + 1.0 + i as f64)
+        });
+    -tmp + (2.506_628_274_631_000_5 * series / x).ln()
+}
+
+/// Regularized incomplete beta I_x(a, b)
+fn regularized_incomplete_beta(x: f64, a: f64, b: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+    let front =
+        (ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln()).exp();
+    // The continued fraction converges fastest on this side of the mean
+    if x < (a + 1.0) / (a + b + 2.0) {
+        front * beta_continued_fraction(x, a, b) / a
+    } else {
+        1.0 - front * beta_continued_fraction(1.0 - x, b, a) / b
+    }
+}
+
+/// Lentz's method for the incomplete beta continued fraction
+fn beta_continued_fraction(x: f64, a: f64, b: f64) -> f64 {
+    const TINY: f64 = 1e-300;
+    const EPSILON: f64 = 1e-14;
+    let mut c = 1.0;
+    let mut d = 1.0 - (a + b) * x / (a + 1.0);
+    if d.abs() < TINY {
+        d = TINY;
+    }
+    d = 1.0 / d;
+    let mut result = d;
+
+    for m in 1..=300 {
+        let m = m as f64;
+        for numerator in [
+            m * (b - m) * x / ((a + 2.0 * m - 1.0) * (a + 2.0 * m)),
+            -(a + m) * (a + b + m) * x / ((a + 2.0 * m) * (a + 2.0 * m + 1.0)),
+        ] {
+            d = 1.0 + numerator * d;
+            if d.abs() < TINY {
+                d = TINY;
+            }
+            c = 1.0 + numerator / c;
+            if c.abs() < TINY {
+                c = TINY;
+            }
+            d = 1.0 / d;
+            result *= d * c;
+        }
+        if (d * c - 1.0).abs() < EPSILON {
+            break;
+        }
+    }
+
+    result
+}
+
+/// erfc(x) with fractional error below 1.2e-7 (Numerical Recipes' Chebyshev fit)
+fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1.0 / (1.0 + 0.5 * z);
+    let polynomial = [
+        -1.265_512_23,
+        1.000_023_68,
+        0.374_091_96,
+        0.096_784_18,
+        -0.186_288_06,
+        0.278_868_07,
+        -1.135_203_98,
+        1.488_515_87,
+        -0.822_152_23,
+        0.170_872_77,
+    ]
+    .iter()
+    .rev()
+    .fold(0.0, |acc, c| acc * t + c);
+    let result = t * (-z * z + polynomial).exp();
+    if x >= 0.0 {
+        result
+    } else {
+        2.0 - result
+    }
+}
+
+/// Two-sided tail probability P(|Z| ≥ |z|) for a standard normal
+fn normal_two_sided(z: f64) -> f64 {
+    erfc(z.abs() / std::f64::consts::SQRT_2).clamp(0.0, 1.0)
+}
+
+/// Average ranks (1-based) of `a` followed by `b`, plus the tie-correction term Σ(t³ − t)
+fn pooled_ranks(a: &[f64], b: &[f64]) -> (Vec<f64>, f64) {
+    let mut order: Vec<(f64, usize)> = a.iter().chain(b).copied().zip(0..).collect();
+    order.sort_by(|x, y| x.0.total_cmp(&y.0));
+    let mut ranks = vec![0.0; order.len()];
+    let mut ties = 0.0;
+    let mut i = 0;
+    while i < order.len() {
+        let mut j = i + 1;
+        while j < order.len() && order[j].0 == order[i].0 {
+            j += 1;
+        }
+        let rank = (i + j + 1) as f64 / 2.0;
+        for (_, index) in &order[i..j] {
+            ranks[*index] = rank;
+        }
+        let t = (j - i) as f64;
+        ties += t * t * t - t;
+        i = j;
+    }
+    (ranks, ties)
+}
+
+/// Mann–Whitney U of `b` over `a`: pairs where b > a, counting ties as half
+fn u_statistic(a: &[f64], b: &[f64], ranks: &[f64]) -> f64 {
+    let nb = b.len() as f64;
+    let rank_sum_b: f64 = ranks[a.len()..].iter().sum();
+    rank_sum_b - nb * (nb + 1.0) / 2.0
+}
+
+fn require_finite(values: &[f64], name: &str, min_len: usize) -> Result<()> {
+    if values.len() < min_len {
+        return Err(Error::new(
+            Status::InvalidArg,
+            format!("{} needs at least {} values", name, min_len),
+        ));
+    }
+    if values.iter().any(|v| !v.is_finite()) {
+        return Err(Error::new(
+            Status::InvalidArg,
+            format!("{} contains NaN or infinite values", name),
+        ));
+    }
+    Ok(())
+}
+
+/// Options for bootstrap_ci
+#[napi(object)]
+pub struct BootstrapOptions {
+    /// "mean" (default) or "median"
+    pub statistic: Option<String>,
+    /// Resamples (default: 1000)
+    pub iterations: Option<u32>,
+    /// Confidence level (default: 0.95)
+    pub confidence: Option<f64>,
+    /// Resampling seed, so intervals are reproducible (default: fixed)
+    pub seed: Option<u32>,
+}
+
+/// A statistic with its bootstrap confidence interval
+#[napi(object)]
+pub struct ConfidenceInterval {
+    pub statistic: String,
+    pub estimate: f64,
+    pub lower: f64,
+    pub upper: f64,
+    pub confidence: f64,
+    pub samples: u32,
+}
+
+/// Result of t_test
+#[napi(object)]
+pub struct TTestResult {
+    pub mean_a: f64,
+    pub mean_b: f64,
+    /// mean(b) − mean(a)
+    pub mean_difference: f64,
+    pub t_statistic: f64,
+    pub degrees_of_freedom: f64,
+    /// Two-sided p-value
+    pub p_value: f64,
+}
+
+/// Result of mann_whitney
+#[napi(object)]
+pub struct MannWhitneyResult {
+    /// U of b over a: pairs where b > a, ties counting half
+    pub u_statistic: f64,
+    /// Normal approximation with tie and continuity corrections
+    pub z_score: f64,
+    /// Two-sided p-value
+    pub p_value: f64,
+    /// 2U / (n_a n_b) − 1, from −1 (a always larger) to 1 (b always larger)
+    pub rank_biserial: f64,
+}
+
+/// Result of effect_size
+#[napi(object)]
+pub struct EffectSizeResult {
+    /// Standardized mean difference of b over a (pooled standard deviation)
+    pub cohens_d: f64,
+    /// Cohen's d with the small-sample bias correction
+    pub hedges_g: f64,
+    /// P(b > a) − P(b < a)
+    pub cliffs_delta: f64,
+    /// Cohen's conventional label for |d|: "negligible", "small", "medium", or "large"
+    pub magnitude: String,
+}
+
+/**
+ * Bootstrap confidence interval for the mean or median of a metric
+ *
+ * @param values - Metric samples
+ * @param options - Statistic, resamples, confidence level, and seed
+ * @returns Point estimate with percentile interval
+ */
+#[napi]
+pub fn bootstrap_ci(
+    values: Vec<f64>,
+    options: Option<BootstrapOptions>,
+) -> Result<ConfidenceInterval> {
+    require_finite(&values, "values", 1)?;
+    let options = options.unwrap_or(BootstrapOptions {
+        statistic: None,
+        iterations: None,
+        confidence: None,
+        seed: None,
+    });
+    let statistic = options.statistic.unwrap_or_else(|| "mean".to_string());
+    let iterations = options.iterations.unwrap_or(DEFAULT_BOOTSTRAP_ITERATIONS);
+    let confidence = options.confidence.unwrap_or(DEFAULT_CONFIDENCE);
+    let seed = options.seed.unwrap_or(DEFAULT_SEED) as u64;
+    validate_bootstrap(iterations, confidence)?;
+
+    let interval = match statistic.as_str() {
+        "mean" => {
+            let samples: Vec<(f64, f64)> = values.iter().map(|v| (*v, 1.0)).collect();
+            bootstrap_ratio(&samples, iterations, confidence, seed)
+        }
+        "median" => bootstrap_median(&values, iterations, confidence, seed),
+        other => {
+            return Err(Error::new(
+                Status::InvalidArg,
+                format!("Unknown statistic '{}', expected mean or median", other),
+            ))
+        }
+    };
+    let interval = interval.expect("non-empty samples always have an estimate");
+
+    Ok(ConfidenceInterval {
+        statistic,
+        estimate: interval.value,
+        lower: interval.lower,
+        upper: interval.upper,
+        confidence,
+        samples: values.len() as u32,
+    })
+}
+
+/**
+ * Welch's unequal-variance t-test of mean(b) − mean(a)
+ *
+ * @param a - Baseline samples (at least two)
+ * @param b - Comparison samples (at least two)
+ * @returns t statistic, Welch–Satterthwaite degrees of freedom, and two-sided p-value
+ */
+#[napi]
+pub fn t_test(a: Vec<f64>, b: Vec<f64>) -> Result<TTestResult> {
+    require_finite(&a, "a", 2)?;
+    require_finite(&b, "b", 2)?;
+    let test = welch_t_test(&a, &b).expect("both samples have at least two values");
+    let (mean_a, mean_b) = (mean(&a), mean(&b));
+
+    Ok(TTestResult {
+        mean_a,
+        mean_b,
+        mean_difference: mean_b - mean_a,
+        t_statistic: test.t,
+        degrees_of_freedom: test.degrees_of_freedom,
+        p_value: test.p_value,
+    })
+}
+
+/**
+ * Mann–Whitney U test (Wilcoxon rank-sum) of b against a
+ *
+ * Distribution-free alternative to t_test for skewed metrics like latency
+ * or churn. Uses the normal approximation, so p-values are approximate for
+ * very small samples.
+ *
+ * @param a - Baseline samples
+ * @param b - Comparison samples
+ * @returns U statistic, z score, two-sided p-value, and rank-biserial correlation
+ */
+#[napi]
+pub fn mann_whitney(a: Vec<f64>, b: Vec<f64>) -> Result<MannWhitneyResult> {
+    require_finite(&a, "a", 1)?;
+    require_finite(&b, "b", 1)?;
+    let (na, nb) = (a.len() as f64, b.len() as f64);
+    let n = na + nb;
+    let (ranks, ties) = pooled_ranks(&a, &b);
+    let u = u_statistic(&a, &b, &ranks);
+
+    let expected = na * nb / 2.0;
+    let tie_term = if n > 1.0 { ties / (n * (n - 1.0)) } else { 0.0 };
+    let sigma = (na * nb / 12.0 * ((n + 1.0) - tie_term)).sqrt();
+    let z = if sigma > 0.0 {
+        let deviation = u - expected;
+        let corrected = (deviation.abs() - 0.5).max(0.0) * deviation.signum();
+        corrected / sigma
+    } else {
+        0.0
+    };
+
+    Ok(MannWhitneyResult {
+        u_statistic: u,
+        z_score: z,
+        p_value: if sigma > 0.0 {
+            normal_two_sided(z)
+        } else {
+            1.0
+        },
+        rank_biserial: 2.0 * u / (na * nb) - 1.0,
+    })
+}
+
+/**
+ * Effect sizes of b relative to a
+ *
+ * @param a - Baseline samples (at least two)
+ * @param b - Comparison samples (at least two)
+ * @returns Cohen's d, Hedges' g, Cliff's delta, and a magnitude label
+ */
+#[napi]
+pub fn effect_size(a: Vec<f64>, b: Vec<f64>) -> Result<EffectSizeResult> {
+    require_finite(&a, "a", 2)?;
+    require_finite(&b, "b", 2)?;
+    let (na, nb) = (a.len() as f64, b.len() as f64);
+    let d = cohens_d(&a, &b);
+    let correction = 1.0 - 3.0 / (4.0 * (na + nb) - 9.0);
+    let (ranks, _) = pooled_ranks(&a, &b);
+    let cliffs_delta = 2.0 * u_statistic(&a, &b, &ranks) / (na * nb) - 1.0;
+    let magnitude = match d.abs() {
+        m if m < 0.2 => "negligible",
+        m if m < 0.5 => "small",
+        m if m < 0.8 => "medium",
+        _ => "large",
+    };
+
+    Ok(EffectSizeResult {
+        cohens_d: d,
+        hedges_g: d * correction,
+        cliffs_delta,
+        magnitude: magnitude.to_string(),
+    })
+}
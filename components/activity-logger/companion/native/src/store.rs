@@ -0,0 +1,789 @@
+/*!
+ * Event store
+ * Events in an embedded SQLite database, indexed by time, session, and file
+ */
+
+use crate::ingest::{details_object, event_field, parse_timestamp};
+use crate::retention::{Cutoffs, EventAggregate, Fate, RetentionPolicy, RetentionProgress, Rollup};
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use rusqlite::types::Value as SqlValue;
+use rusqlite::{params, params_from_iter, Connection, OpenFlags, ToSql};
+use serde_json::{Map, Value};
+use std::time::Duration;
+
+/// Bumped (through `PRAGMA user_version`) when the table layout changes
+const SCHEMA_VERSION: i64 = 2;
+const DEFAULT_BUSY_TIMEOUT_MS: u32 = 5_000;
+/// Columns an index may cover
+const INDEXABLE_COLUMNS: [&str; 5] = [
+    "session_id",
+    "workspace_path",
+    "file_path",
+    "type",
+    "timestamp",
+];
+/// Indexes every store starts with, one per query the logger runs most
+const DEFAULT_INDEXES: [&[&str]; 3] = [
+    &["timestamp"],
+    &["session_id", "timestamp"],
+    &["file_path", "timestamp"],
+];
+
+/// Options for an EventStore
+#[napi(object)]
+pub struct EventStoreOptions {
+    /// Open an existing store without write access (default: false)
+    pub read_only: Option<bool>,
+    /// How long a write waits for another connection's lock (default: 5000)
+    pub busy_timeout_ms: Option<u32>,
+}
+
+/// Filter for query, count, and delete_where; unset fields match everything
+#[napi(object)]
+pub struct EventQuery {
+    /// Earliest timestamp, inclusive, in epoch milliseconds
+    pub start: Option<i64>,
+    /// Latest timestamp, exclusive, in epoch milliseconds
+    pub end: Option<i64>,
+    pub session_id: Option<String>,
+    pub workspace_path: Option<String>,
+    pub file_path: Option<String>,
+    #[napi(js_name = "type")]
+    pub event_type: Option<String>,
+    /// Most events returned by query (default: all)
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+    /// Newest first instead of oldest first (default: false)
+    pub descending: Option<bool>,
+}
+
+/// Result of insert_batch
+#[napi(object)]
+pub struct StoreWrite {
+    /// Events whose id was new
+    pub inserted: u32,
+    /// Events that overwrote a stored event with the same id
+    pub replaced: u32,
+}
+
+/// An index on the events table
+#[napi(object)]
+pub struct StoreIndex {
+    pub name: String,
+    pub columns: Vec<String>,
+}
+
+/// SQLite-backed store of events keyed by id
+#[napi]
+pub struct EventStore {
+    path: String,
+    /// None once closed
+    connection: Option<Connection>,
+}
+
+fn sql_error(path: &str, error: rusqlite::Error) -> Error {
+    Error::new(
+        Status::GenericFailure,
+        format!("Event store {}: {}", path, error),
+    )
+}
+
+/// The columns an event is stored under, pulled from the top level or `details`
+pub(crate) struct Row {
+    pub id: String,
+    pub session_id: Option<String>,
+    pub workspace_path: Option<String>,
+    pub file_path: Option<String>,
+    pub event_type: String,
+    pub timestamp: i64,
+    /// The whole event as JSON
+    pub payload: String,
+}
+
+fn row_of(index: usize, event: &Value) -> Result<Row> {
+    let invalid =
+        |message: String| Error::new(Status::InvalidArg, format!("Event {}: {}", index, message));
+    let Value::Object(fields) = event else {
+        return Err(invalid("not an object".to_string()));
+    };
+    let details = details_object(fields);
+    let text = |keys: &[&str]| -> Option<String> {
+        match event_field(fields, details.as_ref(), keys)? {
+            Value::String(text) if !text.is_empty() => Some(text),
+            _ => None,
+        }
+    };
+    let id = match fields.get("id") {
+        Some(Value::String(id)) if !id.is_empty() => id.clone(),
+        Some(Value::Number(id)) => id.to_string(),
+        _ => return Err(invalid("missing a string or numeric id".to_string())),
+    };
+    let event_type = match fields.get("type") {
+        Some(Value::String(kind)) if !kind.is_empty() => kind.clone(),
+        _ => return Err(invalid(format!("{} has no type", id))),
+    };
+    let timestamp = fields
+        .get("timestamp")
+        .and_then(parse_timestamp)
+        .ok_or_else(|| invalid(format!("{} has no valid timestamp", id)))?;
+    Ok(Row {
+        session_id: text(&["session_id", "sessionId"]),
+        workspace_path: text(&["workspace_path", "workspacePath"]),
+        file_path: text(&["file_path", "filePath", "file"]),
+        payload: serde_json::to_string(fields).unwrap_or_default(),
+        timestamp: timestamp.round() as i64,
+        event_type,
+        id,
+    })
+}
+
+/// WHERE clause and parameters for a filter
+fn where_clause(filter: &EventQuery) -> (String, Vec<SqlValue>) {
+    let mut conditions = Vec::new();
+    let mut values = Vec::new();
+    if let Some(start) = filter.start {
+        conditions.push("timestamp >= ?");
+        values.push(SqlValue::Integer(start));
+    }
+    if let Some(end) = filter.end {
+        conditions.push("timestamp < ?");
+        values.push(SqlValue::Integer(end));
+    }
+    for (column, value) in [
+        ("session_id = ?", &filter.session_id),
+        ("workspace_path = ?", &filter.workspace_path),
+        ("file_path = ?", &filter.file_path),
+        ("type = ?", &filter.event_type),
+    ] {
+        if let Some(value) = value {
+            conditions.push(column);
+            values.push(SqlValue::Text(value.clone()));
+        }
+    }
+    if conditions.is_empty() {
+        (String::new(), values)
+    } else {
+        (format!(" WHERE {}", conditions.join(" AND ")), values)
+    }
+}
+
+/// SELECT of `columns` for a filter, honoring its order, limit, and offset
+fn select_sql(filter: &EventQuery, columns: &str) -> (String, Vec<SqlValue>) {
+    let (clause, mut values) = where_clause(filter);
+    let order = if filter.descending.unwrap_or(false) {
+        "DESC"
+    } else {
+        "ASC"
+    };
+    // SQLite needs a LIMIT before an OFFSET; -1 is no limit
+    values.push(SqlValue::Integer(filter.limit.map_or(-1, i64::from)));
+    values.push(SqlValue::Integer(filter.offset.unwrap_or(0) as i64));
+    let sql = format!(
+        "SELECT {} FROM events{} ORDER BY timestamp {}, rowid {} LIMIT ? OFFSET ?",
+        columns, clause, order, order
+    );
+    (sql, values)
+}
+
+pub(crate) fn empty_query() -> EventQuery {
+    EventQuery {
+        start: None,
+        end: None,
+        session_id: None,
+        workspace_path: None,
+        file_path: None,
+        event_type: None,
+        limit: None,
+        offset: None,
+        descending: None,
+    }
+}
+
+fn index_name(columns: &[&str]) -> String {
+    format!("idx_events_{}", columns.join("_"))
+}
+
+impl EventStore {
+    fn connection(&self) -> Result<&Connection> {
+        self.connection.as_ref().ok_or_else(|| {
+            Error::new(
+                Status::GenericFailure,
+                format!("Event store {} is closed", self.path),
+            )
+        })
+    }
+
+    fn connection_mut(&mut self) -> Result<&mut Connection> {
+        let path = &self.path;
+        self.connection.as_mut().ok_or_else(|| {
+            Error::new(
+                Status::GenericFailure,
+                format!("Event store {} is closed", path),
+            )
+        })
+    }
+
+    /// Stream the rows matching `filter`, in query order, without holding them all
+    pub(crate) fn scan(
+        &self,
+        filter: &EventQuery,
+        mut visit: impl FnMut(Row) -> Result<()>,
+    ) -> Result<()> {
+        let (sql, values) = select_sql(
+            filter,
+            "id, session_id, workspace_path, file_path, type, timestamp, payload",
+        );
+        let connection = self.connection()?;
+        let mut statement = connection
+            .prepare_cached(&sql)
+            .map_err(|e| sql_error(&self.path, e))?;
+        let mut rows = statement
+            .query(params_from_iter(values))
+            .map_err(|e| sql_error(&self.path, e))?;
+        while let Some(row) = rows.next().map_err(|e| sql_error(&self.path, e))? {
+            let read = || -> rusqlite::Result<Row> {
+                Ok(Row {
+                    id: row.get(0)?,
+                    session_id: row.get(1)?,
+                    workspace_path: row.get(2)?,
+                    file_path: row.get(3)?,
+                    event_type: row.get(4)?,
+                    timestamp: row.get(5)?,
+                    payload: row.get(6)?,
+                })
+            };
+            visit(read().map_err(|e| sql_error(&self.path, e))?)?;
+        }
+        Ok(())
+    }
+
+    /// Create the tables and default indexes, or check an existing store's version
+    fn migrate(connection: &Connection, path: &str, read_only: bool) -> Result<()> {
+        let version: i64 = connection
+            .pragma_query_value(None, "user_version", |row| row.get(0))
+            .map_err(|e| sql_error(path, e))?;
+        if version > SCHEMA_VERSION {
+            return Err(Error::new(
+                Status::GenericFailure,
+                format!(
+                    "Event store {} has schema version {}, newer than this module's {}",
+                    path, version, SCHEMA_VERSION
+                ),
+            ));
+        }
+        if version == SCHEMA_VERSION || read_only {
+            return Ok(());
+        }
+        let mut sql = String::from(
+            "CREATE TABLE IF NOT EXISTS events (
+                id TEXT PRIMARY KEY,
+                session_id TEXT,
+                workspace_path TEXT,
+                file_path TEXT,
+                type TEXT NOT NULL,
+                timestamp INTEGER NOT NULL,
+                payload TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS event_aggregates (
+                bucket_start INTEGER NOT NULL,
+                type TEXT NOT NULL,
+                workspace_path TEXT NOT NULL,
+                file_path TEXT NOT NULL,
+                count INTEGER NOT NULL,
+                lines_added INTEGER NOT NULL,
+                lines_removed INTEGER NOT NULL,
+                first_seen INTEGER NOT NULL,
+                last_seen INTEGER NOT NULL,
+                PRIMARY KEY (bucket_start, type, workspace_path, file_path)
+            );",
+        );
+        for columns in DEFAULT_INDEXES {
+            sql.push_str(&format!(
+                "CREATE INDEX IF NOT EXISTS {} ON events({});",
+                index_name(columns),
+                columns.join(", ")
+            ));
+        }
+        sql.push_str(&format!("PRAGMA user_version = {};", SCHEMA_VERSION));
+        connection
+            .execute_batch(&sql)
+            .map_err(|e| sql_error(path, e))
+    }
+}
+
+#[napi]
+impl EventStore {
+    /**
+     * Open the store at `path`, creating it if needed
+     *
+     * A new store gets the events table and indexes on timestamp,
+     * (session_id, timestamp), and (file_path, timestamp). The database is
+     * put in WAL mode so readers don't block the writer. ":memory:" opens a
+     * private in-memory store.
+     */
+    #[napi(constructor)]
+    pub fn new(path: String, options: Option<EventStoreOptions>) -> Result<Self> {
+        let read_only = options.as_ref().and_then(|o| o.read_only).unwrap_or(false);
+        let busy_timeout_ms = options
+            .and_then(|o| o.busy_timeout_ms)
+            .unwrap_or(DEFAULT_BUSY_TIMEOUT_MS);
+        let flags = if read_only {
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX
+        } else {
+            OpenFlags::default()
+        };
+        let connection =
+            Connection::open_with_flags(&path, flags).map_err(|e| sql_error(&path, e))?;
+        connection
+            .busy_timeout(Duration::from_millis(busy_timeout_ms as u64))
+            .map_err(|e| sql_error(&path, e))?;
+        if !read_only && path != ":memory:" {
+            connection
+                .pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get::<_, String>(0))
+                .and_then(|_| connection.pragma_update(None, "synchronous", "NORMAL"))
+                .map_err(|e| sql_error(&path, e))?;
+        }
+        Self::migrate(&connection, &path, read_only)?;
+        Ok(EventStore {
+            path,
+            connection: Some(connection),
+        })
+    }
+
+    /**
+     * Write `events` in a single transaction
+     *
+     * Each event needs an `id` (string or number), a `type`, and a
+     * `timestamp` (epoch ms or ISO-8601); `session_id`, `workspace_path`,
+     * and `file_path` are indexed when present, at the top level or inside
+     * `details`, in snake_case or camelCase. The whole event is stored as
+     * JSON. An event whose id is already stored replaces it. If any event
+     * is invalid nothing is written.
+     */
+    #[napi(ts_args_type = "events: Array<Record<string, any>>")]
+    pub fn insert_batch(&mut self, events: Vec<Value>) -> Result<StoreWrite> {
+        let rows = events
+            .iter()
+            .enumerate()
+            .map(|(index, event)| row_of(index, event))
+            .collect::<Result<Vec<_>>>()?;
+        let path = self.path.clone();
+        let connection = self.connection_mut()?;
+        let transaction = connection.transaction().map_err(|e| sql_error(&path, e))?;
+        let mut write = StoreWrite {
+            inserted: 0,
+            replaced: 0,
+        };
+        {
+            let mut insert = transaction
+                .prepare_cached(
+                    "INSERT INTO events (id, session_id, workspace_path, file_path, type, timestamp, payload)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7) ON CONFLICT(id) DO NOTHING",
+                )
+                .map_err(|e| sql_error(&path, e))?;
+            let mut update = transaction
+                .prepare_cached(
+                    "UPDATE events SET session_id = ?2, workspace_path = ?3, file_path = ?4,
+                     type = ?5, timestamp = ?6, payload = ?7 WHERE id = ?1",
+                )
+                .map_err(|e| sql_error(&path, e))?;
+            for row in &rows {
+                let values: [&dyn ToSql; 7] = [
+                    &row.id,
+                    &row.session_id,
+                    &row.workspace_path,
+                    &row.file_path,
+                    &row.event_type,
+                    &row.timestamp,
+                    &row.payload,
+                ];
+                if insert.execute(values).map_err(|e| sql_error(&path, e))? == 1 {
+                    write.inserted += 1;
+                } else {
+                    update.execute(values).map_err(|e| sql_error(&path, e))?;
+                    write.replaced += 1;
+                }
+            }
+        }
+        transaction.commit().map_err(|e| sql_error(&path, e))?;
+        Ok(write)
+    }
+
+    /**
+     * Stored events matching `filter`, oldest first
+     *
+     * Time bounds are half-open, `[start, end)`. Session and file filters
+     * use their (column, timestamp) indexes, so a range within one session
+     * or file reads only the matching rows.
+     */
+    #[napi(ts_return_type = "Array<Record<string, any>>")]
+    pub fn query(&self, filter: Option<EventQuery>) -> Result<Vec<Value>> {
+        let (sql, values) = select_sql(&filter.unwrap_or_else(empty_query), "payload");
+        let connection = self.connection()?;
+        let mut statement = connection
+            .prepare_cached(&sql)
+            .map_err(|e| sql_error(&self.path, e))?;
+        let payloads = statement
+            .query_map(params_from_iter(values), |row| row.get::<_, String>(0))
+            .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+            .map_err(|e| sql_error(&self.path, e))?;
+        Ok(payloads
+            .iter()
+            .map(|payload| {
+                serde_json::from_str(payload).unwrap_or_else(|_| Value::Object(Map::new()))
+            })
+            .collect())
+    }
+
+    /// Number of stored events matching `filter`; `limit` and `offset` are ignored
+    #[napi]
+    pub fn count(&self, filter: Option<EventQuery>) -> Result<u32> {
+        let (clause, values) = where_clause(&filter.unwrap_or_else(empty_query));
+        let connection = self.connection()?;
+        connection
+            .query_row(
+                &format!("SELECT COUNT(*) FROM events{}", clause),
+                params_from_iter(values),
+                |row| row.get::<_, i64>(0),
+            )
+            .map(|count| count as u32)
+            .map_err(|e| sql_error(&self.path, e))
+    }
+
+    /// The stored event with this id
+    #[napi(ts_return_type = "Record<string, any> | null")]
+    pub fn get(&self, id: String) -> Result<Option<Value>> {
+        let connection = self.connection()?;
+        let payload = connection
+            .query_row(
+                "SELECT payload FROM events WHERE id = ?1",
+                params![id],
+                |row| row.get::<_, String>(0),
+            )
+            .map(Some)
+            .or_else(|e| match e {
+                rusqlite::Error::QueryReturnedNoRows => Ok(None),
+                other => Err(sql_error(&self.path, other)),
+            })?;
+        Ok(payload.and_then(|payload| serde_json::from_str(&payload).ok()))
+    }
+
+    /// Delete events by id; returns how many were stored
+    #[napi]
+    pub fn delete(&mut self, ids: Vec<String>) -> Result<u32> {
+        let path = self.path.clone();
+        let connection = self.connection_mut()?;
+        let transaction = connection.transaction().map_err(|e| sql_error(&path, e))?;
+        let mut deleted = 0;
+        {
+            let mut statement = transaction
+                .prepare_cached("DELETE FROM events WHERE id = ?1")
+                .map_err(|e| sql_error(&path, e))?;
+            for id in &ids {
+                deleted += statement
+                    .execute(params![id])
+                    .map_err(|e| sql_error(&path, e))?;
+            }
+        }
+        transaction.commit().map_err(|e| sql_error(&path, e))?;
+        Ok(deleted as u32)
+    }
+
+    /// Delete every event matching `filter`; `limit` and `offset` are ignored
+    #[napi]
+    pub fn delete_where(&mut self, filter: EventQuery) -> Result<u32> {
+        let (clause, values) = where_clause(&filter);
+        let path = self.path.clone();
+        self.connection()?
+            .execute(
+                &format!("DELETE FROM events{}", clause),
+                params_from_iter(values),
+            )
+            .map(|deleted| deleted as u32)
+            .map_err(|e| sql_error(&path, e))
+    }
+
+    /**
+     * Add an index on `columns`, in order; returns its name
+     *
+     * Columns are any of session_id, workspace_path, file_path, type, and
+     * timestamp. Creating an index that exists is a no-op.
+     */
+    #[napi]
+    pub fn create_index(&mut self, columns: Vec<String>) -> Result<String> {
+        if columns.is_empty() {
+            return Err(Error::new(Status::InvalidArg, "columns must not be empty"));
+        }
+        if let Some(unknown) = columns
+            .iter()
+            .find(|column| !INDEXABLE_COLUMNS.contains(&column.as_str()))
+        {
+            return Err(Error::new(
+                Status::InvalidArg,
+                format!(
+                    "Cannot index '{}' (expected one of {})",
+                    unknown,
+                    INDEXABLE_COLUMNS.join(", ")
+                ),
+            ));
+        }
+        let columns: Vec<&str> = columns.iter().map(String::as_str).collect();
+        let name = index_name(&columns);
+        self.connection()?
+            .execute_batch(&format!(
+                "CREATE INDEX IF NOT EXISTS {} ON events({})",
+                name,
+                columns.join(", ")
+            ))
+            .map_err(|e| sql_error(&self.path, e))?;
+        Ok(name)
+    }
+
+    /// Drop an index by name; returns whether it existed
+    #[napi]
+    pub fn drop_index(&mut self, name: String) -> Result<bool> {
+        if !self.indexes()?.iter().any(|index| index.name == name) {
+            return Ok(false);
+        }
+        // The name matched an existing index, so it is safe to splice in
+        self.connection()?
+            .execute_batch(&format!("DROP INDEX {}", name))
+            .map_err(|e| sql_error(&self.path, e))?;
+        Ok(true)
+    }
+
+    /// Indexes on the events table, by name; the primary key isn't listed
+    #[napi]
+    pub fn indexes(&self) -> Result<Vec<StoreIndex>> {
+        let connection = self.connection()?;
+        let names = connection
+            .prepare_cached(
+                "SELECT name FROM sqlite_master
+                 WHERE type = 'index' AND tbl_name = 'events' AND sql IS NOT NULL ORDER BY name",
+            )
+            .and_then(|mut statement| {
+                let names = statement
+                    .query_map([], |row| row.get::<_, String>(0))?
+                    .collect::<rusqlite::Result<Vec<_>>>();
+                names
+            })
+            .map_err(|e| sql_error(&self.path, e))?;
+        let mut statement = connection
+            .prepare_cached("SELECT name FROM pragma_index_info(?1) ORDER BY seqno")
+            .map_err(|e| sql_error(&self.path, e))?;
+        names
+            .into_iter()
+            .map(|name| {
+                let columns = statement
+                    .query_map(params![name], |row| row.get::<_, String>(0))
+                    .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+                    .map_err(|e| sql_error(&self.path, e))?;
+                Ok(StoreIndex { name, columns })
+            })
+            .collect()
+    }
+
+    /**
+     * Apply a retention policy to the oldest events past its full-detail cutoff
+     *
+     * Up to batch_size events are handled per call, in one transaction:
+     * those within aggregate_ms are folded into per-bucket rollups in the
+     * event_aggregates table, older ones are deleted, and so are rollups
+     * whose newest event is past aggregate_ms. Call repeatedly, e.g. when
+     * idle, until `done`; `remaining` counts the expired events left.
+     */
+    #[napi]
+    pub fn apply_retention(&mut self, policy: RetentionPolicy) -> Result<RetentionProgress> {
+        let cutoffs = Cutoffs::of(&policy)?;
+        let path = self.path.clone();
+        let connection = self.connection_mut()?;
+        let transaction = connection.transaction().map_err(|e| sql_error(&path, e))?;
+        let detail = cutoffs.detail.ceil() as i64;
+        let mut progress = RetentionProgress {
+            aggregates_deleted: transaction
+                .execute(
+                    "DELETE FROM event_aggregates WHERE last_seen < ?1",
+                    params![cutoffs.aggregate.ceil() as i64],
+                )
+                .map_err(|e| sql_error(&path, e))? as u32,
+            ..Default::default()
+        };
+        {
+            let mut select = transaction
+                .prepare_cached(
+                    "SELECT id, timestamp, payload FROM events WHERE timestamp < ?1
+                     ORDER BY timestamp LIMIT ?2",
+                )
+                .map_err(|e| sql_error(&path, e))?;
+            let rows = select
+                .query_map(params![detail, cutoffs.batch_size as i64], |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, i64>(1)?,
+                        row.get::<_, String>(2)?,
+                    ))
+                })
+                .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+                .map_err(|e| sql_error(&path, e))?;
+            let mut delete = transaction
+                .prepare_cached("DELETE FROM events WHERE id = ?1")
+                .map_err(|e| sql_error(&path, e))?;
+            let mut rollup = Rollup::default();
+            for (id, timestamp, payload) in &rows {
+                let timestamp = *timestamp as f64;
+                if cutoffs.fate(timestamp) == Fate::Aggregate {
+                    let event = match serde_json::from_str(payload) {
+                        Ok(Value::Object(event)) => event,
+                        _ => Map::new(),
+                    };
+                    rollup.add(&event, timestamp, cutoffs.bucket_ms);
+                    progress.aggregated += 1;
+                } else {
+                    progress.deleted += 1;
+                }
+                delete
+                    .execute(params![id])
+                    .map_err(|e| sql_error(&path, e))?;
+            }
+            let mut upsert = transaction
+                .prepare_cached(
+                    "INSERT INTO event_aggregates (bucket_start, type, workspace_path, file_path,
+                     count, lines_added, lines_removed, first_seen, last_seen)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+                     ON CONFLICT(bucket_start, type, workspace_path, file_path) DO UPDATE SET
+                     count = count + excluded.count,
+                     lines_added = lines_added + excluded.lines_added,
+                     lines_removed = lines_removed + excluded.lines_removed,
+                     first_seen = MIN(first_seen, excluded.first_seen),
+                     last_seen = MAX(last_seen, excluded.last_seen)",
+                )
+                .map_err(|e| sql_error(&path, e))?;
+            for aggregate in rollup.into_aggregates() {
+                // Empty rather than NULL, since NULLs never conflict in the primary key
+                let values: [&dyn ToSql; 9] = [
+                    &aggregate.bucket_start,
+                    &aggregate.event_type,
+                    &aggregate.workspace_path.unwrap_or_default(),
+                    &aggregate.file_path.unwrap_or_default(),
+                    &aggregate.count,
+                    &aggregate.lines_added,
+                    &aggregate.lines_removed,
+                    &aggregate.first_seen,
+                    &aggregate.last_seen,
+                ];
+                upsert.execute(values).map_err(|e| sql_error(&path, e))?;
+                progress.buckets += 1;
+            }
+            progress.processed = rows.len() as u32;
+        }
+        progress.remaining = transaction
+            .query_row(
+                "SELECT COUNT(*) FROM events WHERE timestamp < ?1",
+                params![detail],
+                |row| row.get::<_, i64>(0),
+            )
+            .map_err(|e| sql_error(&path, e))? as u32;
+        progress.done = progress.remaining == 0;
+        transaction.commit().map_err(|e| sql_error(&path, e))?;
+        Ok(progress)
+    }
+
+    /**
+     * Rollups written by apply_retention, oldest bucket first
+     *
+     * `start` and `end` bound the bucket start; `type`, `workspace_path`,
+     * `file_path`, `limit`, and `offset` apply as in query, and
+     * `session_id` is ignored since rollups span sessions.
+     */
+    #[napi]
+    pub fn aggregates(&self, filter: Option<EventQuery>) -> Result<Vec<EventAggregate>> {
+        let filter = filter.unwrap_or_else(empty_query);
+        let mut conditions = Vec::new();
+        let mut values = Vec::new();
+        if let Some(start) = filter.start {
+            conditions.push("bucket_start >= ?");
+            values.push(SqlValue::Integer(start));
+        }
+        if let Some(end) = filter.end {
+            conditions.push("bucket_start < ?");
+            values.push(SqlValue::Integer(end));
+        }
+        for (column, value) in [
+            ("type = ?", &filter.event_type),
+            ("workspace_path = ?", &filter.workspace_path),
+            ("file_path = ?", &filter.file_path),
+        ] {
+            if let Some(value) = value {
+                conditions.push(column);
+                values.push(SqlValue::Text(value.clone()));
+            }
+        }
+        let clause = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!(" WHERE {}", conditions.join(" AND "))
+        };
+        values.push(SqlValue::Integer(filter.limit.map_or(-1, i64::from)));
+        values.push(SqlValue::Integer(filter.offset.unwrap_or(0) as i64));
+        let sql = format!(
+            "SELECT bucket_start, type, workspace_path, file_path, count, lines_added,
+             lines_removed, first_seen, last_seen FROM event_aggregates{}
+             ORDER BY bucket_start, type, workspace_path, file_path LIMIT ? OFFSET ?",
+            clause
+        );
+        let connection = self.connection()?;
+        let mut statement = connection
+            .prepare_cached(&sql)
+            .map_err(|e| sql_error(&self.path, e))?;
+        let aggregates = statement
+            .query_map(params_from_iter(values), |row| {
+                let optional = |text: String| Some(text).filter(|text| !text.is_empty());
+                Ok(EventAggregate {
+                    bucket_start: row.get(0)?,
+                    event_type: row.get(1)?,
+                    workspace_path: optional(row.get(2)?),
+                    file_path: optional(row.get(3)?),
+                    count: row.get(4)?,
+                    lines_added: row.get(5)?,
+                    lines_removed: row.get(6)?,
+                    first_seen: row.get(7)?,
+                    last_seen: row.get(8)?,
+                })
+            })
+            .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+            .map_err(|e| sql_error(&self.path, e))?;
+        Ok(aggregates)
+    }
+
+    /// Refresh the query planner's statistics, after large inserts or deletes
+    #[napi]
+    pub fn optimize(&self) -> Result<()> {
+        self.connection()?
+            .execute_batch("PRAGMA optimize")
+            .map_err(|e| sql_error(&self.path, e))
+    }
+
+    /// Number of stored events
+    #[napi(getter)]
+    pub fn size(&self) -> Result<u32> {
+        self.count(None)
+    }
+
+    /// Where the store was opened
+    #[napi(getter)]
+    pub fn path(&self) -> String {
+        self.path.clone()
+    }
+
+    /// Close the database; later calls throw
+    #[napi]
+    pub fn close(&mut self) -> Result<()> {
+        match self.connection.take() {
+            Some(connection) => connection
+                .close()
+                .map_err(|(_, e)| sql_error(&self.path, e)),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,317 @@
+/*!
+ * Symbol extraction
+ * Structured function, method, and type definitions, via tree-sitter when built with it
+ */
+
+use crate::comments::strip_comments_and_strings;
+use crate::functions::{
+    find_functions, indent_body_end, line_of, line_starts, matching_brace, FunctionSpan,
+};
+use napi_derive::napi;
+use regex::Regex;
+
+/// Symbol kinds that define a type rather than a function
+const TYPE_KINDS: [&str; 6] = ["class", "struct", "enum", "interface", "trait", "type"];
+
+/// A definition located in source
+#[napi(object)]
+#[derive(Clone, Debug)]
+pub struct Symbol {
+    pub name: String,
+    /// "function", "method", "class", "struct", "enum", "interface", "trait", or "type"
+    pub kind: String,
+    /// Declaration up to the body, whitespace collapsed
+    pub signature: String,
+    /// 1-based, including decorators and export keywords
+    pub start_line: u32,
+    pub end_line: u32,
+    /// Doc comment above the definition, or the Python docstring, without markers
+    pub docstring: Option<String>,
+    /// Enclosing class, impl, trait, or function
+    pub parent: Option<String>,
+}
+
+/// Collapse runs of whitespace into single spaces
+pub(crate) fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Text of a doc comment without `///`, `/**`, `*`, `//`, or quote markers
+pub(crate) fn clean_doc(raw: &str) -> Option<String> {
+    let lines: Vec<&str> = raw
+        .lines()
+        .map(|line| {
+            let line = line.trim();
+            let line = line
+                .trim_start_matches("/**")
+                .trim_start_matches("/*!")
+                .trim_start_matches("/*")
+                .trim_end_matches("*/");
+            let line = line
+                .strip_prefix("///")
+                .or_else(|| line.strip_prefix("//!"))
+                .or_else(|| line.strip_prefix("//"))
+                .or_else(|| line.strip_prefix('*'))
+                .or_else(|| line.strip_prefix('#'))
+                .unwrap_or(line);
+            line.trim()
+        })
+        .collect();
+    let start = lines.iter().position(|l| !l.is_empty())?;
+    let end = lines.iter().rposition(|l| !l.is_empty())?;
+    Some(lines[start..=end].join("\n"))
+}
+
+/// Python string literal contents without prefix and quotes
+pub(crate) fn unquote_python(literal: &str) -> &str {
+    let body = literal.trim_start_matches(|c: char| "rRuUbBfF".contains(c));
+    for quote in ["\"\"\"", "'''", "\"", "'"] {
+        if let Some(inner) = body
+            .strip_prefix(quote)
+            .and_then(|rest| rest.strip_suffix(quote))
+        {
+            return inner;
+        }
+    }
+    body
+}
+
+/// Comment lines directly above `line_index` (0-based), skipping attributes and decorators
+fn comment_above(lines: &[&str], line_index: usize, language: &str) -> Option<String> {
+    let doc_markers: &[&str] = match language {
+        "python" | "ruby" | "shell" => &["#"],
+        _ => &["///", "//", "/*", "*"],
+    };
+    let mut first = line_index;
+    let mut index = line_index;
+    while index > 0 {
+        let line = lines[index - 1].trim();
+        if line.starts_with('@') || line.starts_with("#[") {
+            index -= 1;
+            if first == index + 1 {
+                first = index;
+            }
+            continue;
+        }
+        if line.is_empty() || !doc_markers.iter().any(|m| line.starts_with(m)) {
+            break;
+        }
+        index -= 1;
+    }
+    let comments: Vec<&str> = lines[index..first]
+        .iter()
+        .copied()
+        .filter(|l| !l.trim_start().starts_with('@') && !l.trim_start().starts_with("#["))
+        .collect();
+    clean_doc(&comments.join("\n"))
+}
+
+/// Docstring opening on the first body line after a Python `def`
+fn python_docstring(lines: &[&str], def_line: usize) -> Option<String> {
+    let mut index = def_line + 1;
+    while lines.get(index)?.trim().is_empty() {
+        index += 1;
+    }
+    let first = lines[index].trim();
+    let quote = ["\"\"\"", "'''"]
+        .into_iter()
+        .find(|q| first.trim_start_matches(['r', 'u']).starts_with(q))?;
+    let mut literal = first.to_string();
+    let mut next = index + 1;
+    while literal.matches(quote).count() < 2 {
+        literal.push('\n');
+        literal.push_str(lines.get(next)?);
+        next += 1;
+    }
+    clean_doc(unquote_python(literal.trim()))
+}
+
+/// Declaration text of a regex-located span: its first line up to the body
+fn span_signature(content: &str, span: &FunctionSpan, language: &str) -> String {
+    let text = &content[span.start..span.end];
+    let first_line = text.lines().next().unwrap_or("");
+    let signature = match language {
+        "python" => first_line.trim_end().trim_end_matches(':'),
+        _ => first_line.split('{').next().unwrap_or(first_line),
+    };
+    collapse_whitespace(signature.trim_end().trim_end_matches([';', '=']))
+}
+
+/// Type definitions in stripped code, with their kind
+fn find_types(code: &str, language: &str) -> Vec<(FunctionSpan, &'static str)> {
+    let pattern = match language {
+        "javascript" | "typescript" => {
+            r"(?m)^[ \t]*(?:export\s+)?(?:default\s+)?(?:declare\s+)?(?:abstract\s+)?(class|interface|enum|type)\s+(\w+)"
+        }
+        "python" => r"(?m)^[ \t]*(class)\s+(\w+)",
+        "rust" => r"(?m)^[ \t]*(?:pub(?:\([^)]*\))?\s+)?(struct|enum|union|trait|type)\s+(\w+)",
+        "go" => r"(?m)^[ \t]*(type)\s+(\w+)",
+        "java" | "csharp" => {
+            r"(?m)^[ \t]*(?:(?:public|private|protected|internal|static|abstract|final|sealed|partial)\s+)*(class|interface|enum|struct|record)\s+(\w+)"
+        }
+        "c" | "cpp" => r"(?m)^[ \t]*(?:typedef\s+)?(class|struct|enum|union)\s+(\w+)",
+        _ => return Vec::new(),
+    };
+    let re = Regex::new(pattern).unwrap();
+    let starts = line_starts(code);
+    let mut types = Vec::new();
+    for cap in re.captures_iter(code) {
+        let (Some(whole), Some(keyword), Some(name)) = (cap.get(0), cap.get(1), cap.get(2)) else {
+            continue;
+        };
+        let start = whole.start() + (whole.as_str().len() - whole.as_str().trim_start().len());
+        let rest = &code[name.end()..];
+        let line_end = name.end() + rest.find('\n').unwrap_or(rest.len());
+        let kind = match keyword.as_str() {
+            "type" if language == "go" => {
+                let definition = rest.trim_start();
+                if definition.starts_with("struct") {
+                    "struct"
+                } else if definition.starts_with("interface") {
+                    "interface"
+                } else {
+                    "type"
+                }
+            }
+            "union" => "struct",
+            "record" => "class",
+            keyword => TYPE_KINDS
+                .iter()
+                .find(|k| **k == keyword)
+                .copied()
+                .unwrap_or("type"),
+        };
+        let end = if language == "python" {
+            indent_body_end(code, start)
+        } else {
+            // A body opens before the declaration ends with `;`; aliases must open it on their line
+            let search = if kind == "type" || language == "go" {
+                &rest[..line_end - name.end()]
+            } else {
+                rest
+            };
+            let open = search.find(['{', ';']);
+            match open {
+                Some(i) if rest.as_bytes()[i] == b'{' => {
+                    matching_brace(code.as_bytes(), name.end() + i)
+                }
+                Some(i) => name.end() + i + 1,
+                None => line_end,
+            }
+        };
+        types.push((
+            FunctionSpan {
+                name: name.as_str().to_string(),
+                start,
+                end,
+                start_line: line_of(&starts, start),
+                end_line: line_of(&starts, end.saturating_sub(1).max(start)),
+            },
+            kind,
+        ));
+    }
+    types
+}
+
+/// Heuristic symbols from `find_functions` and `find_types`, for builds and languages without a grammar
+fn regex_symbols(content: &str, language: &str) -> Vec<Symbol> {
+    let code = strip_comments_and_strings(content, language);
+    let mut spans: Vec<(FunctionSpan, &str)> = find_functions(&code, language)
+        .into_iter()
+        .map(|span| (span, "function"))
+        .chain(find_types(&code, language))
+        .collect();
+    spans.sort_by_key(|(span, _)| span.start);
+    let lines: Vec<&str> = content.lines().collect();
+    spans
+        .iter()
+        .enumerate()
+        .map(|(i, (span, kind))| {
+            let parent = spans[..i]
+                .iter()
+                .rev()
+                .find(|(outer, _)| outer.start < span.start && span.end <= outer.end);
+            let kind = match parent {
+                Some((_, parent_kind))
+                    if *kind == "function" && TYPE_KINDS.contains(parent_kind) =>
+                {
+                    "method"
+                }
+                _ => kind,
+            };
+            let parent = parent.map(|(outer, _)| outer.name.clone());
+            let line_index = span.start_line as usize - 1;
+            let docstring = if language == "python" {
+                python_docstring(&lines, line_index)
+            } else {
+                None
+            }
+            .or_else(|| comment_above(&lines, line_index, language));
+            Symbol {
+                name: span.name.clone(),
+                kind: kind.to_string(),
+                signature: span_signature(content, span, language),
+                start_line: span.start_line,
+                end_line: span.end_line,
+                docstring,
+                parent,
+            }
+        })
+        .collect()
+}
+
+/// Function, method, and type symbols in source order
+fn all_symbols(content: &str, language: &str) -> Vec<Symbol> {
+    #[cfg(feature = "tree-sitter")]
+    if let Some(symbols) = crate::syntax::symbols(content, language) {
+        return symbols;
+    }
+    regex_symbols(content, language)
+}
+
+/// Function and method symbols in source order
+pub(crate) fn function_symbols(content: &str, language: &str) -> Vec<Symbol> {
+    all_symbols(content, language)
+        .into_iter()
+        .filter(|s| !TYPE_KINDS.contains(&s.kind.as_str()))
+        .collect()
+}
+
+/**
+ * Extract function and method definitions as structured symbols
+ *
+ * With the `tree-sitter` feature (default), javascript, typescript, python,
+ * rust, and go are parsed properly: arrow functions assigned to variables
+ * and class fields, decorated Python defs, and methods inside impl and
+ * trait blocks are all found, and methods report their class or type as
+ * `parent`. Other languages, and builds without the feature, fall back to
+ * the heuristic function finder used by calculate_complexity.
+ *
+ * @param content - Source code
+ * @param language - Language name as returned by detect_language
+ * @returns Symbols ordered by start line
+ */
+#[napi]
+pub fn extract_symbols(content: String, language: String) -> Vec<Symbol> {
+    function_symbols(&content, &language)
+}
+
+/**
+ * Extract type definitions as structured symbols
+ *
+ * Returns classes, structs, enums, interfaces, traits, and type aliases with
+ * their line ranges, signatures, doc comments, and enclosing definition,
+ * for outline views. Uses the same backends as extract_symbols; the
+ * heuristic fallback also covers java, c, cpp, and csharp.
+ *
+ * @param content - Source code
+ * @param language - Language name as returned by detect_language
+ * @returns Type symbols ordered by start line
+ */
+#[napi]
+pub fn extract_types(content: String, language: String) -> Vec<Symbol> {
+    all_symbols(&content, &language)
+        .into_iter()
+        .filter(|s| TYPE_KINDS.contains(&s.kind.as_str()))
+        .collect()
+}
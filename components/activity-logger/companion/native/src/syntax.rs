@@ -0,0 +1,375 @@
+/*!
+ * Tree-sitter backend
+ * Syntax-tree walks for the languages we ship grammars for
+ */
+
+use crate::callgraph::CallSite;
+use crate::symbols::{clean_doc, collapse_whitespace, unquote_python, Symbol};
+use tree_sitter::{Language, Node, Parser, Query, QueryCursor, StreamingIterator, Tree};
+
+/// Grammar for a detect_language name, None when it isn't bundled
+fn grammar(language: &str) -> Option<Language> {
+    Some(match language {
+        "javascript" => tree_sitter_javascript::LANGUAGE.into(),
+        "typescript" => tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+        "python" => tree_sitter_python::LANGUAGE.into(),
+        "rust" => tree_sitter_rust::LANGUAGE.into(),
+        "go" => tree_sitter_go::LANGUAGE.into(),
+        _ => return None,
+    })
+}
+
+/// Parse `content`, None when the language has no grammar
+pub(crate) fn parse(content: &str, language: &str) -> Option<Tree> {
+    let mut parser = Parser::new();
+    parser.set_language(&grammar(language)?).ok()?;
+    parser.parse(content, None)
+}
+
+/// What an enclosing definition makes of the functions inside it
+#[derive(Clone, Copy, PartialEq)]
+enum Scope {
+    /// Class, impl, or trait: functions directly inside are methods
+    Type,
+    Function,
+}
+
+struct Walker<'a> {
+    source: &'a [u8],
+    language: &'a str,
+    symbols: Vec<Symbol>,
+}
+
+impl<'a> Walker<'a> {
+    fn text(&self, node: Node) -> &'a str {
+        node.utf8_text(self.source).unwrap_or("")
+    }
+
+    fn field_text(&self, node: Node, field: &str) -> Option<&'a str> {
+        node.child_by_field_name(field).map(|n| self.text(n))
+    }
+
+    /// The type a definition node introduces: (name, kind, body)
+    fn type_definition(&self, node: Node<'a>) -> Option<(&'a str, &'static str, Option<Node<'a>>)> {
+        let kind = match (self.language, node.kind()) {
+            (
+                "javascript" | "typescript",
+                "class_declaration" | "class" | "abstract_class_declaration",
+            )
+            | ("python", "class_definition") => "class",
+            ("typescript", "interface_declaration") => "interface",
+            ("typescript", "enum_declaration") | ("rust", "enum_item") => "enum",
+            ("typescript", "type_alias_declaration")
+            | ("rust", "type_item")
+            | ("go", "type_alias") => "type",
+            ("rust", "struct_item" | "union_item") => "struct",
+            ("rust", "trait_item") => "trait",
+            ("go", "type_spec") => match node.child_by_field_name("type")?.kind() {
+                "struct_type" => "struct",
+                "interface_type" => "interface",
+                _ => "type",
+            },
+            _ => return None,
+        };
+        let body = match kind {
+            "type" => None,
+            _ => node.child_by_field_name("body"),
+        };
+        Some((self.field_text(node, "name")?, kind, body))
+    }
+
+    /// Name of an impl block, which scopes methods without defining a type
+    fn impl_scope(&self, node: Node) -> Option<String> {
+        match (self.language, node.kind()) {
+            ("rust", "impl_item") => self.field_text(node, "type").map(str::to_string),
+            _ => None,
+        }
+    }
+
+    /// The function a definition node introduces: (name, body, explicit parent)
+    fn function(&self, node: Node<'a>) -> Option<(&'a str, Option<Node<'a>>, Option<String>)> {
+        let name = || self.field_text(node, "name");
+        let body = node.child_by_field_name("body");
+        match (self.language, node.kind()) {
+            (
+                "javascript" | "typescript",
+                "function_declaration" | "generator_function_declaration" | "method_definition",
+            )
+            | ("python", "function_definition")
+            | ("rust", "function_item")
+            | ("go", "function_declaration") => Some((name()?, body, None)),
+            ("rust", "function_signature_item") => Some((name()?, None, None)),
+            ("javascript" | "typescript", "variable_declarator")
+            | ("javascript", "field_definition")
+            | ("typescript", "public_field_definition") => {
+                let value = node.child_by_field_name("value")?;
+                if !matches!(
+                    value.kind(),
+                    "arrow_function" | "function_expression" | "function" | "generator_function"
+                ) {
+                    return None;
+                }
+                let name = self
+                    .field_text(node, "name")
+                    .or_else(|| self.field_text(node, "property"))?;
+                Some((name, value.child_by_field_name("body"), None))
+            }
+            ("go", "method_declaration") => {
+                let receiver = node
+                    .child_by_field_name("receiver")?
+                    .named_child(0)?
+                    .child_by_field_name("type")?;
+                let receiver = self
+                    .text(receiver)
+                    .trim_start_matches('*')
+                    .split('[')
+                    .next()
+                    .unwrap_or("")
+                    .to_string();
+                Some((name()?, body, Some(receiver)))
+            }
+            _ => None,
+        }
+    }
+
+    /// Node covering the whole definition, including decorators and export/declaration keywords
+    fn outer(&self, node: Node<'a>) -> Node<'a> {
+        let mut outer = node;
+        if node.kind() == "variable_declarator" {
+            outer = node.parent().unwrap_or(node);
+        }
+        if let Some(parent) = node.parent() {
+            // `type Foo struct {...}`, but not the specs of a grouped `type (...)`
+            if parent.kind() == "type_declaration" && parent.named_child_count() == 1 {
+                outer = parent;
+            }
+        }
+        while let Some(parent) = outer.parent() {
+            if matches!(parent.kind(), "decorated_definition" | "export_statement") {
+                outer = parent;
+            } else {
+                break;
+            }
+        }
+        outer
+    }
+
+    /// Comments directly above `outer`, skipping attributes
+    fn leading_doc(&self, outer: Node) -> Option<String> {
+        let mut comments = Vec::new();
+        let mut next_row = outer.start_position().row;
+        let mut sibling = outer.prev_sibling();
+        while let Some(node) = sibling {
+            if node.end_position().row + 1 < next_row {
+                break;
+            }
+            match node.kind() {
+                "attribute_item" | "decorator" => {}
+                "comment" | "line_comment" | "block_comment" => {
+                    let text = self.text(node);
+                    let is_doc = match self.language {
+                        "javascript" | "typescript" => text.starts_with("/**"),
+                        "rust" => text.starts_with("///") || text.starts_with("/**"),
+                        _ => true,
+                    };
+                    if !is_doc {
+                        break;
+                    }
+                    comments.push(text);
+                }
+                _ => break,
+            }
+            next_row = node.start_position().row;
+            sibling = node.prev_sibling();
+        }
+        comments.reverse();
+        clean_doc(&comments.join("\n"))
+    }
+
+    /// First statement of a Python body when it is a string literal
+    fn python_docstring(&self, body: Option<Node>) -> Option<String> {
+        let first = body?.named_child(0)?;
+        let literal = match first.kind() {
+            "expression_statement" => first.named_child(0)?,
+            _ => return None,
+        };
+        if literal.kind() != "string" {
+            return None;
+        }
+        clean_doc(unquote_python(self.text(literal)))
+    }
+
+    fn signature(&self, node: Node, body: Option<Node>) -> String {
+        let start = match node.kind() {
+            "variable_declarator" => node.parent().unwrap_or(node).start_byte(),
+            "type_spec" | "type_alias" => self.outer(node).start_byte(),
+            _ => node.start_byte(),
+        };
+        let end = body.map(|b| b.start_byte()).unwrap_or(node.end_byte());
+        let mut text = std::str::from_utf8(&self.source[start..end]).unwrap_or("");
+        if body.is_none() && self.language != "python" {
+            text = text.split('{').next().unwrap_or(text);
+        }
+        let text = text.trim_end();
+        let text = match self.language {
+            "python" => text.trim_end_matches(':'),
+            _ => text.trim_end_matches([';', '=']),
+        };
+        collapse_whitespace(text)
+    }
+
+    /// Doc comment above `outer`, or the docstring opening a Python body
+    fn docstring(&self, outer: Node, body: Option<Node>) -> Option<String> {
+        if self.language == "python" {
+            if let Some(doc) = self.python_docstring(body) {
+                return Some(doc);
+            }
+        }
+        self.leading_doc(outer)
+    }
+
+    fn push(
+        &mut self,
+        node: Node,
+        name: &str,
+        kind: &str,
+        body: Option<Node>,
+        parent: Option<String>,
+    ) {
+        let outer = self.outer(node);
+        self.symbols.push(Symbol {
+            name: name.to_string(),
+            kind: kind.to_string(),
+            signature: self.signature(node, body),
+            start_line: outer.start_position().row as u32 + 1,
+            end_line: node.end_position().row as u32 + 1,
+            docstring: self.docstring(outer, body),
+            parent,
+        });
+    }
+
+    fn visit(&mut self, node: Node<'a>, scopes: &mut Vec<(String, Scope)>) {
+        let mut pushed = false;
+        let enclosing = scopes.last().map(|(name, _)| name.clone());
+        if let Some((name, body, explicit_parent)) = self.function(node) {
+            let in_type = scopes
+                .last()
+                .is_some_and(|(_, scope)| *scope == Scope::Type);
+            let kind = if in_type || explicit_parent.is_some() {
+                "method"
+            } else {
+                "function"
+            };
+            self.push(node, name, kind, body, explicit_parent.or(enclosing));
+            scopes.push((name.to_string(), Scope::Function));
+            pushed = true;
+        } else if let Some((name, kind, body)) = self.type_definition(node) {
+            self.push(node, name, kind, body, enclosing);
+            scopes.push((name.to_string(), Scope::Type));
+            pushed = true;
+        } else if let Some(name) = self.impl_scope(node) {
+            scopes.push((name, Scope::Type));
+            pushed = true;
+        }
+
+        let mut cursor = node.walk();
+        let children: Vec<Node<'a>> = node.named_children(&mut cursor).collect();
+        for child in children {
+            self.visit(child, scopes);
+        }
+        if pushed {
+            scopes.pop();
+        }
+    }
+}
+
+/// Function, method, and type symbols, None when the language has no grammar
+pub(crate) fn symbols(content: &str, language: &str) -> Option<Vec<Symbol>> {
+    let tree = parse(content, language)?;
+    let mut walker = Walker {
+        source: content.as_bytes(),
+        language,
+        symbols: Vec::new(),
+    };
+    walker.visit(tree.root_node(), &mut Vec::new());
+    let mut symbols = walker.symbols;
+    symbols.sort_by_key(|s| s.start_line);
+    Some(symbols)
+}
+
+/// Query capturing the called name of every call, `@callee`
+fn call_query(language: &str) -> Option<&'static str> {
+    Some(match language {
+        "javascript" | "typescript" => {
+            r#"
+            (call_expression function: [
+              (identifier) @callee
+              (member_expression property: (property_identifier) @callee)])
+            (new_expression constructor: (identifier) @callee)
+            "#
+        }
+        "python" => {
+            r#"
+            (call function: [
+              (identifier) @callee
+              (attribute attribute: (identifier) @callee)])
+            "#
+        }
+        "rust" => {
+            r#"
+            (call_expression function: [
+              (identifier) @callee
+              (scoped_identifier name: (identifier) @callee)
+              (field_expression field: (field_identifier) @callee)
+              (generic_function function: [
+                (identifier) @callee
+                (scoped_identifier name: (identifier) @callee)
+                (field_expression field: (field_identifier) @callee)])])
+            "#
+        }
+        "go" => {
+            r#"
+            (call_expression function: [
+              (identifier) @callee
+              (selector_expression field: (field_identifier) @callee)])
+            "#
+        }
+        _ => return None,
+    })
+}
+
+/// Every call with the function it sits in, None when the language has no grammar
+pub(crate) fn call_sites(content: &str, language: &str) -> Option<Vec<CallSite>> {
+    let tree = parse(content, language)?;
+    let query = Query::new(&grammar(language)?, call_query(language)?).ok()?;
+    let walker = Walker {
+        source: content.as_bytes(),
+        language,
+        symbols: Vec::new(),
+    };
+
+    let mut sites = Vec::new();
+    let mut cursor = QueryCursor::new();
+    let mut matches = cursor.matches(&query, tree.root_node(), content.as_bytes());
+    while let Some(found) = matches.next() {
+        for capture in found.captures {
+            let callee = capture.node;
+            let mut scope = callee.parent();
+            let caller = loop {
+                let Some(node) = scope else {
+                    break None;
+                };
+                if let Some((name, _, _)) = walker.function(node) {
+                    break Some(name.to_string());
+                }
+                scope = node.parent();
+            };
+            sites.push((
+                caller,
+                walker.text(callee).to_string(),
+                callee.start_position().row as u32 + 1,
+            ));
+        }
+    }
+    Some(sites)
+}
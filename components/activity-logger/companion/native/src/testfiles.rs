@@ -0,0 +1,300 @@
+/*!
+ * Test detection
+ * Test files and the test functions inside them, for "did this session add tests" metrics
+ */
+
+use crate::comments::strip_comments_and_strings;
+use crate::functions::{line_of, line_starts};
+use napi_derive::napi;
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// One test or test group
+#[napi(object)]
+pub struct TestCase {
+    /// Test description or function name
+    pub name: String,
+    /// "test" for a single test, "suite" for a describe block, test class, or test module
+    pub kind: String,
+    /// 1-based line of the definition
+    pub line: u32,
+    /// Marked skipped: .skip/.todo, xit, or a skip, ignore, or disabled decorator or attribute
+    pub skipped: bool,
+}
+
+/// Result of detect_tests
+#[napi(object)]
+pub struct TestDetection {
+    /// The file is a test file by its name or location
+    pub is_test_file: bool,
+    /// Naming convention or directory that marked it, e.g. "*.test.*", "tests/"
+    pub reason: Option<String>,
+    /// "jest", "vitest", "mocha", "node:test", "pytest", "unittest", "rust", "go", "junit", "xunit", "nunit", "mstest", "rspec", or "minitest"
+    pub framework: Option<String>,
+    /// Tests and suites in source order
+    pub tests: Vec<TestCase>,
+}
+
+/// Test-file convention matched by `filename`, checked on the path with `/` separators
+fn test_file_reason(filename: &str) -> Option<&'static str> {
+    let path = filename.replace('\\', "/");
+    let dirs: Vec<&str> = path.split('/').collect();
+    let base = dirs.last().copied().unwrap_or("");
+    let stem = base.split('.').next().unwrap_or(base);
+    let dir_named = |name: &str| dirs[..dirs.len() - 1].contains(&name);
+
+    if base.contains(".test.") {
+        Some("*.test.*")
+    } else if base.contains(".spec.") {
+        Some("*.spec.*")
+    } else if base.ends_with("_test.go") {
+        Some("*_test.go")
+    } else if base.ends_with("_spec.rb") {
+        Some("*_spec.rb")
+    } else if base == "conftest.py" {
+        Some("conftest.py")
+    } else if stem.starts_with("test_") && base.ends_with(".py") {
+        Some("test_*.py")
+    } else if stem.ends_with("_test") {
+        Some("*_test.*")
+    } else if (stem.ends_with("Test") || stem.ends_with("Tests")) && stem.len() > 5 {
+        Some("*Test(s).*")
+    } else if dir_named("__tests__") {
+        Some("__tests__/")
+    } else if dir_named("tests") || dir_named("test") {
+        Some("tests/")
+    } else if dir_named("spec") {
+        Some("spec/")
+    } else {
+        None
+    }
+}
+
+/// A test pattern: group 1 is the keyword, group 2 the name
+struct TestPattern {
+    regex: Regex,
+    kind: &'static str,
+}
+
+fn test_pattern(regex: &str, kind: &'static str) -> TestPattern {
+    TestPattern {
+        regex: Regex::new(regex).unwrap(),
+        kind,
+    }
+}
+
+static JAVASCRIPT: LazyLock<Vec<TestPattern>> = LazyLock::new(|| {
+    vec![
+        test_pattern(
+            r#"\b(describe|suite|context|fdescribe|xdescribe)(?:\.(?:only|skip|each\s*\([^)]*\)))?\s*\(\s*['"`]([^'"`\n]+)"#,
+            "suite",
+        ),
+        test_pattern(
+            r#"\b(it|test|fit|xit|specify)(?:\.(?:only|skip|todo|concurrent|each\s*\([^)]*\)))?\s*\(\s*['"`]([^'"`\n]+)"#,
+            "test",
+        ),
+    ]
+});
+
+static PYTHON: LazyLock<Vec<TestPattern>> = LazyLock::new(|| {
+    vec![
+        test_pattern(r"(?m)^[ \t]*(class)\s+(Test\w*)", "suite"),
+        test_pattern(r"(?m)^[ \t]*(?:async\s+)?(def)\s+(test\w*)\s*\(", "test"),
+    ]
+});
+
+static RUST: LazyLock<Vec<TestPattern>> = LazyLock::new(|| {
+    vec![
+        test_pattern(
+            r"(?m)^[ \t]*#\[(cfg\(test\))\]\s*(?:pub\s+)?mod\s+(\w+)",
+            "suite",
+        ),
+        test_pattern(
+            r"(?m)^[ \t]*#\[((?:\w+::)*test(?:\([^)]*\))?|rstest|test_case\([^\]]*\))\]\s*(?:#\[[^\]]*\]\s*)*(?:pub\s+)?(?:async\s+)?fn\s+(\w+)",
+            "test",
+        ),
+    ]
+});
+
+static GO: LazyLock<Vec<TestPattern>> = LazyLock::new(|| {
+    vec![
+        test_pattern(
+            r"(?m)^func\s+(Test|Benchmark|Example|Fuzz)(\w*)\s*\(",
+            "test",
+        ),
+        test_pattern(r#"\.(Run)\(\s*"([^"\n]+)""#, "test"),
+    ]
+});
+
+static JAVA: LazyLock<Vec<TestPattern>> = LazyLock::new(|| {
+    vec![
+        test_pattern(
+            r"(?m)^[ \t]*(?:public\s+)?(?:final\s+)?(class)\s+(\w+Tests?)\b",
+            "suite",
+        ),
+        test_pattern(
+            r"(?m)^[ \t]*@(Test|ParameterizedTest|RepeatedTest|TestFactory)\b[^\n]*\n(?:[ \t]*@[^\n]*\n)*[^\n(]*?\b(\w+)\s*\(",
+            "test",
+        ),
+    ]
+});
+
+static CSHARP: LazyLock<Vec<TestPattern>> = LazyLock::new(|| {
+    vec![
+        test_pattern(
+            r"(?m)^[ \t]*(?:(?:public|internal|sealed|partial)\s+)*(class)\s+(\w+Tests?)\b",
+            "suite",
+        ),
+        test_pattern(
+            r"(?m)^[ \t]*\[(Fact|Theory|Test|TestMethod|TestCase)\b[^\n]*\n(?:[ \t]*\[[^\n]*\n)*[^\n(]*?\b(\w+)\s*\(",
+            "test",
+        ),
+    ]
+});
+
+static RUBY: LazyLock<Vec<TestPattern>> = LazyLock::new(|| {
+    vec![
+        test_pattern(
+            r#"(?m)^[ \t]*(describe|context|RSpec\.describe)\s*\(?\s*['"]?([^'"\n]+?)['"]?\s*(?:do|\))"#,
+            "suite",
+        ),
+        test_pattern(
+            r#"(?m)^[ \t]*(it|specify|xit)\s*\(?\s*['"]([^'"\n]+)['"]"#,
+            "test",
+        ),
+        test_pattern(r"(?m)^[ \t]*(def)\s+(test_\w+)", "test"),
+    ]
+});
+
+/// A decorator, attribute, or annotation directly above `start` that skips the test
+fn skip_marker_above(content: &str, start: usize) -> bool {
+    let above = &content[..content[..start].rfind('\n').unwrap_or(0)];
+    above
+        .lines()
+        .rev()
+        .map(str::trim)
+        .take_while(|line| line.starts_with('@') || line.starts_with("#[") || line.starts_with('['))
+        .any(|line| {
+            let line = line.to_ascii_lowercase();
+            line.contains("skip") || line.contains("ignore") || line.contains("disabled")
+        })
+}
+
+/// Test framework from imports and markers in the file
+fn framework(content: &str, language: &str, tests: &[TestCase]) -> Option<&'static str> {
+    let uses = |needle: &str| content.contains(needle);
+    Some(match language {
+        "javascript" | "typescript" => {
+            if uses("'vitest'") || uses("\"vitest\"") {
+                "vitest"
+            } else if uses("'node:test'") || uses("\"node:test\"") {
+                "node:test"
+            } else if uses("'mocha'") || uses("'chai'") || uses("\"chai\"") {
+                "mocha"
+            } else if !tests.is_empty() {
+                "jest"
+            } else {
+                return None;
+            }
+        }
+        "python" if uses("unittest.TestCase") || uses("(TestCase)") => "unittest",
+        "python" if !tests.is_empty() || uses("import pytest") => "pytest",
+        "rust" if !tests.is_empty() => "rust",
+        "go" if !tests.is_empty() => "go",
+        "java" if !tests.is_empty() => "junit",
+        "csharp" if uses("[Fact") || uses("[Theory") => "xunit",
+        "csharp" if uses("[TestMethod") => "mstest",
+        "csharp" if !tests.is_empty() => "nunit",
+        "ruby" if uses("Minitest") || uses("Test::Unit") => "minitest",
+        "ruby" if !tests.is_empty() => "rspec",
+        _ => return None,
+    })
+}
+
+/**
+ * Detect test files and the tests they define
+ *
+ * Flags test files by naming convention and location (`*.test.js`,
+ * `test_*.py`, `*_test.go`, `FooTest.java`, `__tests__/`, `tests/`, ...)
+ * and lists their tests: jest/vitest/mocha `describe`/`it`/`test` blocks,
+ * pytest and unittest `test*` functions and `Test*` classes, Rust `#[test]`
+ * functions and `#[cfg(test)]` modules, Go `TestXxx` functions and `t.Run`
+ * subtests, JUnit/xUnit annotated methods, and RSpec examples. Tests are
+ * listed for any file, so Rust's inline test modules count even though the
+ * file itself is not a test file.
+ *
+ * @param content - Source code
+ * @param language - Language name as returned by detect_language
+ * @param filename - File path, used for naming conventions
+ * @returns Whether the file is a test file, its framework, and its tests
+ */
+#[napi]
+pub fn detect_tests(content: String, language: String, filename: String) -> TestDetection {
+    let patterns: &[TestPattern] = match language.as_str() {
+        "javascript" | "typescript" => &JAVASCRIPT,
+        "python" => &PYTHON,
+        "rust" => &RUST,
+        "go" => &GO,
+        "java" => &JAVA,
+        "csharp" => &CSHARP,
+        "ruby" => &RUBY,
+        _ => &[],
+    };
+    // Matches that start inside a comment or string literal are ignored
+    let code = strip_comments_and_strings(&content, &language);
+    let starts = line_starts(&content);
+
+    let mut found: Vec<(usize, TestCase)> = Vec::new();
+    for pattern in patterns {
+        for caps in pattern.regex.captures_iter(&content) {
+            let (Some(keyword), Some(name)) = (caps.get(1), caps.get(2)) else {
+                continue;
+            };
+            let whole = caps.get(0).unwrap();
+            let start = whole.start() + (whole.as_str().len() - whole.as_str().trim_start().len());
+            if code
+                .as_bytes()
+                .get(start)
+                .is_none_or(|b| b.is_ascii_whitespace())
+            {
+                continue;
+            }
+            // `regex.test('x')` and other method calls named like test functions
+            if language != "go" && start > 0 && content.as_bytes()[start - 1] == b'.' {
+                continue;
+            }
+            let definition = whole.as_str();
+            let skipped = definition.contains(".skip")
+                || definition.contains(".todo")
+                || definition.contains("#[ignore")
+                || keyword.as_str().starts_with('x')
+                || skip_marker_above(&content, start);
+            let name = match language.as_str() {
+                // Go test functions keep their prefix: TestParse, BenchmarkParse
+                "go" if keyword.as_str() != "Run" => {
+                    format!("{}{}", keyword.as_str(), name.as_str())
+                }
+                _ => name.as_str().trim().to_string(),
+            };
+            found.push((
+                start,
+                TestCase {
+                    name,
+                    kind: pattern.kind.to_string(),
+                    line: line_of(&starts, start),
+                    skipped,
+                },
+            ));
+        }
+    }
+    found.sort_by_key(|(offset, _)| *offset);
+    let tests: Vec<TestCase> = found.into_iter().map(|(_, test)| test).collect();
+
+    let reason = test_file_reason(&filename);
+    TestDetection {
+        is_test_file: reason.is_some(),
+        reason: reason.map(str::to_string),
+        framework: framework(&content, &language, &tests).map(str::to_string),
+        tests,
+    }
+}
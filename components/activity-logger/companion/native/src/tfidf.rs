@@ -0,0 +1,236 @@
+/*!
+ * TF-IDF vectors
+ * A fitted vocabulary for order-insensitive code and prompt similarity
+ */
+
+use crate::keywords::{term_counts, words};
+use ahash::AHashMap;
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use rayon::prelude::*;
+
+/// Options for a TfidfModel
+#[napi(object)]
+pub struct TfidfOptions {
+    /// Weight terms by 1 + ln(count) rather than the raw count (default: true)
+    pub sublinear_tf: Option<bool>,
+    /// Drop terms in fewer documents than this (default: 1)
+    pub min_df: Option<u32>,
+    /// Drop terms in more than this fraction of documents (default: 1.0, keep all)
+    pub max_df: Option<f64>,
+    /// Keep only the most frequent terms across the corpus (default: all)
+    pub max_features: Option<u32>,
+}
+
+/// One weighted term of a vector
+#[napi(object)]
+pub struct TfidfTerm {
+    pub term: String,
+    pub weight: f64,
+}
+
+/// A fitted document close to the query
+#[napi(object)]
+pub struct TfidfNeighbor {
+    /// Position in the documents the model was fitted on
+    pub index: u32,
+    /// Cosine similarity, 0-1
+    pub similarity: f64,
+}
+
+/// Sparse L2-normalized vector as (term id, weight), sorted by term id
+type Vector = Vec<(u32, f64)>;
+
+fn dot(a: &Vector, b: &Vector) -> f64 {
+    let (mut i, mut j, mut sum) = (0, 0, 0.0);
+    while i < a.len() && j < b.len() {
+        match a[i].0.cmp(&b[j].0) {
+            std::cmp::Ordering::Less => i += 1,
+            std::cmp::Ordering::Greater => j += 1,
+            std::cmp::Ordering::Equal => {
+                sum += a[i].1 * b[j].1;
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    sum
+}
+
+/// TF-IDF model fitted on a corpus, with the corpus vectors kept for neighbor queries
+#[napi]
+pub struct TfidfModel {
+    sublinear_tf: bool,
+    vocabulary: AHashMap<String, u32>,
+    terms: Vec<String>,
+    idf: Vec<f64>,
+    vectors: Vec<Vector>,
+}
+
+impl TfidfModel {
+    fn vector(&self, text: &str) -> Vector {
+        let words = words(text, 2);
+        let mut vector: Vector = term_counts(&words)
+            .into_iter()
+            .filter_map(|(term, count)| {
+                let id = *self.vocabulary.get(term)?;
+                let tf = if self.sublinear_tf {
+                    1.0 + (count as f64).ln()
+                } else {
+                    count as f64
+                };
+                Some((id, tf * self.idf[id as usize]))
+            })
+            .collect();
+        let norm = vector.iter().map(|(_, w)| w * w).sum::<f64>().sqrt();
+        if norm > 0.0 {
+            for (_, weight) in &mut vector {
+                *weight /= norm;
+            }
+        }
+        vector.sort_unstable_by_key(|(id, _)| *id);
+        vector
+    }
+}
+
+#[napi]
+impl TfidfModel {
+    /**
+     * Fit term weights on `documents`
+     *
+     * Text is split into words as in extract_keywords: identifiers are
+     * broken at camelCase and snake_case boundaries, lowercased, and
+     * stopwords and language keywords are dropped. IDF is smoothed,
+     * ln((1 + n) / (1 + df)) + 1, so terms in every document still count a
+     * little.
+     */
+    #[napi(constructor)]
+    pub fn new(documents: Vec<String>, options: Option<TfidfOptions>) -> Result<Self> {
+        let sublinear_tf = options
+            .as_ref()
+            .and_then(|o| o.sublinear_tf)
+            .unwrap_or(true);
+        let min_df = options.as_ref().and_then(|o| o.min_df).unwrap_or(1).max(1) as usize;
+        let max_df = options.as_ref().and_then(|o| o.max_df).unwrap_or(1.0);
+        if !(max_df > 0.0 && max_df <= 1.0) {
+            return Err(Error::new(
+                Status::InvalidArg,
+                format!("maxDf must be in (0, 1], got {}", max_df),
+            ));
+        }
+        let max_features = options.and_then(|o| o.max_features);
+
+        let words: Vec<Vec<Option<String>>> = documents.par_iter().map(|d| words(d, 2)).collect();
+        // Document frequency and total count per term
+        let mut stats: AHashMap<&str, (usize, u64)> = AHashMap::new();
+        for document in &words {
+            for (term, count) in term_counts(document) {
+                let entry = stats.entry(term).or_default();
+                entry.0 += 1;
+                entry.1 += count as u64;
+            }
+        }
+        let n = documents.len();
+        let max_count = (max_df * n as f64).floor() as usize;
+        let mut kept: Vec<(&str, usize, u64)> = stats
+            .into_iter()
+            .filter(|(_, (df, _))| *df >= min_df && *df <= max_count.max(1))
+            .map(|(term, (df, total))| (term, df, total))
+            .collect();
+        // Most frequent first, so max_features keeps the top; ties by term for a stable vocabulary
+        kept.sort_by(|a, b| b.2.cmp(&a.2).then_with(|| a.0.cmp(b.0)));
+        if let Some(max) = max_features {
+            kept.truncate(max as usize);
+        }
+
+        let mut model = TfidfModel {
+            sublinear_tf,
+            vocabulary: AHashMap::with_capacity(kept.len()),
+            terms: Vec::with_capacity(kept.len()),
+            idf: Vec::with_capacity(kept.len()),
+            vectors: Vec::new(),
+        };
+        for (id, (term, df, _)) in kept.into_iter().enumerate() {
+            model.vocabulary.insert(term.to_string(), id as u32);
+            model.terms.push(term.to_string());
+            model
+                .idf
+                .push(((1 + n) as f64 / (1 + df) as f64).ln() + 1.0);
+        }
+        model.vectors = documents.par_iter().map(|d| model.vector(d)).collect();
+        Ok(model)
+    }
+
+    /// Weighted terms of `text`, heaviest first; words outside the vocabulary are left out
+    #[napi]
+    pub fn vectorize(&self, text: String) -> Vec<TfidfTerm> {
+        let mut terms: Vec<TfidfTerm> = self
+            .vector(&text)
+            .into_iter()
+            .map(|(id, weight)| TfidfTerm {
+                term: self.terms[id as usize].clone(),
+                weight,
+            })
+            .collect();
+        terms.sort_by(|a, b| {
+            b.weight
+                .total_cmp(&a.weight)
+                .then_with(|| a.term.cmp(&b.term))
+        });
+        terms
+    }
+
+    /// Cosine similarity of two texts' vectors, 0 when either has no known terms
+    #[napi]
+    pub fn similarity(&self, a: String, b: String) -> f64 {
+        dot(&self.vector(&a), &self.vector(&b))
+    }
+
+    /**
+     * Fitted documents most similar to `text`
+     *
+     * @param text - Query text
+     * @param limit - Most neighbors to return (default: 10)
+     * @param min_similarity - Lowest cosine similarity returned (default: any above 0)
+     * @returns Neighbors, most similar first, ties by index
+     */
+    #[napi]
+    pub fn nearest(
+        &self,
+        text: String,
+        limit: Option<u32>,
+        min_similarity: Option<f64>,
+    ) -> Vec<TfidfNeighbor> {
+        let query = self.vector(&text);
+        let min_similarity = min_similarity.unwrap_or(0.0);
+        let mut neighbors: Vec<TfidfNeighbor> = self
+            .vectors
+            .par_iter()
+            .enumerate()
+            .map(|(index, vector)| TfidfNeighbor {
+                index: index as u32,
+                similarity: dot(&query, vector),
+            })
+            .filter(|n| n.similarity > 0.0 && n.similarity >= min_similarity)
+            .collect();
+        neighbors.sort_by(|a, b| {
+            b.similarity
+                .total_cmp(&a.similarity)
+                .then(a.index.cmp(&b.index))
+        });
+        neighbors.truncate(limit.unwrap_or(10) as usize);
+        neighbors
+    }
+
+    /// Number of documents the model was fitted on
+    #[napi(getter)]
+    pub fn documents(&self) -> u32 {
+        self.vectors.len() as u32
+    }
+
+    /// Number of terms kept
+    #[napi(getter)]
+    pub fn vocabulary_size(&self) -> u32 {
+        self.terms.len() as u32
+    }
+}
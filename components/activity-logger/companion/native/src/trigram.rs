@@ -0,0 +1,184 @@
+/*!
+ * Trigram index
+ * Incremental candidate lookup for substring search over logged snippets
+ */
+
+use ahash::{AHashMap, AHashSet};
+use napi_derive::napi;
+
+/// Options for a TrigramIndex
+#[napi(object)]
+pub struct TrigramIndexOptions {
+    /// Distinguish ASCII case (default: false)
+    pub case_sensitive: Option<bool>,
+}
+
+/// Size counters for a TrigramIndex
+#[napi(object)]
+pub struct TrigramIndexStats {
+    pub documents: u32,
+    /// Distinct trigrams across all documents
+    pub trigrams: u32,
+    /// Document entries across all posting lists
+    pub postings: u32,
+}
+
+/// Three bytes packed into the low 24 bits
+fn trigrams(text: &str, case_sensitive: bool) -> AHashSet<u32> {
+    let bytes = text.as_bytes();
+    let fold = |b: u8| {
+        if case_sensitive {
+            b
+        } else {
+            b.to_ascii_lowercase()
+        }
+    };
+    bytes
+        .windows(3)
+        .map(|w| (fold(w[0]) as u32) << 16 | (fold(w[1]) as u32) << 8 | fold(w[2]) as u32)
+        .collect()
+}
+
+/// Inverted index from byte trigrams to documents, updated one document at a time
+#[napi]
+pub struct TrigramIndex {
+    case_sensitive: bool,
+    /// Document slot per id
+    slots: AHashMap<String, u32>,
+    /// Id and trigrams per slot; None once removed
+    documents: Vec<Option<(String, Vec<u32>)>>,
+    free: Vec<u32>,
+    postings: AHashMap<u32, AHashSet<u32>>,
+}
+
+#[napi]
+impl TrigramIndex {
+    /// Create an empty index, case-insensitive unless `case_sensitive` is set
+    #[napi(constructor)]
+    pub fn new(options: Option<TrigramIndexOptions>) -> Self {
+        TrigramIndex {
+            case_sensitive: options.and_then(|o| o.case_sensitive).unwrap_or(false),
+            slots: AHashMap::new(),
+            documents: Vec::new(),
+            free: Vec::new(),
+            postings: AHashMap::new(),
+        }
+    }
+
+    /// Index `text` under `id`, replacing any document already stored there
+    #[napi]
+    pub fn add_document(&mut self, id: String, text: String) {
+        self.remove(id.clone());
+        let grams: Vec<u32> = trigrams(&text, self.case_sensitive).into_iter().collect();
+        let slot = match self.free.pop() {
+            Some(slot) => slot,
+            None => {
+                self.documents.push(None);
+                self.documents.len() as u32 - 1
+            }
+        };
+        for gram in &grams {
+            self.postings.entry(*gram).or_default().insert(slot);
+        }
+        self.documents[slot as usize] = Some((id.clone(), grams));
+        self.slots.insert(id, slot);
+    }
+
+    /// Drop a document; returns whether it was indexed
+    #[napi]
+    pub fn remove(&mut self, id: String) -> bool {
+        let Some(slot) = self.slots.remove(&id) else {
+            return false;
+        };
+        if let Some((_, grams)) = self.documents[slot as usize].take() {
+            for gram in grams {
+                if let Some(posting) = self.postings.get_mut(&gram) {
+                    posting.remove(&slot);
+                    if posting.is_empty() {
+                        self.postings.remove(&gram);
+                    }
+                }
+            }
+        }
+        self.free.push(slot);
+        true
+    }
+
+    /**
+     * Ids of documents that may contain `text` as a substring
+     *
+     * A document is a candidate when it has every trigram of the query, so
+     * all true matches are returned and a few false positives may be;
+     * confirm with an exact search. Queries under three bytes have no
+     * trigrams and return every document.
+     *
+     * @param text - Literal substring to look for
+     * @param limit - Most candidates to return
+     * @returns Candidate ids
+     */
+    #[napi]
+    pub fn query(&self, text: String, limit: Option<u32>) -> Vec<String> {
+        let limit = limit.map_or(usize::MAX, |limit| limit as usize);
+        let grams = trigrams(&text, self.case_sensitive);
+        let mut lists = Vec::with_capacity(grams.len());
+        for gram in &grams {
+            match self.postings.get(gram) {
+                Some(posting) => lists.push(posting),
+                None => return Vec::new(),
+            }
+        }
+        let mut slots: Vec<u32> = match lists.iter().min_by_key(|list| list.len()) {
+            // Intersect by filtering the shortest posting list
+            Some(shortest) => shortest
+                .iter()
+                .copied()
+                .filter(|slot| lists.iter().all(|list| list.contains(slot)))
+                .collect(),
+            None => self
+                .documents
+                .iter()
+                .enumerate()
+                .filter(|(_, document)| document.is_some())
+                .map(|(slot, _)| slot as u32)
+                .collect(),
+        };
+        slots.sort_unstable();
+        slots
+            .into_iter()
+            .take(limit)
+            .filter_map(|slot| self.documents[slot as usize].as_ref())
+            .map(|(id, _)| id.clone())
+            .collect()
+    }
+
+    /// Whether a document is indexed under `id`
+    #[napi]
+    pub fn has(&self, id: String) -> bool {
+        self.slots.contains_key(&id)
+    }
+
+    /// Number of indexed documents
+    #[napi(getter)]
+    pub fn size(&self) -> u32 {
+        self.slots.len() as u32
+    }
+
+    /// Document, trigram, and posting counts
+    #[napi]
+    pub fn stats(&self) -> TrigramIndexStats {
+        TrigramIndexStats {
+            documents: self.slots.len() as u32,
+            trigrams: self.postings.len() as u32,
+            postings: self.postings.values().map(|p| p.len() as u32).sum(),
+        }
+    }
+
+    /// Drop every document
+    #[napi]
+    pub fn clear(&mut self) {
+        self.slots.clear();
+        self.documents.clear();
+        self.free.clear();
+        self.postings.clear();
+    }
+}
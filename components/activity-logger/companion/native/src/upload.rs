@@ -0,0 +1,477 @@
+/*!
+ * HTTP batch upload
+ * Compressed event batches posted to an ingest endpoint with retries,
+ * jittered backoff, and response checks, sent directly or drained from
+ * a DiskQueue
+ */
+
+use crate::msgpack::encode_batch;
+use crate::queue::QueueItem;
+use crate::sessions::days_from_civil;
+use crate::stats::SplitMix64;
+use flate2::write::GzEncoder;
+use napi::bindgen_prelude::*;
+use napi::JsObject;
+use napi_derive::napi;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io::Write;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const DEFAULT_TIMEOUT_MS: u32 = 10_000;
+const DEFAULT_MAX_ATTEMPTS: u32 = 5;
+const DEFAULT_INITIAL_BACKOFF_MS: u32 = 500;
+const DEFAULT_MAX_BACKOFF_MS: u32 = 30_000;
+const ZSTD_LEVEL: i32 = 3;
+
+/// Options for upload_events and upload_queued
+#[napi(object)]
+pub struct UploadOptions {
+    /// URL batches are POSTed to
+    pub endpoint: String,
+    /// Extra request headers, e.g. an authorization token
+    pub headers: Option<HashMap<String, String>>,
+    /// "json" (default), "ndjson", or "msgpack" (as encode_events); sets Content-Type, and how upload_events encodes a batch
+    pub format: Option<String>,
+    /// "gzip" (default), "zstd", or "none"; sets Content-Encoding
+    pub compression: Option<String>,
+    /// Tries per batch, the first included (default: 5)
+    pub max_attempts: Option<u32>,
+    /// Upper bound on the first retry's delay; doubles each retry, and the delay is drawn uniformly below it (default: 500)
+    pub initial_backoff_ms: Option<u32>,
+    /// Longest delay between tries, Retry-After included (default: 30000)
+    pub max_backoff_ms: Option<u32>,
+    /// Per-request timeout (default: 10000)
+    pub timeout_ms: Option<u32>,
+    /// Treat a success without a JSON object body as a failed try, e.g. a captive portal's HTML page (default: false)
+    pub expect_json: Option<bool>,
+}
+
+/// Outcome of upload_events
+#[napi(object)]
+pub struct UploadResult {
+    /// Status of the accepting response
+    pub status: u32,
+    /// Tries made, the first included
+    pub attempts: u32,
+    /// Batch size before compression
+    pub raw_bytes: u32,
+    /// Request body size as sent
+    pub sent_bytes: u32,
+    /// The response body, when it is JSON
+    pub response: Option<Value>,
+}
+
+/// Outcome of upload_queued; pass the ids on to the queue
+#[napi(object)]
+pub struct QueuedUploadResult {
+    /// Records the endpoint accepted, for DiskQueue.ack
+    pub delivered: Vec<i64>,
+    /// Records the endpoint refused outright (a 4xx other than 408 or 429); retrying won't help, so ack or set them aside
+    pub rejected: Vec<i64>,
+    /// Records not delivered for now, for DiskQueue.nack
+    pub failed: Vec<i64>,
+    /// Requests made across all records
+    pub attempts: u32,
+    /// Why the first rejected or failed record wasn't delivered
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Copy)]
+enum Format {
+    Json,
+    Ndjson,
+    Msgpack,
+}
+
+#[derive(Clone, Copy)]
+enum Compression {
+    Gzip,
+    Zstd,
+    None,
+}
+
+/// Why a try failed
+enum Failure {
+    /// Worth another try, after at least the server's Retry-After if it sent one
+    Transient(String, Option<u64>),
+    /// The endpoint refused this batch; sending it again gets the same answer
+    Rejected(String),
+}
+
+impl Failure {
+    fn message(&self) -> &str {
+        match self {
+            Failure::Transient(message, _) | Failure::Rejected(message) => message,
+        }
+    }
+}
+
+/// A successful try: status and parsed body
+type Accepted = (u16, Option<Value>);
+
+/// Validated options, shared by both tasks
+struct Sender {
+    endpoint: String,
+    headers: Vec<(String, String)>,
+    format: Format,
+    compression: Compression,
+    max_attempts: u32,
+    initial_backoff_ms: u64,
+    max_backoff_ms: u64,
+    timeout_ms: u64,
+    expect_json: bool,
+}
+
+impl Sender {
+    fn of(options: UploadOptions) -> Result<Self> {
+        let invalid = |message: String| Error::new(Status::InvalidArg, message);
+        if !options.endpoint.starts_with("http://") && !options.endpoint.starts_with("https://") {
+            return Err(invalid(format!(
+                "endpoint '{}' must be an http or https URL",
+                options.endpoint
+            )));
+        }
+        let format = match options.format.as_deref() {
+            None | Some("json") => Format::Json,
+            Some("ndjson") => Format::Ndjson,
+            Some("msgpack") => Format::Msgpack,
+            Some(other) => {
+                return Err(invalid(format!(
+                    "Unknown format '{}' (expected json, ndjson, or msgpack)",
+                    other
+                )))
+            }
+        };
+        let compression = match options.compression.as_deref() {
+            None | Some("gzip") => Compression::Gzip,
+            Some("zstd") => Compression::Zstd,
+            Some("none") => Compression::None,
+            Some(other) => {
+                return Err(invalid(format!(
+                    "Unknown compression '{}' (expected gzip, zstd, or none)",
+                    other
+                )))
+            }
+        };
+        let initial_backoff_ms = options
+            .initial_backoff_ms
+            .unwrap_or(DEFAULT_INITIAL_BACKOFF_MS) as u64;
+        let mut headers: Vec<_> = options.headers.unwrap_or_default().into_iter().collect();
+        headers.sort();
+        Ok(Sender {
+            endpoint: options.endpoint,
+            headers,
+            format,
+            compression,
+            max_attempts: options.max_attempts.unwrap_or(DEFAULT_MAX_ATTEMPTS).max(1),
+            initial_backoff_ms,
+            max_backoff_ms: (options.max_backoff_ms.unwrap_or(DEFAULT_MAX_BACKOFF_MS) as u64)
+                .max(initial_backoff_ms),
+            timeout_ms: options.timeout_ms.unwrap_or(DEFAULT_TIMEOUT_MS) as u64,
+            expect_json: options.expect_json.unwrap_or(false),
+        })
+    }
+
+    fn encode(&self, events: &[Value]) -> Result<Vec<u8>> {
+        match self.format {
+            Format::Json => Ok(Value::from(events).to_string().into_bytes()),
+            Format::Ndjson => {
+                let mut bytes = Vec::with_capacity(events.len() * 128);
+                for event in events {
+                    bytes.extend_from_slice(event.to_string().as_bytes());
+                    bytes.push(b'\n');
+                }
+                Ok(bytes)
+            }
+            Format::Msgpack => encode_batch(events),
+        }
+    }
+
+    fn compress(&self, body: &[u8]) -> Result<Vec<u8>> {
+        let failed = |e: std::io::Error| {
+            Error::new(
+                Status::GenericFailure,
+                format!("Failed to compress batch: {}", e),
+            )
+        };
+        match self.compression {
+            Compression::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(body).map_err(failed)?;
+                encoder.finish().map_err(failed)
+            }
+            Compression::Zstd => zstd::encode_all(body, ZSTD_LEVEL).map_err(failed),
+            Compression::None => Ok(body.to_vec()),
+        }
+    }
+
+    fn agent(&self) -> ureq::Agent {
+        ureq::AgentBuilder::new()
+            .timeout(Duration::from_millis(self.timeout_ms))
+            .build()
+    }
+
+    /// One POST, classified
+    fn try_once(&self, agent: &ureq::Agent, body: &[u8]) -> std::result::Result<Accepted, Failure> {
+        let content_type = match self.format {
+            Format::Json => "application/json",
+            Format::Ndjson => "application/x-ndjson",
+            Format::Msgpack => "application/msgpack",
+        };
+        let mut request = agent.post(&self.endpoint).set("Content-Type", content_type);
+        request = match self.compression {
+            Compression::Gzip => request.set("Content-Encoding", "gzip"),
+            Compression::Zstd => request.set("Content-Encoding", "zstd"),
+            Compression::None => request,
+        };
+        for (name, value) in &self.headers {
+            request = request.set(name, value);
+        }
+        let response = match request.send_bytes(body) {
+            Ok(response) => response,
+            Err(ureq::Error::Status(status, response)) => {
+                let retry_after = response
+                    .header("Retry-After")
+                    .and_then(|value| retry_after_ms(value, SystemTime::now()));
+                let message = format!(
+                    "{} returned {}: {}",
+                    self.endpoint,
+                    status,
+                    response.into_string().unwrap_or_default().trim()
+                );
+                return Err(match status {
+                    408 | 429 | 500..=599 => Failure::Transient(message, retry_after),
+                    _ => Failure::Rejected(message),
+                });
+            }
+            Err(other) => {
+                return Err(Failure::Transient(
+                    format!("Failed to reach {}: {}", self.endpoint, other),
+                    None,
+                ))
+            }
+        };
+        let status = response.status();
+        let text = response.into_string().map_err(|e| {
+            Failure::Transient(
+                format!("Failed to read response from {}: {}", self.endpoint, e),
+                None,
+            )
+        })?;
+        let body = serde_json::from_str::<Value>(&text).ok();
+        if self.expect_json && !matches!(body, Some(Value::Object(_))) {
+            return Err(Failure::Transient(
+                format!(
+                    "{} returned {} without a JSON object body",
+                    self.endpoint, status
+                ),
+                None,
+            ));
+        }
+        Ok((status, body))
+    }
+
+    /// Try until accepted, refused, or out of attempts, sleeping between tries
+    fn send(
+        &self,
+        agent: &ureq::Agent,
+        body: &[u8],
+        rng: &mut SplitMix64,
+        attempts: &mut u32,
+    ) -> std::result::Result<Accepted, Failure> {
+        let mut tries = 0;
+        loop {
+            tries += 1;
+            *attempts += 1;
+            let failure = match self.try_once(agent, body) {
+                Ok(accepted) => return Ok(accepted),
+                Err(failure @ Failure::Rejected(_)) => return Err(failure),
+                Err(failure) => failure,
+            };
+            if tries >= self.max_attempts {
+                return Err(failure);
+            }
+            std::thread::sleep(Duration::from_millis(self.delay(tries, &failure, rng)));
+        }
+    }
+
+    /// Full jitter: uniform below initial × 2^(retry − 1), capped, but no sooner than Retry-After
+    fn delay(&self, retry: u32, failure: &Failure, rng: &mut SplitMix64) -> u64 {
+        let ceiling = self
+            .initial_backoff_ms
+            .saturating_mul(1u64 << (retry - 1).min(32))
+            .min(self.max_backoff_ms);
+        let jittered = rng.below(ceiling as usize + 1) as u64;
+        match failure {
+            Failure::Transient(_, Some(retry_after)) => {
+                jittered.max(*retry_after).min(self.max_backoff_ms)
+            }
+            _ => jittered,
+        }
+    }
+}
+
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// Milliseconds to wait from `now`, for a Retry-After of delay-seconds or an
+/// HTTP-date ("Sun, 06 Nov 1994 08:49:37 GMT"); a date in the past means now
+fn retry_after_ms(value: &str, now: SystemTime) -> Option<u64> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(seconds.saturating_mul(1000));
+    }
+    let parts: Vec<&str> = value.split_whitespace().collect();
+    let [_, day, month, year, time, "GMT"] = parts[..] else {
+        return None;
+    };
+    let month = MONTHS.iter().position(|m| *m == month)? as i64 + 1;
+    let mut clock = time.split(':').map(|part| part.parse::<i64>().ok());
+    let (Some(Some(hour)), Some(Some(minute)), Some(Some(second)), None) =
+        (clock.next(), clock.next(), clock.next(), clock.next())
+    else {
+        return None;
+    };
+    let days = days_from_civil(year.parse().ok()?, month, day.parse().ok()?);
+    let at_ms = (days * 86_400 + hour * 3_600 + minute * 60 + second) * 1000;
+    let now_ms = now.duration_since(UNIX_EPOCH).ok()?.as_millis() as i64;
+    Some((at_ms - now_ms).max(0) as u64)
+}
+
+/// Seed for backoff jitter, so clients that failed together don't retry together
+fn jitter_seed() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos() as u64)
+        ^ std::process::id() as u64
+}
+
+fn upload(sender: &Sender, body: &[u8]) -> Result<UploadResult> {
+    let compressed = sender.compress(body)?;
+    let mut attempts = 0;
+    let (status, response) = sender
+        .send(
+            &sender.agent(),
+            &compressed,
+            &mut SplitMix64::new(jitter_seed()),
+            &mut attempts,
+        )
+        .map_err(|failure| {
+            Error::new(
+                Status::GenericFailure,
+                format!(
+                    "Upload failed after {} attempt(s): {}",
+                    attempts,
+                    failure.message()
+                ),
+            )
+        })?;
+    Ok(UploadResult {
+        status: status as u32,
+        attempts,
+        raw_bytes: body.len() as u32,
+        sent_bytes: compressed.len() as u32,
+        response,
+    })
+}
+
+fn upload_records(sender: &Sender, records: &[(i64, Vec<u8>)]) -> Result<QueuedUploadResult> {
+    let agent = sender.agent();
+    let mut rng = SplitMix64::new(jitter_seed());
+    let mut result = QueuedUploadResult {
+        delivered: Vec::new(),
+        rejected: Vec::new(),
+        failed: Vec::new(),
+        attempts: 0,
+        error: None,
+    };
+    for (index, (id, data)) in records.iter().enumerate() {
+        let compressed = sender.compress(data)?;
+        match sender.send(&agent, &compressed, &mut rng, &mut result.attempts) {
+            Ok(_) => result.delivered.push(*id),
+            Err(failure) => {
+                result
+                    .error
+                    .get_or_insert_with(|| failure.message().to_string());
+                if let Failure::Rejected(_) = failure {
+                    result.rejected.push(*id);
+                    continue;
+                }
+                // Out of attempts: likely offline, so leave the rest queued in order
+                result
+                    .failed
+                    .extend(records[index..].iter().map(|(id, _)| *id));
+                break;
+            }
+        }
+    }
+    Ok(result)
+}
+
+/// Run `work` on a thread of its own and settle a promise with its result
+///
+/// Uploads spend most of their time waiting on the network and sleeping
+/// between retries, which would hold one of the few libuv pool threads
+/// that file system and other async work share.
+fn spawn_upload<T, F>(env: &Env, work: F) -> Result<JsObject>
+where
+    T: ToNapiValue + Send + 'static,
+    F: FnOnce() -> Result<T> + Send + 'static,
+{
+    let (deferred, promise) = env.create_deferred()?;
+    std::thread::spawn(move || match work() {
+        Ok(output) => deferred.resolve(move |_env| Ok(output)),
+        Err(err) => deferred.reject(err),
+    });
+    Ok(promise)
+}
+
+/**
+ * Post a batch of events to an ingest endpoint, retrying transient failures
+ *
+ * The batch is encoded and compressed per `options` and sent from a thread
+ * of its own, so retries never tie up the libuv pool. Network errors,
+ * timeouts, 408, 429, and 5xx responses are retried up to max_attempts
+ * with exponential backoff and full jitter, honoring Retry-After as
+ * seconds or an HTTP date. Rejects once attempts run out, or at once on
+ * another 4xx, so the caller can keep the batch (e.g. in a DiskQueue).
+ *
+ * @param events - Events to send
+ * @param options - Endpoint, encoding, compression, and retry policy
+ * @returns The accepting response's status and body, and attempts made
+ */
+#[napi(
+    ts_args_type = "events: Array<Record<string, any>>, options: UploadOptions",
+    ts_return_type = "Promise<UploadResult>"
+)]
+pub fn upload_events(env: Env, events: Vec<Value>, options: UploadOptions) -> Result<JsObject> {
+    let sender = Sender::of(options)?;
+    let body = sender.encode(&events)?;
+    spawn_upload(&env, move || upload(&sender, &body))
+}
+
+/**
+ * Upload records dequeued from a DiskQueue, one request per record
+ *
+ * Each record is sent as it was enqueued, compressed per `options`, so
+ * enqueue batches already encoded in the chosen format. Records go in
+ * order with the same retry policy as upload_events. Once one runs out of
+ * attempts the rest are left for later rather than tried against an
+ * endpoint that is likely unreachable. Hand the ids in the result to
+ * ack and nack.
+ *
+ * @param items - Records from DiskQueue.dequeue
+ * @param options - Endpoint, format, compression, and retry policy
+ * @returns Ids delivered, rejected, and still to send
+ */
+#[napi(ts_return_type = "Promise<QueuedUploadResult>")]
+pub fn upload_queued(env: Env, items: Vec<QueueItem>, options: UploadOptions) -> Result<JsObject> {
+    let sender = Sender::of(options)?;
+    let records: Vec<(i64, Vec<u8>)> = items
+        .into_iter()
+        .map(|item| (item.id, item.data.to_vec()))
+        .collect();
+    spawn_upload(&env, move || upload_records(&sender, &records))
+}
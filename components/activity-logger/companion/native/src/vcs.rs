@@ -0,0 +1,220 @@
+/*!
+ * Git checkout and stash awareness
+ *
+ * Branch switches and stashes rewrite many files at once. Detecting them
+ * lets the timeline show one explicit VCS event instead of attributing
+ * hundreds of deleted lines to the user.
+ */
+
+use crate::events::{sorted_by_time, ActivityEvent};
+use ahash::AHashSet;
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+
+const DEFAULT_WINDOW_MS: i64 = 2_000;
+
+/// Point-in-time `git status` sample captured by the watcher
+#[napi(object)]
+pub struct GitStatusSnapshot {
+    pub timestamp: i64,
+    pub branch: Option<String>,
+    pub head: Option<String>,
+    pub stash_count: Option<u32>,
+    /// Paths reported as modified/untracked at this moment
+    pub dirty_files: Option<Vec<String>>,
+}
+
+/// Detected VCS operations and the file events they account for
+#[napi(object)]
+pub struct VcsDetection {
+    /// Timeline events of type "branch_switch", "stash", or "stash_pop"
+    pub events: Vec<ActivityEvent>,
+    /// Ids of file events caused by those operations rather than by editing
+    pub explained_event_ids: Vec<String>,
+}
+
+struct VcsOperation {
+    kind: &'static str,
+    /// Earliest time the operation could have happened
+    since: i64,
+    /// File events after this time (and within the window) are explained by the operation
+    after: i64,
+    timestamp: i64,
+    from_branch: Option<String>,
+    to_branch: Option<String>,
+    /// Files known to be touched; None means any file changed in the window
+    files: Option<AHashSet<String>>,
+}
+
+/**
+ * Detect branch switches and stashes from git status deltas and watcher events
+ *
+ * Compares consecutive status snapshots (branch, stash count, dirty files),
+ * and falls back to branch changes between consecutive events when no
+ * snapshot covers them. File events landing inside an operation's window
+ * are returned as explained so callers can exclude them from edit metrics.
+ *
+ * @param events - Watcher/activity events (with `branch` when known)
+ * @param snapshots - Git status samples in any order
+ * @param window_ms - Grace period after an operation for its file events (default: 2000)
+ */
+#[napi]
+pub fn detect_vcs_events(
+    events: Vec<ActivityEvent>,
+    snapshots: Vec<GitStatusSnapshot>,
+    window_ms: Option<i64>,
+) -> Result<VcsDetection> {
+    let window_ms = window_ms.unwrap_or(DEFAULT_WINDOW_MS);
+    let sorted_events = sorted_by_time(&events);
+    let mut snapshots: Vec<&GitStatusSnapshot> = snapshots.iter().collect();
+    snapshots.sort_by_key(|s| s.timestamp);
+
+    let mut operations = operations_from_snapshots(&snapshots);
+    let inferred: Vec<VcsOperation> = operations_from_events(&sorted_events, window_ms)
+        .into_iter()
+        .filter(|op| !covered_by(op, &operations, window_ms))
+        .collect();
+    operations.extend(inferred);
+    operations.sort_by_key(|op| op.timestamp);
+
+    let mut explained = Vec::new();
+    for event in &sorted_events {
+        let (Some(id), Some(path)) = (event.id.as_ref(), event.file_path.as_ref()) else {
+            continue;
+        };
+        let is_explained = operations.iter().any(|op| {
+            event.timestamp > op.after
+                && event.timestamp <= op.timestamp + window_ms
+                && op.files.as_ref().is_none_or(|files| files.contains(path))
+        });
+        if is_explained {
+            explained.push(id.clone());
+        }
+    }
+
+    let timeline = operations.iter().map(to_timeline_event).collect();
+
+    Ok(VcsDetection {
+        events: timeline,
+        explained_event_ids: explained,
+    })
+}
+
+fn operations_from_snapshots(snapshots: &[&GitStatusSnapshot]) -> Vec<VcsOperation> {
+    let mut operations = Vec::new();
+
+    for pair in snapshots.windows(2) {
+        let (prev, cur) = (pair[0], pair[1]);
+
+        if let (Some(from), Some(to)) = (&prev.branch, &cur.branch) {
+            if from != to {
+                operations.push(VcsOperation {
+                    kind: "branch_switch",
+                    since: prev.timestamp,
+                    after: prev.timestamp,
+                    timestamp: cur.timestamp,
+                    from_branch: Some(from.clone()),
+                    to_branch: Some(to.clone()),
+                    files: None,
+                });
+            }
+        }
+
+        if let (Some(before), Some(after)) = (prev.stash_count, cur.stash_count) {
+            let kind = match after.cmp(&before) {
+                std::cmp::Ordering::Greater => Some("stash"),
+                std::cmp::Ordering::Less => Some("stash_pop"),
+                std::cmp::Ordering::Equal => None,
+            };
+            if let Some(kind) = kind {
+                operations.push(VcsOperation {
+                    kind,
+                    since: prev.timestamp,
+                    after: prev.timestamp,
+                    timestamp: cur.timestamp,
+                    from_branch: cur.branch.clone(),
+                    to_branch: cur.branch.clone(),
+                    files: stash_files(kind, prev, cur),
+                });
+            }
+        }
+    }
+
+    operations
+}
+
+/// Files that left (stash) or entered (pop) the dirty set between two snapshots
+fn stash_files(
+    kind: &str,
+    prev: &GitStatusSnapshot,
+    cur: &GitStatusSnapshot,
+) -> Option<AHashSet<String>> {
+    let before: AHashSet<&String> = prev.dirty_files.as_ref()?.iter().collect();
+    let after: AHashSet<&String> = cur.dirty_files.as_ref()?.iter().collect();
+    let changed: AHashSet<String> = if kind == "stash" {
+        before.difference(&after).map(|s| (*s).clone()).collect()
+    } else {
+        after.difference(&before).map(|s| (*s).clone()).collect()
+    };
+    Some(changed)
+}
+
+fn operations_from_events(sorted: &[&ActivityEvent], window_ms: i64) -> Vec<VcsOperation> {
+    let mut operations = Vec::new();
+    let mut last: Option<(&str, i64)> = None;
+
+    for event in sorted {
+        let Some(branch) = event.branch.as_deref() else {
+            continue;
+        };
+        if let Some((prev_branch, prev_ts)) = last {
+            if prev_branch != branch {
+                // Only the first event on the new branch is observed, so just the
+                // burst of file events around it is attributed to the checkout
+                operations.push(VcsOperation {
+                    kind: "branch_switch",
+                    since: prev_ts,
+                    after: event.timestamp - window_ms,
+                    timestamp: event.timestamp,
+                    from_branch: Some(prev_branch.to_string()),
+                    to_branch: Some(branch.to_string()),
+                    files: None,
+                });
+            }
+        }
+        last = Some((branch, event.timestamp));
+    }
+
+    operations
+}
+
+fn covered_by(candidate: &VcsOperation, known: &[VcsOperation], window_ms: i64) -> bool {
+    known.iter().any(|op| {
+        op.kind == candidate.kind
+            && op.to_branch == candidate.to_branch
+            && candidate.since < op.timestamp + window_ms
+            && candidate.timestamp > op.since
+    })
+}
+
+fn to_timeline_event(op: &VcsOperation) -> ActivityEvent {
+    let details = match op.kind {
+        "branch_switch" => format!(
+            "Switched branch {} → {}",
+            op.from_branch.as_deref().unwrap_or("?"),
+            op.to_branch.as_deref().unwrap_or("?")
+        ),
+        "stash" => "Stashed working tree changes".to_string(),
+        _ => "Restored stashed changes".to_string(),
+    };
+
+    ActivityEvent {
+        id: Some(format!("vcs-{}-{}", op.kind, op.timestamp)),
+        timestamp: op.timestamp,
+        event_type: op.kind.to_string(),
+        ai_generated: Some(false),
+        details: Some(details),
+        branch: op.to_branch.clone(),
+        ..Default::default()
+    }
+}
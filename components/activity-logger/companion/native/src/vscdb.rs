@@ -0,0 +1,945 @@
+/*!
+ * Cursor storage
+ * AI chat transcripts and the suggestions and edits they produced, read
+ * straight from Cursor's state.vscdb SQLite files, the global one and each
+ * workspace's, and a watcher reporting rows as Cursor writes them
+ */
+
+use crate::ingest::parse_timestamp;
+use napi::bindgen_prelude::*;
+use napi::threadsafe_function::{
+    ErrorStrategy, ThreadSafeCallContext, ThreadsafeFunction, ThreadsafeFunctionCallMode,
+};
+use napi::JsFunction;
+use napi_derive::napi;
+use rusqlite::types::ValueRef;
+use rusqlite::{Connection, OpenFlags, OptionalExtension};
+use serde_json::{json, Map, Value};
+use similar::{ChangeTag, TextDiff};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// How long a read waits while Cursor holds a write lock
+const BUSY_TIMEOUT: Duration = Duration::from_millis(2000);
+/// Chat panel tabs in a workspace's ItemTable
+const CHAT_DATA_KEY: &str = "workbench.panel.aichat.view.aichat.chatdata";
+/// The key-value tables in a state.vscdb, the only ones a watcher reads
+const TABLES: [&str; 2] = ["ItemTable", "cursorDiskKV"];
+const DEFAULT_WATCH_INTERVAL_MS: u32 = 1000;
+const DEFAULT_WATCH_MAX_BATCH: u32 = 1000;
+/// Longest single sleep, so stop is noticed promptly between polls
+const MAX_SLEEP: Duration = Duration::from_millis(50);
+
+/// Options for read_cursor_chats
+#[napi(object)]
+#[derive(Default)]
+pub struct CursorChatOptions {
+    /// Skip transcripts whose last activity is before this, in epoch milliseconds
+    pub since: Option<f64>,
+}
+
+/// Options for read_cursor_ai_events
+#[napi(object)]
+#[derive(Default)]
+pub struct CursorAiEventOptions {
+    /// Skip events before this, in epoch milliseconds
+    pub since: Option<f64>,
+    /// Keep only events for files under this directory
+    pub workspace_path: Option<String>,
+}
+
+/// One turn of a Cursor chat
+#[napi(object)]
+pub struct CursorChatMessage {
+    /// Bubble or generation id, when Cursor stored one
+    pub id: Option<String>,
+    /// "user" or "assistant"
+    pub role: String,
+    pub text: String,
+    /// Epoch milliseconds, when Cursor stored one
+    pub timestamp: Option<f64>,
+    pub model: Option<String>,
+}
+
+/// A conversation read from state.vscdb
+#[napi(object)]
+pub struct CursorChatTranscript {
+    /// Composer id, chat tab id, or "aiService"
+    pub id: String,
+    /// "composer" (cursorDiskKV composerData), "chat" (the chat panel), or "aiService" (prompt history)
+    pub source: String,
+    pub title: Option<String>,
+    pub created_at: Option<f64>,
+    /// Last activity, from the record or its newest message
+    pub updated_at: Option<f64>,
+    /// Model configured for the conversation, else the last one that answered
+    pub model: Option<String>,
+    pub messages: Vec<CursorChatMessage>,
+}
+
+fn db_error(path: &str, error: rusqlite::Error) -> Error {
+    Error::new(
+        Status::GenericFailure,
+        format!("Cursor database {}: {}", path, error),
+    )
+}
+
+/// Open a state.vscdb read-only, so Cursor's own writes are never blocked for long
+pub(crate) fn open_vscdb(path: &str) -> Result<Connection> {
+    let connection = Connection::open_with_flags(
+        path,
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )
+    .map_err(|e| db_error(path, e))?;
+    connection
+        .busy_timeout(BUSY_TIMEOUT)
+        .map_err(|e| db_error(path, e))?;
+    Ok(connection)
+}
+
+pub(crate) fn has_table(connection: &Connection, path: &str, table: &str) -> Result<bool> {
+    connection
+        .query_row(
+            "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1",
+            [table],
+            |_| Ok(()),
+        )
+        .optional()
+        .map(|found| found.is_some())
+        .map_err(|e| db_error(path, e))
+}
+
+/// A stored value as JSON; Cursor writes both TEXT and BLOB values
+pub(crate) fn json_value(value: ValueRef) -> Option<Value> {
+    match value {
+        ValueRef::Text(bytes) | ValueRef::Blob(bytes) => serde_json::from_slice(bytes).ok(),
+        _ => None,
+    }
+}
+
+/// The JSON value stored under `key` in ItemTable
+pub(crate) fn item(connection: &Connection, path: &str, key: &str) -> Result<Option<Value>> {
+    if !has_table(connection, path, "ItemTable")? {
+        return Ok(None);
+    }
+    connection
+        .query_row("SELECT value FROM ItemTable WHERE key = ?1", [key], |row| {
+            Ok(json_value(row.get_ref(0)?))
+        })
+        .optional()
+        .map(Option::flatten)
+        .map_err(|e| db_error(path, e))
+}
+
+/// Every cursorDiskKV entry whose key starts with `prefix`, with its rowid,
+/// as (rowid, key, JSON) in key order; entries that aren't JSON are skipped
+pub(crate) fn disk_entries(
+    connection: &Connection,
+    path: &str,
+    prefix: &str,
+) -> Result<Vec<(i64, String, Value)>> {
+    if !has_table(connection, path, "cursorDiskKV")? {
+        return Ok(Vec::new());
+    }
+    // A key range rather than LIKE, so the primary key index is used
+    let mut end = prefix.to_string();
+    let last = end
+        .pop()
+        .map_or('\u{0}', |c| char::from_u32(c as u32 + 1).unwrap_or(c));
+    end.push(last);
+    let mut statement = connection
+        .prepare_cached(
+            "SELECT rowid, key, value FROM cursorDiskKV WHERE key >= ?1 AND key < ?2 ORDER BY key",
+        )
+        .map_err(|e| db_error(path, e))?;
+    let rows = statement
+        .query_map([prefix, end.as_str()], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                json_value(row.get_ref(2)?),
+            ))
+        })
+        .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+        .map_err(|e| db_error(path, e))?;
+    Ok(rows
+        .into_iter()
+        .filter_map(|(rowid, key, value)| Some((rowid, key, value?)))
+        .collect())
+}
+
+pub(crate) fn text_of<'a>(record: &'a Map<String, Value>, keys: &[&str]) -> Option<&'a str> {
+    keys.iter()
+        .find_map(|key| record.get(*key)?.as_str())
+        .map(str::trim)
+        .filter(|text| !text.is_empty())
+}
+
+pub(crate) fn time_of(record: &Map<String, Value>, keys: &[&str]) -> Option<f64> {
+    keys.iter()
+        .find_map(|key| parse_timestamp(record.get(*key)?))
+}
+
+/// A string at a path of object keys, e.g. modelInfo.modelName
+pub(crate) fn nested_text(record: &Map<String, Value>, path: &[&str]) -> Option<String> {
+    let (last, parents) = path.split_last()?;
+    let mut object = record;
+    for key in parents {
+        object = object.get(*key)?.as_object()?;
+    }
+    text_of(object, &[last]).map(str::to_string)
+}
+
+/// A composer bubble: type 1 is the user, 2 the assistant
+fn bubble_message(bubble: &Map<String, Value>) -> Option<CursorChatMessage> {
+    let role = match bubble.get("type")?.as_u64()? {
+        1 => "user",
+        2 => "assistant",
+        _ => return None,
+    };
+    let timestamp = time_of(bubble, &["createdAt", "timestamp"]).or_else(|| {
+        let timing = bubble.get("timingInfo")?.as_object()?;
+        time_of(timing, &["clientStartTime", "clientRpcSendTime"])
+    });
+    Some(CursorChatMessage {
+        id: text_of(bubble, &["bubbleId"]).map(str::to_string),
+        role: role.to_string(),
+        text: text_of(bubble, &["text", "rawText"])?.to_string(),
+        timestamp,
+        model: nested_text(bubble, &["modelInfo", "modelName"]),
+    })
+}
+
+fn finish(mut transcript: CursorChatTranscript) -> CursorChatTranscript {
+    let newest = transcript
+        .messages
+        .iter()
+        .filter_map(|m| m.timestamp)
+        .fold(None, |newest: Option<f64>, t| {
+            Some(newest.map_or(t, |n| n.max(t)))
+        });
+    transcript.updated_at = match (transcript.updated_at, newest) {
+        (Some(a), Some(b)) => Some(a.max(b)),
+        (a, b) => a.or(b),
+    };
+    if transcript.model.is_none() {
+        transcript.model = transcript
+            .messages
+            .iter()
+            .rev()
+            .find_map(|m| m.model.clone().filter(|_| m.role == "assistant"));
+    }
+    transcript
+}
+
+fn composer_id(key: &str, composer: &Map<String, Value>) -> String {
+    text_of(composer, &["composerId"])
+        .unwrap_or(&key["composerData:".len()..])
+        .to_string()
+}
+
+/// Composer conversations from cursorDiskKV (global storage)
+fn composer_transcripts(connection: &Connection, path: &str) -> Result<Vec<CursorChatTranscript>> {
+    let mut transcripts = Vec::new();
+    for (_, key, value) in disk_entries(connection, path, "composerData:")? {
+        let Value::Object(composer) = value else {
+            continue;
+        };
+        let id = composer_id(&key, &composer);
+        // Newer versions keep only headers inline and each bubble under its own key
+        let inline = composer.get("conversation").and_then(Value::as_array);
+        let messages: Vec<CursorChatMessage> = match inline.filter(|c| !c.is_empty()) {
+            Some(conversation) => conversation
+                .iter()
+                .filter_map(|bubble| bubble_message(bubble.as_object()?))
+                .collect(),
+            None => {
+                let prefix = format!("bubbleId:{}:", id);
+                let bubbles: HashMap<String, Map<String, Value>> =
+                    disk_entries(connection, path, &prefix)?
+                        .into_iter()
+                        .filter_map(|(_, key, value)| match value {
+                            Value::Object(bubble) => {
+                                Some((key[prefix.len()..].to_string(), bubble))
+                            }
+                            _ => None,
+                        })
+                        .collect();
+                composer
+                    .get("fullConversationHeadersOnly")
+                    .and_then(Value::as_array)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|header| {
+                        let bubble_id = header.get("bubbleId")?.as_str()?;
+                        let mut message = bubble_message(bubbles.get(bubble_id)?)?;
+                        message.id.get_or_insert_with(|| bubble_id.to_string());
+                        Some(message)
+                    })
+                    .collect()
+            }
+        };
+        transcripts.push(finish(CursorChatTranscript {
+            id,
+            source: "composer".to_string(),
+            title: text_of(&composer, &["name"]).map(str::to_string),
+            created_at: time_of(&composer, &["createdAt"]),
+            updated_at: time_of(&composer, &["lastUpdatedAt"]),
+            model: nested_text(&composer, &["modelConfig", "modelName"]),
+            messages,
+        }));
+    }
+    Ok(transcripts)
+}
+
+/// Chat panel tabs from a workspace's ItemTable
+fn chat_transcripts(connection: &Connection, path: &str) -> Result<Vec<CursorChatTranscript>> {
+    let Some(data) = item(connection, path, CHAT_DATA_KEY)? else {
+        return Ok(Vec::new());
+    };
+    let tabs = data.get("tabs").and_then(Value::as_array);
+    Ok(tabs
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_object)
+        .map(|tab| {
+            let messages = tab
+                .get("bubbles")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(|bubble| {
+                    let bubble = bubble.as_object()?;
+                    let role = match bubble.get("type")?.as_str()? {
+                        "user" => "user",
+                        "ai" => "assistant",
+                        _ => return None,
+                    };
+                    Some(CursorChatMessage {
+                        id: text_of(bubble, &["id"]).map(str::to_string),
+                        role: role.to_string(),
+                        text: text_of(bubble, &["text", "rawText"])?.to_string(),
+                        timestamp: time_of(bubble, &["timestamp", "createdAt"]),
+                        model: text_of(bubble, &["modelType"]).map(str::to_string),
+                    })
+                })
+                .collect();
+            finish(CursorChatTranscript {
+                id: text_of(tab, &["tabId"]).unwrap_or_default().to_string(),
+                source: "chat".to_string(),
+                title: text_of(tab, &["chatTitle"]).map(str::to_string),
+                created_at: None,
+                updated_at: time_of(tab, &["lastSendTime"]),
+                model: None,
+                messages,
+            })
+        })
+        .collect())
+}
+
+/// Prompt history from aiService.prompts and aiService.generations
+fn ai_service_transcript(
+    connection: &Connection,
+    path: &str,
+) -> Result<Option<CursorChatTranscript>> {
+    let records = |key: &str| -> Result<Vec<Map<String, Value>>> {
+        Ok(match item(connection, path, key)? {
+            Some(Value::Array(records)) => records
+                .into_iter()
+                .filter_map(|record| match record {
+                    Value::Object(record) => Some(record),
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        })
+    };
+    let message = |record: &Map<String, Value>, role: &str| {
+        Some(CursorChatMessage {
+            id: text_of(record, &["generationUUID", "uuid"]).map(str::to_string),
+            role: role.to_string(),
+            text: text_of(record, &["text", "textDescription", "prompt", "content"])?.to_string(),
+            timestamp: time_of(record, &["unixMs", "timestamp"]),
+            model: text_of(record, &["model", "modelName"]).map(str::to_string),
+        })
+    };
+    let mut messages: Vec<CursorChatMessage> = records("aiService.prompts")?
+        .iter()
+        .filter_map(|record| message(record, "user"))
+        .chain(
+            records("aiService.generations")?
+                .iter()
+                .filter_map(|record| message(record, "assistant")),
+        )
+        .collect();
+    if messages.is_empty() {
+        return Ok(None);
+    }
+    // Stable, so messages without a time keep their stored order up front
+    messages.sort_by(|a, b| {
+        a.timestamp
+            .unwrap_or(0.0)
+            .total_cmp(&b.timestamp.unwrap_or(0.0))
+    });
+    Ok(Some(finish(CursorChatTranscript {
+        id: "aiService".to_string(),
+        source: "aiService".to_string(),
+        title: None,
+        created_at: None,
+        updated_at: None,
+        model: None,
+        messages,
+    })))
+}
+
+fn read_chats(path: &str, options: &CursorChatOptions) -> Result<Vec<CursorChatTranscript>> {
+    let connection = open_vscdb(path)?;
+    let mut transcripts = composer_transcripts(&connection, path)?;
+    transcripts.extend(chat_transcripts(&connection, path)?);
+    transcripts.extend(ai_service_transcript(&connection, path)?);
+    transcripts.retain(|t| !t.messages.is_empty());
+    if let Some(since) = options.since {
+        transcripts.retain(|t| t.updated_at.is_some_and(|updated| updated >= since));
+    }
+    transcripts.sort_by(|a, b| {
+        a.updated_at
+            .unwrap_or(0.0)
+            .total_cmp(&b.updated_at.unwrap_or(0.0))
+    });
+    Ok(transcripts)
+}
+
+pub struct CursorChatTask {
+    path: String,
+    options: CursorChatOptions,
+}
+
+impl Task for CursorChatTask {
+    type Output = Vec<CursorChatTranscript>;
+    type JsValue = Vec<CursorChatTranscript>;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        read_chats(&self.path, &self.options)
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(output)
+    }
+}
+
+/**
+ * Read the AI chat transcripts stored in a Cursor state.vscdb
+ *
+ * Collects composer conversations from cursorDiskKV (in the global
+ * storage database, following each bubble's own key in newer versions),
+ * chat panel tabs from a workspace database's ItemTable, and the
+ * aiService prompt history. The database is opened read-only off the main
+ * thread, so Cursor can keep writing to it. Messages without text, such
+ * as tool-call bubbles, are skipped, as are transcripts left empty.
+ *
+ * @param path - A state.vscdb, e.g. under User/globalStorage or User/workspaceStorage/<id>
+ * @param options - Only transcripts active since a time
+ * @returns Transcripts, least recently active first
+ */
+#[napi(ts_return_type = "Promise<Array<CursorChatTranscript>>")]
+pub fn read_cursor_chats(
+    path: String,
+    options: Option<CursorChatOptions>,
+) -> Result<AsyncTask<CursorChatTask>> {
+    Ok(AsyncTask::new(CursorChatTask {
+        path,
+        options: options.unwrap_or_default(),
+    }))
+}
+
+/// A file path from a VS Code URI object, or from a URI string
+fn uri_path(uri: &Value) -> Option<String> {
+    match uri {
+        Value::Object(uri) => text_of(uri, &["fsPath", "path"])
+            .map(str::to_string)
+            .or_else(|| uri.get("external").and_then(uri_path)),
+        Value::String(text) => Some(text.strip_prefix("file://").unwrap_or(text).to_string()),
+        _ => None,
+    }
+}
+
+/// ai_suggestion events for the code blocks a composer proposed
+fn code_block_events(key: &str, composer: &Map<String, Value>, events: &mut Vec<Value>) {
+    let Some(Value::Object(files)) = composer.get("codeBlockData") else {
+        return;
+    };
+    let composer_id = composer_id(key, composer);
+    let model = nested_text(composer, &["modelConfig", "modelName"]);
+    // unifiedMode in current versions, forceMode before it
+    let style = text_of(composer, &["unifiedMode", "forceMode"]).unwrap_or("chat");
+    let fallback_time = time_of(composer, &["lastUpdatedAt", "createdAt"]);
+    for (uri, blocks) in files {
+        let blocks: Vec<&Value> = match blocks {
+            Value::Array(blocks) => blocks.iter().collect(),
+            Value::Object(blocks) => blocks.values().collect(),
+            _ => continue,
+        };
+        for (index, block) in blocks.into_iter().enumerate() {
+            let Value::Object(block) = block else {
+                continue;
+            };
+            let Some(timestamp) = time_of(block, &["createdAt", "timestamp"]).or(fallback_time)
+            else {
+                continue;
+            };
+            let status = text_of(block, &["status"]).unwrap_or("none");
+            let accepted = match status {
+                "accepted" => Some(true),
+                "rejected" => Some(false),
+                _ => None,
+            };
+            let block_id = block
+                .get("codeBlockIdx")
+                .map(|idx| idx.to_string())
+                .unwrap_or_else(|| index.to_string());
+            let file_path = block
+                .get("uri")
+                .and_then(uri_path)
+                .or_else(|| uri_path(&Value::String(uri.clone())));
+            let lines = text_of(block, &["content"]).map(|content| content.lines().count());
+            events.push(json!({
+                "id": format!("cursor:{}:{}:{}", composer_id, uri, block_id),
+                "type": "ai_suggestion",
+                "timestamp": timestamp,
+                "sessionId": composer_id,
+                "filePath": file_path,
+                "aiGenerated": true,
+                "accepted": accepted,
+                "model": model,
+                "promptStyle": style,
+                "details": {
+                    "source": "composer",
+                    "status": status,
+                    "bubbleId": text_of(block, &["bubbleId"]),
+                    "languageId": text_of(block, &["languageId"]),
+                    "lines": lines,
+                },
+            }));
+        }
+    }
+}
+
+/// code_change events for edits Cursor applied inline, from inlineDiffs-<workspace> entries
+fn inline_diff_events(workspace_id: &str, diffs: &Value, events: &mut Vec<Value>) {
+    let lines = |diff: &Map<String, Value>, key: &str| -> Vec<String> {
+        diff.get(key)
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|line| Some(format!("{}\n", line.as_str()?)))
+            .collect()
+    };
+    for diff in diffs.as_array().into_iter().flatten() {
+        let Value::Object(diff) = diff else {
+            continue;
+        };
+        let (Some(diff_id), Some(timestamp)) = (
+            text_of(diff, &["diffId"]),
+            time_of(diff, &["timestamp", "createdAt"]),
+        ) else {
+            continue;
+        };
+        let (original, modified) = (
+            lines(diff, "originalTextLines"),
+            lines(diff, "modifiedTextLines"),
+        );
+        let original: Vec<&str> = original.iter().map(String::as_str).collect();
+        let modified: Vec<&str> = modified.iter().map(String::as_str).collect();
+        let (mut added, mut removed) = (0, 0);
+        for change in TextDiff::from_slices(&original, &modified).iter_all_changes() {
+            match change.tag() {
+                ChangeTag::Insert => added += 1,
+                ChangeTag::Delete => removed += 1,
+                ChangeTag::Equal => {}
+            }
+        }
+        events.push(json!({
+            "id": format!("cursor:diff:{}", diff_id),
+            "type": "code_change",
+            "timestamp": timestamp,
+            "sessionId": text_of(diff, &["composerId"]),
+            "filePath": diff.get("uri").and_then(uri_path),
+            "linesAdded": added,
+            "linesRemoved": removed,
+            "aiGenerated": true,
+            "accepted": true,
+            "promptStyle": "inline",
+            "details": {
+                "source": "inline_diff",
+                "diffId": diff_id,
+                "promptId": text_of(diff, &["promptId"]),
+                "workspaceId": workspace_id,
+            },
+        }));
+    }
+}
+
+fn read_ai_events(path: &str, options: &CursorAiEventOptions) -> Result<Vec<Value>> {
+    let connection = open_vscdb(path)?;
+    let mut events = Vec::new();
+    for (_, key, value) in disk_entries(&connection, path, "composerData:")? {
+        if let Value::Object(composer) = value {
+            code_block_events(&key, &composer, &mut events);
+        }
+    }
+    for (_, key, value) in disk_entries(&connection, path, "inlineDiffs-")? {
+        inline_diff_events(&key["inlineDiffs-".len()..], &value, &mut events);
+    }
+    let timestamp = |event: &Value| event["timestamp"].as_f64().unwrap_or(0.0);
+    events.retain(|event| {
+        options.since.is_none_or(|since| timestamp(event) >= since)
+            && options.workspace_path.as_deref().is_none_or(|root| {
+                event["filePath"]
+                    .as_str()
+                    .is_some_and(|file| file.starts_with(root))
+            })
+    });
+    events.sort_by(|a, b| timestamp(a).total_cmp(&timestamp(b)));
+    Ok(events)
+}
+
+pub struct CursorAiEventTask {
+    path: String,
+    options: CursorAiEventOptions,
+}
+
+impl Task for CursorAiEventTask {
+    type Output = Vec<Value>;
+    type JsValue = Vec<Value>;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        read_ai_events(&self.path, &self.options)
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(output)
+    }
+}
+
+/**
+ * Read the AI suggestions and applied edits recorded in a Cursor state.vscdb
+ *
+ * Normalizes Cursor's own records into events for insert_batch or
+ * ingest_batch, so the interactions behind a change are captured and not
+ * just the diff it left. Each code block a composer proposed becomes an
+ * `ai_suggestion` event, `accepted` true or false once the user kept or
+ * rejected it and unset while pending; `details.status` keeps Cursor's
+ * status. Each inline diff Cursor applied becomes a `code_change` event
+ * with its line counts. Records without a time are skipped. Ids are
+ * derived from Cursor's, so re-reading the same database replaces events
+ * rather than duplicating them.
+ *
+ * @param path - A state.vscdb, usually User/globalStorage/state.vscdb
+ * @param options - Only events since a time, or under a workspace
+ * @returns Events, oldest first
+ */
+#[napi(ts_return_type = "Promise<Array<Record<string, any>>>")]
+pub fn read_cursor_ai_events(
+    path: String,
+    options: Option<CursorAiEventOptions>,
+) -> Result<AsyncTask<CursorAiEventTask>> {
+    Ok(AsyncTask::new(CursorAiEventTask {
+        path,
+        options: options.unwrap_or_default(),
+    }))
+}
+
+/// Options for CursorDbWatcher.start
+#[napi(object)]
+#[derive(Default)]
+pub struct CursorWatchOptions {
+    /// Time between polls (default: 1000)
+    pub interval_ms: Option<u32>,
+    /// Tables to watch, of ItemTable and cursorDiskKV (default: both)
+    pub tables: Option<Vec<String>>,
+    /// Report only keys starting with one of these, e.g. "composerData:" (default: every key)
+    pub key_prefixes: Option<Vec<String>>,
+    /// Last rowid already seen per table, from a previous watcher's cursor(); tables not listed start at their current end
+    pub cursor: Option<HashMap<String, i64>>,
+    /// Most rows read per table per poll; the rest wait for the next (default: 1000)
+    pub max_batch: Option<u32>,
+}
+
+/// A row written to a watched table
+#[napi(object)]
+pub struct CursorDbChange {
+    pub table: String,
+    pub key: String,
+    pub rowid: i64,
+    /// The value parsed as JSON, else as text, else null
+    pub value: Value,
+}
+
+/// Counters for a CursorDbWatcher
+#[napi(object)]
+pub struct CursorWatchStats {
+    /// Polls that found the database changed and read it
+    pub reads: u32,
+    /// Polls skipped because nothing was committed since the last read
+    pub skipped: u32,
+    /// Rows passed to the callback
+    pub changes: f64,
+    /// Polls that failed, e.g. while the file was being replaced
+    pub errors: u32,
+    pub last_error: Option<String>,
+}
+
+fn change_value(value: ValueRef) -> Value {
+    json_value(value).unwrap_or_else(|| match value {
+        ValueRef::Text(bytes) | ValueRef::Blob(bytes) => std::str::from_utf8(bytes)
+            .map(|text| Value::String(text.to_string()))
+            .unwrap_or(Value::Null),
+        _ => Value::Null,
+    })
+}
+
+fn max_rowid(connection: &Connection, path: &str, table: &str) -> Result<i64> {
+    connection
+        .query_row(
+            &format!("SELECT COALESCE(MAX(rowid), 0) FROM {}", table),
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|e| db_error(path, e))
+}
+
+/// Where each table was last read up to, and the poll that advances it
+struct Watch {
+    path: String,
+    connection: Connection,
+    /// (table, last rowid seen)
+    cursor: Arc<Mutex<Vec<(String, i64)>>>,
+    key_prefixes: Vec<String>,
+    max_batch: u32,
+    /// PRAGMA data_version at the last read; it changes when another connection commits
+    data_version: Option<i64>,
+    /// The last poll stopped at max_batch with rows left to read
+    behind: bool,
+}
+
+impl Watch {
+    /// Rows added since the last poll, or None when nothing was committed
+    fn poll(&mut self) -> Result<Option<Vec<CursorDbChange>>> {
+        let version: i64 = self
+            .connection
+            .query_row("PRAGMA data_version", [], |row| row.get(0))
+            .map_err(|e| db_error(&self.path, e))?;
+        if self.data_version == Some(version) && !self.behind {
+            return Ok(None);
+        }
+        self.behind = false;
+        let mut changes = Vec::new();
+        let mut tables = self.cursor.lock().unwrap().clone();
+        for (table, after) in tables.iter_mut() {
+            let mut statement = self
+                .connection
+                .prepare_cached(&format!(
+                    "SELECT rowid, key, value FROM {} WHERE rowid > ?1 ORDER BY rowid LIMIT ?2",
+                    table
+                ))
+                .map_err(|e| db_error(&self.path, e))?;
+            let rows = statement
+                .query_map([*after, self.max_batch as i64], |row| {
+                    Ok(CursorDbChange {
+                        table: table.clone(),
+                        rowid: row.get(0)?,
+                        key: row.get(1)?,
+                        value: change_value(row.get_ref(2)?),
+                    })
+                })
+                .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+                .map_err(|e| db_error(&self.path, e))?;
+            self.behind = rows.len() as u32 == self.max_batch;
+            if let Some(last) = rows.last() {
+                *after = last.rowid;
+            }
+            changes.extend(rows.into_iter().filter(|change| {
+                self.key_prefixes.is_empty()
+                    || self
+                        .key_prefixes
+                        .iter()
+                        .any(|prefix| change.key.starts_with(prefix.as_str()))
+            }));
+            if self.behind {
+                break;
+            }
+        }
+        // Advanced only once every table was read, so a failed poll is retried whole
+        *self.cursor.lock().unwrap() = tables;
+        self.data_version = Some(version);
+        Ok(Some(changes))
+    }
+}
+
+/// Watches a state.vscdb and reports rows Cursor adds, without re-reading whole tables
+#[napi]
+pub struct CursorDbWatcher {
+    cursor: Arc<Mutex<Vec<(String, i64)>>>,
+    stats: Arc<Mutex<CursorWatchStats>>,
+    stopped: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+#[napi]
+impl CursorDbWatcher {
+    /**
+     * Poll the state.vscdb at `path` and call `callback` with rows added since the last poll
+     *
+     * Each table is read past the highest rowid seen so far, so a poll
+     * costs the new rows rather than the table. VS Code's storage writes a
+     * changed key as a replacement row, so updates to existing keys arrive
+     * too, with a new rowid. A poll is skipped outright when SQLite reports
+     * no commit since the last read. Reads happen on a background thread
+     * over a read-only connection; the callback gets one batch per poll
+     * that found matching rows.
+     */
+    #[napi(
+        factory,
+        ts_args_type = "path: string, callback: (changes: Array<CursorDbChange>) => void, options?: CursorWatchOptions | undefined | null"
+    )]
+    pub fn start(
+        path: String,
+        callback: JsFunction,
+        options: Option<CursorWatchOptions>,
+    ) -> Result<Self> {
+        let options = options.unwrap_or_default();
+        let tables = match options.tables {
+            Some(tables) => {
+                if let Some(unknown) = tables.iter().find(|t| !TABLES.contains(&t.as_str())) {
+                    return Err(Error::new(
+                        Status::InvalidArg,
+                        format!(
+                            "Unknown table '{}' (expected ItemTable or cursorDiskKV)",
+                            unknown
+                        ),
+                    ));
+                }
+                tables
+            }
+            None => TABLES.iter().map(|t| t.to_string()).collect(),
+        };
+        let connection = open_vscdb(&path)?;
+        let mut cursor = Vec::new();
+        for table in tables {
+            // A workspace database may lack cursorDiskKV; watch what is there
+            if !has_table(&connection, &path, &table)? {
+                continue;
+            }
+            let start = match options.cursor.as_ref().and_then(|c| c.get(&table)) {
+                Some(rowid) => *rowid,
+                None => max_rowid(&connection, &path, &table)?,
+            };
+            cursor.push((table, start));
+        }
+        let cursor = Arc::new(Mutex::new(cursor));
+        let mut watch = Watch {
+            path,
+            connection,
+            cursor: cursor.clone(),
+            key_prefixes: options.key_prefixes.unwrap_or_default(),
+            max_batch: options.max_batch.unwrap_or(DEFAULT_WATCH_MAX_BATCH).max(1),
+            data_version: None,
+            behind: false,
+        };
+        let interval =
+            Duration::from_millis(options.interval_ms.unwrap_or(DEFAULT_WATCH_INTERVAL_MS) as u64);
+        let tsfn: ThreadsafeFunction<Vec<CursorDbChange>, ErrorStrategy::Fatal> = callback
+            .create_threadsafe_function(0, |ctx: ThreadSafeCallContext<Vec<CursorDbChange>>| {
+                Ok(vec![ctx.value])
+            })?;
+        let stats = Arc::new(Mutex::new(CursorWatchStats {
+            reads: 0,
+            skipped: 0,
+            changes: 0.0,
+            errors: 0,
+            last_error: None,
+        }));
+        let stopped = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let stats = stats.clone();
+            let stopped = stopped.clone();
+            std::thread::spawn(move || {
+                while !stopped.load(Ordering::Relaxed) {
+                    let started = Instant::now();
+                    let polled = watch.poll();
+                    let mut stats = stats.lock().unwrap();
+                    match polled {
+                        Ok(None) => stats.skipped += 1,
+                        Ok(Some(changes)) => {
+                            stats.reads += 1;
+                            stats.changes += changes.len() as f64;
+                            if !changes.is_empty() {
+                                tsfn.call(changes, ThreadsafeFunctionCallMode::NonBlocking);
+                            }
+                        }
+                        Err(e) => {
+                            stats.errors += 1;
+                            stats.last_error = Some(e.reason);
+                        }
+                    }
+                    drop(stats);
+                    // Catch up without waiting when a poll hit max_batch
+                    if watch.behind {
+                        continue;
+                    }
+                    while !stopped.load(Ordering::Relaxed) {
+                        match interval.checked_sub(started.elapsed()) {
+                            Some(rest) if !rest.is_zero() => {
+                                std::thread::sleep(rest.min(MAX_SLEEP))
+                            }
+                            _ => break,
+                        }
+                    }
+                }
+            })
+        };
+        Ok(CursorDbWatcher {
+            cursor,
+            stats,
+            stopped,
+            thread: Some(thread),
+        })
+    }
+
+    /// Last rowid read per table; pass it as options.cursor to resume after a restart
+    #[napi(ts_return_type = "Record<string, number>")]
+    pub fn cursor(&self) -> HashMap<String, i64> {
+        self.cursor.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// Poll and row counts since start
+    #[napi]
+    pub fn stats(&self) -> CursorWatchStats {
+        let stats = self.stats.lock().unwrap();
+        CursorWatchStats {
+            reads: stats.reads,
+            skipped: stats.skipped,
+            changes: stats.changes,
+            errors: stats.errors,
+            last_error: stats.last_error.clone(),
+        }
+    }
+
+    /// Stop polling; waits for a poll in progress to finish
+    #[napi]
+    pub fn stop(&mut self) {
+        self.stopped.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for CursorDbWatcher {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
@@ -0,0 +1,457 @@
+/*!
+ * WebSocket push server
+ * Processed events broadcast to connected dashboards from background
+ * threads, with a bounded queue per client so a slow one can't stall the
+ * rest
+ */
+
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use serde_json::Value;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use tungstenite::handshake::server::{Callback, ErrorResponse, Request, Response};
+use tungstenite::protocol::frame::coding::CloseCode;
+use tungstenite::protocol::CloseFrame;
+use tungstenite::{Message, WebSocket};
+
+/// How often idle threads check for messages and a stop
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+const DEFAULT_MAX_CLIENTS: u32 = 64;
+const DEFAULT_MAX_QUEUE: u32 = 256;
+const DEFAULT_HANDSHAKE_TIMEOUT_MS: u32 = 5_000;
+const DEFAULT_WRITE_TIMEOUT_MS: u32 = 5_000;
+
+/// Options for WebSocketServer.start
+#[napi(object)]
+#[derive(Default)]
+pub struct WebSocketServerOptions {
+    /// Connections beyond this, handshakes included, are refused with a 503 (default: 64)
+    pub max_clients: Option<u32>,
+    /// Messages waiting per client before new ones are dropped for it (default: 256)
+    pub max_queue: Option<u32>,
+    /// Time a client has for its whole handshake before the connection is
+    /// dropped (default: 5000)
+    pub handshake_timeout_ms: Option<u32>,
+    /// Time a client has to take each message; one that doesn't has stopped
+    /// reading and is disconnected (default: 5000)
+    pub write_timeout_ms: Option<u32>,
+}
+
+/// Counters for a WebSocketServer
+#[napi(object)]
+pub struct WebSocketServerStats {
+    /// Clients connected now
+    pub clients: u32,
+    /// Messages queued to a client, counted once per client
+    pub sent: f64,
+    /// Messages skipped for a client whose queue was full
+    pub dropped: f64,
+}
+
+/// A connected client and what it asked for
+struct Client {
+    /// Topics from `?topics=a,b`; None receives everything
+    topics: Option<Vec<String>>,
+    queue: SyncSender<Arc<str>>,
+}
+
+impl Client {
+    fn wants(&self, topic: Option<&str>) -> bool {
+        match (&self.topics, topic) {
+            (None, _) | (_, None) => true,
+            (Some(topics), Some(topic)) => topics.iter().any(|t| t == topic),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Counters {
+    sent: AtomicU64,
+    dropped: AtomicU64,
+}
+
+fn topics_of(request: &Request) -> Option<Vec<String>> {
+    let query = request.uri().query()?;
+    let topics = query
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("topics="))?;
+    Some(
+        topics
+            .split(',')
+            .map(|topic| topic.trim().to_string())
+            .filter(|topic| !topic.is_empty())
+            .collect(),
+    )
+}
+
+/// Records the topics a client asked for while accepting its handshake
+struct Handshake<'a> {
+    topics: &'a mut Option<Vec<String>>,
+}
+
+impl Callback for Handshake<'_> {
+    // The signature is tungstenite's; ErrorResponse is an http::Response
+    #[allow(clippy::result_large_err)]
+    fn on_request(
+        self,
+        request: &Request,
+        response: Response,
+    ) -> std::result::Result<Response, ErrorResponse> {
+        *self.topics = topics_of(request);
+        Ok(response)
+    }
+}
+
+/// A client's socket, with reads and writes held to overall deadlines
+///
+/// Socket timeouts apply per call, and a call that moves a few bytes
+/// starts the next one afresh, so on their own they would let a client that
+/// trickles its handshake in, or reads a frame a few bytes at a time, hold
+/// its thread indefinitely.
+struct Connection {
+    stream: TcpStream,
+    /// Deadline for reads: the handshake's, then None, leaving each read to
+    /// wait POLL_INTERVAL
+    read_until: Option<Instant>,
+    /// Deadline for the write in progress
+    write_until: Option<Instant>,
+    write_timeout: Duration,
+}
+
+impl Connection {
+    /// Give the writes that follow `write_timeout` to complete
+    fn start_write(&mut self) {
+        self.write_until = Some(Instant::now() + self.write_timeout);
+    }
+}
+
+/// Time left before `until`, or TimedOut once it has passed
+fn time_left(until: Option<Instant>) -> std::io::Result<Option<Duration>> {
+    let Some(until) = until else {
+        return Ok(None);
+    };
+    let left = until.saturating_duration_since(Instant::now());
+    if left.is_zero() {
+        return Err(std::io::ErrorKind::TimedOut.into());
+    }
+    Ok(Some(left))
+}
+
+impl Read for Connection {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if let Some(left) = time_left(self.read_until)? {
+            self.stream.set_read_timeout(Some(left))?;
+        }
+        self.stream.read(buf)
+    }
+}
+
+impl Write for Connection {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if let Some(left) = time_left(self.write_until)? {
+            self.stream.set_write_timeout(Some(left))?;
+        }
+        self.stream.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+/// Frees a connection slot when its thread ends
+struct Slot(Arc<AtomicUsize>);
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Answer a connection over the limit without a thread or a handshake
+///
+/// The response fits in an empty socket buffer, so the write doesn't
+/// stall the acceptor; a client whose request is still arriving may see a
+/// reset instead.
+fn refuse(mut stream: TcpStream) {
+    let _ = stream.write_all(
+        b"HTTP/1.1 503 Service Unavailable\r\nContent-Type: text/plain\r\nContent-Length: 16\r\nConnection: close\r\n\r\nToo many clients",
+    );
+    let _ = stream.shutdown(std::net::Shutdown::Write);
+}
+
+/// Per-connection settings from WebSocketServerOptions
+struct Limits {
+    max_queue: usize,
+    handshake_timeout: Duration,
+    write_timeout: Duration,
+}
+
+fn is_timeout(error: &tungstenite::Error) -> bool {
+    matches!(
+        error,
+        tungstenite::Error::Io(e)
+            if matches!(e.kind(), std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut)
+    )
+}
+
+/// Write queued messages to one client and read its frames until either side closes
+///
+/// Each write and flush gets the write timeout, and any write error, a timeout
+/// included, ends the connection: a client that stops reading would
+/// otherwise hold its thread in a blocked write.
+fn pump(mut websocket: WebSocket<Connection>, queue: Receiver<Arc<str>>, stopped: &AtomicBool) {
+    loop {
+        if stopped.load(Ordering::Relaxed) {
+            websocket.get_mut().start_write();
+            let _ = websocket.close(Some(CloseFrame {
+                code: CloseCode::Away,
+                reason: "Server stopping".into(),
+            }));
+            let _ = websocket.flush();
+            return;
+        }
+        loop {
+            match queue.try_recv() {
+                Ok(text) => {
+                    websocket.get_mut().start_write();
+                    if websocket.write(Message::Text(text.to_string())).is_err() {
+                        return;
+                    }
+                }
+                Err(mpsc::TryRecvError::Empty) => break,
+                Err(mpsc::TryRecvError::Disconnected) => return,
+            }
+        }
+        // Flushed on every pass, not just after a write, so a reply that read()
+        // couldn't finish writing below is caught here as a write error
+        websocket.get_mut().start_write();
+        if websocket.flush().is_err() {
+            return;
+        }
+        // Reading also answers pings and completes a close the client started
+        websocket.get_mut().start_write();
+        match websocket.read() {
+            Ok(Message::Close(_)) => {
+                let _ = websocket.flush();
+                return;
+            }
+            Ok(_) => {}
+            Err(e) if is_timeout(&e) => {}
+            Err(_) => return,
+        }
+    }
+}
+
+/// Handshake, then pump until the connection ends; runs on its own thread
+fn serve(stream: TcpStream, clients: &Mutex<Vec<Client>>, limits: &Limits, stopped: &AtomicBool) {
+    let _ = stream.set_nonblocking(false);
+    let deadline = Some(Instant::now() + limits.handshake_timeout);
+    let connection = Connection {
+        stream,
+        read_until: deadline,
+        write_until: deadline,
+        write_timeout: limits.write_timeout,
+    };
+    let mut topics = None;
+    let handshake = Handshake {
+        topics: &mut topics,
+    };
+    // Any error, the deadline passing included, drops the connection
+    let Ok(mut websocket) = tungstenite::accept_hdr(connection, handshake) else {
+        return;
+    };
+    websocket.get_mut().read_until = None;
+    if websocket
+        .get_ref()
+        .stream
+        .set_read_timeout(Some(POLL_INTERVAL))
+        .is_err()
+    {
+        return;
+    }
+    let (sender, receiver) = mpsc::sync_channel(limits.max_queue);
+    clients.lock().unwrap().push(Client {
+        topics,
+        queue: sender,
+    });
+    pump(websocket, receiver, stopped);
+    // The dropped receiver makes the next broadcast remove this client
+}
+
+/// Broadcasts JSON messages to WebSocket clients, for live dashboards
+#[napi]
+pub struct WebSocketServer {
+    clients: Arc<Mutex<Vec<Client>>>,
+    counters: Arc<Counters>,
+    stopped: Arc<AtomicBool>,
+    threads: Arc<Mutex<Vec<JoinHandle<()>>>>,
+    port: u32,
+}
+
+#[napi]
+impl WebSocketServer {
+    /**
+     * Listen on `host:port` (default host 127.0.0.1) and accept WebSocket clients
+     *
+     * Port 0 picks a free port; read it back from `port`. Clients connect
+     * to `ws://host:port/`, optionally with
+     * `?topics=diffs,sessions` to receive only messages broadcast under
+     * those topics. Each client has its own thread and queue, so one that
+     * reads slowly loses messages instead of delaying the others.
+     */
+    #[napi(factory)]
+    pub fn start(
+        port: u32,
+        host: Option<String>,
+        options: Option<WebSocketServerOptions>,
+    ) -> Result<Self> {
+        let options = options.unwrap_or_default();
+        let host = host.unwrap_or_else(|| "127.0.0.1".to_string());
+        let address = format!("{}:{}", host, port);
+        let fail = |action: &str, error: std::io::Error| {
+            Error::new(
+                Status::GenericFailure,
+                format!("Failed to {} {}: {}", action, address, error),
+            )
+        };
+        let listener = TcpListener::bind(&address).map_err(|e| fail("listen on", e))?;
+        listener
+            .set_nonblocking(true)
+            .map_err(|e| fail("configure", e))?;
+        let port = listener
+            .local_addr()
+            .map_err(|e| fail("listen on", e))?
+            .port() as u32;
+        let max_clients = options.max_clients.unwrap_or(DEFAULT_MAX_CLIENTS) as usize;
+        let limits = Arc::new(Limits {
+            max_queue: options.max_queue.unwrap_or(DEFAULT_MAX_QUEUE).max(1) as usize,
+            handshake_timeout: Duration::from_millis(
+                options
+                    .handshake_timeout_ms
+                    .unwrap_or(DEFAULT_HANDSHAKE_TIMEOUT_MS) as u64,
+            ),
+            write_timeout: Duration::from_millis(
+                options.write_timeout_ms.unwrap_or(DEFAULT_WRITE_TIMEOUT_MS) as u64,
+            ),
+        });
+        let clients = Arc::new(Mutex::new(Vec::new()));
+        let stopped = Arc::new(AtomicBool::new(false));
+        let threads: Arc<Mutex<Vec<JoinHandle<()>>>> = Arc::new(Mutex::new(Vec::new()));
+        // Connections being served, handshakes included; checked before a thread is spawned
+        let connections = Arc::new(AtomicUsize::new(0));
+        let acceptor = {
+            let clients = clients.clone();
+            let stopped = stopped.clone();
+            let threads = threads.clone();
+            std::thread::spawn(move || {
+                while !stopped.load(Ordering::Relaxed) {
+                    match listener.accept() {
+                        Ok((stream, _)) => {
+                            if connections.load(Ordering::Relaxed) >= max_clients {
+                                refuse(stream);
+                                continue;
+                            }
+                            connections.fetch_add(1, Ordering::Relaxed);
+                            let slot = Slot(connections.clone());
+                            let clients = clients.clone();
+                            let limits = limits.clone();
+                            let stopped = stopped.clone();
+                            let thread = std::thread::spawn(move || {
+                                let _slot = slot;
+                                serve(stream, &clients, &limits, &stopped)
+                            });
+                            let mut threads = threads.lock().unwrap();
+                            threads.retain(|thread| !thread.is_finished());
+                            threads.push(thread);
+                        }
+                        // WouldBlock when idle; other accept errors are per-connection
+                        Err(_) => std::thread::sleep(POLL_INTERVAL),
+                    }
+                }
+            })
+        };
+        threads.lock().unwrap().push(acceptor);
+        Ok(WebSocketServer {
+            clients,
+            counters: Arc::new(Counters::default()),
+            stopped,
+            threads,
+            port,
+        })
+    }
+
+    /**
+     * Queue a message, as JSON text, for every client that wants it
+     *
+     * Without a topic every client gets it; with one, clients that asked
+     * for topics get it only if it is among them. Never blocks: a client
+     * whose queue is full misses this message. Returns the clients it was
+     * queued for.
+     */
+    #[napi(ts_args_type = "message: any, topic?: string | undefined | null")]
+    pub fn broadcast(&self, message: Value, topic: Option<String>) -> u32 {
+        let text: Arc<str> = Arc::from(message.to_string());
+        let mut queued = 0;
+        let mut clients = self.clients.lock().unwrap();
+        clients.retain(|client| {
+            if !client.wants(topic.as_deref()) {
+                return true;
+            }
+            match client.queue.try_send(text.clone()) {
+                Ok(()) => {
+                    queued += 1;
+                    true
+                }
+                Err(TrySendError::Full(_)) => {
+                    self.counters.dropped.fetch_add(1, Ordering::Relaxed);
+                    true
+                }
+                Err(TrySendError::Disconnected(_)) => false,
+            }
+        });
+        self.counters
+            .sent
+            .fetch_add(queued as u64, Ordering::Relaxed);
+        queued
+    }
+
+    /// Connected clients and message counts
+    #[napi]
+    pub fn stats(&self) -> WebSocketServerStats {
+        WebSocketServerStats {
+            clients: self.clients.lock().unwrap().len() as u32,
+            sent: self.counters.sent.load(Ordering::Relaxed) as f64,
+            dropped: self.counters.dropped.load(Ordering::Relaxed) as f64,
+        }
+    }
+
+    /// The port being listened on
+    #[napi(getter)]
+    pub fn port(&self) -> u32 {
+        self.port
+    }
+
+    /// Close every client and stop listening
+    #[napi]
+    pub fn stop(&mut self) {
+        self.stopped.store(true, Ordering::Relaxed);
+        let threads: Vec<_> = self.threads.lock().unwrap().drain(..).collect();
+        for thread in threads {
+            let _ = thread.join();
+        }
+        self.clients.lock().unwrap().clear();
+    }
+}
+
+impl Drop for WebSocketServer {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
@@ -0,0 +1,372 @@
+/**
+ * Store backup and restore
+ * Consistent snapshots of the SQLite store, kept as content-addressed chunks with a manifest per backup
+ *
+ * Layout of a backup directory:
+ *   manifests/<id>.json   one per backup, naming its parent and its chunks in order
+ *   chunks/ab/<sha256>    gzipped, shared by every backup in the chain that contains them
+ *
+ * An incremental backup only writes the chunks its parents don't already have.
+ * Chunk boundaries are content-defined, so a change shifts the boundaries
+ * near it rather than every one after it. Pages VACUUM INTO moves still
+ * cost their chunks again, so a day of scattered writes can cost more than
+ * what changed, and a backup after a full VACUUM is close to a full copy.
+ */
+
+const sqlite3 = require('sqlite3');
+const fs = require('fs');
+const path = require('path');
+const zlib = require('zlib');
+const crypto = require('crypto');
+
+const MANIFEST_VERSION = 1;
+const DEFAULT_CHUNK_SIZE = 1024 * 1024;
+
+const sha256 = (data) => crypto.createHash('sha256').update(data).digest('hex');
+
+// Per-byte values for the rolling gear hash, fixed so boundaries are stable across runs
+const GEAR = Array.from({ length: 256 }, (_, i) =>
+  crypto.createHash('sha256').update(`gear-${i}`).digest().readUInt32LE(0)
+);
+
+function openDatabase(file, mode) {
+  return new Promise((resolve, reject) => {
+    const db = new sqlite3.Database(file, mode, (err) => (err ? reject(err) : resolve(db)));
+  });
+}
+
+function runSql(db, sql, params = []) {
+  return new Promise((resolve, reject) => {
+    db.run(sql, params, (err) => (err ? reject(err) : resolve()));
+  });
+}
+
+function allSql(db, sql, params = []) {
+  return new Promise((resolve, reject) => {
+    db.all(sql, params, (err, rows) => (err ? reject(err) : resolve(rows)));
+  });
+}
+
+function closeDatabase(db) {
+  return new Promise((resolve) => db.close(() => resolve()));
+}
+
+/**
+ * Copy a store with VACUUM INTO, which reads a consistent snapshot while the service keeps writing
+ */
+async function snapshot(source, target) {
+  const db = await openDatabase(source, sqlite3.OPEN_READONLY);
+  try {
+    await runSql(db, 'VACUUM INTO ?', [target]);
+  } finally {
+    await closeDatabase(db);
+  }
+}
+
+/**
+ * Errors from PRAGMA integrity_check, empty when the file is a sound database
+ */
+async function integrityErrors(file) {
+  const db = await openDatabase(file, sqlite3.OPEN_READONLY);
+  try {
+    const rows = await allSql(db, 'PRAGMA integrity_check');
+    return rows.map((row) => row.integrity_check).filter((result) => result !== 'ok');
+  } finally {
+    await closeDatabase(db);
+  }
+}
+
+function chunkPath(dest, hash) {
+  return path.join(dest, 'chunks', hash.slice(0, 2), hash);
+}
+
+function manifestPath(dest, id) {
+  return path.join(dest, 'manifests', `${id}.json`);
+}
+
+/**
+ * Manifests in a backup directory, oldest first
+ */
+function listManifests(dest) {
+  const dir = path.join(dest, 'manifests');
+  if (!fs.existsSync(dir)) return [];
+  return fs
+    .readdirSync(dir)
+    .filter((name) => name.endsWith('.json'))
+    .sort()
+    .map((name) => JSON.parse(fs.readFileSync(path.join(dir, name), 'utf8')));
+}
+
+/**
+ * Read the manifest named by `src`: a manifest file, or a backup directory for its latest backup
+ */
+function readManifest(src) {
+  if (fs.statSync(src).isFile()) {
+    const manifest = JSON.parse(fs.readFileSync(src, 'utf8'));
+    return { dir: path.dirname(path.dirname(src)), manifest };
+  }
+  const manifests = listManifests(src);
+  if (manifests.length === 0) {
+    throw new Error(`No backups found in ${src}`);
+  }
+  return { dir: src, manifest: manifests[manifests.length - 1] };
+}
+
+/**
+ * Manifests from `manifest` back to the start of its chain, newest first
+ */
+function chainOf(dir, manifest) {
+  const chain = [manifest];
+  let parent = manifest.parent;
+  while (parent) {
+    const file = manifestPath(dir, parent);
+    if (!fs.existsSync(file)) {
+      throw new Error(`Backup ${chain[chain.length - 1].id} depends on missing backup ${parent}`);
+    }
+    const next = JSON.parse(fs.readFileSync(file, 'utf8'));
+    chain.push(next);
+    parent = next.parent;
+  }
+  return chain;
+}
+
+/**
+ * Split a file into content-defined chunks, calling `onChunk` with each in order
+ *
+ * A boundary falls where a rolling gear hash of the last 32 bytes has its top
+ * bits clear, giving chunks of about `chunkSize` bytes, between a quarter and
+ * four times that. The file is read `chunkSize` bytes at a time, so memory
+ * stays at a few chunks whatever the store's size.
+ */
+function forEachChunk(file, chunkSize, onChunk) {
+  const bits = Math.min(31, Math.max(1, Math.round(Math.log2(chunkSize))));
+  const mask = (((1 << bits) - 1) << (32 - bits)) >>> 0;
+  const min = Math.max(1, chunkSize >> 2);
+  const max = chunkSize * 4;
+
+  const fd = fs.openSync(file, 'r');
+  try {
+    let pending = [];
+    let length = 0;
+    let hash = 0;
+    for (;;) {
+      const piece = Buffer.allocUnsafe(chunkSize);
+      const read = fs.readSync(fd, piece, 0, chunkSize, null);
+      if (read === 0) break;
+
+      let start = 0;
+      for (let i = 0; i < read; i++) {
+        hash = ((hash << 1) + GEAR[piece[i]]) >>> 0;
+        length++;
+        if (length >= max || (length >= min && (hash & mask) === 0)) {
+          pending.push(piece.subarray(start, i + 1));
+          onChunk(Buffer.concat(pending));
+          pending = [];
+          length = 0;
+          start = i + 1;
+        }
+      }
+      if (start < read) pending.push(piece.subarray(start, read));
+    }
+    if (pending.length > 0) onChunk(Buffer.concat(pending));
+  } finally {
+    fs.closeSync(fd);
+  }
+}
+
+/**
+ * Back up the store at `storePath` into the backup directory `dest`
+ *
+ * With `incremental` (default) the backup extends the latest chain in
+ * `dest`, writing only chunks the chain doesn't have yet; otherwise it starts
+ * a new chain and rewrites every chunk. The snapshot is integrity-checked
+ * before anything is written, and the manifest is written last, so an
+ * interrupted backup never shows up as a restorable one.
+ */
+async function backupStore(storePath, dest, options = {}) {
+  const { incremental = true, chunkSize = DEFAULT_CHUNK_SIZE } = options;
+  const started = Date.now();
+  if (!fs.existsSync(storePath)) {
+    throw new Error(`Store not found: ${storePath}`);
+  }
+  fs.mkdirSync(path.join(dest, 'manifests'), { recursive: true });
+
+  const temp = path.join(dest, `.snapshot-${process.pid}-${Date.now()}.db`);
+  try {
+    await snapshot(storePath, temp);
+    const errors = await integrityErrors(temp);
+    if (errors.length > 0) {
+      throw new Error(
+        `Store failed its integrity check, not backing up: ${errors.slice(0, 3).join('; ')}`
+      );
+    }
+
+    const previous = listManifests(dest);
+    const parent = incremental && previous.length > 0 ? previous[previous.length - 1] : null;
+    const known = new Set();
+    if (parent) {
+      for (const manifest of chainOf(dest, parent)) {
+        manifest.chunks.forEach((hash) => known.add(hash));
+      }
+    }
+
+    const chunks = [];
+    const whole = crypto.createHash('sha256');
+    let size = 0;
+    let written = 0;
+    let bytesWritten = 0;
+    forEachChunk(temp, chunkSize, (chunk) => {
+      whole.update(chunk);
+      size += chunk.length;
+      const hash = sha256(chunk);
+      chunks.push(hash);
+      if (known.has(hash)) return;
+      known.add(hash);
+
+      const file = chunkPath(dest, hash);
+      fs.mkdirSync(path.dirname(file), { recursive: true });
+      const body = zlib.gzipSync(chunk);
+      fs.writeFileSync(`${file}.tmp`, body);
+      fs.renameSync(`${file}.tmp`, file);
+      written++;
+      bytesWritten += body.length;
+    });
+
+    const createdAt = new Date();
+    const manifest = {
+      version: MANIFEST_VERSION,
+      id: createdAt.toISOString().replace(/[:.]/g, '-'),
+      parent: parent ? parent.id : null,
+      createdAt: createdAt.toISOString(),
+      source: path.resolve(storePath),
+      size,
+      sha256: whole.digest('hex'),
+      chunkSize,
+      chunks,
+    };
+    const file = manifestPath(dest, manifest.id);
+    fs.writeFileSync(`${file}.tmp`, JSON.stringify(manifest, null, 2));
+    fs.renameSync(`${file}.tmp`, file);
+
+    return {
+      id: manifest.id,
+      parent: manifest.parent,
+      incremental: Boolean(parent),
+      size: manifest.size,
+      chunks: chunks.length,
+      chunksWritten: written,
+      bytesWritten,
+      durationMs: Date.now() - started,
+    };
+  } finally {
+    fs.rmSync(temp, { force: true });
+  }
+}
+
+/**
+ * Check a backup without restoring it
+ * Returns the problems found: missing parents, missing or altered chunks, and a whole-file mismatch
+ */
+function verifyBackup(src) {
+  const { dir, manifest } = readManifest(src);
+  const problems = [];
+  try {
+    chainOf(dir, manifest);
+  } catch (error) {
+    problems.push(error.message);
+  }
+  if (manifest.version !== MANIFEST_VERSION) {
+    problems.push(`Unsupported manifest version ${manifest.version}`);
+  }
+
+  const whole = crypto.createHash('sha256');
+  let size = 0;
+  for (const hash of manifest.chunks) {
+    const file = chunkPath(dir, hash);
+    if (!fs.existsSync(file)) {
+      problems.push(`Missing chunk ${hash}`);
+      continue;
+    }
+    let chunk;
+    try {
+      chunk = zlib.gunzipSync(fs.readFileSync(file));
+    } catch (error) {
+      problems.push(`Unreadable chunk ${hash}`);
+      continue;
+    }
+    if (sha256(chunk) !== hash) {
+      problems.push(`Chunk ${hash} does not match its hash`);
+      continue;
+    }
+    whole.update(chunk);
+    size += chunk.length;
+  }
+  if (problems.length === 0 && (size !== manifest.size || whole.digest('hex') !== manifest.sha256)) {
+    problems.push('Reassembled store does not match the manifest checksum');
+  }
+  return { id: manifest.id, ok: problems.length === 0, problems };
+}
+
+/**
+ * Restore the backup named by `src` (a backup directory or one of its manifests) to `dest`
+ *
+ * Every chunk and the reassembled file are checked against the manifest, and
+ * the result must pass SQLite's integrity check before it replaces `dest`.
+ * An existing `dest` is only replaced with `overwrite`; stop the companion
+ * service first when restoring over its live store.
+ */
+async function restoreStore(src, dest, options = {}) {
+  const { overwrite = false } = options;
+  const started = Date.now();
+  if (fs.existsSync(dest) && !overwrite) {
+    throw new Error(`${dest} already exists (pass overwrite to replace it)`);
+  }
+
+  const { dir, manifest } = readManifest(src);
+  const verification = verifyBackup(src);
+  if (!verification.ok) {
+    const problems = verification.problems.slice(0, 3).join('; ');
+    throw new Error(`Backup ${manifest.id} failed verification: ${problems}`);
+  }
+
+  fs.mkdirSync(path.dirname(path.resolve(dest)), { recursive: true });
+  const temp = `${dest}.restore-${process.pid}`;
+  try {
+    const fd = fs.openSync(temp, 'w');
+    try {
+      for (const hash of manifest.chunks) {
+        fs.writeSync(fd, zlib.gunzipSync(fs.readFileSync(chunkPath(dir, hash))));
+      }
+      fs.fsyncSync(fd);
+    } finally {
+      fs.closeSync(fd);
+    }
+
+    const errors = await integrityErrors(temp);
+    if (errors.length > 0) {
+      throw new Error(`Restored store failed its integrity check: ${errors.slice(0, 3).join('; ')}`);
+    }
+
+    // Stale WAL and shared-memory files would be replayed over the restored store
+    for (const suffix of ['-wal', '-shm']) {
+      fs.rmSync(`${dest}${suffix}`, { force: true });
+    }
+    fs.renameSync(temp, dest);
+  } finally {
+    fs.rmSync(temp, { force: true });
+  }
+
+  return {
+    id: manifest.id,
+    chain: chainOf(dir, manifest).length,
+    size: manifest.size,
+    durationMs: Date.now() - started,
+  };
+}
+
+module.exports = {
+  backupStore,
+  restoreStore,
+  verifyBackup,
+  listManifests,
+};
@@ -0,0 +1,213 @@
+/**
+ * Cold storage for tiered events
+ * Day segments of stored event rows as gzipped JSONL, in a local directory or an S3-compatible bucket
+ */
+
+const fs = require('fs');
+const path = require('path');
+const zlib = require('zlib');
+const crypto = require('crypto');
+
+/**
+ * Segments under a directory, e.g. an external drive or a synced folder
+ */
+class DirectoryBackend {
+  constructor(root) {
+    this.root = path.resolve(root);
+    this.location = this.root;
+  }
+
+  async put(key, body) {
+    const file = path.join(this.root, key);
+    await fs.promises.mkdir(path.dirname(file), { recursive: true });
+    // Write then rename so a crash never leaves a truncated segment behind
+    const temp = `${file}.${process.pid}.tmp`;
+    await fs.promises.writeFile(temp, body);
+    await fs.promises.rename(temp, file);
+  }
+
+  async get(key) {
+    try {
+      return await fs.promises.readFile(path.join(this.root, key));
+    } catch (error) {
+      if (error.code === 'ENOENT') return null;
+      throw error;
+    }
+  }
+  async delete(key) {
+    await fs.promises.rm(path.join(this.root, key), { force: true });
+  }
+}
+
+/**
+ * Segments in an S3-compatible bucket (AWS, MinIO, R2, ...)
+ * Needs the optional @aws-sdk/client-s3 package; credentials come from the SDK's usual chain
+ */
+class S3Backend {
+  constructor({ bucket, prefix = '', endpoint, region }) {
+    let s3;
+    try {
+      s3 = require('@aws-sdk/client-s3');
+    } catch (error) {
+      throw new Error('S3 cold storage requires the @aws-sdk/client-s3 package (npm install @aws-sdk/client-s3)');
+    }
+    this.s3 = s3;
+    this.bucket = bucket;
+    this.prefix = prefix ? `${prefix.replace(/\/+$/, '')}/` : '';
+    this.client = new s3.S3Client({
+      region: region || 'us-east-1',
+      endpoint: endpoint || undefined,
+      // MinIO and most self-hosted endpoints don't support virtual-hosted buckets
+      forcePathStyle: Boolean(endpoint),
+    });
+    this.location = `s3://${bucket}/${this.prefix}`;
+  }
+
+  async put(key, body) {
+    await this.client.send(
+      new this.s3.PutObjectCommand({
+        Bucket: this.bucket,
+        Key: this.prefix + key,
+        Body: body,
+        ContentType: 'application/gzip',
+      })
+    );
+  }
+
+  async get(key) {
+    try {
+      const result = await this.client.send(
+        new this.s3.GetObjectCommand({ Bucket: this.bucket, Key: this.prefix + key })
+      );
+      return Buffer.from(await result.Body.transformToByteArray());
+    } catch (error) {
+      if (error.name === 'NoSuchKey' || error.$metadata?.httpStatusCode === 404) return null;
+      throw error;
+    }
+  }
+  async delete(key) {
+    await this.client.send(
+      new this.s3.DeleteObjectCommand({ Bucket: this.bucket, Key: this.prefix + key })
+    );
+  }
+}
+
+class ColdStorage {
+  constructor(backend, options = {}) {
+    this.backend = backend;
+    this.afterDays = options.afterDays || ColdStorage.DEFAULT_AFTER_DAYS;
+    // Recently read segments, so paging through an old range doesn't refetch them
+    this.cache = new Map();
+    this.cacheSize = options.cacheSize || 8;
+  }
+
+  /**
+   * Cold storage configured by environment, or null when tiering is off
+   *
+   * COLD_STORAGE_PATH selects a directory. The S3 backend is behind the
+   * COLD_STORAGE_S3=true flag and reads COLD_STORAGE_S3_BUCKET, _PREFIX,
+   * _ENDPOINT, and _REGION. COLD_STORAGE_AFTER_DAYS sets the age (default 90).
+   */
+  static fromEnv(env = process.env) {
+    const options = { afterDays: parseInt(env.COLD_STORAGE_AFTER_DAYS) || undefined };
+    if (env.COLD_STORAGE_S3 === 'true' && env.COLD_STORAGE_S3_BUCKET) {
+      return new ColdStorage(
+        new S3Backend({
+          bucket: env.COLD_STORAGE_S3_BUCKET,
+          prefix: env.COLD_STORAGE_S3_PREFIX,
+          endpoint: env.COLD_STORAGE_S3_ENDPOINT,
+          region: env.COLD_STORAGE_S3_REGION || env.AWS_REGION,
+        }),
+        options
+      );
+    }
+    if (env.COLD_STORAGE_PATH) {
+      return new ColdStorage(new DirectoryBackend(env.COLD_STORAGE_PATH), options);
+    }
+    return null;
+  }
+
+  get location() {
+    return this.backend.location;
+  }
+
+  /**
+   * Key of the segment holding a UTC day's events
+   */
+  static segmentKey(day) {
+    return `events/${day.slice(0, 4)}/${day}.jsonl.gz`;
+  }
+
+  /**
+   * Key for a segment merging several small ones from a UTC month
+   * Unique per merge, so a half-finished merge never overwrites a recorded segment
+   */
+  static mergedSegmentKey(month) {
+    return `events/${month.slice(0, 4)}/${month}.merged-${Date.now()}.jsonl.gz`;
+  }
+
+  static checksum(body) {
+    return crypto.createHash('sha256').update(body).digest('hex');
+  }
+
+  /**
+   * Write rows as a segment, reading it back before reporting success
+   */
+  async writeSegment(key, rows) {
+    const body = zlib.gzipSync(rows.map((row) => JSON.stringify(row)).join('\n') + '\n');
+    const checksum = ColdStorage.checksum(body);
+    await this.backend.put(key, body);
+
+    const written = await this.backend.get(key);
+    if (!written || ColdStorage.checksum(written) !== checksum) {
+      throw new Error(`Cold segment ${key} did not read back intact from ${this.location}`);
+    }
+    this.cache.delete(key);
+    return { checksum, bytes: body.length };
+  }
+
+  /**
+   * Remove a segment that is no longer recorded in the store
+   */
+  async deleteSegment(key) {
+    this.cache.delete(key);
+    await this.backend.delete(key);
+  }
+
+  /**
+   * Rows of a segment, or null when it doesn't exist
+   * With `checksum`, a segment that doesn't match it is an error rather than silently partial data
+   */
+  async readSegment(key, checksum = null) {
+    if (this.cache.has(key)) {
+      const rows = this.cache.get(key);
+      this.cache.delete(key);
+      this.cache.set(key, rows);
+      return rows;
+    }
+
+    const body = await this.backend.get(key);
+    if (!body) return null;
+    if (checksum && ColdStorage.checksum(body) !== checksum) {
+      throw new Error(`Cold segment ${key} in ${this.location} does not match its recorded checksum`);
+    }
+    const rows = zlib
+      .gunzipSync(body)
+      .toString('utf8')
+      .split('\n')
+      .filter((line) => line.trim())
+      .map((line) => JSON.parse(line));
+
+    this.cache.set(key, rows);
+    if (this.cache.size > this.cacheSize) {
+      this.cache.delete(this.cache.keys().next().value);
+    }
+    return rows;
+  }
+}
+
+ColdStorage.DEFAULT_AFTER_DAYS = 90;
+ColdStorage.DirectoryBackend = DirectoryBackend;
+ColdStorage.S3Backend = S3Backend;
+
+module.exports = ColdStorage;
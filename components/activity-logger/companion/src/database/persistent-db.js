@@ -1,25 +1,61 @@
 const sqlite3 = require('sqlite3').verbose();
 const path = require('path');
 const fs = require('fs');
+const crypto = require('crypto');
+const os = require('os');
+const { AsyncLocalStorage } = require('async_hooks');
 const PostgresAdapter = require('./postgres-adapter');
+const ColdStorage = require('./cold-storage');
+
+// The store whose write queue the current async call chain holds, so nested writes don't wait on themselves
+const exclusiveWriter = new AsyncLocalStorage();
+
+// Native module validates, redacts, and coalesces ingest batches when built
+let native = null;
+try {
+  native = require('../../native');
+} catch (error) {
+  native = null;
+}
 
 /**
  * Persistent Database for Companion Service
  * Supports both SQLite (local) and PostgreSQL (cloud)
  */
 class PersistentDB {
-  constructor(dbPath = null) {
+  constructor(dbPath = null, options = {}) {
+    // Read-only stores are always local SQLite files, whatever the environment says
+    this.readOnly = Boolean(options.readOnly);
+
     // Determine database type from environment
-    const dbType = process.env.DATABASE_TYPE || (process.env.DATABASE_URL ? 'postgres' : 'sqlite');
+    const dbType = this.readOnly
+      ? 'sqlite'
+      : process.env.DATABASE_TYPE || (process.env.DATABASE_URL ? 'postgres' : 'sqlite');
     
     this.dbType = dbType;
     this.dbPath = dbPath || path.join(__dirname, '../data/companion.db');
     this.db = null;
     this.postgresAdapter = null;
     this._initPromise = null;
+    // Tail of the write queue; see runExclusive
+    this._writeQueue = Promise.resolve();
+    // Damaged records found by the most recent read that found any
+    this.readDamage = null;
+    // Backups to recover damaged events from, and the latest one restored for that
+    this.backupDir = process.env.BACKUP_DIR || null;
+    this._backupSnapshot = null;
+    // Where tierColdEvents moves old events, null when tiering isn't configured
+    this.coldStorage = null;
+    try {
+      this.coldStorage = ColdStorage.fromEnv();
+    } catch (error) {
+      console.warn('[DB] Cold storage disabled:', error.message);
+    }
 
     // Initialize appropriate database adapter
-    if (this.dbType === 'postgres' || process.env.DATABASE_URL) {
+    if (this.readOnly) {
+      console.log(`[DB] Using SQLite database read-only: ${this.dbPath}`);
+    } else if (this.dbType === 'postgres' || process.env.DATABASE_URL) {
       console.log('[DB] Using PostgreSQL database');
       this.postgresAdapter = new PostgresAdapter(process.env.DATABASE_URL);
     } else {
@@ -39,6 +75,11 @@ class PersistentDB {
       return this._initPromise;
     }
 
+    if (this.readOnly) {
+      this._initPromise = this.openReadOnly();
+      return this._initPromise;
+    }
+
     // Otherwise use SQLite
     this._initPromise = new Promise((resolve, reject) => {
       // Ensure data directory exists
@@ -59,6 +100,11 @@ class PersistentDB {
         // Enable foreign key constraints
         this.db.run('PRAGMA foreign_keys = ON');
 
+        // Wait for other writers (extension, CLI, compaction) instead of failing with SQLITE_BUSY
+        this.db.run('PRAGMA busy_timeout = 5000');
+        // Only takes effect on a new database; compactStore({ online: false }) converts existing ones
+        this.db.run('PRAGMA auto_vacuum = INCREMENTAL');
+
         // Performance optimizations for SQLite
         this.db.run('PRAGMA journal_mode=WAL;'); // Write-Ahead Logging for better concurrency
         this.db.run('PRAGMA synchronous=NORMAL;'); // Faster than FULL, still safe
@@ -372,6 +418,86 @@ class PersistentDB {
                       `ALTER TABLE events ADD COLUMN ai_generated INTEGER DEFAULT 0`,
                       () => {}
                     );
+                    // Verified on read; NULL for rows written before checksums existed
+                    this.db.run(`ALTER TABLE events ADD COLUMN checksum TEXT`, () => {});
+                    res();
+                  }
+                }
+              );
+            })
+          );
+
+          // Outcome labels table (good/bad/reverted verdicts on AI edits)
+          tables.push(
+            new Promise((res, rej) => {
+              this.db.run(
+                `
+              CREATE TABLE IF NOT EXISTS event_labels (
+                event_id TEXT PRIMARY KEY,
+                label TEXT NOT NULL,
+                note TEXT,
+                labeled_at TEXT
+              )
+            `,
+                (err) => {
+                  if (err) {
+                    console.error('Error creating event_labels table:', err);
+                    rej(err);
+                  } else {
+                    this.db.run(
+                      `CREATE INDEX IF NOT EXISTS idx_event_labels_label ON event_labels(label)`,
+                      () => {}
+                    );
+                    res();
+                  }
+                }
+              );
+            })
+          );
+
+          // Cold-storage segments holding events moved out by tierColdEvents
+          tables.push(
+            new Promise((res, rej) => {
+              this.db.run(
+                `
+              CREATE TABLE IF NOT EXISTS cold_segments (
+                key TEXT PRIMARY KEY,
+                location TEXT,
+                event_count INTEGER,
+                min_time INTEGER,
+                max_time INTEGER,
+                checksum TEXT,
+                tiered_at TEXT
+              )
+            `,
+                (err) => {
+                  if (err) {
+                    console.error('Error creating cold_segments table:', err);
+                    rej(err);
+                  } else {
+                    res();
+                  }
+                }
+              );
+            })
+          );
+
+          // Leases that serialize maintenance across every process sharing the store
+          tables.push(
+            new Promise((res, rej) => {
+              this.db.run(
+                `
+              CREATE TABLE IF NOT EXISTS store_locks (
+                name TEXT PRIMARY KEY,
+                owner TEXT NOT NULL,
+                expires_at INTEGER NOT NULL
+              )
+            `,
+                (err) => {
+                  if (err) {
+                    console.error('Error creating store_locks table:', err);
+                    rej(err);
+                  } else {
                     res();
                   }
                 }
@@ -1196,6 +1322,70 @@ class PersistentDB {
     return this._initPromise;
   }
 
+  /**
+   * Open an existing store for reading only
+   *
+   * SQLite itself refuses writes: the file is opened with OPEN_READONLY and
+   * query_only is set, so every save, label, migration, or vacuum fails with
+   * SQLITE_READONLY instead of touching the file. No tables are created, so
+   * the store is read exactly as it was captured.
+   */
+  openReadOnly() {
+    return new Promise((resolve, reject) => {
+      if (!fs.existsSync(this.dbPath)) {
+        reject(new Error(`Store not found: ${this.dbPath}`));
+        return;
+      }
+
+      this.db = new sqlite3.Database(this.dbPath, sqlite3.OPEN_READONLY, (err) => {
+        if (err) {
+          console.error('Failed to open database read-only:', err);
+          reject(err);
+          return;
+        }
+
+        this.db.run('PRAGMA query_only = ON');
+        this.db.run('PRAGMA busy_timeout = 5000');
+        this.db.run('PRAGMA cache_size=-64000;');
+        this.db.run('PRAGMA temp_store=MEMORY;', (pragmaErr) => {
+          if (pragmaErr) {
+            reject(pragmaErr);
+          } else {
+            console.log(`[SAVE] Opened SQLite database read-only: ${this.dbPath}`);
+            resolve();
+          }
+        });
+      });
+    });
+  }
+
+  /**
+   * Open a copy of someone's store for analysis, with writes refused by SQLite
+   * All getters and the native aggregation inputs (getAllEvents, getEventsInRange, ...) work as usual
+   */
+  static async openStoreReadonly(dbPath) {
+    const store = new PersistentDB(dbPath, { readOnly: true });
+    await store.init();
+    return store;
+  }
+
+  /**
+   * Run `fn` once every write queued before it has finished, with the connection to itself
+   *
+   * All writes share one connection, and a transaction awaits its statements
+   * one at a time, so a write issued in between would run inside it and be
+   * thrown away by a ROLLBACK. Every write method goes through this queue
+   * (see WRITE_METHODS); writes made from inside `fn` run immediately.
+   */
+  runExclusive(fn) {
+    if (exclusiveWriter.getStore() === this) {
+      return fn();
+    }
+    const result = this._writeQueue.then(() => exclusiveWriter.run(this, fn));
+    this._writeQueue = result.catch(() => {});
+    return result;
+  }
+
   /**
    * Save an entry to the database
    */
@@ -1378,24 +1568,9 @@ class PersistentDB {
     }
 
     return new Promise((resolve, reject) => {
-      const stmt = this.db.prepare(`
-        INSERT OR REPLACE INTO events 
-        (id, session_id, workspace_path, timestamp, type, details, annotation, intent, tags, ai_generated)
-        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
-      `);
+      const stmt = this.db.prepare(PersistentDB.EVENT_UPSERT_SQL);
 
-      stmt.run(
-        event.id,
-        event.session_id,
-        event.workspace_path,
-        event.timestamp,
-        event.type,
-        typeof event.details === 'object' ? JSON.stringify(event.details) : event.details,
-        event.annotation || null,
-        event.intent || null,
-        Array.isArray(event.tags) ? JSON.stringify(event.tags) : event.tags || null,
-        event.ai_generated ? 1 : 0
-      );
+      stmt.run(...PersistentDB.eventRow(event));
 
       stmt.finalize((err) => {
         if (err) {
@@ -1408,6 +1583,306 @@ class PersistentDB {
     });
   }
 
+  /**
+   * Persist a micro-batch of events in a single transaction
+   *
+   * The batch is validated and redacted by the native ingest_batch when
+   * available, which also coalesces rapid edits to a file if given
+   * `coalesceWindowMs`. In 'atomic' mode (default) nothing is written if any event
+   * is invalid; in 'partial' mode valid events are written and the rest are
+   * reported in `rejected`. Either way a storage error rolls back the batch.
+   */
+  async ingestBatch(events, options = {}) {
+    await this.init();
+
+    const batch = PersistentDB.prepareIngestBatch(events, options);
+    if (batch.accepted.length === 0) {
+      return batch;
+    }
+
+    // Use PostgreSQL adapter if available
+    if (this.postgresAdapter) {
+      try {
+        await this.postgresAdapter.saveEvents(batch.accepted);
+        return batch;
+      } catch (error) {
+        console.error('[DB] Error ingesting batch to PostgreSQL:', error);
+        throw error;
+      }
+    }
+
+    const run = (sql, params = []) =>
+      new Promise((resolve, reject) => {
+        this.db.run(sql, params, (err) => (err ? reject(err) : resolve()));
+      });
+
+    await this.runExclusive(async () => {
+      await run('BEGIN IMMEDIATE');
+      try {
+        for (const event of batch.accepted) {
+          await run(PersistentDB.EVENT_UPSERT_SQL, PersistentDB.eventRow(event));
+        }
+        await run('COMMIT');
+      } catch (error) {
+        await run('ROLLBACK').catch(() => {});
+        console.error('Error ingesting event batch:', error);
+        throw error;
+      }
+    });
+    return batch;
+  }
+
+  /**
+   * Validate and clean a batch before it is written
+   * Falls back to type/timestamp checks only when the native module isn't built
+   */
+  static prepareIngestBatch(events, options = {}) {
+    if (native) {
+      return native.ingestBatch(events, options);
+    }
+
+    const mode = options.mode || 'atomic';
+    const rejected = [];
+    const accepted = [];
+    events.forEach((event, index) => {
+      if (!event || typeof event !== 'object' || !event.type) {
+        rejected.push({ index, id: event && event.id, code: 'MissingType', message: 'Event has no type' });
+      } else if (event.timestamp === undefined || Number.isNaN(new Date(event.timestamp).getTime())) {
+        rejected.push({ index, id: event.id, code: 'InvalidTimestamp', message: 'Invalid timestamp' });
+      } else {
+        accepted.push(event);
+      }
+    });
+    const ok = rejected.length === 0;
+    return {
+      ok,
+      mode,
+      received: events.length,
+      accepted: ok || mode === 'partial' ? accepted : [],
+      rejected,
+      coalesced: 0,
+      duplicates: 0,
+      redactions: 0,
+    };
+  }
+
+  /**
+   * Column values for EVENT_UPSERT_SQL, ending with their checksum
+   * Text columns are bound as strings, so what SQLite stores is exactly what was checksummed
+   */
+  static eventRow(event) {
+    const text = (value) => (value === null || value === undefined ? null : String(value));
+    const values = [
+      text(event.id),
+      text(event.session_id),
+      text(event.workspace_path),
+      text(event.timestamp),
+      text(event.type),
+      text(typeof event.details === 'object' ? JSON.stringify(event.details) : event.details),
+      text(event.annotation || null),
+      text(event.intent || null),
+      text(Array.isArray(event.tags) ? JSON.stringify(event.tags) : event.tags || null),
+      event.ai_generated ? 1 : 0,
+    ];
+    return [...values, PersistentDB.eventChecksum(values)];
+  }
+
+  /**
+   * Checksum of an event's stored columns: the JSON of their typed values,
+   * prefixed with the format version
+   */
+  static eventChecksum(values) {
+    const typed = values.map((value) => (value === undefined ? null : value));
+    const digest = crypto.createHash('sha256').update(JSON.stringify(typed)).digest('hex');
+    return `${PersistentDB.EVENT_CHECKSUM_VERSION}:${digest.slice(0, 16)}`;
+  }
+
+  /**
+   * Whether a stored row matches its checksum
+   * Unversioned checksums predate typed values; they hashed each value as text,
+   * which numbers bound into TEXT columns don't always survive, so they also
+   * accept the text SQLite would have stored for a numeric column
+   */
+  static eventChecksumMatches(row) {
+    const stored = PersistentDB.EVENT_COLUMNS.map((column) => row[column]);
+    if (row.checksum.includes(':')) {
+      return PersistentDB.eventChecksum(stored) === row.checksum;
+    }
+    const legacy = (values) => {
+      const text = values.map((value) => (value === null || value === undefined ? '' : String(value)));
+      return crypto.createHash('sha256').update(text.join('\u0000')).digest('hex').slice(0, 16);
+    };
+    const numeric = stored.map((value) =>
+      typeof value === 'string' && value.trim() !== '' && Number.isFinite(Number(value))
+        ? Number(value)
+        : value
+    );
+    return legacy(stored) === row.checksum || legacy(numeric) === row.checksum;
+  }
+
+  /**
+   * Decode a stored event, or return null and add it to `damage` when its checksum
+   * doesn't match or its JSON columns don't parse
+   */
+  static decodeEventRow(row, damage) {
+    if (row.checksum && !PersistentDB.eventChecksumMatches(row)) {
+      damage.push({ id: row.id, timestamp: row.timestamp, reason: 'checksum' });
+      return null;
+    }
+
+    try {
+      const { checksum, ...event } = row;
+      return {
+        ...event,
+        details: row.details ? JSON.parse(row.details) : {},
+        tags: row.tags ? JSON.parse(row.tags || '[]') : [],
+        ai_generated: row.ai_generated === 1,
+      };
+    } catch (error) {
+      damage.push({ id: row.id, timestamp: row.timestamp, reason: 'invalid-json' });
+      return null;
+    }
+  }
+
+  /**
+   * Decode stored events, in order
+   *
+   * Damaged rows are replaced by their copy in the latest backup (BACKUP_DIR)
+   * when that copy verifies, and skipped otherwise. Either way the damaged
+   * range is recorded for getReadDamage.
+   *
+   * Only the events table carries checksums; other tables are read as stored.
+   */
+  async decodeEventRows(rows, source) {
+    const damage = [];
+    const decoded = rows.map((row) => PersistentDB.decodeEventRow(row, damage));
+    if (damage.length === 0) {
+      return decoded;
+    }
+
+    const { backup, replacements } = await this.recoverEvents(damage.map((d) => d.id));
+    const events = rows
+      .map((row, i) => decoded[i] || replacements.get(row.id) || null)
+      .filter(Boolean);
+    for (const record of damage) {
+      record.recovered = replacements.has(record.id);
+    }
+
+    const timestamps = damage.map((d) => d.timestamp).filter(Boolean).sort();
+    this.readDamage = {
+      table: 'events',
+      source,
+      count: damage.length,
+      recovered: replacements.size,
+      backup,
+      from: timestamps[0] || null,
+      to: timestamps[timestamps.length - 1] || null,
+      records: damage.slice(0, 100),
+      detectedAt: new Date().toISOString(),
+    };
+    console.warn(
+      `[DB] ${damage.length} damaged event(s) in ${source} (${this.readDamage.from} .. ${this.readDamage.to}), ` +
+        `${replacements.size} recovered from ${backup ? `backup ${backup}` : 'no backup'}`
+    );
+    return events;
+  }
+
+  /**
+   * Verified copies of the given events from the latest backup in BACKUP_DIR,
+   * keyed by id, and the id of the backup they came from
+   *
+   * The backup is restored to a temporary file on first use and reused until
+   * a newer backup appears.
+   */
+  async recoverEvents(ids) {
+    const replacements = new Map();
+    let snapshot;
+    try {
+      snapshot = await this.openBackupSnapshot();
+    } catch (error) {
+      console.warn('[DB] Cannot read the latest backup:', error.message);
+      return { backup: null, replacements };
+    }
+    if (!snapshot) {
+      return { backup: null, replacements };
+    }
+
+    const rows = await new Promise((resolve, reject) => {
+      snapshot.db.all(
+        `SELECT * FROM events WHERE id IN (${ids.map(() => '?').join(', ')})`,
+        ids,
+        (err, found) => (err ? reject(err) : resolve(found))
+      );
+    }).catch((error) => {
+      console.warn(`[DB] Cannot read events from backup ${snapshot.id}:`, error.message);
+      return [];
+    });
+    // A backup row is only used when it verifies too
+    const stillDamaged = [];
+    for (const row of rows) {
+      const event = PersistentDB.decodeEventRow(row, stillDamaged);
+      if (event) replacements.set(row.id, event);
+    }
+    return { backup: snapshot.id, replacements };
+  }
+
+  /**
+   * A read-only handle on the latest backup in BACKUP_DIR, or null when there is none
+   */
+  async openBackupSnapshot() {
+    if (!this.backupDir) {
+      return null;
+    }
+    const { listManifests, restoreStore } = require('./backup');
+    const manifests = listManifests(this.backupDir);
+    if (manifests.length === 0) {
+      return null;
+    }
+    const latest = manifests[manifests.length - 1];
+    if (this._backupSnapshot && (await this._backupSnapshot).id === latest.id) {
+      return this._backupSnapshot;
+    }
+    this.closeBackupSnapshot();
+
+    const pending = (async () => {
+      const file = path.join(os.tmpdir(), `companion-backup-${process.pid}-${latest.id}.db`);
+      await restoreStore(path.join(this.backupDir, 'manifests', `${latest.id}.json`), file, {
+        overwrite: true,
+      });
+      const db = await new Promise((resolve, reject) => {
+        const handle = new sqlite3.Database(file, sqlite3.OPEN_READONLY, (err) =>
+          err ? reject(err) : resolve(handle)
+        );
+      });
+      return { id: latest.id, file, db };
+    })();
+    this._backupSnapshot = pending;
+    // A failed restore is retried on the next damaged read
+    pending.catch(() => {
+      if (this._backupSnapshot === pending) this._backupSnapshot = null;
+    });
+    return pending;
+  }
+
+  /**
+   * Close and delete the restored backup opened by openBackupSnapshot
+   */
+  closeBackupSnapshot() {
+    const pending = this._backupSnapshot;
+    this._backupSnapshot = null;
+    if (!pending) return;
+    pending
+      .then(({ file, db }) => db.close(() => fs.rmSync(file, { force: true })))
+      .catch(() => {});
+  }
+
+  /**
+   * Damaged records found by the most recent read that found any, or null
+   */
+  getReadDamage() {
+    return this.readDamage;
+  }
+
   /**
    * Get a single event by ID
    */
@@ -1422,12 +1897,7 @@ class PersistentDB {
         } else if (!row) {
           resolve(null);
         } else {
-          resolve({
-            ...row,
-            details: row.details ? JSON.parse(row.details) : {},
-            tags: row.tags ? JSON.parse(row.tags || '[]') : [],
-            ai_generated: row.ai_generated === 1,
-          });
+          resolve(this.decodeEventRows([row], 'getEvent').then((events) => events[0] || null));
         }
       });
     });
@@ -1448,14 +1918,7 @@ class PersistentDB {
             console.error('Error getting events by session:', err);
             reject(err);
           } else {
-            resolve(
-              rows.map((row) => ({
-                ...row,
-                details: row.details ? JSON.parse(row.details) : {},
-                tags: row.tags ? JSON.parse(row.tags || '[]') : [],
-                ai_generated: row.ai_generated === 1,
-              }))
-            );
+            resolve(this.decodeEventRows(rows, 'getEventsBySession'));
           }
         }
       );
@@ -1484,19 +1947,382 @@ class PersistentDB {
           console.error('Error getting recent events:', err);
           reject(err);
         } else {
-          resolve(
-            rows.map((row) => ({
-              ...row,
-              details: row.details ? JSON.parse(row.details) : {},
-              tags: row.tags ? JSON.parse(row.tags || '[]') : [],
-              ai_generated: row.ai_generated === 1,
-            }))
+          resolve(this.decodeEventRows(rows, 'getRecentEvents'));
+        }
+      });
+    });
+  }
+
+  /**
+   * Get events between two times, newest first
+   * Reads through to cold storage for days that tierColdEvents has moved out
+   */
+  async getEventsInRange(since, until, limit = 1000) {
+    await this.init();
+    const sinceMs = since ? new Date(since).getTime() : 0;
+    const untilMs = until ? new Date(until).getTime() : Date.now();
+
+    // Use PostgreSQL adapter if available
+    if (this.postgresAdapter) {
+      try {
+        return await this.postgresAdapter.getEventsInRange(
+          new Date(sinceMs).toISOString(),
+          new Date(untilMs).toISOString(),
+          limit
+        );
+      } catch (error) {
+        console.error('[DB] Error getting events in range from PostgreSQL:', error);
+        throw error;
+      }
+    }
+
+    const all = (sql, params) =>
+      new Promise((resolve, reject) => {
+        this.db.all(sql, params, (err, rows) => (err ? reject(err) : resolve(rows)));
+      });
+    const newestFirst = (a, b) =>
+      PersistentDB.eventTimeMs(b.timestamp) - PersistentDB.eventTimeMs(a.timestamp);
+
+    const hot = await all(
+      `SELECT * FROM events WHERE ${PersistentDB.EVENT_TIME_SQL} BETWEEN ? AND ?
+       ORDER BY ${PersistentDB.EVENT_TIME_SQL} DESC LIMIT ?`,
+      [sinceMs, untilMs, limit]
+    );
+    const events = await this.decodeEventRows(hot, 'getEventsInRange');
+
+    const segments = await all(
+      `SELECT key, checksum, max_time FROM cold_segments
+       WHERE min_time <= ? AND max_time >= ? ORDER BY max_time DESC`,
+      [untilMs, sinceMs]
+    );
+    if (segments.length > 0 && !this.coldStorage) {
+      console.warn(
+        `[DB] ${segments.length} cold segment(s) overlap the requested range but cold storage is not configured`
+      );
+      return events;
+    }
+
+    const seen = new Set(events.map((event) => event.id));
+    for (const segment of segments) {
+      // Segments are newest first, so stop once they can't displace what we have
+      if (
+        events.length >= limit &&
+        PersistentDB.eventTimeMs(events[limit - 1].timestamp) > segment.max_time
+      ) {
+        break;
+      }
+      const rows = (await this.coldStorage.readSegment(segment.key, segment.checksum)) || [];
+      const inRange = rows.filter((row) => {
+        const time = PersistentDB.eventTimeMs(row.timestamp);
+        return time >= sinceMs && time <= untilMs && !seen.has(row.id);
+      });
+      events.push(...(await this.decodeEventRows(inRange, `cold:${segment.key}`)));
+      events.sort(newestFirst);
+    }
+    return events.slice(0, limit);
+  }
+
+  /**
+   * Take the named store lock, shared by every process that opens this store
+   *
+   * The lock is a lease row claimed with a single upsert, so SQLite's own
+   * write lock decides between processes racing for it. A holder that dies
+   * loses the lease after `ttlMs`; long holders call renew() as they go,
+   * which fails once the lease has been lost. Throws when another owner
+   * holds it.
+   */
+  async acquireStoreLock(name, ttlMs = PersistentDB.STORE_LOCK_TTL_MS) {
+    await this.init();
+    const owner = `${os.hostname()}:${process.pid}:${crypto.randomBytes(4).toString('hex')}`;
+    const run = (sql, params) =>
+      this.runExclusive(
+        () =>
+          new Promise((resolve, reject) => {
+            this.db.run(sql, params, function (err) {
+              if (err) reject(err);
+              else resolve(this.changes);
+            });
+          })
+      );
+    const claim = async () => {
+      const now = Date.now();
+      const changes = await run(
+        `INSERT INTO store_locks (name, owner, expires_at) VALUES (?, ?, ?)
+         ON CONFLICT(name) DO UPDATE SET owner = excluded.owner, expires_at = excluded.expires_at
+         WHERE store_locks.owner = excluded.owner OR store_locks.expires_at < ?`,
+        [name, owner, now + ttlMs, now]
+      );
+      if (changes === 0) {
+        const holder = await new Promise((resolve, reject) => {
+          this.db.get(`SELECT owner, expires_at FROM store_locks WHERE name = ?`, [name], (err, row) =>
+            err ? reject(err) : resolve(row)
           );
+        });
+        const error = new Error(
+          `Store lock ${name} is held by ${holder?.owner || 'another process'}` +
+            (holder ? ` until ${new Date(holder.expires_at).toISOString()}` : '')
+        );
+        error.code = 'STORE_LOCKED';
+        throw error;
+      }
+    };
+
+    await claim();
+    return {
+      owner,
+      renew: claim,
+      release: () =>
+        run(`DELETE FROM store_locks WHERE name = ? AND owner = ?`, [name, owner]).catch((error) =>
+          console.warn(`[DB] Failed to release store lock ${name}:`, error.message)
+        ),
+    };
+  }
+
+  /**
+   * Move events older than `olderThanDays` to cold storage
+   *
+   * Each UTC day becomes one segment, merged with that day's existing segment
+   * when late events arrive. A day's rows are deleted from the store only
+   * after its segment has been written and read back intact, so an
+   * interrupted run leaves events in the store rather than losing them.
+   * Runs under the `cold-segments` store lock, so it never races another
+   * process's tiering or compactStore's segment merging.
+   */
+  async tierColdEvents(options = {}) {
+    await this.init();
+    const coldStorage = options.coldStorage || this.coldStorage;
+    if (!coldStorage) {
+      throw new Error('Cold storage is not configured (set COLD_STORAGE_PATH or COLD_STORAGE_S3)');
+    }
+    if (this.postgresAdapter) {
+      throw new Error('Cold-storage tiering is only available for the SQLite store');
+    }
+    // Segments are written before rows are deleted, so check up front rather than at the delete
+    if (this.readOnly) {
+      throw new Error(`Store is open read-only: ${this.dbPath}`);
+    }
+
+    const olderThanDays = options.olderThanDays ?? coldStorage.afterDays;
+    const cutoff = Date.now() - olderThanDays * 24 * 60 * 60 * 1000;
+    const started = Date.now();
+    const time = PersistentDB.EVENT_TIME_SQL;
+
+    const all = (sql, params = []) =>
+      new Promise((resolve, reject) => {
+        this.db.all(sql, params, (err, rows) => (err ? reject(err) : resolve(rows)));
+      });
+    const get = (sql, params = []) =>
+      new Promise((resolve, reject) => {
+        this.db.get(sql, params, (err, row) => (err ? reject(err) : resolve(row)));
+      });
+    const run = (sql, params = []) =>
+      new Promise((resolve, reject) => {
+        this.db.run(sql, params, (err) => (err ? reject(err) : resolve()));
+      });
+
+    const days = await all(
+      `SELECT DISTINCT strftime('%Y-%m-%d', ${time} / 1000.0, 'unixepoch') AS day
+       FROM events WHERE ${time} < ? ORDER BY day`,
+      [cutoff]
+    );
+
+    const result = {
+      location: coldStorage.location,
+      cutoff: new Date(cutoff).toISOString(),
+      segments: 0,
+      events: 0,
+      bytes: 0,
+    };
+    const lock = await this.acquireStoreLock('cold-segments');
+    try {
+      for (const { day } of days) {
+        if (!day) continue;
+        await lock.renew();
+        const dayStart = Date.parse(`${day}T00:00:00.000Z`);
+        const rows = await all(`SELECT * FROM events WHERE ${time} >= ? AND ${time} < ?`, [
+          dayStart,
+          Math.min(dayStart + 24 * 60 * 60 * 1000, cutoff),
+        ]);
+        if (rows.length === 0) continue;
+
+        const key = ColdStorage.segmentKey(day);
+        const existing = await get(`SELECT checksum FROM cold_segments WHERE key = ?`, [key]);
+        const previous = existing ? await coldStorage.readSegment(key, existing.checksum) : [];
+        if (!previous) {
+          throw new Error(`Cold segment ${key} is recorded but missing from ${coldStorage.location}`);
+        }
+        const ids = new Set(rows.map((row) => row.id));
+        const merged = [...previous.filter((row) => !ids.has(row.id)), ...rows];
+        // A loop rather than Math.min(...times): a busy day has more rows than the argument limit
+        let minTime = Infinity;
+        let maxTime = -Infinity;
+        for (const row of merged) {
+          const ms = PersistentDB.eventTimeMs(row.timestamp);
+          if (ms < minTime) minTime = ms;
+          if (ms > maxTime) maxTime = ms;
+        }
+        const { checksum, bytes } = await coldStorage.writeSegment(key, merged);
+
+        // Only the transaction holds the write queue, not the segment upload
+        await this.runExclusive(async () => {
+          await run('BEGIN IMMEDIATE');
+          try {
+            await run(
+              `INSERT OR REPLACE INTO cold_segments
+               (key, location, event_count, min_time, max_time, checksum, tiered_at)
+               VALUES (?, ?, ?, ?, ?, ?, ?)`,
+              [
+                key,
+                coldStorage.location,
+                merged.length,
+                minTime,
+                maxTime,
+                checksum,
+                new Date().toISOString(),
+              ]
+            );
+            const moved = [...ids];
+            for (let i = 0; i < moved.length; i += 500) {
+              const chunk = moved.slice(i, i + 500);
+              await run(`DELETE FROM events WHERE id IN (${chunk.map(() => '?').join(', ')})`, chunk);
+            }
+            await run('COMMIT');
+          } catch (error) {
+            await run('ROLLBACK').catch(() => {});
+            throw error;
+          }
+        });
+
+        result.segments++;
+        result.events += rows.length;
+        result.bytes += bytes;
+      }
+    } finally {
+      await lock.release();
+    }
+
+    result.durationMs = Date.now() - started;
+    return result;
+  }
+
+  /**
+   * Milliseconds since the epoch of a stored timestamp, which is either epoch millis or ISO text
+   */
+  static eventTimeMs(timestamp) {
+    if (typeof timestamp === 'number' || /^\d+$/.test(String(timestamp))) {
+      return Number(timestamp);
+    }
+    return new Date(timestamp).getTime();
+  }
+
+  /**
+   * Label the outcome of an event (e.g. an AI edit) as good, bad, or reverted
+   * Labels live in their own table so re-saving the event keeps them
+   */
+  async labelEvent(eventId, label, note = null) {
+    await this.init();
+
+    if (!PersistentDB.OUTCOME_LABELS.includes(label)) {
+      throw new Error(
+        `Invalid label "${label}", expected one of: ${PersistentDB.OUTCOME_LABELS.join(', ')}`
+      );
+    }
+
+    const record = {
+      event_id: String(eventId),
+      label,
+      note: note || null,
+      labeled_at: new Date().toISOString(),
+    };
+
+    // Use PostgreSQL adapter if available
+    if (this.postgresAdapter) {
+      try {
+        return await this.postgresAdapter.labelEvent(record);
+      } catch (error) {
+        console.error('[DB] Error labeling event in PostgreSQL:', error);
+        throw error;
+      }
+    }
+
+    return new Promise((resolve, reject) => {
+      this.db.run(
+        `INSERT OR REPLACE INTO event_labels (event_id, label, note, labeled_at) VALUES (?, ?, ?, ?)`,
+        [record.event_id, record.label, record.note, record.labeled_at],
+        (err) => {
+          if (err) {
+            console.error('Error labeling event:', err);
+            reject(err);
+          } else {
+            resolve(record);
+          }
+        }
+      );
+    });
+  }
+
+  /**
+   * Remove the outcome label from an event
+   */
+  async unlabelEvent(eventId) {
+    await this.init();
+
+    if (this.postgresAdapter) {
+      return this.postgresAdapter.unlabelEvent(String(eventId));
+    }
+
+    return new Promise((resolve, reject) => {
+      this.db.run(`DELETE FROM event_labels WHERE event_id = ?`, [String(eventId)], function (err) {
+        if (err) {
+          console.error('Error unlabeling event:', err);
+          reject(err);
+        } else {
+          resolve(this.changes > 0);
         }
       });
     });
   }
 
+  /**
+   * Get labeled events, optionally filtered to one label
+   * Each event carries `label`, `label_note`, and `labeled_at`
+   */
+  async getEventsByLabel(label = null, limit = 1000) {
+    await this.init();
+
+    if (this.postgresAdapter) {
+      try {
+        return await this.postgresAdapter.getEventsByLabel(label, limit);
+      } catch (error) {
+        console.error('[DB] Error getting labeled events from PostgreSQL:', error);
+        throw error;
+      }
+    }
+
+    const where = label ? 'WHERE l.label = ?' : '';
+    const params = label ? [label, limit] : [limit];
+
+    return new Promise((resolve, reject) => {
+      this.db.all(
+        `SELECT e.*, l.label AS label, l.note AS label_note, l.labeled_at AS labeled_at
+         FROM event_labels l
+         JOIN events e ON e.id = l.event_id
+         ${where}
+         ORDER BY l.labeled_at DESC
+         LIMIT ?`,
+        params,
+        (err, rows) => {
+          if (err) {
+            console.error('Error getting events by label:', err);
+            reject(err);
+          } else {
+            resolve(this.decodeEventRows(rows, 'getEventsByLabel'));
+          }
+        }
+      );
+    });
+  }
+
   /**
    * Save a terminal command to the database
    */
@@ -2032,15 +2858,8 @@ class PersistentDB {
           console.error('Error loading events:', err);
           reject(err);
         } else {
-          resolve(
-            rows.map((row) => ({
-              ...row,
-              details: row.details ? JSON.parse(row.details) : {},
-              tags: row.tags ? JSON.parse(row.tags || '[]') : [],
-              ai_generated: row.ai_generated === 1,
-              // annotation field is already a string, no parsing needed
-            }))
-          );
+          // annotation field is already a string, no parsing needed
+          resolve(this.decodeEventRows(rows, 'getAllEvents'));
         }
       });
     });
@@ -2261,6 +3080,220 @@ class PersistentDB {
     });
   }
 
+  /**
+   * Reclaim free pages without closing the database
+   *
+   * Online (default), free pages are released in small incremental_vacuum
+   * steps with pauses in between, so ingestion from this and other processes
+   * keeps going; SQLite's file locks and busy_timeout coordinate the writers.
+   * This needs auto_vacuum=INCREMENTAL, which new databases get. Older
+   * databases report `needsOfflineCompaction` until compacted once with
+   * `{ online: false }`, which runs a full VACUUM (blocking writers while it
+   * runs) and switches them to incremental mode.
+   *
+   * With cold storage configured, it then merges cold segments holding fewer
+   * than `smallSegmentEvents` events into one segment per UTC month (see
+   * mergeColdSegments). Pass `mergeSegments: false` to skip that.
+   */
+  async compactStore(options = {}) {
+    await this.init();
+    const {
+      online = true,
+      pagesPerStep = 256,
+      pauseMs = 10,
+      mergeSegments = true,
+      smallSegmentEvents,
+    } = options;
+    const started = Date.now();
+
+    // Use PostgreSQL adapter if available
+    if (this.postgresAdapter) {
+      await this.postgresAdapter.compact();
+      return { online: true, mode: 'postgres-vacuum', durationMs: Date.now() - started };
+    }
+
+    const get = (sql) =>
+      new Promise((resolve, reject) => {
+        this.db.get(sql, (err, row) => (err ? reject(err) : resolve(row)));
+      });
+    // Each step queues on its own, so writes get in between steps
+    const run = (sql) =>
+      this.runExclusive(
+        () =>
+          new Promise((resolve, reject) => {
+            this.db.run(sql, (err) => (err ? reject(err) : resolve()));
+          })
+      );
+    const pages = async () => ({
+      total: (await get('PRAGMA page_count')).page_count,
+      free: (await get('PRAGMA freelist_count')).freelist_count,
+    });
+
+    const pageSize = (await get('PRAGMA page_size')).page_size;
+    const incremental = (await get('PRAGMA auto_vacuum')).auto_vacuum === 2;
+    const before = await pages();
+    let steps = 0;
+    let mode;
+
+    if (!online) {
+      await run('PRAGMA auto_vacuum = INCREMENTAL');
+      await run('VACUUM');
+      await run('PRAGMA wal_checkpoint(TRUNCATE)');
+      mode = 'vacuum';
+    } else if (incremental) {
+      let free = before.free;
+      while (free > 0) {
+        await run(`PRAGMA incremental_vacuum(${Math.max(1, pagesPerStep)})`);
+        steps++;
+        const remaining = (await get('PRAGMA freelist_count')).freelist_count;
+        if (remaining >= free) break;
+        free = remaining;
+        await new Promise((resolve) => setTimeout(resolve, pauseMs));
+      }
+      // PASSIVE never waits on readers or writers
+      await run('PRAGMA wal_checkpoint(PASSIVE)');
+      mode = 'incremental';
+    } else {
+      await run('PRAGMA wal_checkpoint(PASSIVE)');
+      mode = 'checkpoint';
+    }
+
+    const after = await pages();
+    return {
+      online,
+      mode,
+      steps,
+      pagesBefore: before.total,
+      pagesAfter: after.total,
+      freePagesBefore: before.free,
+      freePagesAfter: after.free,
+      bytesReclaimed: Math.max(0, before.total - after.total) * pageSize,
+      needsOfflineCompaction: online && !incremental && before.free > 0,
+      segments:
+        mergeSegments && this.coldStorage
+          ? await this.mergeColdSegments({ smallSegmentEvents })
+          : null,
+      durationMs: Date.now() - started,
+    };
+  }
+
+  /**
+   * Merge small cold segments into one segment per UTC month
+   *
+   * Runs under the `cold-segments` store lock, which tierColdEvents in any
+   * process also takes; when another process holds it the merge is skipped
+   * and reported as such. Each merged segment is written and read back before
+   * one transaction records it in place of its parts, and the parts are only
+   * deleted from cold storage after that commits, so an interrupted merge
+   * leaves at worst an unrecorded object behind.
+   */
+  async mergeColdSegments(options = {}) {
+    await this.init();
+    const coldStorage = options.coldStorage || this.coldStorage;
+    if (!coldStorage) {
+      throw new Error('Cold storage is not configured (set COLD_STORAGE_PATH or COLD_STORAGE_S3)');
+    }
+    const smallSegmentEvents = options.smallSegmentEvents || PersistentDB.SMALL_SEGMENT_EVENTS;
+
+    const all = (sql, params = []) =>
+      new Promise((resolve, reject) => {
+        this.db.all(sql, params, (err, rows) => (err ? reject(err) : resolve(rows)));
+      });
+    const run = (sql, params = []) =>
+      new Promise((resolve, reject) => {
+        this.db.run(sql, params, (err) => (err ? reject(err) : resolve()));
+      });
+
+    const result = { merged: 0, segmentsBefore: 0, segmentsAfter: 0, skipped: null };
+    let lock;
+    try {
+      lock = await this.acquireStoreLock('cold-segments');
+    } catch (error) {
+      if (error.code !== 'STORE_LOCKED') throw error;
+      result.skipped = error.message;
+      return result;
+    }
+
+    try {
+      const small = await all(
+        `SELECT * FROM cold_segments WHERE location = ? AND event_count < ? ORDER BY min_time`,
+        [coldStorage.location, smallSegmentEvents]
+      );
+      const months = new Map();
+      for (const segment of small) {
+        const month = new Date(segment.min_time).toISOString().slice(0, 7);
+        if (!months.has(month)) months.set(month, []);
+        months.get(month).push(segment);
+      }
+
+      for (const [month, parts] of months) {
+        if (parts.length < 2) continue;
+        await lock.renew();
+
+        const seen = new Set();
+        const rows = [];
+        for (const part of parts) {
+          const partRows = await coldStorage.readSegment(part.key, part.checksum);
+          if (!partRows) {
+            throw new Error(
+              `Cold segment ${part.key} is recorded but missing from ${coldStorage.location}`
+            );
+          }
+          for (const row of partRows) {
+            if (seen.has(row.id)) continue;
+            seen.add(row.id);
+            rows.push(row);
+          }
+        }
+        const key = ColdStorage.mergedSegmentKey(month);
+        const { checksum } = await coldStorage.writeSegment(key, rows);
+
+        await this.runExclusive(async () => {
+          await run('BEGIN IMMEDIATE');
+          try {
+            await run(
+              `INSERT INTO cold_segments
+               (key, location, event_count, min_time, max_time, checksum, tiered_at)
+               VALUES (?, ?, ?, ?, ?, ?, ?)`,
+              [
+                key,
+                coldStorage.location,
+                rows.length,
+                Math.min(...parts.map((part) => part.min_time)),
+                Math.max(...parts.map((part) => part.max_time)),
+                checksum,
+                new Date().toISOString(),
+              ]
+            );
+            await run(
+              `DELETE FROM cold_segments WHERE key IN (${parts.map(() => '?').join(', ')})`,
+              parts.map((part) => part.key)
+            );
+            await run('COMMIT');
+          } catch (error) {
+            await run('ROLLBACK').catch(() => {});
+            await coldStorage.deleteSegment(key).catch(() => {});
+            throw error;
+          }
+        });
+
+        for (const part of parts) {
+          await coldStorage
+            .deleteSegment(part.key)
+            .catch((error) =>
+              console.warn(`[DB] Merged cold segment ${part.key} could not be deleted:`, error.message)
+            );
+        }
+        result.merged++;
+        result.segmentsBefore += parts.length;
+        result.segmentsAfter++;
+      }
+    } finally {
+      await lock.release();
+    }
+    return result;
+  }
+
   /**
    * Validate database integrity
    */
@@ -2330,6 +3363,7 @@ class PersistentDB {
    * Close the database connection
    */
   close() {
+    this.closeBackupSnapshot();
     if (this.db) {
       this.db.close((err) => {
         if (err) {
@@ -4400,4 +5434,84 @@ class PersistentDB {
   }
 }
 
+PersistentDB.OUTCOME_LABELS = ['good', 'bad', 'reverted'];
+
+PersistentDB.EVENT_COLUMNS = [
+  'id',
+  'session_id',
+  'workspace_path',
+  'timestamp',
+  'type',
+  'details',
+  'annotation',
+  'intent',
+  'tags',
+  'ai_generated',
+];
+
+// Lease on a store lock before a holder that stopped renewing loses it
+PersistentDB.STORE_LOCK_TTL_MS = 10 * 60 * 1000;
+
+// Cold segments with fewer events than this are merged by compactStore
+PersistentDB.SMALL_SEGMENT_EVENTS = 1000;
+
+// Prefix of checksums over typed values; unprefixed ones are the older text form
+PersistentDB.EVENT_CHECKSUM_VERSION = 2;
+
+// Event time in epoch millis, for timestamps stored as either epoch millis or ISO text
+PersistentDB.EVENT_TIME_SQL = `(CASE WHEN timestamp NOT GLOB '*[^0-9]*'
+  THEN CAST(timestamp AS INTEGER)
+  ELSE CAST(ROUND((julianday(timestamp) - 2440587.5) * 86400000) AS INTEGER) END)`;
+
+PersistentDB.EVENT_UPSERT_SQL = `
+  INSERT OR REPLACE INTO events 
+  (${PersistentDB.EVENT_COLUMNS.join(', ')}, checksum)
+  VALUES (${PersistentDB.EVENT_COLUMNS.map(() => '?').join(', ')}, ?)
+`;
+
+// Methods that write with single statements; each waits its turn in the
+// write queue. ingestBatch, tierColdEvents, and compactStore queue only their
+// writes, so reads and cold-storage uploads don't hold up other writers.
+PersistentDB.WRITE_METHODS = [
+  'saveEntry',
+  'savePrompt',
+  'saveEvent',
+  'labelEvent',
+  'unlabelEvent',
+  'saveTerminalCommand',
+  'updateEntry',
+  'updatePrompt',
+  'saveContextSnapshot',
+  'saveContextChange',
+  'addColumn',
+  'saveCustomFieldConfig',
+  'deleteCustomFieldConfig',
+  'saveStatusMessage',
+  'linkStatusToContextChange',
+  'saveTodo',
+  'updateTodoStatus',
+  'linkEventToTodo',
+  'addPromptToTodo',
+  'addFileToTodo',
+  'saveConversation',
+  'updateConversationMetadata',
+  'logAuditEvent',
+  'saveShareLink',
+  'updateShareLink',
+  'deleteShareLink',
+  'saveHistoricalCommit',
+  'saveHistoricalBranch',
+  'saveHistoricalDiff',
+  'saveHistoricalCommand',
+  'saveHistoricalPrompt',
+  'saveFileTimestamp',
+  'saveMiningRun',
+];
+for (const name of PersistentDB.WRITE_METHODS) {
+  const write = PersistentDB.prototype[name];
+  PersistentDB.prototype[name] = function (...args) {
+    return this.runExclusive(() => write.apply(this, args));
+  };
+}
+
 module.exports = PersistentDB;
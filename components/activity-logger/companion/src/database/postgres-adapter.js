@@ -157,7 +157,14 @@ class PostgresAdapter {
       )`,
       
       // Workspaces table
-      `CREATE TABLE IF NOT EXISTS workspaces (
+      `CREATE TABLE IF NOT EXISTS event_labels (
+        event_id TEXT PRIMARY KEY,
+        label TEXT NOT NULL,
+        note TEXT,
+        labeled_at TEXT
+      )`,
+      
+            `CREATE TABLE IF NOT EXISTS workspaces (
         id SERIAL PRIMARY KEY,
         path TEXT UNIQUE,
         name TEXT,
@@ -175,6 +182,7 @@ class PostgresAdapter {
       'CREATE INDEX IF NOT EXISTS idx_prompts_conversation ON prompts(conversation_id)',
       'CREATE INDEX IF NOT EXISTS idx_events_timestamp ON events(timestamp)',
       'CREATE INDEX IF NOT EXISTS idx_events_workspace ON events(workspace_path)',
+      'CREATE INDEX IF NOT EXISTS idx_event_labels_label ON event_labels(label)',
       'CREATE INDEX IF NOT EXISTS idx_terminal_timestamp ON terminal_commands(timestamp)',
       'CREATE INDEX IF NOT EXISTS idx_context_prompt_id ON context_snapshots(prompt_id)',
     ];
@@ -373,8 +381,9 @@ class PostgresAdapter {
 
   /**
    * Save event
+   * Pass a client to run inside a transaction started by the caller
    */
-  async saveEvent(event) {
+  async saveEvent(event, client = null) {
     // Use INSERT ... ON CONFLICT for upsert behavior
     const query = event.id
       ? `
@@ -433,11 +442,32 @@ class PostgresAdapter {
           event.file_path || null
         ];
 
-    const result = await this.pool.query(query, values);
+    const result = await (client || this.pool).query(query, values);
     const savedId = result.rows[0].id;
     return { ...event, id: savedId };
   }
 
+  /**
+   * Save events in a single transaction, rolling back all of them on any error
+   */
+  async saveEvents(events) {
+    const client = await this.pool.getClient();
+    try {
+      await client.query('BEGIN');
+      const saved = [];
+      for (const event of events) {
+        saved.push(await this.saveEvent(event, client));
+      }
+      await client.query('COMMIT');
+      return saved;
+    } catch (error) {
+      await client.query('ROLLBACK').catch(() => {});
+      throw error;
+    } finally {
+      client.release();
+    }
+  }
+
   /**
    * Get recent entries
    */
@@ -497,6 +527,56 @@ class PostgresAdapter {
     return result.rows.map(row => this.normalizeEvent(row));
   }
 
+  /**
+   * Get events between two ISO times, newest first
+   */
+  async getEventsInRange(since, until, limit = 1000) {
+    const query =
+      'SELECT * FROM events WHERE timestamp >= $1 AND timestamp <= $2 ORDER BY timestamp DESC LIMIT $3';
+    const result = await this.pool.query(query, [since, until, limit]);
+    return result.rows.map(row => this.normalizeEvent(row));
+  }
+
+  /**
+   * Upsert an event outcome label
+   */
+  async labelEvent(record) {
+    const query = `
+      INSERT INTO event_labels (event_id, label, note, labeled_at)
+      VALUES ($1, $2, $3, $4)
+      ON CONFLICT (event_id) DO UPDATE SET
+        label = EXCLUDED.label,
+        note = EXCLUDED.note,
+        labeled_at = EXCLUDED.labeled_at
+    `;
+    await this.pool.query(query, [record.event_id, record.label, record.note, record.labeled_at]);
+    return record;
+  }
+
+  /**
+   * Delete an event outcome label
+   */
+  async unlabelEvent(eventId) {
+    const result = await this.pool.query('DELETE FROM event_labels WHERE event_id = $1', [eventId]);
+    return result.rowCount > 0;
+  }
+
+  /**
+   * Get labeled events, optionally filtered to one label
+   */
+  async getEventsByLabel(label = null, limit = 1000) {
+    const query = `
+      SELECT e.*, l.label AS label, l.note AS label_note, l.labeled_at AS labeled_at
+      FROM event_labels l
+      JOIN events e ON e.id::text = l.event_id
+      ${label ? 'WHERE l.label = $1' : ''}
+      ORDER BY l.labeled_at DESC
+      LIMIT ${label ? '$2' : '$1'}
+    `;
+    const result = await this.pool.query(query, label ? [label, limit] : [limit]);
+    return result.rows.map(row => this.normalizeEvent(row));
+  }
+
   /**
    * Normalize entry from database
    */
@@ -530,6 +610,13 @@ class PostgresAdapter {
     };
   }
 
+  /**
+   * Reclaim dead rows and refresh planner statistics; VACUUM doesn't block reads or writes
+   */
+  async compact() {
+    await this.pool.query('VACUUM (ANALYZE)');
+  }
+
   /**
    * Get database stats
    */
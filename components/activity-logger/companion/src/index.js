@@ -548,6 +548,16 @@ let events = [];
 let sequence = 0;
 let queue = []; // { seq, kind: 'entry'|'event', payload }
 
+// Events awaiting persistence, written together by flushPendingEvents
+const EVENT_FLUSH_DELAY_MS = 250;
+const EVENT_FLUSH_MAX_BATCH = 200;
+// How long shutdown waits for the last micro-batch to be written
+const SHUTDOWN_FLUSH_TIMEOUT_MS = 5000;
+let pendingEvents = [];
+let eventFlushTimer = null;
+// The latest flush; each one starts after the previous has finished
+let eventFlush = Promise.resolve();
+
 let lunrIndex; // Lunr index instance
 let indexedDocs = []; // Array to store documents added to the index
 
@@ -722,16 +732,13 @@ function enqueue(kind, payload) {
     entries.push(payload);
   } else if (kind === 'event') {
     events.push(payload);
-    // Persist events to disk
-    persistentDB
-      .saveEvent(payload)
-      .then(() => {
-        // Notify procedural knowledge builder
-        if (proceduralKnowledgeBuilder && typeof proceduralKnowledgeBuilder.notifyDataCaptured === 'function') {
-          proceduralKnowledgeBuilder.notifyDataCaptured('events', 1);
-        }
-      })
-      .catch((err) => console.error('Error persisting event:', err.message));
+    // Persist events to disk in micro-batches
+    pendingEvents.push(payload);
+    if (pendingEvents.length >= EVENT_FLUSH_MAX_BATCH) {
+      flushPendingEvents();
+    } else if (!eventFlushTimer) {
+      eventFlushTimer = setTimeout(flushPendingEvents, EVENT_FLUSH_DELAY_MS);
+    }
 
     // Annotate event asynchronously (non-blocking)
     if (process.env.OPENROUTER_API_KEY) {
@@ -768,6 +775,40 @@ function enqueue(kind, payload) {
   updateLunrIndex(item); // Update Lunr index with the new item
 }
 
+// Write pending events in one transaction, reporting invalid ones without dropping the rest.
+// Resolves once this batch and every earlier one has been written or has failed.
+function flushPendingEvents() {
+  clearTimeout(eventFlushTimer);
+  eventFlushTimer = null;
+  const batch = pendingEvents;
+  pendingEvents = [];
+  if (batch.length === 0) return eventFlush;
+
+  eventFlush = eventFlush.then(() => writeEventBatch(batch));
+  return eventFlush;
+}
+
+function writeEventBatch(batch) {
+  return persistentDB
+    .ingestBatch(batch, { mode: 'partial' })
+    .then((result) => {
+      for (const rejection of result.rejected) {
+        console.warn(`Rejected event ${rejection.id || `#${rejection.index}`}: ${rejection.message}`);
+      }
+      // Notify procedural knowledge builder
+      if (proceduralKnowledgeBuilder && typeof proceduralKnowledgeBuilder.notifyDataCaptured === 'function') {
+        proceduralKnowledgeBuilder.notifyDataCaptured('events', result.accepted.length);
+      }
+    })
+    .catch((err) => console.error('Error persisting events:', err.message));
+}
+
+// Flush the last micro-batch before exiting, without letting a stuck write hold up shutdown
+function flushBeforeExit() {
+  const timeout = new Promise((resolve) => setTimeout(resolve, SHUTDOWN_FLUSH_TIMEOUT_MS).unref());
+  return Promise.race([flushPendingEvents(), timeout]);
+}
+
 // Lunr indexing functions
 function addToLunrIndex(item) {
   if (!lunrIndex) return;
@@ -2296,7 +2337,7 @@ io.on('connection', (socket) => {
 // Server startup moved to end of file to avoid duplicate
 
 // Graceful shutdown
-process.on('SIGINT', () => {
+process.on('SIGINT', async () => {
   console.log('\n Shutting down companion service...');
 
   // Stop automatic mining scheduler
@@ -2336,13 +2377,16 @@ process.on('SIGINT', () => {
     global.plotFileMonitors.clear();
   }
 
+  // Write events still waiting for the next micro-batch, now that nothing adds to it
+  await flushBeforeExit();
+
   server.close(() => {
     console.log(' Companion service stopped');
     process.exit(0);
   });
 });
 
-process.on('SIGTERM', () => {
+process.on('SIGTERM', async () => {
   console.log('\n Shutting down companion service...');
 
   // Stop file watcher service
@@ -2376,6 +2420,9 @@ process.on('SIGTERM', () => {
     global.plotFileMonitors.clear();
   }
 
+  // Write events still waiting for the next micro-batch, now that nothing adds to it
+  await flushBeforeExit();
+
   server.close(() => {
     console.log(' Companion service stopped');
     process.exit(0);
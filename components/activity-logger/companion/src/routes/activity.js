@@ -245,6 +245,30 @@ function createActivityRoutes(deps) {
     }
   });
 
+  // Events in a time range, including days moved to cold storage
+  app.get('/api/activity/events', async (req, res) => {
+    try {
+      const limit = Math.min(parseInt(req.query.limit) || 500, 5000);
+      const since = req.query.since || null;
+      const until = req.query.until || null;
+      const invalid = (value) => value && Number.isNaN(new Date(value).getTime());
+      if (invalid(since) || invalid(until)) {
+        return res.status(400).json({ success: false, error: 'since and until must be dates' });
+      }
+
+      const events = await persistentDB.getEventsInRange(since, until, limit);
+
+      res.json({
+        success: true,
+        data: events,
+        count: events.length,
+      });
+    } catch (error) {
+      console.error('Error getting events in range:', error);
+      res.status(500).json({ success: false, error: error.message });
+    }
+  });
+
   // Get entries with prompts
   app.get('/entries', async (req, res) => {
     try {
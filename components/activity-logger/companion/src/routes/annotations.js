@@ -259,6 +259,85 @@ function createAnnotationRoutes(deps) {
       });
     }
   });
+
+  /**
+   * Label the outcome of an event (good, bad, or reverted)
+   */
+  app.post('/api/annotations/label', async (req, res) => {
+    try {
+      const { eventId, label, note } = req.body;
+
+      if (!eventId || !label) {
+        return res.status(400).json({
+          success: false,
+          error: 'eventId and label are required',
+        });
+      }
+
+      if (!persistentDB.constructor.OUTCOME_LABELS.includes(label)) {
+        return res.status(400).json({
+          success: false,
+          error: `label must be one of: ${persistentDB.constructor.OUTCOME_LABELS.join(', ')}`,
+        });
+      }
+
+      const record = await persistentDB.labelEvent(eventId, label, note);
+
+      res.json({
+        success: true,
+        label: record,
+      });
+    } catch (error) {
+      console.error('[ANNOTATIONS] Error labeling event:', error);
+      res.status(500).json({
+        success: false,
+        error: error.message,
+      });
+    }
+  });
+
+  /**
+   * Remove an event's outcome label
+   */
+  app.delete('/api/annotations/label/:eventId', async (req, res) => {
+    try {
+      const removed = await persistentDB.unlabelEvent(req.params.eventId);
+
+      res.json({
+        success: true,
+        removed,
+      });
+    } catch (error) {
+      console.error('[ANNOTATIONS] Error removing label:', error);
+      res.status(500).json({
+        success: false,
+        error: error.message,
+      });
+    }
+  });
+
+  /**
+   * Get labeled events, optionally filtered by label
+   */
+  app.get('/api/annotations/labels', async (req, res) => {
+    try {
+      const { label } = req.query;
+      const limit = parseInt(req.query.limit) || 1000;
+      const events = await persistentDB.getEventsByLabel(label || null, limit);
+
+      res.json({
+        success: true,
+        events,
+        count: events.length,
+      });
+    } catch (error) {
+      console.error('[ANNOTATIONS] Error getting labeled events:', error);
+      res.status(500).json({
+        success: false,
+        error: error.message,
+      });
+    }
+  });
 }
 
 module.exports = createAnnotationRoutes;
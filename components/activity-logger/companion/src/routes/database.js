@@ -14,6 +14,7 @@ function createDatabaseRoutes(deps) {
         success: true,
         stats,
         integrity,
+        readDamage: persistentDB.getReadDamage(),
       });
     } catch (error) {
       console.error('Error getting database stats:', error);
@@ -21,6 +22,39 @@ function createDatabaseRoutes(deps) {
     }
   });
 
+  app.post('/api/database/compact', async (req, res) => {
+    try {
+      const online = req.body?.online !== false;
+      const mergeSegments = req.body?.mergeSegments !== false;
+      const result = await persistentDB.compactStore({ online, mergeSegments });
+
+      res.json({
+        success: true,
+        result,
+      });
+    } catch (error) {
+      console.error('Error compacting database:', error);
+      res.status(500).json({ success: false, error: error.message });
+    }
+  });
+
+  app.post('/api/database/tier', async (req, res) => {
+    try {
+      const olderThanDays = req.body?.olderThanDays;
+      const result = await persistentDB.tierColdEvents(
+        olderThanDays !== undefined ? { olderThanDays: Number(olderThanDays) } : {}
+      );
+
+      res.json({
+        success: true,
+        result,
+      });
+    } catch (error) {
+      console.error('Error tiering events to cold storage:', error);
+      res.status(500).json({ success: false, error: error.message });
+    }
+  });
+
   app.get('/api/database/entries-with-prompts', async (req, res) => {
     try {
       const limit = parseInt(req.query.limit) || 100;
@@ -95,6 +95,20 @@ function createFileWatcherService(deps) {
             before_content: previousContent,
           });
 
+          // Build output and codegen still get logged, but tagged so metrics can leave them out
+          const generated = diffEngine.detectGenerated(content, relativePath);
+          const tags = ['filewatcher-detected', 'code-change'];
+          if (generated.isGenerated) tags.push('generated');
+          if (generated.isMinified) tags.push('minified');
+
+          let notes = `File change detected. Diff: ${diff.summary}`;
+          const dependencies = diffEngine.diffManifests(previousContent, content, relativePath);
+          if (dependencies && dependencies.changes.length > 0) {
+            tags.push('dependencies-changed');
+            const { added, removed, upgraded, downgraded } = dependencies;
+            notes += `. Dependencies: ${added} added, ${removed} removed, ${upgraded} upgraded, ${downgraded} downgraded`;
+          }
+
           const entry = queueSystem.addEntry({
             session_id: workspaceSession,
             workspace_path: workspacePath,
@@ -102,8 +116,8 @@ function createFileWatcherService(deps) {
             file_path: relativePath,
             before_code: previousContent,
             after_code: content,
-            notes: `File change detected. Diff: ${diff.summary}`,
-            tags: ['filewatcher-detected', 'code-change'],
+            notes,
+            tags,
             modelInfo: modelInfo,
           });
 
@@ -278,6 +278,20 @@ function createStartupService(deps) {
               console.warn('[STATS] Stats table initialization failed:', err.message);
             });
 
+            // Move old events to cold storage nightly when it's configured
+            if (persistentDB.coldStorage) {
+              cron.schedule('30 3 * * *', async () => {
+                try {
+                  const result = await persistentDB.tierColdEvents();
+                  console.log(`[COLD] Moved ${result.events} event(s) to ${result.location}`);
+                } catch (error) {
+                  console.warn('[COLD] Scheduled tiering failed:', error.message);
+                }
+              });
+              const { afterDays, location } = persistentDB.coldStorage;
+              console.log(`[COLD] Events older than ${afterDays} days tier to ${location}`);
+            }
+
             resolve();
           });
         })
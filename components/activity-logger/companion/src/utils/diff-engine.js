@@ -9,16 +9,39 @@ const diff = require('diff');
 
 // Try to load native module
 let native = null;
+let nativeEngine = null;
 let useNative = false;
 
 try {
   native = require('../../native');
+  // Shared engine caches results so duplicate saves/retries don't re-diff
+  nativeEngine = new native.DiffEngine();
   useNative = true;
   console.log('[DIFF] ⚡ Using Rust native module for 5-10x faster diffs');
 } catch (error) {
   console.log('[DIFF] Using JavaScript diff (build native module with: cd native && npm run build)');
 }
 
+// Grapheme segmentation is available in Node 16+; fall back to code points otherwise
+const graphemeSegmenter = typeof Intl !== 'undefined' && Intl.Segmenter
+  ? new Intl.Segmenter(undefined, { granularity: 'grapheme' })
+  : null;
+
+/**
+ * Measure text in code points, grapheme clusters, and UTF-8 bytes
+ * (String#length counts UTF-16 units, which splits emoji and astral CJK)
+ */
+function textLength(text) {
+  let chars = 0;
+  for (const _ of text) chars++;
+  let graphemes = chars;
+  if (graphemeSegmenter) {
+    graphemes = 0;
+    for (const _ of graphemeSegmenter.segment(text)) graphemes++;
+  }
+  return { chars, graphemes, bytes: Buffer.byteLength(text, 'utf8') };
+}
+
 /**
  * Calculate diff between two texts
  * Automatically uses native Rust implementation when available
@@ -29,11 +52,13 @@ function calculateDiff(text1, text2, options = {}) {
   if (useNative && native) {
     try {
       // Use Rust implementation (5-10x faster)
-      return native.calculateDiff(
+      return nativeEngine.calculateDiff(
         text1,
         text2,
         threshold,
-        options.includeUnified || false
+        options.includeUnified || false,
+        options.normalizeEol || false,
+        options.budget
       );
     } catch (error) {
       console.warn('[DIFF] Native diff failed, falling back to JS:', error.message);
@@ -42,25 +67,36 @@ function calculateDiff(text1, text2, options = {}) {
   }
 
   // JavaScript fallback implementation
-  const diffSize = Math.abs(text2.length - text1.length);
+  const afterContent = text2;
+  if (options.normalizeEol) {
+    text1 = text1.replace(/\r\n?/g, '\n');
+    text2 = text2.replace(/\r\n?/g, '\n');
+  }
+  const before = textLength(text1);
+  const after = textLength(text2);
+  const diffSize = Math.abs(after.chars - before.chars);
   const isSignificant = diffSize >= threshold;
 
   const lines1 = text1.split('\n');
   const lines2 = text2.split('\n');
   const linesAdded = Math.max(0, lines2.length - lines1.length);
   const linesRemoved = Math.max(0, lines1.length - lines2.length);
-  const charsAdded = Math.max(0, text2.length - text1.length);
-  const charsDeleted = Math.max(0, text1.length - text2.length);
+  const charsAdded = Math.max(0, after.chars - before.chars);
+  const charsDeleted = Math.max(0, before.chars - after.chars);
 
   return {
     diffSize,
     isSignificant,
-    summary: `+${text2.length - text1.length} chars`,
+    summary: `+${after.chars - before.chars} chars`,
     linesAdded,
     linesRemoved,
     charsAdded,
     charsDeleted,
-    afterContent: text2,
+    graphemesAdded: Math.max(0, after.graphemes - before.graphemes),
+    graphemesDeleted: Math.max(0, before.graphemes - after.graphemes),
+    bytesAdded: Math.max(0, after.bytes - before.bytes),
+    bytesDeleted: Math.max(0, before.bytes - after.bytes),
+    afterContent,
   };
 }
 
@@ -133,9 +169,12 @@ function calculateFileStats(content) {
     words += trimmed.split(/\s+/).length;
   }
 
+  const length = textLength(content);
   return {
     lines: totalLines,
-    chars: content.length,
+    chars: length.chars,
+    graphemes: length.graphemes,
+    bytes: length.bytes,
     words,
     blankLines,
     commentLines,
@@ -148,7 +187,10 @@ function calculateFileStats(content) {
 function batchCalculateDiffs(pairs, threshold = 10) {
   if (useNative && native) {
     try {
-      return native.batchCalculateDiffs(pairs, threshold);
+      // Native returns one { ok, result, error } item per pair; recompute failed pairs in JS
+      return native.batchCalculateDiffs(pairs, threshold).map((item, i) =>
+        item.ok ? item.result : calculateDiff(pairs[i][0], pairs[i][1], { threshold })
+      );
     } catch (error) {
       console.warn('[DIFF] Native batch diff failed:', error.message);
     }
@@ -158,6 +200,30 @@ function batchCalculateDiffs(pairs, threshold = 10) {
   return pairs.map(([text1, text2]) => calculateDiff(text1, text2, { threshold }));
 }
 
+/**
+ * Read and analyze files from disk (parallel in Rust)
+ * Returns one { path, ok, result, error } item per path
+ */
+function batchFileStats(paths) {
+  if (useNative && native) {
+    try {
+      return native.batchFileStats(paths);
+    } catch (error) {
+      console.warn('[DIFF] Native batch file stats failed:', error.message);
+    }
+  }
+
+  // JavaScript fallback - sequential reads
+  const fs = require('fs');
+  return paths.map((path) => {
+    try {
+      return { path, ok: true, result: calculateFileStats(fs.readFileSync(path, 'utf8')) };
+    } catch (error) {
+      return { path, ok: false, error: { code: error.code || 'GenericFailure', message: error.message } };
+    }
+  });
+}
+
 /**
  * Calculate similarity ratio between two texts
  */
@@ -192,7 +258,7 @@ function calculateSimilarity(text1, text2) {
 function detectLanguage(content, filename = null) {
   if (useNative && native) {
     try {
-      return native.detectLanguage(content, filename);
+      return native.detectLanguage(content, filename).language;
     } catch (error) {
       console.warn('[DIFF] Native language detection failed:', error.message);
     }
@@ -241,21 +307,102 @@ function extractFunctions(content, language) {
 }
 
 /**
- * Estimate token count
+ * Detect generated and minified files (codegen markers, build output names, long dense lines)
+ * Returns { isGenerated, isMinified, reasons, ... }
+ */
+function detectGenerated(content, filename = null) {
+  if (useNative && native) {
+    try {
+      return native.detectGenerated(content, filename);
+    } catch (error) {
+      console.warn('[DIFF] Native generated-file detection failed:', error.message);
+    }
+  }
+
+  // JavaScript fallback - header markers and name patterns only
+  const reasons = [];
+  const header = content.split('\n', 30).join('\n');
+  if (/@generated\b|DO NOT EDIT|auto-?generated/i.test(header)) reasons.push('marker');
+  const isMinified = Boolean(filename && /\.min\.(js|mjs|css)$/i.test(filename));
+  if (isMinified) reasons.push('filename');
+  return { isGenerated: reasons.length > 0, isMinified, reasons };
+}
+
+const MANIFEST_FILES = new Set(['package.json', 'cargo.toml', 'pyproject.toml', 'go.mod']);
+
+/**
+ * Dependency changes between two versions of package.json, Cargo.toml, pyproject.toml, or go.mod
+ * Returns null for other files, or when the native module isn't available
+ */
+function diffManifests(before, after, filename) {
+  const base = filename.split(/[\\/]/).pop().toLowerCase();
+  if (!MANIFEST_FILES.has(base) || !(useNative && native)) return null;
+  try {
+    return native.diffManifests(before, after, filename);
+  } catch (error) {
+    console.warn('[DIFF] Manifest diff failed:', error.message);
+    return null;
+  }
+}
+
+let warnedHeuristicTokens = false;
+
+/**
+ * Warn once that token counts are estimates; no rank files ship with the package
+ */
+function warnHeuristicTokens() {
+  if (warnedHeuristicTokens) return;
+  warnedHeuristicTokens = true;
+  console.warn(
+    '[DIFF] Token counts are chars/words estimates: no tiktoken encoding is loaded. ' +
+      'Point TIKTOKEN_ENCODINGS_DIR at a directory of <encoding>.tiktoken files for exact counts.'
+  );
+}
+
+/**
+ * Count tokens and say whether the count is exact
+ * exact is false when the native tiktoken encoding isn't loaded and the heuristic was used
  */
-function estimateTokens(text) {
+function countTokens(text, encoding = null) {
   if (useNative && native) {
     try {
-      return native.estimateTokens(text);
+      const result = native.countTokens(text, encoding || undefined);
+      if (!result.exact) warnHeuristicTokens();
+      return { tokens: result.tokens, exact: result.exact };
     } catch (error) {
       console.warn('[DIFF] Native token estimation failed:', error.message);
     }
   }
 
   // JavaScript fallback
+  warnHeuristicTokens();
   const words = text.split(/\s+/).length;
   const chars = text.length;
-  return Math.ceil((words * 1.3 + chars / 4) / 2);
+  return { tokens: Math.ceil((words * 1.3 + chars / 4) / 2), exact: false };
+}
+
+/**
+ * Estimate token count; exact once the native tiktoken encoding is loaded, see countTokens
+ */
+function estimateTokens(text, encoding = null) {
+  return countTokens(text, encoding).tokens;
+}
+
+/**
+ * Estimate token counts for many texts, in parallel when native is available
+ */
+function batchEstimateTokens(texts, model = null) {
+  if (useNative && native) {
+    try {
+      const result = native.batchEstimateTokens(texts, model || undefined);
+      if (!result.exact) warnHeuristicTokens();
+      return result.counts;
+    } catch (error) {
+      console.warn('[DIFF] Native batch token estimation failed:', error.message);
+    }
+  }
+
+  return texts.map((text) => estimateTokens(text));
 }
 
 /**
@@ -275,6 +422,7 @@ function getPerformanceInfo() {
     features: {
       calculateDiff: true,
       batchDiffs: useNative,
+      batchFileStats: true,
       parallelProcessing: useNative,
       lineChanges: true,
       fileStats: true,
@@ -282,6 +430,8 @@ function getPerformanceInfo() {
       languageDetection: true,
       functionExtraction: true,
       tokenEstimation: true,
+      generatedDetection: true,
+      manifestDiffs: useNative,
     },
   };
 }
@@ -291,10 +441,15 @@ module.exports = {
   getLineChanges,
   calculateFileStats,
   batchCalculateDiffs,
+  batchFileStats,
   calculateSimilarity,
   detectLanguage,
   extractFunctions,
+  detectGenerated,
+  diffManifests,
+  countTokens,
   estimateTokens,
+  batchEstimateTokens,
   isNativeAvailable,
   getPerformanceInfo,
 };